@@ -0,0 +1,184 @@
+//! Aligning text to a common column by padding it with spaces, see the `:align` command.
+
+use helix_stdx::rope::{self, RopeSliceExt};
+
+use crate::{
+    doc_formatter::TextFormat, line_ending::line_end_char_index, text_annotations::TextAnnotations,
+    visual_offset_from_block, Change, RopeSlice, Selection,
+};
+
+/// Which side of a selection, or of a delimiter match, is aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    /// The selection or match itself starts at the common column.
+    Start,
+    /// The text after the selection or match starts at the common column.
+    End,
+}
+
+/// Returns the positions to align for a selection. Without a delimiter this is the start or
+/// end of each range. With a delimiter it is the start or end of its first match on each line
+/// of each range, lines without a match are left alone.
+pub fn alignment_points(
+    text: RopeSlice,
+    selection: &Selection,
+    delimiter: Option<&rope::Regex>,
+    anchor: Anchor,
+) -> Vec<usize> {
+    let side = |from: usize, to: usize| match anchor {
+        Anchor::Start => from,
+        Anchor::End => to,
+    };
+
+    let Some(delimiter) = delimiter else {
+        return selection
+            .iter()
+            .map(|range| side(range.from(), range.to()))
+            .collect();
+    };
+
+    let mut points = Vec::new();
+    for range in selection {
+        let first_line = text.char_to_line(range.from());
+        let last_line = text.char_to_line(range.to().saturating_sub(1).max(range.from()));
+        for line in first_line..=last_line {
+            let start = text.line_to_char(line).max(range.from());
+            let end = line_end_char_index(&text, line).min(range.to());
+            if start > end {
+                continue;
+            }
+            if let Some(mat) = delimiter.find(text.regex_input_at(start..end)) {
+                points.push(side(
+                    text.byte_to_char(mat.start()),
+                    text.byte_to_char(mat.end()),
+                ));
+            }
+        }
+    }
+    points
+}
+
+/// Returns the changes padding each of `points` with spaces so they line up. Points on the same
+/// line form columns: the first point of each line is aligned with the first point of the
+/// other lines, the second with the second, and so on.
+pub fn align(text: RopeSlice, points: &[usize], tab_width: usize) -> Vec<Change> {
+    let text_fmt = TextFormat {
+        soft_wrap: false,
+        tab_width: tab_width as u16,
+        ..TextFormat::default()
+    };
+    let annotations = TextAnnotations::default();
+
+    let mut points = points.to_vec();
+    points.sort_unstable();
+    points.dedup();
+
+    // The points of each line as (position, visual column)
+    let mut rows: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut last_line = None;
+    for pos in points {
+        let line = text.char_to_line(pos);
+        let line_start = text.line_to_char(line);
+        let col = visual_offset_from_block(text, line_start, pos, &text_fmt, &annotations)
+            .0
+            .col;
+        if last_line != Some(line) {
+            rows.push(Vec::new());
+            last_line = Some(line);
+        }
+        rows.last_mut().unwrap().push((pos, col));
+    }
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    // The padding inserted so far on each row, which shifts the following points
+    let mut offsets = vec![0; rows.len()];
+    let mut changes = Vec::new();
+    for column in 0..columns {
+        let target = rows
+            .iter()
+            .zip(&offsets)
+            .filter_map(|(row, offset)| row.get(column).map(|(_, col)| col + offset))
+            .max()
+            .unwrap_or(0);
+        for (row, offset) in rows.iter().zip(&mut offsets) {
+            let Some(&(pos, col)) = row.get(column) else {
+                continue;
+            };
+            let padding = target - (col + *offset);
+            if padding > 0 {
+                *offset += padding;
+                changes.push((pos, pos, Some(" ".repeat(padding).into())));
+            }
+        }
+    }
+
+    changes.sort_unstable_by_key(|(from, _, _)| *from);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{smallvec, Range, Rope, Transaction};
+
+    use super::*;
+
+    fn aligned(
+        text: &str,
+        selection: Selection,
+        delimiter: Option<&str>,
+        anchor: Anchor,
+    ) -> String {
+        let mut doc = Rope::from(text);
+        let delimiter = delimiter.map(|delimiter| rope::Regex::new(delimiter).unwrap());
+        let points = alignment_points(doc.slice(..), &selection, delimiter.as_ref(), anchor);
+        let changes = align(doc.slice(..), &points, 4);
+        Transaction::change(&doc, changes.into_iter()).apply(&mut doc);
+        doc.to_string()
+    }
+
+    #[test]
+    fn align_on_delimiter() {
+        let text = "a = 1\nlonger = 2\nno match\n\tb = 3\n";
+        let all = Selection::single(0, text.chars().count());
+        assert_eq!(
+            aligned(text, all.clone(), Some("="), Anchor::Start),
+            "a      = 1\nlonger = 2\nno match\n\tb  = 3\n"
+        );
+        assert_eq!(
+            aligned(text, all, Some(r"=\s*"), Anchor::End),
+            "a =      1\nlonger = 2\nno match\n\tb =  3\n"
+        );
+    }
+
+    #[test]
+    fn align_selections() {
+        let text = "x: 1, 2\nlong: 10, 20\n";
+        // Cursors on the values, two columns per line
+        let selection = Selection::new(
+            [3, 6, 14, 18]
+                .into_iter()
+                .map(|pos| Range::new(pos, pos + 1))
+                .collect(),
+            0,
+        );
+        assert_eq!(
+            aligned(text, selection, None, Anchor::Start),
+            "x:    1,  2\nlong: 10, 20\n"
+        );
+
+        // The text after each selection is aligned instead
+        let keys = Selection::new(smallvec![Range::new(0, 2), Range::new(8, 13)], 0);
+        assert_eq!(
+            aligned(text, keys, None, Anchor::End),
+            "x:    1, 2\nlong: 10, 20\n"
+        );
+    }
+
+    #[test]
+    fn align_at_end_of_text() {
+        // The last point is the end of the text, past the last grapheme
+        let text = "ab\nc";
+        let selection = Selection::new(smallvec![Range::point(2), Range::point(4)], 0);
+        assert_eq!(aligned(text, selection, None, Anchor::Start), "ab\nc ");
+    }
+}
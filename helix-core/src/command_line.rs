@@ -253,16 +253,26 @@ pub enum ExpansionKind {
     ///
     /// For example `%sh{echo hello}`.
     Shell,
+    /// Read an environment variable. Variables which aren't set expand to nothing.
+    ///
+    /// For example `%env{HOME}`.
+    Environment,
 }
 
 impl ExpansionKind {
-    pub const VARIANTS: &'static [Self] = &[Self::Variable, Self::Unicode, Self::Shell];
+    pub const VARIANTS: &'static [Self] = &[
+        Self::Variable,
+        Self::Unicode,
+        Self::Shell,
+        Self::Environment,
+    ];
 
     pub const fn as_str(&self) -> &'static str {
         match self {
             Self::Variable => "",
             Self::Unicode => "u",
             Self::Shell => "sh",
+            Self::Environment => "env",
         }
     }
 
@@ -271,6 +281,7 @@ pub fn from_kind(name: &str) -> Option<Self> {
             "" => Some(Self::Variable),
             "u" => Some(Self::Unicode),
             "sh" => Some(Self::Shell),
+            "env" => Some(Self::Environment),
             _ => None,
         }
     }
@@ -1118,6 +1129,14 @@ fn tokenize_percent() {
             r#"echo %sh{echo "%{cursor_line}"}"#,
             &["echo", r#"echo "%{cursor_line}""#],
         );
+        assert_eq!(
+            Tokenizer::new("%env{HOME}", true)
+                .next()
+                .unwrap()
+                .unwrap()
+                .kind,
+            TokenKind::Expansion(ExpansionKind::Environment)
+        );
         // Completion should provide variable names here. (Unbalanced nesting)
         assert_incomplete_tokens(r#"echo %sh{echo "%{c"#, &["echo", r#"echo "%{c"#]);
         assert_incomplete_tokens(r#"echo %{hello {{} world}"#, &["echo", "hello {{} world}"]);
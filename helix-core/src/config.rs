@@ -2,6 +2,7 @@
     config::{Configuration, LanguageConfiguration},
     Loader, LoaderError,
 };
+use helix_loader::config::ConfigDiagnostic;
 
 /// Language configuration based on built-in languages.toml.
 pub fn default_lang_config() -> Configuration {
@@ -17,11 +18,23 @@ pub fn default_lang_loader() -> Loader {
 
 #[derive(Debug)]
 pub enum LanguageLoaderError {
-    DeserializeError(toml::de::Error),
-    ConfigError(toml::de::Error, String),
+    DeserializeError(ConfigDiagnostic),
+    ConfigError(ConfigDiagnostic, String),
     LoaderError(LoaderError),
 }
 
+impl LanguageLoaderError {
+    /// The located configuration error, if this is one.
+    pub fn diagnostic(&self) -> Option<&ConfigDiagnostic> {
+        match self {
+            Self::DeserializeError(diagnostic) | Self::ConfigError(diagnostic, _) => {
+                Some(diagnostic)
+            }
+            Self::LoaderError(_) => None,
+        }
+    }
+}
+
 impl std::fmt::Display for LanguageLoaderError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -37,8 +50,12 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 impl std::error::Error for LanguageLoaderError {}
 
 /// Language configuration based on user configured languages.toml.
-pub fn user_lang_config() -> Result<Configuration, toml::de::Error> {
-    helix_loader::config::user_lang_config()?.try_into()
+pub fn user_lang_config() -> Result<Configuration, ConfigDiagnostic> {
+    helix_loader::config::user_lang_config()?
+        .try_into()
+        .map_err(|err| {
+            ConfigDiagnostic::new(&err).locate(&helix_loader::config::user_lang_config_files())
+        })
 }
 
 /// Language configuration loader based on user configured languages.toml.
@@ -46,6 +63,8 @@ pub fn user_lang_loader() -> Result<Loader, LanguageLoaderError> {
     let config_val =
         helix_loader::config::user_lang_config().map_err(LanguageLoaderError::DeserializeError)?;
     let config = config_val.clone().try_into().map_err(|e| {
+        // Values merged from several files have no spans, look for the key in the user's files.
+        let files = helix_loader::config::user_lang_config_files();
         if let Some(languages) = config_val.get("language").and_then(|v| v.as_array()) {
             for lang in languages.iter() {
                 let res: Result<LanguageConfiguration, _> = lang.clone().try_into();
@@ -54,11 +73,12 @@ pub fn user_lang_loader() -> Result<Loader, LanguageLoaderError> {
                         Some(name) => format!("for language {}", name),
                         None => "for unknown language".to_owned(),
                     };
-                    return LanguageLoaderError::ConfigError(inner_err, context);
+                    let diagnostic = ConfigDiagnostic::new(&inner_err).locate(&files);
+                    return LanguageLoaderError::ConfigError(diagnostic, context);
                 }
             }
         }
-        LanguageLoaderError::ConfigError(e, String::new())
+        LanguageLoaderError::ConfigError(ConfigDiagnostic::new(&e).locate(&files), String::new())
     })?;
     Loader::new(config).map_err(LanguageLoaderError::LoaderError)
 }
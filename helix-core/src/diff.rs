@@ -168,6 +168,132 @@ pub fn compare_ropes(before: &Rope, after: &Rope) -> Transaction {
     res
 }
 
+/// The result of [merge_ropes].
+#[derive(Debug)]
+pub struct Merge {
+    pub text: Rope,
+    /// The line ranges of the conflicts in `text`, from the start to the end marker.
+    pub conflicts: Vec<Range<usize>>,
+}
+
+fn line_hunks(before: RopeSlice, after: RopeSlice) -> Vec<Hunk> {
+    let file = InternedInput::new(RopeLines(before), RopeLines(after));
+    Diff::compute(Algorithm::Histogram, &file).hunks().collect()
+}
+
+fn slice_lines(text: RopeSlice, lines: Range<u32>) -> RopeSlice {
+    text.slice(text.line_to_char(lines.start as usize)..text.line_to_char(lines.end as usize))
+}
+
+#[derive(Default)]
+struct MergeBuilder {
+    text: String,
+    lines: usize,
+}
+
+impl MergeBuilder {
+    fn push(&mut self, slice: RopeSlice) {
+        for chunk in slice.chunks() {
+            self.text.push_str(chunk);
+        }
+        self.lines += slice.len_lines() - 1;
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+        self.lines += 1;
+    }
+
+    /// Terminates the last line, the last line of a file may lack a line ending.
+    fn end_line(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+            self.lines += 1;
+        }
+    }
+}
+
+/// Merges the changes from `base` to `ours` and from `base` to `theirs` line by line.
+/// Where both sides changed the same (or adjacent) lines differently, both versions are
+/// kept between conflict markers labeled with `labels`, like in a git merge.
+pub fn merge_ropes(base: &Rope, ours: &Rope, theirs: &Rope, labels: (&str, &str)) -> Merge {
+    let base = base.slice(..);
+    let texts = [ours.slice(..), theirs.slice(..)];
+    let hunks = [line_hunks(base, texts[0]), line_hunks(base, texts[1])];
+
+    let mut merged = MergeBuilder::default();
+    let mut conflicts = Vec::new();
+    let mut next = [0, 0];
+    let mut pos = 0;
+    loop {
+        // start a region at the first remaining hunk of either side and grow it until no
+        // hunk of either side overlaps or touches it
+        let Some(first) = (0..2)
+            .filter(|&side| next[side] < hunks[side].len())
+            .min_by_key(|&side| hunks[side][next[side]].before.start)
+        else {
+            break;
+        };
+        let start = hunks[first][next[first]].before.start;
+        let mut end = start;
+        let mut region = [next[0]..next[0], next[1]..next[1]];
+        let mut grown = true;
+        while grown {
+            grown = false;
+            for side in 0..2 {
+                while let Some(hunk) = hunks[side].get(region[side].end) {
+                    if hunk.before.start > end {
+                        break;
+                    }
+                    end = end.max(hunk.before.end);
+                    region[side].end += 1;
+                    grown = true;
+                }
+            }
+        }
+
+        merged.push(slice_lines(base, pos..start));
+        // the lines of each side which correspond to the region of the base
+        let [ours, theirs] = [0, 1].map(|side| {
+            let region = &region[side];
+            if region.is_empty() {
+                return slice_lines(base, start..end);
+            }
+            let first = &hunks[side][region.start];
+            let last = &hunks[side][region.end - 1];
+            let from = first.after.start - (first.before.start - start);
+            let to = last.after.end + (end - last.before.end);
+            slice_lines(texts[side], from..to)
+        });
+
+        if region[1].is_empty() || ours == theirs {
+            merged.push(ours);
+        } else if region[0].is_empty() {
+            merged.push(theirs);
+        } else {
+            let conflict_start = merged.lines;
+            merged.push_line(&format!("<<<<<<< {}", labels.0));
+            merged.push(ours);
+            merged.end_line();
+            merged.push_line("=======");
+            merged.push(theirs);
+            merged.end_line();
+            merged.push_line(&format!(">>>>>>> {}", labels.1));
+            conflicts.push(conflict_start..merged.lines);
+        }
+
+        pos = end;
+        next = [region[0].end, region[1].end];
+    }
+    merged.push(slice_lines(base, pos..base.len_lines() as u32));
+
+    Merge {
+        text: Rope::from(merged.text),
+        conflicts,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -208,4 +334,30 @@ fn new_file() {
     fn deleted_file() {
         test_identity("foo", "");
     }
+
+    #[test]
+    fn merge() {
+        let base = Rope::from("a\nb\nc\nd\ne\n");
+
+        // changes to different lines are merged
+        let ours = Rope::from("a\nB\nc\nd\ne\n");
+        let theirs = Rope::from("a\nb\nc\nd\nE\nf\n");
+        let merge = merge_ropes(&base, &ours, &theirs, ("ours", "theirs"));
+        assert_eq!(merge.text, "a\nB\nc\nd\nE\nf\n");
+        assert!(merge.conflicts.is_empty());
+
+        // identical changes are applied once
+        let merge = merge_ropes(&base, &ours, &ours, ("ours", "theirs"));
+        assert_eq!(merge.text, ours);
+        assert!(merge.conflicts.is_empty());
+
+        // different changes to the same lines conflict
+        let theirs = Rope::from("a\nX\nc\nd\nE\n");
+        let merge = merge_ropes(&base, &ours, &theirs, ("ours", "theirs"));
+        assert_eq!(
+            merge.text,
+            "a\n<<<<<<< ours\nB\n=======\nX\n>>>>>>> theirs\nc\nd\nE\n"
+        );
+        assert_eq!(merge.conflicts, vec![1..6]);
+    }
 }
@@ -8,6 +8,10 @@
 //! it is generally not possible to find the start of the previous visual line.
 //! Instead the `DocumentFormatter` starts at the last "checkpoint" (usually a linebreak)
 //! called a "block" and the caller must advance it as needed.
+//!
+//! Lines longer than [`MAX_BLOCK_CHARS`] are split into several blocks, which always start a
+//! new visual line. Otherwise formatting the end of a pathologically long line, like the single
+//! line of a minified file, would require formatting all of it.
 
 use std::borrow::Cow;
 use std::cmp::Ordering;
@@ -21,7 +25,8 @@
 
 use helix_stdx::rope::{RopeGraphemes, RopeSliceExt};
 
-use crate::graphemes::{Grapheme, GraphemeStr};
+use crate::graphemes::{ensure_grapheme_boundary_prev, Grapheme, GraphemeStr};
+use crate::line_ending::line_end_char_index;
 use crate::syntax::Highlight;
 use crate::text_annotations::TextAnnotations;
 use crate::{Position, RopeSlice};
@@ -147,6 +152,8 @@ pub struct TextFormat {
     pub tab_width: u16,
     pub max_wrap: u16,
     pub max_indent_retain: u16,
+    /// Extra indentation of soft wrapped lines, added to the indentation carried over.
+    pub continuation_indent: u16,
     pub wrap_indicator: Box<str>,
     pub wrap_indicator_highlight: Option<Highlight>,
     pub viewport_width: u16,
@@ -161,6 +168,7 @@ fn default() -> Self {
             tab_width: 4,
             max_wrap: 3,
             max_indent_retain: 4,
+            continuation_indent: 0,
             wrap_indicator: Box::from(" "),
             viewport_width: 17,
             wrap_indicator_highlight: None,
@@ -169,10 +177,34 @@ fn default() -> Self {
     }
 }
 
+/// The number of chars after which a line is split into another block.
+pub const MAX_BLOCK_CHARS: usize = 8192;
+
+/// Returns the char index the block `block` of the line starting at `line_start` starts at,
+/// `None` if the line ends before. Blocks start on grapheme boundaries.
+fn block_boundary(text: RopeSlice, line_start: usize, block: usize) -> Option<usize> {
+    if block == 0 {
+        return Some(line_start);
+    }
+    let line_end = line_end_char_index(&text, text.char_to_line(line_start));
+    let boundary = line_start + block * MAX_BLOCK_CHARS;
+    (boundary < line_end).then(|| ensure_grapheme_boundary_prev(text, boundary))
+}
+
+/// Returns the char index of the start of the block containing `char_idx`, the start of its
+/// line unless the line is longer than [`MAX_BLOCK_CHARS`].
+pub fn block_start(text: RopeSlice, char_idx: usize) -> usize {
+    let char_idx = char_idx.min(text.len_chars());
+    let line_start = text.line_to_char(text.char_to_line(char_idx));
+    let block = (char_idx - line_start) / MAX_BLOCK_CHARS;
+    block_boundary(text, line_start, block).unwrap_or(line_start)
+}
+
 #[derive(Debug)]
 pub struct DocumentFormatter<'t> {
     text_fmt: &'t TextFormat,
     annotations: &'t TextAnnotations<'t>,
+    text: RopeSlice<'t>,
 
     /// The visual position at the end of the last yielded word boundary
     visual_pos: Position,
@@ -181,6 +213,10 @@ pub struct DocumentFormatter<'t> {
     char_pos: usize,
     /// The line pos of the `graphemes` iter used for inserting annotations
     line_pos: usize,
+    /// The char index of the start of the current line
+    line_start: usize,
+    /// The index of the next block of the current line and the char index it starts at
+    next_block: (usize, Option<usize>),
     exhausted: bool,
 
     inline_annotation_graphemes: Option<(Graphemes<'t>, Option<Highlight>)>,
@@ -204,21 +240,24 @@ impl<'t> DocumentFormatter<'t> {
     /// A block is a chunk which always ends with a linebreak.
     /// This is usually just a normal line break.
     /// However very long lines are always wrapped at constant intervals that can be cheaply calculated
-    /// to avoid pathological behaviour.
+    /// to avoid pathological behaviour, see [`MAX_BLOCK_CHARS`].
     pub fn new_at_prev_checkpoint(
         text: RopeSlice<'t>,
         text_fmt: &'t TextFormat,
         annotations: &'t TextAnnotations,
         char_idx: usize,
     ) -> Self {
-        // TODO divide long lines into blocks to avoid bad performance for long lines
-        let block_line_idx = text.char_to_line(char_idx.min(text.len_chars()));
-        let block_char_idx = text.line_to_char(block_line_idx);
+        let char_idx = char_idx.min(text.len_chars());
+        let block_line_idx = text.char_to_line(char_idx);
+        let line_start = text.line_to_char(block_line_idx);
+        let block = (char_idx - line_start) / MAX_BLOCK_CHARS;
+        let block_char_idx = block_boundary(text, line_start, block).unwrap_or(line_start);
         annotations.reset_pos(block_char_idx);
 
         DocumentFormatter {
             text_fmt,
             annotations,
+            text,
             visual_pos: Position { row: 0, col: 0 },
             graphemes: text.slice(block_char_idx..).graphemes(),
             char_pos: block_char_idx,
@@ -228,10 +267,19 @@ pub fn new_at_prev_checkpoint(
             word_buf: Vec::with_capacity(64),
             word_i: 0,
             line_pos: block_line_idx,
+            line_start,
+            next_block: (block + 1, block_boundary(text, line_start, block + 1)),
             inline_annotation_graphemes: None,
         }
     }
 
+    /// Whether the current block of a very long line ends at the current position.
+    fn at_block_end(&self) -> bool {
+        self.next_block
+            .1
+            .is_some_and(|block_start| self.char_pos >= block_start)
+    }
+
     fn next_inline_annotation_grapheme(
         &mut self,
         char_pos: usize,
@@ -308,7 +356,7 @@ fn wrap_word(&mut self) -> usize {
         let virtual_lines =
             self.annotations
                 .virtual_lines_at(self.char_pos, self.visual_pos, self.line_pos);
-        self.visual_pos.col = indent_carry_over as usize;
+        self.visual_pos.col = (indent_carry_over + self.text_fmt.continuation_indent) as usize;
         self.visual_pos.row += 1 + virtual_lines;
         let mut i = 0;
         let mut word_width = 0;
@@ -413,7 +461,12 @@ fn advance_to_next_word(&mut self) {
             word_width += grapheme.width();
             self.word_buf.push(grapheme);
 
-            if is_word_boundary {
+            // Words end at the end of a block, which starts a new visual line
+            let at_block_end = self
+                .next_block
+                .1
+                .is_some_and(|block_start| self.char_pos + word_chars >= block_start);
+            if is_word_boundary || at_block_end {
                 return;
             }
         }
@@ -470,9 +523,18 @@ fn next(&mut self) -> Option<Self::Item> {
             self.visual_pos.col = 0;
             if !grapheme.is_virtual() {
                 self.line_pos += 1;
+                self.line_start = self.char_pos;
+                self.next_block = (1, block_boundary(self.text, self.line_start, 1));
             }
         } else {
             self.visual_pos.col += grapheme.width();
+            if !grapheme.is_virtual() && self.at_block_end() {
+                self.visual_pos.row += 1;
+                self.visual_pos.col = 0;
+                self.indent_level = None;
+                let block = self.next_block.0 + 1;
+                self.next_block = (block, block_boundary(self.text, self.line_start, block));
+            }
         }
         Some(grapheme)
     }
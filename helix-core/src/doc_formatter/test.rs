@@ -1,4 +1,4 @@
-use crate::doc_formatter::{DocumentFormatter, TextFormat};
+use crate::doc_formatter::{block_start, DocumentFormatter, TextFormat, MAX_BLOCK_CHARS};
 use crate::text_annotations::{InlineAnnotation, Overlay, TextAnnotations};
 
 impl TextFormat {
@@ -8,6 +8,7 @@ fn new_test(softwrap: bool) -> Self {
             tab_width: 2,
             max_wrap: 3,
             max_indent_retain: 4,
+            continuation_indent: 0,
             wrap_indicator: ".".into(),
             wrap_indicator_highlight: None,
             // use a prime number to allow lining up too often with repeat
@@ -82,6 +83,42 @@ fn softwrap_indentation() {
     );
 }
 
+#[test]
+fn softwrap_continuation_indent() {
+    let text_fmt = TextFormat {
+        continuation_indent: 2,
+        ..TextFormat::new_test(true)
+    };
+    assert_eq!(
+        DocumentFormatter::new_at_prev_checkpoint(
+            "\t\tfoo1 foo2 foo3 foo4 foo5 foo6\n".into(),
+            &text_fmt,
+            &TextAnnotations::default(),
+            0,
+        )
+        .collect_to_str(),
+        "    foo1 foo2 \n.......foo3 foo4 \n.......foo5 foo6 \n "
+    );
+}
+
+#[test]
+fn softwrap_without_word_boundaries() {
+    let text_fmt = TextFormat {
+        max_wrap: 0,
+        ..TextFormat::new_test(true)
+    };
+    assert_eq!(
+        DocumentFormatter::new_at_prev_checkpoint(
+            "foo ".repeat(10).as_str().into(),
+            &text_fmt,
+            &TextAnnotations::default(),
+            0,
+        )
+        .collect_to_str(),
+        "foo foo foo foo f\n.oo foo foo foo f\n.oo foo  "
+    );
+}
+
 #[test]
 fn long_word_softwrap() {
     assert_eq!(
@@ -211,3 +248,36 @@ fn annotation_and_overlay() {
         "fooo  bar "
     );
 }
+
+#[test]
+fn long_lines_are_split_into_blocks() {
+    let line_len = MAX_BLOCK_CHARS * 2 + 10;
+    let text = format!("{}\nb", "a".repeat(line_len));
+    let text = crate::Rope::from(text.as_str());
+    let text = text.slice(..);
+    let text_fmt = TextFormat::new_test(false);
+    let annotations = TextAnnotations::default();
+
+    let positions: Vec<_> =
+        DocumentFormatter::new_at_prev_checkpoint(text, &text_fmt, &annotations, 0)
+            .filter(|grapheme| {
+                [MAX_BLOCK_CHARS - 1, MAX_BLOCK_CHARS, line_len + 1].contains(&grapheme.char_idx)
+            })
+            .map(|grapheme| (grapheme.visual_pos.row, grapheme.visual_pos.col))
+            .collect();
+    assert_eq!(positions, [(0, MAX_BLOCK_CHARS - 1), (1, 0), (3, 0)]);
+
+    // Formatting the middle of the line starts at its block
+    let mut formatter = DocumentFormatter::new_at_prev_checkpoint(
+        text,
+        &text_fmt,
+        &annotations,
+        MAX_BLOCK_CHARS + 100,
+    );
+    assert_eq!(formatter.next_char_pos(), MAX_BLOCK_CHARS);
+    let grapheme = formatter.next().unwrap();
+    assert_eq!((grapheme.visual_pos.row, grapheme.visual_pos.col), (0, 0));
+
+    assert_eq!(block_start(text, line_len - 1), MAX_BLOCK_CHARS * 2);
+    assert_eq!(block_start(text, line_len + 1), line_len + 1);
+}
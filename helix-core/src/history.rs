@@ -34,8 +34,10 @@ pub struct State {
 /// Revisions are committed with a timestamp. :earlier and :later can be used
 /// to jump to the closest revision to a moment in time relative to the timestamp
 /// of the current revision plus (:later) or minus (:earlier) the duration
-/// given to the command. If a single integer is given, the editor will instead
-/// jump the given number of revisions in the vector.
+/// given to the command. A duration followed by `ago` (e.g. `:earlier 5m ago`)
+/// is instead relative to the current time, jumping to the revision closest to
+/// that moment. If a single integer is given, the editor will instead jump the
+/// given number of revisions in the vector.
 ///
 /// Limitations:
 ///  * Changes in selections currently don't commit history changes. The selection
@@ -134,6 +136,21 @@ pub fn changes_since(&self, revision: usize) -> Option<Transaction> {
         down_txns.chain(up_txns).reduce(|acc, tx| tx.compose(acc))
     }
 
+    /// Returns the changes which take the document from the current revision back to
+    /// `revision`, the reverse of [History::changes_since].
+    pub fn changes_to(&self, revision: usize) -> Option<Transaction> {
+        let lca = self.lowest_common_ancestor(revision, self.current);
+        let up = self.path_up(self.current, lca);
+        let down = self.path_up(revision, lca);
+        let up_txns = up.iter().map(|&n| self.revisions[n].inversion.clone());
+        let down_txns = down
+            .iter()
+            .rev()
+            .map(|&n| self.revisions[n].transaction.clone());
+
+        up_txns.chain(down_txns).reduce(|acc, tx| acc.compose(tx))
+    }
+
     /// Undo the last edit.
     pub fn undo(&mut self) -> Option<&Transaction> {
         if self.at_root() {
@@ -283,12 +300,22 @@ fn jump_duration_forward(&mut self, duration: Duration) -> Vec<Transaction> {
         }
     }
 
+    /// Creates a [`Transaction`] that will match a revision created `duration`
+    /// before `now`.
+    fn jump_ago(&mut self, now: Instant, duration: Duration) -> Vec<Transaction> {
+        match now.checked_sub(duration) {
+            Some(instant) => self.jump_instant(instant),
+            None => self.jump_to(0),
+        }
+    }
+
     /// Creates an undo [`Transaction`].
     pub fn earlier(&mut self, uk: UndoKind) -> Vec<Transaction> {
         use UndoKind::*;
         match uk {
             Steps(n) => self.jump_backward(n),
             TimePeriod(d) => self.jump_duration_backward(d),
+            Ago(d) => self.jump_ago(Instant::now(), d),
         }
     }
 
@@ -298,6 +325,7 @@ pub fn later(&mut self, uk: UndoKind) -> Vec<Transaction> {
         match uk {
             Steps(n) => self.jump_forward(n),
             TimePeriod(d) => self.jump_duration_forward(d),
+            Ago(d) => self.jump_ago(Instant::now(), d),
         }
     }
 }
@@ -307,6 +335,9 @@ pub fn later(&mut self, uk: UndoKind) -> Vec<Transaction> {
 pub enum UndoKind {
     Steps(usize),
     TimePeriod(std::time::Duration),
+    /// The point in (wall-clock) time a duration before now, regardless of the
+    /// timestamp of the current revision.
+    Ago(std::time::Duration),
 }
 
 /// A subset of systemd.time time span syntax units.
@@ -378,6 +409,8 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
             Ok(Self::Steps(1usize))
         } else if let Ok(n) = s.parse::<usize>() {
             Ok(UndoKind::Steps(n))
+        } else if let Some(s) = s.strip_suffix("ago") {
+            Ok(Self::Ago(parse_human_duration(s.trim_end())?))
         } else {
             Ok(Self::TimePeriod(parse_human_duration(s)?))
         }
@@ -441,6 +474,41 @@ fn redo(history: &mut History, state: &mut State) {
         assert_eq!("hello", state.doc);
     }
 
+    #[test]
+    fn test_changes_to() {
+        let mut history = History::default();
+        let mut state = State {
+            doc: Rope::from("a\n"),
+            selection: Selection::point(0),
+        };
+
+        fn commit(history: &mut History, state: &mut State, pos: usize, text: &str) {
+            let transaction =
+                Transaction::change(&state.doc, [(pos, pos, Some(text.into()))].into_iter());
+            history.commit_revision(&transaction, state);
+            transaction.apply(&mut state.doc);
+        }
+        commit(&mut history, &mut state, 1, "b");
+        commit(&mut history, &mut state, 2, "c");
+        assert_eq!("abc\n", state.doc);
+
+        // branch off the first revision
+        let transaction = history.undo().unwrap().clone();
+        transaction.apply(&mut state.doc);
+        commit(&mut history, &mut state, 0, "d");
+        assert_eq!("dab\n", state.doc);
+
+        let mut doc = state.doc.clone();
+        history.changes_to(2).unwrap().apply(&mut doc);
+        assert_eq!("abc\n", doc);
+
+        let mut doc = state.doc.clone();
+        history.changes_to(0).unwrap().apply(&mut doc);
+        assert_eq!("a\n", doc);
+
+        assert!(history.changes_to(history.current_revision()).is_none());
+    }
+
     #[test]
     fn test_earlier_later() {
         let mut history = History::default();
@@ -546,6 +614,40 @@ fn commit_change(
         assert_eq!("a\n", state.doc);
     }
 
+    #[test]
+    fn test_jump_ago() {
+        let mut history = History::default();
+        let mut state = State {
+            doc: Rope::from("a\n"),
+            selection: Selection::point(0),
+        };
+
+        let t0 = Instant::now();
+        let t = |n| t0.checked_add(Duration::from_secs(n)).unwrap();
+        for (i, text) in [" b", " c", " d"].into_iter().enumerate() {
+            let len = state.doc.len_chars() - 1;
+            let txn = Transaction::change(&state.doc, [(len, len, Some(text.into()))].into_iter());
+            history.commit_revision_at_timestamp(&txn, &state, t(i as u64 * 100));
+            txn.apply(&mut state.doc);
+        }
+        assert_eq!("a b c d\n", state.doc);
+
+        // Relative to "now" rather than to the current revision.
+        let now = t(250);
+        for txn in history.jump_ago(now, Duration::from_secs(140)) {
+            txn.apply(&mut state.doc);
+        }
+        assert_eq!("a b c\n", state.doc);
+        for txn in history.jump_ago(now, Duration::from_secs(140)) {
+            txn.apply(&mut state.doc);
+        }
+        assert_eq!("a b c\n", state.doc);
+        for txn in history.jump_ago(now, Duration::from_secs(10)) {
+            txn.apply(&mut state.doc);
+        }
+        assert_eq!("a b c d\n", state.doc);
+    }
+
     #[test]
     fn test_parse_undo_kind() {
         use UndoKind::*;
@@ -620,6 +722,17 @@ fn test_parse_undo_kind() {
             )))
         );
 
+        // A trailing `ago` is relative to the current time.
+        assert_eq!(
+            "5m ago".parse::<UndoKind>(),
+            Ok(Ago(Duration::from_secs(5 * 60)))
+        );
+        assert_eq!(
+            "1h30m ago".parse::<UndoKind>(),
+            Ok(Ago(Duration::from_secs(90 * 60)))
+        );
+        assert_eq!("ago".parse::<UndoKind>(), validation_err);
+
         // Sum overflow is handled.
         assert_eq!(
             "18446744073709551615minutes".parse::<UndoKind>(),
@@ -0,0 +1,65 @@
+/// Increment the red, green and blue channels of a hex color.
+///
+/// Supported forms are `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`. The alpha channel is left
+/// alone. Channels saturate at their minimum and maximum, and upper case digits stay upper case.
+pub fn increment(selected_text: &str, amount: i64) -> Option<String> {
+    let digits = selected_text.strip_prefix('#')?;
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel_len = match digits.len() {
+        3 | 4 => 1,
+        6 | 8 => 2,
+        _ => return None,
+    };
+    let max = if channel_len == 1 { 0xf } else { 0xff };
+    let upper = digits.bytes().any(|b| b.is_ascii_uppercase());
+
+    let mut new_text = String::from("#");
+    for (i, channel) in digits.as_bytes().chunks(channel_len).enumerate() {
+        let channel = std::str::from_utf8(channel).ok()?;
+        if i == 3 {
+            new_text.push_str(channel);
+            break;
+        }
+        let value = i64::from_str_radix(channel, 16).ok()?;
+        let value = value.saturating_add(amount).clamp(0, max);
+        let channel = if upper {
+            format!("{value:0channel_len$X}")
+        } else {
+            format!("{value:0channel_len$x}")
+        };
+        new_text.push_str(&channel);
+    }
+    Some(new_text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_increment_hex_colors() {
+        let tests = [
+            ("#000000", 1, "#010101"),
+            ("#0a0b0c", 10, "#141516"),
+            ("#FF8000", 1, "#FF8101"),
+            ("#ff8000", -1, "#fe7f00"),
+            ("#123", 1, "#234"),
+            ("#fff", 5, "#fff"),
+            ("#10203080", 16, "#20304080"),
+            ("#abcd", -1, "#9abd"),
+        ];
+
+        for (original, amount, expected) in tests {
+            assert_eq!(increment(original, amount).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_invalid_hex_colors() {
+        for invalid in ["#", "#12", "#12345", "#gggggg", "123456", "#1234567"] {
+            assert_eq!(increment(invalid, 1), None);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 mod date_time;
+mod hex_color;
 mod integer;
+mod word;
 
 pub fn integer(selected_text: &str, amount: i64) -> Option<String> {
     integer::increment(selected_text, amount)
@@ -8,3 +10,11 @@ pub fn integer(selected_text: &str, amount: i64) -> Option<String> {
 pub fn date_time(selected_text: &str, amount: i64) -> Option<String> {
     date_time::increment(selected_text, amount)
 }
+
+pub fn hex_color(selected_text: &str, amount: i64) -> Option<String> {
+    hex_color::increment(selected_text, amount)
+}
+
+pub fn word(selected_text: &str, amount: i64, word_lists: &[Vec<String>]) -> Option<String> {
+    word::increment(selected_text, amount, word_lists)
+}
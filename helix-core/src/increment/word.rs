@@ -0,0 +1,84 @@
+/// Cycle a word through the list containing it, for example `true` to `false`.
+///
+/// Words are matched case-insensitively. The replacement is written in the case of the selected
+/// word: lower case, upper case or capitalized. `amount` moves that many words forward (or
+/// backward if negative) in the list, wrapping around at its ends.
+pub fn increment(selected_text: &str, amount: i64, word_lists: &[Vec<String>]) -> Option<String> {
+    if selected_text.is_empty() {
+        return None;
+    }
+
+    word_lists.iter().find_map(|words| {
+        let index = words
+            .iter()
+            .position(|word| word.eq_ignore_ascii_case(selected_text))?;
+        let len = words.len() as i64;
+        let new_index = (index as i64).checked_add(amount % len)?.rem_euclid(len);
+        Some(match_case(&words[new_index as usize], selected_text))
+    })
+}
+
+/// Writes `word` in the case of `original`.
+fn match_case(word: &str, original: &str) -> String {
+    let mut chars = original.chars().filter(|c| c.is_alphabetic());
+    let first_upper = chars.next().is_some_and(char::is_uppercase);
+    let rest: Vec<bool> = chars.map(char::is_uppercase).collect();
+
+    if first_upper && !rest.is_empty() && rest.iter().all(|&upper| upper) {
+        word.to_uppercase()
+    } else if first_upper {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars.as_str().chars()).collect())
+            .unwrap_or_default()
+    } else if rest.iter().any(|&upper| upper) {
+        // Mixed case, keep the list's spelling
+        word.to_string()
+    } else {
+        word.to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lists() -> Vec<Vec<String>> {
+        [
+            vec!["true", "false"],
+            vec!["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+        ]
+        .into_iter()
+        .map(|words| words.into_iter().map(String::from).collect())
+        .collect()
+    }
+
+    #[test]
+    fn test_cycle_words() {
+        let lists = lists();
+        let tests = [
+            ("true", 1, "false"),
+            ("false", 1, "true"),
+            ("True", 1, "False"),
+            ("TRUE", -1, "FALSE"),
+            ("true", 2, "true"),
+            ("mon", 1, "tue"),
+            ("Mon", 3, "Thu"),
+            ("Mon", -1, "Sun"),
+            ("Sun", 15, "Mon"),
+        ];
+
+        for (original, amount, expected) in tests {
+            assert_eq!(increment(original, amount, &lists).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_unknown_words() {
+        let lists = lists();
+        for unknown in ["", "maybe", "truth", "Monday"] {
+            assert_eq!(increment(unknown, 1, &lists), None);
+        }
+    }
+}
@@ -215,6 +215,16 @@ pub fn find_matching_bracket_plaintext(doc: RopeSlice, cursor_pos: usize) -> Opt
 /// assert_eq!(get_pair('}'), ('{', '}'));
 /// assert_eq!(get_pair('"'), ('"', '"'));
 /// ```
+/// Like [get_pair] but the `custom_pairs`, from opening to closing character, take
+/// precedence over [PAIRS].
+pub fn get_pair_with(ch: char, custom_pairs: &[(char, char)]) -> (char, char) {
+    custom_pairs
+        .iter()
+        .find(|(open, close)| *open == ch || *close == ch)
+        .copied()
+        .unwrap_or_else(|| get_pair(ch))
+}
+
 pub fn get_pair(ch: char) -> (char, char) {
     PAIRS
         .iter()
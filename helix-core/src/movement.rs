@@ -1,6 +1,7 @@
 use std::{borrow::Cow, cmp::Reverse, iter};
 
 use ropey::iter::Chars;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     char_idx_at_visual_offset,
@@ -31,6 +32,53 @@ pub enum Movement {
     Move,
 }
 
+/// Where words are split into sub words by the sub word motions and textobject, see
+/// `editor.sub-word`. Boundaries between characters of different categories, like a word and
+/// punctuation, always split sub words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SubWordRules {
+    /// Split at transitions from lowercase to uppercase, as in `camelCase`.
+    pub camel_case: bool,
+    /// Split at underscores, as in `snake_case`. The underscores aren't part of the sub words.
+    pub snake_case: bool,
+}
+
+impl Default for SubWordRules {
+    fn default() -> Self {
+        Self {
+            camel_case: true,
+            snake_case: true,
+        }
+    }
+}
+
+impl SubWordRules {
+    /// Whether `ch` separates sub words without being part of one.
+    pub fn is_separator(&self, ch: char) -> bool {
+        ch.is_whitespace() || (self.snake_case && ch == '_')
+    }
+
+    /// Whether there is a sub word boundary between `a` and `b`. Boundaries are directional: in
+    /// `fooBar` there is a boundary between `o` and `B` going forward, and between `B` and `a`
+    /// going backward.
+    pub fn is_boundary(&self, a: char, b: char, dir: Direction) -> bool {
+        match (categorize_char(a), categorize_char(b)) {
+            (CharCategory::Word, CharCategory::Word) => {
+                if self.snake_case && (a == '_') != (b == '_') {
+                    return true;
+                }
+                self.camel_case
+                    && match dir {
+                        Direction::Forward => a.is_lowercase() && b.is_uppercase(),
+                        Direction::Backward => a.is_uppercase() && b.is_lowercase(),
+                    }
+            }
+            (a, b) => a != b,
+        }
+    }
+}
+
 pub fn move_horizontally(
     slice: RopeSlice,
     range: Range,
@@ -197,23 +245,65 @@ pub fn move_prev_long_word_end(slice: RopeSlice, range: Range, count: usize) ->
     word_move(slice, range, count, WordMotionTarget::PrevLongWordEnd)
 }
 
-pub fn move_next_sub_word_start(slice: RopeSlice, range: Range, count: usize) -> Range {
-    word_move(slice, range, count, WordMotionTarget::NextSubWordStart)
+pub fn move_next_sub_word_start(
+    slice: RopeSlice,
+    range: Range,
+    count: usize,
+    rules: SubWordRules,
+) -> Range {
+    word_move_with(
+        slice,
+        range,
+        count,
+        WordMotionTarget::NextSubWordStart,
+        rules,
+    )
 }
 
-pub fn move_next_sub_word_end(slice: RopeSlice, range: Range, count: usize) -> Range {
-    word_move(slice, range, count, WordMotionTarget::NextSubWordEnd)
+pub fn move_next_sub_word_end(
+    slice: RopeSlice,
+    range: Range,
+    count: usize,
+    rules: SubWordRules,
+) -> Range {
+    word_move_with(slice, range, count, WordMotionTarget::NextSubWordEnd, rules)
 }
 
-pub fn move_prev_sub_word_start(slice: RopeSlice, range: Range, count: usize) -> Range {
-    word_move(slice, range, count, WordMotionTarget::PrevSubWordStart)
+pub fn move_prev_sub_word_start(
+    slice: RopeSlice,
+    range: Range,
+    count: usize,
+    rules: SubWordRules,
+) -> Range {
+    word_move_with(
+        slice,
+        range,
+        count,
+        WordMotionTarget::PrevSubWordStart,
+        rules,
+    )
 }
 
-pub fn move_prev_sub_word_end(slice: RopeSlice, range: Range, count: usize) -> Range {
-    word_move(slice, range, count, WordMotionTarget::PrevSubWordEnd)
+pub fn move_prev_sub_word_end(
+    slice: RopeSlice,
+    range: Range,
+    count: usize,
+    rules: SubWordRules,
+) -> Range {
+    word_move_with(slice, range, count, WordMotionTarget::PrevSubWordEnd, rules)
 }
 
 fn word_move(slice: RopeSlice, range: Range, count: usize, target: WordMotionTarget) -> Range {
+    word_move_with(slice, range, count, target, SubWordRules::default())
+}
+
+fn word_move_with(
+    slice: RopeSlice,
+    range: Range,
+    count: usize,
+    target: WordMotionTarget,
+    rules: SubWordRules,
+) -> Range {
     let is_prev = matches!(
         target,
         WordMotionTarget::PrevWordStart
@@ -252,7 +342,9 @@ fn word_move(slice: RopeSlice, range: Range, count: usize, target: WordMotionTar
     // Do the main work.
     let mut range = start_range;
     for _ in 0..count {
-        let next_range = slice.chars_at(range.head).range_to_target(target, range);
+        let next_range = slice
+            .chars_at(range.head)
+            .range_to_target(target, range, rules);
         if range == next_range {
             break;
         }
@@ -410,14 +502,24 @@ pub enum WordMotionTarget {
 }
 
 pub trait CharHelpers {
-    fn range_to_target(&mut self, target: WordMotionTarget, origin: Range) -> Range;
+    fn range_to_target(
+        &mut self,
+        target: WordMotionTarget,
+        origin: Range,
+        rules: SubWordRules,
+    ) -> Range;
 }
 
 impl CharHelpers for Chars<'_> {
     /// Note: this only changes the anchor of the range if the head is effectively
     /// starting on a boundary (either directly or after skipping newline characters).
     /// Any other changes to the anchor should be handled by the calling code.
-    fn range_to_target(&mut self, target: WordMotionTarget, origin: Range) -> Range {
+    fn range_to_target(
+        &mut self,
+        target: WordMotionTarget,
+        origin: Range,
+        rules: SubWordRules,
+    ) -> Range {
         let is_prev = matches!(
             target,
             WordMotionTarget::PrevWordStart
@@ -469,7 +571,7 @@ fn range_to_target(&mut self, target: WordMotionTarget, origin: Range) -> Range
         let head_start = head;
         #[allow(clippy::while_let_on_iterator)] // Clippy's suggestion to fix doesn't work here.
         while let Some(next_ch) = self.next() {
-            if prev_ch.is_none() || reached_target(target, prev_ch.unwrap(), next_ch) {
+            if prev_ch.is_none() || reached_target(target, prev_ch.unwrap(), next_ch, rules) {
                 if head == head_start {
                     anchor = head;
                 } else {
@@ -502,26 +604,12 @@ fn is_long_word_boundary(a: char, b: char) -> bool {
     }
 }
 
-fn is_sub_word_boundary(a: char, b: char, dir: Direction) -> bool {
-    match (categorize_char(a), categorize_char(b)) {
-        (CharCategory::Word, CharCategory::Word) => {
-            if (a == '_') != (b == '_') {
-                return true;
-            }
-
-            // Subword boundaries are directional: in 'fooBar', there is a
-            // boundary between 'o' and 'B', but not between 'B' and 'a'.
-            match dir {
-                Direction::Forward => a.is_lowercase() && b.is_uppercase(),
-                Direction::Backward => a.is_uppercase() && b.is_lowercase(),
-            }
-        }
-        (a, b) if a != b => true,
-        _ => false,
-    }
-}
-
-fn reached_target(target: WordMotionTarget, prev_ch: char, next_ch: char) -> bool {
+fn reached_target(
+    target: WordMotionTarget,
+    prev_ch: char,
+    next_ch: char,
+    rules: SubWordRules,
+) -> bool {
     match target {
         WordMotionTarget::NextWordStart | WordMotionTarget::PrevWordEnd => {
             is_word_boundary(prev_ch, next_ch)
@@ -540,20 +628,20 @@ fn reached_target(target: WordMotionTarget, prev_ch: char, next_ch: char) -> boo
                 && (!prev_ch.is_whitespace() || char_is_line_ending(next_ch))
         }
         WordMotionTarget::NextSubWordStart => {
-            is_sub_word_boundary(prev_ch, next_ch, Direction::Forward)
-                && (char_is_line_ending(next_ch) || !(next_ch.is_whitespace() || next_ch == '_'))
+            rules.is_boundary(prev_ch, next_ch, Direction::Forward)
+                && (char_is_line_ending(next_ch) || !rules.is_separator(next_ch))
         }
         WordMotionTarget::PrevSubWordEnd => {
-            is_sub_word_boundary(prev_ch, next_ch, Direction::Backward)
-                && (char_is_line_ending(next_ch) || !(next_ch.is_whitespace() || next_ch == '_'))
+            rules.is_boundary(prev_ch, next_ch, Direction::Backward)
+                && (char_is_line_ending(next_ch) || !rules.is_separator(next_ch))
         }
         WordMotionTarget::NextSubWordEnd => {
-            is_sub_word_boundary(prev_ch, next_ch, Direction::Forward)
-                && (!(prev_ch.is_whitespace() || prev_ch == '_') || char_is_line_ending(next_ch))
+            rules.is_boundary(prev_ch, next_ch, Direction::Forward)
+                && (!rules.is_separator(prev_ch) || char_is_line_ending(next_ch))
         }
         WordMotionTarget::PrevSubWordStart => {
-            is_sub_word_boundary(prev_ch, next_ch, Direction::Backward)
-                && (!(prev_ch.is_whitespace() || prev_ch == '_') || char_is_line_ending(next_ch))
+            rules.is_boundary(prev_ch, next_ch, Direction::Backward)
+                && (!rules.is_separator(prev_ch) || char_is_line_ending(next_ch))
         }
     }
 }
@@ -1155,7 +1243,12 @@ fn test_behaviour_when_moving_to_start_of_next_sub_words() {
 
         for (sample, scenario) in tests {
             for (count, begin, expected_end) in scenario.into_iter() {
-                let range = move_next_sub_word_start(Rope::from(sample).slice(..), begin, count);
+                let range = move_next_sub_word_start(
+                    Rope::from(sample).slice(..),
+                    begin,
+                    count,
+                    SubWordRules::default(),
+                );
                 assert_eq!(range, expected_end, "Case failed: [{}]", sample);
             }
         }
@@ -1241,7 +1334,12 @@ fn test_behaviour_when_moving_to_end_of_next_sub_words() {
 
         for (sample, scenario) in tests {
             for (count, begin, expected_end) in scenario.into_iter() {
-                let range = move_next_sub_word_end(Rope::from(sample).slice(..), begin, count);
+                let range = move_next_sub_word_end(
+                    Rope::from(sample).slice(..),
+                    begin,
+                    count,
+                    SubWordRules::default(),
+                );
                 assert_eq!(range, expected_end, "Case failed: [{}]", sample);
             }
         }
@@ -1496,7 +1594,12 @@ fn test_behaviour_when_moving_to_start_of_previous_sub_words() {
 
         for (sample, scenario) in tests {
             for (count, begin, expected_end) in scenario.into_iter() {
-                let range = move_prev_sub_word_start(Rope::from(sample).slice(..), begin, count);
+                let range = move_prev_sub_word_start(
+                    Rope::from(sample).slice(..),
+                    begin,
+                    count,
+                    SubWordRules::default(),
+                );
                 assert_eq!(range, expected_end, "Case failed: [{}]", sample);
             }
         }
@@ -1845,7 +1948,12 @@ fn test_behaviour_when_moving_to_end_of_previous_sub_words() {
 
         for (sample, scenario) in tests {
             for (count, begin, expected_end) in scenario.into_iter() {
-                let range = move_prev_sub_word_end(Rope::from(sample).slice(..), begin, count);
+                let range = move_prev_sub_word_end(
+                    Rope::from(sample).slice(..),
+                    begin,
+                    count,
+                    SubWordRules::default(),
+                );
                 assert_eq!(range, expected_end, "Case failed: [{}]", sample);
             }
         }
@@ -2194,4 +2302,34 @@ fn test_behaviour_when_moving_to_next_paragraph_extend() {
             assert_eq!(actual, expected, "\nbefore: `{:?}`", before);
         }
     }
+
+    #[test]
+    fn test_sub_word_rules() {
+        let text = Rope::from("fooBar snake_case");
+        let slice = text.slice(..);
+        let next_starts = |rules| {
+            let mut range = Range::point(0);
+            let mut starts = Vec::new();
+            loop {
+                let next = move_next_sub_word_start(slice, range, 1, rules);
+                if next == range {
+                    break starts;
+                }
+                starts.push(next.from());
+                range = next;
+            }
+        };
+
+        assert_eq!(next_starts(SubWordRules::default()), [0, 3, 7, 13]);
+        let camel_case = SubWordRules {
+            camel_case: true,
+            snake_case: false,
+        };
+        assert_eq!(next_starts(camel_case), [0, 3, 7]);
+        let snake_case = SubWordRules {
+            camel_case: false,
+            snake_case: true,
+        };
+        assert_eq!(next_starts(snake_case), [0, 7, 13]);
+    }
 }
@@ -8,7 +8,7 @@
 
 use crate::{
     chars::char_is_line_ending,
-    doc_formatter::{DocumentFormatter, TextFormat},
+    doc_formatter::{DocumentFormatter, TextFormat, MAX_BLOCK_CHARS},
     graphemes::{ensure_grapheme_boundary_prev, grapheme_width},
     line_ending::line_end_char_index,
     text_annotations::TextAnnotations,
@@ -93,12 +93,19 @@ fn from(tuple: (usize, usize)) -> Self {
 ///
 /// column in `char` count which can be used for row:column display in
 /// status line. See [`visual_coords_at_pos`] for a visual one.
+///
+/// Past [`MAX_BLOCK_CHARS`] into a line the column is the number of chars instead of
+/// graphemes, counting the graphemes of a very long line on every render would be too slow.
 pub fn coords_at_pos(text: RopeSlice, pos: usize) -> Position {
     let line = text.char_to_line(pos);
 
     let line_start = text.line_to_char(line);
     let pos = ensure_grapheme_boundary_prev(text, pos);
-    let col = text.slice(line_start..pos).graphemes().count();
+    let col = if pos - line_start > MAX_BLOCK_CHARS {
+        pos - line_start
+    } else {
+        text.slice(line_start..pos).graphemes().count()
+    };
 
     Position::new(line, col)
 }
@@ -138,9 +145,9 @@ pub fn visual_coords_at_pos(text: RopeSlice, pos: usize, tab_width: usize) -> Po
 /// Returns the visual offset from the start of the first visual line
 /// in the block that contains anchor.
 /// Text is always wrapped at blocks, they usually correspond to
-/// actual line breaks but for very long lines
-/// softwrapping positions are estimated with an O(1) algorithm
-/// to ensure consistent performance for large lines (currently unimplemented)
+/// actual line breaks but very long lines are split into blocks
+/// of constant size to ensure consistent performance for large lines,
+/// see [`crate::doc_formatter::MAX_BLOCK_CHARS`]
 ///
 /// Usually you want to use `visual_offset_from_anchor` instead but this function
 /// can be useful (and faster) if
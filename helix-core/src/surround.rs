@@ -163,6 +163,18 @@ pub fn find_nth_pairs_pos(
     ch: char,
     range: Range,
     n: usize,
+) -> Result<(usize, usize)> {
+    find_nth_pairs_pos_of(syntax, text, get_pair(ch), range, n)
+}
+
+/// Like [find_nth_pairs_pos] for the pair of `open` and `close`, which may be the same
+/// character. Used for pairs which aren't in [`crate::match_brackets::PAIRS`].
+pub fn find_nth_pairs_pos_of(
+    syntax: Option<&Syntax>,
+    text: RopeSlice,
+    (open, close): (char, char),
+    range: Range,
+    n: usize,
 ) -> Result<(usize, usize)> {
     if text.len_chars() < 2 {
         return Err(Error::PairNotFound);
@@ -171,7 +183,6 @@ pub fn find_nth_pairs_pos(
         return Err(Error::RangeExceedsText);
     }
 
-    let (open, close) = get_pair(ch);
     let pos = range.cursor(text);
 
     let (open, close) = if open == close {
@@ -308,13 +319,24 @@ pub fn get_surround_pos(
     selection: &Selection,
     ch: Option<char>,
     skip: usize,
+) -> Result<Vec<usize>> {
+    get_surround_pos_of(syntax, text, selection, ch.map(get_pair), skip)
+}
+
+/// Like [get_surround_pos] for the pair of `open` and `close` characters.
+pub fn get_surround_pos_of(
+    syntax: Option<&Syntax>,
+    text: RopeSlice,
+    selection: &Selection,
+    pair: Option<(char, char)>,
+    skip: usize,
 ) -> Result<Vec<usize>> {
     let mut change_pos = Vec::new();
 
     for &range in selection {
         let (open_pos, close_pos) = {
-            let range_raw = match ch {
-                Some(ch) => find_nth_pairs_pos(syntax, text, ch, range, skip)?,
+            let range_raw = match pair {
+                Some(pair) => find_nth_pairs_pos_of(syntax, text, pair, range, skip)?,
                 None => find_nth_closest_pairs_pos(syntax, text, range, skip)?,
             };
             let range = Range::new(range_raw.0, range_raw.1);
@@ -352,6 +374,21 @@ fn test_get_surround_pos() {
         );
     }
 
+    #[test]
+    fn test_get_surround_pos_of_custom_pair() {
+        #[rustfmt::skip]
+        let (doc, selection, expectations) =
+            rope_with_selections_and_expectations(
+                "/some\\ /chars\\",
+                "_ ^  _ _ ^   _"
+            );
+
+        assert_eq!(
+            get_surround_pos_of(None, doc.slice(..), &selection, Some(('/', '\\')), 1).unwrap(),
+            expectations
+        );
+    }
+
     #[test]
     fn test_get_surround_pos_bail_different_surround_chars() {
         #[rustfmt::skip]
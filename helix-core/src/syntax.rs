@@ -93,12 +93,14 @@ pub fn compile_syntax_config(
     fn syntax_config(&self, loader: &Loader) -> Option<&SyntaxConfig> {
         self.syntax
             .get_or_init(|| {
-                Self::compile_syntax_config(&self.config, loader)
-                    .map_err(|err| {
-                        log::error!("{err:#}");
-                    })
-                    .ok()
-                    .flatten()
+                helix_loader::startup::measure("grammar loading", || {
+                    Self::compile_syntax_config(&self.config, loader)
+                })
+                .map_err(|err| {
+                    log::error!("{err:#}");
+                })
+                .ok()
+                .flatten()
             })
             .as_ref()
     }
@@ -140,7 +142,20 @@ pub fn compile_textobject_query(
         config: &LanguageConfiguration,
     ) -> Result<Option<TextObjectQuery>> {
         let name = &config.language_id;
-        let text = read_query(name, "textobjects.scm");
+        let mut text = read_query(name, "textobjects.scm");
+        // Invalid queries from the configuration are skipped so that they don't break the
+        // text objects from textobjects.scm, `hx --health` reports them.
+        for textobject in &config.textobjects {
+            if let Err(err) = Query::new(grammar, &textobject.query, |_, _| Ok(())) {
+                log::error!(
+                    "Failed to compile the query of the '{}' textobject for '{name}': {err}",
+                    textobject.name
+                );
+                continue;
+            }
+            text.push('\n');
+            text.push_str(&textobject.query);
+        }
         if text.is_empty() {
             return Ok(None);
         }
@@ -274,7 +289,8 @@ pub fn read_query(lang: &str, query_filename: &str) -> String {
 #[derive(Debug, Default)]
 pub struct Loader {
     languages: Vec<LanguageData>,
-    languages_by_extension: HashMap<String, Language>,
+    /// The languages for each extension, in the order they are configured.
+    languages_by_extension: HashMap<String, Vec<Language>>,
     languages_by_shebang: HashMap<String, Language>,
     languages_glob_matcher: FileTypeGlobMatcher,
     language_server_configs: HashMap<String, LanguageServerConfiguration>,
@@ -297,7 +313,10 @@ pub fn new(config: Configuration) -> Result<Self, LoaderError> {
             for file_type in &config.file_types {
                 match file_type {
                     FileType::Extension(extension) => {
-                        languages_by_extension.insert(extension.clone(), language);
+                        languages_by_extension
+                            .entry(extension.clone())
+                            .or_default()
+                            .push(language);
                     }
                     FileType::Glob(glob) => {
                         file_type_globs.push(FileTypeGlob::new(glob.to_owned(), language));
@@ -384,10 +403,62 @@ pub fn language_for_filename(&self, path: &Path) -> Option<Language> {
             .or_else(|| {
                 path.extension()
                     .and_then(|extension| extension.to_str())
-                    .and_then(|extension| self.languages_by_extension.get(extension).copied())
+                    .and_then(|extension| self.languages_by_extension.get(extension))
+                    .and_then(|languages| languages.last().copied())
             })
     }
 
+    /// Detects the language of a file from its name, shebang and content. The content rules of
+    /// the languages decide between languages sharing the file's extension, and detect the
+    /// language of files whose name and shebang don't match any language.
+    pub fn language_for_file(&self, path: Option<&Path>, text: RopeSlice) -> Option<Language> {
+        if let Some(path) = path {
+            if let Some(language) = self.languages_glob_matcher.language_for_path(path) {
+                return Some(language);
+            }
+            let candidates = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(|extension| self.languages_by_extension.get(extension));
+            if let Some(candidates) = candidates {
+                if candidates.len() > 1 {
+                    let language =
+                        self.language_for_content(text, |language| candidates.contains(&language));
+                    if language.is_some() {
+                        return language;
+                    }
+                }
+                return candidates.last().copied();
+            }
+        }
+        self.language_for_shebang(text)
+            .or_else(|| self.language_for_content(text, |_| true))
+    }
+
+    /// Returns the language whose content rule with the highest priority matches `text`,
+    /// considering only the languages accepted by `filter`.
+    fn language_for_content(
+        &self,
+        text: RopeSlice,
+        filter: impl Fn(Language) -> bool,
+    ) -> Option<Language> {
+        let mut best: Option<(i32, Language)> = None;
+        for (language, data) in self.languages() {
+            if !filter(language) {
+                continue;
+            }
+            for rule in &data.config.content_rules {
+                if best.is_some_and(|(priority, _)| priority >= rule.priority) {
+                    continue;
+                }
+                if rule.is_match(text) {
+                    best = Some((rule.priority, language));
+                }
+            }
+        }
+        best.map(|(_, language)| language)
+    }
+
     pub fn language_for_shebang(&self, text: RopeSlice) -> Option<Language> {
         // NOTE: this is slightly different than the one for injection markers in tree-house. It
         // is anchored at the beginning.
@@ -420,6 +491,18 @@ pub fn tag_query(&self, lang: Language) -> Option<&TagQuery> {
         self.language(lang).tag_query(self)
     }
 
+    /// Compiles a user-provided structural search query for the given language.
+    ///
+    /// Returns `None` if the grammar for the language is not available.
+    pub fn structural_query(
+        &self,
+        lang: Language,
+        source: &str,
+    ) -> Option<Result<StructuralQuery, tree_sitter::query::ParseError>> {
+        let grammar = self.language(lang).syntax_config(self)?.grammar;
+        Some(StructuralQuery::new(grammar, source))
+    }
+
     fn rainbow_query(&self, lang: Language) -> Option<&RainbowQuery> {
         self.language(lang).rainbow_query(self)
     }
@@ -626,10 +709,11 @@ pub fn tags<'a>(
         )
     }
 
+    /// Highlights brackets by their nesting depth, cycling through the `palette`.
     pub fn rainbow_highlights(
         &self,
         source: RopeSlice,
-        rainbow_length: usize,
+        palette: &[Highlight],
         loader: &Loader,
         range: impl RangeBounds<u32>,
     ) -> OverlayHighlights {
@@ -641,6 +725,9 @@ struct RainbowScope<'tree> {
 
         let mut scope_stack = Vec::<RainbowScope>::new();
         let mut highlights = Vec::new();
+        if palette.is_empty() {
+            return OverlayHighlights::Heterogenous { highlights };
+        }
         let mut query_iter = self.query_iter::<_, (), _>(
             source,
             |lang| loader.rainbow_query(lang).map(|q| &q.query),
@@ -677,7 +764,7 @@ struct RainbowScope<'tree> {
                     } else {
                         Some(mat.node.clone())
                     },
-                    highlight: Highlight::new((scope_stack.len() % rainbow_length) as u32),
+                    highlight: palette[scope_stack.len() % palette.len()],
                 });
             } else if capture == rainbow_query.bracket_capture {
                 if let Some(scope) = scope_stack.last() {
@@ -1073,6 +1160,150 @@ pub struct TagQuery {
     pub query: Query,
 }
 
+/// A user-provided query used for structural (syntax-aware) search.
+///
+/// Besides plain tree-sitter query syntax, metavariables like `$name` are accepted as a
+/// shorthand for `(_) @name`. If the query contains a `@match` capture only nodes captured by
+/// it are reported, otherwise every captured node is. Queries without any capture are captured
+/// as a whole.
+///
+/// ```query
+/// (call_expression function: $callee arguments: $args)
+/// ```
+#[derive(Debug)]
+pub struct StructuralQuery {
+    query: Query,
+    match_capture: Option<Capture>,
+}
+
+impl StructuralQuery {
+    pub fn new(grammar: Grammar, source: &str) -> Result<Self, tree_sitter::query::ParseError> {
+        let source = expand_metavariables(source);
+        let query = Query::new(grammar, &source, |_pattern, predicate| {
+            Err(InvalidPredicateError::unknown(predicate))
+        })?;
+
+        Ok(Self {
+            match_capture: query.get_capture("match"),
+            query,
+        })
+    }
+
+    /// Runs the query on the given node and returns the byte ranges of the reported captures
+    /// which start within `range`.
+    pub fn matches<'a>(
+        &'a self,
+        node: &Node<'a>,
+        slice: RopeSlice<'a>,
+        range: ops::Range<u32>,
+    ) -> impl Iterator<Item = ops::Range<u32>> + 'a {
+        let match_capture = self.match_capture;
+        let mut cursor = InactiveQueryCursor::new(range, TREE_SITTER_MATCH_LIMIT).execute_query(
+            &self.query,
+            node,
+            RopeInput::new(slice),
+        );
+        iter::from_fn(move || {
+            let mat = cursor.next_match()?;
+            let ranges: Vec<_> = match match_capture {
+                Some(capture) => mat
+                    .nodes_for_capture(capture)
+                    .map(|node| node.byte_range())
+                    .collect(),
+                None => mat
+                    .matched_nodes()
+                    .map(|matched_node| matched_node.node.byte_range())
+                    .collect(),
+            };
+            Some(ranges)
+        })
+        .flatten()
+    }
+
+    /// Runs the query on the given node and returns every captured node which starts within
+    /// `range` together with the name of its capture, regardless of `@match`.
+    pub fn captures<'a>(
+        &'a self,
+        node: &Node<'a>,
+        slice: RopeSlice<'a>,
+        range: ops::Range<u32>,
+    ) -> impl Iterator<Item = (&'a str, ops::Range<u32>)> + 'a {
+        let mut cursor = InactiveQueryCursor::new(range, TREE_SITTER_MATCH_LIMIT).execute_query(
+            &self.query,
+            node,
+            RopeInput::new(slice),
+        );
+        iter::from_fn(move || {
+            let mat = cursor.next_match()?;
+            let captures: Vec<_> = mat
+                .matched_nodes()
+                .map(|matched_node| {
+                    let name = self.query.capture_name(matched_node.capture);
+                    (name, matched_node.node.byte_range())
+                })
+                .collect();
+            Some(captures)
+        })
+        .flatten()
+    }
+}
+
+/// Rewrites `$name` metavariables into `(_) @name` captures and captures the whole pattern as
+/// `@match` when the query has no captures at all. String literals and `;` comments are left
+/// untouched.
+fn expand_metavariables(source: &str) -> Cow<'_, str> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    if !source.contains(['$', ';']) && source.contains('@') {
+        return Cow::Borrowed(source);
+    }
+
+    let mut expanded = String::with_capacity(source.len());
+    let mut has_capture = false;
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' if in_comment => {
+                in_comment = false;
+                expanded.push(c);
+            }
+            _ if in_comment => expanded.push(c),
+            ';' if !in_string => {
+                in_comment = true;
+                expanded.push(c);
+            }
+            '\\' if in_string => {
+                expanded.push(c);
+                expanded.extend(chars.next());
+            }
+            '"' => {
+                in_string = !in_string;
+                expanded.push(c);
+            }
+            '@' if !in_string => {
+                has_capture = true;
+                expanded.push(c);
+            }
+            '$' if !in_string && chars.peek().is_some_and(|&c| is_ident(c)) => {
+                has_capture = true;
+                expanded.push_str("(_) @");
+                while let Some(c) = chars.next_if(|&c| is_ident(c)) {
+                    expanded.push(c);
+                }
+            }
+            _ => expanded.push(c),
+        }
+    }
+    if !has_capture {
+        if in_comment {
+            expanded.push('\n');
+        }
+        expanded.push_str(" @match");
+    }
+    Cow::Owned(expanded)
+}
+
 pub fn pretty_print_tree<W: fmt::Write>(fmt: &mut W, node: Node) -> fmt::Result {
     if node.child_count() == 0 {
         if node_is_visible(&node) {
@@ -1251,6 +1482,65 @@ fn test_textobject_queries() {
         // test("multiple_nodes_grouped", 1..37);
     }
 
+    #[test]
+    fn test_expand_metavariables() {
+        assert_eq!(
+            expand_metavariables("(call_expression function: $callee)"),
+            "(call_expression function: (_) @callee)"
+        );
+        assert_eq!(
+            expand_metavariables("(line_comment)"),
+            "(line_comment) @match"
+        );
+        assert_eq!(
+            expand_metavariables(r#"((identifier) @id (#eq? @id "$x"))"#),
+            r#"((identifier) @id (#eq? @id "$x"))"#
+        );
+        assert_eq!(
+            expand_metavariables(r#"((string) (#match? "\"$"))"#),
+            r#"((string) (#match? "\"$")) @match"#
+        );
+        // Comments are left alone and don't end up around the `@match` capture
+        assert_eq!(
+            expand_metavariables("; calls of $f\n(call_expression function: $callee)"),
+            "; calls of $f\n(call_expression function: (_) @callee)"
+        );
+        assert_eq!(
+            expand_metavariables("(line_comment) ; with @capture"),
+            "(line_comment) ; with @capture\n @match"
+        );
+    }
+
+    #[test]
+    fn test_structural_query() {
+        let source = Rope::from_str("fn a() { foo(1); bar(2, 3); }");
+        let language = LOADER.language_for_name("rust").unwrap();
+        let syntax = Syntax::new(source.slice(..), language, &LOADER).unwrap();
+        let query = LOADER
+            .structural_query(language, "(call_expression function: $callee)")
+            .unwrap()
+            .unwrap();
+
+        let root = syntax.tree().root_node();
+        let callees: Vec<_> = query
+            .matches(&root, source.slice(..), 0..u32::MAX)
+            .map(|range| source.byte_slice(range.start as usize..range.end as usize))
+            .collect();
+        assert_eq!(callees, ["foo", "bar"]);
+
+        let captures: Vec<_> = query
+            .captures(&root, source.slice(..), 0..u32::MAX)
+            .map(|(name, range)| {
+                let text = source.byte_slice(range.start as usize..range.end as usize);
+                (name, text.to_string())
+            })
+            .collect();
+        assert_eq!(
+            captures,
+            [("callee", "foo".to_string()), ("callee", "bar".to_string())]
+        );
+    }
+
     #[test]
     fn test_input_edits() {
         use tree_sitter::{InputEdit, Point};
@@ -1389,4 +1679,42 @@ fn test_pretty_print() {
             source.len(),
         );
     }
+
+    #[test]
+    fn test_content_rules() {
+        let config: Configuration = toml::from_str(
+            r#"
+            [[language]]
+            name = "xml"
+            scope = "text.xml"
+            file-types = ["xml", "page"]
+            content-rules = [{ first-line = '^<\?xml' }]
+
+            [[language]]
+            name = "html"
+            scope = "text.html.basic"
+            file-types = ["page"]
+            content-rules = [{ doctype = "html", priority = 1 }]
+            "#,
+        )
+        .unwrap();
+        let loader = Loader::new(config).unwrap();
+        let detect = |path: Option<&str>, text: &str| {
+            let language =
+                loader.language_for_file(path.map(Path::new), Rope::from(text).slice(..));
+            language.map(|language| loader.language(language).config().language_id.as_str())
+        };
+
+        let xml = "<?xml version=\"1.0\"?>\n<root/>\n";
+        let xhtml = "<?xml version=\"1.0\"?>\n<!DOCTYPE HTML>\n<html/>\n";
+        assert_eq!(detect(Some("a.page"), xml), Some("xml"));
+        assert_eq!(detect(Some("a.page"), xhtml), Some("html"));
+        // The last language wins without a matching rule
+        assert_eq!(detect(Some("a.page"), "text"), Some("html"));
+        // Rules don't override an unambiguous extension
+        assert_eq!(detect(Some("a.xml"), xhtml), Some("xml"));
+        assert_eq!(detect(Some("page"), xml), Some("xml"));
+        assert_eq!(detect(None, "<!doctype html>"), Some("html"));
+        assert_eq!(detect(None, "text"), None);
+    }
 }
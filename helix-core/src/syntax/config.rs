@@ -1,6 +1,9 @@
-use crate::{auto_pairs::AutoPairs, diagnostic::Severity, Language};
+use crate::{
+    auto_pairs::AutoPairs, diagnostic::Severity, line_ending::line_without_line_ending, Language,
+    RopeSlice,
+};
 
-use helix_stdx::rope;
+use helix_stdx::rope::{self, RopeSliceExt as _};
 use serde::{ser::SerializeSeq as _, Deserialize, Serialize};
 use serde_json::Value;
 
@@ -35,8 +38,15 @@ pub struct LanguageConfiguration {
     pub file_types: Vec<FileType>, // filename extension or ends_with? <Gemfile, rb, etc>
     #[serde(default)]
     pub shebangs: Vec<String>, // interpreter(s) associated with language
+    /// Rules detecting the language from the content of files whose name doesn't determine
+    /// their language, see [`ContentRule`].
+    #[serde(default, skip_serializing)]
+    pub content_rules: Vec<ContentRule>,
     #[serde(default)]
     pub roots: RootMarkers, // these indicate project roots <.git, Cargo.toml>
+    /// Which directory containing one of the `roots` language servers are started in.
+    #[serde(default)]
+    pub root_detection: RootDetection,
     #[serde(
         default,
         skip_serializing,
@@ -59,6 +69,10 @@ pub struct LanguageConfiguration {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub formatter: Option<FormatterConfiguration>,
 
+    /// The interpreter code is sent to with `send_to_repl`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repl: Option<ReplConfiguration>,
+
     /// If set, overrides `editor.path-completion`.
     pub path_completion: Option<bool>,
     /// If set, overrides `editor.word-completion`.
@@ -94,7 +108,8 @@ pub struct LanguageConfiguration {
     #[serde(default, skip_serializing, deserialize_with = "deserialize_auto_pairs")]
     pub auto_pairs: Option<AutoPairs>,
 
-    pub rulers: Option<Vec<u16>>, // if set, override editor's rulers
+    pub rulers: Option<Vec<u16>>,     // if set, override editor's rulers
+    pub gutters: Option<Vec<String>>, // if set, override editor's gutter layout
 
     /// Hardcoded LSP root directories relative to the workspace root, like `examples` or `tools/fuzz`.
     /// Falling back to the current working directory if none are configured.
@@ -103,6 +118,18 @@ pub struct LanguageConfiguration {
     pub persistent_diagnostic_sources: Vec<String>,
     /// Overrides the `editor.rainbow-brackets` config key for the language.
     pub rainbow_brackets: Option<bool>,
+    /// Insert mode abbreviations for the language. These take precedence over the
+    /// abbreviations in `editor.abbreviations`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub abbreviations: HashMap<String, String>,
+    /// Additional pairs for the surround commands and the `mi`/`ma` text objects, from the
+    /// opening to the closing character. These take precedence over `editor.surround-pairs`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub surround_pairs: HashMap<char, char>,
+    /// Text objects defined by tree-sitter queries, in addition to the ones from the
+    /// language's `textobjects.scm`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub textobjects: Vec<TextObjectConfiguration>,
 }
 
 impl LanguageConfiguration {
@@ -450,6 +477,17 @@ pub struct FormatterConfiguration {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environment: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ReplConfiguration {
+    pub command: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
@@ -499,6 +537,75 @@ pub struct DebuggerQuirks {
     pub absolute_paths: bool,
 }
 
+/// A text object defined in the language configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TextObjectConfiguration {
+    /// The name of the text object. The query captures `<name>.inside` and `<name>.around`,
+    /// like the queries in `textobjects.scm`.
+    pub name: String,
+    /// The key selecting the text object after `mi` and `ma`. Not needed for the names
+    /// which already have a key, like `function`.
+    pub key: Option<char>,
+    pub query: String,
+}
+
+/// A rule detecting a language from the start of a file. Content rules are used when the file's
+/// extension is shared by several languages and for files whose name and shebang don't match
+/// any language.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ContentRule {
+    /// A regex matched against the first line, without its line ending.
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub first_line: Option<rope::Regex>,
+    /// The root element of the `<!DOCTYPE ...>` declaration of an XML or HTML document,
+    /// compared case insensitively.
+    pub doctype: Option<String>,
+    /// When rules of several languages match, the rule with the highest priority wins.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl ContentRule {
+    /// Whether all conditions of the rule match `text`. A rule without conditions never
+    /// matches.
+    pub fn is_match(&self, text: RopeSlice) -> bool {
+        if self.first_line.is_none() && self.doctype.is_none() {
+            return false;
+        }
+        if let Some(regex) = &self.first_line {
+            let first_line = line_without_line_ending(&text, 0);
+            if !regex.is_match(first_line.regex_input()) {
+                return false;
+            }
+        }
+        if let Some(doctype) = &self.doctype {
+            if !xml_doctype(text).is_some_and(|root| root.eq_ignore_ascii_case(doctype)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Returns the root element of the doctype declaration at the start of an XML or HTML
+/// document, for example `html` for `<!DOCTYPE html>`.
+fn xml_doctype(text: RopeSlice) -> Option<String> {
+    /// The doctype follows the XML declaration and comments, only the start of the text is
+    /// searched for it.
+    const MAX_PROLOG_CHARS: usize = 1024;
+    const DOCTYPE: &str = "<!doctype";
+
+    let prolog = String::from(text.slice(..text.len_chars().min(MAX_PROLOG_CHARS)));
+    let start = prolog.to_ascii_lowercase().find(DOCTYPE)? + DOCTYPE.len();
+    let root = prolog[start..]
+        .trim_start()
+        .split(|ch: char| ch.is_whitespace() || ch == '>' || ch == '[')
+        .next()?;
+    (!root.is_empty()).then(|| root.to_string())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct IndentationConfiguration {
@@ -507,6 +614,23 @@ pub struct IndentationConfiguration {
     pub unit: String,
 }
 
+/// How the root directory of a language server is found from the directory of a file, see
+/// `helix_lsp::find_lsp_workspace`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RootDetection {
+    /// The outermost directory containing a root marker, e.g. the workspace of a Cargo
+    /// workspace rather than one of its crates.
+    #[default]
+    Outermost,
+    /// The innermost directory containing a root marker, e.g. each crate of a Cargo workspace
+    /// is a root of its own.
+    Nearest,
+    /// Always the workspace, a single root for all files of the workspace regardless of root
+    /// markers and `workspace-lsp-roots`.
+    Workspace,
+}
+
 /// How the indentation for a newly inserted line should be determined.
 /// If the selected heuristic is not available (e.g. because the current
 /// language has no tree-sitter indent queries), a simpler one will be used.
@@ -523,6 +647,10 @@ pub enum IndentationHeuristic {
     Hybrid,
 }
 
+impl IndentationHeuristic {
+    pub const VARIANTS: &'static [&'static str] = &["simple", "tree-sitter", "hybrid"];
+}
+
 /// Configuration for auto pairs
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields, untagged)]
@@ -594,6 +722,18 @@ pub struct SoftWrap {
     pub wrap_indicator: Option<String>,
     /// Softwrap at `text_width` instead of viewport width if it is shorter
     pub wrap_at_text_width: Option<bool>,
+    /// Wrap lines at word boundaries (within `max_wrap`). If disabled lines are always wrapped
+    /// at the last column, splitting words.
+    ///
+    /// Default to true
+    pub wrap_at_word: Option<bool>,
+    /// Additional indentation of soft wrapped lines, on top of the indentation carried over
+    /// from the wrapped line.
+    ///
+    /// This is automatically hard-limited to a quarter of the viewport.
+    ///
+    /// Default to 0
+    pub continuation_indent: Option<u16>,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
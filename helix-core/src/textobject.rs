@@ -2,10 +2,11 @@
 
 use ropey::RopeSlice;
 
-use crate::chars::{categorize_char, char_is_whitespace, CharCategory};
+use crate::chars::{categorize_char, char_is_line_ending, char_is_whitespace, CharCategory};
 use crate::graphemes::{next_grapheme_boundary, prev_grapheme_boundary};
 use crate::line_ending::rope_is_line_ending;
-use crate::movement::Direction;
+use crate::match_brackets::get_pair;
+use crate::movement::{Direction, SubWordRules};
 use crate::syntax;
 use crate::Range;
 use crate::{surround, Syntax};
@@ -111,6 +112,48 @@ pub fn textobject_word(
     }
 }
 
+/// Selects the sub word under the cursor, with the rules of `rules`. Around also selects the
+/// separators after it, or the ones before it at the end of a word.
+pub fn textobject_sub_word(
+    slice: RopeSlice,
+    range: Range,
+    textobject: TextObject,
+    rules: SubWordRules,
+) -> Range {
+    let pos = range.cursor(slice);
+    match slice.get_char(pos) {
+        Some(ch) if !rules.is_separator(ch) => (),
+        _ => return Range::new(pos, pos),
+    }
+    let is_boundary =
+        |pos: usize| rules.is_boundary(slice.char(pos - 1), slice.char(pos), Direction::Forward);
+
+    let mut start = pos;
+    while start > 0 && !is_boundary(start) {
+        start -= 1;
+    }
+    let mut end = pos + 1;
+    while end < slice.len_chars() && !is_boundary(end) {
+        end += 1;
+    }
+
+    match textobject {
+        TextObject::Inside => Range::new(start, end),
+        TextObject::Around => {
+            let is_separator = |ch: &char| rules.is_separator(*ch) && !char_is_line_ending(*ch);
+            let separators_right = slice.chars_at(end).take_while(is_separator).count();
+            if separators_right > 0 {
+                Range::new(start, end + separators_right)
+            } else {
+                let mut iter = slice.chars_at(start);
+                iter.reverse();
+                Range::new(start - iter.take_while(is_separator).count(), end)
+            }
+        }
+        TextObject::Movement => unreachable!(),
+    }
+}
+
 pub fn textobject_paragraph(
     slice: RopeSlice,
     range: Range,
@@ -205,7 +248,19 @@ pub fn textobject_pair_surround(
     ch: char,
     count: usize,
 ) -> Range {
-    textobject_pair_surround_impl(syntax, slice, range, textobject, Some(ch), count)
+    textobject_pair_surround_impl(syntax, slice, range, textobject, Some(get_pair(ch)), count)
+}
+
+/// Like [textobject_pair_surround] for the pair of `open` and `close` characters.
+pub fn textobject_pair_surround_of(
+    syntax: Option<&Syntax>,
+    slice: RopeSlice,
+    range: Range,
+    textobject: TextObject,
+    pair: (char, char),
+    count: usize,
+) -> Range {
+    textobject_pair_surround_impl(syntax, slice, range, textobject, Some(pair), count)
 }
 
 pub fn textobject_pair_surround_closest(
@@ -223,11 +278,11 @@ fn textobject_pair_surround_impl(
     slice: RopeSlice,
     range: Range,
     textobject: TextObject,
-    ch: Option<char>,
+    pair: Option<(char, char)>,
     count: usize,
 ) -> Range {
-    let pair_pos = match ch {
-        Some(ch) => surround::find_nth_pairs_pos(syntax, slice, ch, range, count),
+    let pair_pos = match pair {
+        Some(pair) => surround::find_nth_pairs_pos_of(syntax, slice, pair, range, count),
         None => surround::find_nth_closest_pairs_pos(syntax, slice, range, count),
     };
     pair_pos
@@ -297,6 +352,37 @@ mod test {
     use crate::Range;
     use ropey::Rope;
 
+    #[test]
+    fn test_textobject_sub_word() {
+        let text = Rope::from("fooBarBaz snake_case_name\n");
+        let slice = text.slice(..);
+        let rules = SubWordRules::default();
+        // (cursor, textobject, expected range)
+        let tests = [
+            (0, Inside, (0, 3)),
+            (4, Inside, (3, 6)),
+            (7, Around, (6, 10)),
+            (12, Inside, (10, 15)),
+            (16, Inside, (16, 20)),
+            (16, Around, (16, 21)),
+            (23, Around, (20, 25)),
+            (15, Inside, (15, 15)),
+        ];
+        for (cursor, textobject, expected) in tests {
+            let range = textobject_sub_word(slice, Range::point(cursor), textobject, rules);
+            assert_eq!(range, expected.into(), "cursor at {cursor}");
+        }
+
+        let rules = SubWordRules {
+            camel_case: false,
+            snake_case: false,
+        };
+        let range = textobject_sub_word(slice, Range::point(4), Inside, rules);
+        assert_eq!(range, (0, 9).into());
+        let range = textobject_sub_word(slice, Range::point(12), Inside, rules);
+        assert_eq!(range, (10, 25).into());
+    }
+
     #[test]
     fn test_textobject_word() {
         // (text, [(char position, textobject, final range), ...])
@@ -1,3 +1,6 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::str::from_utf8;
 
 /// Default built-in languages.toml.
@@ -7,24 +10,250 @@ pub fn default_lang_config() -> toml::Value {
         .expect("Could not parse built-in languages.toml to valid toml")
 }
 
-/// User configured languages.toml file, merged with the default config.
-pub fn user_lang_config() -> Result<toml::Value, toml::de::Error> {
-    let config = [
-        crate::config_dir(),
-        crate::find_workspace().0.join(".helix"),
+/// The user's languages.toml files which exist, global first, with their contents.
+pub fn user_lang_config_files() -> Vec<(PathBuf, String)> {
+    [
+        crate::lang_config_file(),
+        crate::find_workspace()
+            .0
+            .join(".helix")
+            .join("languages.toml"),
     ]
     .into_iter()
-    .map(|path| path.join("languages.toml"))
     .filter_map(|file| {
-        std::fs::read_to_string(file)
-            .map(|config| toml::from_str(&config))
-            .ok()
+        let config = std::fs::read_to_string(&file).ok()?;
+        Some((file, config))
     })
-    .collect::<Result<Vec<_>, _>>()?
-    .into_iter()
-    .fold(default_lang_config(), |a, b| {
-        crate::merge_toml_values(a, b, 3)
-    });
+    .collect()
+}
+
+/// User configured languages.toml file, merged with the default config.
+pub fn user_lang_config() -> Result<toml::Value, ConfigDiagnostic> {
+    let config = user_lang_config_files()
+        .into_iter()
+        .map(|(file, config)| {
+            toml::from_str(&config)
+                .map_err(|err| ConfigDiagnostic::new(&err).with_source(&file, &config, err.span()))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .fold(default_lang_config(), |a, b| {
+            crate::merge_toml_values(a, b, 3)
+        });
 
     Ok(config)
 }
+
+/// An error in a configuration file, located as precisely as possible so that it can be shown
+/// to the user together with the offending line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    pub message: String,
+    /// The file the error was found in.
+    pub path: Option<PathBuf>,
+    /// The 1-based line and column of the error.
+    pub position: Option<(usize, usize)>,
+    /// The text of the line the error is on.
+    pub line: Option<String>,
+    /// The unknown key or value, for errors about one.
+    pub key: Option<String>,
+    /// A known key or value which is similar to `key`.
+    pub suggestion: Option<String>,
+}
+
+impl ConfigDiagnostic {
+    pub fn new(err: &toml::de::Error) -> Self {
+        let message = err.message().trim().to_string();
+        let (key, suggestion) = match unknown_name(&message) {
+            Some((key, expected)) => {
+                let suggestion = did_you_mean(key, &expected).map(str::to_string);
+                (Some(key.to_string()), suggestion)
+            }
+            None => (None, None),
+        };
+        Self {
+            message,
+            path: None,
+            position: None,
+            line: None,
+            key,
+            suggestion,
+        }
+    }
+
+    /// Locates the error in `source`, which was read from `path`. Errors from deserializing a
+    /// `toml::Value` have no `span`, in that case the line of the offending key is used if it
+    /// can be found.
+    pub fn with_source(mut self, path: &Path, source: &str, span: Option<Range<usize>>) -> Self {
+        let offset = span.map(|span| span.start).or_else(|| {
+            let key = self.key.as_deref()?;
+            find_key(source, key)
+        });
+        if let Some(offset) = offset.filter(|&offset| offset <= source.len()) {
+            let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[offset..]
+                .find('\n')
+                .map_or(source.len(), |i| offset + i);
+            let line = source[..offset].matches('\n').count() + 1;
+            let column = source[line_start..offset].chars().count() + 1;
+            self.position = Some((line, column));
+            self.line = Some(source[line_start..line_end].trim_end().to_string());
+        }
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    /// Locates an error without a span in `files`, given as paths with their contents from the
+    /// least to the most specific. The most specific file which contains the offending key wins.
+    pub fn locate(self, files: &[(PathBuf, String)]) -> Self {
+        let key = match &self.key {
+            Some(key) => key,
+            None => return self,
+        };
+        match files
+            .iter()
+            .rev()
+            .find(|(_, source)| find_key(source, key).is_some())
+        {
+            Some((path, source)) => self.with_source(path, source, None),
+            None => self,
+        }
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}", path.display())?;
+            if let Some((line, column)) = self.position {
+                write!(f, ":{line}:{column}")?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let (Some(line), Some((_, column))) = (&self.line, self.position) {
+            write!(f, "\n    {line}\n    {:>column$}", "^")?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\ndid you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigDiagnostic {}
+
+/// Parses serde's "unknown field" and "unknown variant" messages into the unknown name and the
+/// names which were expected instead.
+fn unknown_name(message: &str) -> Option<(&str, Vec<&str>)> {
+    let rest = message
+        .strip_prefix("unknown field ")
+        .or_else(|| message.strip_prefix("unknown variant "))?;
+    let mut quoted = rest.split('`').skip(1).step_by(2);
+    let name = quoted.next()?;
+    Some((name, quoted.collect()))
+}
+
+/// Returns the candidate closest to `name`, if any is close enough to be a likely typo.
+fn did_you_mean<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(2);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Finds the byte offset of the first line which assigns `key`, like `key = ...` or
+/// `key.nested = ...`.
+fn find_key(source: &str, key: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let unquoted = trimmed
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_prefix(key))
+            .and_then(|rest| rest.strip_prefix('"'))
+            .or_else(|| trimmed.strip_prefix(key));
+        if let Some(rest) = unquoted {
+            if rest.trim_start().starts_with(['=', '.']) {
+                return Some(offset + line.len() - trimmed.len());
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Sample {
+        scrolloff: Option<usize>,
+        mouse: Option<bool>,
+    }
+
+    #[test]
+    fn unknown_key_with_span() {
+        let source = "scrolloff = 5\nmosue = true\n";
+        let err = toml::from_str::<Sample>(source).unwrap_err();
+        let diagnostic =
+            ConfigDiagnostic::new(&err).with_source(Path::new("config.toml"), source, err.span());
+
+        assert_eq!(diagnostic.key.as_deref(), Some("mosue"));
+        assert_eq!(diagnostic.suggestion.as_deref(), Some("mouse"));
+        assert_eq!(diagnostic.position, Some((2, 1)));
+        assert_eq!(diagnostic.line.as_deref(), Some("mosue = true"));
+        assert!(diagnostic
+            .to_string()
+            .starts_with("config.toml:2:1: unknown field `mosue`"));
+        assert!(diagnostic.to_string().ends_with("did you mean `mouse`?"));
+    }
+
+    #[test]
+    fn unknown_key_without_span() {
+        let source = "[editor]\n  scrolof = 3\n";
+        let value: toml::Value = toml::from_str("scrolof = 3").unwrap();
+        let err = value.try_into::<Sample>().unwrap_err();
+        let diagnostic = ConfigDiagnostic::new(&err)
+            .locate(&[(PathBuf::from("config.toml"), source.to_string())]);
+
+        assert_eq!(diagnostic.suggestion.as_deref(), Some("scrolloff"));
+        assert_eq!(diagnostic.position, Some((2, 3)));
+    }
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(
+            did_you_mean("lin-number", &["line-number", "mouse"]),
+            Some("line-number")
+        );
+        assert_eq!(did_you_mean("xyz", &["line-number", "mouse"]), None);
+        assert_eq!(
+            unknown_name("unknown variant `relativ`, expected `absolute` or `relative`"),
+            Some(("relativ", vec!["absolute", "relative"]))
+        );
+    }
+}
@@ -37,7 +37,7 @@ pub enum GrammarSelection {
     Except { except: HashSet<String> },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct GrammarConfiguration {
     #[serde(rename = "name")]
@@ -45,7 +45,7 @@ pub struct GrammarConfiguration {
     pub source: GrammarSource,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase", untagged)]
 pub enum GrammarSource {
     Local {
@@ -81,6 +81,46 @@ pub fn get_language(name: &str) -> Result<Option<Grammar>> {
     Ok(Some(grammar))
 }
 
+/// Whether the shared library of the grammar `name` exists in one of the runtime directories.
+pub fn is_grammar_built(name: &str) -> bool {
+    let mut rel_library_path = PathBuf::new().join("grammars").join(name);
+    rel_library_path.set_extension(DYLIB_EXTENSION);
+    crate::runtime_file(&rel_library_path).exists()
+}
+
+/// Fetches and builds the grammars of `names` one after the other, for grammars found missing
+/// while the editor runs. Grammars which aren't configured in `languages.toml` or excluded by
+/// `use-grammars` are skipped. `progress` is called before each grammar with its name, its
+/// index and the number of grammars. Returns the names of the grammars which were built and the
+/// errors of the grammars which failed.
+pub fn fetch_and_build_grammars(
+    names: &[String],
+    mut progress: impl FnMut(&str, usize, usize),
+) -> Result<(Vec<String>, Vec<(String, anyhow::Error)>)> {
+    let mut grammars = get_grammar_configs()?;
+    grammars.retain(|grammar| names.contains(&grammar.grammar_id));
+    if grammars
+        .iter()
+        .any(|grammar| matches!(grammar.source, GrammarSource::Git { .. }))
+    {
+        ensure_git_is_available()?;
+    }
+
+    let mut built = Vec::new();
+    let mut errors = Vec::new();
+    let total = grammars.len();
+    for (i, grammar) in grammars.into_iter().enumerate() {
+        let grammar_id = grammar.grammar_id.clone();
+        progress(&grammar_id, i, total);
+        let result = fetch_grammar(grammar.clone()).and_then(|_| build_grammar(grammar, None));
+        match result {
+            Ok(_) => built.push(grammar_id),
+            Err(err) => errors.push((grammar_id, err)),
+        }
+    }
+    Ok((built, errors))
+}
+
 fn ensure_git_is_available() -> Result<()> {
     helix_stdx::env::which("git")?;
     Ok(())
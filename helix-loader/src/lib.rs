@@ -1,5 +1,6 @@
 pub mod config;
 pub mod grammar;
+pub mod startup;
 
 use helix_stdx::{env::current_working_dir, path};
 
@@ -15,6 +16,54 @@
 
 static LOG_FILE: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
 
+static PROFILE_DIR: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
+
+/// Selects the config profile named `specified_profile`, or the one named by the `HELIX_PROFILE`
+/// environment variable. This must be called before `initialize_config_file`.
+///
+/// Profiles live in `profiles/<name>` under the config directory and have their own
+/// `config.toml`, `languages.toml` and `themes` which replace the top-level ones. Runtime files
+/// are shared.
+///
+/// Fails if the name isn't a plain directory name. A profile whose directory doesn't exist is
+/// still selected, so that it falls back to the default configuration, and a warning for the
+/// caller to report along with the errors in the config files is returned.
+pub fn initialize_profile(specified_profile: Option<String>) -> anyhow::Result<Option<String>> {
+    let profile = specified_profile
+        .or_else(|| std::env::var("HELIX_PROFILE").ok())
+        .filter(|profile| !profile.is_empty());
+    let Some(profile) = profile else {
+        return Ok(None);
+    };
+    let dir = profile_path(&config_dir(), &profile)?;
+    let warning = (!dir.is_dir()).then(|| format!("config profile not found: {}", dir.display()));
+    PROFILE_DIR.set(dir).ok();
+    Ok(warning)
+}
+
+/// The directory of the profile named `profile` under `config_dir`. The name must be a single
+/// normal path component so that it can't point outside of the `profiles` directory.
+fn profile_path(config_dir: &Path, profile: &str) -> anyhow::Result<PathBuf> {
+    let mut components = Path::new(profile).components();
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(name)), None) if name == profile => {
+            Ok(config_dir.join("profiles").join(name))
+        }
+        _ => anyhow::bail!("invalid config profile name: {profile:?}"),
+    }
+}
+
+/// The directory of the selected config profile, if any.
+pub fn profile_dir() -> Option<PathBuf> {
+    PROFILE_DIR.get().cloned()
+}
+
+/// The directory holding the user's `config.toml`, `languages.toml` and `themes`: the selected
+/// profile's directory or the config directory.
+pub fn user_config_dir() -> PathBuf {
+    profile_dir().unwrap_or_else(config_dir)
+}
+
 pub fn initialize_config_file(specified_file: Option<PathBuf>) {
     let config_file = specified_file.unwrap_or_else(default_config_file);
     ensure_parent_dir(&config_file);
@@ -132,6 +181,16 @@ pub fn cache_dir() -> PathBuf {
     path
 }
 
+/// Directory for state that should outlive a session but is not configuration, such as
+/// persisted registers. Falls back to the cache directory on platforms without a
+/// dedicated state directory.
+pub fn state_dir() -> PathBuf {
+    let strategy = choose_base_strategy().expect("Unable to find the state directory!");
+    let mut path = strategy.state_dir().unwrap_or_else(|| strategy.cache_dir());
+    path.push("helix");
+    path
+}
+
 pub fn config_file() -> PathBuf {
     CONFIG_FILE.get().map(|path| path.to_path_buf()).unwrap()
 }
@@ -145,7 +204,7 @@ pub fn workspace_config_file() -> PathBuf {
 }
 
 pub fn lang_config_file() -> PathBuf {
-    config_dir().join("languages.toml")
+    user_config_dir().join("languages.toml")
 }
 
 pub fn default_log_file() -> PathBuf {
@@ -263,7 +322,7 @@ pub fn find_workspace_in(dir: impl AsRef<Path>) -> (PathBuf, bool) {
 }
 
 fn default_config_file() -> PathBuf {
-    config_dir().join("config.toml")
+    user_config_dir().join("config.toml")
 }
 
 fn ensure_parent_dir(path: &Path) {
@@ -274,6 +333,31 @@ fn ensure_parent_dir(path: &Path) {
     }
 }
 
+#[cfg(test)]
+mod profile_tests {
+    use std::path::Path;
+
+    use super::profile_path;
+
+    #[test]
+    fn profile_path_joins_name() {
+        assert_eq!(
+            profile_path(Path::new("/config"), "work").unwrap(),
+            Path::new("/config/profiles/work")
+        );
+    }
+
+    #[test]
+    fn profile_path_rejects_non_normal_names() {
+        for name in ["..", ".", "a/b", "../work", "/work", "work/"] {
+            assert!(
+                profile_path(Path::new("/config"), name).is_err(),
+                "{name:?} should be rejected"
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod merge_toml_tests {
     use std::str;
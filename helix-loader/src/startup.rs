@@ -0,0 +1,93 @@
+//! Measures where the time to start the editor goes, reported with `--startup-timing`.
+//!
+//! Phases are measured with [measure] until the first frame is rendered and [finish] is called.
+//! A phase measured several times, like loading the grammars of several files, is reported
+//! with the total time.
+
+use std::{
+    fmt::Write as _,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+struct Timings {
+    start: Instant,
+    phases: Vec<(&'static str, Duration)>,
+    total: Option<Duration>,
+}
+
+static TIMINGS: Lazy<Mutex<Timings>> = Lazy::new(|| {
+    Mutex::new(Timings {
+        start: Instant::now(),
+        phases: Vec::new(),
+        total: None,
+    })
+});
+
+/// Starts the clock of the startup. Called first thing in `main`, the startup is measured from
+/// the first call to any function of this module otherwise.
+pub fn start() {
+    Lazy::force(&TIMINGS);
+}
+
+/// Runs `f`, adding the time it takes to `phase` while the editor is starting.
+pub fn measure<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+
+    let mut timings = TIMINGS.lock().unwrap();
+    if timings.total.is_none() {
+        match timings.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, duration)) => *duration += elapsed,
+            None => timings.phases.push((phase, elapsed)),
+        }
+    }
+    result
+}
+
+/// Marks the end of the startup, once the first frame was rendered. Later calls do nothing.
+pub fn finish() {
+    let mut timings = TIMINGS.lock().unwrap();
+    if timings.total.is_none() {
+        timings.total = Some(timings.start.elapsed());
+    }
+}
+
+/// The time spent in each phase in the order they were first measured, and the total time to
+/// the first frame.
+pub fn report() -> String {
+    let timings = TIMINGS.lock().unwrap();
+    let width = timings
+        .phases
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["first render".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut report = String::new();
+    for (name, duration) in &timings.phases {
+        let _ = writeln!(report, "{name:<width$}  {:>8.2}ms", millis(*duration));
+    }
+    match timings.total {
+        Some(total) => {
+            let _ = writeln!(
+                report,
+                "{:<width$}  {:>8.2}ms",
+                "first render",
+                millis(total)
+            );
+        }
+        None => {
+            let _ = writeln!(report, "{:<width$}  {:>10}", "first render", "-");
+        }
+    }
+    report
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -12,7 +12,7 @@
 };
 use helix_core::{
     find_workspace,
-    syntax::config::{LanguageServerFeature, RootMarkers},
+    syntax::config::{LanguageServerFeature, RootDetection, RootMarkers},
     ChangeSet, Rope,
 };
 use helix_loader::VERSION_AND_GIT_HASH;
@@ -24,7 +24,7 @@
 use std::{
     ffi::OsStr,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
 };
@@ -39,6 +39,47 @@
     },
 };
 
+/// The maximum number of requests in flight to each language server, `0` for no limit.
+static MAX_CONCURRENT_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Limits the number of requests in flight to each language server, `0` removes the limit.
+/// Requests sent while a server has this many requests in flight fail with
+/// [`Error::TooManyRequests`] instead of queueing up behind a server which can't keep up.
+pub fn set_max_concurrent_requests(max: usize) {
+    MAX_CONCURRENT_REQUESTS.store(max, Ordering::Relaxed);
+}
+
+/// Counts a request as in flight until it is dropped.
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn acquire(in_flight: &Arc<AtomicUsize>) -> Result<Self> {
+        let max = MAX_CONCURRENT_REQUESTS.load(Ordering::Relaxed);
+        in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |count| {
+                (max == 0 || count < max).then_some(count + 1)
+            })
+            .map_err(|_| Error::TooManyRequests)?;
+        Ok(Self(in_flight.clone()))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The URI of a file or directory in file operation requests and notifications.
+fn file_operation_uri(path: &Path, is_dir: bool) -> Option<String> {
+    let url = if is_dir {
+        Url::from_directory_path(path)
+    } else {
+        Url::from_file_path(path)
+    };
+    Some(url.ok()?.to_string())
+}
+
 fn workspace_for_uri(uri: lsp::Url) -> WorkspaceFolder {
     lsp::WorkspaceFolder {
         name: uri
@@ -57,6 +98,8 @@ pub struct Client {
     _process: Child,
     server_tx: UnboundedSender<Payload>,
     request_counter: AtomicU64,
+    /// The number of requests waiting for a response, see [`set_max_concurrent_requests`].
+    in_flight: Arc<AtomicUsize>,
     pub(crate) capabilities: OnceCell<lsp::ServerCapabilities>,
     pub(crate) file_operation_interest: OnceLock<FileOperationsInterest>,
     config: Option<Value>,
@@ -72,6 +115,7 @@ impl Client {
     pub fn try_add_doc(
         self: &Arc<Self>,
         root_markers: &RootMarkers,
+        root_detection: RootDetection,
         manual_roots: &[PathBuf],
         doc_path: Option<&std::path::PathBuf>,
         may_support_workspace: bool,
@@ -83,6 +127,7 @@ pub fn try_add_doc(
                 .and_then(|x| x.parent().and_then(|x| x.to_str()))
                 .unwrap_or("."),
             root_markers,
+            root_detection,
             manual_roots,
             &workspace,
             workspace_is_cwd,
@@ -254,6 +299,7 @@ pub fn start(
             _process: process,
             server_tx,
             request_counter: AtomicU64::new(0),
+            in_flight: Arc::default(),
             capabilities: OnceCell::new(),
             file_operation_interest: OnceLock::new(),
             config,
@@ -453,33 +499,34 @@ fn call_with_timeout<R: lsp::request::Request>(
     {
         let server_tx = self.server_tx.clone();
         let id = self.next_request_id();
+        let in_flight = InFlight::acquire(&self.in_flight);
 
         // It's important that this is not part of the future so that it gets executed right away
         // and the request order stays consistent.
-        let rx = serde_json::to_value(params)
-            .map_err(Error::from)
-            .and_then(|params| {
-                let request = jsonrpc::MethodCall {
-                    jsonrpc: Some(jsonrpc::Version::V2),
-                    id: id.clone(),
-                    method: R::METHOD.to_string(),
-                    params: Self::value_into_params(params),
-                };
-                let (tx, rx) = channel::<Result<Value>>(1);
-                server_tx
-                    .send(Payload::Request {
-                        chan: tx,
-                        value: request,
-                    })
-                    .map_err(|e| Error::Other(e.into()))?;
-                Ok(rx)
-            });
+        let rx = in_flight.and_then(|in_flight| {
+            let params = serde_json::to_value(params)?;
+            let request = jsonrpc::MethodCall {
+                jsonrpc: Some(jsonrpc::Version::V2),
+                id: id.clone(),
+                method: R::METHOD.to_string(),
+                params: Self::value_into_params(params),
+            };
+            let (tx, rx) = channel::<Result<Value>>(1);
+            server_tx
+                .send(Payload::Request {
+                    chan: tx,
+                    value: request,
+                })
+                .map_err(|e| Error::Other(e.into()))?;
+            Ok((rx, in_flight))
+        });
 
         async move {
             use std::time::Duration;
             use tokio::time::timeout;
+            let (mut rx, _in_flight) = rx?;
             // TODO: delay other calls until initialize success
-            timeout(Duration::from_secs(timeout_secs), rx?.recv())
+            timeout(Duration::from_secs(timeout_secs), rx.recv())
                 .await
                 .map_err(|_| Error::Timeout(id))? // return Timeout
                 .ok_or(Error::StreamClosed)?
@@ -603,8 +650,11 @@ pub(crate) async fn initialize(&self, enable_snippets: bool) -> Result<lsp::Init
                         relative_pattern_support: Some(true),
                     }),
                     file_operations: Some(lsp::WorkspaceFileOperationsClientCapabilities {
+                        did_create: Some(true),
                         will_rename: Some(true),
                         did_rename: Some(true),
+                        will_delete: Some(true),
+                        did_delete: Some(true),
                         ..Default::default()
                     }),
                     diagnostic: Some(lsp::DiagnosticWorkspaceClientCapabilities {
@@ -790,17 +840,9 @@ pub fn will_rename(
         if !capabilities.will_rename.has_interest(old_path, is_dir) {
             return None;
         }
-        let url_from_path = |path| {
-            let url = if is_dir {
-                Url::from_directory_path(path)
-            } else {
-                Url::from_file_path(path)
-            };
-            Some(url.ok()?.to_string())
-        };
         let files = vec![lsp::FileRename {
-            old_uri: url_from_path(old_path)?,
-            new_uri: url_from_path(new_path)?,
+            old_uri: file_operation_uri(old_path, is_dir)?,
+            new_uri: file_operation_uri(new_path, is_dir)?,
         }];
         Some(self.call_with_timeout::<lsp::request::WillRenameFiles>(
             &lsp::RenameFilesParams { files },
@@ -813,23 +855,57 @@ pub fn did_rename(&self, old_path: &Path, new_path: &Path, is_dir: bool) -> Opti
         if !capabilities.did_rename.has_interest(new_path, is_dir) {
             return None;
         }
-        let url_from_path = |path| {
-            let url = if is_dir {
-                Url::from_directory_path(path)
-            } else {
-                Url::from_file_path(path)
-            };
-            Some(url.ok()?.to_string())
-        };
 
         let files = vec![lsp::FileRename {
-            old_uri: url_from_path(old_path)?,
-            new_uri: url_from_path(new_path)?,
+            old_uri: file_operation_uri(old_path, is_dir)?,
+            new_uri: file_operation_uri(new_path, is_dir)?,
         }];
         self.notify::<lsp::notification::DidRenameFiles>(lsp::RenameFilesParams { files });
         Some(())
     }
 
+    pub fn did_create(&self, path: &Path, is_dir: bool) -> Option<()> {
+        let capabilities = self.file_operations_intests();
+        if !capabilities.did_create.has_interest(path, is_dir) {
+            return None;
+        }
+        let files = vec![lsp::FileCreate {
+            uri: file_operation_uri(path, is_dir)?,
+        }];
+        self.notify::<lsp::notification::DidCreateFiles>(lsp::CreateFilesParams { files });
+        Some(())
+    }
+
+    pub fn will_delete(
+        &self,
+        path: &Path,
+        is_dir: bool,
+    ) -> Option<impl Future<Output = Result<Option<lsp::WorkspaceEdit>>>> {
+        let capabilities = self.file_operations_intests();
+        if !capabilities.will_delete.has_interest(path, is_dir) {
+            return None;
+        }
+        let files = vec![lsp::FileDelete {
+            uri: file_operation_uri(path, is_dir)?,
+        }];
+        Some(self.call_with_timeout::<lsp::request::WillDeleteFiles>(
+            &lsp::DeleteFilesParams { files },
+            5,
+        ))
+    }
+
+    pub fn did_delete(&self, path: &Path, is_dir: bool) -> Option<()> {
+        let capabilities = self.file_operations_intests();
+        if !capabilities.did_delete.has_interest(path, is_dir) {
+            return None;
+        }
+        let files = vec![lsp::FileDelete {
+            uri: file_operation_uri(path, is_dir)?,
+        }];
+        self.notify::<lsp::notification::DidDeleteFiles>(lsp::DeleteFilesParams { files });
+        Some(())
+    }
+
     // -------------------------------------------------------------------------------------------
     // Text document
     // -------------------------------------------------------------------------------------------
@@ -1,13 +1,17 @@
-use std::{collections::HashMap, path::PathBuf, sync::Weak};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Weak,
+};
 
-use globset::{GlobBuilder, GlobSetBuilder};
+use globset::{GlobBuilder, GlobMatcher};
 use tokio::sync::mpsc;
 
 use crate::{lsp, Client, LanguageServerId};
 
 enum Event {
-    FileChanged {
-        path: PathBuf,
+    FilesChanged {
+        changes: Vec<(PathBuf, lsp::FileChangeType)>,
     },
     Register {
         client_id: LanguageServerId,
@@ -24,10 +28,62 @@ enum Event {
     },
 }
 
+/// A glob pattern registered by a language server with the kinds of changes it is interested in.
+struct Watcher {
+    glob: GlobMatcher,
+    /// The directory a relative pattern is matched against.
+    base: Option<PathBuf>,
+    kind: lsp::WatchKind,
+}
+
+impl Watcher {
+    fn new(watcher: lsp::FileSystemWatcher) -> Option<Self> {
+        let (pattern, base) = match watcher.glob_pattern {
+            lsp::GlobPattern::String(pattern) => (pattern, None),
+            lsp::GlobPattern::Relative(relative) => {
+                let base_uri = match relative.base_uri {
+                    lsp::OneOf::Left(folder) => folder.uri,
+                    lsp::OneOf::Right(uri) => uri,
+                };
+                (relative.pattern, Some(base_uri.to_file_path().ok()?))
+            }
+        };
+        let glob = match GlobBuilder::new(&pattern).build() {
+            Ok(glob) => glob.compile_matcher(),
+            Err(err) => {
+                log::warn!("Invalid glob pattern '{pattern}' for didChangeWatchedFiles: {err}");
+                return None;
+            }
+        };
+        Some(Self {
+            glob,
+            base,
+            kind: watcher.kind.unwrap_or(lsp::WatchKind::all()),
+        })
+    }
+
+    fn matches(&self, path: &Path, typ: lsp::FileChangeType) -> bool {
+        let kind = match typ {
+            lsp::FileChangeType::CREATED => lsp::WatchKind::Create,
+            lsp::FileChangeType::DELETED => lsp::WatchKind::Delete,
+            _ => lsp::WatchKind::Change,
+        };
+        if !self.kind.contains(kind) {
+            return false;
+        }
+        match &self.base {
+            Some(base) => path
+                .strip_prefix(base)
+                .is_ok_and(|path| self.glob.is_match(path)),
+            None => self.glob.is_match(path),
+        }
+    }
+}
+
 #[derive(Default)]
 struct ClientState {
     client: Weak<Client>,
-    registered: HashMap<String, globset::GlobSet>,
+    registered: HashMap<String, Vec<Watcher>>,
 }
 
 /// The Handler uses a dedicated tokio task to respond to file change events by
@@ -80,7 +136,13 @@ pub fn unregister(&self, client_id: LanguageServerId, registration_id: String) {
     }
 
     pub fn file_changed(&self, path: PathBuf) {
-        let _ = self.tx.send(Event::FileChanged { path });
+        self.files_changed(vec![(path, lsp::FileChangeType::CHANGED)]);
+    }
+
+    /// Notifies the language servers watching the files of a batch of changes, with one
+    /// notification per server.
+    pub fn files_changed(&self, changes: Vec<(PathBuf, lsp::FileChangeType)>) {
+        let _ = self.tx.send(Event::FilesChanged { changes });
     }
 
     pub fn remove_client(&self, client_id: LanguageServerId) {
@@ -91,35 +153,38 @@ async fn run(mut rx: mpsc::UnboundedReceiver<Event>) {
         let mut state: HashMap<LanguageServerId, ClientState> = HashMap::new();
         while let Some(event) = rx.recv().await {
             match event {
-                Event::FileChanged { path } => {
-                    log::debug!("Received file event for {:?}", &path);
+                Event::FilesChanged { changes } => {
+                    log::debug!("Received file events for {:?}", &changes);
 
                     state.retain(|id, client_state| {
-                        if !client_state
-                            .registered
-                            .values()
-                            .any(|glob| glob.is_match(&path))
-                        {
+                        let events: Vec<_> = changes
+                            .iter()
+                            .filter(|(path, typ)| {
+                                client_state
+                                    .registered
+                                    .values()
+                                    .flatten()
+                                    .any(|watcher| watcher.matches(path, *typ))
+                            })
+                            .filter_map(|(path, typ)| {
+                                Some(lsp::FileEvent {
+                                    uri: lsp::Url::from_file_path(path).ok()?,
+                                    typ: *typ,
+                                })
+                            })
+                            .collect();
+                        if events.is_empty() {
                             return true;
                         }
                         let Some(client) = client_state.client.upgrade() else {
                             log::warn!("LSP client was dropped: {id}");
                             return false;
                         };
-                        let Ok(uri) = lsp::Url::from_file_path(&path) else {
-                            return true;
-                        };
                         log::debug!(
                             "Sending didChangeWatchedFiles notification to client '{}'",
                             client.name()
                         );
-                        client.did_change_watched_files(vec![lsp::FileEvent {
-                            uri,
-                            // We currently always send the CHANGED state
-                            // since we don't actually have more context at
-                            // the moment.
-                            typ: lsp::FileChangeType::CHANGED,
-                        }]);
+                        client.did_change_watched_files(events);
                         true
                     });
                 }
@@ -138,29 +203,17 @@ async fn run(mut rx: mpsc::UnboundedReceiver<Event>) {
                     let entry = state.entry(client_id).or_default();
                     entry.client = client;
 
-                    let mut builder = GlobSetBuilder::new();
-                    for watcher in ops.watchers {
-                        if let lsp::GlobPattern::String(pattern) = watcher.glob_pattern {
-                            if let Ok(glob) = GlobBuilder::new(&pattern).build() {
-                                builder.add(glob);
-                            }
-                        }
-                    }
-                    match builder.build() {
-                        Ok(globset) => {
-                            entry.registered.insert(registration_id, globset);
-                        }
-                        Err(err) => {
-                            // Remove any old state for that registration id and
-                            // remove the entire client if it's now empty.
-                            entry.registered.remove(&registration_id);
-                            if entry.registered.is_empty() {
-                                state.remove(&client_id);
-                            }
-                            log::warn!(
-                                "Unable to build globset for LSP didChangeWatchedFiles {err}"
-                            )
+                    let watchers: Vec<_> =
+                        ops.watchers.into_iter().filter_map(Watcher::new).collect();
+                    if watchers.is_empty() {
+                        // Remove any old state for that registration id and
+                        // remove the entire client if it's now empty.
+                        entry.registered.remove(&registration_id);
+                        if entry.registered.is_empty() {
+                            state.remove(&client_id);
                         }
+                    } else {
+                        entry.registered.insert(registration_id, watchers);
                     }
                 }
                 Event::Unregister {
@@ -187,3 +240,39 @@ async fn run(mut rx: mpsc::UnboundedReceiver<Event>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchers_match_pattern_and_kind() {
+        let watcher = Watcher::new(lsp::FileSystemWatcher {
+            glob_pattern: lsp::GlobPattern::String("**/*.rs".to_string()),
+            kind: Some(lsp::WatchKind::Create | lsp::WatchKind::Delete),
+        })
+        .unwrap();
+        let path = Path::new("/project/src/main.rs");
+        assert!(watcher.matches(path, lsp::FileChangeType::CREATED));
+        assert!(!watcher.matches(path, lsp::FileChangeType::CHANGED));
+        assert!(!watcher.matches(
+            Path::new("/project/Cargo.toml"),
+            lsp::FileChangeType::DELETED
+        ));
+
+        let base = lsp::Url::from_file_path("/project").unwrap();
+        let watcher = Watcher::new(lsp::FileSystemWatcher {
+            glob_pattern: lsp::GlobPattern::Relative(lsp::RelativePattern {
+                base_uri: lsp::OneOf::Right(base),
+                pattern: "*.toml".to_string(),
+            }),
+            kind: None,
+        })
+        .unwrap();
+        assert!(watcher.matches(
+            Path::new("/project/Cargo.toml"),
+            lsp::FileChangeType::CHANGED
+        ));
+        assert!(!watcher.matches(Path::new("/other/Cargo.toml"), lsp::FileChangeType::CHANGED));
+    }
+}
@@ -5,7 +5,7 @@
 mod transport;
 
 use arc_swap::ArcSwap;
-pub use client::Client;
+pub use client::{set_max_concurrent_requests, Client};
 pub use futures_executor::block_on;
 pub use helix_lsp_types as lsp;
 pub use jsonrpc::Call;
@@ -13,14 +13,15 @@
 
 use futures_util::stream::select_all::SelectAll;
 use helix_core::syntax::config::{
-    LanguageConfiguration, LanguageServerConfiguration, LanguageServerFeatures, RootMarkers,
+    LanguageConfiguration, LanguageServerConfiguration, LanguageServerFeatures, RootDetection,
+    RootMarkers,
 };
 use helix_stdx::path;
 use slotmap::SlotMap;
 use tokio::sync::mpsc::UnboundedReceiver;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
     sync::Arc,
@@ -45,6 +46,8 @@ pub enum Error {
     Timeout(jsonrpc::Id),
     #[error("server closed the stream")]
     StreamClosed,
+    #[error("too many requests in flight")]
+    TooManyRequests,
     #[error("Unhandled")]
     Unhandled,
     #[error(transparent)]
@@ -679,7 +682,9 @@ pub fn restart_server(
         Some(Ok(client))
     }
 
-    pub fn stop(&mut self, name: &str) {
+    /// Stops all clients of the language server and returns their ids.
+    pub fn stop(&mut self, name: &str) -> Vec<LanguageServerId> {
+        let mut stopped = Vec::new();
         if let Some(clients) = self.inner_by_name.get_mut(name) {
             // Drain the clients vec so that the entry in `inner_by_name` remains
             // empty. We use the empty vec as a "tombstone" to mean that a server
@@ -689,22 +694,30 @@ pub fn stop(&mut self, name: &str) {
             for client in clients.drain(..) {
                 self.file_event_handler.remove_client(client.id());
                 self.inner.remove(client.id());
+                stopped.push(client.id());
                 tokio::spawn(async move {
                     let _ = client.force_shutdown().await;
                 });
             }
         }
+        stopped
     }
 
+    /// Returns the clients of the language servers configured for the language, starting them
+    /// if needed. The servers in `disabled` are skipped, they were disabled for the document.
     pub fn get<'a>(
         &'a mut self,
         language_config: &'a LanguageConfiguration,
         doc_path: Option<&'a std::path::PathBuf>,
         root_dirs: &'a [PathBuf],
         enable_snippets: bool,
+        disabled: &'a HashSet<LanguageServerName>,
     ) -> impl Iterator<Item = (LanguageServerName, Result<Arc<Client>>)> + 'a {
         language_config.language_servers.iter().filter_map(
             move |LanguageServerFeatures { name, .. }| {
+                if disabled.contains(name) {
+                    return None;
+                }
                 if let Some(clients) = self.inner_by_name.get(name) {
                     // If the clients vec is empty, do not automatically start a client
                     // for this server. The empty vec is a tombstone left to mean that a
@@ -719,7 +732,13 @@ pub fn get<'a>(
                             .workspace_lsp_roots
                             .as_deref()
                             .unwrap_or(root_dirs);
-                        client.try_add_doc(&language_config.roots, manual_roots, doc_path, *i == 0)
+                        client.try_add_doc(
+                            &language_config.roots,
+                            language_config.root_detection,
+                            manual_roots,
+                            doc_path,
+                            *i == 0,
+                        )
                     }) {
                         return Some((name.to_owned(), Ok(client.clone())));
                     }
@@ -815,6 +834,11 @@ pub fn is_created(&mut self, id: LanguageServerId, token: &lsp::ProgressToken) -
             .unwrap_or_default()
     }
 
+    /// Forgets all progress of the server with `id`, for when it exited.
+    pub fn remove_server(&mut self, id: LanguageServerId) {
+        self.0.remove(&id);
+    }
+
     pub fn create(&mut self, id: LanguageServerId, token: lsp::ProgressToken) {
         self.0
             .entry(id)
@@ -898,6 +922,7 @@ fn start_client(
             .and_then(|x| x.parent().and_then(|x| x.to_str()))
             .unwrap_or("."),
         &config.roots,
+        config.root_detection,
         config.workspace_lsp_roots.as_deref().unwrap_or(root_dirs),
         &workspace,
         workspace_is_cwd,
@@ -965,13 +990,17 @@ fn start_client(
 /// * start at `file` and search the file tree upward
 /// * stop the search at the first `root_dirs` entry that contains `file`
 /// * if no `root_dirs` matches `file` stop at workspace
-/// * Returns the top most directory that contains a `root_marker`
+/// * Returns the top most directory that contains a `root_marker`, or the closest one with
+///   [`RootDetection::Nearest`]
 /// * If no root marker and we stopped at a `root_dirs` entry, return the directory we stopped at
 /// * If we stopped at `workspace` instead and `workspace_is_cwd == false` return `None`
 /// * If we stopped at `workspace` instead and `workspace_is_cwd == true` return `workspace`
+///
+/// With [`RootDetection::Workspace`] any file inside `workspace` returns `workspace`.
 pub fn find_lsp_workspace(
     file: &str,
     root_markers: &RootMarkers,
+    detection: RootDetection,
     root_dirs: &[PathBuf],
     workspace: &Path,
     workspace_is_cwd: bool,
@@ -988,6 +1017,9 @@ pub fn find_lsp_workspace(
     if !file.starts_with(workspace) {
         return None;
     }
+    if detection == RootDetection::Workspace {
+        return Some(workspace.to_owned());
+    }
 
     let mut top_marker = None;
     for ancestor in file.ancestors() {
@@ -1001,6 +1033,9 @@ pub fn find_lsp_workspace(
             }
             false
         }) {
+            if detection == RootDetection::Nearest {
+                return Some(ancestor.to_owned());
+            }
             top_marker = Some(ancestor);
         }
 
@@ -6,5 +6,6 @@
 pub mod path;
 pub mod range;
 pub mod rope;
+pub mod trash;
 
 pub use range::Range;
@@ -0,0 +1,156 @@
+//! Moving files to the system trash.
+
+use std::io;
+use std::path::Path;
+
+/// Moves the file or directory at `path` to the trash of the current user.
+pub fn trash(path: &Path) -> io::Result<()> {
+    imp::trash(&crate::path::canonicalize(path))
+}
+
+/// The home trash of the [FreeDesktop.org trash specification][spec]. Files on other file
+/// systems than the home directory can't be moved there, the per-volume trash directories
+/// are not supported.
+///
+/// [spec]: https://specifications.freedesktop.org/trash-spec/latest/
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use std::ffi::OsString;
+    use std::fmt::Write as _;
+    use std::fs;
+    use std::io::{self, Write as _};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    fn trash_dir() -> io::Result<PathBuf> {
+        let data_dir = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| Some(etcetera::home_dir().ok()?.join(".local/share")))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+        Ok(data_dir.join("Trash"))
+    }
+
+    /// Percent-encodes `path` for the `Path` key of a `.trashinfo` file.
+    pub(super) fn encode_path(path: &Path) -> String {
+        let mut encoded = String::new();
+        for &byte in path.as_os_str().as_bytes() {
+            if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+        encoded
+    }
+
+    pub fn trash(path: &Path) -> io::Result<()> {
+        let name = path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "can't trash the root directory",
+            )
+        })?;
+        let trash_dir = trash_dir()?;
+        let files_dir = trash_dir.join("files");
+        let info_dir = trash_dir.join("info");
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(path),
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        // The name in the trash is reserved by creating its info file.
+        let mut suffix = 1;
+        loop {
+            let mut trash_name = name.to_os_string();
+            if suffix > 1 {
+                trash_name.push(format!(".{suffix}"));
+            }
+            let mut info_name = OsString::from(&trash_name);
+            info_name.push(".trashinfo");
+            let info_path = info_dir.join(info_name);
+
+            let mut file = match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    suffix += 1;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            let result = file
+                .write_all(info.as_bytes())
+                .and_then(|_| fs::rename(path, files_dir.join(&trash_name)));
+            if result.is_err() {
+                let _ = fs::remove_file(&info_path);
+            }
+            return result;
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::io;
+    use std::path::Path;
+    use std::process::{Command, Stdio};
+
+    pub fn trash(path: &Path) -> io::Result<()> {
+        let path = path.to_str().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "path is not valid UTF-8")
+        })?;
+        let path = path.replace('\\', "\\\\").replace('"', "\\\"");
+        let status = Command::new("osascript")
+            .arg("-e")
+            .arg(format!(
+                "tell application \"Finder\" to delete POSIX file \"{path}\""
+            ))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(
+                "Finder failed to move the file to the trash",
+            ))
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+    use std::path::Path;
+
+    pub fn trash(_path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "moving files to the trash is not supported on this platform",
+        ))
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use std::path::Path;
+
+    use super::imp::encode_path;
+
+    #[test]
+    fn trash_info_path_is_percent_encoded() {
+        assert_eq!(
+            encode_path(Path::new("/home/user/my file%.rs")),
+            "/home/user/my%20file%25.rs"
+        );
+        assert_eq!(encode_path(Path::new("/tmp/ä")), "/tmp/%C3%A4");
+    }
+}
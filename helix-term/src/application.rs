@@ -1,22 +1,20 @@
 use arc_swap::{access::Map, ArcSwap};
 use futures_util::Stream;
-use helix_core::{diagnostic::Severity, pos_at_coords, syntax, Range, Selection};
-use helix_lsp::{
-    lsp::{self, notification::Notification},
-    util::lsp_range_to_range,
-    LanguageServerId, LspProgressMap,
-};
+use helix_core::{diagnostic::Severity, pos_at_coords, syntax, Range};
+use helix_lsp::{lsp, LanguageServerId};
 use helix_stdx::path::get_relative_path;
 use helix_view::{
     align_view,
     document::{DocumentOpenError, DocumentSavedEventResult},
     editor::{ConfigEvent, EditorEvent},
+    events::DocumentDidSave,
     graphics::Rect,
-    theme,
+    metrics::Phase,
+    resources::LatencyMonitor,
+    session, theme,
     tree::Layout,
     Align, Editor,
 };
-use serde_json::json;
 use tui::backend::Backend;
 
 use crate::{
@@ -26,14 +24,16 @@
     handlers,
     job::Jobs,
     keymap::Keymaps,
+    lsp_messages::LspMessageHandler,
     ui::{self, overlay::overlaid},
 };
 
-use log::{debug, error, info, warn};
+use log::{debug, error, warn};
 use std::{
     io::{stdin, IsTerminal},
     path::Path,
     sync::Arc,
+    time::Instant,
 };
 
 #[cfg_attr(windows, allow(unused_imports))]
@@ -63,7 +63,8 @@ pub struct Application {
 
     signals: Signals,
     jobs: Jobs,
-    lsp_progress: LspProgressMap,
+    lsp: LspMessageHandler,
+    latency: LatencyMonitor,
 
     theme_mode: Option<theme::Mode>,
 }
@@ -97,7 +98,7 @@ pub fn new(args: Args, config: Config, lang_loader: syntax::Loader) -> Result<Se
 
         use helix_view::editor::Action;
 
-        let mut theme_parent_dirs = vec![helix_loader::config_dir()];
+        let mut theme_parent_dirs = vec![helix_loader::user_config_dir()];
         theme_parent_dirs.extend(helix_loader::runtime_dirs().iter().cloned());
         let theme_loader = theme::Loader::new(&theme_parent_dirs);
 
@@ -123,12 +124,16 @@ pub fn new(args: Args, config: Config, lang_loader: syntax::Loader) -> Result<Se
             })),
             handlers,
         );
-        Self::load_configured_theme(
-            &mut editor,
-            &config.load(),
-            terminal.backend().supports_true_color(),
-            theme_mode,
-        );
+        editor.terminal_true_color = terminal.backend().supports_true_color();
+        editor.terminal_images = terminal.backend().supports_images();
+        helix_loader::startup::measure("theme loading", || {
+            Self::load_configured_theme(
+                &mut editor,
+                &config.load(),
+                editor.terminal_true_color,
+                theme_mode,
+            )
+        });
 
         let keys = Box::new(Map::new(Arc::clone(&config), |config: &Config| {
             &config.keys
@@ -223,6 +228,13 @@ pub fn new(args: Args, config: Config, lang_loader: syntax::Loader) -> Result<Se
             }
         } else if stdin().is_terminal() || cfg!(feature = "integration") {
             editor.new_file(Action::VerticalSplit);
+            if args.restore_session || editor.config().restore_session {
+                match session::restore(&mut editor, &session::session_file()) {
+                    Ok(true) => (),
+                    Ok(false) => editor.set_status("No session saved for this workspace"),
+                    Err(err) => editor.set_error(format!("Failed to restore the session: {err}")),
+                }
+            }
         } else {
             editor
                 .new_file_from_stdin(Action::VerticalSplit)
@@ -248,7 +260,8 @@ pub fn new(args: Args, config: Config, lang_loader: syntax::Loader) -> Result<Se
             config,
             signals,
             jobs,
-            lsp_progress: LspProgressMap::new(),
+            lsp: LspMessageHandler::new(),
+            latency: LatencyMonitor::new(),
             theme_mode,
         };
 
@@ -279,13 +292,22 @@ async fn render(&mut self) {
 
         let surface = self.terminal.current_buffer_mut();
 
+        let start = cx.editor.metrics.start();
         self.compositor.render(area, surface, &mut cx);
+        self.editor.metrics.finish(Phase::Render, start);
         let (pos, kind) = self.compositor.cursor(area, &self.editor);
         // reset cursor cache
         self.editor.cursor_cache.reset();
 
         let pos = pos.map(|pos| (pos.col as u16, pos.row as u16));
+        if let Err(err) = self.terminal.set_cursor_color(self.editor.cursor_color()) {
+            log::warn!("failed to set the cursor color: {err}");
+        }
         self.terminal.draw(pos, kind).unwrap();
+        let (draw, flush) = self.terminal.draw_timings();
+        self.editor.metrics.record(Phase::Draw, draw);
+        self.editor.metrics.record(Phase::Flush, flush);
+        helix_loader::startup::finish();
     }
 
     pub async fn event_loop<S>(&mut self, input_stream: &mut S)
@@ -334,8 +356,7 @@ pub async fn event_loop_until_idle<S>(&mut self, input_stream: &mut S) -> bool
                         helix_event::status::Severity::Warning => Severity::Warning,
                         helix_event::status::Severity::Error => Severity::Error,
                     };
-                    // TODO: show multiple status messages at once to avoid clobbering
-                    self.editor.status_msg = Some((msg.message, severity));
+                    self.editor.set_message(msg.message, severity);
                     helix_event::request_redraw();
                 }
                 Some(callback) = self.jobs.wait_futures.next() => {
@@ -394,6 +415,14 @@ pub fn handle_config_events(&mut self, config_event: ConfigEvent) {
         }
     }
 
+    /// Shows errors found in the configuration files in a popup.
+    pub fn show_config_errors(&mut self, errors: &[String]) {
+        if !errors.is_empty() {
+            let popup = ui::config_errors_popup(errors, &self.editor);
+            self.compositor.push(Box::new(popup));
+        }
+    }
+
     fn refresh_config(&mut self) {
         let mut refresh_config = || -> Result<(), Error> {
             let default_config = Config::load_default()
@@ -436,13 +465,14 @@ fn refresh_config(&mut self) {
                 self.editor.set_status("Config refreshed");
             }
             Err(err) => {
-                self.editor.set_error(err.to_string());
+                self.editor.set_error("Failed to refresh config");
+                self.show_config_errors(&[err.to_string()]);
             }
         }
     }
 
     /// Load the theme set in configuration
-    fn load_configured_theme(
+    pub fn load_configured_theme(
         editor: &mut Editor,
         config: &Config,
         terminal_true_color: bool,
@@ -561,16 +591,18 @@ pub async fn handle_idle_timeout(&mut self) {
         }
     }
 
-    pub fn handle_document_write(&mut self, doc_save_event: DocumentSavedEventResult) {
+    /// Marks the document of a finished write as saved and reports the write in the
+    /// statusline.
+    pub fn handle_document_write(editor: &mut Editor, doc_save_event: DocumentSavedEventResult) {
         let doc_save_event = match doc_save_event {
             Ok(event) => event,
             Err(err) => {
-                self.editor.set_error(err.to_string());
+                editor.set_error(err.to_string());
                 return;
             }
         };
 
-        let doc = match self.editor.document_mut(doc_save_event.doc_id) {
+        let doc = match editor.document_mut(doc_save_event.doc_id) {
             None => {
                 warn!(
                     "received document saved event for non-existent doc id: {}",
@@ -620,13 +652,16 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             Size::HumanReadable(size, SUFFIX[i])
         };
 
-        self.editor
-            .set_doc_path(doc_save_event.doc_id, &doc_save_event.path);
+        editor.set_doc_path(doc_save_event.doc_id, &doc_save_event.path);
         // TODO: fix being overwritten by lsp
-        self.editor.set_status(format!(
+        editor.set_status(format!(
             "'{}' written, {lines}L {size}",
             get_relative_path(&doc_save_event.path).to_string_lossy(),
         ));
+        helix_event::dispatch(DocumentDidSave {
+            editor,
+            doc: doc_save_event.doc_id,
+        });
     }
 
     #[inline(always)]
@@ -635,7 +670,7 @@ pub async fn handle_editor_event(&mut self, event: EditorEvent) -> bool {
 
         match event {
             EditorEvent::DocumentSaved(event) => {
-                self.handle_document_write(event);
+                Self::handle_document_write(&mut self.editor, event);
                 self.render().await;
             }
             EditorEvent::ConfigEvent(event) => {
@@ -653,6 +688,11 @@ pub async fn handle_editor_event(&mut self, event: EditorEvent) -> bool {
                     self.render().await;
                 }
             }
+            EditorEvent::TerminalOutput(output) => {
+                self.editor.handle_terminal_output(output);
+                // limit render calls for shells printing a lot
+                helix_event::request_redraw();
+            }
             EditorEvent::Redraw => {
                 self.render().await;
             }
@@ -674,6 +714,8 @@ pub async fn handle_terminal_events(&mut self, event: std::io::Result<TerminalEv
         #[cfg(not(windows))]
         use termina::escape::csi;
 
+        let received = Instant::now();
+        let start = self.editor.metrics.start();
         let mut cx = crate::compositor::Context {
             editor: &mut self.editor,
             jobs: &mut self.jobs,
@@ -712,10 +754,13 @@ pub async fn handle_terminal_events(&mut self, event: std::io::Result<TerminalEv
             }
             event => self.compositor.handle_event(&event.into(), &mut cx),
         };
+        self.editor.metrics.finish(Phase::Events, start);
 
         if should_redraw && !self.editor.should_close() {
             self.render().await;
         }
+        self.latency
+            .record(received.elapsed(), &self.editor.config().resources);
     }
 
     pub async fn handle_language_server_message(
@@ -723,488 +768,15 @@ pub async fn handle_language_server_message(
         call: helix_lsp::Call,
         server_id: LanguageServerId,
     ) {
-        use helix_lsp::{Call, MethodCall, Notification};
-
-        macro_rules! language_server {
-            () => {
-                match self.editor.language_server_by_id(server_id) {
-                    Some(language_server) => language_server,
-                    None => {
-                        warn!("can't find language server with id `{}`", server_id);
-                        return;
-                    }
-                }
-            };
-        }
-
-        match call {
-            Call::Notification(helix_lsp::jsonrpc::Notification { method, params, .. }) => {
-                let notification = match Notification::parse(&method, params) {
-                    Ok(notification) => notification,
-                    Err(helix_lsp::Error::Unhandled) => {
-                        info!("Ignoring Unhandled notification from Language Server");
-                        return;
-                    }
-                    Err(err) => {
-                        error!(
-                            "Ignoring unknown notification from Language Server: {}",
-                            err
-                        );
-                        return;
-                    }
-                };
-
-                match notification {
-                    Notification::Initialized => {
-                        let language_server = language_server!();
-
-                        // Trigger a workspace/didChangeConfiguration notification after initialization.
-                        // This might not be required by the spec but Neovim does this as well, so it's
-                        // probably a good idea for compatibility.
-                        if let Some(config) = language_server.config() {
-                            language_server.did_change_configuration(config.clone());
-                        }
-
-                        helix_event::dispatch(helix_view::events::LanguageServerInitialized {
-                            editor: &mut self.editor,
-                            server_id,
-                        });
-                    }
-                    Notification::PublishDiagnostics(params) => {
-                        let uri = match helix_core::Uri::try_from(params.uri) {
-                            Ok(uri) => uri,
-                            Err(err) => {
-                                log::error!("{err}");
-                                return;
-                            }
-                        };
-                        let language_server = language_server!();
-                        if !language_server.is_initialized() {
-                            log::error!("Discarding publishDiagnostic notification sent by an uninitialized server: {}", language_server.name());
-                            return;
-                        }
-                        let provider = helix_core::diagnostic::DiagnosticProvider::Lsp {
-                            server_id,
-                            identifier: None,
-                        };
-                        self.editor.handle_lsp_diagnostics(
-                            &provider,
-                            uri,
-                            params.version,
-                            params.diagnostics,
-                        );
-                    }
-                    Notification::ShowMessage(params) => {
-                        self.handle_show_message(params.typ, params.message);
-                    }
-                    Notification::LogMessage(params) => {
-                        log::info!("window/logMessage: {:?}", params);
-                    }
-                    Notification::ProgressMessage(params)
-                        if !self
-                            .compositor
-                            .has_component(std::any::type_name::<ui::Prompt>()) =>
-                    {
-                        let editor_view = self
-                            .compositor
-                            .find::<ui::EditorView>()
-                            .expect("expected at least one EditorView");
-                        let lsp::ProgressParams {
-                            token,
-                            value: lsp::ProgressParamsValue::WorkDone(work),
-                        } = params;
-                        let (title, message, percentage) = match &work {
-                            lsp::WorkDoneProgress::Begin(lsp::WorkDoneProgressBegin {
-                                title,
-                                message,
-                                percentage,
-                                ..
-                            }) => (Some(title), message, percentage),
-                            lsp::WorkDoneProgress::Report(lsp::WorkDoneProgressReport {
-                                message,
-                                percentage,
-                                ..
-                            }) => (None, message, percentage),
-                            lsp::WorkDoneProgress::End(lsp::WorkDoneProgressEnd { message }) => {
-                                if message.is_some() {
-                                    (None, message, &None)
-                                } else {
-                                    self.lsp_progress.end_progress(server_id, &token);
-                                    if !self.lsp_progress.is_progressing(server_id) {
-                                        editor_view.spinners_mut().get_or_create(server_id).stop();
-                                    }
-                                    self.editor.clear_status();
-
-                                    // we want to render to clear any leftover spinners or messages
-                                    return;
-                                }
-                            }
-                        };
-
-                        if self.editor.config().lsp.display_progress_messages {
-                            let title =
-                                title.or_else(|| self.lsp_progress.title(server_id, &token));
-                            if title.is_some() || percentage.is_some() || message.is_some() {
-                                use std::fmt::Write as _;
-                                let mut status = format!("{}: ", language_server!().name());
-                                if let Some(percentage) = percentage {
-                                    write!(status, "{percentage:>2}% ").unwrap();
-                                }
-                                if let Some(title) = title {
-                                    status.push_str(title);
-                                }
-                                if title.is_some() && message.is_some() {
-                                    status.push_str(" ⋅ ");
-                                }
-                                if let Some(message) = message {
-                                    status.push_str(message);
-                                }
-                                self.editor.set_status(status);
-                            }
-                        }
-
-                        match work {
-                            lsp::WorkDoneProgress::Begin(begin_status) => {
-                                self.lsp_progress
-                                    .begin(server_id, token.clone(), begin_status);
-                            }
-                            lsp::WorkDoneProgress::Report(report_status) => {
-                                self.lsp_progress
-                                    .update(server_id, token.clone(), report_status);
-                            }
-                            lsp::WorkDoneProgress::End(_) => {
-                                self.lsp_progress.end_progress(server_id, &token);
-                                if !self.lsp_progress.is_progressing(server_id) {
-                                    editor_view.spinners_mut().get_or_create(server_id).stop();
-                                };
-                            }
-                        }
-                    }
-                    Notification::ProgressMessage(_params) => {
-                        // do nothing
-                    }
-                    Notification::Exit => {
-                        self.editor.set_status("Language server exited");
-
-                        // LSPs may produce diagnostics for files that haven't been opened in helix,
-                        // we need to clear those and remove the entries from the list if this leads to
-                        // an empty diagnostic list for said files
-                        for diags in self.editor.diagnostics.values_mut() {
-                            diags.retain(|(_, provider)| {
-                                provider.language_server_id() != Some(server_id)
-                            });
-                        }
-
-                        self.editor.diagnostics.retain(|_, diags| !diags.is_empty());
-
-                        // Clear any diagnostics for documents with this server open.
-                        for doc in self.editor.documents_mut() {
-                            doc.clear_diagnostics_for_language_server(server_id);
-                        }
-
-                        helix_event::dispatch(helix_view::events::LanguageServerExited {
-                            editor: &mut self.editor,
-                            server_id,
-                        });
-
-                        // Remove the language server from the registry.
-                        self.editor.language_servers.remove_by_id(server_id);
-                    }
-                }
-            }
-            Call::MethodCall(helix_lsp::jsonrpc::MethodCall {
-                method, params, id, ..
-            }) => {
-                let reply = match MethodCall::parse(&method, params) {
-                    Err(helix_lsp::Error::Unhandled) => {
-                        error!(
-                            "Language Server: Method {} not found in request {}",
-                            method, id
-                        );
-                        Err(helix_lsp::jsonrpc::Error {
-                            code: helix_lsp::jsonrpc::ErrorCode::MethodNotFound,
-                            message: format!("Method not found: {}", method),
-                            data: None,
-                        })
-                    }
-                    Err(err) => {
-                        log::error!(
-                            "Language Server: Received malformed method call {} in request {}: {}",
-                            method,
-                            id,
-                            err
-                        );
-                        Err(helix_lsp::jsonrpc::Error {
-                            code: helix_lsp::jsonrpc::ErrorCode::ParseError,
-                            message: format!("Malformed method call: {}", method),
-                            data: None,
-                        })
-                    }
-                    Ok(MethodCall::WorkDoneProgressCreate(params)) => {
-                        self.lsp_progress.create(server_id, params.token);
-
-                        let editor_view = self
-                            .compositor
-                            .find::<ui::EditorView>()
-                            .expect("expected at least one EditorView");
-                        let spinner = editor_view.spinners_mut().get_or_create(server_id);
-                        if spinner.is_stopped() {
-                            spinner.start();
-                        }
-
-                        Ok(serde_json::Value::Null)
-                    }
-                    Ok(MethodCall::ApplyWorkspaceEdit(params)) => {
-                        let language_server = language_server!();
-                        if language_server.is_initialized() {
-                            let offset_encoding = language_server.offset_encoding();
-                            let res = self
-                                .editor
-                                .apply_workspace_edit(offset_encoding, &params.edit);
-
-                            Ok(json!(lsp::ApplyWorkspaceEditResponse {
-                                applied: res.is_ok(),
-                                failure_reason: res.as_ref().err().map(|err| err.kind.to_string()),
-                                failed_change: res
-                                    .as_ref()
-                                    .err()
-                                    .map(|err| err.failed_change_idx as u32),
-                            }))
-                        } else {
-                            Err(helix_lsp::jsonrpc::Error {
-                                code: helix_lsp::jsonrpc::ErrorCode::InvalidRequest,
-                                message: "Server must be initialized to request workspace edits"
-                                    .to_string(),
-                                data: None,
-                            })
-                        }
-                    }
-                    Ok(MethodCall::WorkspaceFolders) => {
-                        Ok(json!(&*language_server!().workspace_folders().await))
-                    }
-                    Ok(MethodCall::WorkspaceConfiguration(params)) => {
-                        let language_server = language_server!();
-                        let result: Vec<_> = params
-                            .items
-                            .iter()
-                            .map(|item| {
-                                let mut config = language_server.config()?;
-                                if let Some(section) = item.section.as_ref() {
-                                    // for some reason some lsps send an empty string (observed in 'vscode-eslint-language-server')
-                                    if !section.is_empty() {
-                                        for part in section.split('.') {
-                                            config = config.get(part)?;
-                                        }
-                                    }
-                                }
-                                Some(config)
-                            })
-                            .collect();
-                        Ok(json!(result))
-                    }
-                    Ok(MethodCall::RegisterCapability(params)) => {
-                        if let Some(client) = self.editor.language_servers.get_by_id(server_id) {
-                            for reg in params.registrations {
-                                match reg.method.as_str() {
-                                    lsp::notification::DidChangeWatchedFiles::METHOD => {
-                                        let Some(options) = reg.register_options else {
-                                            continue;
-                                        };
-                                        let ops: lsp::DidChangeWatchedFilesRegistrationOptions =
-                                            match serde_json::from_value(options) {
-                                                Ok(ops) => ops,
-                                                Err(err) => {
-                                                    log::warn!("Failed to deserialize DidChangeWatchedFilesRegistrationOptions: {err}");
-                                                    continue;
-                                                }
-                                            };
-                                        self.editor.language_servers.file_event_handler.register(
-                                            client.id(),
-                                            Arc::downgrade(client),
-                                            reg.id,
-                                            ops,
-                                        )
-                                    }
-                                    _ => {
-                                        // Language Servers based on the `vscode-languageserver-node` library often send
-                                        // client/registerCapability even though we do not enable dynamic registration
-                                        // for most capabilities. We should send a MethodNotFound JSONRPC error in this
-                                        // case but that rejects the registration promise in the server which causes an
-                                        // exit. So we work around this by ignoring the request and sending back an OK
-                                        // response.
-                                        log::warn!("Ignoring a client/registerCapability request because dynamic capability registration is not enabled. Please report this upstream to the language server");
-                                    }
-                                }
-                            }
-                        }
-
-                        Ok(serde_json::Value::Null)
-                    }
-                    Ok(MethodCall::UnregisterCapability(params)) => {
-                        for unreg in params.unregisterations {
-                            match unreg.method.as_str() {
-                                lsp::notification::DidChangeWatchedFiles::METHOD => {
-                                    self.editor
-                                        .language_servers
-                                        .file_event_handler
-                                        .unregister(server_id, unreg.id);
-                                }
-                                _ => {
-                                    log::warn!("Received unregistration request for unsupported method: {}", unreg.method);
-                                }
-                            }
-                        }
-                        Ok(serde_json::Value::Null)
-                    }
-                    Ok(MethodCall::ShowDocument(params)) => {
-                        let language_server = language_server!();
-                        let offset_encoding = language_server.offset_encoding();
-
-                        let result = self.handle_show_document(params, offset_encoding);
-                        Ok(json!(result))
-                    }
-                    Ok(MethodCall::WorkspaceDiagnosticRefresh) => {
-                        let language_server = language_server!().id();
-
-                        let documents: Vec<_> = self
-                            .editor
-                            .documents
-                            .values()
-                            .filter(|x| x.supports_language_server(language_server))
-                            .map(|x| x.id())
-                            .collect();
-
-                        for document in documents {
-                            handlers::diagnostics::request_document_diagnostics(
-                                &mut self.editor,
-                                document,
-                            );
-                        }
-
-                        Ok(serde_json::Value::Null)
-                    }
-                    Ok(MethodCall::ShowMessageRequest(params)) => {
-                        if let Some(actions) = params.actions.filter(|a| !a.is_empty()) {
-                            let id = id.clone();
-                            let select = ui::Select::new(
-                                params.message,
-                                actions,
-                                (),
-                                move |editor, action, event| {
-                                    let reply = match event {
-                                        ui::PromptEvent::Update => return,
-                                        ui::PromptEvent::Validate => Some(action.clone()),
-                                        ui::PromptEvent::Abort => None,
-                                    };
-                                    if let Some(language_server) =
-                                        editor.language_server_by_id(server_id)
-                                    {
-                                        if let Err(err) =
-                                            language_server.reply(id.clone(), Ok(json!(reply)))
-                                        {
-                                            log::error!(
-                                                "Failed to send reply to server '{}' request {id}: {err}",
-                                                language_server.name()
-                                            );
-                                        }
-                                    }
-                                },
-                            );
-                            self.compositor
-                                .replace_or_push("lsp-show-message-request", select);
-                            // Avoid sending a reply. The `Select` callback above sends the reply.
-                            return;
-                        } else {
-                            self.handle_show_message(params.typ, params.message);
-                            Ok(serde_json::Value::Null)
-                        }
-                    }
-                };
-
-                let language_server = language_server!();
-                if let Err(err) = language_server.reply(id.clone(), reply) {
-                    log::error!(
-                        "Failed to send reply to server '{}' request {id}: {err}",
-                        language_server.name()
-                    );
-                }
-            }
-            Call::Invalid { id } => log::error!("LSP invalid method call id={:?}", id),
-        }
-    }
-
-    fn handle_show_message(&mut self, message_type: lsp::MessageType, message: String) {
-        if self.config.load().editor.lsp.display_messages {
-            match message_type {
-                lsp::MessageType::ERROR => self.editor.set_error(message),
-                lsp::MessageType::WARNING => self.editor.set_warning(message),
-                _ => self.editor.set_status(message),
-            }
-        }
-    }
-
-    fn handle_show_document(
-        &mut self,
-        params: lsp::ShowDocumentParams,
-        offset_encoding: helix_lsp::OffsetEncoding,
-    ) -> lsp::ShowDocumentResult {
-        if let lsp::ShowDocumentParams {
-            external: Some(true),
-            uri,
-            ..
-        } = params
-        {
-            self.jobs.callback(crate::open_external_url_callback(uri));
-            return lsp::ShowDocumentResult { success: true };
-        };
-
-        let lsp::ShowDocumentParams {
-            uri,
-            selection,
-            take_focus,
-            ..
-        } = params;
-
-        let uri = match helix_core::Uri::try_from(uri) {
-            Ok(uri) => uri,
-            Err(err) => {
-                log::error!("{err}");
-                return lsp::ShowDocumentResult { success: false };
-            }
-        };
-        // If `Uri` gets another variant other than `Path` this may not be valid.
-        let path = uri.as_path().expect("URIs are valid paths");
-
-        let action = match take_focus {
-            Some(true) => helix_view::editor::Action::Replace,
-            _ => helix_view::editor::Action::VerticalSplit,
-        };
-
-        let doc_id = match self.editor.open(path, action) {
-            Ok(id) => id,
-            Err(err) => {
-                log::error!("failed to open path: {:?}: {:?}", uri, err);
-                return lsp::ShowDocumentResult { success: false };
-            }
-        };
-
-        let doc = doc_mut!(self.editor, &doc_id);
-        if let Some(range) = selection {
-            // TODO: convert inside server
-            if let Some(new_range) = lsp_range_to_range(doc.text(), range, offset_encoding) {
-                let view = view_mut!(self.editor);
-
-                // we flip the range so that the cursor sits on the start of the symbol
-                // (for example start of the function).
-                doc.set_selection(view.id, Selection::single(new_range.head, new_range.anchor));
-                if action.align_view(view, doc.id()) {
-                    align_view(doc, view, Align::Center);
-                }
-            } else {
-                log::warn!("lsp position out of bounds - {:?}", range);
-            };
-        };
-        lsp::ShowDocumentResult { success: true }
+        self.lsp
+            .handle_message(
+                &mut self.editor,
+                &mut self.compositor,
+                &mut self.jobs,
+                call,
+                server_id,
+            )
+            .await;
     }
 
     fn restore_term(&mut self) -> std::io::Result<()> {
@@ -1291,6 +863,11 @@ pub async fn close(&mut self) -> Vec<anyhow::Error> {
             errs.push(err);
         }
 
+        if let Err(err) = self.editor.save_registers() {
+            log::error!("Error saving registers: {}", err);
+            errs.push(err);
+        }
+
         if self.editor.close_language_servers(None).await.is_err() {
             log::error!("Timed out waiting for language servers to shutdown");
             errs.push(anyhow::format_err!(
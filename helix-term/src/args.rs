@@ -14,9 +14,17 @@ pub struct Args {
     pub fetch_grammars: bool,
     pub build_grammars: bool,
     pub split: Option<Layout>,
+    pub restore_session: bool,
+    pub startup_timing: bool,
+    pub script: Option<PathBuf>,
+    pub execute: Option<String>,
+    pub dump_frame: bool,
+    pub write_all: bool,
+    pub server: Option<PathBuf>,
     pub verbosity: u64,
     pub log_file: Option<PathBuf>,
     pub config_file: Option<PathBuf>,
+    pub profile: Option<String>,
     pub files: IndexMap<PathBuf, Vec<Position>>,
     pub working_directory: Option<PathBuf>,
 }
@@ -25,7 +33,7 @@ impl Args {
     pub fn parse_args() -> Result<Args> {
         let mut args = Args::default();
         let mut argv = std::env::args().peekable();
-        let mut line_number = 0;
+        let mut first_position = None;
 
         let mut insert_file_with_position = |file_with_position: &str| {
             let (filename, position) = parse_file(file_with_position);
@@ -55,10 +63,33 @@ pub fn parse_args() -> Result<Args> {
                     Some(_) => anyhow::bail!("can only set a split once of a specific type"),
                     None => args.split = Some(Layout::Horizontal),
                 },
+                "--restore-session" => args.restore_session = true,
+                "--startup-timing" => args.startup_timing = true,
+                "--script" => match argv.next().as_deref() {
+                    Some(_) if args.execute.is_some() => {
+                        anyhow::bail!("--script can't be used with --execute")
+                    }
+                    Some(path) => args.script = Some(path.into()),
+                    None => anyhow::bail!("--script must specify a path to read"),
+                },
+                "--execute" => match argv.next() {
+                    Some(_) if args.script.is_some() => {
+                        anyhow::bail!("--execute can't be used with --script")
+                    }
+                    Some(keys) => args.execute = Some(keys),
+                    None => anyhow::bail!("--execute must specify the keys to press"),
+                },
+                "--dump-frame" => args.dump_frame = true,
+                "--write-all" => args.write_all = true,
+                "--server" => match argv.next().as_deref() {
+                    Some(path) => args.server = Some(path.into()),
+                    None => anyhow::bail!("--server must specify the path of a socket"),
+                },
                 "--health" => {
                     args.health = true;
                     args.health_arg = argv.next_if(|opt| !opt.starts_with('-'));
                 }
+                "--build-grammars" => args.build_grammars = true,
                 "-g" | "--grammar" => match argv.next().as_deref() {
                     Some("fetch") => args.fetch_grammars = true,
                     Some("build") => args.build_grammars = true,
@@ -70,6 +101,10 @@ pub fn parse_args() -> Result<Args> {
                     Some(path) => args.config_file = Some(path.into()),
                     None => anyhow::bail!("--config must specify a path to read"),
                 },
+                "--profile" => match argv.next() {
+                    Some(profile) => args.profile = Some(profile),
+                    None => anyhow::bail!("--profile must specify a profile name"),
+                },
                 "--log" => match argv.next().as_deref() {
                     Some(path) => args.log_file = Some(path.into()),
                     None => anyhow::bail!("--log must specify a path to write"),
@@ -102,13 +137,11 @@ pub fn parse_args() -> Result<Args> {
                         }
                     }
                 }
-                "+" => line_number = usize::MAX,
-                arg if arg.starts_with('+') => {
-                    match arg[1..].parse::<usize>() {
-                        Ok(n) => line_number = n.saturating_sub(1),
-                        _ => insert_file_with_position(arg),
-                    };
-                }
+                "+" => first_position = Some((usize::MAX, None)),
+                arg if arg.starts_with('+') => match parse_position(&arg[1..]) {
+                    Some(position) => first_position = Some(position),
+                    None => insert_file_with_position(arg),
+                },
                 arg => insert_file_with_position(arg),
             }
         }
@@ -118,13 +151,16 @@ pub fn parse_args() -> Result<Args> {
             insert_file_with_position(&arg);
         }
 
-        if line_number != 0 {
-            if let Some(first_position) = args
+        if let Some((row, col)) = first_position {
+            if let Some(position) = args
                 .files
                 .first_mut()
                 .and_then(|(_, positions)| positions.first_mut())
             {
-                first_position.row = line_number;
+                position.row = row;
+                if let Some(col) = col {
+                    position.col = col;
+                }
             }
         }
 
@@ -143,6 +179,15 @@ pub(crate) fn parse_file(s: &str) -> (PathBuf, Position) {
         .unwrap_or_else(def)
 }
 
+/// Parse the `N` or `N:M` of a `+N:M` argument into a zero-based row and optional column.
+fn parse_position(s: &str) -> Option<(usize, Option<usize>)> {
+    let (row, col) = match s.split_once(':') {
+        Some((row, col)) => (row, Some(col.parse::<usize>().ok()?.saturating_sub(1))),
+        None => (s, None),
+    };
+    Some((row.parse::<usize>().ok()?.saturating_sub(1), col))
+}
+
 /// Split file.rs:10:2 into [`PathBuf`], row and col.
 ///
 /// Does not validate if file.rs is a file or directory.
@@ -1,17 +1,21 @@
+pub(crate) mod custom;
 pub(crate) mod dap;
 pub(crate) mod lsp;
+pub(crate) mod repl;
 pub(crate) mod syntax;
 pub(crate) mod typed;
 
+pub use custom::*;
 pub use dap::*;
 use futures_util::FutureExt;
-use helix_event::status;
+use helix_event::{status, TaskHandle};
 use helix_stdx::{
     path::{self, find_paths},
     rope::{self, RopeSliceExt},
 };
 use helix_vcs::{FileChange, Hunk};
 pub use lsp::*;
+pub use repl::*;
 pub use syntax::*;
 use tui::{
     text::{Span, Spans},
@@ -46,11 +50,15 @@
 };
 use helix_view::{
     document::{FormatterError, Mode, SCRATCH_BUFFER_NAME},
-    editor::Action,
+    editor::{
+        Action, BufferLine, ConfigEvent, PasteFilePaths, SearchMatches, Severity, WhitespaceRender,
+        WhitespaceRenderValue,
+    },
     expansion,
     info::Info,
     input::KeyEvent,
     keyboard::KeyCode,
+    quickfix::{QuickfixEntry, QuickfixList},
     theme::Style,
     tree,
     view::View,
@@ -79,6 +87,7 @@
     future::Future,
     io::Read,
     num::NonZeroUsize,
+    sync::Arc,
 };
 
 use std::{
@@ -260,6 +269,15 @@ pub fn execute(&self, cx: &mut Context) {
                     {
                         cx.editor.set_error(format!("{}", e));
                     }
+                } else if let Some(command) = custom_command(name) {
+                    let mut cx = compositor::Context {
+                        editor: cx.editor,
+                        jobs: cx.jobs,
+                        scroll: None,
+                    };
+                    if let Err(e) = command.execute(&mut cx, args, PromptEvent::Validate) {
+                        cx.editor.set_error(format!("{}", e));
+                    }
                 } else {
                     cx.editor.set_error(format!("no such command: '{name}'"));
                 }
@@ -383,6 +401,7 @@ pub fn doc(&self) -> &str {
         search_selection_detect_word_boundaries, "Use current selection as the search pattern, automatically wrapping with `\\b` on word boundaries",
         make_search_word_bounded, "Modify current search to make it word bounded",
         global_search, "Global search in workspace folder",
+        search_to_quickfix, "Add all search matches to the quickfix list",
         extend_line, "Select current line, if already selected, extend to another line based on the anchor",
         extend_line_below, "Select current line, if already selected, extend to next line",
         extend_line_above, "Select current line, if already selected, extend to previous line",
@@ -409,6 +428,7 @@ pub fn doc(&self) -> &str {
         code_action, "Perform code action",
         buffer_picker, "Open buffer picker",
         jumplist_picker, "Open jumplist picker",
+        quickfix_picker, "Open quickfix list picker",
         symbol_picker, "Open symbol picker",
         syntax_symbol_picker, "Open symbol picker from syntax information",
         lsp_or_syntax_symbol_picker, "Open symbol picker from LSP or syntax information",
@@ -417,6 +437,7 @@ pub fn doc(&self) -> &str {
         workspace_symbol_picker, "Open workspace symbol picker",
         syntax_workspace_symbol_picker, "Open workspace symbol picker from syntax information",
         lsp_or_syntax_workspace_symbol_picker, "Open workspace symbol picker from LSP or syntax information",
+        structural_search, "Search the workspace with a tree-sitter query",
         diagnostics_picker, "Open diagnostic picker",
         workspace_diagnostics_picker, "Open workspace diagnostic picker",
         last_picker, "Open last picker",
@@ -454,8 +475,12 @@ pub fn doc(&self) -> &str {
         goto_last_diag, "Goto last diagnostic",
         goto_next_diag, "Goto next diagnostic",
         goto_prev_diag, "Goto previous diagnostic",
+        goto_next_error, "Goto next error",
+        goto_prev_error, "Goto previous error",
         goto_next_change, "Goto next change",
         goto_prev_change, "Goto previous change",
+        goto_next_quickfix, "Goto next quickfix entry",
+        goto_prev_quickfix, "Goto previous quickfix entry",
         goto_first_change, "Goto first change",
         goto_last_change, "Goto last change",
         goto_line_start, "Goto line start",
@@ -534,6 +559,8 @@ pub fn doc(&self) -> &str {
         jump_forward, "Jump forward on jumplist",
         jump_backward, "Jump backward on jumplist",
         save_selection, "Save current selection to jumplist",
+        undo_selection, "Undo the last selection change",
+        redo_selection, "Redo the last undone selection change",
         jump_view_right, "Jump to right split",
         jump_view_left, "Jump to left split",
         jump_view_up, "Jump to split above",
@@ -551,6 +578,11 @@ pub fn doc(&self) -> &str {
         vsplit_new, "Vertical right split scratch buffer",
         wclose, "Close window",
         wonly, "Close windows except current",
+        toggle_pin_view, "Pin or unpin the current split",
+        toggle_scroll_bind, "Toggle scrolling the current split along with other bound splits",
+        toggle_whitespace, "Toggle rendering of whitespace and control characters",
+        goto_next_layout, "Goto next layout",
+        goto_prev_layout, "Goto previous layout",
         select_register, "Select register",
         insert_register, "Insert register",
         copy_between_registers, "Copy between two registers",
@@ -603,7 +635,9 @@ pub fn doc(&self) -> &str {
         shell_insert_output, "Insert shell command output before selections",
         shell_append_output, "Append shell command output after selections",
         shell_keep_pipe, "Filter selections with shell predicate",
+        send_to_repl, "Send selections, or the paragraph at the cursor, to the language's REPL",
         suspend, "Suspend and return to shell",
+        toggle_terminal, "Toggle the terminal panel",
         rename_symbol, "Rename symbol",
         increment, "Increment item under cursor",
         decrement, "Decrement item under cursor",
@@ -611,6 +645,7 @@ pub fn doc(&self) -> &str {
         replay_macro, "Replay macro",
         command_palette, "Open command palette",
         goto_word, "Jump to a two-character label",
+        goto_word_all_views, "Jump to a two-character label in any view or on the bufferline",
         extend_to_word, "Extend to a two-character label",
         goto_next_tabstop, "Goto next snippet placeholder",
         goto_prev_tabstop, "Goto next snippet placeholder",
@@ -652,21 +687,24 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some(suffix) = s.strip_prefix(':') {
             let (name, args, _) = command_line::split(suffix);
             ensure!(!name.is_empty(), "Expected typable command name");
-            typed::TYPABLE_COMMAND_MAP
-                .get(name)
-                .map(|cmd| {
-                    let doc = if args.is_empty() {
-                        cmd.doc.to_string()
-                    } else {
-                        format!(":{} {:?}", cmd.name, args)
-                    };
-                    MappableCommand::Typable {
-                        name: cmd.name.to_owned(),
-                        doc,
-                        args: args.to_string(),
-                    }
-                })
-                .ok_or_else(|| anyhow!("No TypableCommand named '{}'", s))
+            let (name, doc) = match typed::TYPABLE_COMMAND_MAP.get(name) {
+                Some(cmd) => (cmd.name.to_owned(), cmd.doc.to_owned()),
+                None => {
+                    let cmd = custom_command(name)
+                        .ok_or_else(|| anyhow!("No TypableCommand named '{}'", s))?;
+                    (cmd.name().to_owned(), cmd.doc().to_owned())
+                }
+            };
+            let doc = if args.is_empty() {
+                doc
+            } else {
+                format!(":{} {:?}", name, args)
+            };
+            Ok(MappableCommand::Typable {
+                name,
+                doc,
+                args: args.to_string(),
+            })
         } else if let Some(suffix) = s.strip_prefix('@') {
             helix_view::input::parse_macro(suffix).map(|keys| Self::Macro {
                 name: s.to_string(),
@@ -677,6 +715,14 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
                 .iter()
                 .find(|cmd| cmd.name() == s)
                 .cloned()
+                .or_else(|| {
+                    // Registered commands can be bound without the `:` as well
+                    custom_command(s).map(|cmd| MappableCommand::Typable {
+                        name: cmd.name().to_owned(),
+                        args: String::new(),
+                        doc: cmd.doc().to_owned(),
+                    })
+                })
                 .ok_or_else(|| anyhow!("No command named '{}'", s))
         }
     }
@@ -1226,19 +1272,31 @@ fn move_next_long_word_end(cx: &mut Context) {
 }
 
 fn move_next_sub_word_start(cx: &mut Context) {
-    move_word_impl(cx, movement::move_next_sub_word_start)
+    let rules = cx.editor.config().sub_word;
+    move_word_impl(cx, |text, range, count| {
+        movement::move_next_sub_word_start(text, range, count, rules)
+    })
 }
 
 fn move_prev_sub_word_start(cx: &mut Context) {
-    move_word_impl(cx, movement::move_prev_sub_word_start)
+    let rules = cx.editor.config().sub_word;
+    move_word_impl(cx, |text, range, count| {
+        movement::move_prev_sub_word_start(text, range, count, rules)
+    })
 }
 
 fn move_prev_sub_word_end(cx: &mut Context) {
-    move_word_impl(cx, movement::move_prev_sub_word_end)
+    let rules = cx.editor.config().sub_word;
+    move_word_impl(cx, |text, range, count| {
+        movement::move_prev_sub_word_end(text, range, count, rules)
+    })
 }
 
 fn move_next_sub_word_end(cx: &mut Context) {
-    move_word_impl(cx, movement::move_next_sub_word_end)
+    let rules = cx.editor.config().sub_word;
+    move_word_impl(cx, |text, range, count| {
+        movement::move_next_sub_word_end(text, range, count, rules)
+    })
 }
 
 fn goto_para_impl<F>(cx: &mut Context, move_fn: F)
@@ -1472,19 +1530,31 @@ fn extend_next_long_word_end(cx: &mut Context) {
 }
 
 fn extend_next_sub_word_start(cx: &mut Context) {
-    extend_word_impl(cx, movement::move_next_sub_word_start)
+    let rules = cx.editor.config().sub_word;
+    extend_word_impl(cx, |text, range, count| {
+        movement::move_next_sub_word_start(text, range, count, rules)
+    })
 }
 
 fn extend_prev_sub_word_start(cx: &mut Context) {
-    extend_word_impl(cx, movement::move_prev_sub_word_start)
+    let rules = cx.editor.config().sub_word;
+    extend_word_impl(cx, |text, range, count| {
+        movement::move_prev_sub_word_start(text, range, count, rules)
+    })
 }
 
 fn extend_prev_sub_word_end(cx: &mut Context) {
-    extend_word_impl(cx, movement::move_prev_sub_word_end)
+    let rules = cx.editor.config().sub_word;
+    extend_word_impl(cx, |text, range, count| {
+        movement::move_prev_sub_word_end(text, range, count, rules)
+    })
 }
 
 fn extend_next_sub_word_end(cx: &mut Context) {
-    extend_word_impl(cx, movement::move_next_sub_word_end)
+    let rules = cx.editor.config().sub_word;
+    extend_word_impl(cx, |text, range, count| {
+        movement::move_next_sub_word_end(text, range, count, rules)
+    })
 }
 
 /// Separate branch to find_char designed only for `<ret>` char.
@@ -2086,7 +2156,7 @@ fn select_regex(cx: &mut Context) {
         cx,
         "select:".into(),
         Some(reg),
-        ui::completers::none,
+        pattern_history(reg),
         move |cx, regex, event| {
             let (view, doc) = current!(cx.editor);
             if !matches!(event, PromptEvent::Update | PromptEvent::Validate) {
@@ -2110,7 +2180,7 @@ fn split_selection(cx: &mut Context) {
         cx,
         "split:".into(),
         Some(reg),
-        ui::completers::none,
+        pattern_history(reg),
         move |cx, regex, event| {
             let (view, doc) = current!(cx.editor);
             if !matches!(event, PromptEvent::Update | PromptEvent::Validate) {
@@ -2225,13 +2295,27 @@ fn search_impl(
     };
 }
 
-fn search_completions(cx: &mut Context, reg: Option<char>) -> Vec<String> {
-    let mut items = reg
-        .and_then(|reg| cx.editor.registers.read(reg, cx.editor))
-        .map_or(Vec::new(), |reg| reg.take(200).collect());
-    items.sort_unstable();
-    items.dedup();
-    items.into_iter().map(|value| value.to_string()).collect()
+/// Completes regex prompts with the patterns of the history register `reg`, fuzzy matched
+/// against the input. The search, select, split, keep and remove prompts share the `/` register
+/// by default, so a pattern entered in one can be recalled in the others, and across sessions
+/// with `persist-history`.
+fn pattern_history(reg: char) -> impl Fn(&Editor, &str) -> Vec<ui::prompt::Completion> {
+    move |editor, input| {
+        let Some(values) = editor.registers.read(reg, editor) else {
+            return Vec::new();
+        };
+        // Most recent first, without repeats
+        let mut seen = HashSet::new();
+        let patterns: Vec<_> = values
+            .take(200)
+            .map(|value| value.to_string())
+            .filter(|value| seen.insert(value.clone()))
+            .collect();
+        helix_core::fuzzy::fuzzy_match(input, patterns, false)
+            .into_iter()
+            .map(|(pattern, _)| (0.., pattern.into()))
+            .collect()
+    }
 }
 
 fn search(cx: &mut Context) {
@@ -2242,6 +2326,85 @@ fn rsearch(cx: &mut Context) {
     searcher(cx, Direction::Backward)
 }
 
+/// Documents up to this size are searched for all matches right away, larger documents are
+/// searched in the background.
+const SEARCH_MATCHES_SYNC_LIMIT: usize = 1024 * 1024;
+
+/// How often a background search publishes the matches it found so far.
+const SEARCH_MATCHES_BATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Returns the char ranges of all matches of `regex` in `text`.
+fn find_search_matches(text: RopeSlice, regex: &rope::Regex) -> Vec<std::ops::Range<usize>> {
+    regex
+        .find_iter(text.regex_input())
+        .map(|mat| text.byte_to_char(mat.start())..text.byte_to_char(mat.end()))
+        .collect()
+}
+
+/// Updates [`Editor::search_matches`] for a search for `query` in the current document. The
+/// matches of large documents are computed in the background.
+fn update_search_matches(editor: &mut Editor, regex: &rope::Regex, query: &str) {
+    let doc = doc!(editor);
+    let (doc_id, version) = (doc.id(), doc.version());
+    let flags = editor.search_flags;
+    if editor.search_matches.as_ref().is_some_and(|matches| {
+        matches.doc == doc_id
+            && matches.version == version
+            && matches.query == query
+            && matches.flags == flags
+    }) {
+        return;
+    }
+
+    let text = doc.text().clone();
+    let handle = editor.search_matches_controller.restart();
+    let mut search_matches = SearchMatches {
+        doc: doc_id,
+        version,
+        query: query.to_string(),
+        flags,
+        matches: Vec::new(),
+        complete: false,
+    };
+    if text.len_bytes() <= SEARCH_MATCHES_SYNC_LIMIT {
+        search_matches.matches = find_search_matches(text.slice(..), regex);
+        search_matches.complete = true;
+        editor.search_matches = Some(search_matches);
+        return;
+    }
+    editor.search_matches = Some(search_matches);
+
+    fn publish(handle: TaskHandle, batch: Vec<std::ops::Range<usize>>, complete: bool) {
+        job::dispatch_blocking(move |editor, _| {
+            if handle.is_canceled() {
+                return;
+            }
+            if let Some(matches) = &mut editor.search_matches {
+                matches.matches.extend(batch);
+                matches.complete = complete;
+            }
+        });
+    }
+
+    let regex = regex.clone();
+    tokio::task::spawn_blocking(move || {
+        let text = text.slice(..);
+        let mut batch = Vec::new();
+        let mut last_publish = std::time::Instant::now();
+        for mat in regex.find_iter(text.regex_input()) {
+            if handle.is_canceled() {
+                return;
+            }
+            batch.push(text.byte_to_char(mat.start())..text.byte_to_char(mat.end()));
+            if last_publish.elapsed() >= SEARCH_MATCHES_BATCH_INTERVAL {
+                publish(handle.clone(), std::mem::take(&mut batch), false);
+                last_publish = std::time::Instant::now();
+            }
+        }
+        publish(handle, batch, true);
+    });
+}
+
 fn searcher(cx: &mut Context, direction: Direction) {
     let reg = cx.register.unwrap_or('/');
     let config = cx.editor.config();
@@ -2253,26 +2416,17 @@ fn searcher(cx: &mut Context, direction: Direction) {
         Movement::Move
     };
 
-    // TODO: could probably share with select_on_matches?
-    let completions = search_completions(cx, Some(reg));
-
-    ui::regex_prompt(
+    ui::search_prompt(
         cx,
-        "search:".into(),
         Some(reg),
-        move |_editor: &Editor, input: &str| {
-            completions
-                .iter()
-                .filter(|comp| comp.starts_with(input))
-                .map(|comp| (0.., comp.clone().into()))
-                .collect()
-        },
-        move |cx, regex, event| {
+        pattern_history(reg),
+        move |cx, regex, input, event| {
             if event == PromptEvent::Validate {
                 cx.editor.registers.last_search_register = reg;
             } else if event != PromptEvent::Update {
                 return;
             }
+            update_search_matches(cx.editor, &regex, input);
             search_impl(
                 cx.editor,
                 &regex,
@@ -2293,22 +2447,16 @@ fn search_next_or_prev_impl(cx: &mut Context, movement: Movement, direction: Dir
         .unwrap_or(cx.editor.registers.last_search_register);
     let config = cx.editor.config();
     let scrolloff = config.scrolloff;
-    if let Some(query) = cx.editor.registers.first(register, cx.editor) {
-        let search_config = &config.search;
-        let case_insensitive = if search_config.smart_case {
-            !query.chars().any(char::is_uppercase)
-        } else {
-            false
-        };
-        let wrap_around = search_config.wrap_around;
-        if let Ok(regex) = rope::RegexBuilder::new()
-            .syntax(
-                rope::Config::new()
-                    .case_insensitive(case_insensitive)
-                    .multi_line(true),
-            )
-            .build(&query)
-        {
+    let query = cx
+        .editor
+        .registers
+        .first(register, cx.editor)
+        .map(Cow::into_owned);
+    if let Some(query) = query {
+        let wrap_around = config.search.wrap_around;
+        let flags = cx.editor.search_flags;
+        if let Ok(regex) = ui::search_regex(&config.search, flags, &query) {
+            update_search_matches(cx.editor, &regex, &query);
             for _ in 0..count {
                 search_impl(
                     cx.editor,
@@ -2327,6 +2475,53 @@ fn search_next_or_prev_impl(cx: &mut Context, movement: Movement, direction: Dir
     }
 }
 
+fn search_to_quickfix(cx: &mut Context) {
+    let register = cx
+        .register
+        .unwrap_or(cx.editor.registers.last_search_register);
+    let Some(query) = cx
+        .editor
+        .registers
+        .first(register, cx.editor)
+        .map(Cow::into_owned)
+    else {
+        cx.editor.set_error("No search pattern");
+        return;
+    };
+    let Ok(regex) = ui::search_regex(&cx.editor.config().search, cx.editor.search_flags, &query)
+    else {
+        cx.editor.set_error(format!("Invalid regex: {}", query));
+        return;
+    };
+
+    let doc = doc!(cx.editor);
+    let text = doc.text().slice(..);
+    let position = |char_idx: usize| {
+        let line = text.char_to_line(char_idx);
+        Position::new(line, char_idx - text.line_to_char(line))
+    };
+    let entries: Vec<_> = find_search_matches(text, &regex)
+        .into_iter()
+        .map(|range| {
+            let start = position(range.start);
+            QuickfixEntry {
+                doc_id: Some(doc.id()),
+                path: doc.path().cloned(),
+                start,
+                end: position(range.end),
+                text: text.line(start.row).to_string().trim().to_string(),
+            }
+        })
+        .collect();
+
+    let count = entries.len();
+    cx.editor.quickfix = QuickfixList::new(format!("search: {query}"), entries);
+    cx.editor.set_status(format!(
+        "{count} match{} added to the quickfix list",
+        if count == 1 { "" } else { "es" }
+    ));
+}
+
 fn search_next(cx: &mut Context) {
     search_next_or_prev_impl(cx, Movement::Move, Direction::Forward);
 }
@@ -2402,8 +2597,10 @@ fn is_at_word_end(text: RopeSlice, index: usize) -> bool {
         .join("|");
 
     let msg = format!("register '{}' set to '{}'", register, &regex);
-    match cx.editor.registers.push(register, regex) {
+    match cx.editor.registers.push_history(register, regex) {
         Ok(_) => {
+            // the register now holds a regex, it mustn't be escaped again by `n`
+            cx.editor.search_flags.literal = false;
             cx.editor.registers.last_search_register = register;
             cx.editor.set_status(msg)
         }
@@ -2442,8 +2639,9 @@ fn make_search_word_bounded(cx: &mut Context) {
     }
 
     let msg = format!("register '{}' set to '{}'", register, &new_regex);
-    match cx.editor.registers.push(register, new_regex) {
+    match cx.editor.registers.push_history(register, new_regex) {
         Ok(_) => {
+            cx.editor.search_flags.literal = false;
             cx.editor.registers.last_search_register = register;
             cx.editor.set_status(msg)
         }
@@ -2471,6 +2669,7 @@ fn new(path: &Path, line_num: usize) -> Self {
     struct GlobalSearchConfig {
         smart_case: bool,
         file_picker_config: helix_view::editor::FilePickerConfig,
+        threads: usize,
         directory_style: Style,
         number_style: Style,
         colon_style: Style,
@@ -2480,6 +2679,7 @@ struct GlobalSearchConfig {
     let config = GlobalSearchConfig {
         smart_case: config.search.smart_case,
         file_picker_config: config.file_picker.clone(),
+        threads: config.resources.grep_threads,
         directory_style: cx.editor.theme.get("ui.text.directory"),
         number_style: cx.editor.theme.get("constant.numeric.integer"),
         colon_style: cx.editor.theme.get("punctuation"),
@@ -2564,6 +2764,7 @@ struct GlobalSearchConfig {
                 .git_global(config.file_picker_config.git_global)
                 .git_exclude(config.file_picker_config.git_exclude)
                 .max_depth(config.file_picker_config.max_depth)
+                .threads(config.threads)
                 .filter_entry(move |entry| {
                     filter_picker_entry(entry, &absolute_root, dedup_symlinks)
                 })
@@ -3329,6 +3530,139 @@ struct JumpMeta {
     cx.push_layer(Box::new(overlaid(picker)));
 }
 
+/// Opens the document of a quickfix entry and selects its range.
+fn goto_quickfix_entry(editor: &mut Editor, entry: &QuickfixEntry, action: Action) {
+    let (view, doc) = current!(editor);
+    push_jump(view, doc);
+
+    match (entry.doc_id, &entry.path) {
+        (Some(doc_id), _) if editor.documents.contains_key(&doc_id) => {
+            editor.switch(doc_id, action)
+        }
+        (_, Some(path)) => {
+            if let Err(err) = editor.open(path, action) {
+                editor.set_error(format!("Failed to open '{}': {err}", path.display()));
+                return;
+            }
+        }
+        _ => {
+            editor.set_error("The document of the quickfix entry was closed");
+            return;
+        }
+    }
+
+    let (view, doc) = current!(editor);
+    let text = doc.text().slice(..);
+    let char_idx = |pos: Position| {
+        let line = pos.row.min(text.len_lines() - 1);
+        (text.line_to_char(line) + pos.col).min(line_end_char_index(&text, line))
+    };
+    let (start, end) = (char_idx(entry.start), char_idx(entry.end));
+    doc.set_selection(view.id, Selection::single(start, end));
+    align_view(doc, view, Align::Center);
+}
+
+fn goto_quickfix_impl(cx: &mut Context, direction: Direction) {
+    let count = cx.count();
+    let quickfix = &mut cx.editor.quickfix;
+    let entry = match direction {
+        Direction::Forward => quickfix.next(count),
+        Direction::Backward => quickfix.prev(count),
+    }
+    .cloned();
+    let Some(entry) = entry else {
+        cx.editor.set_error("The quickfix list is empty");
+        return;
+    };
+
+    goto_quickfix_entry(cx.editor, &entry, Action::Replace);
+    let quickfix = &cx.editor.quickfix;
+    let status = format!(
+        "[{}/{}] {}",
+        quickfix.current.map_or(0, |idx| idx + 1),
+        quickfix.entries.len(),
+        entry.text
+    );
+    cx.editor.set_status(status);
+}
+
+fn goto_next_quickfix(cx: &mut Context) {
+    goto_quickfix_impl(cx, Direction::Forward)
+}
+
+fn goto_prev_quickfix(cx: &mut Context) {
+    goto_quickfix_impl(cx, Direction::Backward)
+}
+
+pub struct QuickfixItem {
+    idx: usize,
+    location: String,
+    entry: QuickfixEntry,
+}
+
+pub type QuickfixPicker = Picker<QuickfixItem, ()>;
+
+/// Builds a picker over the entries of the quickfix list.
+pub(crate) fn make_quickfix_picker(editor: &Editor) -> QuickfixPicker {
+    let items: Vec<_> = editor
+        .quickfix
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let name = match (&entry.path, entry.doc_id) {
+                (Some(path), _) => helix_stdx::path::get_relative_path(path)
+                    .to_string_lossy()
+                    .into_owned(),
+                (None, Some(doc_id)) => editor
+                    .document(doc_id)
+                    .map_or(SCRATCH_BUFFER_NAME.into(), |doc| {
+                        doc.display_name().into_owned()
+                    }),
+                (None, None) => SCRATCH_BUFFER_NAME.into(),
+            };
+            QuickfixItem {
+                idx,
+                location: format!("{name}:{}:{}", entry.start.row + 1, entry.start.col + 1),
+                entry: entry.clone(),
+            }
+        })
+        .collect();
+
+    let columns = [
+        ui::PickerColumn::new("location", |item: &QuickfixItem, _| {
+            item.location.as_str().into()
+        }),
+        ui::PickerColumn::new("text", |item: &QuickfixItem, _| {
+            item.entry.text.as_str().into()
+        }),
+    ];
+
+    Picker::new(columns, 0, items, (), |cx, item, action| {
+        cx.editor.quickfix.select(item.idx);
+        goto_quickfix_entry(cx.editor, &item.entry, action);
+    })
+    .with_preview(|editor, item| {
+        let lines = Some((item.entry.start.row, item.entry.end.row));
+        match (item.entry.doc_id, &item.entry.path) {
+            (Some(doc_id), _) if editor.documents.contains_key(&doc_id) => {
+                Some((doc_id.into(), lines))
+            }
+            (_, Some(path)) => Some((path.as_path().into(), lines)),
+            _ => None,
+        }
+    })
+}
+
+fn quickfix_picker(cx: &mut Context) {
+    if cx.editor.quickfix.is_empty() {
+        cx.editor.set_error("The quickfix list is empty");
+        return;
+    }
+    let picker = make_quickfix_picker(cx.editor);
+    cx.push_layer(Box::new(overlaid(picker)));
+}
+
 fn changed_file_picker(cx: &mut Context) {
     pub struct FileChangeData {
         cwd: PathBuf,
@@ -3433,15 +3767,27 @@ pub fn command_palette(cx: &mut Context) {
                 [&cx.editor.mode]
                 .reverse_map();
 
-            let commands = MappableCommand::STATIC_COMMAND_LIST.iter().cloned().chain(
-                typed::TYPABLE_COMMAND_LIST
-                    .iter()
-                    .map(|cmd| MappableCommand::Typable {
-                        name: cmd.name.to_owned(),
-                        args: String::new(),
-                        doc: cmd.doc.to_owned(),
-                    }),
-            );
+            let commands = MappableCommand::STATIC_COMMAND_LIST
+                .iter()
+                .cloned()
+                .chain(
+                    typed::TYPABLE_COMMAND_LIST
+                        .iter()
+                        .map(|cmd| MappableCommand::Typable {
+                            name: cmd.name.to_owned(),
+                            args: String::new(),
+                            doc: cmd.doc.to_owned(),
+                        }),
+                )
+                .chain(
+                    custom_commands()
+                        .into_iter()
+                        .map(|cmd| MappableCommand::Typable {
+                            name: cmd.name().to_owned(),
+                            args: String::new(),
+                            doc: cmd.doc().to_owned(),
+                        }),
+                );
 
             let columns = [
                 ui::PickerColumn::new("name", |item, _| match item {
@@ -3619,19 +3965,43 @@ async fn make_format_callback(
             return;
         }
 
-        let scrolloff = editor.config().scrolloff;
+        let confirm_threshold = editor.config().format_confirm_threshold;
         let doc = doc_mut!(editor, &doc_id);
-        let view = view_mut!(editor, view_id);
 
         match format {
             Ok(format) => {
-                if doc.version() == doc_version {
-                    doc.apply(&format, view.id);
-                    doc.append_changes_to_history(view);
-                    doc.detect_indent_and_line_ending();
-                    view.ensure_cursor_in_view(doc, scrolloff);
-                } else {
+                let total_lines = doc.text().len_lines();
+                let needs_confirm = confirm_threshold.and_then(|percent| {
+                    let (changed_lines, diff) = format_diff(doc.text(), &format, FORMAT_DIFF_LINES);
+                    (changed_lines * 100 > total_lines * percent as usize)
+                        .then_some((changed_lines, diff))
+                });
+                if doc.version() != doc_version {
                     log::info!("discarded formatting changes because the document changed");
+                } else if let Some((changed_lines, diff)) = needs_confirm {
+                    // Ask before applying. When writing, the file is saved without the changes
+                    // below, so a misbehaving formatter can't destroy it even if the editor quits.
+                    let mut summary = format!(
+                        "The formatter changes {changed_lines} of {total_lines} lines of {}.",
+                        doc.display_name()
+                    );
+                    if write.is_some() {
+                        summary.push_str(" The file was saved without the changes.");
+                    }
+                    let pending = Arc::new(PendingFormat {
+                        doc_id,
+                        view_id,
+                        doc_version,
+                        transaction: format,
+                        write: write.clone(),
+                        summary,
+                        diff,
+                    });
+                    job::dispatch_blocking(move |_, compositor| {
+                        confirm_format(compositor, pending, false)
+                    });
+                } else {
+                    apply_format(editor, doc_id, view_id, &format);
                 }
             }
             Err(err) => {
@@ -3644,8 +4014,7 @@ async fn make_format_callback(
         }
 
         if let Some((path, force)) = write {
-            let id = doc.id();
-            if let Err(err) = editor.save(id, path, force) {
+            if let Err(err) = editor.save(doc_id, path, force) {
                 editor.set_error(format!("Error saving: {}", err));
             }
         }
@@ -3654,6 +4023,146 @@ async fn make_format_callback(
     Ok(call)
 }
 
+/// The number of lines of the diff shown when asking before applying formatting changes.
+const FORMAT_DIFF_LINES: usize = 30;
+
+fn apply_format(editor: &mut Editor, doc_id: DocumentId, view_id: ViewId, format: &Transaction) {
+    let scrolloff = editor.config().scrolloff;
+    let doc = doc_mut!(editor, &doc_id);
+    let view = view_mut!(editor, view_id);
+    doc.apply(format, view.id);
+    doc.append_changes_to_history(view);
+    doc.detect_indent_and_line_ending();
+    view.ensure_cursor_in_view(doc, scrolloff);
+}
+
+/// Formatting changes waiting for confirmation, see `format-confirm-threshold`.
+struct PendingFormat {
+    doc_id: DocumentId,
+    view_id: ViewId,
+    doc_version: i32,
+    transaction: Transaction,
+    write: Option<(Option<PathBuf>, bool)>,
+    summary: String,
+    diff: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormatChoice {
+    Apply,
+    Discard,
+    ShowDiff,
+}
+
+impl ui::menu::Item for FormatChoice {
+    type Data = ();
+
+    fn format(&self, _data: &Self::Data) -> tui::widgets::Row<'_> {
+        match self {
+            Self::Apply => "Apply the changes",
+            Self::Discard => "Discard the changes",
+            Self::ShowDiff => "Show the changes",
+        }
+        .into()
+    }
+}
+
+fn confirm_format(compositor: &mut Compositor, pending: Arc<PendingFormat>, show_diff: bool) {
+    let (message, choices) = if show_diff {
+        let message = format!("{}\n\n{}", pending.summary, pending.diff);
+        (message, vec![FormatChoice::Apply, FormatChoice::Discard])
+    } else {
+        let choices = vec![
+            FormatChoice::Apply,
+            FormatChoice::Discard,
+            FormatChoice::ShowDiff,
+        ];
+        (pending.summary.clone(), choices)
+    };
+
+    let select = ui::Select::new(message, choices, (), move |editor, choice, event| {
+        if event != PromptEvent::Validate {
+            return;
+        }
+        match choice {
+            FormatChoice::Apply => {
+                let (doc_id, view_id) = (pending.doc_id, pending.view_id);
+                let applicable = editor.tree.contains(view_id)
+                    && editor
+                        .document(doc_id)
+                        .is_some_and(|doc| doc.version() == pending.doc_version);
+                if !applicable {
+                    editor.set_error("The document changed, the formatting changes were discarded");
+                    return;
+                }
+                apply_format(editor, doc_id, view_id, &pending.transaction);
+                if let Some((path, force)) = pending.write.clone() {
+                    if let Err(err) = editor.save(doc_id, path, force) {
+                        editor.set_error(format!("Error saving: {}", err));
+                    }
+                }
+            }
+            FormatChoice::Discard => {}
+            FormatChoice::ShowDiff => {
+                let pending = pending.clone();
+                job::dispatch_blocking(move |_, compositor| {
+                    confirm_format(compositor, pending, true)
+                });
+            }
+        }
+    });
+    compositor.replace_or_push("format-confirm", select);
+}
+
+/// Returns the number of lines changed by formatting and a diff of them, limited to `max_lines`
+/// lines. Changes on the same line are shown together, as the whole old and new lines.
+fn format_diff(text: &Rope, transaction: &Transaction, max_lines: usize) -> (usize, String) {
+    let text = text.slice(..);
+    let mut hunks: Vec<(usize, usize, Vec<helix_core::Change>)> = Vec::new();
+    for change in transaction.changes().changes_iter() {
+        let start_line = text.char_to_line(change.0);
+        // A change ending at the start of a line doesn't touch that line
+        let end_line = text.char_to_line(change.1.saturating_sub(1).max(change.0));
+        match hunks.last_mut() {
+            Some((_, last_end, changes)) if start_line <= *last_end => {
+                *last_end = end_line.max(*last_end);
+                changes.push(change);
+            }
+            _ => hunks.push((start_line, end_line, vec![change])),
+        }
+    }
+
+    let mut changed_lines = 0;
+    let mut lines = Vec::new();
+    for (start_line, end_line, changes) in &hunks {
+        let start = text.line_to_char(*start_line);
+        let end = text.line_to_char(*end_line + 1);
+        let mut new = String::new();
+        let mut pos = start;
+        for (from, to, insert) in changes {
+            new.extend(text.slice(pos..*from).chunks());
+            if let Some(insert) = insert {
+                new.push_str(insert);
+            }
+            pos = *to;
+        }
+        new.extend(text.slice(pos..end).chunks());
+
+        changed_lines += end_line - start_line + 1;
+        lines.push(format!("@@ line {} @@", start_line + 1));
+        let old = text.slice(start..end).to_string();
+        lines.extend(old.lines().map(|line| format!("-{line}")));
+        lines.extend(new.lines().map(|line| format!("+{line}")));
+    }
+
+    if lines.len() > max_lines {
+        let more = lines.len() - max_lines;
+        lines.truncate(max_lines);
+        lines.push(format!("… {more} more lines"));
+    }
+    (changed_lines, lines.join("\n"))
+}
+
 #[derive(PartialEq, Eq)]
 pub enum Open {
     Below,
@@ -3959,9 +4468,19 @@ fn exit_select_mode(cx: &mut Context) {
     }
 }
 
+/// Whether `diag` is visited by the diagnostic motions with the given minimum severity.
+fn diag_is_at_least(diag: &helix_core::Diagnostic, min_severity: Severity) -> bool {
+    diag.severity.unwrap_or(Severity::Hint) >= min_severity
+}
+
 fn goto_first_diag(cx: &mut Context) {
+    let min_severity = cx.editor.config().diagnostic_navigation.min_severity;
     let (view, doc) = current!(cx.editor);
-    let selection = match doc.diagnostics().first() {
+    let selection = match doc
+        .diagnostics()
+        .iter()
+        .find(|diag| diag_is_at_least(diag, min_severity))
+    {
         Some(diag) => Selection::single(diag.range.start, diag.range.end),
         None => return,
     };
@@ -3972,8 +4491,13 @@ fn goto_first_diag(cx: &mut Context) {
 }
 
 fn goto_last_diag(cx: &mut Context) {
+    let min_severity = cx.editor.config().diagnostic_navigation.min_severity;
     let (view, doc) = current!(cx.editor);
-    let selection = match doc.diagnostics().last() {
+    let selection = match doc
+        .diagnostics()
+        .iter()
+        .rfind(|diag| diag_is_at_least(diag, min_severity))
+    {
         Some(diag) => Selection::single(diag.range.start, diag.range.end),
         None => return,
     };
@@ -3984,33 +4508,25 @@ fn goto_last_diag(cx: &mut Context) {
 }
 
 fn goto_next_diag(cx: &mut Context) {
-    let motion = move |editor: &mut Editor| {
-        let (view, doc) = current!(editor);
-
-        let cursor_pos = doc
-            .selection(view.id)
-            .primary()
-            .cursor(doc.text().slice(..));
+    let min_severity = cx.editor.config().diagnostic_navigation.min_severity;
+    goto_diag_impl(cx, Direction::Forward, min_severity)
+}
 
-        let diag = doc
-            .diagnostics()
-            .iter()
-            .find(|diag| diag.range.start > cursor_pos);
+fn goto_prev_diag(cx: &mut Context) {
+    let min_severity = cx.editor.config().diagnostic_navigation.min_severity;
+    goto_diag_impl(cx, Direction::Backward, min_severity)
+}
 
-        let selection = match diag {
-            Some(diag) => Selection::single(diag.range.start, diag.range.end),
-            None => return,
-        };
-        push_jump(view, doc);
-        doc.set_selection(view.id, selection);
-        view.diagnostics_handler
-            .immediately_show_diagnostic(doc, view.id);
-    };
+fn goto_next_error(cx: &mut Context) {
+    goto_diag_impl(cx, Direction::Forward, Severity::Error)
+}
 
-    cx.editor.apply_motion(motion);
+fn goto_prev_error(cx: &mut Context) {
+    goto_diag_impl(cx, Direction::Backward, Severity::Error)
 }
 
-fn goto_prev_diag(cx: &mut Context) {
+fn goto_diag_impl(cx: &mut Context, direction: Direction, min_severity: Severity) {
+    let wrap_around = cx.editor.config().diagnostic_navigation.wrap_around;
     let motion = move |editor: &mut Editor| {
         let (view, doc) = current!(editor);
 
@@ -4019,22 +4535,42 @@ fn goto_prev_diag(cx: &mut Context) {
             .primary()
             .cursor(doc.text().slice(..));
 
-        let diag = doc
+        let mut diags = doc
             .diagnostics()
             .iter()
-            .rev()
-            .find(|diag| diag.range.start < cursor_pos);
+            .filter(|diag| diag_is_at_least(diag, min_severity));
+        let next = match direction {
+            Direction::Forward => diags.clone().find(|diag| diag.range.start > cursor_pos),
+            Direction::Backward => diags.clone().rfind(|diag| diag.range.start < cursor_pos),
+        };
+        let (diag, wrapped) = match next {
+            Some(diag) => (diag, false),
+            None if wrap_around => {
+                let diag = match direction {
+                    Direction::Forward => diags.next(),
+                    Direction::Backward => diags.next_back(),
+                };
+                match diag {
+                    Some(diag) => (diag, true),
+                    None => return,
+                }
+            }
+            None => return,
+        };
 
-        let selection = match diag {
+        let selection = match direction {
+            Direction::Forward => Selection::single(diag.range.start, diag.range.end),
             // NOTE: the selection is reversed because we're jumping to the
             // previous diagnostic.
-            Some(diag) => Selection::single(diag.range.end, diag.range.start),
-            None => return,
+            Direction::Backward => Selection::single(diag.range.end, diag.range.start),
         };
         push_jump(view, doc);
         doc.set_selection(view.id, selection);
         view.diagnostics_handler
             .immediately_show_diagnostic(doc, view.id);
+        if wrapped {
+            editor.set_status("Wrapped around document");
+        }
     };
     cx.editor.apply_motion(motion)
 }
@@ -4169,7 +4705,68 @@ fn insert(doc: &Rope, selection: &Selection, ch: char) -> Option<Transaction> {
     use helix_core::auto_pairs;
     use helix_view::editor::SmartTabConfig;
 
+    /// Replaces the abbreviations ending at the cursors with their expansions, see
+    /// `editor.abbreviations`. The language's abbreviations take precedence.
+    fn expand_abbreviations(cx: &mut Context) {
+        let config = cx.editor.config();
+        let (view, doc) = current!(cx.editor);
+        let language = doc.language_config().map(|lang| &lang.abbreviations);
+        let has_abbreviations =
+            !config.abbreviations.is_empty() || language.is_some_and(|abbrevs| !abbrevs.is_empty());
+        if !has_abbreviations {
+            return;
+        }
+
+        let changes = abbreviation_changes(
+            doc.text().slice(..),
+            doc.selection(view.id),
+            &config.abbreviations,
+            language,
+        );
+        if !changes.is_empty() {
+            let transaction = Transaction::change(doc.text(), changes.into_iter());
+            doc.apply(&transaction, view.id);
+        }
+    }
+
+    pub(super) fn abbreviation_changes(
+        text: RopeSlice,
+        selection: &Selection,
+        abbreviations: &HashMap<String, String>,
+        language: Option<&HashMap<String, String>>,
+    ) -> Vec<helix_core::Change> {
+        let mut changes = Vec::new();
+        let mut last_end = 0;
+        for range in selection {
+            let cursor = range.cursor(text);
+            let len = text
+                .chars_at(cursor)
+                .reversed()
+                .take_while(|&ch| char_is_word(ch))
+                .count();
+            let start = cursor - len;
+            // cursors inside the same word would produce overlapping changes
+            if len == 0 || start < last_end {
+                continue;
+            }
+            let word = Cow::from(text.slice(start..cursor));
+            let expansion = language
+                .and_then(|abbrevs| abbrevs.get(word.as_ref()))
+                .or_else(|| abbreviations.get(word.as_ref()));
+            if let Some(expansion) = expansion {
+                changes.push((start, cursor, Some(Tendril::from(expansion.as_str()))));
+                last_end = cursor;
+            }
+        }
+        changes
+    }
+
     pub fn insert_char(cx: &mut Context, c: char) {
+        // abbreviations are expanded before auto pairs and the post insert hooks see the char
+        if !char_is_word(c) {
+            expand_abbreviations(cx);
+        }
+
         let (view, doc) = current_ref!(cx.editor);
         let text = doc.text();
         let selection = doc.selection(view.id);
@@ -4817,6 +5414,121 @@ pub(crate) fn paste_bracketed_value(cx: &mut Context, contents: String) {
     exit_select_mode(cx);
 }
 
+/// Handles a paste of only paths of existing files, see `editor.paste-file-paths`. Returns
+/// whether the paste was handled, otherwise it should be inserted as usual.
+pub(crate) fn paste_file_paths(cx: &mut Context, contents: &str) -> bool {
+    let handling = cx.editor.config().paste_file_paths;
+    if handling == PasteFilePaths::Insert {
+        return false;
+    }
+    let Some(paths) = dropped_file_paths(contents) else {
+        return false;
+    };
+    if handling == PasteFilePaths::Open {
+        open_dropped_files(cx.editor, &paths);
+        return true;
+    }
+
+    let message = match paths.as_slice() {
+        [path] => format!("Pasted the path of {}", path.display()),
+        paths => format!("Pasted the paths of {} files", paths.len()),
+    };
+    let contents = contents.to_string();
+    let count = cx.count();
+    let choices = [DroppedFilesChoice::Open, DroppedFilesChoice::Insert];
+    let select = ui::Select::new(message, choices, (), move |editor, choice, event| {
+        if event != PromptEvent::Validate {
+            return;
+        }
+        match choice {
+            DroppedFilesChoice::Open => open_dropped_files(editor, &paths),
+            DroppedFilesChoice::Insert => {
+                let mode = editor.mode;
+                let paste = match mode {
+                    Mode::Insert | Mode::Select => Paste::Cursor,
+                    Mode::Normal => Paste::Before,
+                };
+                let (view, doc) = current!(editor);
+                paste_impl(&[contents.clone()], doc, view, paste, count, mode);
+            }
+        }
+    });
+    cx.replace_or_push_layer("paste-file-paths", select);
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DroppedFilesChoice {
+    Open,
+    Insert,
+}
+
+impl ui::menu::Item for DroppedFilesChoice {
+    type Data = ();
+
+    fn format(&self, _data: &Self::Data) -> tui::widgets::Row<'_> {
+        match self {
+            Self::Open => "Open the files",
+            Self::Insert => "Insert the paths",
+        }
+        .into()
+    }
+}
+
+/// The paths in `contents` if it only consists of absolute paths of existing files, as
+/// terminals paste files dropped on them: separated by whitespace, with quotes or backslashes
+/// around special characters, or as `file://` URIs.
+fn dropped_file_paths(contents: &str) -> Option<Vec<PathBuf>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = contents.chars();
+    while let Some(ch) = chars.next() {
+        match (quote, ch) {
+            (Some(end), ch) if ch == end => quote = None,
+            (Some(_), ch) => word.get_or_insert_default().push(ch),
+            (None, '\'' | '"') => {
+                quote = Some(ch);
+                word.get_or_insert_default();
+            }
+            // Backslashes separate the components of paths on Windows
+            (None, '\\') if cfg!(unix) => word.get_or_insert_default().push(chars.next()?),
+            (None, ch) if ch.is_whitespace() => words.extend(word.take()),
+            (None, ch) => word.get_or_insert_default().push(ch),
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    words.extend(word);
+
+    let paths = words
+        .into_iter()
+        .map(|word| match word.strip_prefix("file://") {
+            Some(_) => Url::parse(&word).ok()?.to_file_path().ok(),
+            None => Some(PathBuf::from(word)),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let files = !paths.is_empty()
+        && paths
+            .iter()
+            .all(|path| path.is_absolute() && path.is_file());
+    files.then_some(paths)
+}
+
+fn open_dropped_files(editor: &mut Editor, paths: &[PathBuf]) {
+    for (i, path) in paths.iter().enumerate() {
+        let action = if i + 1 == paths.len() {
+            Action::Replace
+        } else {
+            Action::Load
+        };
+        if let Err(err) = editor.open(path, action) {
+            editor.set_error(format!("Failed to open {}: {err}", path.display()));
+        }
+    }
+}
+
 fn paste_clipboard_after(cx: &mut Context) {
     paste(cx.editor, '+', Paste::After, cx.count());
     exit_select_mode(cx);
@@ -5200,7 +5912,7 @@ fn keep_or_remove_selections_impl(cx: &mut Context, remove: bool) {
         cx,
         if remove { "remove:" } else { "keep:" }.into(),
         Some(reg),
-        ui::completers::none,
+        pattern_history(reg),
         move |cx, regex, event| {
             let (view, doc) = current!(cx.editor);
             if !matches!(event, PromptEvent::Update | PromptEvent::Validate) {
@@ -5671,6 +6383,26 @@ fn save_selection(cx: &mut Context) {
     cx.editor.set_status("Selection saved to jumplist");
 }
 
+fn undo_selection(cx: &mut Context) {
+    let count = cx.count();
+    let (view, doc) = current!(cx.editor);
+    let current = doc.selection(view.id).clone();
+    match view.selection_history.undo(doc, &current, count) {
+        Some(selection) => doc.set_selection(view.id, selection),
+        None => cx.editor.set_status("Already at oldest selection"),
+    }
+}
+
+fn redo_selection(cx: &mut Context) {
+    let count = cx.count();
+    let (view, doc) = current!(cx.editor);
+    let current = doc.selection(view.id).clone();
+    match view.selection_history.redo(doc, &current, count) {
+        Some(selection) => doc.set_selection(view.id, selection),
+        None => cx.editor.set_status("Already at newest selection"),
+    }
+}
+
 fn rotate_view(cx: &mut Context) {
     cx.editor.focus_next()
 }
@@ -5751,7 +6483,7 @@ fn vsplit_new(cx: &mut Context) {
 }
 
 fn wclose(cx: &mut Context) {
-    if cx.editor.tree.views().count() == 1 {
+    if cx.editor.tree.views().count() == 1 && cx.editor.tree.layout_count() == 1 {
         if let Err(err) = typed::buffers_remaining_impl(cx.editor) {
             cx.editor.set_error(err.to_string());
             return;
@@ -5762,6 +6494,62 @@ fn wclose(cx: &mut Context) {
     cx.editor.close(view_id);
 }
 
+fn toggle_pin_view(cx: &mut Context) {
+    let view = view_mut!(cx.editor);
+    view.pinned = !view.pinned;
+    let status = if view.pinned {
+        "Pinned the split, documents are opened in a preview split"
+    } else {
+        "Unpinned the split"
+    };
+    cx.editor.set_status(status);
+}
+
+fn toggle_scroll_bind(cx: &mut Context) {
+    let view_id = view!(cx.editor).id;
+    let status = if cx.editor.toggle_scroll_bind(view_id) {
+        "Scroll binding enabled"
+    } else {
+        "Scroll binding disabled"
+    };
+    cx.editor.set_status(status);
+}
+
+fn toggle_whitespace(cx: &mut Context) {
+    let mut config = (*cx.editor.config()).clone();
+    let (render, status) = if config.whitespace.render.is_visible() {
+        (WhitespaceRenderValue::None, "Whitespace hidden")
+    } else {
+        (WhitespaceRenderValue::All, "Whitespace shown")
+    };
+    config.whitespace.render = WhitespaceRender::Basic(render);
+    let _ = cx
+        .editor
+        .config_events
+        .0
+        .send(ConfigEvent::Update(Box::new(config)));
+    cx.editor.set_status(status);
+}
+
+fn goto_layout_impl(cx: &mut Context, direction: Direction) {
+    let count = cx.count();
+    let len = cx.editor.tree.layout_count();
+    let current = cx.editor.tree.current_layout();
+    let idx = match direction {
+        Direction::Forward => (current + count) % len,
+        Direction::Backward => (current + len - count % len) % len,
+    };
+    cx.editor.switch_layout(idx);
+}
+
+fn goto_next_layout(cx: &mut Context) {
+    goto_layout_impl(cx, Direction::Forward)
+}
+
+fn goto_prev_layout(cx: &mut Context) {
+    goto_layout_impl(cx, Direction::Backward)
+}
+
 fn wonly(cx: &mut Context) {
     let views = cx
         .editor
@@ -6006,9 +6794,18 @@ fn select_textobject(cx: &mut Context, objtype: textobject::TextObject) {
         cx.editor.autoinfo = None;
         if let Some(ch) = event.char() {
             let textobject = move |editor: &mut Editor| {
+                let pairs = custom_surround_pairs(editor, doc!(editor));
+                let sub_word = editor.config().sub_word;
                 let (view, doc) = current!(editor);
                 let loader = editor.syn_loader.load();
                 let text = doc.text().slice(..);
+                let custom_textobject = doc.language_config().and_then(|config| {
+                    config
+                        .textobjects
+                        .iter()
+                        .find(|textobject| textobject.key == Some(ch))
+                        .map(|textobject| textobject.name.clone())
+                });
 
                 let textobject_treesitter = |obj_name: &str, range: Range| -> Range {
                     let Some(syntax) = doc.syntax() else {
@@ -6044,6 +6841,7 @@ fn select_textobject(cx: &mut Context, objtype: textobject::TextObject) {
                     match ch {
                         'w' => textobject::textobject_word(text, range, objtype, count, false),
                         'W' => textobject::textobject_word(text, range, objtype, count, true),
+                        's' => textobject::textobject_sub_word(text, range, objtype, sub_word),
                         't' => textobject_treesitter("class", range),
                         'f' => textobject_treesitter("function", range),
                         'a' => textobject_treesitter("parameter", range),
@@ -6060,15 +6858,20 @@ fn select_textobject(cx: &mut Context, objtype: textobject::TextObject) {
                             count,
                         ),
                         'g' => textobject_change(range),
+                        _ if custom_textobject.is_some() => {
+                            textobject_treesitter(custom_textobject.as_deref().unwrap(), range)
+                        }
                         // TODO: cancel new ranges if inconsistent surround matches across lines
-                        ch if !ch.is_ascii_alphanumeric() => textobject::textobject_pair_surround(
-                            doc.syntax(),
-                            text,
-                            range,
-                            objtype,
-                            ch,
-                            count,
-                        ),
+                        ch if !ch.is_ascii_alphanumeric() => {
+                            textobject::textobject_pair_surround_of(
+                                doc.syntax(),
+                                text,
+                                range,
+                                objtype,
+                                match_brackets::get_pair_with(ch, &pairs),
+                                count,
+                            )
+                        }
                         _ => range,
                     }
                 });
@@ -6086,6 +6889,7 @@ fn select_textobject(cx: &mut Context, objtype: textobject::TextObject) {
     let help_text = [
         ("w", "Word"),
         ("W", "WORD"),
+        ("s", "Sub word"),
         ("p", "Paragraph"),
         ("t", "Type definition (tree-sitter)"),
         ("f", "Function (tree-sitter)"),
@@ -6098,6 +6902,18 @@ fn select_textobject(cx: &mut Context, objtype: textobject::TextObject) {
         ("x", "(X)HTML element (tree-sitter)"),
         (" ", "... or any character acting as a pair"),
     ];
+    let mut help_text: Vec<(Cow<str>, Cow<str>)> = help_text
+        .into_iter()
+        .map(|(key, desc)| (key.into(), desc.into()))
+        .collect();
+    // text objects from the language configuration
+    if let Some(config) = doc!(cx.editor).language_config() {
+        help_text.extend(config.textobjects.iter().filter_map(|textobject| {
+            let key = textobject.key?;
+            let desc = format!("{} (tree-sitter)", textobject.name);
+            Some((key.to_string().into(), desc.into()))
+        }));
+    }
 
     cx.editor.autoinfo = Some(Info::new(title, &help_text));
 }
@@ -6111,14 +6927,27 @@ fn select_textobject(cx: &mut Context, objtype: textobject::TextObject) {
     (" ", "... or any character"),
 ];
 
+/// The surround pairs of the document's language followed by the ones from
+/// `editor.surround-pairs`, see [match_brackets::get_pair_with].
+fn custom_surround_pairs(editor: &Editor, doc: &Document) -> Vec<(char, char)> {
+    let config = editor.config();
+    doc.language_config()
+        .into_iter()
+        .flat_map(|config| &config.surround_pairs)
+        .chain(&config.surround_pairs)
+        .map(|(&open, &close)| (open, close))
+        .collect()
+}
+
 fn surround_add(cx: &mut Context) {
     cx.on_next_key(move |cx, event| {
         cx.editor.autoinfo = None;
+        let pairs = custom_surround_pairs(cx.editor, doc!(cx.editor));
         let (view, doc) = current!(cx.editor);
         // surround_len is the number of new characters being added.
         let (open, close, surround_len) = match event.char() {
             Some(ch) => {
-                let (o, c) = match_brackets::get_pair(ch);
+                let (o, c) = match_brackets::get_pair_with(ch, &pairs);
                 let mut open = Tendril::new();
                 open.push(o);
                 let mut close = Tendril::new();
@@ -6171,18 +7000,25 @@ fn surround_replace(cx: &mut Context) {
             Some(ch) => Some(ch),
             None => return,
         };
+        let pairs = custom_surround_pairs(cx.editor, doc!(cx.editor));
+        let surround_pair = surround_ch.map(|ch| match_brackets::get_pair_with(ch, &pairs));
         let (view, doc) = current!(cx.editor);
         let text = doc.text().slice(..);
         let selection = doc.selection(view.id);
 
-        let change_pos =
-            match surround::get_surround_pos(doc.syntax(), text, selection, surround_ch, count) {
-                Ok(c) => c,
-                Err(err) => {
-                    cx.editor.set_error(err.to_string());
-                    return;
-                }
-            };
+        let change_pos = match surround::get_surround_pos_of(
+            doc.syntax(),
+            text,
+            selection,
+            surround_pair,
+            count,
+        ) {
+            Ok(c) => c,
+            Err(err) => {
+                cx.editor.set_error(err.to_string());
+                return;
+            }
+        };
 
         let selection = selection.clone();
         let ranges: SmallVec<[Range; 1]> = change_pos.iter().map(|&p| Range::point(p)).collect();
@@ -6198,7 +7034,7 @@ fn surround_replace(cx: &mut Context) {
                 Some(to) => to,
                 None => return doc.set_selection(view.id, selection),
             };
-            let (open, close) = match_brackets::get_pair(to);
+            let (open, close) = match_brackets::get_pair_with(to, &pairs);
 
             // the changeset has to be sorted to allow nested surrounds
             let mut sorted_pos: Vec<(usize, char)> = Vec::new();
@@ -6242,18 +7078,25 @@ fn surround_delete(cx: &mut Context) {
             Some(ch) => Some(ch),
             None => return,
         };
+        let pairs = custom_surround_pairs(cx.editor, doc!(cx.editor));
+        let surround_pair = surround_ch.map(|ch| match_brackets::get_pair_with(ch, &pairs));
         let (view, doc) = current!(cx.editor);
         let text = doc.text().slice(..);
         let selection = doc.selection(view.id);
 
-        let mut change_pos =
-            match surround::get_surround_pos(doc.syntax(), text, selection, surround_ch, count) {
-                Ok(c) => c,
-                Err(err) => {
-                    cx.editor.set_error(err.to_string());
-                    return;
-                }
-            };
+        let mut change_pos = match surround::get_surround_pos_of(
+            doc.syntax(),
+            text,
+            selection,
+            surround_pair,
+            count,
+        ) {
+            Ok(c) => c,
+            Err(err) => {
+                cx.editor.set_error(err.to_string());
+                return;
+            }
+        };
         change_pos.sort_unstable(); // the changeset has to be sorted to allow nested surrounds
         let transaction =
             Transaction::change(doc.text(), change_pos.into_iter().map(|p| (p, p + 1, None)));
@@ -6518,6 +7361,24 @@ fn suspend(_cx: &mut Context) {
     }
 }
 
+fn toggle_terminal(cx: &mut Context) {
+    cx.callback.push(Box::new(
+        |compositor: &mut Compositor, cx: &mut compositor::Context| {
+            let Some(editor_view) = compositor.find::<ui::EditorView>() else {
+                return;
+            };
+            // The shell keeps running while the panel is hidden
+            if editor_view.terminal_panel.take().is_some() {
+                return;
+            }
+            if let Err(err) = editor_view.open_terminal(cx.editor) {
+                cx.editor
+                    .set_error(format!("Failed to start the terminal: {err}"));
+            }
+        },
+    ));
+}
+
 fn add_newline_above(cx: &mut Context) {
     add_newline_impl(cx, Open::Above);
 }
@@ -6576,6 +7437,7 @@ fn increment_impl(cx: &mut Context, increment_direction: IncrementDirection) {
     let mut amount = sign * cx.count() as i64;
     // If the register is `#` then increase or decrease the `amount` by 1 per element
     let increase_by = if cx.register == Some('#') { sign } else { 0 };
+    let words = cx.editor.config().increment_words.clone();
 
     let (view, doc) = current!(cx.editor);
     let selection = doc.selection(view.id);
@@ -6588,9 +7450,14 @@ fn increment_impl(cx: &mut Context, increment_direction: IncrementDirection) {
     for range in selection {
         let selected_text: Cow<str> = range.fragment(text);
         let new_from = ((range.from() as i128) + cumulative_length_diff) as usize;
-        let incremented = [increment::integer, increment::date_time]
-            .iter()
-            .find_map(|incrementor| incrementor(selected_text.as_ref(), amount));
+        let incremented = [
+            increment::integer,
+            increment::date_time,
+            increment::hex_color,
+        ]
+        .iter()
+        .find_map(|incrementor| incrementor(selected_text.as_ref(), amount))
+        .or_else(|| increment::word(selected_text.as_ref(), amount, &words));
 
         amount += increase_by;
 
@@ -6603,8 +7470,10 @@ fn increment_impl(cx: &mut Context, increment_direction: IncrementDirection) {
                 new_selection_ranges.push(new_range);
             }
             Some(new_text) => {
-                let new_range = Range::new(new_from, new_from + new_text.len());
-                cumulative_length_diff += new_text.len() as i128 - selected_text.len() as i128;
+                // Words from the configured lists are not necessarily ASCII
+                let new_len = new_text.chars().count();
+                let new_range = Range::new(new_from, new_from + new_len);
+                cumulative_length_diff += new_len as i128 - selected_text.chars().count() as i128;
                 new_selection_ranges.push(new_range);
                 changes.push((range.from(), range.to(), Some(new_text.into())));
             }
@@ -6746,8 +7615,28 @@ fn extend_to_word(cx: &mut Context) {
     jump_to_word(cx, Movement::Extend)
 }
 
-fn jump_to_label(cx: &mut Context, labels: Vec<Range>, behaviour: Movement) {
-    let doc = doc!(cx.editor);
+/// A location which can be jumped to with a jump label.
+#[derive(Debug, Clone, Copy)]
+enum JumpTarget {
+    /// A range in a view.
+    Range(ViewId, Range),
+    /// A buffer on the bufferline, jumping to it opens it in the focused view.
+    Buffer(DocumentId),
+}
+
+fn remove_jump_labels(editor: &mut Editor) {
+    let views: Vec<_> = editor
+        .tree
+        .views()
+        .map(|(view, _)| (view.id, view.doc))
+        .collect();
+    for (view, doc) in views {
+        doc_mut!(editor, &doc).remove_jump_labels(view);
+    }
+    editor.bufferline_jump_labels.clear();
+}
+
+fn jump_to_label(cx: &mut Context, labels: Vec<JumpTarget>, behaviour: Movement) {
     let alphabet = &cx.editor.config().jump_label_alphabet;
     if labels.is_empty() {
         return;
@@ -6758,30 +7647,40 @@ fn jump_to_label(cx: &mut Context, labels: Vec<Range>, behaviour: Movement) {
         res
     };
 
-    // Add label for each jump candidate to the View as virtual text.
-    let text = doc.text().slice(..);
-    let mut overlays: Vec<_> = labels
-        .iter()
-        .enumerate()
-        .flat_map(|(i, range)| {
-            [
-                Overlay::new(range.from(), alphabet_char(i / alphabet.len())),
-                Overlay::new(
-                    graphemes::next_grapheme_boundary(text, range.from()),
-                    alphabet_char(i % alphabet.len()),
-                ),
-            ]
-        })
-        .collect();
-    overlays.sort_unstable_by_key(|overlay| overlay.char_idx);
-    let (view, doc) = current!(cx.editor);
-    doc.set_jump_labels(view.id, overlays);
+    // Add label for each jump candidate to its View as virtual text, or to the bufferline.
+    let mut overlays: HashMap<ViewId, Vec<Overlay>> = HashMap::new();
+    for (i, target) in labels.iter().enumerate() {
+        match *target {
+            JumpTarget::Range(view, range) => {
+                let doc = doc!(cx.editor, &cx.editor.tree.get(view).doc);
+                let text = doc.text().slice(..);
+                overlays.entry(view).or_default().extend([
+                    Overlay::new(range.from(), alphabet_char(i / alphabet.len())),
+                    Overlay::new(
+                        graphemes::next_grapheme_boundary(text, range.from()),
+                        alphabet_char(i % alphabet.len()),
+                    ),
+                ]);
+            }
+            JumpTarget::Buffer(doc) => {
+                let label = [alphabet[i / alphabet.len()], alphabet[i % alphabet.len()]];
+                cx.editor
+                    .bufferline_jump_labels
+                    .push((doc, label.iter().collect()));
+            }
+        }
+    }
+    for (view, mut overlays) in overlays {
+        overlays.sort_unstable_by_key(|overlay| overlay.char_idx);
+        let doc = cx.editor.tree.get(view).doc;
+        doc_mut!(cx.editor, &doc).set_jump_labels(view, overlays);
+    }
 
     // Accept two characters matching a visible label. Jump to the candidate
     // for that label if it exists.
+    let (view, doc) = current_ref!(cx.editor);
     let primary_selection = doc.selection(view.id).primary();
-    let view = view.id;
-    let doc = doc.id();
+    let focus = view.id;
     cx.on_next_key(move |cx, event| {
         let alphabet = &cx.editor.config().jump_label_alphabet;
         let Some(i) = event
@@ -6789,17 +7688,17 @@ fn jump_to_label(cx: &mut Context, labels: Vec<Range>, behaviour: Movement) {
             .filter(|_| event.modifiers.is_empty())
             .and_then(|ch| alphabet.iter().position(|&it| it == ch))
         else {
-            doc_mut!(cx.editor, &doc).remove_jump_labels(view);
+            remove_jump_labels(cx.editor);
             return;
         };
         let outer = i * alphabet.len();
         // Bail if the given character cannot be a jump label.
         if outer > labels.len() {
-            doc_mut!(cx.editor, &doc).remove_jump_labels(view);
+            remove_jump_labels(cx.editor);
             return;
         }
         cx.on_next_key(move |cx, event| {
-            doc_mut!(cx.editor, &doc).remove_jump_labels(view);
+            remove_jump_labels(cx.editor);
             let alphabet = &cx.editor.config().jump_label_alphabet;
             let Some(inner) = event
                 .char()
@@ -6808,45 +7707,105 @@ fn jump_to_label(cx: &mut Context, labels: Vec<Range>, behaviour: Movement) {
             else {
                 return;
             };
-            if let Some(mut range) = labels.get(outer + inner).copied() {
-                range = if behaviour == Movement::Extend {
-                    let anchor = if range.anchor < range.head {
-                        let from = primary_selection.from();
-                        if range.anchor < from {
-                            range.anchor
+            match labels.get(outer + inner).copied() {
+                Some(JumpTarget::Range(view, mut range)) if cx.editor.tree.contains(view) => {
+                    range = if behaviour == Movement::Extend && view == focus {
+                        let anchor = if range.anchor < range.head {
+                            let from = primary_selection.from();
+                            if range.anchor < from {
+                                range.anchor
+                            } else {
+                                from
+                            }
                         } else {
-                            from
-                        }
+                            let to = primary_selection.to();
+                            if range.anchor > to {
+                                range.anchor
+                            } else {
+                                to
+                            }
+                        };
+                        Range::new(anchor, range.head)
                     } else {
-                        let to = primary_selection.to();
-                        if range.anchor > to {
-                            range.anchor
-                        } else {
-                            to
-                        }
+                        range.with_direction(Direction::Forward)
                     };
-                    Range::new(anchor, range.head)
-                } else {
-                    range.with_direction(Direction::Forward)
-                };
-                save_selection(cx);
-                doc_mut!(cx.editor, &doc).set_selection(view, range.into());
+                    if view != cx.editor.tree.focus {
+                        cx.editor.focus(view);
+                    }
+                    save_selection(cx);
+                    let doc = cx.editor.tree.get(view).doc;
+                    doc_mut!(cx.editor, &doc).set_selection(view, range.into());
+                }
+                Some(JumpTarget::Buffer(doc)) if cx.editor.documents.contains_key(&doc) => {
+                    cx.editor.switch(doc, Action::Replace);
+                }
+                _ => (),
             }
         });
     });
 }
 
 fn jump_to_word(cx: &mut Context, behaviour: Movement) {
-    // Calculate the jump candidates: ranges for any visible words with two or
-    // more characters.
     let alphabet = &cx.editor.config().jump_label_alphabet;
     if alphabet.is_empty() {
         return;
     }
 
+    let (view, doc) = current_ref!(cx.editor);
+    let words = jump_word_candidates(view, doc, alphabet.len() * alphabet.len())
+        .into_iter()
+        .map(|range| JumpTarget::Range(view.id, range))
+        .collect();
+    jump_to_label(cx, words, behaviour)
+}
+
+fn goto_word_all_views(cx: &mut Context) {
+    let alphabet = &cx.editor.config().jump_label_alphabet;
+    if alphabet.is_empty() {
+        return;
+    }
     let jump_label_limit = alphabet.len() * alphabet.len();
+
+    // Label the focused view first so that its words get the shortest distance to type,
+    // then the other views in layout order and finally the buffers on the bufferline.
+    let focus = cx.editor.tree.focus;
+    let mut views: Vec<_> = cx.editor.tree.views().map(|(view, _)| view).collect();
+    views.sort_by_key(|view| view.id != focus);
+    let mut targets = Vec::with_capacity(jump_label_limit);
+    for view in views {
+        let doc = doc!(cx.editor, &view.doc);
+        let limit = jump_label_limit - targets.len();
+        targets.extend(
+            jump_word_candidates(view, doc, limit)
+                .into_iter()
+                .map(|range| JumpTarget::Range(view.id, range)),
+        );
+    }
+
+    let config = cx.editor.config();
+    let use_bufferline = match config.bufferline {
+        BufferLine::Always => true,
+        BufferLine::Multiple => cx.editor.documents.len() > 1,
+        BufferLine::Never => false,
+    };
+    if use_bufferline {
+        let buffers = cx
+            .editor
+            .documents()
+            .map(|doc| JumpTarget::Buffer(doc.id()))
+            .take(jump_label_limit - targets.len());
+        targets.extend(buffers);
+    }
+    jump_to_label(cx, targets, Movement::Move)
+}
+
+/// Calculates the jump candidates of a view: ranges for any visible words with two or
+/// more characters, closest to the cursor first.
+fn jump_word_candidates(view: &View, doc: &Document, jump_label_limit: usize) -> Vec<Range> {
     let mut words = Vec::with_capacity(jump_label_limit);
-    let (view, doc) = current_ref!(cx.editor);
+    if jump_label_limit == 0 {
+        return words;
+    }
     let text = doc.text().slice(..);
 
     // This is not necessarily exact if there is virtual text like soft wrap.
@@ -6931,7 +7890,7 @@ fn jump_to_word(cx: &mut Context, behaviour: Movement) {
             break;
         }
     }
-    jump_to_label(cx, words, behaviour)
+    words
 }
 
 fn lsp_or_syntax_symbol_picker(cx: &mut Context) {
@@ -6964,3 +7923,126 @@ fn lsp_or_syntax_workspace_symbol_picker(cx: &mut Context) {
         syntax_workspace_symbol_picker(cx);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expand(
+        text: &str,
+        selection: Selection,
+        abbreviations: &[(&str, &str)],
+        language: Option<&[(&str, &str)]>,
+    ) -> String {
+        let map = |abbrevs: &[(&str, &str)]| -> HashMap<String, String> {
+            abbrevs
+                .iter()
+                .map(|(abbrev, expansion)| (abbrev.to_string(), expansion.to_string()))
+                .collect()
+        };
+        let language = language.map(map);
+        let mut text = Rope::from(text);
+        let changes = insert::abbreviation_changes(
+            text.slice(..),
+            &selection,
+            &map(abbreviations),
+            language.as_ref(),
+        );
+        Transaction::change(&text, changes.into_iter()).apply(&mut text);
+        text.to_string()
+    }
+
+    #[test]
+    fn expand_abbreviations_multiple_cursors() {
+        // cursors after "teh" on the first and last line and after "othe" on the second
+        let selection = Selection::new(
+            vec![Range::point(3), Range::point(8), Range::point(13)].into(),
+            0,
+        );
+        assert_eq!(
+            expand("teh\nother\nteh\n", selection, &[("teh", "the")], None),
+            "the\nother\nthe\n"
+        );
+    }
+
+    #[test]
+    fn expand_abbreviations_language_override() {
+        let abbreviations = [("fn", "function"), ("teh", "the")];
+        let language = [("fn", "fun")];
+        assert_eq!(
+            expand("fn", Selection::point(2), &abbreviations, Some(&language)),
+            "fun"
+        );
+        assert_eq!(
+            expand("teh", Selection::point(3), &abbreviations, Some(&language)),
+            "the"
+        );
+    }
+
+    #[test]
+    fn expand_abbreviations_not_on_prefix() {
+        let abbreviations = [("teh", "the")];
+        assert_eq!(
+            expand("tehx", Selection::point(4), &abbreviations, None),
+            "tehx"
+        );
+        assert_eq!(
+            expand("te", Selection::point(2), &abbreviations, None),
+            "te"
+        );
+        assert_eq!(
+            expand("xteh", Selection::point(4), &abbreviations, None),
+            "xteh"
+        );
+    }
+
+    fn diff(text: &str, changes: Vec<helix_core::Change>, max_lines: usize) -> (usize, String) {
+        let text = Rope::from(text);
+        let transaction = Transaction::change(&text, changes.into_iter());
+        format_diff(&text, &transaction, max_lines)
+    }
+
+    #[test]
+    fn format_diff_insertion() {
+        assert_eq!(
+            diff("a\nb\n", vec![(2, 2, Some("x\n".into()))], 30),
+            (1, "@@ line 2 @@\n-b\n+x\n+b".to_string())
+        );
+    }
+
+    #[test]
+    fn format_diff_deletion() {
+        assert_eq!(
+            diff("a\nb\nc\n", vec![(2, 4, None)], 30),
+            (1, "@@ line 2 @@\n-b".to_string())
+        );
+    }
+
+    #[test]
+    fn format_diff_changes_on_one_line() {
+        assert_eq!(
+            diff(
+                "let  a=1;\n",
+                vec![(3, 5, Some(" ".into())), (6, 7, Some(" = ".into()))],
+                30
+            ),
+            (1, "@@ line 1 @@\n-let  a=1;\n+let a = 1;".to_string())
+        );
+    }
+
+    #[test]
+    fn format_diff_no_change() {
+        assert_eq!(diff("a\nb\n", Vec::new(), 30), (0, String::new()));
+    }
+
+    #[test]
+    fn format_diff_truncated() {
+        let (changed_lines, diff) = diff(
+            "a\nb\nc\n",
+            vec![(0, 1, Some("A".into())), (4, 5, Some("C".into()))],
+            3,
+        );
+        assert_eq!(changed_lines, 2);
+        assert_eq!(diff, "@@ line 1 @@\n-a\n+A\n… 3 more lines");
+    }
+}
@@ -0,0 +1,127 @@
+//! Typable commands registered at runtime, so that embedders of the editor can add their own
+//! commands (`:deploy`, `:run-tests`, ...) without patching helix-term.
+//!
+//! A registered command runs like a builtin typable command: typed into the command prompt,
+//! bound in the keymap as `":deploy"` or `"deploy"` and listed in the command palette. Builtin commands take
+//! precedence over registered commands of the same name. Commands bound in the keymap are
+//! resolved when the config is loaded, register them before that.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::anyhow;
+use helix_core::command_line::{Args, Signature};
+use helix_view::expansion;
+use once_cell::sync::Lazy;
+
+use crate::{compositor, ui::PromptEvent};
+
+type CommandFn = Box<dyn Fn(&mut compositor::Context, Args) -> anyhow::Result<()> + Send + Sync>;
+
+pub struct CustomCommand {
+    name: String,
+    doc: String,
+    signature: Signature,
+    fun: CommandFn,
+}
+
+impl CustomCommand {
+    /// A command calling `fun` when it is run. It takes no arguments, see
+    /// [`CustomCommand::with_signature`].
+    pub fn new(
+        name: impl Into<String>,
+        doc: impl Into<String>,
+        fun: impl Fn(&mut compositor::Context, Args) -> anyhow::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            doc: doc.into(),
+            signature: Signature {
+                positionals: (0, Some(0)),
+                ..Signature::DEFAULT
+            },
+            fun: Box::new(fun),
+        }
+    }
+
+    /// Replaces the arguments and flags the command takes.
+    pub fn with_signature(mut self, signature: Signature) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn doc(&self) -> &str {
+        &self.doc
+    }
+
+    /// Parses `args`, expanding the variables in them, and runs the command. The command only
+    /// runs when the input is validated, not while it is typed.
+    pub fn execute(
+        &self,
+        cx: &mut compositor::Context,
+        args: &str,
+        event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        if event != PromptEvent::Validate {
+            return Ok(());
+        }
+        let args = Args::parse(args, self.signature, true, |token| {
+            expansion::expand(cx.editor, token).map_err(|err| err.into())
+        })
+        .map_err(|err| anyhow!("'{}': {err}", self.name))?;
+        (self.fun)(cx, args).map_err(|err| anyhow!("'{}': {err}", self.name))
+    }
+}
+
+static COMMANDS: Lazy<RwLock<BTreeMap<String, Arc<CustomCommand>>>> = Lazy::new(Default::default);
+
+/// Registers `command`, replacing a command registered under the same name.
+pub fn register_command(command: CustomCommand) {
+    COMMANDS
+        .write()
+        .unwrap()
+        .insert(command.name.clone(), Arc::new(command));
+}
+
+/// Removes the command registered under `name`.
+pub fn unregister_command(name: &str) {
+    COMMANDS.write().unwrap().remove(name);
+}
+
+/// The command registered under `name`.
+pub fn custom_command(name: &str) -> Option<Arc<CustomCommand>> {
+    COMMANDS.read().unwrap().get(name).cloned()
+}
+
+/// The registered commands, sorted by name.
+pub fn custom_commands() -> Vec<Arc<CustomCommand>> {
+    COMMANDS.read().unwrap().values().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::MappableCommand;
+
+    #[test]
+    fn registered_commands_can_be_bound() {
+        register_command(CustomCommand::new("test-deploy", "Deploy", |_, _| Ok(())));
+        for binding in [":test-deploy", "test-deploy"] {
+            let command: MappableCommand = binding.parse().unwrap();
+            assert!(matches!(
+                command,
+                MappableCommand::Typable { ref name, ref doc, .. }
+                    if name == "test-deploy" && doc == "Deploy"
+            ));
+        }
+
+        unregister_command("test-deploy");
+        assert!(":test-deploy".parse::<MappableCommand>().is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use futures_util::{stream::FuturesOrdered, FutureExt};
 use helix_lsp::{
     block_on,
+    jsonrpc::ErrorCode,
     lsp::{
         self, CodeAction, CodeActionOrCommand, CodeActionTriggerKind, DiagnosticSeverity,
         NumberOrString,
@@ -1456,3 +1457,91 @@ fn compute_inlay_hints_for_view(
 
     Some(callback)
 }
+
+/// Parses the arguments of a workspace command: any number of JSON values separated by
+/// whitespace. Returns `None` if there are none.
+pub(crate) fn parse_command_arguments(input: &str) -> anyhow::Result<Option<Vec<Value>>> {
+    let arguments = serde_json::Deserializer::from_str(input)
+        .into_iter()
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|err| anyhow::anyhow!("failed to parse arguments: {err}"))?;
+    Ok(Some(arguments).filter(|arguments| !arguments.is_empty()))
+}
+
+/// Executes a workspace command of a language server, see `:lsp-workspace-command`.
+///
+/// A result is shown in a popup. Servers can't advertise the arguments a command takes, so
+/// if the server rejects the arguments a prompt asks for them and the command is retried.
+pub fn execute_workspace_command(
+    jobs: &mut crate::job::Jobs,
+    editor: &mut Editor,
+    command: lsp::Command,
+    language_server_id: LanguageServerId,
+) {
+    let Some(future) = editor
+        .language_server_by_id(language_server_id)
+        .and_then(|server| server.command(command.clone()))
+    else {
+        editor.set_error("Language server does not support executing commands");
+        return;
+    };
+
+    jobs.callback(async move {
+        let result = future.await;
+        let call = move |editor: &mut Editor, compositor: &mut Compositor| match result {
+            Ok(None | Some(Value::Null)) => {
+                editor.set_status(format!("`{}` finished", command.command));
+            }
+            Ok(Some(result)) => {
+                let contents = format!(
+                    "```json\n{}\n```",
+                    serde_json::to_string_pretty(&result).unwrap_or_default()
+                );
+                let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                let popup = Popup::new("lsp-command-result", contents).auto_close(true);
+                compositor.replace_or_push("lsp-command-result", popup);
+            }
+            Err(helix_lsp::Error::Rpc(err)) if err.code == ErrorCode::InvalidParams => {
+                editor.set_error(format!(
+                    "`{}` rejected its arguments: {}",
+                    command.command, err.message
+                ));
+                compositor.push(Box::new(command_arguments_prompt(
+                    command,
+                    language_server_id,
+                )));
+            }
+            Err(err) => editor.set_error(format!("`{}` failed: {err}", command.command)),
+        };
+        Ok(Callback::EditorCompositor(Box::new(call)))
+    });
+}
+
+fn command_arguments_prompt(
+    command: lsp::Command,
+    language_server_id: LanguageServerId,
+) -> ui::Prompt {
+    let prompt = format!("{} arguments (JSON): ", command.command);
+    ui::Prompt::new(
+        prompt.into(),
+        None,
+        ui::completers::none,
+        move |cx: &mut compositor::Context, input: &str, event: PromptEvent| {
+            if event != PromptEvent::Validate {
+                return;
+            }
+            let arguments = match parse_command_arguments(input) {
+                Ok(arguments) => arguments,
+                Err(err) => {
+                    cx.editor.set_error(err.to_string());
+                    return;
+                }
+            };
+            let command = lsp::Command {
+                arguments,
+                ..command.clone()
+            };
+            execute_workspace_command(cx.jobs, cx.editor, command, language_server_id);
+        },
+    )
+}
@@ -0,0 +1,166 @@
+//! Sends code to a REPL, an interpreter configured per language with `repl` in languages.toml.
+//! Each language has one REPL process, started the first time code is sent to it. Its output
+//! is appended to a scratch buffer opened in a split.
+
+use std::process::Stdio;
+
+use anyhow::Context as _;
+use helix_core::{
+    syntax::config::ReplConfiguration,
+    textobject::{self, TextObject},
+};
+use helix_event::{cancelable_future, TaskController, TaskHandle};
+use helix_view::{
+    editor::{Action, Repl},
+    DocumentId, Editor,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    process::{Child, ChildStdin},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+};
+
+use super::Context;
+use crate::{handlers::fifo::take_text, job};
+
+const BUF_SIZE: usize = 8192;
+
+pub fn send_to_repl(cx: &mut Context) {
+    let (view, doc) = current_ref!(cx.editor);
+    let Some(language) = doc.language_config() else {
+        cx.editor
+            .set_error("The buffer has no language to start a REPL for");
+        return;
+    };
+    let Some(config) = language.repl.clone() else {
+        let message = format!("No REPL is configured for {}", language.language_id);
+        cx.editor.set_error(message);
+        return;
+    };
+    let name = language.language_id.clone();
+
+    // A selection of a single character sends the paragraph around it
+    let text = doc.text().slice(..);
+    let mut code = String::new();
+    for range in doc.selection(view.id) {
+        let range = if range.len() <= 1 {
+            textobject::textobject_paragraph(text, *range, TextObject::Around, 1)
+        } else {
+            *range
+        };
+        code.push_str(&range.fragment(text));
+        if !code.ends_with('\n') {
+            code.push('\n');
+        }
+    }
+
+    if !cx.editor.repls.contains_key(&name) {
+        if let Err(err) = start_repl(cx.editor, &name, &config) {
+            cx.editor.set_error(format!("{err:#}"));
+            return;
+        }
+    }
+    if cx.editor.repls[&name].input.send(code).is_err() {
+        cx.editor.repls.remove(&name);
+        cx.editor
+            .set_error(format!("The {name} REPL is no longer running"));
+    }
+}
+
+/// Starts the REPL of the language `name` and opens its output buffer in a split, keeping the
+/// focus on the current view.
+fn start_repl(editor: &mut Editor, name: &str, config: &ReplConfiguration) -> anyhow::Result<()> {
+    let mut process = tokio::process::Command::new(&config.command)
+        .args(&config.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start the {name} REPL `{}`", config.command))?;
+    let (Some(stdin), Some(stdout), Some(stderr)) = (
+        process.stdin.take(),
+        process.stdout.take(),
+        process.stderr.take(),
+    ) else {
+        anyhow::bail!("Failed to connect to the {name} REPL");
+    };
+
+    let focus = editor.tree.focus;
+    let output = editor.new_file(Action::VerticalSplit);
+    editor.focus(focus);
+
+    let mut controller = TaskController::new();
+    let cancel = controller.restart();
+    let (input, input_rx) = unbounded_channel();
+    tokio::spawn(write_input(stdin, input_rx, cancel.clone()));
+    tokio::spawn(read_output(stdout, output, cancel.clone()));
+    tokio::spawn(read_output(stderr, output, cancel.clone()));
+    tokio::spawn(wait_for_exit(process, name.to_string(), cancel));
+
+    editor.repls.insert(
+        name.to_string(),
+        Repl {
+            input,
+            output,
+            controller,
+        },
+    );
+    Ok(())
+}
+
+async fn write_input(
+    mut stdin: ChildStdin,
+    mut input: UnboundedReceiver<String>,
+    cancel: TaskHandle,
+) {
+    while let Some(Some(code)) = cancelable_future(input.recv(), &cancel).await {
+        let written = async {
+            stdin.write_all(code.as_bytes()).await?;
+            stdin.flush().await
+        };
+        if let Err(err) = written.await {
+            log::error!("failed to write to REPL: {err}");
+            return;
+        }
+    }
+}
+
+async fn read_output(mut output: impl AsyncRead + Unpin, doc_id: DocumentId, cancel: TaskHandle) {
+    let mut buf = vec![0; BUF_SIZE];
+    let mut pending = Vec::new();
+    loop {
+        let read = match cancelable_future(output.read(&mut buf), &cancel).await {
+            None | Some(Ok(0)) => return,
+            Some(Ok(read)) => read,
+            Some(Err(err)) => {
+                log::error!("failed to read REPL output: {err}");
+                return;
+            }
+        };
+        pending.extend_from_slice(&buf[..read]);
+        let Some(text) = take_text(&mut pending) else {
+            continue;
+        };
+        job::dispatch(move |editor, _compositor| {
+            editor.append_to_document(doc_id, &text);
+        })
+        .await;
+    }
+}
+
+/// Forgets the REPL when its process exits on its own. When the REPL is stopped, dropping the
+/// process kills it.
+async fn wait_for_exit(mut process: Child, name: String, cancel: TaskHandle) {
+    let Some(status) = cancelable_future(process.wait(), &cancel).await else {
+        return;
+    };
+    job::dispatch(move |editor, _compositor| {
+        editor.repls.remove(&name);
+        match status {
+            Ok(status) => editor.set_status(format!("The {name} REPL exited ({status})")),
+            Err(err) => editor.set_error(format!("The {name} REPL failed: {err}")),
+        }
+    })
+    .await;
+}
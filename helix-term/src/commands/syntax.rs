@@ -10,8 +10,8 @@
 use grep_regex::RegexMatcherBuilder;
 use grep_searcher::{sinks, BinaryDetection, SearcherBuilder};
 use helix_core::{
-    syntax::{Loader, QueryIterEvent},
-    Rope, RopeSlice, Selection, Syntax, Uri,
+    syntax::{Loader, QueryIterEvent, StructuralQuery},
+    Language, Range, Rope, RopeSlice, Selection, SmallVec, Syntax, Uri,
 };
 use helix_stdx::{
     path,
@@ -20,12 +20,13 @@
 use helix_view::{
     align_view,
     document::{from_reader, SCRATCH_BUFFER_NAME},
+    editor::Action,
     Align, Document, DocumentId, Editor,
 };
 use ignore::{DirEntry, WalkBuilder, WalkState};
 
 use crate::{
-    filter_picker_entry,
+    compositor, filter_picker_entry,
     ui::{
         overlay::overlaid,
         picker::{Injector, PathOrId},
@@ -150,6 +151,86 @@ fn tags_iter<'a>(
     })
 }
 
+/// Returns the root of the workspace the focused document is within and a builder walking its
+/// files according to the file picker config.
+fn workspace_walker(editor: &Editor) -> (PathBuf, WalkBuilder) {
+    // Search from the workspace that the currently focused document is within. This behaves like global
+    // search most of the time but helps when you have two projects open in splits.
+    let search_root = if let Some(path) = doc!(editor).path() {
+        helix_loader::find_workspace_in(path).0
+    } else {
+        helix_loader::find_workspace().0
+    };
+
+    let absolute_root = search_root
+        .canonicalize()
+        .unwrap_or_else(|_| search_root.clone());
+
+    let config = editor.config();
+    let dedup_symlinks = config.file_picker.deduplicate_links;
+
+    let mut walk_builder = WalkBuilder::new(&search_root);
+    walk_builder
+        .hidden(config.file_picker.hidden)
+        .parents(config.file_picker.parents)
+        .ignore(config.file_picker.ignore)
+        .follow_links(config.file_picker.follow_symlinks)
+        .git_ignore(config.file_picker.git_ignore)
+        .git_global(config.file_picker.git_global)
+        .git_exclude(config.file_picker.git_exclude)
+        .max_depth(config.file_picker.max_depth)
+        .threads(config.resources.grep_threads)
+        .filter_entry(move |entry| filter_picker_entry(entry, &absolute_root, dedup_symlinks))
+        .add_custom_ignore_filename(helix_loader::config_dir().join("ignore"))
+        .add_custom_ignore_filename(".helix/ignore");
+
+    (search_root, walk_builder)
+}
+
+/// Opens the document of a picked location and selects `start..end` in it.
+fn jump_to_location(
+    cx: &mut compositor::Context,
+    doc: &UriOrDocumentId,
+    start: usize,
+    end: usize,
+    action: Action,
+) {
+    let doc_id = match doc {
+        UriOrDocumentId::Id(id) => {
+            cx.editor.switch(*id, action);
+            *id
+        }
+        UriOrDocumentId::Uri(uri) => {
+            let Some(path) = uri.as_path() else {
+                cx.editor
+                    .set_error(format!("Failed to open '{uri}': not a file"));
+                return;
+            };
+            match cx.editor.open(path, action) {
+                Ok(id) => id,
+                Err(e) => {
+                    cx.editor
+                        .set_error(format!("Failed to open file '{uri:?}': {e}"));
+                    return;
+                }
+            }
+        }
+    };
+    let doc = doc_mut!(cx.editor, &doc_id);
+    let view = view_mut!(cx.editor);
+    let len_chars = doc.text().len_chars();
+    if start >= len_chars || end > len_chars {
+        cx.editor.set_error(
+            "The location you jumped to does not exist anymore because the file has changed.",
+        );
+        return;
+    }
+    doc.set_selection(view.id, Selection::single(start, end));
+    if action.align_view(view, doc.id()) {
+        align_view(doc, view, Align::Center)
+    }
+}
+
 pub fn syntax_symbol_picker(cx: &mut Context) {
     let doc = doc!(cx.editor);
     let Some(syntax) = doc.syntax() else {
@@ -205,34 +286,8 @@ struct SearchState {
     let mut searcher_builder = SearcherBuilder::new();
     searcher_builder.binary_detection(BinaryDetection::quit(b'\x00'));
 
-    // Search from the workspace that the currently focused document is within. This behaves like global
-    // search most of the time but helps when you have two projects open in splits.
-    let search_root = if let Some(path) = doc!(cx.editor).path() {
-        helix_loader::find_workspace_in(path).0
-    } else {
-        helix_loader::find_workspace().0
-    };
-
-    let absolute_root = search_root
-        .canonicalize()
-        .unwrap_or_else(|_| search_root.clone());
-
+    let (search_root, walk_builder) = workspace_walker(cx.editor);
     let config = cx.editor.config();
-    let dedup_symlinks = config.file_picker.deduplicate_links;
-
-    let mut walk_builder = WalkBuilder::new(&search_root);
-    walk_builder
-        .hidden(config.file_picker.hidden)
-        .parents(config.file_picker.parents)
-        .ignore(config.file_picker.ignore)
-        .follow_links(config.file_picker.follow_symlinks)
-        .git_ignore(config.file_picker.git_ignore)
-        .git_global(config.file_picker.git_global)
-        .git_exclude(config.file_picker.git_exclude)
-        .max_depth(config.file_picker.max_depth)
-        .filter_entry(move |entry| filter_picker_entry(entry, &absolute_root, dedup_symlinks))
-        .add_custom_ignore_filename(helix_loader::config_dir().join("ignore"))
-        .add_custom_ignore_filename(".helix/ignore");
 
     let mut regex_matcher_builder = RegexMatcherBuilder::new();
     regex_matcher_builder.case_smart(config.search.smart_case);
@@ -397,37 +452,11 @@ struct SearchState {
         1, // name
         [],
         state,
-        move |cx, tag, action| {
-            let doc_id = match &tag.doc {
-                UriOrDocumentId::Id(id) => *id,
-                UriOrDocumentId::Uri(uri) => match cx.editor.open(uri.as_path().expect(""), action) {
-                    Ok(id) => id,
-                    Err(e) => {
-                        cx.editor
-                            .set_error(format!("Failed to open file '{uri:?}': {e}"));
-                        return;
-                    }
-                }
-            };
-            let doc = doc_mut!(cx.editor, &doc_id);
-            let view = view_mut!(cx.editor);
-            let len_chars = doc.text().len_chars();
-            if tag.start >= len_chars || tag.end > len_chars {
-                cx.editor.set_error("The location you jumped to does not exist anymore because the file has changed.");
-                return;
-            }
-            doc.set_selection(view.id, Selection::single(tag.start, tag.end));
-            if action.align_view(view, doc.id()) {
-                align_view(doc, view, Align::Center)
-            }
-        },
+        move |cx, tag, action| jump_to_location(cx, &tag.doc, tag.start, tag.end, action),
     )
     .with_dynamic_query(get_tags, Some(275))
     .with_preview(move |_editor, tag| {
-        Some((
-            tag.doc.path_or_id()?,
-            Some((tag.start_line, tag.end_line)),
-        ))
+        Some((tag.doc.path_or_id()?, Some((tag.start_line, tag.end_line))))
     })
     .with_history_register(Some(reg))
     .truncate_start(false);
@@ -439,10 +468,250 @@ fn syntax_for_path(path: &Path, loader: &Loader) -> Option<(Rope, Syntax)> {
     let mut file = std::fs::File::open(path).ok()?;
     let (rope, _encoding, _has_bom) = from_reader(&mut file, None).ok()?;
     let text = rope.slice(..);
-    let language = loader
-        .language_for_filename(path)
-        .or_else(|| loader.language_for_shebang(text))?;
+    let language = loader.language_for_file(Some(path), text)?;
     Syntax::new(text, language, loader)
         .ok()
         .map(|syntax| (rope, syntax))
 }
+
+#[derive(Debug)]
+struct StructuralMatch {
+    text: String,
+    start: usize,
+    end: usize,
+    start_line: usize,
+    end_line: usize,
+    doc: UriOrDocumentId,
+}
+
+/// The query of a structural search, compiled once for each language it runs on.
+struct StructuralQueries {
+    source: String,
+    /// The compiled queries by language, `None` if the grammar isn't available. Errors are kept
+    /// as messages so that they can be reported for each document.
+    compiled: DashMap<Language, Option<Result<Arc<StructuralQuery>, String>>>,
+}
+
+impl StructuralQueries {
+    fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            compiled: DashMap::default(),
+        }
+    }
+
+    /// The query compiled for `language`, `None` if its grammar isn't available.
+    fn get(
+        &self,
+        loader: &Loader,
+        language: Language,
+    ) -> Option<anyhow::Result<Arc<StructuralQuery>>> {
+        let compiled = self.compiled.entry(language).or_insert_with(|| {
+            let query = loader.structural_query(language, &self.source)?;
+            Some(query.map(Arc::new).map_err(|err| err.to_string()))
+        });
+        compiled
+            .clone()
+            .map(|query| query.map_err(anyhow::Error::msg))
+    }
+}
+
+fn structural_matches(
+    syntax: &Syntax,
+    loader: &Loader,
+    text: RopeSlice,
+    doc: UriOrDocumentId,
+    queries: &StructuralQueries,
+) -> anyhow::Result<Vec<StructuralMatch>> {
+    let Some(query) = queries.get(loader, syntax.root_language()) else {
+        return Ok(Vec::new());
+    };
+    let query = query?;
+    let root = syntax.tree().root_node();
+    let matches = query
+        .matches(&root, text, 0..u32::MAX)
+        .map(|range| {
+            let start = text.byte_to_char(range.start as usize);
+            let end = text.byte_to_char(range.end as usize);
+            let start_line = text.char_to_line(start);
+            let end_line = text.char_to_line(end);
+            let line = text.line(start_line);
+            StructuralMatch {
+                text: line.to_string().trim().to_string(),
+                start,
+                end,
+                start_line,
+                end_line,
+                doc: doc.clone(),
+            }
+        })
+        .collect();
+    Ok(matches)
+}
+
+/// Selects the nodes captured by a tree-sitter query within the current selections.
+pub(crate) fn select_structural_matches(editor: &mut Editor, source: &str) -> anyhow::Result<()> {
+    let (view, doc) = current!(editor);
+    let Some(syntax) = doc.syntax() else {
+        anyhow::bail!("Syntax tree is not available on this buffer");
+    };
+    let loader = editor.syn_loader.load();
+    let Some(query) = loader.structural_query(syntax.root_language(), source) else {
+        anyhow::bail!("Grammar is not available for this buffer's language");
+    };
+    let query = query?;
+
+    let text = doc.text().slice(..);
+    let root = syntax.tree().root_node();
+    let mut ranges = SmallVec::new();
+    for range in doc.selection(view.id) {
+        let from = text.char_to_byte(range.from()) as u32;
+        let to = text.char_to_byte(range.to()) as u32;
+        for mat in query.matches(&root, text, from..to) {
+            if mat.start >= from && mat.end <= to {
+                ranges.push(Range::new(
+                    text.byte_to_char(mat.start as usize),
+                    text.byte_to_char(mat.end as usize),
+                ));
+            }
+        }
+    }
+    if ranges.is_empty() {
+        anyhow::bail!("nothing selected");
+    }
+    doc.set_selection(view.id, Selection::new(ranges, 0));
+    Ok(())
+}
+
+pub fn structural_search(cx: &mut Context) {
+    struct SearchState {
+        walk_builder: WalkBuilder,
+        search_root: PathBuf,
+        /// A cache of files that have been parsed in prior searches.
+        syntax_cache: DashMap<PathBuf, Option<(Rope, Syntax)>>,
+    }
+
+    let (search_root, walk_builder) = workspace_walker(cx.editor);
+
+    let state = SearchState {
+        walk_builder,
+        search_root,
+        syntax_cache: DashMap::default(),
+    };
+    let columns = vec![
+        PickerColumn::new("path", |mat: &StructuralMatch, state: &SearchState| {
+            let path = match &mat.doc {
+                UriOrDocumentId::Uri(uri) => match uri.as_path() {
+                    Some(path) => path
+                        .strip_prefix(&state.search_root)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .into_owned(),
+                    None => uri.to_string(),
+                },
+                UriOrDocumentId::Id(_) => SCRATCH_BUFFER_NAME.to_string(),
+            };
+            format!("{path}:{}", mat.start_line + 1).into()
+        }),
+        PickerColumn::new("match", |mat: &StructuralMatch, _| mat.text.as_str().into())
+            .without_filtering(),
+    ];
+
+    let get_matches =
+        |query: &str, editor: &mut Editor, state: Arc<SearchState>, injector: &Injector<_, _>| {
+            if query.trim().is_empty() {
+                return async { Ok(()) }.boxed();
+            }
+            let loader = editor.syn_loader.load();
+            let focused_doc = doc!(editor).id();
+            let queries = StructuralQueries::new(query);
+            // Search open documents first so that unsaved edits are taken into account.
+            for doc in editor.documents() {
+                let Some(syntax) = doc.syntax() else { continue };
+                let uri_or_id = doc
+                    .uri()
+                    .map(UriOrDocumentId::Uri)
+                    .unwrap_or_else(|| UriOrDocumentId::Id(doc.id()));
+                let text = doc.text().slice(..);
+                match structural_matches(syntax, &loader, text, uri_or_id, &queries) {
+                    Ok(matches) => {
+                        for mat in matches {
+                            if injector.push(mat).is_err() {
+                                return async { Ok(()) }.boxed();
+                            }
+                        }
+                    }
+                    // The query is compiled per-language so only report errors for the language
+                    // of the focused document.
+                    Err(err) if doc.id() == focused_doc => {
+                        return async move { Err(err) }.boxed();
+                    }
+                    Err(_) => (),
+                }
+            }
+            if !state.search_root.exists() {
+                return async { Err(anyhow::anyhow!("Current working directory does not exist")) }
+                    .boxed();
+            }
+            editor.clear_status();
+            let injector = injector.clone();
+            let documents: HashSet<_> = editor
+                .documents()
+                .filter_map(Document::path)
+                .cloned()
+                .collect();
+            async move {
+                state.walk_builder.build_parallel().run(|| {
+                    let injector = injector.clone();
+                    let loader = loader.clone();
+                    let documents = &documents;
+                    let queries = &queries;
+                    let syntax_cache = &state.syntax_cache;
+                    Box::new(move |entry: Result<DirEntry, ignore::Error>| -> WalkState {
+                        let entry = match entry {
+                            Ok(entry) => entry,
+                            Err(_) => return WalkState::Continue,
+                        };
+                        let path = entry.path();
+                        if !path.is_file() || documents.contains(path) {
+                            return WalkState::Continue;
+                        }
+                        if !syntax_cache.contains_key(path) {
+                            syntax_cache.insert(path.to_path_buf(), syntax_for_path(path, &loader));
+                        }
+                        let entry = syntax_cache.get(path).unwrap();
+                        let Some((text, syntax)) = entry.value() else {
+                            return WalkState::Continue;
+                        };
+                        let uri = UriOrDocumentId::Uri(Uri::from(path::normalize(path)));
+                        let Ok(matches) =
+                            structural_matches(syntax, &loader, text.slice(..), uri, queries)
+                        else {
+                            return WalkState::Continue;
+                        };
+                        for mat in matches {
+                            if injector.push(mat).is_err() {
+                                return WalkState::Quit;
+                            }
+                        }
+                        WalkState::Continue
+                    })
+                });
+                Ok(())
+            }
+            .boxed()
+        };
+    let picker = Picker::new(
+        columns,
+        1, // match
+        [],
+        state,
+        move |cx, mat, action| jump_to_location(cx, &mat.doc, mat.start, mat.end, action),
+    )
+    .with_dynamic_query(get_matches, Some(275))
+    .with_preview(move |_editor, mat| {
+        Some((mat.doc.path_or_id()?, Some((mat.start_line, mat.end_line))))
+    })
+    .truncate_start(false);
+    cx.push_layer(Box::new(overlaid(picker)));
+}
@@ -2,7 +2,7 @@
 use std::io::BufReader;
 use std::ops::{self, Deref};
 
-use crate::job::Job;
+use crate::job::{Job, RunningJob};
 
 use super::*;
 
@@ -14,6 +14,7 @@
 use helix_view::document::{read_to_string, DEFAULT_LANGUAGE_NAME};
 use helix_view::editor::{CloseError, ConfigEvent};
 use helix_view::expansion;
+use helix_view::watch::WatchTask;
 use serde_json::Value;
 use ui::completers::{self, Completer};
 
@@ -36,6 +37,9 @@ pub struct CommandCompleter {
 
     // All remaining arguments will use this completion method, if set.
     var_args: Completer,
+
+    // Completes the argument after the positional ones based on the first argument, if set.
+    dependent_arg: Option<fn(&Editor, &str, &str) -> Vec<ui::prompt::Completion>>,
 }
 
 impl CommandCompleter {
@@ -43,6 +47,7 @@ const fn none() -> Self {
         Self {
             positional_args: &[],
             var_args: completers::none,
+            dependent_arg: None,
         }
     }
 
@@ -50,6 +55,7 @@ const fn positional(completers: &'static [Completer]) -> Self {
         Self {
             positional_args: completers,
             var_args: completers::none,
+            dependent_arg: None,
         }
     }
 
@@ -57,6 +63,18 @@ const fn all(completer: Completer) -> Self {
         Self {
             positional_args: &[],
             var_args: completer,
+            dependent_arg: None,
+        }
+    }
+
+    const fn dependent(
+        first: &'static [Completer],
+        completer: fn(&Editor, &str, &str) -> Vec<ui::prompt::Completion>,
+    ) -> Self {
+        Self {
+            positional_args: first,
+            var_args: completers::none,
+            dependent_arg: Some(completer),
         }
     }
 
@@ -114,7 +132,7 @@ fn quit(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow
     }
 
     // last view and we have unsaved changes
-    if cx.editor.tree.views().count() == 1 {
+    if cx.editor.tree.views().count() == 1 && cx.editor.tree.layout_count() == 1 {
         buffers_remaining_impl(cx.editor)?
     }
 
@@ -407,8 +425,10 @@ fn write_impl(
                 fmt,
                 Some((path.map(Into::into), options.force)),
             );
-
-            jobs.add(Job::with_callback(callback).wait_before_exiting());
+            let job = Job::with_callback(callback)
+                .wait_before_exiting()
+                .named(format!("format {}", doc.display_name()));
+            jobs.add(job);
         })
     } else {
         None
@@ -814,8 +834,6 @@ pub fn write_all_impl(
     options: WriteAllOptions,
 ) -> anyhow::Result<()> {
     let mut errors: Vec<&'static str> = Vec::new();
-    let config = cx.editor.config();
-    let jobs = &mut cx.jobs;
     let saves: Vec<_> = cx
         .editor
         .documents
@@ -842,41 +860,7 @@ pub fn write_all_impl(
         .collect();
 
     for (doc_id, target_view) in saves {
-        let doc = doc_mut!(cx.editor, &doc_id);
-        let view = view_mut!(cx.editor, target_view);
-
-        if doc.trim_trailing_whitespace() {
-            trim_trailing_whitespace(doc, target_view);
-        }
-        if config.trim_final_newlines {
-            trim_final_newlines(doc, target_view);
-        }
-        if doc.insert_final_newline() {
-            insert_final_newline(doc, target_view);
-        }
-
-        // Save an undo checkpoint for any outstanding changes.
-        doc.append_changes_to_history(view);
-
-        let fmt = if options.auto_format && config.auto_format {
-            let doc = doc!(cx.editor, &doc_id);
-            doc.auto_format(cx.editor).map(|fmt| {
-                let callback = make_format_callback(
-                    doc_id,
-                    doc.version(),
-                    target_view,
-                    fmt,
-                    Some((None, options.force)),
-                );
-                jobs.add(Job::with_callback(callback).wait_before_exiting());
-            })
-        } else {
-            None
-        };
-
-        if fmt.is_none() {
-            cx.editor.save::<PathBuf>(doc_id, None, options.force)?;
-        }
+        write_document_impl(cx, doc_id, target_view, options)?;
     }
 
     if !errors.is_empty() && !options.force {
@@ -886,6 +870,56 @@ pub fn write_all_impl(
     Ok(())
 }
 
+/// Writes a single document for `:write-all`, applying the same transformations as `:write`.
+fn write_document_impl(
+    cx: &mut compositor::Context,
+    doc_id: DocumentId,
+    target_view: ViewId,
+    options: WriteAllOptions,
+) -> anyhow::Result<()> {
+    let config = cx.editor.config();
+    let jobs = &mut cx.jobs;
+    let doc = doc_mut!(cx.editor, &doc_id);
+    let view = view_mut!(cx.editor, target_view);
+
+    if doc.trim_trailing_whitespace() {
+        trim_trailing_whitespace(doc, target_view);
+    }
+    if config.trim_final_newlines {
+        trim_final_newlines(doc, target_view);
+    }
+    if doc.insert_final_newline() {
+        insert_final_newline(doc, target_view);
+    }
+
+    // Save an undo checkpoint for any outstanding changes.
+    doc.append_changes_to_history(view);
+
+    let fmt = if options.auto_format && config.auto_format {
+        let doc = doc!(cx.editor, &doc_id);
+        doc.auto_format(cx.editor).map(|fmt| {
+            let callback = make_format_callback(
+                doc_id,
+                doc.version(),
+                target_view,
+                fmt,
+                Some((None, options.force)),
+            );
+            let job = Job::with_callback(callback)
+                .wait_before_exiting()
+                .named(format!("format {}", doc.display_name()));
+            jobs.add(job);
+        })
+    } else {
+        None
+    };
+
+    if fmt.is_none() {
+        cx.editor.save::<PathBuf>(doc_id, None, options.force)?;
+    }
+    Ok(())
+}
+
 fn write_all(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
@@ -936,7 +970,7 @@ fn write_all_quit(
             auto_format: !args.has_flag(WRITE_NO_FORMAT_FLAG.name),
         },
     )?;
-    quit_all_impl(cx, false)
+    quit_all_or_prompt(cx)
 }
 
 fn force_write_all_quit(
@@ -965,7 +999,12 @@ fn quit_all_impl(cx: &mut compositor::Context, force: bool) -> anyhow::Result<()
     }
 
     // close all views
-    let views: Vec<_> = cx.editor.tree.views().map(|(view, _)| view.id).collect();
+    let views: Vec<_> = cx
+        .editor
+        .tree
+        .all_views()
+        .map(|(view, _)| view.id)
+        .collect();
     for view_id in views {
         cx.editor.close(view_id);
     }
@@ -973,12 +1012,107 @@ fn quit_all_impl(cx: &mut compositor::Context, force: bool) -> anyhow::Result<()
     Ok(())
 }
 
+/// Quits like [quit_all_impl] but instead of failing on modified buffers asks whether to save
+/// or discard the changes of each of them, see [quit_all_prompt].
+fn quit_all_or_prompt(cx: &mut compositor::Context) -> anyhow::Result<()> {
+    cx.block_try_flush_writes()?;
+    let modified: Vec<_> = cx
+        .editor
+        .documents()
+        .filter(|doc| doc.is_modified())
+        .map(|doc| doc.id())
+        .collect();
+
+    if modified.is_empty() {
+        quit_all_impl(cx, false)
+    } else {
+        quit_all_prompt(cx, modified);
+        Ok(())
+    }
+}
+
+/// Asks what to do with the changes of the first of the `remaining` documents: save them,
+/// discard them or cancel quitting. Once all documents are handled the pending writes are
+/// flushed and the editor quits.
+fn quit_all_prompt(cx: &mut compositor::Context, mut remaining: Vec<DocumentId>) {
+    remaining.retain(|&id| cx.editor.document(id).is_some_and(|doc| doc.is_modified()));
+    if remaining.is_empty() {
+        if let Err(err) = cx
+            .block_try_flush_writes()
+            .and_then(|_| quit_all_impl(cx, true))
+        {
+            cx.editor.set_error(err.to_string());
+        }
+        return;
+    }
+
+    let doc_id = remaining.remove(0);
+    cx.editor.switch(doc_id, Action::Replace);
+    let doc = doc!(cx.editor, &doc_id);
+    // Buffers without a path can't be saved from here, only discarded.
+    let scratch = doc.path().is_none();
+    // Saving recreates deleted files along with their parent directories.
+    let deleted = doc.path().is_some_and(|path| !path.exists());
+    let prompt = format!(
+        "{}{} has unsaved changes, {}(d)iscard or (c)ancel: ",
+        doc.display_name(),
+        if deleted { " (deleted)" } else { "" },
+        if scratch { "" } else { "(s)ave, " },
+    );
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |_editor: &mut Editor, compositor: &mut Compositor| {
+                let prompt = Prompt::new(
+                    prompt.into(),
+                    None,
+                    ui::completers::none,
+                    move |cx: &mut compositor::Context, input: &str, event: PromptEvent| {
+                        if event != PromptEvent::Validate {
+                            return;
+                        }
+                        match input.trim().chars().next() {
+                            Some('s' | 'S') if !scratch => {
+                                let target_view = cx.editor.get_synced_view_id(doc_id);
+                                let options = WriteAllOptions {
+                                    force: deleted,
+                                    write_scratch: false,
+                                    auto_format: true,
+                                };
+                                if let Err(err) =
+                                    write_document_impl(cx, doc_id, target_view, options)
+                                {
+                                    // Ask again, the changes can still be discarded
+                                    cx.editor.set_error(err.to_string());
+                                    let mut remaining = remaining.clone();
+                                    remaining.insert(0, doc_id);
+                                    quit_all_prompt(cx, remaining);
+                                    return;
+                                }
+                            }
+                            Some('d' | 'D') => (),
+                            _ => {
+                                cx.editor.set_status("Quit cancelled");
+                                return;
+                            }
+                        }
+                        quit_all_prompt(cx, remaining.clone());
+                    },
+                );
+                compositor.push(Box::new(prompt));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+}
+
 fn quit_all(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
     }
 
-    quit_all_impl(cx, false)
+    quit_all_or_prompt(cx)
 }
 
 fn force_quit_all(
@@ -1022,7 +1156,8 @@ fn force_cquit(cx: &mut compositor::Context, args: Args, event: PromptEvent) ->
 }
 
 fn theme(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
-    let true_color = cx.editor.config.load().true_color || crate::true_color();
+    let true_color =
+        cx.editor.terminal_true_color || cx.editor.config.load().true_color || crate::true_color();
     match event {
         PromptEvent::Abort => {
             cx.editor.unset_theme_preview();
@@ -1447,6 +1582,135 @@ fn reload(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyh
     Ok(())
 }
 
+/// Opens the edit journal of the current document, or copies it to the given path.
+fn journal(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let doc = doc!(cx.editor);
+    let Some(path) = doc.path() else {
+        bail!("Scratch buffers have no edit journal");
+    };
+    let file = helix_view::journal::journal_file(path);
+    if !file.exists() {
+        bail!(
+            "No edit journal for {}, see the `editor.edit-journal` option",
+            doc.display_name()
+        );
+    }
+
+    if let Some(target) = args.first() {
+        let target = helix_stdx::path::expand_tilde(Path::new(target));
+        std::fs::copy(&file, &target)?;
+        cx.editor
+            .set_status(format!("Exported the edit journal to {}", target.display()));
+        return Ok(());
+    }
+
+    let id = cx.editor.open(&file, Action::VerticalSplit)?;
+    doc_mut!(cx.editor, &id).readonly = true;
+    Ok(())
+}
+
+fn selection_save(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let (view, doc) = current!(cx.editor);
+    let selection = doc.selection(view.id).clone();
+    let count = selection.len();
+    doc.named_selections.insert(args[0].to_string(), selection);
+    cx.editor.set_status(format!(
+        "Saved {count} selection{} as '{}'",
+        if count == 1 { "" } else { "s" },
+        &args[0]
+    ));
+    Ok(())
+}
+
+fn selection_restore(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let (view, doc) = current!(cx.editor);
+    let Some(selection) = doc.named_selections.get(&args[0]).cloned() else {
+        bail!("No selection named '{}'", &args[0]);
+    };
+    // Allow jumping back to the replaced selection
+    view.jumps.push((doc.id(), doc.selection(view.id).clone()));
+    doc.set_selection(view.id, selection);
+    Ok(())
+}
+
+fn selection_delete(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let doc = doc_mut!(cx.editor);
+    if doc.named_selections.remove(&args[0]).is_none() {
+        bail!("No selection named '{}'", &args[0]);
+    }
+    Ok(())
+}
+
+/// Toggles following the current document's file: content appended to the file is loaded
+/// as it arrives, like `tail -f`.
+fn follow(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    /// How often the followed file is checked for appended content.
+    const INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let doc = doc_mut!(cx.editor);
+    let following = !doc.is_following();
+    doc.set_following(following)?;
+    let name = doc.display_name().into_owned();
+    if !following {
+        cx.editor.set_status(format!("stopped following {name}"));
+        return Ok(());
+    }
+
+    let doc_id = doc.id();
+    let cancel = doc.follow_controller.restart();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+            if cancel.is_canceled() {
+                break;
+            }
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            job::dispatch(move |editor, _compositor| {
+                let _ = tx.send(editor.follow_document(doc_id));
+            })
+            .await;
+            if !rx.await.unwrap_or(false) {
+                break;
+            }
+        }
+    });
+    cx.editor.set_status(format!("following {name}"));
+    Ok(())
+}
+
 fn reload_all(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
@@ -1571,7 +1835,12 @@ fn lsp_workspace_command(
                         commands,
                         (),
                         move |cx, (ls_id, command), _action| {
-                            cx.editor.execute_lsp_command(command.clone(), *ls_id);
+                            lsp::execute_workspace_command(
+                                cx.jobs,
+                                cx.editor,
+                                command.clone(),
+                                *ls_id,
+                            );
                         },
                     );
                     compositor.push(Box::new(overlaid(picker)))
@@ -1588,18 +1857,14 @@ fn lsp_workspace_command(
 
         match matches.as_slice() {
             [(ls_id, _command)] => {
-                let arguments = args
-                    .get(1)
-                    .map(|rest| {
-                        serde_json::Deserializer::from_str(rest)
-                            .into_iter()
-                            .collect::<Result<Vec<Value>, _>>()
-                            .map_err(|err| anyhow!("failed to parse arguments: {err}"))
-                    })
-                    .transpose()?
-                    .filter(|args| !args.is_empty());
+                let arguments = match args.get(1) {
+                    Some(rest) => lsp::parse_command_arguments(rest)?,
+                    None => None,
+                };
 
-                cx.editor.execute_lsp_command(
+                lsp::execute_workspace_command(
+                    cx.jobs,
+                    cx.editor,
                     helix_lsp::lsp::Command {
                         title: command.clone(),
                         arguments,
@@ -1733,11 +1998,13 @@ fn lsp_stop(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> any
     };
 
     for ls_name in &language_servers {
-        cx.editor.language_servers.stop(ls_name);
+        // Also clears the diagnostics the server published for files which aren't open.
+        for server_id in cx.editor.language_servers.stop(ls_name) {
+            cx.editor.clear_language_server_diagnostics(server_id);
+        }
 
         for doc in cx.editor.documents_mut() {
-            if let Some(client) = doc.remove_language_server_by_name(ls_name) {
-                doc.clear_diagnostics_for_language_server(client.id());
+            if doc.remove_language_server_by_name(ls_name).is_some() {
                 doc.reset_all_inlay_hints();
                 doc.inlay_hints_oudated = true;
             }
@@ -1747,6 +2014,124 @@ fn lsp_stop(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> any
     Ok(())
 }
 
+fn lsp_disable(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+    let doc = doc_mut!(cx.editor);
+
+    let language_servers: Vec<_> = doc
+        .language_servers()
+        .map(|ls| ls.name().to_string())
+        .collect();
+    let language_servers = if args.is_empty() {
+        language_servers
+    } else {
+        let (valid, invalid): (Vec<_>, Vec<_>) = args
+            .iter()
+            .map(|arg| arg.to_string())
+            .partition(|name| language_servers.contains(name));
+        if !invalid.is_empty() {
+            let s = if invalid.len() == 1 { "" } else { "s" };
+            bail!("Unknown language server{s}: {}", invalid.join(", "));
+        }
+        valid
+    };
+    if language_servers.is_empty() {
+        bail!("No language servers are attached to the current document");
+    }
+
+    for ls_name in &language_servers {
+        doc.disable_language_server(ls_name);
+    }
+    cx.editor.set_status(format!(
+        "Disabled for this document: {}",
+        language_servers.join(", ")
+    ));
+
+    Ok(())
+}
+
+fn lsp_enable(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+    let doc = doc_mut!(cx.editor);
+
+    let language_servers: Vec<_> = if args.is_empty() {
+        doc.disabled_language_servers().map(String::from).collect()
+    } else {
+        let (valid, invalid): (Vec<_>, Vec<_>) = args
+            .iter()
+            .map(|arg| arg.to_string())
+            .partition(|name| doc.disabled_language_servers().any(|ls| ls == name));
+        if !invalid.is_empty() {
+            let s = if invalid.len() == 1 { "" } else { "s" };
+            bail!("Language server{s} not disabled: {}", invalid.join(", "));
+        }
+        valid
+    };
+    if language_servers.is_empty() {
+        bail!("No language servers are disabled for the current document");
+    }
+
+    for ls_name in &language_servers {
+        doc.enable_language_server(ls_name);
+    }
+    let doc_id = doc.id();
+    cx.editor.refresh_language_servers(doc_id);
+    cx.editor.set_status(format!(
+        "Enabled for this document: {}",
+        language_servers.join(", ")
+    ));
+
+    Ok(())
+}
+
+fn repl_stop(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let name = match args.first() {
+        Some(name) => name.to_string(),
+        None => doc!(cx.editor)
+            .language_name()
+            .context("The buffer has no language")?
+            .to_string(),
+    };
+    // Dropping the REPL kills its process
+    if cx.editor.repls.remove(&name).is_none() {
+        bail!("No {name} REPL is running");
+    }
+    cx.editor.set_status(format!("Stopped the {name} REPL"));
+
+    Ok(())
+}
+
+fn terminal(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let Some(editor_view) = compositor.find::<ui::EditorView>() else {
+                    return;
+                };
+                if let Err(err) = editor_view.open_terminal(editor) {
+                    editor.set_error(format!("Failed to start the terminal: {err}"));
+                }
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
 fn tree_sitter_scopes(
     cx: &mut compositor::Context,
     _args: Args,
@@ -1946,20 +2331,81 @@ fn hsplit_new(cx: &mut compositor::Context, _args: Args, event: PromptEvent) ->
     Ok(())
 }
 
-fn debug_eval(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+fn layout_new(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
     }
 
-    if let Some(debugger) = cx.editor.debug_adapters.get_active_client() {
-        let (frame, thread_id) = match (debugger.active_frame, debugger.thread_id) {
-            (Some(frame), Some(thread_id)) => (frame, thread_id),
-            _ => {
-                bail!("Cannot find current stack frame to access variables")
-            }
-        };
-
-        // TODO: support no frame_id
+    cx.editor
+        .new_layout(args.first().map(|name| name.to_string()));
+
+    Ok(())
+}
+
+fn layout_close(
+    cx: &mut compositor::Context,
+    _args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    ensure!(cx.editor.close_layout(), "Cannot close the only layout");
+
+    Ok(())
+}
+
+fn layout_switch(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let target = &args[0];
+    let idx = cx
+        .editor
+        .tree
+        .layout_names()
+        .position(|name| name == target)
+        .or_else(|| target.parse::<usize>().ok()?.checked_sub(1))
+        .ok_or_else(|| anyhow!("No layout named '{target}'"))?;
+    ensure!(cx.editor.switch_layout(idx), "No layout number {}", idx + 1);
+
+    Ok(())
+}
+
+fn layout_rename(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    cx.editor.tree.rename_layout(args[0].to_string());
+
+    Ok(())
+}
+
+fn debug_eval(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    if let Some(debugger) = cx.editor.debug_adapters.get_active_client() {
+        let (frame, thread_id) = match (debugger.active_frame, debugger.thread_id) {
+            (Some(frame), Some(thread_id)) => (frame, thread_id),
+            _ => {
+                bail!("Cannot find current stack frame to access variables")
+            }
+        };
+
+        // TODO: support no frame_id
 
         let frame_id = debugger.stack_frames[&thread_id][frame].id;
         let response = helix_lsp::block_on(debugger.eval(args.join(" "), Some(frame_id)))?;
@@ -2117,6 +2563,10 @@ fn set_option(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> a
     *value = if value.is_string() {
         // JSON strings require quotes, so we can't .parse() directly
         Value::String(arg.to_string())
+    } else if value.is_null() {
+        // Unset options may hold any type, fall back to a string if the value isn't JSON.
+        arg.parse()
+            .unwrap_or_else(|_| Value::String(arg.to_string()))
     } else {
         arg.parse().map_err(field_error)?
     };
@@ -2246,15 +2696,45 @@ fn language(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> any
     } else {
         doc.set_language_by_language_id(&args[0], &loader)?;
     }
+    language_changed(cx.editor);
+    Ok(())
+}
+
+/// Detects the language of the current document again from its file name and shebang, for
+/// example after adding a shebang to a new file or after `:set-language`.
+fn detect_language(
+    cx: &mut compositor::Context,
+    _args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let doc = doc_mut!(cx.editor);
+    let loader = cx.editor.syn_loader.load();
+    doc.detect_language(&loader);
+    let language = doc
+        .language_name()
+        .unwrap_or(DEFAULT_LANGUAGE_NAME)
+        .to_string();
+    language_changed(cx.editor);
+    cx.editor
+        .set_status(format!("Detected language: {language}"));
+    Ok(())
+}
+
+/// Updates the indentation, language servers and diagnostics of the current document after its
+/// language changed.
+fn language_changed(editor: &mut Editor) {
+    let doc = doc_mut!(editor);
     doc.detect_indent_and_line_ending();
 
     let id = doc.id();
-    cx.editor.refresh_language_servers(id);
-    let doc = doc_mut!(cx.editor);
-    let diagnostics =
-        Editor::doc_diagnostics(&cx.editor.language_servers, &cx.editor.diagnostics, doc);
+    editor.refresh_language_servers(id);
+    let doc = doc_mut!(editor);
+    let diagnostics = Editor::doc_diagnostics(&editor.language_servers, &editor.diagnostics, doc);
     doc.replace_diagnostics(diagnostics, &[], None);
-    Ok(())
 }
 
 fn sort(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
@@ -2301,6 +2781,34 @@ fn sort(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow:
     Ok(())
 }
 
+fn align_text(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    use helix_core::align::{align, alignment_points, Anchor};
+
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let anchor = match args.get_flag("anchor") {
+        None | Some("start") => Anchor::Start,
+        Some("end") => Anchor::End,
+        Some(anchor) => bail!("Unknown anchor '{anchor}', expected 'start' or 'end'"),
+    };
+    let delimiter = args.first().map(helix_stdx::rope::Regex::new).transpose()?;
+
+    let scrolloff = cx.editor.config().scrolloff;
+    let (view, doc) = current!(cx.editor);
+    let text = doc.text().slice(..);
+    let points = alignment_points(text, doc.selection(view.id), delimiter.as_ref(), anchor);
+    let changes = align(text, &points, doc.tab_width());
+
+    let transaction = Transaction::change(doc.text(), changes.into_iter());
+    doc.apply(&transaction, view.id);
+    doc.append_changes_to_history(view);
+    view.ensure_cursor_in_view(doc, scrolloff);
+
+    Ok(())
+}
+
 fn reflow(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
@@ -2375,6 +2883,244 @@ fn tree_sitter_subtree(
     Ok(())
 }
 
+fn tree_sitter_playground(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let query = args.first().map(|query| query.to_string());
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let Some(editor_view) = compositor.find::<ui::EditorView>() else {
+                    return;
+                };
+                // Without a query the command toggles the panel
+                editor_view.syntax_tree = match (&editor_view.syntax_tree, query) {
+                    (Some(_), None) => None,
+                    (_, query) => Some(ui::SyntaxTreePanel::new(query)),
+                };
+                editor_view.resize_terminal(editor);
+                if editor_view.syntax_tree.is_some() && doc!(editor).syntax().is_none() {
+                    editor.set_status("Syntax tree is not available on this buffer");
+                }
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
+fn select_query(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    select_structural_matches(cx.editor, &args[0])
+}
+
+fn explain_keys(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let keys = helix_view::input::parse_macro(&args[0])?;
+    let mode = match args.get_flag("mode") {
+        Some(mode) => mode.parse::<Mode>()?,
+        None => cx.editor.mode(),
+    };
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let Some(editor_view) = compositor.find::<ui::EditorView>() else {
+                    return;
+                };
+                let lines = editor_view.keymaps.explain(mode, &keys);
+                let mut contents = format!("Keys in {mode} mode:\n");
+                for line in lines {
+                    contents.push_str(&format!("\n- {line}"));
+                }
+                let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                let popup = Popup::new("hover", contents).auto_close(true);
+                compositor.replace_or_push("hover", popup);
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
+fn messages(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    if cx.editor.messages.is_empty() {
+        cx.editor.set_status("No messages");
+        return Ok(());
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                use helix_view::editor::Severity;
+                use tui::text::{Span, Spans};
+
+                let theme = &editor.theme;
+                let time_style = theme.get("ui.text.inactive");
+                // Newest first, so the latest messages are visible without scrolling
+                let lines: Vec<_> = editor
+                    .messages
+                    .iter()
+                    .rev()
+                    .map(|message| {
+                        let time = chrono::DateTime::<chrono::Local>::from(message.time);
+                        let (label, style) = match message.severity {
+                            Severity::Error => ("error", theme.get("error")),
+                            Severity::Warning => ("warning", theme.get("warning")),
+                            Severity::Info => ("info", theme.get("info")),
+                            Severity::Hint => ("hint", theme.get("hint")),
+                        };
+                        let mut spans = vec![
+                            Span::styled(time.format("%H:%M:%S ").to_string(), time_style),
+                            Span::styled(format!("{label:<8}"), style),
+                            Span::raw(message.text.to_string()),
+                        ];
+                        if message.count > 1 {
+                            spans.push(Span::styled(format!(" (×{})", message.count), time_style));
+                        }
+                        Spans::from(spans)
+                    })
+                    .collect();
+                let contents = ui::Text::from(tui::text::Text::from(lines));
+                let popup = Popup::new("messages", contents).auto_close(true);
+                compositor.replace_or_push("messages", popup);
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
+fn key_trace(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |_editor: &mut Editor, compositor: &mut Compositor| {
+                if compositor.remove(ui::key_trace::ID).is_none() {
+                    compositor.push(Box::new(ui::key_trace::KeyTrace::default()));
+                }
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
+fn metrics(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let metrics = &mut cx.editor.metrics;
+    match args.first() {
+        Some("on") => {
+            metrics.set_enabled(true);
+            cx.editor.set_status("Recording frame metrics");
+        }
+        Some("off") => {
+            metrics.set_enabled(false);
+            cx.editor.set_status("Stopped recording frame metrics");
+        }
+        Some("reset") => metrics.reset(),
+        Some(arg) => bail!("Expected 'on', 'off' or 'reset', got '{arg}'"),
+        None => {
+            ensure!(
+                metrics.enabled(),
+                "Frame metrics aren't recorded, start with `:metrics on`"
+            );
+            let contents = format!("```\n{}\n```", metrics.summary());
+            let callback = async move {
+                let call: job::Callback = Callback::EditorCompositor(Box::new(
+                    move |editor: &mut Editor, compositor: &mut Compositor| {
+                        let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                        let popup = Popup::new("hover", contents).auto_close(true);
+                        compositor.replace_or_push("hover", popup);
+                    },
+                ));
+                Ok(call)
+            };
+            cx.jobs.callback(callback);
+        }
+    }
+
+    Ok(())
+}
+
+fn jobs(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let jobs = job::running_jobs();
+    ensure!(!jobs.is_empty(), "No background jobs are running");
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |_editor: &mut Editor, compositor: &mut Compositor| {
+                let columns = [
+                    ui::PickerColumn::new("name", |job: &RunningJob, _| job.name.as_str().into()),
+                    ui::PickerColumn::new("time", |job: &RunningJob, _| {
+                        format!("{}s", job.started.elapsed().as_secs()).into()
+                    }),
+                    ui::PickerColumn::new("progress", |job: &RunningJob, _| {
+                        job.progress.as_deref().unwrap_or_default().into()
+                    }),
+                ];
+                let picker = ui::Picker::new(columns, 0, jobs, (), |cx, job, _action| {
+                    match job::cancel(job.id) {
+                        Some(name) => cx.editor.set_status(format!("Cancelled {name}")),
+                        None if job.cancellable() => cx
+                            .editor
+                            .set_status(format!("{} already finished", job.name)),
+                        None => cx.editor.set_error(format!(
+                            "{} must finish before exiting and can't be cancelled",
+                            job.name
+                        )),
+                    }
+                });
+                compositor.push(Box::new(overlaid(picker)))
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
+
 fn open_config(
     cx: &mut compositor::Context,
     _args: Args,
@@ -2561,6 +3307,122 @@ fn reset_diff_change(
     Ok(())
 }
 
+fn quickfix(cx: &mut compositor::Context, _args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+    ensure!(!cx.editor.quickfix.is_empty(), "The quickfix list is empty");
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let picker = make_quickfix_picker(editor);
+                compositor.push(Box::new(overlaid(picker)))
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
+
+fn watch(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    if args.is_empty() {
+        ensure!(
+            cx.editor.watch.take().is_some(),
+            "No command is being watched"
+        );
+        cx.editor.set_status("Stopped watching");
+        return Ok(());
+    }
+    cx.editor.watch = Some(WatchTask::new(args.join(" ")));
+    crate::handlers::watch::run(cx.editor);
+    Ok(())
+}
+
+fn windo(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let focus = view!(cx.editor).id;
+    let views: Vec<_> = cx.editor.tree.views().map(|(view, _)| view.id).collect();
+    let mut errors = Vec::new();
+    for &view_id in &views {
+        // The command may have closed views
+        if !cx.editor.tree.contains(view_id) {
+            continue;
+        }
+        cx.editor.focus(view_id);
+        if let Err(err) = execute_command_line(cx, &args[0], event) {
+            errors.push(format!("{}: {err}", doc!(cx.editor).display_name()));
+        }
+    }
+    if cx.editor.tree.contains(focus) {
+        cx.editor.focus(focus);
+    }
+    report_errors(&errors, views.len(), "views")
+}
+
+fn bufdo(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let current = doc!(cx.editor).id();
+    let docs: Vec<_> = cx.editor.documents.keys().copied().collect();
+    let mut errors = Vec::new();
+    for &doc_id in &docs {
+        // The command may have closed documents
+        let Some(doc) = cx.editor.document(doc_id) else {
+            continue;
+        };
+        let name = doc.display_name().into_owned();
+        cx.editor.switch(doc_id, Action::Replace);
+        if let Err(err) = execute_command_line(cx, &args[0], event) {
+            errors.push(format!("{name}: {err}"));
+        }
+    }
+    if cx.editor.document(current).is_some() {
+        cx.editor.switch(current, Action::Replace);
+    }
+    report_errors(&errors, docs.len(), "documents")
+}
+
+/// Combines the errors of a command run in each of `total` views or documents into one.
+fn report_errors(errors: &[String], total: usize, targets: &str) -> anyhow::Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "failed in {} of {total} {targets}: {}",
+        errors.len(),
+        errors.join("; ")
+    )
+}
+
+fn picker(cx: &mut compositor::Context, args: Args, event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let picker = ui::picker::source::picker(cx.editor, &args[0])?;
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |_editor: &mut Editor, compositor: &mut Compositor| {
+                compositor.push(Box::new(overlaid(picker)))
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
+
 fn clear_register(
     cx: &mut compositor::Context,
     args: Args,
@@ -2673,23 +3535,110 @@ fn move_buffer_impl(
         .filter(|_| new_path.is_dir())
         .map(|old_file_name| new_path.join(old_file_name))
         .unwrap_or(new_path);
-
-    if old_path.exists() {
-        if let Some(parent) = new_path.parent() {
-            if !parent.exists() {
-                if options.force {
-                    std::fs::DirBuilder::new().recursive(true).create(parent)?;
-                } else {
-                    bail!(
-                        "can't move file, parent directory does not exist (use :mv! to create it)"
-                    )
-                }
-            }
-        }
+
+    if old_path.exists() {
+        if let Some(parent) = new_path.parent() {
+            if !parent.exists() {
+                if options.force {
+                    std::fs::DirBuilder::new().recursive(true).create(parent)?;
+                } else {
+                    bail!(
+                        "can't move file, parent directory does not exist (use :mv! to create it)"
+                    )
+                }
+            }
+        }
+    }
+
+    if let Err(err) = cx.editor.move_path(&old_path, new_path.as_ref()) {
+        bail!("Could not move file: {err}");
+    }
+    Ok(())
+}
+
+fn remove_buffer_file(
+    cx: &mut compositor::Context,
+    _args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    remove_buffer_file_impl(cx, false)
+}
+
+fn force_remove_buffer_file(
+    cx: &mut compositor::Context,
+    _args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    remove_buffer_file_impl(cx, true)
+}
+
+fn remove_buffer_file_impl(cx: &mut compositor::Context, permanently: bool) -> anyhow::Result<()> {
+    let path = doc!(cx.editor)
+        .path()
+        .context("Scratch buffer has no file to remove")?
+        .clone();
+
+    if let Err(err) = cx.editor.remove_path(&path, permanently) {
+        if permanently {
+            bail!("Could not delete file: {err}");
+        }
+        bail!("Could not move file to the trash: {err} (use :rm! to delete it permanently)");
+    }
+
+    let action = if permanently {
+        "Deleted"
+    } else {
+        "Moved to the trash:"
+    };
+    cx.editor.set_status(format!(
+        "{action} {}",
+        helix_stdx::path::get_relative_path(&path).display()
+    ));
+    Ok(())
+}
+
+fn duplicate_buffer_file(
+    cx: &mut compositor::Context,
+    args: Args,
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let doc = doc!(cx.editor);
+    let old_path = doc
+        .path()
+        .context("Scratch buffer cannot be duplicated. Use :write instead")?
+        .clone();
+    let modified = doc.is_modified();
+
+    // if the path is a directory, copy the file into that directory
+    let new_path: PathBuf = args[0].into();
+    let new_path = old_path
+        .file_name()
+        .filter(|_| new_path.is_dir())
+        .map(|old_file_name| new_path.join(old_file_name))
+        .unwrap_or(new_path);
+    if new_path.exists() {
+        bail!("{} already exists", new_path.display());
     }
 
-    if let Err(err) = cx.editor.move_path(&old_path, new_path.as_ref()) {
-        bail!("Could not move file: {err}");
+    if let Err(err) = cx.editor.copy_path(&old_path, &new_path) {
+        bail!("Could not copy file: {err}");
+    }
+    cx.editor.open(&new_path, Action::Replace)?;
+    if modified {
+        cx.editor
+            .set_warning("Unsaved changes of the original buffer were not copied");
     }
     Ok(())
 }
@@ -3059,7 +4008,7 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
     TypableCommand {
         name: "earlier",
         aliases: &["ear"],
-        doc: "Jump back to an earlier point in edit history. Accepts a number of steps or a time span.",
+        doc: "Jump back to an earlier point in edit history. Accepts a number of steps, a time span or a time span followed by `ago`.",
         fun: earlier,
         completer: CommandCompleter::none(),
         signature: Signature {
@@ -3070,7 +4019,7 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
     TypableCommand {
         name: "later",
         aliases: &["lat"],
-        doc: "Jump to a later point in edit history. Accepts a number of steps or a time span.",
+        doc: "Jump to a later point in edit history. Accepts a number of steps, a time span or a time span followed by `ago`.",
         fun: later,
         completer: CommandCompleter::none(),
         signature: Signature {
@@ -3129,7 +4078,7 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
     TypableCommand {
         name: "write-quit-all",
         aliases: &["wqa", "xa"],
-        doc: "Write changes from all buffers to disk and close all views.",
+        doc: "Write changes from all buffers to disk and close all views, asking what to do with buffers which can't be written.",
         fun: write_all_quit,
         completer: CommandCompleter::none(),
         signature: Signature {
@@ -3153,7 +4102,7 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
     TypableCommand {
         name: "quit-all",
         aliases: &["qa"],
-        doc: "Close all views.",
+        doc: "Close all views, asking whether to save or discard the changes of each modified buffer.",
         fun: quit_all,
         completer: CommandCompleter::none(),
         signature: Signature {
@@ -3403,6 +4352,61 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "follow",
+        aliases: &[],
+        doc: "Toggle loading content appended to the file, like `tail -f`. The view stays at the end of the file while the cursor is on the last line.",
+        fun: follow,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "journal",
+        aliases: &[],
+        doc: "Open the edit journal of the current buffer, or export it to the given path. Changes are only recorded while `editor.edit-journal` is enabled.",
+        fun: journal,
+        completer: CommandCompleter::positional(&[completers::filename]),
+        signature: Signature {
+            positionals: (0, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "selection-save",
+        aliases: &[],
+        doc: "Save the current selections of the buffer under the given name.",
+        fun: selection_save,
+        completer: CommandCompleter::positional(&[completers::named_selection]),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "selection-restore",
+        aliases: &[],
+        doc: "Restore the selections saved under the given name with :selection-save.",
+        fun: selection_restore,
+        completer: CommandCompleter::positional(&[completers::named_selection]),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "selection-delete",
+        aliases: &[],
+        doc: "Delete the selections saved under the given name.",
+        fun: selection_delete,
+        completer: CommandCompleter::positional(&[completers::named_selection]),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "update",
         aliases: &["u"],
@@ -3417,8 +4421,8 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
     },
     TypableCommand {
         name: "lsp-workspace-command",
-        aliases: &[],
-        doc: "Open workspace command picker",
+        aliases: &["lsp-command"],
+        doc: "Open workspace command picker, or run the given command with optional JSON arguments. Prompts for arguments if the server rejects them",
         fun: lsp_workspace_command,
         completer: CommandCompleter::positional(&[completers::lsp_workspace_command]),
         signature: Signature {
@@ -3449,6 +4453,50 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "lsp-disable",
+        aliases: &[],
+        doc: "Detaches the given language servers, or all language servers that are used by the current file if no arguments are supplied, from the current file until they are enabled with :lsp-enable. They keep running for other files.",
+        fun: lsp_disable,
+        completer: CommandCompleter::all(completers::active_language_servers),
+        signature: Signature {
+            positionals: (0, None),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "lsp-enable",
+        aliases: &[],
+        doc: "Attaches the given language servers, or all language servers disabled with :lsp-disable if no arguments are supplied, to the current file again.",
+        fun: lsp_enable,
+        completer: CommandCompleter::all(completers::configured_language_servers),
+        signature: Signature {
+            positionals: (0, None),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "repl-stop",
+        aliases: &[],
+        doc: "Stops the REPL of the given language, or of the current file's language if no argument is supplied.",
+        fun: repl_stop,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "terminal",
+        aliases: &["term"],
+        doc: "Open the terminal panel below the splits and focus it, starting $SHELL if it isn't running. Keys bound to `toggle_terminal` hide the panel again.",
+        fun: terminal,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "tree-sitter-scopes",
         aliases: &[],
@@ -3559,6 +4607,50 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "layout-new",
+        aliases: &["tabnew"],
+        doc: "Open a new layout (tab page) showing the current buffer. The layout is named after its number unless a name is given.",
+        fun: layout_new,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "layout-close",
+        aliases: &["tabclose"],
+        doc: "Close the current layout and its splits. The buffers stay open.",
+        fun: layout_close,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "layout",
+        aliases: &["tab"],
+        doc: "Switch to the layout with the given name or number.",
+        fun: layout_switch,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "layout-rename",
+        aliases: &[],
+        doc: "Rename the current layout.",
+        fun: layout_rename,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "tutor",
         aliases: &[],
@@ -3592,13 +4684,23 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "detect-language",
+        aliases: &[],
+        doc: "Detect the language of the current buffer again from its file name and shebang.",
+        fun: detect_language,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "set-option",
         aliases: &["set"],
         doc: "Set a config option at runtime.\nFor example to disable smart case search, use `:set search.smart-case false`.",
         fun: set_option,
-        // TODO: Add support for completion of the options value(s), when appropriate.
-        completer: CommandCompleter::positional(&[completers::setting]),
+        completer: CommandCompleter::dependent(&[completers::setting], completers::setting_value),
         signature: Signature {
             positionals: (2, Some(2)),
             raw_after: Some(1),
@@ -3610,7 +4712,7 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
         aliases: &["toggle"],
         doc: "Toggle a config option at runtime.\nFor example to toggle smart case search, use `:toggle search.smart-case`.",
         fun: toggle_option,
-        completer: CommandCompleter::positional(&[completers::setting]),
+        completer: CommandCompleter::dependent(&[completers::setting], completers::setting_value),
         signature: Signature {
             positionals: (1, None),
             raw_after: Some(1),
@@ -3653,6 +4755,23 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "align",
+        aliases: &[],
+        doc: "Align the selections to a common column by padding them with spaces. Accepts an optional regex to align its first match on each line of the selections instead.",
+        fun: align_text,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(1)),
+            flags: &[Flag {
+                name: "anchor",
+                alias: Some('a'),
+                doc: "align the start of the selections or matches (the default) or the text after them (end)",
+                completions: Some(&["start", "end"]),
+            }],
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "reflow",
         aliases: &[],
@@ -3664,6 +4783,30 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "select-query",
+        aliases: &[],
+        doc: "Select the nodes captured by a tree-sitter query within the current selections. Metavariables like `$name` are shorthand for `(_) @name`.",
+        fun: select_query,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            raw_after: Some(0),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "tree-sitter-playground",
+        aliases: &["ts-playground"],
+        doc: "Toggle a panel showing the syntax tree of the viewport with the node under the cursor highlighted. With a query, also show and highlight the nodes it captures.",
+        fun: tree_sitter_playground,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(1)),
+            raw_after: Some(0),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "tree-sitter-subtree",
         aliases: &["ts-subtree"],
@@ -3675,6 +4818,67 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "explain-keys",
+        aliases: &[],
+        doc: "Explain how a key sequence like `<space>wv` resolves in the keymap, including sub-keymaps, timeouts and fall-through.",
+        fun: explain_keys,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            flags: &[Flag {
+                name: "mode",
+                alias: Some('m'),
+                doc: "the mode to look the keys up in, the current mode by default",
+                completions: Some(&["normal", "select", "insert"]),
+            }],
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "messages",
+        aliases: &[],
+        doc: "Show the status messages of this session, newest first.",
+        fun: messages,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "key-trace",
+        aliases: &[],
+        doc: "Toggle a panel showing how each key is routed: the components that ignored it, the one that handled it and the keymap node or command it resolved to.",
+        fun: key_trace,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "metrics",
+        aliases: &[],
+        doc: "Show the timings of event handling, language server messages, rendering, drawing and flushing frames. `:metrics on` starts recording them, which also logs them periodically, `:metrics off` stops and `:metrics reset` clears them.",
+        fun: metrics,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "jobs",
+        aliases: &[],
+        doc: "Open a picker of the named background jobs which are running, such as formatters and grammar builds, with their progress. Selecting a job cancels it.",
+        fun: jobs,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "config-reload",
         aliases: &[],
@@ -3770,6 +4974,64 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "quickfix",
+        aliases: &["copen"],
+        doc: "Open a picker over the entries of the quickfix list.",
+        fun: quickfix,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "watch",
+        aliases: &[],
+        doc: "Run a shell command now and each time a file of the workspace is saved, replacing the quickfix list with the locations in its output. Stops watching without a command.",
+        fun: watch,
+        completer: SHELL_COMPLETER,
+        signature: Signature {
+            positionals: (0, Some(2)),
+            raw_after: Some(1),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "windo",
+        aliases: &[],
+        doc: "Run a command in each view, for example `:windo set-option soft-wrap.enable true`. The errors of all views are reported together.",
+        fun: windo,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            raw_after: Some(0),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "bufdo",
+        aliases: &[],
+        doc: "Run a command with each open document as the current one, for example `:bufdo reload`. The errors of all documents are reported together.",
+        fun: bufdo,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            raw_after: Some(0),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "picker",
+        aliases: &[],
+        doc: "Open the picker registered under the given name by an extension or embedder.",
+        fun: picker,
+        completer: CommandCompleter::positional(&[completers::picker_source]),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "clear-register",
         aliases: &[],
@@ -3826,6 +5088,39 @@ fn noop(_cx: &mut compositor::Context, _args: Args, _event: PromptEvent) -> anyh
             ..Signature::DEFAULT
         },
     },
+    TypableCommand {
+        name: "remove",
+        aliases: &["rm"],
+        doc: "Move the file of the current buffer to the trash and close the buffer",
+        fun: remove_buffer_file,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "remove!",
+        aliases: &["rm!"],
+        doc: "Permanently delete the file of the current buffer and close the buffer",
+        fun: force_remove_buffer_file,
+        completer: CommandCompleter::none(),
+        signature: Signature {
+            positionals: (0, Some(0)),
+            ..Signature::DEFAULT
+        },
+    },
+    TypableCommand {
+        name: "duplicate",
+        aliases: &["cp"],
+        doc: "Copy the file of the current buffer to a different path and open the copy",
+        fun: duplicate_buffer_file,
+        completer: CommandCompleter::positional(&[completers::filename]),
+        signature: Signature {
+            positionals: (1, Some(1)),
+            ..Signature::DEFAULT
+        },
+    },
     TypableCommand {
         name: "yank-diagnostic",
         aliases: &[],
@@ -3899,8 +5194,11 @@ fn execute_command_line(
         return execute_command(cx, cmd, command, event);
     }
 
-    match typed::TYPABLE_COMMAND_MAP.get(command) {
-        Some(cmd) => execute_command(cx, cmd, rest, event),
+    if let Some(cmd) = typed::TYPABLE_COMMAND_MAP.get(command) {
+        return execute_command(cx, cmd, rest, event);
+    }
+    match custom_command(command) {
+        Some(cmd) => cmd.execute(cx, rest, event),
         None if event == PromptEvent::Validate => Err(anyhow!("no such command: '{command}'")),
         None => Ok(()),
     }
@@ -3946,7 +5244,9 @@ pub(super) fn command_mode(cx: &mut Context) {
 
 fn command_line_doc(input: &str) -> Option<Cow<'_, str>> {
     let (command, _, _) = command_line::split(input);
-    let command = TYPABLE_COMMAND_MAP.get(command)?;
+    let Some(command) = TYPABLE_COMMAND_MAP.get(command) else {
+        return custom_command(command).map(|command| Cow::Owned(command.doc().to_owned()));
+    };
 
     if command.aliases.is_empty() && command.signature.flags.is_empty() {
         return Some(Cow::Borrowed(command.doc));
@@ -4020,14 +5320,15 @@ fn complete_command_line(editor: &Editor, input: &str) -> Vec<ui::prompt::Comple
     let (command, rest, complete_command) = command_line::split(input);
 
     if complete_command {
-        fuzzy_match(
-            input,
-            TYPABLE_COMMAND_LIST.iter().map(|command| command.name),
-            false,
-        )
-        .into_iter()
-        .map(|(name, _)| (0.., name.into()))
-        .collect()
+        let custom = custom_commands();
+        let names = TYPABLE_COMMAND_LIST
+            .iter()
+            .map(|command| command.name)
+            .chain(custom.iter().map(|command| command.name()));
+        fuzzy_match(input, names, false)
+            .into_iter()
+            .map(|(name, _)| (0.., name.to_owned().into()))
+            .collect()
     } else {
         TYPABLE_COMMAND_MAP
             .get(command)
@@ -4097,9 +5398,14 @@ pub fn complete_command_args(
                         .len()
                         .checked_sub(1)
                         .expect("completion state to be positional");
-                    let completer = completer.for_argument_number(n);
-
-                    completer(editor, &token.content)
+                    let completions = match completer.dependent_arg {
+                        Some(dependent) if n == completer.positional_args.len() && n > 0 => {
+                            dependent(editor, &args[0], &token.content)
+                        }
+                        _ => completer.for_argument_number(n)(editor, &token.content),
+                    };
+
+                    completions
                         .into_iter()
                         .map(|(range, span)| quote_completion(&token, range, span, offset))
                         .collect()
@@ -4138,7 +5444,7 @@ pub fn complete_command_args(
         TokenKind::Expansion(ExpansionKind::Variable) => {
             complete_variable_expansion(&token.content, offset + token.content_start)
         }
-        TokenKind::Expansion(ExpansionKind::Unicode) => Vec::new(),
+        TokenKind::Expansion(ExpansionKind::Unicode | ExpansionKind::Environment) => Vec::new(),
         TokenKind::ExpansionKind => {
             complete_expansion_kind(&token.content, offset + token.content_start)
         }
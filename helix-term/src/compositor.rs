@@ -2,7 +2,7 @@
 // Q: how does this work with popups?
 // cursive does compositor.screen_mut().add_layer_at(pos::absolute(x, y), <component>)
 use helix_core::Position;
-use helix_view::graphics::{CursorKind, Rect};
+use helix_view::graphics::{Color, CursorKind, Modifier, Rect};
 
 use tui::buffer::Buffer as Surface;
 
@@ -16,7 +16,7 @@ pub enum EventResult {
 }
 
 use crate::job::Jobs;
-use crate::ui::picker;
+use crate::ui::{key_trace, picker};
 use helix_view::Editor;
 
 pub use helix_view::input::Event;
@@ -73,6 +73,63 @@ fn type_name(&self) -> &'static str {
     fn id(&self) -> Option<&'static str> {
         None
     }
+
+    /// The stacking order of the layer. Layers with a higher z-index are kept in front of those
+    /// with a lower one, layers with the same z-index in the order they were pushed. The
+    /// editor's own components use 0.
+    fn z_index(&self) -> i32 {
+        0
+    }
+
+    /// Whether the layer stays open until it is removed explicitly, like the status bars and
+    /// sidebars of embedders. [`Compositor::pop`] skips persistent layers, and they receive
+    /// [`Event::Resize`] and [`Event::IdleTimeout`] even when a layer in front consumed it.
+    fn persistent(&self) -> bool {
+        false
+    }
+
+    /// How much to dim the layers below this component while it is open, in percent.
+    fn background_dim(&self, _editor: &Editor) -> u8 {
+        0
+    }
+
+    /// Describes what the component did with the last key event, for example which keymap
+    /// node or command it resolved to. Shown by `:key-trace`.
+    fn last_key_trace(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Blends the colors in `area` `amount` percent toward `background`. Colors which can't be
+/// blended are dimmed with the `DIM` modifier instead.
+pub(crate) fn dim_surface(
+    surface: &mut Surface,
+    area: Rect,
+    background: Option<Color>,
+    amount: u8,
+) {
+    let blend = |color: Color| match (color, background) {
+        (Color::Rgb(r, g, b), Some(Color::Rgb(br, bg, bb))) => {
+            let mix = |c: u8, bc: u8| {
+                let (c, bc, amount) = (c as u16, bc as u16, amount as u16);
+                ((c * (100 - amount) + bc * amount) / 100) as u8
+            };
+            Some(Color::Rgb(mix(r, br), mix(g, bg), mix(b, bb)))
+        }
+        _ => None,
+    };
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            let cell = &mut surface[(x, y)];
+            match blend(cell.fg) {
+                Some(fg) => cell.fg = fg,
+                None => cell.modifier.insert(Modifier::DIM),
+            }
+            if let Some(bg) = blend(cell.bg) {
+                cell.bg = bg;
+            }
+        }
+    }
 }
 
 pub struct Compositor {
@@ -101,7 +158,11 @@ pub fn resize(&mut self, area: Rect) {
         self.area = area;
     }
 
-    /// Add a layer to be rendered in front of all existing layers.
+    /// Add a layer to be rendered in front of all existing layers with the same or a lower
+    /// [z-index](Component::z_index).
+    ///
+    /// Embedders add their own components, for example a persistent status bar, with this and
+    /// look them up again by [id](Component::id) with [`Compositor::find_id`].
     pub fn push(&mut self, mut layer: Box<dyn Component>) {
         // immediately clear last_picker field to avoid excessive memory
         // consumption for picker with many items
@@ -111,7 +172,11 @@ pub fn push(&mut self, mut layer: Box<dyn Component>) {
         let size = self.size();
         // trigger required_size on init
         layer.required_size((size.width, size.height));
-        self.layers.push(layer);
+        let z_index = layer.z_index();
+        let idx = self
+            .layers
+            .partition_point(|layer| layer.z_index() <= z_index);
+        self.layers.insert(idx, layer);
     }
 
     /// Replace a component that has the given `id` with the new layer and if
@@ -124,8 +189,10 @@ pub fn replace_or_push<T: Component>(&mut self, id: &'static str, layer: T) {
         }
     }
 
+    /// Removes the frontmost layer which isn't [persistent](Component::persistent).
     pub fn pop(&mut self) -> Option<Box<dyn Component>> {
-        self.layers.pop()
+        let idx = self.layers.iter().rposition(|layer| !layer.persistent())?;
+        Some(self.layers.remove(idx))
     }
 
     pub fn remove(&mut self, id: &'static str) -> Option<Box<dyn Component>> {
@@ -152,26 +219,63 @@ pub fn handle_event(&mut self, event: &Event, cx: &mut Context) -> bool {
 
         let mut callbacks = Vec::new();
         let mut consumed = false;
+        let tracing = matches!(event, Event::Key(_))
+            && self
+                .layers
+                .iter()
+                .any(|layer| layer.id() == Some(key_trace::ID));
+        let mut route = Vec::new();
+        let mut reached = self.layers.len();
 
         // propagate events through the layers until we either find a layer that consumes it or we
         // run out of layers (event bubbling), starting at the front layer and then moving to the
         // background.
-        for layer in self.layers.iter_mut().rev() {
+        for (i, layer) in self.layers.iter_mut().enumerate().rev() {
+            reached = i;
             match layer.handle_event(event, cx) {
                 EventResult::Consumed(Some(callback)) => {
                     callbacks.push(callback);
                     consumed = true;
-                    break;
                 }
                 EventResult::Consumed(None) => {
                     consumed = true;
-                    break;
                 }
                 EventResult::Ignored(Some(callback)) => {
                     callbacks.push(callback);
                 }
                 EventResult::Ignored(None) => {}
             };
+            if tracing && layer.id() != Some(key_trace::ID) {
+                route.push(key_trace::LayerTrace {
+                    component: key_trace::short_type_name(layer.type_name()).to_string(),
+                    consumed,
+                    details: layer.last_key_trace(),
+                });
+            }
+            if consumed {
+                break;
+            }
+        }
+
+        if matches!(event, Event::Resize(..) | Event::IdleTimeout) {
+            let behind = self.layers[..reached]
+                .iter_mut()
+                .filter(|layer| layer.persistent());
+            for layer in behind {
+                match layer.handle_event(event, cx) {
+                    EventResult::Consumed(callback) => {
+                        callbacks.extend(callback);
+                        consumed = true;
+                    }
+                    EventResult::Ignored(callback) => callbacks.extend(callback),
+                }
+            }
+        }
+
+        if let (Event::Key(key), true) = (event, tracing) {
+            if let Some(trace) = self.find_id::<key_trace::KeyTrace>(key_trace::ID) {
+                trace.record(*key, route);
+            }
         }
 
         for callback in callbacks {
@@ -182,7 +286,16 @@ pub fn handle_event(&mut self, event: &Event, cx: &mut Context) -> bool {
     }
 
     pub fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
-        for layer in &mut self.layers {
+        // Only the layers below the topmost dimming component are dimmed, and only once.
+        let dim = self.layers.iter().enumerate().rev().find_map(|(i, layer)| {
+            let amount = layer.background_dim(cx.editor).min(100);
+            (i > 0 && amount > 0).then_some((i, amount))
+        });
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            if let Some((_, amount)) = dim.filter(|&(dim_layer, _)| dim_layer == i) {
+                let background = cx.editor.theme.get("ui.background").bg;
+                dim_surface(surface, area, background, amount);
+            }
             layer.render(area, surface, cx);
         }
     }
@@ -210,6 +323,7 @@ pub fn find<T: 'static>(&mut self) -> Option<&mut T> {
             .and_then(|component| component.as_any_mut().downcast_mut())
     }
 
+    /// Finds the layer with the given [id](Component::id), if it is of type `T`.
     pub fn find_id<T: 'static>(&mut self, id: &'static str) -> Option<&mut T> {
         self.layers
             .iter_mut()
@@ -293,3 +407,94 @@ pub fn is<T: Any>(&mut self) -> bool {
         self.as_any().is::<T>()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Layer {
+        id: &'static str,
+        z_index: i32,
+        persistent: bool,
+    }
+
+    impl Component for Layer {
+        fn render(&mut self, _area: Rect, _frame: &mut Surface, _ctx: &mut Context) {}
+
+        fn id(&self) -> Option<&'static str> {
+            Some(self.id)
+        }
+
+        fn z_index(&self) -> i32 {
+            self.z_index
+        }
+
+        fn persistent(&self) -> bool {
+            self.persistent
+        }
+    }
+
+    fn push(compositor: &mut Compositor, id: &'static str, z_index: i32, persistent: bool) {
+        compositor.push(Box::new(Layer {
+            id,
+            z_index,
+            persistent,
+        }));
+    }
+
+    fn ids(compositor: &Compositor) -> Vec<&'static str> {
+        compositor
+            .layers
+            .iter()
+            .filter_map(|layer| layer.id())
+            .collect()
+    }
+
+    #[test]
+    fn layers_are_stacked_by_z_index() {
+        let mut compositor = Compositor::new(Rect::new(0, 0, 80, 24));
+        push(&mut compositor, "editor", 0, false);
+        push(&mut compositor, "hud", 10, true);
+        push(&mut compositor, "sidebar", -1, true);
+        push(&mut compositor, "popup", 0, false);
+        assert_eq!(ids(&compositor), ["sidebar", "editor", "popup", "hud"]);
+
+        assert_eq!(compositor.pop().and_then(|layer| layer.id()), Some("popup"));
+        assert_eq!(
+            compositor.pop().and_then(|layer| layer.id()),
+            Some("editor")
+        );
+        assert!(compositor.pop().is_none());
+        assert!(compositor.find_id::<Layer>("hud").is_some());
+        assert_eq!(
+            compositor.remove("hud").and_then(|layer| layer.id()),
+            Some("hud")
+        );
+        assert_eq!(ids(&compositor), ["sidebar"]);
+    }
+
+    #[test]
+    fn dim_surface_blends_toward_background() {
+        let area = Rect::new(0, 0, 2, 1);
+        let mut surface = Surface::empty(area);
+        surface[(0, 0)].fg = Color::Rgb(200, 100, 0);
+        surface[(0, 0)].bg = Color::Rgb(0, 0, 0);
+        surface[(1, 0)].fg = Color::Red;
+
+        dim_surface(&mut surface, area, Some(Color::Rgb(0, 0, 100)), 50);
+        assert_eq!(surface[(0, 0)].fg, Color::Rgb(100, 50, 50));
+        assert_eq!(surface[(0, 0)].bg, Color::Rgb(0, 0, 50));
+        assert!(!surface[(0, 0)].modifier.contains(Modifier::DIM));
+        // Named colors can't be blended
+        assert_eq!(surface[(1, 0)].fg, Color::Red);
+        assert_eq!(surface[(1, 0)].bg, Color::Reset);
+        assert!(surface[(1, 0)].modifier.contains(Modifier::DIM));
+
+        // Without an RGB background everything is dimmed with the modifier
+        let mut surface = Surface::empty(area);
+        surface[(0, 0)].fg = Color::Rgb(200, 100, 0);
+        dim_surface(&mut surface, area, None, 50);
+        assert_eq!(surface[(0, 0)].fg, Color::Rgb(200, 100, 0));
+        assert!(surface[(0, 0)].modifier.contains(Modifier::DIM));
+    }
+}
@@ -1,13 +1,13 @@
 use crate::keymap;
 use crate::keymap::{merge_keys, KeyTrie};
-use helix_loader::merge_toml_values;
+use helix_loader::{config::ConfigDiagnostic, merge_toml_values};
 use helix_view::{document::Mode, theme};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs;
 use std::io::Error as IOError;
-use toml::de::Error as TomlError;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -36,7 +36,7 @@ fn default() -> Config {
 
 #[derive(Debug)]
 pub enum ConfigLoadError {
-    BadConfig(TomlError),
+    BadConfig(ConfigDiagnostic),
     Error(IOError),
 }
 
@@ -56,15 +56,30 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 }
 
 impl Config {
+    /// Loads the global and workspace config files, given as paths with their contents. The
+    /// paths point the errors at the file they were found in.
     pub fn load(
-        global: Result<String, ConfigLoadError>,
-        local: Result<String, ConfigLoadError>,
+        global: Result<(PathBuf, String), ConfigLoadError>,
+        local: Result<(PathBuf, String), ConfigLoadError>,
     ) -> Result<Config, ConfigLoadError> {
-        let global_config: Result<ConfigRaw, ConfigLoadError> =
-            global.and_then(|file| toml::from_str(&file).map_err(ConfigLoadError::BadConfig));
-        let local_config: Result<ConfigRaw, ConfigLoadError> =
-            local.and_then(|file| toml::from_str(&file).map_err(ConfigLoadError::BadConfig));
-        let res = match (global_config, local_config) {
+        let files: Vec<_> = [&global, &local]
+            .into_iter()
+            .filter_map(|file| file.as_ref().ok().cloned())
+            .collect();
+        // Errors from deserializing `toml::Value`s have no span, look for the key in the files.
+        let bad_config = |err: toml::de::Error| {
+            ConfigLoadError::BadConfig(ConfigDiagnostic::new(&err).locate(&files))
+        };
+        let parse = |file: Result<(PathBuf, String), ConfigLoadError>| {
+            file.and_then(|(path, source)| {
+                toml::from_str::<ConfigRaw>(&source).map_err(|err| {
+                    let diagnostic =
+                        ConfigDiagnostic::new(&err).with_source(&path, &source, err.span());
+                    ConfigLoadError::BadConfig(diagnostic)
+                })
+            })
+        };
+        let res = match (parse(global), parse(local)) {
             (Ok(global), Ok(local)) => {
                 let mut keys = keymap::default();
                 if let Some(global_keys) = global.keys {
@@ -76,12 +91,10 @@ pub fn load(
 
                 let editor = match (global.editor, local.editor) {
                     (None, None) => helix_view::editor::Config::default(),
-                    (None, Some(val)) | (Some(val), None) => {
-                        val.try_into().map_err(ConfigLoadError::BadConfig)?
-                    }
+                    (None, Some(val)) | (Some(val), None) => val.try_into().map_err(bad_config)?,
                     (Some(global), Some(local)) => merge_toml_values(global, local, 3)
                         .try_into()
-                        .map_err(ConfigLoadError::BadConfig)?,
+                        .map_err(bad_config)?,
                 };
 
                 Config {
@@ -105,7 +118,7 @@ pub fn load(
                     keys,
                     editor: config.editor.map_or_else(
                         || Ok(helix_view::editor::Config::default()),
-                        |val| val.try_into().map_err(ConfigLoadError::BadConfig),
+                        |val| val.try_into().map_err(bad_config),
                     )?,
                 }
             }
@@ -118,11 +131,15 @@ pub fn load(
     }
 
     pub fn load_default() -> Result<Config, ConfigLoadError> {
-        let global_config =
-            fs::read_to_string(helix_loader::config_file()).map_err(ConfigLoadError::Error);
-        let local_config = fs::read_to_string(helix_loader::workspace_config_file())
-            .map_err(ConfigLoadError::Error);
-        Config::load(global_config, local_config)
+        let read = |path: PathBuf| {
+            fs::read_to_string(&path)
+                .map(|source| (path, source))
+                .map_err(ConfigLoadError::Error)
+        };
+        Config::load(
+            read(helix_loader::config_file()),
+            read(helix_loader::workspace_config_file()),
+        )
     }
 }
 
@@ -132,7 +149,11 @@ mod tests {
 
     impl Config {
         fn load_test(config: &str) -> Config {
-            Config::load(Ok(config.to_owned()), Err(ConfigLoadError::default())).unwrap()
+            Config::load(
+                Ok((PathBuf::from("config.toml"), config.to_owned())),
+                Err(ConfigLoadError::default()),
+            )
+            .unwrap()
         }
     }
 
@@ -174,6 +195,33 @@ fn parsing_keymaps_config_file() {
         );
     }
 
+    #[test]
+    fn bad_config_is_located() {
+        let global = PathBuf::from("/home/user/.config/helix/config.toml");
+        let local = PathBuf::from("/project/.helix/config.toml");
+        let config = "[editor]\nscrolloff = 5\nline-numbr = \"relative\"\n";
+        let Err(ConfigLoadError::BadConfig(diagnostic)) = Config::load(
+            Ok((global.clone(), "theme = \"onedark\"\n".to_owned())),
+            Ok((local.clone(), config.to_owned())),
+        ) else {
+            panic!("expected a bad config");
+        };
+        assert_eq!(diagnostic.path, Some(local));
+        assert_eq!(diagnostic.key.as_deref(), Some("line-numbr"));
+        assert_eq!(diagnostic.suggestion.as_deref(), Some("line-number"));
+        assert_eq!(diagnostic.position, Some((3, 1)));
+
+        let config = "[keys.normal]\nC-s = \"no_such_command\"\n";
+        let Err(ConfigLoadError::BadConfig(diagnostic)) = Config::load(
+            Ok((global.clone(), config.to_owned())),
+            Err(ConfigLoadError::default()),
+        ) else {
+            panic!("expected a bad config");
+        };
+        assert_eq!(diagnostic.path, Some(global));
+        assert_eq!(diagnostic.position.map(|(line, _)| line), Some(2));
+    }
+
     #[test]
     fn keys_resolve_to_correct_defaults() {
         // From serde default
@@ -2,7 +2,8 @@
 use helix_view::document::Mode;
 use helix_view::events::{
     ConfigDidChange, DiagnosticsDidChange, DocumentDidChange, DocumentDidClose, DocumentDidOpen,
-    DocumentFocusLost, LanguageServerExited, LanguageServerInitialized, SelectionDidChange,
+    DocumentDidSave, DocumentDidStartLoading, DocumentEditRejected, DocumentFocusLost,
+    LanguageServerExited, LanguageServerInitialized, SelectionDidChange,
 };
 
 use crate::commands;
@@ -19,8 +20,11 @@ pub fn register() {
     register_event::<PostInsertChar>();
     register_event::<PostCommand>();
     register_event::<DocumentDidOpen>();
+    register_event::<DocumentDidStartLoading>();
+    register_event::<DocumentEditRejected>();
     register_event::<DocumentDidChange>();
     register_event::<DocumentDidClose>();
+    register_event::<DocumentDidSave>();
     register_event::<DocumentFocusLost>();
     register_event::<SelectionDidChange>();
     register_event::<DiagnosticsDidChange>();
@@ -1,12 +1,13 @@
+use std::fmt;
 use std::sync::Arc;
 
 use arc_swap::ArcSwap;
 use diagnostics::PullAllDocumentsDiagnosticHandler;
 use helix_event::AsyncHook;
+use tokio::sync::mpsc::Sender;
 
 use crate::config::Config;
 use crate::events;
-use crate::handlers::auto_save::AutoSaveHandler;
 use crate::handlers::diagnostics::PullDiagnosticsHandler;
 use crate::handlers::signature_help::SignatureHelpHandler;
 
@@ -14,20 +15,84 @@
 
 use self::document_colors::DocumentColorsHandler;
 
-mod auto_save;
+pub(crate) mod auto_save;
 pub mod completion;
 pub mod diagnostics;
 mod document_colors;
+pub(crate) mod fifo;
+mod file_watcher;
+mod image;
+mod journal;
+mod large_file;
 mod prompt;
+mod selection_history;
 mod signature_help;
 mod snippet;
+pub(crate) mod watch;
 
+/// Sets up the built-in handlers and hooks, see [`HandlersBuilder`] to add more.
 pub fn setup(config: Arc<ArcSwap<Config>>) -> Handlers {
+    HandlersBuilder::default().build(config)
+}
+
+type HandlerSetup = Box<dyn FnOnce(&Handlers) + Send>;
+
+/// Sets up the built-in handlers along with the hooks and async handlers of an embedder, for
+/// example an inline suggestion engine or a telemetry hook on `PostCommand`. They are set up in
+/// the order they were added, after the built-in ones, so the events of helix-term are already
+/// registered. Custom events must be registered with [`helix_event::register_event`] before
+/// hooks are registered for them.
+#[derive(Default)]
+pub struct HandlersBuilder {
+    setups: Vec<HandlerSetup>,
+}
+
+impl HandlersBuilder {
+    /// Adds a function registering hooks with [`helix_event::register_hook`]. It gets the
+    /// handlers of the editor to send events to the built-in handlers.
+    pub fn with_hooks(mut self, register: impl FnOnce(&Handlers) + Send + 'static) -> Self {
+        self.setups.push(Box::new(register));
+        self
+    }
+
+    /// Adds an async handler, spawned once the built-in handlers are, and a function
+    /// registering the hooks which send events to it.
+    pub fn with_async_handler<H: AsyncHook>(
+        self,
+        handler: H,
+        register: impl FnOnce(&Handlers, Sender<H::Event>) + Send + 'static,
+    ) -> Self {
+        self.with_hooks(move |handlers| register(handlers, handler.spawn()))
+    }
+
+    /// Spawns the built-in handlers, registers their hooks and then sets up the added hooks
+    /// and handlers. Must be called within a Tokio runtime, once per process.
+    pub fn build(self, config: Arc<ArcSwap<Config>>) -> Handlers {
+        let handlers = setup_builtin(config);
+        for setup in self.setups {
+            setup(&handlers);
+        }
+        handlers
+    }
+}
+
+impl fmt::Debug for HandlersBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandlersBuilder")
+            .field("setups", &self.setups.len())
+            .finish()
+    }
+}
+
+fn setup_builtin(config: Arc<ArcSwap<Config>>) -> Handlers {
     events::register();
 
+    if config.load().editor.file_watcher {
+        file_watcher::spawn();
+    }
+
     let event_tx = completion::CompletionHandler::new(config).spawn();
     let signature_hints = SignatureHelpHandler::new().spawn();
-    let auto_save = AutoSaveHandler::new().spawn();
     let document_colors = DocumentColorsHandler::default().spawn();
     let word_index = word_index::Handler::spawn();
     let pull_diagnostics = PullDiagnosticsHandler::default().spawn();
@@ -36,7 +101,6 @@ pub fn setup(config: Arc<ArcSwap<Config>>) -> Handlers {
     let handlers = Handlers {
         completions: helix_view::handlers::completion::CompletionHandler::new(event_tx),
         signature_hints,
-        auto_save,
         document_colors,
         word_index,
         pull_diagnostics,
@@ -46,10 +110,15 @@ pub fn setup(config: Arc<ArcSwap<Config>>) -> Handlers {
     helix_view::handlers::register_hooks(&handlers);
     completion::register_hooks(&handlers);
     signature_help::register_hooks(&handlers);
-    auto_save::register_hooks(&handlers);
     diagnostics::register_hooks(&handlers);
     snippet::register_hooks(&handlers);
     document_colors::register_hooks(&handlers);
+    fifo::register_hooks(&handlers);
+    image::register_hooks(&handlers);
+    journal::register_hooks(&handlers);
+    large_file::register_hooks(&handlers);
     prompt::register_hooks(&handlers);
+    selection_history::register_hooks(&handlers);
+    watch::register_hooks(&handlers);
     handlers
 }
@@ -3,12 +3,13 @@
 use helix_core::chars::char_is_word;
 use helix_core::completion::CompletionProvider;
 use helix_core::syntax::config::LanguageServerFeature;
+use helix_core::syntax::HighlightEvent;
 use helix_event::{register_hook, TaskHandle};
 use helix_lsp::lsp;
 use helix_stdx::rope::RopeSliceExt;
 use helix_view::document::Mode;
 use helix_view::handlers::completion::{CompletionEvent, ResponseContext};
-use helix_view::Editor;
+use helix_view::{Document, Editor};
 use tokio::task::JoinSet;
 
 use crate::commands;
@@ -143,6 +144,11 @@ pub fn trigger_auto_completion(editor: &Editor, trigger_char_only: bool) {
     #[cfg(not(windows))]
     let is_path_completion_trigger = matches!(cursor_char, Some(b'/'));
 
+    // Paths are usually typed inside of strings, so only the language server trigger
+    // characters are affected by the disabled scopes.
+    let is_trigger_char = is_trigger_char
+        && !in_disabled_scope(editor, doc, cursor, &config.auto_completion_disabled_scopes);
+
     let handler = &editor.handlers.completions;
     if is_trigger_char || (is_path_completion_trigger && doc.path_completion_enabled()) {
         handler.event(CompletionEvent::TriggerChar {
@@ -159,7 +165,8 @@ pub fn trigger_auto_completion(editor: &Editor, trigger_char_only: bool) {
             .chars_at(cursor)
             .reversed()
             .take(config.completion_trigger_len as usize)
-            .all(char_is_word);
+            .all(char_is_word)
+        && !in_disabled_scope(editor, doc, cursor, &config.auto_completion_disabled_scopes);
 
     if is_auto_trigger {
         handler.event(CompletionEvent::AutoTrigger {
@@ -170,6 +177,43 @@ pub fn trigger_auto_completion(editor: &Editor, trigger_char_only: bool) {
     }
 }
 
+/// Whether the text before `cursor` is highlighted with one of `scopes` (or a sub-scope of
+/// them), see `editor.auto-completion-disabled-scopes`.
+fn in_disabled_scope(editor: &Editor, doc: &Document, cursor: usize, scopes: &[String]) -> bool {
+    if scopes.is_empty() || cursor == 0 {
+        return false;
+    }
+    let Some(syntax) = doc.syntax() else {
+        return false;
+    };
+    let text = doc.text().slice(..);
+    let pos = cursor - 1;
+    let byte = text.char_to_byte(pos) as u32;
+    // Only highlight the line of the cursor, the highlighter also reports the scopes of
+    // nodes which started before it.
+    let line_start = text.line_to_byte(text.char_to_line(pos)) as u32;
+
+    let loader = editor.syn_loader.load();
+    let mut highlighter = syntax.highlighter(text, &loader, line_start..byte + 1);
+    let mut highlights = Vec::new();
+    while highlighter.next_event_offset() <= byte {
+        let (event, new_highlights) = highlighter.advance();
+        if event == HighlightEvent::Refresh {
+            highlights.clear();
+        }
+        highlights.extend(new_highlights);
+    }
+
+    highlights.into_iter().any(|highlight| {
+        let scope = editor.theme.scope(highlight);
+        scopes.iter().any(|disabled| {
+            scope
+                .strip_prefix(disabled.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        })
+    })
+}
+
 fn update_completion_filter(cx: &mut commands::Context, c: Option<char>) {
     cx.callback.push(Box::new(move |compositor, cx| {
         let editor_view = compositor.find::<ui::EditorView>().unwrap();
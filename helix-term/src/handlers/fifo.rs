@@ -0,0 +1,75 @@
+//! Reads named pipes opened as documents. Data written to the pipe is appended to the document
+//! as it arrives, which makes it possible to watch the output of a program in a split, for
+//! example with `mkfifo out && make > out` and `:open out`.
+
+use std::path::PathBuf;
+
+use helix_event::{cancelable_future, register_hook, TaskHandle};
+use helix_view::{events::DocumentDidOpen, handlers::Handlers, DocumentId};
+use tokio::io::AsyncReadExt;
+
+use crate::job;
+
+const BUF_SIZE: usize = 8192;
+
+async fn read_fifo(doc_id: DocumentId, path: PathBuf, cancel: TaskHandle) {
+    let mut buf = vec![0; BUF_SIZE];
+    let mut pending = Vec::new();
+    loop {
+        // Opening a pipe for reading waits until a program opens it for writing
+        let mut file = match cancelable_future(tokio::fs::File::open(&path), &cancel).await {
+            None => return,
+            Some(Ok(file)) => file,
+            Some(Err(err)) => {
+                log::error!("failed to open named pipe {}: {err}", path.display());
+                return;
+            }
+        };
+        loop {
+            let read = match cancelable_future(file.read(&mut buf), &cancel).await {
+                None => return,
+                // All writers closed the pipe, wait for the next one
+                Some(Ok(0)) => break,
+                Some(Ok(read)) => read,
+                Some(Err(err)) => {
+                    log::error!("failed to read named pipe {}: {err}", path.display());
+                    return;
+                }
+            };
+            pending.extend_from_slice(&buf[..read]);
+            let Some(text) = take_text(&mut pending) else {
+                continue;
+            };
+            job::dispatch(move |editor, _compositor| {
+                editor.append_to_document(doc_id, &text);
+            })
+            .await;
+        }
+    }
+}
+
+/// Takes the text read so far out of `pending`, keeping a character which was split between two
+/// reads for the next read.
+pub(crate) fn take_text(pending: &mut Vec<u8>) -> Option<String> {
+    let valid = match std::str::from_utf8(pending) {
+        Err(err) if err.error_len().is_none() => err.valid_up_to(),
+        _ => pending.len(),
+    };
+    if valid == 0 {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+    pending.drain(..valid);
+    Some(text)
+}
+
+pub(super) fn register_hooks(_handlers: &Handlers) {
+    register_hook!(move |event: &mut DocumentDidOpen<'_>| {
+        let doc = doc_mut!(event.editor, &event.doc);
+        if let Some(path) = doc.path().filter(|_| doc.is_fifo()).cloned() {
+            let cancel = doc.follow_controller.restart();
+            tokio::spawn(read_fifo(event.doc, path, cancel));
+        }
+        Ok(())
+    });
+}
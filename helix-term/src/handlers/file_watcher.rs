@@ -0,0 +1,179 @@
+//! Watches the workspace for files changed outside of the editor, for example by a `git
+//! checkout` or a code generator, see `editor.file-watcher`. The changes are sent to the
+//! language servers which registered interest in them with `workspace/didChangeWatchedFiles`
+//! and, with `editor.auto-reload`, the open documents of changed files are reloaded.
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use helix_lsp::lsp::FileChangeType;
+use notify::{
+    event::{ModifyKind, RenameMode},
+    EventKind, RecursiveMode, Watcher as _,
+};
+use tokio::sync::mpsc;
+
+use crate::{commands, job, ui::overlay::overlaid};
+
+/// Changes are sent once no file changed for this long, a checkout changes many files at once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Starts watching the workspace. The watcher runs as long as the editor.
+///
+/// Watching the workspace adds a watch for each of its directories, which takes a while in
+/// large workspaces, so it happens in the background instead of delaying the first frame.
+pub(super) fn spawn() {
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        if let Some((rx, watcher)) = watch() {
+            runtime.spawn(run(rx, watcher));
+        }
+    });
+}
+
+type Changes = mpsc::UnboundedReceiver<(PathBuf, FileChangeType)>;
+
+fn watch() -> Option<(Changes, notify::RecommendedWatcher)> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                for change in changes(event) {
+                    let _ = tx.send(change);
+                }
+            }
+            Err(err) => log::warn!("file watcher error: {err}"),
+        });
+    let (workspace, _) = helix_loader::find_workspace();
+    let watcher = watcher.and_then(|mut watcher| {
+        watcher.watch(&workspace, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    });
+    match watcher {
+        Ok(watcher) => Some((rx, watcher)),
+        Err(err) => {
+            log::error!("failed to watch {}: {err}", workspace.display());
+            None
+        }
+    }
+}
+
+/// The changes to send for an event of the watcher.
+fn changes(event: notify::Event) -> Vec<(PathBuf, FileChangeType)> {
+    let typ = match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            FileChangeType::CREATED
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            FileChangeType::DELETED
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            let mut paths = event.paths.into_iter();
+            return paths
+                .next()
+                .map(|from| (from, FileChangeType::DELETED))
+                .into_iter()
+                .chain(paths.next().map(|to| (to, FileChangeType::CREATED)))
+                .filter(|(path, _)| !is_ignored(path))
+                .collect();
+        }
+        EventKind::Modify(_) => FileChangeType::CHANGED,
+        EventKind::Access(_) | EventKind::Any | EventKind::Other => return Vec::new(),
+    };
+    event
+        .paths
+        .into_iter()
+        .filter(|path| !is_ignored(path))
+        .map(|path| (path, typ))
+        .collect()
+}
+
+/// Files in the `.git` directory change all the time and are of no interest to language
+/// servers.
+fn is_ignored(path: &Path) -> bool {
+    path.components()
+        .any(|component| component == Component::Normal(".git".as_ref()))
+}
+
+/// Merges a change into the pending change of the same file: a file created and changed since
+/// the last batch was created, one created and deleted again didn't change at all.
+fn merge(pending: &mut HashMap<PathBuf, FileChangeType>, path: PathBuf, typ: FileChangeType) {
+    match (pending.get(&path).copied(), typ) {
+        (Some(FileChangeType::CREATED), FileChangeType::DELETED) => {
+            pending.remove(&path);
+        }
+        (Some(FileChangeType::CREATED), _) => (),
+        (Some(FileChangeType::DELETED), FileChangeType::CREATED) => {
+            pending.insert(path, FileChangeType::CHANGED);
+        }
+        _ => {
+            pending.insert(path, typ);
+        }
+    }
+}
+
+async fn run(
+    mut rx: Changes,
+    // Dropping the watcher stops it
+    _watcher: notify::RecommendedWatcher,
+) {
+    let mut pending = HashMap::new();
+    loop {
+        let change = if pending.is_empty() {
+            rx.recv().await
+        } else {
+            match tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                Ok(change) => change,
+                Err(_) => {
+                    send(std::mem::take(&mut pending)).await;
+                    continue;
+                }
+            }
+        };
+        let Some((path, typ)) = change else {
+            return;
+        };
+        merge(&mut pending, path, typ);
+    }
+}
+
+async fn send(changes: HashMap<PathBuf, FileChangeType>) {
+    job::dispatch(move |editor, compositor| {
+        editor
+            .language_servers
+            .file_event_handler
+            .files_changed(changes.into_iter().collect());
+        if editor.config().auto_reload && editor.reload_changed_documents() {
+            // show the merge conflicts
+            let picker = commands::make_quickfix_picker(editor);
+            compositor.push(Box::new(overlaid(picker)));
+        }
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_changes() {
+        let path = PathBuf::from("src/lib.rs");
+        let mut pending = HashMap::new();
+        merge(&mut pending, path.clone(), FileChangeType::CREATED);
+        merge(&mut pending, path.clone(), FileChangeType::CHANGED);
+        assert_eq!(pending.get(&path), Some(&FileChangeType::CREATED));
+        merge(&mut pending, path.clone(), FileChangeType::DELETED);
+        assert!(pending.is_empty());
+
+        merge(&mut pending, path.clone(), FileChangeType::DELETED);
+        merge(&mut pending, path.clone(), FileChangeType::CREATED);
+        assert_eq!(pending.get(&path), Some(&FileChangeType::CHANGED));
+
+        assert!(is_ignored(&PathBuf::from("/project/.git/index")));
+        assert!(!is_ignored(&PathBuf::from("/project/src/git.rs")));
+    }
+}
@@ -0,0 +1,30 @@
+//! Opens image files in the image preview, see [`ImagePreview`].
+
+use helix_event::register_hook;
+use helix_view::{events::DocumentDidOpen, handlers::Handlers};
+
+use crate::{
+    job,
+    ui::{image::is_image_path, ImagePreview},
+};
+
+pub(super) fn register_hooks(_handlers: &Handlers) {
+    register_hook!(move |event: &mut DocumentDidOpen<'_>| {
+        let doc = doc!(event.editor, &event.doc);
+        let Some(path) = doc.path().filter(|path| is_image_path(path)).cloned() else {
+            return Ok(());
+        };
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(err) => {
+                log::error!("failed to read image {}: {err}", path.display());
+                return Ok(());
+            }
+        };
+        let doc_id = event.doc;
+        job::dispatch_blocking(move |_editor, compositor| {
+            compositor.push(Box::new(ImagePreview::new(path, doc_id, data)));
+        });
+        Ok(())
+    });
+}
@@ -0,0 +1,44 @@
+//! Appends the changes applied to documents to their edit journal, see `editor.edit-journal`.
+
+use std::path::{Path, PathBuf};
+
+use helix_event::register_hook;
+use helix_view::{events::DocumentDidChange, handlers::Handlers, journal};
+use tokio::{fs, io::AsyncWriteExt, sync::mpsc};
+
+async fn append_entry(file: &Path, entry: &str) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    let mut journal = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)
+        .await?;
+    journal.write_all(format!("{entry}\n").as_bytes()).await
+}
+
+pub(super) fn register_hooks(_handlers: &Handlers) {
+    // Entries are written in order by a single task so that writing doesn't block editing
+    let (tx, mut rx) = mpsc::unbounded_channel::<(PathBuf, String)>();
+    tokio::spawn(async move {
+        while let Some((file, entry)) = rx.recv().await {
+            if let Err(err) = append_entry(&file, &entry).await {
+                log::error!("failed to write edit journal {}: {err}", file.display());
+            }
+        }
+    });
+
+    register_hook!(move |event: &mut DocumentDidChange<'_>| {
+        // Ghost transactions are previews, like the completion preview, which are reverted
+        if event.ghost_transaction || !event.doc.config.load().edit_journal {
+            return Ok(());
+        }
+        if let Some(path) = event.doc.path() {
+            let time = chrono::Local::now().to_rfc3339();
+            let entry = journal::entry(&time, event.doc.version(), event.changes);
+            let _ = tx.send((journal::journal_file(path), entry));
+        }
+        Ok(())
+    });
+}
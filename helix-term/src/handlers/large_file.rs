@@ -0,0 +1,89 @@
+//! Reads large files opened as documents in the background, see `editor.large-file-threshold`.
+//! The document is empty and can't be edited until its text is loaded, the progress is shown
+//! in the statusline like for other named jobs.
+
+use std::io::Read;
+
+use anyhow::{anyhow, Context as _};
+use helix_event::{register_hook, status, TaskHandle};
+use helix_view::{
+    events::{DocumentDidStartLoading, DocumentEditRejected},
+    handlers::Handlers,
+};
+
+use crate::job::{self, Job, JobId};
+
+/// Reads a file and reports the share of it which was read as the progress of a job. Reading
+/// fails once the document is closed.
+struct ProgressReader<R> {
+    inner: R,
+    len: u64,
+    read: u64,
+    /// The percentage reported last.
+    reported: Option<u64>,
+    job: Option<JobId>,
+    cancel: TaskHandle,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.cancel.is_canceled() {
+            return Err(std::io::Error::other("the document was closed"));
+        }
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+        let percent = (self.read * 100).checked_div(self.len).unwrap_or(100);
+        if self.reported != Some(percent) {
+            self.reported = Some(percent);
+            if let Some(job) = self.job {
+                job::report_progress(job, format!("{percent}%"));
+            }
+        }
+        Ok(read)
+    }
+}
+
+pub(super) fn register_hooks(_handlers: &Handlers) {
+    register_hook!(move |event: &mut DocumentDidStartLoading<'_>| {
+        let doc_id = event.doc;
+        let loader = event.editor.syn_loader.clone();
+        let doc = doc_mut!(event.editor, &doc_id);
+        let Some(pending) = doc.pending_load().cloned() else {
+            return Ok(());
+        };
+        let cancel = doc.load_controller.restart();
+        let name = format!("load {}", doc.display_name());
+
+        let load = async move {
+            let job = job::current_job();
+            let reader_cancel = cancel.clone();
+            let loaded = tokio::task::spawn_blocking(move || {
+                let file = std::fs::File::open(&pending.path)?;
+                let mut reader = ProgressReader {
+                    len: file.metadata()?.len(),
+                    inner: file,
+                    read: 0,
+                    reported: None,
+                    job,
+                    cancel: reader_cancel,
+                };
+                pending
+                    .read(&mut reader, &loader.load())
+                    .with_context(|| format!("failed to load {}", pending.path.display()))
+            })
+            .await?;
+            if cancel.is_canceled() {
+                return anyhow::Ok(());
+            }
+            let loaded = loaded?;
+            job::dispatch(move |editor, _compositor| editor.finish_loading(doc_id, loaded)).await;
+            Ok(())
+        };
+        job::spawn(Job::new(load).named(name));
+        Ok(())
+    });
+    register_hook!(move |_event: &mut DocumentEditRejected<'_>| {
+        status::report_blocking(anyhow!("can't edit file, it is still loading"));
+        Ok(())
+    });
+}
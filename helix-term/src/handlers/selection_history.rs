@@ -0,0 +1,12 @@
+use helix_event::register_hook;
+use helix_view::handlers::Handlers;
+
+use crate::events::PostCommand;
+
+pub(super) fn register_hooks(_handlers: &Handlers) {
+    register_hook!(move |event: &mut PostCommand<'_, '_>| {
+        let (view, doc) = current!(event.cx.editor);
+        view.selection_history.update(doc, doc.selection(view.id));
+        Ok(())
+    });
+}
@@ -0,0 +1,107 @@
+//! Runs the command of watch mode, see `:watch`, each time a file of the workspace is saved.
+
+use std::{io, path::Path, process::Stdio};
+
+use helix_event::register_hook;
+use helix_view::{
+    doc,
+    events::DocumentDidSave,
+    handlers::Handlers,
+    quickfix::QuickfixList,
+    watch::{self, WatchState},
+    Editor,
+};
+use tokio::process::Command;
+
+use crate::job;
+
+pub(super) fn register_hooks(_handlers: &Handlers) {
+    register_hook!(move |event: &mut DocumentDidSave<'_>| {
+        let editor = &mut *event.editor;
+        let (workspace, _) = helix_loader::find_workspace();
+        let in_workspace = doc!(editor, &event.doc)
+            .path()
+            .is_some_and(|path| path.starts_with(&workspace));
+        if !in_workspace {
+            return Ok(());
+        }
+        match editor.watch.as_mut() {
+            Some(task) if task.state == WatchState::Running => task.rerun = true,
+            Some(_) => run(editor),
+            None => (),
+        }
+        Ok(())
+    });
+}
+
+/// Runs the command of the watch task in the working directory. Once it finished the
+/// locations in its output replace the quickfix list.
+pub(crate) fn run(editor: &mut Editor) {
+    let Some(task) = editor.watch.as_mut() else {
+        return;
+    };
+    task.state = WatchState::Running;
+    task.rerun = false;
+    let command = task.command.clone();
+    let shell = editor.config().shell.clone();
+    let cwd = helix_stdx::env::current_working_dir();
+    tokio::spawn(async move {
+        let result = run_command(&shell, &command, &cwd).await;
+        job::dispatch(move |editor, _| finish(editor, &command, &cwd, result)).await;
+    });
+}
+
+/// Runs `command` with `shell`. Returns whether it succeeded and its output, stderr after
+/// stdout.
+async fn run_command(shell: &[String], command: &str, cwd: &Path) -> io::Result<(bool, String)> {
+    let Some((program, args)) = shell.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no shell set in editor.shell",
+        ));
+    };
+    let output = Command::new(program)
+        .args(args)
+        .arg(command)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.success(), text))
+}
+
+fn finish(editor: &mut Editor, command: &str, cwd: &Path, result: io::Result<(bool, String)>) {
+    // The task was stopped or replaced while the command was running
+    let Some(task) = editor.watch.as_mut().filter(|task| task.command == command) else {
+        return;
+    };
+    let rerun = task.rerun;
+    match result {
+        Ok((passed, output)) => {
+            let entries = watch::parse_locations(&output, cwd);
+            task.state = if passed {
+                WatchState::Passed
+            } else {
+                WatchState::Failed(entries.len())
+            };
+            if passed {
+                editor.set_status(format!("`{command}` passed"));
+            } else {
+                editor.set_error(format!(
+                    "`{command}` failed with {} locations, see :quickfix",
+                    entries.len()
+                ));
+            }
+            editor.quickfix = QuickfixList::new(format!("watch: {command}"), entries);
+        }
+        Err(err) => {
+            task.state = WatchState::Failed(0);
+            editor.set_error(format!("Failed to run `{command}`: {err}"));
+        }
+    }
+    if rerun {
+        run(editor);
+    }
+}
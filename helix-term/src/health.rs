@@ -1,5 +1,7 @@
 use crate::config::{Config, ConfigLoadError};
 use helix_core::config::{default_lang_config, user_lang_config};
+use helix_core::syntax::config::LanguageConfiguration;
+use helix_core::tree_sitter::Query;
 use helix_loader::grammar::load_runtime_file;
 use std::{
     collections::HashSet,
@@ -150,6 +152,59 @@ pub fn clipboard() -> std::io::Result<()> {
     Ok(())
 }
 
+/// Display the capabilities of the terminal, asked the same way as when the editor starts.
+pub fn terminal() -> std::io::Result<()> {
+    let stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        writeln!(
+            stdout.lock(),
+            "Terminal: not a terminal, capabilities not checked"
+        )?;
+        return Ok(());
+    }
+
+    // The replies to the queries can only be read in raw mode
+    let mut platform_terminal = termina::PlatformTerminal::new()?;
+    platform_terminal.enter_raw_mode()?;
+    let capabilities = tui::backend::AlacrittyBackend::new(std::io::stdout())?.probe();
+    platform_terminal.enter_cooked_mode()?;
+
+    let mut stdout = stdout.lock();
+    let term = std::env::var("TERM").unwrap_or_default();
+    writeln!(stdout, "Terminal: {}", term)?;
+    let images = if capabilities.kitty_graphics {
+        "kitty graphics protocol"
+    } else if capabilities.sixel {
+        "sixel"
+    } else {
+        "no"
+    };
+    let yes_no = |supported: bool| if supported { "yes" } else { "no" };
+    for (name, value) in [
+        ("True color", yes_no(capabilities.true_color)),
+        ("Undercurl", yes_no(capabilities.extended_underlines)),
+        (
+            "Kitty keyboard protocol",
+            yes_no(capabilities.kitty_keyboard),
+        ),
+        (
+            "Synchronized output",
+            yes_no(capabilities.synchronized_output),
+        ),
+        ("Images", images),
+        ("Hyperlinks", yes_no(capabilities.hyperlinks)),
+    ] {
+        let msg = format!("    {}: {}", name, value);
+        if value == "no" {
+            writeln!(stdout, "{}", msg.yellow())?;
+        } else {
+            writeln!(stdout, "{}", msg)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn languages_all() -> std::io::Result<()> {
     languages(None)
 }
@@ -355,6 +410,70 @@ pub fn language(lang_str: String) -> std::io::Result<()> {
         probe_treesitter_feature(&lang_str, *ts_feat)?
     }
 
+    probe_custom_textobjects(lang)?;
+    probe_surround_pairs(lang)?;
+
+    Ok(())
+}
+
+/// The keys of the built-in text objects after `mi` and `ma`.
+const BUILTIN_TEXTOBJECT_KEYS: &[char] = &[
+    'w', 'W', 's', 'p', 't', 'f', 'a', 'c', 'T', 'e', 'm', 'g', 'x',
+];
+
+/// Display diagnostics about the text objects defined in the language configuration.
+fn probe_custom_textobjects(lang: &LanguageConfiguration) -> std::io::Result<()> {
+    if lang.textobjects.is_empty() {
+        return Ok(());
+    }
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+
+    writeln!(stdout, "Configured textobjects:")?;
+    let grammar_name = lang.grammar.as_deref().unwrap_or(&lang.language_id);
+    let grammar = helix_loader::grammar::get_language(grammar_name)
+        .ok()
+        .flatten();
+    for textobject in &lang.textobjects {
+        let error = match textobject.key {
+            Some(key) if BUILTIN_TEXTOBJECT_KEYS.contains(&key) => {
+                Some(format!("the key '{key}' is used by a built-in textobject"))
+            }
+            _ => match grammar {
+                Some(grammar) => Query::new(grammar, &textobject.query, |_, _| Ok(()))
+                    .err()
+                    .map(|err| err.to_string()),
+                None => Some("the tree-sitter parser is not installed".to_string()),
+            },
+        };
+        match error {
+            None => writeln!(stdout, "  {} {}", "✓".green(), textobject.name)?,
+            Some(err) => writeln!(stdout, "  {} {}: {}", "✘".red(), textobject.name, err.red())?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Display diagnostics about the surround pairs defined in the language configuration.
+fn probe_surround_pairs(lang: &LanguageConfiguration) -> std::io::Result<()> {
+    if lang.surround_pairs.is_empty() {
+        return Ok(());
+    }
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+
+    writeln!(stdout, "Configured surround pairs:")?;
+    for (open, close) in &lang.surround_pairs {
+        // `mi` and `ma` treat alphanumeric characters as text object keys
+        if open.is_ascii_alphanumeric() || close.is_ascii_alphanumeric() {
+            let err = "alphanumeric characters can't be used as pairs by `mi` and `ma`";
+            writeln!(stdout, "  {} {open}{close}: {}", "✘".red(), err.red())?;
+        } else {
+            writeln!(stdout, "  {} {open}{close}", "✓".green())?;
+        }
+    }
+
     Ok(())
 }
 
@@ -438,15 +557,18 @@ pub fn print_health(health_arg: Option<String>) -> std::io::Result<()> {
         Some("languages") => languages_selection()?,
         Some("all-languages") => languages_all()?,
         Some("clipboard") => clipboard()?,
+        Some("terminal") => terminal()?,
         None => {
             general()?;
             clipboard()?;
+            terminal()?;
             writeln!(std::io::stdout().lock())?;
             languages_selection()?;
         }
         Some("all") => {
             general()?;
             clipboard()?;
+            terminal()?;
             writeln!(std::io::stdout().lock())?;
             languages_all()?;
         }
@@ -0,0 +1,258 @@
+//! Work that runs while the editor is idle.
+//!
+//! Features which want to run after the user stops typing (inlay hints, auto-save, ...) register
+//! an [`IdleTask`] with the [`IdleScheduler`] instead of reacting to `Event::IdleTimeout` on
+//! their own. When the idle timer fires the scheduler runs the registered tasks in priority
+//! order. A task can also wait for a longer delay than the idle timeout, the scheduler then
+//! fires the idle timer again once it is due.
+//!
+//! Every round has a time budget. Once it is used up the remaining tasks are deferred to a
+//! follow-up round which starts as soon as the event loop has handled any pending input, so a
+//! slow task can't hold up typing. Each task also has its own budget: a task which overruns it
+//! is skipped for a few rounds so that it doesn't starve the tasks after it.
+
+use std::time::{Duration, Instant};
+
+use helix_view::Editor;
+
+use crate::job::Jobs;
+
+/// The time a single round of idle tasks may take before the rest are deferred.
+const ROUND_BUDGET: Duration = Duration::from_millis(16);
+
+/// The maximum number of rounds a task is skipped for after overrunning its budget.
+const MAX_BACKOFF: u32 = 8;
+
+/// The order in which idle tasks run. Tasks with a higher priority run first, tasks with the
+/// same priority run in the order they were registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IdlePriority {
+    Low,
+    Normal,
+    High,
+}
+
+type IdleFn = Box<dyn FnMut(&mut Editor, &mut Jobs)>;
+
+pub struct IdleTask {
+    name: &'static str,
+    priority: IdlePriority,
+    budget: Duration,
+    run: IdleFn,
+    /// How long the editor has to be idle before the task runs, see [`IdleTask::after`].
+    delay: Option<fn(&Editor) -> Duration>,
+    /// Consecutive rounds in which the task overran its budget.
+    overruns: u32,
+    /// Rounds left before the task runs again.
+    skip: u32,
+}
+
+impl IdleTask {
+    pub fn new(
+        name: &'static str,
+        priority: IdlePriority,
+        budget: Duration,
+        run: impl FnMut(&mut Editor, &mut Jobs) + 'static,
+    ) -> Self {
+        Self {
+            name,
+            priority,
+            budget,
+            run: Box::new(run),
+            delay: None,
+            overruns: 0,
+            skip: 0,
+        }
+    }
+
+    /// Runs the task only once the editor has been idle for `delay`, which is read from the
+    /// editor on every round so that it can come from the config.
+    pub fn after(mut self, delay: fn(&Editor) -> Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Runs the task if the editor has been idle for long enough, `idle_for`, returning the time
+    /// it took.
+    fn run(&mut self, editor: &mut Editor, jobs: &mut Jobs, idle_for: Duration) -> Duration {
+        if let Some(delay) = self.delay.map(|delay| delay(editor)) {
+            if idle_for < delay {
+                editor.schedule_idle_timer(delay - idle_for);
+                return Duration::ZERO;
+            }
+        }
+        let start = Instant::now();
+        (self.run)(editor, jobs);
+        let elapsed = start.elapsed();
+        self.record(elapsed);
+        elapsed
+    }
+
+    /// Backs off from a task which overran its budget.
+    fn record(&mut self, elapsed: Duration) {
+        if elapsed > self.budget {
+            self.overruns += 1;
+            self.skip = self.overruns.min(MAX_BACKOFF);
+            log::warn!(
+                "idle task '{}' took {elapsed:?} (budget {:?}), skipping it for {} rounds",
+                self.name,
+                self.budget,
+                self.skip
+            );
+        } else {
+            self.overruns = 0;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct IdleScheduler {
+    /// Registered tasks, sorted by descending priority.
+    tasks: Vec<IdleTask>,
+    /// Indices of the tasks which didn't fit into the previous round.
+    deferred: Vec<usize>,
+    /// Roughly when the last input was, for the tasks which wait for a longer delay.
+    idle_since: Option<Instant>,
+}
+
+impl IdleScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a task to run whenever the editor becomes idle.
+    pub fn register(&mut self, task: IdleTask) {
+        self.cancel();
+        let idx = self
+            .tasks
+            .partition_point(|existing| existing.priority >= task.priority);
+        self.tasks.insert(idx, task);
+    }
+
+    /// Drops the tasks deferred from the last round. Called on input since the deferred tasks
+    /// would work with outdated state, the next idle timeout starts a fresh round.
+    pub fn cancel(&mut self) {
+        self.deferred.clear();
+        self.idle_since = None;
+    }
+
+    /// Runs one round of idle tasks. If the round budget runs out, the remaining tasks are
+    /// deferred and the idle timer is scheduled to fire again right away.
+    pub fn run(&mut self, editor: &mut Editor, jobs: &mut Jobs) {
+        // The first round starts when the idle timeout elapsed after the last input
+        let idle_timeout = editor.config().idle_timeout;
+        let idle_since = *self.idle_since.get_or_insert_with(|| {
+            let now = Instant::now();
+            now.checked_sub(idle_timeout).unwrap_or(now)
+        });
+        let idle_for = idle_since.elapsed();
+        if self.round(|task| task.run(editor, jobs, idle_for)) {
+            editor.schedule_idle_timer(Duration::ZERO);
+        }
+    }
+
+    /// Runs the tasks of a round with `run` until the round budget runs out. Returns whether
+    /// tasks were deferred to the next round.
+    fn round(&mut self, mut run: impl FnMut(&mut IdleTask) -> Duration) -> bool {
+        let queue = if self.deferred.is_empty() {
+            let mut queue = Vec::with_capacity(self.tasks.len());
+            for (idx, task) in self.tasks.iter_mut().enumerate() {
+                if task.skip > 0 {
+                    task.skip -= 1;
+                } else {
+                    queue.push(idx);
+                }
+            }
+            queue
+        } else {
+            std::mem::take(&mut self.deferred)
+        };
+
+        let mut elapsed = Duration::ZERO;
+        for (i, &idx) in queue.iter().enumerate() {
+            if elapsed >= ROUND_BUDGET {
+                self.deferred = queue[i..].to_vec();
+                return true;
+            }
+            elapsed += run(&mut self.tasks[idx]);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &'static str, priority: IdlePriority) -> IdleTask {
+        IdleTask::new(name, priority, Duration::from_millis(5), |_, _| {})
+    }
+
+    /// Runs a round in which every task takes `took`, returning the names of the tasks run.
+    fn round(scheduler: &mut IdleScheduler, took: Duration) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        scheduler.round(|task| {
+            names.push(task.name);
+            task.record(took);
+            took
+        });
+        names
+    }
+
+    #[test]
+    fn tasks_run_by_priority() {
+        let mut scheduler = IdleScheduler::new();
+        scheduler.register(task("low", IdlePriority::Low));
+        scheduler.register(task("high", IdlePriority::High));
+        scheduler.register(task("normal", IdlePriority::Normal));
+        scheduler.register(task("normal 2", IdlePriority::Normal));
+
+        assert_eq!(
+            round(&mut scheduler, Duration::ZERO),
+            ["high", "normal", "normal 2", "low"]
+        );
+    }
+
+    #[test]
+    fn tasks_over_the_round_budget_are_deferred() {
+        let mut scheduler = IdleScheduler::new();
+        for name in ["a", "b", "c", "d"] {
+            let task = IdleTask::new(name, IdlePriority::Normal, ROUND_BUDGET, |_, _| {});
+            scheduler.register(task);
+        }
+        let took = ROUND_BUDGET / 2;
+
+        assert_eq!(round(&mut scheduler, took), ["a", "b"]);
+        assert_eq!(round(&mut scheduler, took), ["c", "d"]);
+
+        // Input drops the deferred tasks, the next round starts over
+        assert_eq!(round(&mut scheduler, took), ["a", "b"]);
+        scheduler.cancel();
+        assert_eq!(round(&mut scheduler, Duration::ZERO), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn tasks_over_their_budget_back_off() {
+        let mut scheduler = IdleScheduler::new();
+        scheduler.register(task("slow", IdlePriority::Normal));
+        scheduler.register(task("fast", IdlePriority::Low));
+
+        let mut slow_rounds = 0;
+        for _ in 0..4 {
+            let mut names = Vec::new();
+            scheduler.round(|task| {
+                names.push(task.name);
+                let took = match task.name {
+                    "slow" => Duration::from_millis(10),
+                    _ => Duration::ZERO,
+                };
+                task.record(took);
+                took
+            });
+            assert!(names.contains(&"fast"));
+            slow_rounds += names.contains(&"slow") as usize;
+        }
+        // Skipped for one round after the first overrun, then for two
+        assert_eq!(slow_rounds, 2);
+    }
+}
@@ -1,5 +1,9 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
 use helix_event::status::StatusMessage;
-use helix_event::{runtime_local, send_blocking};
+use helix_event::{request_redraw, runtime_local, send_blocking};
 use helix_view::Editor;
 use once_cell::sync::OnceCell;
 
@@ -8,12 +12,21 @@
 use futures_util::future::{BoxFuture, Future, FutureExt};
 use futures_util::stream::{FuturesUnordered, StreamExt};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::task::AbortHandle;
 
 pub type EditorCompositorCallback = Box<dyn FnOnce(&mut Editor, &mut Compositor) + Send>;
 pub type EditorCallback = Box<dyn FnOnce(&mut Editor) + Send>;
 
 runtime_local! {
     static JOB_QUEUE: OnceCell<Sender<Callback>> = OnceCell::new();
+    /// The named jobs which are still running, in the order they were started.
+    static RUNNING_JOBS: Mutex<Vec<RunningJob>> = Mutex::new(Vec::new());
+}
+
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(0);
+
+tokio::task_local! {
+    static CURRENT_JOB: JobId;
 }
 
 pub async fn dispatch_callback(job: Callback) {
@@ -43,6 +56,119 @@ pub struct Job {
     pub future: BoxFuture<'static, anyhow::Result<Option<Callback>>>,
     /// Do we need to wait for this job to finish before exiting?
     pub wait: bool,
+    /// Named jobs are listed in the statusline and by `:jobs` while they run.
+    pub name: Option<String>,
+}
+
+/// Identifies a running named job, see [`running_jobs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobId(u64);
+
+/// A named job which is still running.
+#[derive(Debug, Clone)]
+pub struct RunningJob {
+    pub id: JobId,
+    pub name: String,
+    pub started: Instant,
+    /// The last progress reported with [`report_progress`].
+    pub progress: Option<String>,
+    /// Jobs which must finish before exiting can't be cancelled.
+    abort: Option<AbortHandle>,
+}
+
+impl RunningJob {
+    pub fn cancellable(&self) -> bool {
+        self.abort.is_some()
+    }
+}
+
+/// The named jobs which are still running, in the order they were started.
+pub fn running_jobs() -> Vec<RunningJob> {
+    RUNNING_JOBS.lock().unwrap().clone()
+}
+
+/// The job the current task runs, `None` outside of named jobs. Blocking tasks spawned by a
+/// job don't run in it, they can report progress with the id of the job.
+pub fn current_job() -> Option<JobId> {
+    CURRENT_JOB.try_with(|id| *id).ok()
+}
+
+/// Sets the progress of a running job shown by `:jobs`, for example `3/10 files`.
+pub fn report_progress(id: JobId, progress: impl Into<String>) {
+    let mut jobs = RUNNING_JOBS.lock().unwrap();
+    if let Some(job) = jobs.iter_mut().find(|job| job.id == id) {
+        job.progress = Some(progress.into());
+        request_redraw();
+    }
+}
+
+/// Cancels a running job, its callback isn't run. Returns the name of the job, `None` if it
+/// already finished or can't be cancelled.
+pub fn cancel(id: JobId) -> Option<String> {
+    let job = {
+        let mut jobs = RUNNING_JOBS.lock().unwrap();
+        let i = jobs
+            .iter()
+            .position(|job| job.id == id && job.cancellable())?;
+        jobs.remove(i)
+    };
+    // The lock is released first, dropping the job's future unregisters it
+    job.abort?.abort();
+    request_redraw();
+    Some(job.name)
+}
+
+/// Removes a named job from the running jobs when it finishes or is dropped.
+struct Registration(JobId);
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        RUNNING_JOBS.lock().unwrap().retain(|job| job.id != self.0);
+        request_redraw();
+    }
+}
+
+/// Adds a named job to the running jobs, the returned future runs `future` as the job.
+fn register(name: String, future: JobFuture) -> (JobId, JobFuture) {
+    let id = JobId(NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed));
+    RUNNING_JOBS.lock().unwrap().push(RunningJob {
+        id,
+        name,
+        started: Instant::now(),
+        progress: None,
+        abort: None,
+    });
+    let registration = Registration(id);
+    let future = CURRENT_JOB.scope(id, async move {
+        let _registration = registration;
+        future.await
+    });
+    (id, future.boxed())
+}
+
+/// Runs a job in the background, like [`Jobs::add`] does for jobs which don't need to be
+/// waited on, for hooks and other code which can't reach the [`Jobs`] of the event loop.
+pub fn spawn(job: Job) {
+    let (id, future) = match job.name {
+        Some(name) => {
+            let (id, future) = register(name, job.future);
+            (Some(id), future)
+        }
+        None => (None, job.future),
+    };
+    let task = tokio::spawn(async move {
+        match helix_view::resources::limit_background_job(future).await {
+            Ok(Some(cb)) => dispatch_callback(cb).await,
+            Ok(None) => (),
+            Err(err) => helix_event::status::report(err).await,
+        }
+    });
+    if let Some(id) = id {
+        let mut jobs = RUNNING_JOBS.lock().unwrap();
+        if let Some(job) = jobs.iter_mut().find(|job| job.id == id) {
+            job.abort = Some(task.abort_handle());
+        }
+    }
 }
 
 pub struct Jobs {
@@ -57,6 +183,7 @@ pub fn new<F: Future<Output = anyhow::Result<()>> + Send + 'static>(f: F) -> Sel
         Self {
             future: f.map(|r| r.map(|()| None)).boxed(),
             wait: false,
+            name: None,
         }
     }
 
@@ -66,6 +193,7 @@ pub fn with_callback<F: Future<Output = anyhow::Result<Callback>> + Send + 'stat
         Self {
             future: f.map(|r| r.map(Some)).boxed(),
             wait: false,
+            name: None,
         }
     }
 
@@ -73,6 +201,11 @@ pub fn wait_before_exiting(mut self) -> Self {
         self.wait = true;
         self
     }
+
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
 }
 
 impl Jobs {
@@ -117,18 +250,18 @@ pub fn handle_callback(
         }
     }
 
+    /// Jobs which don't need to be waited on run in the background, at most
+    /// `editor.resources.max-background-jobs` of them at once.
+    /// Named jobs are tracked until they finish, see [`running_jobs`].
     pub fn add(&self, j: Job) {
-        if j.wait {
-            self.wait_futures.push(j.future);
-        } else {
-            tokio::spawn(async move {
-                match j.future.await {
-                    Ok(Some(cb)) => dispatch_callback(cb).await,
-                    Ok(None) => (),
-                    Err(err) => helix_event::status::report(err).await,
-                }
-            });
+        if !j.wait {
+            return spawn(j);
         }
+        let future = match j.name {
+            Some(name) => register(name, j.future).1,
+            None => j.future,
+        };
+        self.wait_futures.push(future);
     }
 
     /// Blocks until all the jobs that need to be waited on are done.
@@ -169,3 +302,67 @@ pub async fn finish(
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn running(id: JobId) -> Option<RunningJob> {
+        running_jobs().into_iter().find(|job| job.id == id)
+    }
+
+    #[tokio::test]
+    async fn registered_jobs_run_until_they_finish() {
+        let seen = Arc::new(Mutex::new(None));
+        let job_seen = seen.clone();
+        let (id, future) = register(
+            "registered".to_string(),
+            async move {
+                *job_seen.lock().unwrap() = current_job();
+                Ok(None)
+            }
+            .boxed(),
+        );
+        let job = running(id).unwrap();
+        assert_eq!(job.name, "registered");
+        assert_eq!(job.progress, None);
+        // Only jobs spawned in the background can be cancelled
+        assert!(!job.cancellable());
+        assert_eq!(cancel(id), None);
+
+        report_progress(id, "1/2");
+        assert_eq!(running(id).unwrap().progress.as_deref(), Some("1/2"));
+
+        assert!(future.await.unwrap().is_none());
+        assert_eq!(*seen.lock().unwrap(), Some(id));
+        assert_eq!(current_job(), None);
+        assert!(running(id).is_none());
+    }
+
+    #[tokio::test]
+    async fn dropped_jobs_are_unregistered() {
+        let (id, future) = register("dropped".to_string(), std::future::pending().boxed());
+        assert!(running(id).is_some());
+        drop(future);
+        assert!(running(id).is_none());
+        // Progress of jobs which finished is ignored
+        report_progress(id, "done");
+        assert!(running(id).is_none());
+    }
+
+    #[tokio::test]
+    async fn spawned_jobs_can_be_cancelled() {
+        let name = "cancelled";
+        spawn(Job::new(std::future::pending()).named(name));
+        let job = running_jobs()
+            .into_iter()
+            .find(|job| job.name == name)
+            .unwrap();
+        assert!(job.cancellable());
+        assert_eq!(cancel(job.id).as_deref(), Some(name));
+        assert!(running(job.id).is_none());
+        assert_eq!(cancel(job.id), None);
+    }
+}
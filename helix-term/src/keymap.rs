@@ -13,6 +13,7 @@
     collections::{BTreeSet, HashMap},
     ops::{Deref, DerefMut},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 pub use default::default;
@@ -24,7 +25,21 @@ pub struct KeyTrieNode {
     name: String,
     map: HashMap<KeyEvent, KeyTrie>,
     order: Vec<KeyEvent>,
-    pub is_sticky: bool,
+    /// Whether the node stays active after running a command, see [`Self::is_sticky`]. `None`
+    /// unless set, so that merging a node which doesn't set it keeps the node's setting.
+    pub sticky: Option<bool>,
+    /// How long to wait for the next key before giving up on this prefix. `None` waits
+    /// indefinitely.
+    pub timeout: Option<Duration>,
+    /// The command to run when the timeout elapses. Without one the pending keys are cancelled,
+    /// which for example inserts them in insert mode.
+    pub on_timeout: Option<MappableCommand>,
+    /// Whether keys which aren't bound in this node are looked up in the outer keymap instead
+    /// of cancelling the pending keys.
+    pub fallthrough: bool,
+    /// Keys which leave this node when it is sticky, in addition to `Esc`. A bound exit key runs
+    /// its command before leaving.
+    pub exit: Vec<KeyEvent>,
 }
 
 impl<'de> Deserialize<'de> for KeyTrieNode {
@@ -48,10 +63,22 @@ pub fn new(name: &str, map: HashMap<KeyEvent, KeyTrie>, order: Vec<KeyEvent>) ->
             name: name.to_string(),
             map,
             order,
-            is_sticky: false,
+            sticky: None,
+            timeout: None,
+            on_timeout: None,
+            fallthrough: false,
+            exit: Vec::new(),
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_sticky(&self) -> bool {
+        self.sticky == Some(true)
+    }
+
     /// Merge another Node in. Leaves and subnodes from the other node replace
     /// corresponding keyevent in self, except when both other and self have
     /// subnodes for same key. In that case the merge is recursive.
@@ -70,6 +97,24 @@ pub fn merge(&mut self, mut other: Self) {
                 self.order.push(key);
             }
         }
+        if other.timeout.is_some() {
+            self.timeout = other.timeout;
+        }
+        if other.on_timeout.is_some() {
+            self.on_timeout = other.on_timeout;
+        }
+        self.fallthrough |= other.fallthrough;
+        if !other.name.is_empty() {
+            self.name = other.name;
+        }
+        if other.sticky.is_some() {
+            self.sticky = other.sticky;
+        }
+        for key in other.exit {
+            if !self.exit.contains(&key) {
+                self.exit.push(key);
+            }
+        }
     }
 
     pub fn infobox(&self) -> Info {
@@ -99,6 +144,16 @@ pub fn infobox(&self) -> Info {
                 .unwrap()
         });
 
+        let exit: BTreeSet<_> = self
+            .exit
+            .iter()
+            .filter(|key| !self.map.contains_key(key))
+            .copied()
+            .collect();
+        if !exit.is_empty() {
+            body.push((exit, "Exit"));
+        }
+
         let body: Vec<_> = body
             .into_iter()
             .map(|(events, desc)| {
@@ -199,11 +254,44 @@ fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
     {
         let mut mapping = HashMap::new();
         let mut order = Vec::new();
-        while let Some((key, value)) = map.next_entry::<KeyEvent, KeyTrie>()? {
-            mapping.insert(key, value);
-            order.push(key);
+        let mut timeout = None;
+        let mut on_timeout = None;
+        let mut fallthrough = false;
+        let mut label = String::new();
+        let mut sticky = None;
+        let mut exit: Vec<KeyEvent> = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            // Node options share the table with the keys. None of these names are valid keys.
+            match key.as_str() {
+                "timeout" => timeout = Some(Duration::from_millis(map.next_value()?)),
+                "on-timeout" => on_timeout = Some(map.next_value()?),
+                "fallthrough" => fallthrough = map.next_value()?,
+                "label" => label = map.next_value()?,
+                "sticky" => sticky = Some(map.next_value()?),
+                "exit" => exit = map.next_value()?,
+                _ => {
+                    let key = key.parse::<KeyEvent>().map_err(serde::de::Error::custom)?;
+                    mapping.insert(key, map.next_value::<KeyTrie>()?);
+                    order.push(key);
+                }
+            }
         }
-        Ok(KeyTrie::Node(KeyTrieNode::new("", mapping, order)))
+        // Entering a sub-keymap can't also leave the sticky node
+        if let Some(key) = exit
+            .iter()
+            .find(|key| matches!(mapping.get(key), Some(KeyTrie::Node(_))))
+        {
+            return Err(serde::de::Error::custom(format!(
+                "exit key {key} is bound to a sub-keymap"
+            )));
+        }
+        let mut node = KeyTrieNode::new(&label, mapping, order);
+        node.sticky = sticky;
+        node.exit = exit;
+        node.timeout = timeout;
+        node.on_timeout = on_timeout;
+        node.fallthrough = fallthrough;
+        Ok(KeyTrie::Node(node))
     }
 }
 
@@ -293,6 +381,8 @@ pub struct Keymaps {
     state: Vec<KeyEvent>,
     /// Stores the sticky node if one is activated.
     pub sticky: Option<KeyTrieNode>,
+    /// When the pending keys time out and what to run then, if the pending node has a timeout.
+    pending_deadline: Option<(Instant, Option<MappableCommand>)>,
 }
 
 impl Keymaps {
@@ -301,6 +391,7 @@ pub fn new(map: Box<dyn DynAccess<HashMap<Mode, KeyTrie>>>) -> Self {
             map,
             state: Vec::new(),
             sticky: None,
+            pending_deadline: None,
         }
     }
 
@@ -319,17 +410,81 @@ pub fn sticky(&self) -> Option<&KeyTrieNode> {
 
     pub fn contains_key(&self, mode: Mode, key: KeyEvent) -> bool {
         let keymaps = &*self.map();
-        let keymap = &keymaps[&mode];
-        keymap
-            .search(self.pending())
-            .and_then(KeyTrie::node)
-            .is_some_and(|node| node.contains_key(&key))
+        // Pending keys are relative to the sticky node if one is in use.
+        let node = match &self.sticky {
+            Some(sticky) => Some(sticky).filter(|_| self.state.is_empty()).or_else(|| {
+                sticky
+                    .get(&self.state[0])
+                    .and_then(|trie| trie.search(&self.state[1..]))
+                    .and_then(KeyTrie::node)
+            }),
+            None => keymaps[&mode]
+                .search(self.pending())
+                .and_then(KeyTrie::node),
+        };
+        node.is_some_and(|node| node.contains_key(&key))
+    }
+
+    /// Returns the time left until the pending keys time out, if the pending node has a timeout.
+    pub fn pending_timeout(&self) -> Option<Duration> {
+        self.pending_deadline
+            .as_ref()
+            .map(|(deadline, _)| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Resolves the pending keys if their timeout has elapsed.
+    ///
+    /// Returns `KeymapResult::Matched` if the pending node has an `on-timeout` command and
+    /// `KeymapResult::Cancelled` with the keys that were pending otherwise.
+    pub fn timeout(&mut self) -> Option<KeymapResult> {
+        if Instant::now() < self.pending_deadline.as_ref()?.0 {
+            return None;
+        }
+        let (_, command) = self.pending_deadline.take()?;
+        if self.state.is_empty() {
+            return None;
+        }
+        Some(match command {
+            Some(command) => {
+                self.state.clear();
+                KeymapResult::Matched(command)
+            }
+            None => KeymapResult::Cancelled(self.state.drain(..).collect()),
+        })
     }
 
     /// Lookup `key` in the keymap to try and find a command to execute. Escape
     /// key cancels pending keystrokes. If there are no pending keystrokes but a
     /// sticky node is in use, it will be cleared.
     pub fn get(&mut self, mode: Mode, key: KeyEvent) -> KeymapResult {
+        self.pending_deadline = None;
+        let exits_sticky = self.state.is_empty()
+            && self
+                .sticky
+                .as_ref()
+                .is_some_and(|sticky| sticky.exit.contains(&key));
+        let mut result = self.lookup(mode, key);
+        if exits_sticky {
+            self.sticky = None;
+            self.state.clear();
+            if !matches!(
+                result,
+                KeymapResult::Matched(_) | KeymapResult::MatchedSequence(_)
+            ) {
+                result = KeymapResult::Matched(MappableCommand::no_op);
+            }
+        }
+        if let KeymapResult::Pending(node) = &result {
+            if !self.state.is_empty() {
+                self.pending_deadline = node
+                    .timeout
+                    .map(|timeout| (Instant::now() + timeout, node.on_timeout.clone()));
+            }
+        }
+        result
+    }
+
+    fn lookup(&mut self, mode: Mode, key: KeyEvent) -> KeymapResult {
         // TODO: remove the sticky part and look up manually
         let keymaps = &*self.map();
         let keymap = &keymaps[&mode];
@@ -355,6 +510,14 @@ pub fn get(&mut self, mode: Mode, key: KeyEvent) -> KeymapResult {
             Some(KeyTrie::Sequence(ref cmds)) => {
                 return KeymapResult::MatchedSequence(cmds.clone());
             }
+            // Sticky nodes with fall-through pass unbound keys on to the mode's keymap.
+            None if self
+                .sticky
+                .as_ref()
+                .is_some_and(|sticky| sticky.fallthrough) =>
+            {
+                return Self::lookup_root(keymap, key, &mut self.state);
+            }
             None => return KeymapResult::NotFound,
             Some(t) => t,
         };
@@ -362,7 +525,7 @@ pub fn get(&mut self, mode: Mode, key: KeyEvent) -> KeymapResult {
         self.state.push(key);
         match trie.search(&self.state[1..]) {
             Some(KeyTrie::Node(map)) => {
-                if map.is_sticky {
+                if map.is_sticky() {
                     self.state.clear();
                     self.sticky = Some(map.clone());
                 }
@@ -376,8 +539,121 @@ pub fn get(&mut self, mode: Mode, key: KeyEvent) -> KeymapResult {
                 self.state.clear();
                 KeymapResult::MatchedSequence(cmds.clone())
             }
-            None => KeymapResult::Cancelled(self.state.drain(..).collect()),
+            None => {
+                // The last key isn't bound in the pending node. If the node falls through, the
+                // key is looked up in the outer keymap instead, starting from the mode's root.
+                let pending = &self.state[..self.state.len() - 1];
+                let falls_through = trie
+                    .search(&pending[1..])
+                    .and_then(KeyTrie::node)
+                    .is_some_and(|node| node.fallthrough);
+                if falls_through {
+                    self.state.clear();
+                    Self::lookup_root(keymap, key, &mut self.state)
+                } else {
+                    KeymapResult::Cancelled(self.state.drain(..).collect())
+                }
+            }
+        }
+    }
+
+    /// Looks up a single key in the root keymap of a mode, ignoring any sticky node.
+    fn lookup_root(keymap: &KeyTrie, key: KeyEvent, state: &mut Vec<KeyEvent>) -> KeymapResult {
+        match keymap.search(&[key]) {
+            Some(KeyTrie::MappableCommand(cmd)) => KeymapResult::Matched(cmd.clone()),
+            Some(KeyTrie::Sequence(cmds)) => KeymapResult::MatchedSequence(cmds.clone()),
+            Some(KeyTrie::Node(node)) => {
+                // Nested sticky nodes aren't entered through fall-through.
+                state.push(key);
+                KeymapResult::Pending(node.clone())
+            }
+            None => KeymapResult::NotFound,
+        }
+    }
+
+    /// Describes how `keys` resolve in the keymap for `mode`, one line per key. This takes the
+    /// active sticky node into account but ignores the currently pending keys.
+    pub fn explain(&self, mode: Mode, keys: &[KeyEvent]) -> Vec<String> {
+        fn describe_node(node: &KeyTrieNode) -> String {
+            let mut desc = if node.name.is_empty() {
+                "sub-keymap".to_string()
+            } else {
+                format!("sub-keymap \"{}\"", node.name)
+            };
+            if node.is_sticky() {
+                desc.push_str(", sticky");
+            }
+            if let Some(timeout) = node.timeout {
+                desc.push_str(&format!(", times out after {}ms", timeout.as_millis()));
+                if let Some(cmd) = &node.on_timeout {
+                    desc.push_str(&format!(" running `{}`", cmd.name()));
+                }
+            }
+            if node.fallthrough {
+                desc.push_str(", falls through");
+            }
+            if !node.exit.is_empty() {
+                let keys: Vec<_> = node.exit.iter().map(ToString::to_string).collect();
+                desc.push_str(&format!(", exited with {}", keys.join(", ")));
+            }
+            desc
+        }
+
+        let keymaps = &*self.map();
+        let root = &keymaps[&mode];
+        let mut lines = Vec::with_capacity(keys.len());
+        let mut node = match &self.sticky {
+            Some(sticky) => {
+                lines.push(format!("inside {}", describe_node(sticky)));
+                sticky
+            }
+            None => match root.node() {
+                Some(node) => node,
+                None => return lines,
+            },
+        };
+
+        for (i, key) in keys.iter().enumerate() {
+            let trie = match node.get(key) {
+                Some(trie) => trie,
+                None if node.fallthrough => match root.search(&[*key]) {
+                    Some(trie) => {
+                        lines.push(format!(
+                            "{key}: not bound here, falls through to {mode} mode"
+                        ));
+                        trie
+                    }
+                    None => {
+                        lines.push(format!("{key}: not bound in {mode} mode"));
+                        break;
+                    }
+                },
+                None => {
+                    lines.push(format!("{key}: not bound, pending keys are cancelled"));
+                    break;
+                }
+            };
+            match trie {
+                KeyTrie::MappableCommand(cmd) => {
+                    lines.push(format!("{key}: runs `{}` ({})", cmd.name(), cmd.doc()));
+                }
+                KeyTrie::Sequence(cmds) => {
+                    let names: Vec<_> =
+                        cmds.iter().map(|cmd| format!("`{}`", cmd.name())).collect();
+                    lines.push(format!("{key}: runs {}", names.join(", ")));
+                }
+                KeyTrie::Node(next) => {
+                    lines.push(format!("{key}: enters {}", describe_node(next)));
+                    node = next;
+                    continue;
+                }
+            }
+            if i + 1 < keys.len() {
+                lines.push("remaining keys are handled from the start of the keymap".to_string());
+            }
+            break;
         }
+        lines
     }
 }
 
@@ -608,4 +884,226 @@ fn escaped_keymap() {
 
         assert_eq!(toml::from_str(keys), Ok(expectation));
     }
+
+    #[test]
+    fn node_options() {
+        let keys = r#"
+timeout = 200
+on-timeout = "normal_mode"
+fallthrough = true
+j = "move_line_down"
+        "#;
+
+        let trie: KeyTrie = toml::from_str(keys).unwrap();
+        let node = trie.node().unwrap();
+        assert_eq!(node.timeout, Some(Duration::from_millis(200)));
+        assert_eq!(node.on_timeout, Some(MappableCommand::normal_mode));
+        assert!(node.fallthrough);
+        assert_eq!(node.order, vec![key!('j')]);
+
+        assert!(toml::from_str::<KeyTrie>("not-a-key = \"normal_mode\"").is_err());
+    }
+
+    #[test]
+    fn user_defined_sticky_layer() {
+        let keys = r#"
+[normal."+"]
+label = "Git"
+sticky = true
+exit = ["q", "c"]
+n = "goto_next_change"
+c = ":sh git commit"
+        "#;
+
+        let keys: HashMap<Mode, KeyTrie> = toml::from_str(keys).unwrap();
+        let mut normal_mode = default().remove(&Mode::Normal).unwrap();
+        normal_mode.merge_nodes(keys.into_values().next().unwrap());
+        let mut keymap = Keymaps::new(Box::new(Constant(hashmap! {
+            Mode::Normal => normal_mode,
+        })));
+
+        assert!(matches!(
+            keymap.get(Mode::Normal, key!('+')),
+            KeymapResult::Pending(node) if node.name() == "Git"
+        ));
+        assert_eq!(keymap.sticky().map(KeyTrieNode::name), Some("Git"));
+        assert_eq!(
+            keymap.get(Mode::Normal, key!('n')),
+            KeymapResult::Matched(MappableCommand::goto_next_change)
+        );
+        assert!(keymap.sticky().is_some());
+
+        // Unbound exit keys leave the layer without doing anything else.
+        assert_eq!(
+            keymap.get(Mode::Normal, key!('q')),
+            KeymapResult::Matched(MappableCommand::no_op)
+        );
+        assert!(keymap.sticky().is_none());
+
+        // Bound exit keys run their command and then leave the layer.
+        keymap.get(Mode::Normal, key!('+'));
+        assert!(matches!(
+            keymap.get(Mode::Normal, key!('c')),
+            KeymapResult::Matched(MappableCommand::Typable { name, .. }) if name == "run-shell-command"
+        ));
+        assert!(keymap.sticky().is_none());
+    }
+
+    #[test]
+    fn merge_sticky_layer() {
+        let merged = |keys: &str, key: KeyEvent| {
+            let keys: HashMap<Mode, KeyTrie> = toml::from_str(keys).unwrap();
+            let mut normal_mode = default().remove(&Mode::Normal).unwrap();
+            normal_mode.merge_nodes(keys.into_values().next().unwrap());
+            node_at(&mut normal_mode, &[key]).clone()
+        };
+
+        // Nodes which don't set `sticky` keep the setting of the default node.
+        let node = merged("normal.Z.x = \"normal_mode\"", key!('Z'));
+        assert!(node.is_sticky());
+        assert!(node.contains_key(&key!('x')));
+
+        let node = merged("normal.Z.sticky = false", key!('Z'));
+        assert!(!node.is_sticky());
+
+        let node = merged("normal.g.sticky = true", key!('g'));
+        assert!(node.is_sticky());
+    }
+
+    #[test]
+    fn exit_key_bound_to_sub_keymap() {
+        let keys = r#"
+sticky = true
+exit = ["q"]
+q = { x = "normal_mode" }
+        "#;
+        let err = toml::from_str::<KeyTrie>(keys).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("exit key q is bound to a sub-keymap"));
+
+        let keys = r#"
+sticky = true
+exit = ["q"]
+q = "normal_mode"
+        "#;
+        assert!(toml::from_str::<KeyTrie>(keys).is_ok());
+    }
+
+    fn node_at<'a>(trie: &'a mut KeyTrie, keys: &[KeyEvent]) -> &'a mut KeyTrieNode {
+        keys.iter()
+            .fold(trie, |trie, key| {
+                trie.node_mut().unwrap().get_mut(key).unwrap()
+            })
+            .node_mut()
+            .unwrap()
+    }
+
+    #[test]
+    fn long_sequences_and_fallthrough() {
+        let mut normal_mode = keymap!({ "Normal mode"
+            "g" => goto_file_start,
+            "space" => { "Space"
+                "a" => { "A"
+                    "b" => { "B"
+                        "c" => vsplit,
+                    },
+                },
+                "w" => { "Window"
+                    "v" => hsplit,
+                },
+            },
+        });
+        node_at(&mut normal_mode, &[key!(' '), key!('w')]).fallthrough = true;
+        let mut keymap = Keymaps::new(Box::new(Constant(hashmap! {
+            Mode::Normal => normal_mode,
+        })));
+
+        for key in [key!(' '), key!('a'), key!('b')] {
+            assert!(matches!(
+                keymap.get(Mode::Normal, key),
+                KeymapResult::Pending(_)
+            ));
+        }
+        assert_eq!(
+            keymap.get(Mode::Normal, key!('c')),
+            KeymapResult::Matched(MappableCommand::vsplit)
+        );
+        assert!(keymap.pending().is_empty());
+
+        // Unbound keys cancel pending keys unless the node falls through.
+        keymap.get(Mode::Normal, key!(' '));
+        assert_eq!(
+            keymap.get(Mode::Normal, key!('x')),
+            KeymapResult::Cancelled(vec![key!(' '), key!('x')])
+        );
+        keymap.get(Mode::Normal, key!(' '));
+        keymap.get(Mode::Normal, key!('w'));
+        assert_eq!(
+            keymap.get(Mode::Normal, key!('g')),
+            KeymapResult::Matched(MappableCommand::goto_file_start)
+        );
+        assert!(keymap.pending().is_empty());
+    }
+
+    #[test]
+    fn pending_keys_time_out() {
+        let mut normal_mode = keymap!({ "Normal mode"
+            "g" => { "Goto"
+                "g" => goto_file_start,
+            },
+        });
+        node_at(&mut normal_mode, &[key!('g')]).timeout = Some(Duration::ZERO);
+        let mut keymap = Keymaps::new(Box::new(Constant(hashmap! {
+            Mode::Normal => normal_mode.clone(),
+        })));
+
+        keymap.get(Mode::Normal, key!('g'));
+        assert_eq!(keymap.pending_timeout(), Some(Duration::ZERO));
+        assert_eq!(
+            keymap.timeout(),
+            Some(KeymapResult::Cancelled(vec![key!('g')]))
+        );
+        assert!(keymap.pending().is_empty());
+        assert_eq!(keymap.timeout(), None);
+
+        node_at(&mut normal_mode, &[key!('g')]).on_timeout = Some(MappableCommand::goto_file_end);
+        let mut keymap = Keymaps::new(Box::new(Constant(hashmap! {
+            Mode::Normal => normal_mode,
+        })));
+        keymap.get(Mode::Normal, key!('g'));
+        assert_eq!(
+            keymap.timeout(),
+            Some(KeymapResult::Matched(MappableCommand::goto_file_end))
+        );
+    }
+
+    #[test]
+    fn explain_keys() {
+        let keymap = Keymaps::new(Box::new(Constant(hashmap! {
+            Mode::Normal => keymap!({ "Normal mode"
+                "g" => { "Goto"
+                    "g" => goto_file_start,
+                },
+            }),
+        })));
+
+        assert_eq!(
+            keymap.explain(Mode::Normal, &[key!('g'), key!('g')]),
+            vec![
+                "g: enters sub-keymap \"Goto\"".to_string(),
+                format!(
+                    "g: runs `goto_file_start` ({})",
+                    MappableCommand::goto_file_start.doc()
+                ),
+            ]
+        );
+        assert_eq!(
+            keymap.explain(Mode::Normal, &[key!('g'), key!('x')]),
+            vec![
+                "g: enters sub-keymap \"Goto\"".to_string(),
+                "x: not bound, pending keys are cancelled".to_string(),
+            ]
+        );
+    }
 }
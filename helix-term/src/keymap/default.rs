@@ -60,6 +60,7 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
             "j" => move_line_down,
             "." => goto_last_modification,
             "w" => goto_word,
+            "W" => goto_word_all_views,
         },
         ":" => command_mode,
 
@@ -111,6 +112,7 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
         "[" => { "Left bracket"
             "d" => goto_prev_diag,
             "D" => goto_first_diag,
+            "E" => goto_prev_error,
             "g" => goto_prev_change,
             "G" => goto_first_change,
             "f" => goto_prev_function,
@@ -121,11 +123,13 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
             "T" => goto_prev_test,
             "p" => goto_prev_paragraph,
             "x" => goto_prev_xml_element,
+            "q" => goto_prev_quickfix,
             "space" => add_newline_above,
         },
         "]" => { "Right bracket"
             "d" => goto_next_diag,
             "D" => goto_last_diag,
+            "E" => goto_next_error,
             "g" => goto_next_change,
             "G" => goto_last_change,
             "f" => goto_next_function,
@@ -136,6 +140,7 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
             "T" => goto_next_test,
             "p" => goto_next_paragraph,
             "x" => goto_next_xml_element,
+            "q" => goto_next_quickfix,
             "space" => add_newline_below,
         },
 
@@ -199,6 +204,8 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
             "F" => goto_file_vsplit,
             "C-q" | "q" => wclose,
             "C-o" | "o" => wonly,
+            "p" => toggle_pin_view,
+            "b" => toggle_scroll_bind,
             "C-h" | "h" | "left" => jump_view_left,
             "C-j" | "j" | "down" => jump_view_down,
             "C-k" | "k" | "up" => jump_view_up,
@@ -207,6 +214,8 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
             "K" => swap_view_up,
             "H" => swap_view_left,
             "J" => swap_view_down,
+            "]" => goto_next_layout,
+            "[" => goto_prev_layout,
             "n" => { "New split scratch buffer"
                 "C-s" | "s" => hsplit_new,
                 "C-v" | "v" => vsplit_new,
@@ -221,6 +230,8 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
         "C-i" | "tab" => jump_forward, // tab == <C-i>
         "C-o" => jump_backward,
         "C-s" => save_selection,
+        "A-z" => undo_selection,
+        "A-Z" => redo_selection,
 
         "space" => { "Space"
             "f" => file_picker,
@@ -266,6 +277,8 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
                 "F" => goto_file_vsplit,
                 "C-q" | "q" => wclose,
                 "C-o" | "o" => wonly,
+                "p" => toggle_pin_view,
+                "b" => toggle_scroll_bind,
                 "C-h" | "h" | "left" => jump_view_left,
                 "C-j" | "j" | "down" => jump_view_down,
                 "C-k" | "k" | "up" => jump_view_up,
@@ -274,6 +287,8 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
                 "J" => swap_view_down,
                 "K" => swap_view_up,
                 "L" => swap_view_right,
+                "]" => goto_next_layout,
+                "[" => goto_prev_layout,
                 "n" => { "New split scratch buffer"
                     "C-s" | "s" => hsplit_new,
                     "C-v" | "v" => vsplit_new,
@@ -335,6 +350,7 @@ pub fn default() -> HashMap<Mode, KeyTrie> {
         "A-!" => shell_append_output,
         "$" => shell_keep_pipe,
         "C-z" => suspend,
+        "A-t" => toggle_terminal,
 
         "C-a" => increment,
         "C-x" => decrement,
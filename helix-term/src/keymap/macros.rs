@@ -113,7 +113,7 @@ macro_rules! keymap {
                 )+
             )*
             let mut _node = $crate::keymap::KeyTrieNode::new($label, _map, _order);
-            $( _node.is_sticky = $sticky; )?
+            $( _node.sticky = Some($sticky); )?
             $crate::keymap::KeyTrie::Node(_node)
         }
     };
@@ -8,8 +8,11 @@
 pub mod config;
 pub mod events;
 pub mod health;
+pub mod idle;
 pub mod job;
 pub mod keymap;
+pub mod lsp_messages;
+pub mod render_throttle;
 pub mod ui;
 
 #[cfg(not(windows))]
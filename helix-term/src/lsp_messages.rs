@@ -0,0 +1,528 @@
+//! Handling of the notifications and requests sent by language servers, shared by
+//! [`Application`](crate::application::Application) and embedders driving their own event loop.
+
+use helix_core::Selection;
+use helix_lsp::{
+    lsp::{self, notification::Notification},
+    util::lsp_range_to_range,
+    LanguageServerId, LspProgressMap,
+};
+use helix_view::{align_view, metrics::Phase, Align, Editor};
+use log::{error, info, warn};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{compositor::Compositor, handlers, job::Jobs, ui};
+
+/// Dispatches the messages of language servers, see [`LspMessageHandler::handle_message`].
+/// Pass it the calls of [`EditorEvent::LanguageServerMessage`](helix_view::editor::EditorEvent)
+/// events.
+///
+/// It tracks the work done progress of the servers, which is shown in the statusline and by
+/// the spinners of the [`EditorView`](ui::EditorView) found in the compositor.
+#[derive(Debug, Default)]
+pub struct LspMessageHandler {
+    progress: LspProgressMap,
+}
+
+impl LspMessageHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a notification or request of the language server `server_id`, replying to
+    /// requests. Some requests are replied to later, once the user picked an action.
+    pub async fn handle_message(
+        &mut self,
+        editor: &mut Editor,
+        compositor: &mut Compositor,
+        jobs: &mut Jobs,
+        call: helix_lsp::Call,
+        server_id: LanguageServerId,
+    ) {
+        let start = editor.metrics.start();
+        self.dispatch(editor, compositor, jobs, call, server_id)
+            .await;
+        editor.metrics.finish(Phase::Lsp, start);
+    }
+
+    async fn dispatch(
+        &mut self,
+        editor: &mut Editor,
+        compositor: &mut Compositor,
+        jobs: &mut Jobs,
+        call: helix_lsp::Call,
+        server_id: LanguageServerId,
+    ) {
+        use helix_lsp::{Call, MethodCall, Notification};
+
+        macro_rules! language_server {
+            () => {
+                match editor.language_server_by_id(server_id) {
+                    Some(language_server) => language_server,
+                    None => {
+                        warn!("can't find language server with id `{}`", server_id);
+                        return;
+                    }
+                }
+            };
+        }
+
+        match call {
+            Call::Notification(helix_lsp::jsonrpc::Notification { method, params, .. }) => {
+                let notification = match Notification::parse(&method, params) {
+                    Ok(notification) => notification,
+                    Err(helix_lsp::Error::Unhandled) => {
+                        info!("Ignoring Unhandled notification from Language Server");
+                        return;
+                    }
+                    Err(err) => {
+                        error!(
+                            "Ignoring unknown notification from Language Server: {}",
+                            err
+                        );
+                        return;
+                    }
+                };
+
+                match notification {
+                    Notification::Initialized => {
+                        let language_server = language_server!();
+
+                        // Trigger a workspace/didChangeConfiguration notification after initialization.
+                        // This might not be required by the spec but Neovim does this as well, so it's
+                        // probably a good idea for compatibility.
+                        if let Some(config) = language_server.config() {
+                            language_server.did_change_configuration(config.clone());
+                        }
+
+                        helix_event::dispatch(helix_view::events::LanguageServerInitialized {
+                            editor,
+                            server_id,
+                        });
+                    }
+                    Notification::PublishDiagnostics(params) => {
+                        let uri = match helix_core::Uri::try_from(params.uri) {
+                            Ok(uri) => uri,
+                            Err(err) => {
+                                log::error!("{err}");
+                                return;
+                            }
+                        };
+                        let language_server = language_server!();
+                        if !language_server.is_initialized() {
+                            log::error!("Discarding publishDiagnostic notification sent by an uninitialized server: {}", language_server.name());
+                            return;
+                        }
+                        let provider = helix_core::diagnostic::DiagnosticProvider::Lsp {
+                            server_id,
+                            identifier: None,
+                        };
+                        editor.handle_lsp_diagnostics(
+                            &provider,
+                            uri,
+                            params.version,
+                            params.diagnostics,
+                        );
+                    }
+                    Notification::ShowMessage(params) => {
+                        handle_show_message(editor, params.typ, params.message);
+                    }
+                    Notification::LogMessage(params) => {
+                        log::info!("window/logMessage: {:?}", params);
+                    }
+                    Notification::ProgressMessage(params)
+                        if !self
+                            .compositor
+                            .has_component(std::any::type_name::<ui::Prompt>()) =>
+                    {
+                        let editor_view = self
+                            .compositor
+                            .find::<ui::EditorView>()
+                            .expect("expected at least one EditorView");
+                        let lsp::ProgressParams {
+                            token,
+                            value: lsp::ProgressParamsValue::WorkDone(work),
+                        } = params;
+                        let (title, message, percentage) = match &work {
+                            lsp::WorkDoneProgress::Begin(lsp::WorkDoneProgressBegin {
+                                title,
+                                message,
+                                percentage,
+                                ..
+                            }) => (Some(title), message, percentage),
+                            lsp::WorkDoneProgress::Report(lsp::WorkDoneProgressReport {
+                                message,
+                                percentage,
+                                ..
+                            }) => (None, message, percentage),
+                            lsp::WorkDoneProgress::End(lsp::WorkDoneProgressEnd { message }) => {
+                                if message.is_some() {
+                                    (None, message, &None)
+                                } else {
+                                    self.progress.end_progress(server_id, &token);
+                                    if !self.progress.is_progressing(server_id) {
+                                        editor_view.spinners_mut().get_or_create(server_id).stop();
+                                    }
+                                    editor.clear_status();
+
+                                    // we want to render to clear any leftover spinners or messages
+                                    return;
+                                }
+                            }
+                        };
+
+                        if editor.config().lsp.display_progress_messages {
+                            let title = title.or_else(|| self.progress.title(server_id, &token));
+                            if title.is_some() || percentage.is_some() || message.is_some() {
+                                use std::fmt::Write as _;
+                                let mut status = format!("{}: ", language_server!().name());
+                                if let Some(percentage) = percentage {
+                                    write!(status, "{percentage:>2}% ").unwrap();
+                                }
+                                if let Some(title) = title {
+                                    status.push_str(title);
+                                }
+                                if title.is_some() && message.is_some() {
+                                    status.push_str(" ⋅ ");
+                                }
+                                if let Some(message) = message {
+                                    status.push_str(message);
+                                }
+                                editor.set_status(status);
+                            }
+                        }
+
+                        match work {
+                            lsp::WorkDoneProgress::Begin(begin_status) => {
+                                self.progress.begin(server_id, token.clone(), begin_status);
+                            }
+                            lsp::WorkDoneProgress::Report(report_status) => {
+                                self.progress
+                                    .update(server_id, token.clone(), report_status);
+                            }
+                            lsp::WorkDoneProgress::End(_) => {
+                                self.progress.end_progress(server_id, &token);
+                                if !self.progress.is_progressing(server_id) {
+                                    editor_view.spinners_mut().get_or_create(server_id).stop();
+                                };
+                            }
+                        }
+                    }
+                    Notification::ProgressMessage(_params) => {
+                        // do nothing
+                    }
+                    Notification::Exit => {
+                        editor.set_status("Language server exited");
+
+                        // LSPs may produce diagnostics for files that haven't been opened in helix,
+                        // those are cleared along with the diagnostics of open documents.
+                        editor.clear_language_server_diagnostics(server_id);
+
+                        helix_event::dispatch(helix_view::events::LanguageServerExited {
+                            editor,
+                            server_id,
+                        });
+
+                        // Remove the language server from the registry.
+                        editor.language_servers.remove_by_id(server_id);
+
+                        self.progress.remove_server(server_id);
+                        if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                            editor_view.spinners_mut().get_or_create(server_id).stop();
+                        }
+                    }
+                }
+            }
+            Call::MethodCall(helix_lsp::jsonrpc::MethodCall {
+                method, params, id, ..
+            }) => {
+                let reply = match MethodCall::parse(&method, params) {
+                    Err(helix_lsp::Error::Unhandled) => {
+                        error!(
+                            "Language Server: Method {} not found in request {}",
+                            method, id
+                        );
+                        Err(helix_lsp::jsonrpc::Error {
+                            code: helix_lsp::jsonrpc::ErrorCode::MethodNotFound,
+                            message: format!("Method not found: {}", method),
+                            data: None,
+                        })
+                    }
+                    Err(err) => {
+                        log::error!(
+                            "Language Server: Received malformed method call {} in request {}: {}",
+                            method,
+                            id,
+                            err
+                        );
+                        Err(helix_lsp::jsonrpc::Error {
+                            code: helix_lsp::jsonrpc::ErrorCode::ParseError,
+                            message: format!("Malformed method call: {}", method),
+                            data: None,
+                        })
+                    }
+                    Ok(MethodCall::WorkDoneProgressCreate(params)) => {
+                        self.progress.create(server_id, params.token);
+
+                        let editor_view = self
+                            .compositor
+                            .find::<ui::EditorView>()
+                            .expect("expected at least one EditorView");
+                        let spinner = editor_view.spinners_mut().get_or_create(server_id);
+                        if spinner.is_stopped() {
+                            spinner.start();
+                        }
+
+                        Ok(serde_json::Value::Null)
+                    }
+                    Ok(MethodCall::ApplyWorkspaceEdit(params)) => {
+                        let language_server = language_server!();
+                        if language_server.is_initialized() {
+                            let offset_encoding = language_server.offset_encoding();
+                            let res = self
+                                .editor
+                                .apply_workspace_edit(offset_encoding, &params.edit);
+
+                            Ok(json!(lsp::ApplyWorkspaceEditResponse {
+                                applied: res.is_ok(),
+                                failure_reason: res.as_ref().err().map(|err| err.kind.to_string()),
+                                failed_change: res
+                                    .as_ref()
+                                    .err()
+                                    .map(|err| err.failed_change_idx as u32),
+                            }))
+                        } else {
+                            Err(helix_lsp::jsonrpc::Error {
+                                code: helix_lsp::jsonrpc::ErrorCode::InvalidRequest,
+                                message: "Server must be initialized to request workspace edits"
+                                    .to_string(),
+                                data: None,
+                            })
+                        }
+                    }
+                    Ok(MethodCall::WorkspaceFolders) => {
+                        Ok(json!(&*language_server!().workspace_folders().await))
+                    }
+                    Ok(MethodCall::WorkspaceConfiguration(params)) => {
+                        let language_server = language_server!();
+                        let result: Vec<_> = params
+                            .items
+                            .iter()
+                            .map(|item| {
+                                let mut config = language_server.config()?;
+                                if let Some(section) = item.section.as_ref() {
+                                    // for some reason some lsps send an empty string (observed in 'vscode-eslint-language-server')
+                                    if !section.is_empty() {
+                                        for part in section.split('.') {
+                                            config = config.get(part)?;
+                                        }
+                                    }
+                                }
+                                Some(config)
+                            })
+                            .collect();
+                        Ok(json!(result))
+                    }
+                    Ok(MethodCall::RegisterCapability(params)) => {
+                        if let Some(client) = editor.language_servers.get_by_id(server_id) {
+                            for reg in params.registrations {
+                                match reg.method.as_str() {
+                                    lsp::notification::DidChangeWatchedFiles::METHOD => {
+                                        let Some(options) = reg.register_options else {
+                                            continue;
+                                        };
+                                        let ops: lsp::DidChangeWatchedFilesRegistrationOptions =
+                                            match serde_json::from_value(options) {
+                                                Ok(ops) => ops,
+                                                Err(err) => {
+                                                    log::warn!("Failed to deserialize DidChangeWatchedFilesRegistrationOptions: {err}");
+                                                    continue;
+                                                }
+                                            };
+                                        editor.language_servers.file_event_handler.register(
+                                            client.id(),
+                                            Arc::downgrade(client),
+                                            reg.id,
+                                            ops,
+                                        )
+                                    }
+                                    _ => {
+                                        // Language Servers based on the `vscode-languageserver-node` library often send
+                                        // client/registerCapability even though we do not enable dynamic registration
+                                        // for most capabilities. We should send a MethodNotFound JSONRPC error in this
+                                        // case but that rejects the registration promise in the server which causes an
+                                        // exit. So we work around this by ignoring the request and sending back an OK
+                                        // response.
+                                        log::warn!("Ignoring a client/registerCapability request because dynamic capability registration is not enabled. Please report this upstream to the language server");
+                                    }
+                                }
+                            }
+                        }
+
+                        Ok(serde_json::Value::Null)
+                    }
+                    Ok(MethodCall::UnregisterCapability(params)) => {
+                        for unreg in params.unregisterations {
+                            match unreg.method.as_str() {
+                                lsp::notification::DidChangeWatchedFiles::METHOD => {
+                                    editor
+                                        .language_servers
+                                        .file_event_handler
+                                        .unregister(server_id, unreg.id);
+                                }
+                                _ => {
+                                    log::warn!("Received unregistration request for unsupported method: {}", unreg.method);
+                                }
+                            }
+                        }
+                        Ok(serde_json::Value::Null)
+                    }
+                    Ok(MethodCall::ShowDocument(params)) => {
+                        let language_server = language_server!();
+                        let offset_encoding = language_server.offset_encoding();
+
+                        let result = handle_show_document(editor, jobs, params, offset_encoding);
+                        Ok(json!(result))
+                    }
+                    Ok(MethodCall::WorkspaceDiagnosticRefresh) => {
+                        let language_server = language_server!().id();
+
+                        let documents: Vec<_> = self
+                            .editor
+                            .documents
+                            .values()
+                            .filter(|x| x.supports_language_server(language_server))
+                            .map(|x| x.id())
+                            .collect();
+
+                        for document in documents {
+                            handlers::diagnostics::request_document_diagnostics(editor, document);
+                        }
+
+                        Ok(serde_json::Value::Null)
+                    }
+                    Ok(MethodCall::ShowMessageRequest(params)) => {
+                        if let Some(actions) = params.actions.filter(|a| !a.is_empty()) {
+                            let id = id.clone();
+                            let select = ui::Select::new(
+                                params.message,
+                                actions,
+                                (),
+                                move |editor, action, event| {
+                                    let reply = match event {
+                                        ui::PromptEvent::Update => return,
+                                        ui::PromptEvent::Validate => Some(action.clone()),
+                                        ui::PromptEvent::Abort => None,
+                                    };
+                                    if let Some(language_server) =
+                                        editor.language_server_by_id(server_id)
+                                    {
+                                        if let Err(err) =
+                                            language_server.reply(id.clone(), Ok(json!(reply)))
+                                        {
+                                            log::error!(
+                                                "Failed to send reply to server '{}' request {id}: {err}",
+                                                language_server.name()
+                                            );
+                                        }
+                                    }
+                                },
+                            );
+                            compositor.replace_or_push("lsp-show-message-request", select);
+                            // Avoid sending a reply. The `Select` callback above sends the reply.
+                            return;
+                        } else {
+                            handle_show_message(editor, params.typ, params.message);
+                            Ok(serde_json::Value::Null)
+                        }
+                    }
+                };
+
+                let language_server = language_server!();
+                if let Err(err) = language_server.reply(id.clone(), reply) {
+                    log::error!(
+                        "Failed to send reply to server '{}' request {id}: {err}",
+                        language_server.name()
+                    );
+                }
+            }
+            Call::Invalid { id } => log::error!("LSP invalid method call id={:?}", id),
+        }
+    }
+}
+
+fn handle_show_message(editor: &mut Editor, message_type: lsp::MessageType, message: String) {
+    if editor.config().lsp.display_messages {
+        match message_type {
+            lsp::MessageType::ERROR => editor.set_error(message),
+            lsp::MessageType::WARNING => editor.set_warning(message),
+            _ => editor.set_status(message),
+        }
+    }
+}
+
+fn handle_show_document(
+    editor: &mut Editor,
+    jobs: &mut Jobs,
+    params: lsp::ShowDocumentParams,
+    offset_encoding: helix_lsp::OffsetEncoding,
+) -> lsp::ShowDocumentResult {
+    if let lsp::ShowDocumentParams {
+        external: Some(true),
+        uri,
+        ..
+    } = params
+    {
+        jobs.callback(crate::open_external_url_callback(uri));
+        return lsp::ShowDocumentResult { success: true };
+    };
+
+    let lsp::ShowDocumentParams {
+        uri,
+        selection,
+        take_focus,
+        ..
+    } = params;
+
+    let uri = match helix_core::Uri::try_from(uri) {
+        Ok(uri) => uri,
+        Err(err) => {
+            log::error!("{err}");
+            return lsp::ShowDocumentResult { success: false };
+        }
+    };
+    // If `Uri` gets another variant other than `Path` this may not be valid.
+    let path = uri.as_path().expect("URIs are valid paths");
+
+    let action = match take_focus {
+        Some(true) => helix_view::editor::Action::Replace,
+        _ => helix_view::editor::Action::VerticalSplit,
+    };
+
+    let doc_id = match editor.open(path, action) {
+        Ok(id) => id,
+        Err(err) => {
+            log::error!("failed to open path: {:?}: {:?}", uri, err);
+            return lsp::ShowDocumentResult { success: false };
+        }
+    };
+
+    let doc = doc_mut!(editor, &doc_id);
+    if let Some(range) = selection {
+        // TODO: convert inside server
+        if let Some(new_range) = lsp_range_to_range(doc.text(), range, offset_encoding) {
+            let view = view_mut!(editor);
+
+            // we flip the range so that the cursor sits on the start of the symbol
+            // (for example start of the function).
+            doc.set_selection(view.id, Selection::single(new_range.head, new_range.anchor));
+            if action.align_view(view, doc.id()) {
+                align_view(doc, view, Align::Center);
+            }
+        } else {
+            log::warn!("lsp position out of bounds - {:?}", range);
+        };
+    };
+    lsp::ShowDocumentResult { success: true }
+}
@@ -39,8 +39,10 @@ fn main() -> Result<()> {
 
 #[tokio::main]
 async fn main_impl() -> Result<i32> {
+    helix_loader::startup::start();
     let args = Args::parse_args().context("could not parse arguments")?;
 
+    let profile_warning = helix_loader::initialize_profile(args.profile.clone())?;
     helix_loader::initialize_config_file(args.config_file.clone());
     helix_loader::initialize_log_file(args.log_file.clone());
 
@@ -62,21 +64,26 @@ async fn main_impl() -> Result<i32> {
     -h, --help                     Print help information
     --tutor                        Load the tutorial
     --health [CATEGORY]            Check for potential errors in editor setup
-                                   CATEGORY can be a language or one of 'clipboard', 'languages',
-                                   'all-languages' or 'all'. 'languages' is filtered according to
+                                   CATEGORY can be a language or one of 'clipboard', 'terminal',
+                                   'languages', 'all-languages' or 'all'. 'languages' is filtered according to
                                    user config, 'all-languages' and 'all' are not. If not specified,
                                    the default is the same as 'all', but with languages filtering.
     -g, --grammar {{fetch|build}}    Fetch or builds tree-sitter grammars listed in languages.toml
     -c, --config <file>            Specify a file to use for configuration
+    --profile <name>               Use the config profile <name> from the profiles directory
+                                   of the config directory (default: $HELIX_PROFILE)
     -v                             Increase logging verbosity each use for up to 3 times
     --log <file>                   Specify a file to use for logging
                                    (default file: {})
     -V, --version                  Print version information
     --vsplit                       Split all given files vertically into different windows
     --hsplit                       Split all given files horizontally into different windows
+    --restore-session              Restore the saved session of the workspace when no files
+                                   are given
+    --startup-timing               Print the time spent in each phase of the startup on exit
     -w, --working-dir <path>       Specify an initial working directory
-    +[N]                           Open the first given file at line number N, or the last line, if
-                                   N is not specified.
+    +[N[:M]]                       Open the first given file at line number N and column M, or the
+                                   last line, if N is not specified.
 ",
             env!("CARGO_PKG_NAME"),
             VERSION_AND_GIT_HASH,
@@ -92,6 +99,10 @@ async fn main_impl() -> Result<i32> {
         std::process::exit(0);
     }
 
+    if args.script.is_some() || args.execute.is_some() {
+        anyhow::bail!("--script and --execute are only supported by my_editor");
+    }
+
     if args.health {
         if let Err(err) = helix_term::health::print_health(args.health_arg) {
             // Piping to for example `head -10` requires special handling:
@@ -125,35 +136,44 @@ async fn main_impl() -> Result<i32> {
         helix_stdx::env::set_current_working_dir(path)?;
     }
 
-    let config = match Config::load_default() {
+    // Errors in the config files are shown in a popup once the editor is running.
+    let mut config_errors = Vec::new();
+    if let Some(warning) = profile_warning {
+        log::warn!("{warning}");
+        config_errors.push(warning);
+    }
+    let config = match helix_loader::startup::measure("config", Config::load_default) {
         Ok(config) => config,
         Err(ConfigLoadError::Error(err)) if err.kind() == std::io::ErrorKind::NotFound => {
             Config::default()
         }
         Err(ConfigLoadError::Error(err)) => return Err(Error::new(err)),
         Err(ConfigLoadError::BadConfig(err)) => {
-            eprintln!("Bad config: {}", err);
-            eprintln!("Press <ENTER> to continue with default config");
-            use std::io::Read;
-            let _ = std::io::stdin().read(&mut []);
+            log::error!("Bad config: {}", err);
+            config_errors.push(err.to_string());
             Config::default()
         }
     };
 
-    let lang_loader = helix_core::config::user_lang_loader().unwrap_or_else(|err| {
-        eprintln!("{}", err);
-        eprintln!("Press <ENTER> to continue with default language config");
-        use std::io::Read;
-        // This waits for an enter press.
-        let _ = std::io::stdin().read(&mut []);
-        helix_core::config::default_lang_loader()
-    });
+    let lang_loader =
+        helix_loader::startup::measure("language config", helix_core::config::user_lang_loader)
+            .unwrap_or_else(|err| {
+                log::error!("{}", err);
+                config_errors.push(err.to_string());
+                helix_core::config::default_lang_loader()
+            });
 
+    let startup_timing = args.startup_timing;
     // TODO: use the thread local executor to spawn the application task separately from the work pool
     let mut app = Application::new(args, config, lang_loader).context("unable to start Helix")?;
+    app.show_config_errors(&config_errors);
     let mut events = app.event_stream();
 
     let exit_code = app.run(&mut events).await?;
+    if startup_timing {
+        // The terminal is restored, print the report where it can be read
+        eprint!("{}", helix_loader::startup::report());
+    }
 
     Ok(exit_code)
 }
@@ -0,0 +1,175 @@
+//! Coalesced rendering, and throttled rendering for slow terminals.
+//!
+//! Frames are drawn at most every [`FRAME_INTERVAL`]: a frame requested sooner after the
+//! previous one is deferred, so a burst of events, such as a paste or fast typing, is drawn
+//! once instead of once per event. The first frame after a pause is drawn right away.
+//!
+//! Over a high latency connection (SSH, serial consoles) flushing a frame can take long enough
+//! that drawing on every keypress makes the editor lag behind the input. [`RenderThrottle`]
+//! measures how long frames take to flush and marks the terminal as slow after a run of slow
+//! flushes, see [`Editor::slow_terminal`]. While rendering is throttled, frames are drawn at
+//! most every [`THROTTLED_FRAME_INTERVAL`] instead.
+
+use std::{
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use helix_view::Editor;
+use tokio::time::{sleep, Sleep};
+
+/// Flushes taking longer than this count as slow.
+const SLOW_FLUSH: Duration = Duration::from_millis(40);
+
+/// Flushes taking less than this count as fast.
+const FAST_FLUSH: Duration = Duration::from_millis(15);
+
+/// The number of consecutive slow flushes after which the terminal is considered slow.
+const SLOW_FLUSHES: u32 = 5;
+
+/// The number of consecutive fast flushes after which a slow terminal is considered fast
+/// again. Higher than [`SLOW_FLUSHES`] so that a few fast frames don't switch back and forth.
+const FAST_FLUSHES: u32 = 30;
+
+/// The minimum time between two frames, which caps rendering at about 120 frames per second.
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(8);
+
+/// The minimum time between two frames while rendering is throttled.
+pub const THROTTLED_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+pub struct RenderThrottle {
+    /// Consecutive slow flushes, or fast flushes once the terminal is considered slow.
+    streak: u32,
+    slow: bool,
+    last_frame: Option<Instant>,
+    /// The frame deferred until the frame interval passed since the last one.
+    deferred: Option<Pin<Box<Sleep>>>,
+}
+
+impl RenderThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a frame can be drawn now. Otherwise a deferred frame is scheduled, see
+    /// [`RenderThrottle::deferred_frame`].
+    pub fn start_frame(&mut self, editor: &Editor) -> bool {
+        let interval = if editor.throttle_rendering() {
+            THROTTLED_FRAME_INTERVAL
+        } else {
+            FRAME_INTERVAL
+        };
+        self.start_frame_within(interval)
+    }
+
+    fn start_frame_within(&mut self, interval: Duration) -> bool {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            let next_frame = last_frame + interval;
+            if now < next_frame {
+                self.deferred
+                    .get_or_insert_with(|| Box::pin(sleep(next_frame - now)));
+                return false;
+            }
+        }
+        self.start_frame_now();
+        true
+    }
+
+    /// Records that a frame is drawn now regardless of the frame interval, for example the
+    /// final frame of a script. A deferred frame is no longer needed after it.
+    pub fn start_frame_now(&mut self) {
+        self.deferred = None;
+        self.last_frame = Some(Instant::now());
+    }
+
+    /// Records how long a frame took to flush and updates [`Editor::slow_terminal`].
+    pub fn finish_frame(&mut self, editor: &mut Editor, flush_time: Duration) {
+        if self.record_flush(flush_time) {
+            log::info!(
+                "terminal is {}, frames took {flush_time:?} to flush",
+                if self.slow { "slow" } else { "fast again" }
+            );
+            editor.slow_terminal = self.slow;
+        }
+    }
+
+    /// Returns whether the terminal changed between slow and fast.
+    fn record_flush(&mut self, flush_time: Duration) -> bool {
+        let counts = if self.slow {
+            flush_time < FAST_FLUSH
+        } else {
+            flush_time > SLOW_FLUSH
+        };
+        if !counts {
+            self.streak = 0;
+            return false;
+        }
+        self.streak += 1;
+        let needed = if self.slow {
+            FAST_FLUSHES
+        } else {
+            SLOW_FLUSHES
+        };
+        if self.streak < needed {
+            return false;
+        }
+        self.streak = 0;
+        self.slow = !self.slow;
+        true
+    }
+
+    /// Resolves once a deferred frame is due, never if no frame is deferred.
+    pub async fn deferred_frame(&mut self) {
+        match self.deferred.as_mut() {
+            Some(deferred) => deferred.await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn frames_within_the_interval_are_coalesced() {
+        let mut throttle = RenderThrottle::new();
+        assert!(throttle.start_frame_within(FRAME_INTERVAL));
+        assert!(!throttle.start_frame_within(FRAME_INTERVAL));
+        assert!(!throttle.start_frame_within(FRAME_INTERVAL));
+        assert!(throttle.deferred.is_some());
+
+        throttle.deferred_frame().await;
+        assert!(throttle.start_frame_within(FRAME_INTERVAL));
+        assert!(throttle.deferred.is_none());
+
+        throttle.start_frame_now();
+        assert!(!throttle.start_frame_within(FRAME_INTERVAL));
+    }
+
+    #[test]
+    fn sustained_slow_flushes_mark_the_terminal_slow() {
+        let slow = Duration::from_millis(80);
+        let fast = Duration::from_millis(2);
+        let mut throttle = RenderThrottle::new();
+
+        // A single fast flush resets the streak
+        for _ in 0..SLOW_FLUSHES - 1 {
+            assert!(!throttle.record_flush(slow));
+        }
+        assert!(!throttle.record_flush(fast));
+        for _ in 0..SLOW_FLUSHES - 1 {
+            assert!(!throttle.record_flush(slow));
+        }
+        assert!(throttle.record_flush(slow));
+        assert!(throttle.slow);
+
+        for _ in 0..FAST_FLUSHES - 1 {
+            assert!(!throttle.record_flush(fast));
+        }
+        assert!(throttle.record_flush(fast));
+        assert!(!throttle.slow);
+    }
+}
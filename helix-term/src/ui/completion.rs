@@ -1,7 +1,9 @@
 use crate::handlers::completion::LspCompletionItem;
 use crate::ui::{menu, Markdown, Menu, Popup, PromptEvent};
 use crate::{
+    alt,
     compositor::{Component, Context, Event, EventResult},
+    ctrl,
     handlers::completion::{
         trigger_auto_completion, CompletionItem, CompletionResponse, ResolveHandler,
     },
@@ -116,6 +118,11 @@ fn format(&self, dir_style: &Self::Data) -> menu::Row<'_> {
     }
 }
 
+/// The documentation panel is never resized narrower than this.
+const MIN_DOC_WIDTH: u16 = 20;
+/// How many columns the documentation panel grows or shrinks by per key press.
+const DOC_RESIZE_STEP: u16 = 4;
+
 /// Wraps a Menu.
 pub struct Completion {
     popup: Popup<Menu<CompletionItem>>,
@@ -124,6 +131,10 @@ pub struct Completion {
     filter: String,
     // TODO: move to helix-view/central handler struct in the future
     resolve_handler: ResolveHandler,
+    /// The number of lines the documentation of the selected item is scrolled by.
+    doc_scroll: usize,
+    /// The width the documentation panel was last rendered with, resizing starts from it.
+    doc_width: u16,
 }
 
 impl Completion {
@@ -312,6 +323,8 @@ macro_rules! language_server {
             // and avoid allocation during matching
             filter: String::from(fragment),
             resolve_handler: ResolveHandler::new(),
+            doc_scroll: 0,
+            doc_width: 0,
         };
 
         // need to recompute immediately in case start_offset != trigger_offset
@@ -459,6 +472,35 @@ pub fn area(&mut self, viewport: Rect, editor: &Editor) -> Rect {
 
 impl Component for Completion {
     fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        if let Event::Key(key) = event {
+            let docs = &mut cx.editor.completion_docs;
+            match *key {
+                ctrl!('o') => {
+                    docs.visible = !docs.visible;
+                    self.doc_scroll = 0;
+                    return EventResult::Consumed(None);
+                }
+                ctrl!('e') if docs.visible => {
+                    self.doc_scroll += 1;
+                    return EventResult::Consumed(None);
+                }
+                ctrl!('y') if docs.visible => {
+                    self.doc_scroll = self.doc_scroll.saturating_sub(1);
+                    return EventResult::Consumed(None);
+                }
+                alt!(Right) if docs.visible => {
+                    docs.width = Some(self.doc_width.saturating_add(DOC_RESIZE_STEP));
+                    return EventResult::Consumed(None);
+                }
+                alt!(Left) if docs.visible => {
+                    let width = self.doc_width.saturating_sub(DOC_RESIZE_STEP);
+                    docs.width = Some(width.max(MIN_DOC_WIDTH));
+                    return EventResult::Consumed(None);
+                }
+                // The selection may change, start the documentation of the next item at the top
+                _ => self.doc_scroll = 0,
+            }
+        }
         self.popup.handle_event(event, cx)
     }
 
@@ -477,6 +519,9 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         if let CompletionItem::Lsp(option) = option {
             self.resolve_handler.ensure_item_resolved(cx.editor, option);
         }
+        if !cx.editor.completion_docs.visible {
+            return;
+        }
         // need to render:
         // option.detail
         // ---
@@ -496,7 +541,9 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
                 (None, Some(doc)) => doc.to_string(),
                 (None, None) => String::new(),
             };
-            Markdown::new(md, cx.editor.syn_loader.clone())
+            // Language servers document items of the document's language, the code blocks in it
+            // often omit the language
+            Markdown::new(md, cx.editor.syn_loader.clone()).with_default_language(lang)
         };
 
         let mut markdown_doc = match option {
@@ -538,12 +585,19 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
             let x = popup_area.right();
             let y = popup_area.top();
 
+            let resized_width = cx.editor.completion_docs.width;
+            if let Some(width) = resized_width {
+                doc_width = width.min(doc_width);
+            }
             if let Some((rel_width, rel_height)) =
                 markdown_doc.required_size((doc_width, doc_height))
             {
-                doc_width = rel_width.min(doc_width);
+                if resized_width.is_none() {
+                    doc_width = rel_width.min(doc_width);
+                }
                 doc_height = rel_height.min(doc_height);
             }
+            self.doc_width = doc_width;
             Rect::new(x, y, doc_width, doc_height)
         } else {
             // Documentation should not cover the cursor or the completion popup
@@ -576,6 +630,14 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
             Widget::render(Block::bordered(), doc_area, surface);
         }
 
+        // Documentation longer than the panel is scrolled with Ctrl-e/Ctrl-y
+        let content_height = markdown_doc
+            .required_size((doc_area.width, u16::MAX))
+            .map_or(0, |(_, height)| height);
+        let max_scroll = content_height.saturating_sub(doc_area.height) as usize;
+        self.doc_scroll = self.doc_scroll.min(max_scroll);
+        cx.scroll = Some(self.doc_scroll);
+
         markdown_doc.render(doc_area, surface, cx);
     }
 }
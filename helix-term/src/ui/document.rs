@@ -1,13 +1,18 @@
 use std::cmp::min;
+use std::ops::Range;
 
 use helix_core::doc_formatter::{DocumentFormatter, FormattedGrapheme, GraphemeSource, TextFormat};
 use helix_core::graphemes::Grapheme;
+use helix_core::indent::indent_level_for_line;
+use helix_core::line_ending::line_end_char_index;
 use helix_core::str_utils::char_to_byte_idx;
 use helix_core::syntax::{self, HighlightEvent, Highlighter, OverlayHighlights};
 use helix_core::text_annotations::TextAnnotations;
-use helix_core::{visual_offset_from_block, Position, RopeSlice};
+use helix_core::unicode::segmentation::UnicodeSegmentation;
+use helix_core::unicode::width::UnicodeWidthStr;
+use helix_core::{visual_offset_from_block, Position, RopeSlice, Selection};
 use helix_stdx::rope::RopeSliceExt;
-use helix_view::editor::{WhitespaceConfig, WhitespaceRenderValue};
+use helix_view::editor::{WhitespaceConfig, WhitespaceRender, WhitespaceRenderValue};
 use helix_view::graphics::Rect;
 use helix_view::theme::Style;
 use helix_view::view::ViewPosition;
@@ -38,6 +43,8 @@ pub fn render_document(
     overlay_highlights: Vec<syntax::OverlayHighlights>,
     theme: &Theme,
     decorations: DecorationManager,
+    cursor: Option<usize>,
+    selection: Option<&Selection>,
 ) {
     let mut renderer = TextRenderer::new(
         surface,
@@ -46,6 +53,12 @@ pub fn render_document(
         Position::new(offset.vertical_offset, offset.horizontal_offset),
         viewport,
     );
+    if let Some(cursor) = cursor {
+        renderer.set_active_indent_guide(doc, cursor);
+    }
+    if let Some(selection) = selection {
+        renderer.set_selection(selection);
+    }
     render_text(
         &mut renderer,
         doc.text().slice(..),
@@ -119,10 +132,18 @@ pub fn render_text(
             // in that case we don't need to draw indent guides/virtual text
             if last_line_pos.doc_line != usize::MAX {
                 // draw indent guides for the last line
-                renderer.draw_indent_guides(last_line_indent_level, last_line_pos.visual_line);
+                renderer.draw_indent_guides(
+                    last_line_indent_level,
+                    last_line_pos.doc_line,
+                    last_line_pos.visual_line,
+                );
                 is_in_indent_area = true;
                 decorations.render_virtual_lines(renderer, last_line_pos, last_line_end)
             }
+            if grapheme.line_idx != last_line_pos.doc_line {
+                renderer.trailing_whitespace_start =
+                    trailing_whitespace_start(text, grapheme.line_idx);
+            }
             last_line_pos = LinePos {
                 first_visual_line: grapheme.line_idx != last_line_pos.doc_line,
                 doc_line: grapheme.line_idx,
@@ -168,17 +189,66 @@ pub fn render_text(
         last_line_end = grapheme.visual_pos.col + grapheme_width;
     }
 
-    renderer.draw_indent_guides(last_line_indent_level, last_line_pos.visual_line);
+    renderer.draw_indent_guides(
+        last_line_indent_level,
+        last_line_pos.doc_line,
+        last_line_pos.visual_line,
+    );
     decorations.render_virtual_lines(renderer, last_line_pos, last_line_end)
 }
 
+/// Returns the char index of the first space or tab in the trailing whitespace of `line`.
+fn trailing_whitespace_start(text: RopeSlice, line: usize) -> usize {
+    let line_start = text.line_to_char(line);
+    let line_end = line_end_char_index(&text, line);
+    let len = text
+        .chars_at(line_end)
+        .reversed()
+        .take(line_end - line_start)
+        .take_while(|&ch| ch == ' ' || ch == '\t')
+        .count();
+    line_end - len
+}
+
+/// Returns the Unicode control picture (`␀`, `␁`, ..., `␡`) of ASCII control characters.
+fn control_character_picture(grapheme: &str) -> Option<char> {
+    let mut chars = grapheme.chars();
+    let picture = match (chars.next()?, chars.next()) {
+        (ch @ '\0'..='\u{1F}', None) => 0x2400 + ch as u32,
+        ('\u{7F}', None) => 0x2421,
+        _ => return None,
+    };
+    char::from_u32(picture)
+}
+
+/// The indentation level of the scope containing the cursor and the document lines of its body.
+#[derive(Debug)]
+pub struct ActiveIndentGuide {
+    level: usize,
+    lines: Range<usize>,
+}
+
 #[derive(Debug)]
 pub struct TextRenderer<'a> {
     surface: &'a mut Surface,
     pub text_style: Style,
     pub whitespace_style: Style,
+    pub tab_style: Style,
+    pub nbsp_style: Style,
+    pub trailing_whitespace_style: Style,
+    pub control_style: Style,
+    pub whitespace_render: WhitespaceRender,
+    /// The selection of the view, used to render whitespace only inside selections.
+    pub selection: Option<Selection>,
+    /// Spaces and tabs from this char index to the end of the current line are trailing.
+    pub trailing_whitespace_start: usize,
     pub indent_guide_char: String,
     pub indent_guide_style: Style,
+    /// The indent guide of the scope containing the cursor, see
+    /// [`TextRenderer::set_active_indent_guide`].
+    pub active_indent_guide: Option<ActiveIndentGuide>,
+    pub active_indent_guide_char: String,
+    pub active_indent_guide_style: Style,
     pub newline: String,
     pub nbsp: String,
     pub nnbsp: String,
@@ -211,40 +281,27 @@ pub fn new(
             characters: ws_chars,
         } = &editor_config.whitespace;
 
+        // Whether whitespace is rendered is decided per grapheme, see `draw_grapheme`
         let tab_width = doc.tab_width();
-        let tab = if ws_render.tab() == WhitespaceRenderValue::All {
-            std::iter::once(ws_chars.tab)
-                .chain(std::iter::repeat_n(ws_chars.tabpad, tab_width - 1))
-                .collect()
-        } else {
-            " ".repeat(tab_width)
-        };
+        let tab = std::iter::once(ws_chars.tab)
+            .chain(std::iter::repeat_n(ws_chars.tabpad, tab_width - 1))
+            .collect();
         let virtual_tab = " ".repeat(tab_width);
-        let newline = if ws_render.newline() == WhitespaceRenderValue::All {
-            ws_chars.newline.into()
-        } else {
-            " ".to_owned()
-        };
-
-        let space = if ws_render.space() == WhitespaceRenderValue::All {
-            ws_chars.space.into()
-        } else {
-            " ".to_owned()
-        };
-        let nbsp = if ws_render.nbsp() == WhitespaceRenderValue::All {
-            ws_chars.nbsp.into()
-        } else {
-            " ".to_owned()
-        };
-        let nnbsp = if ws_render.nnbsp() == WhitespaceRenderValue::All {
-            ws_chars.nnbsp.into()
-        } else {
-            " ".to_owned()
-        };
+        let newline = ws_chars.newline.into();
+        let space = ws_chars.space.into();
+        let nbsp = ws_chars.nbsp.into();
+        let nnbsp = ws_chars.nnbsp.into();
 
         let text_style = theme.get("ui.text");
+        let whitespace_style = theme.get("ui.virtual.whitespace");
+        let whitespace_scope = |scope: &str| theme.try_get(scope).unwrap_or(whitespace_style);
 
         let indent_width = doc.indent_style.indent_width(tab_width) as u16;
+        let indent_guide_style = text_style.patch(
+            theme
+                .try_get("ui.virtual.indent-guide")
+                .unwrap_or_else(|| theme.get("ui.virtual.whitespace")),
+        );
 
         TextRenderer {
             surface,
@@ -255,16 +312,27 @@ pub fn new(
             space,
             tab,
             virtual_tab,
-            whitespace_style: theme.get("ui.virtual.whitespace"),
+            whitespace_style,
+            tab_style: whitespace_scope("ui.virtual.whitespace.tab"),
+            nbsp_style: whitespace_scope("ui.virtual.whitespace.nbsp"),
+            trailing_whitespace_style: whitespace_scope("ui.virtual.whitespace.trailing"),
+            control_style: whitespace_scope("ui.virtual.control-character"),
+            whitespace_render: *ws_render,
+            selection: None,
+            trailing_whitespace_start: usize::MAX,
             indent_width,
             starting_indent: offset.col / indent_width as usize
                 + !offset.col.is_multiple_of(indent_width as usize) as usize
                 + editor_config.indent_guides.skip_levels as usize,
-            indent_guide_style: text_style.patch(
-                theme
-                    .try_get("ui.virtual.indent-guide")
-                    .unwrap_or_else(|| theme.get("ui.virtual.whitespace")),
-            ),
+            indent_guide_style,
+            active_indent_guide: None,
+            active_indent_guide_char: editor_config
+                .indent_guides
+                .active_character
+                .unwrap_or(editor_config.indent_guides.character)
+                .into(),
+            active_indent_guide_style: indent_guide_style
+                .patch(theme.get("ui.virtual.indent-guide.active")),
             text_style,
             draw_indent_guides: editor_config.indent_guides.render,
             viewport,
@@ -326,36 +394,72 @@ pub fn draw_grapheme(
         position.row -= self.offset.row;
         let cut_off_start = self.offset.col.saturating_sub(position.col);
         let is_whitespace = grapheme.is_whitespace();
-
-        // TODO is it correct to apply the whitespace style to all unicode white spaces?
-        let mut style = grapheme_style.syntax_style;
-        if is_whitespace {
-            style = style.patch(self.whitespace_style);
-        }
-        style = style.patch(grapheme_style.overlay_style);
+        let char_idx = grapheme.char_idx;
+        let render = self.whitespace_render;
+        let is_trailing = !is_virtual
+            && char_idx >= self.trailing_whitespace_start
+            && !matches!(grapheme.raw, Grapheme::Newline)
+            && !grapheme.source.is_eof()
+            && self.show_whitespace(render.trailing(), char_idx);
+        let show = |value| !is_virtual && self.show_whitespace(value, char_idx);
 
         let width = grapheme.width();
-        let space = if is_virtual { " " } else { &self.space };
-        let nbsp = if is_virtual { " " } else { &self.nbsp };
-        let nnbsp = if is_virtual { " " } else { &self.nnbsp };
-        let tab = if is_virtual {
-            &self.virtual_tab
-        } else {
-            &self.tab
-        };
-        let grapheme = match grapheme.raw {
+        let mut control_picture = [0; 4];
+        let (grapheme, kind_style) = match grapheme.raw {
             Grapheme::Tab { width } => {
+                let tab = if is_trailing || show(render.tab()) {
+                    &self.tab
+                } else {
+                    &self.virtual_tab
+                };
                 let grapheme_tab_width = char_to_byte_idx(tab, width);
-                &tab[..grapheme_tab_width]
+                (&tab[..grapheme_tab_width], Some(self.tab_style))
             }
-            // TODO special rendering for other whitespaces?
-            Grapheme::Other { ref g } if g == " " && !grapheme.source.is_eof() => space,
-            Grapheme::Other { ref g } if g == "\u{00A0}" => nbsp,
-            Grapheme::Other { ref g } if g == "\u{202F}" => nnbsp,
-            Grapheme::Other { ref g } => g,
-            Grapheme::Newline => &self.newline,
+            Grapheme::Other { ref g } if g == " " && !grapheme.source.is_eof() => {
+                if is_trailing || show(render.space()) {
+                    (self.space.as_str(), None)
+                } else {
+                    (" ", None)
+                }
+            }
+            Grapheme::Other { ref g } if g == "\u{00A0}" => {
+                let nbsp = if show(render.nbsp()) {
+                    self.nbsp.as_str()
+                } else {
+                    " "
+                };
+                (nbsp, Some(self.nbsp_style))
+            }
+            Grapheme::Other { ref g } if g == "\u{202F}" => {
+                let nnbsp = if show(render.nnbsp()) {
+                    self.nnbsp.as_str()
+                } else {
+                    " "
+                };
+                (nnbsp, Some(self.nbsp_style))
+            }
+            Grapheme::Other { ref g } => match control_character_picture(g) {
+                Some(picture) if show(render.control()) => (
+                    &*picture.encode_utf8(&mut control_picture),
+                    Some(self.control_style),
+                ),
+                _ => (&**g, None),
+            },
+            Grapheme::Newline if show(render.newline()) => (self.newline.as_str(), None),
+            Grapheme::Newline => (" ", None),
         };
 
+        // TODO is it correct to apply the whitespace style to all unicode white spaces?
+        let mut style = grapheme_style.syntax_style;
+        if is_trailing && is_whitespace {
+            style = style.patch(self.trailing_whitespace_style);
+        } else if let Some(kind_style) = kind_style {
+            style = style.patch(kind_style);
+        } else if is_whitespace {
+            style = style.patch(self.whitespace_style);
+        }
+        style = style.patch(grapheme_style.overlay_style);
+
         let in_bounds = self.column_in_bounds(position.col, width);
 
         if in_bounds {
@@ -383,17 +487,78 @@ pub fn draw_grapheme(
         width
     }
 
+    /// Whether whitespace configured with `value` is rendered at `char_idx`.
+    fn show_whitespace(&self, value: WhitespaceRenderValue, char_idx: usize) -> bool {
+        match value {
+            WhitespaceRenderValue::None => false,
+            WhitespaceRenderValue::All => true,
+            WhitespaceRenderValue::Selection => self.selection.as_ref().is_some_and(|selection| {
+                let ranges = selection.ranges();
+                let idx = ranges.partition_point(|range| range.to() <= char_idx);
+                ranges
+                    .get(idx)
+                    .is_some_and(|range| range.from() <= char_idx)
+            }),
+        }
+    }
+
+    /// Sets the selection used for `WhitespaceRenderValue::Selection`.
+    pub fn set_selection(&mut self, selection: &Selection) {
+        if self.whitespace_render.uses_selection() {
+            self.selection = Some(selection.clone());
+        }
+    }
+
     pub fn column_in_bounds(&self, colum: usize, width: usize) -> bool {
         self.offset.col <= colum && colum + width <= self.offset.col + self.viewport.width as usize
     }
 
+    /// Draws a single line of virtual `text` at the visual position `row`/`col` of the
+    /// document, before scrolling is applied. Parts of the text that are scrolled out of view
+    /// are skipped so that the text moves with the document when scrolling horizontally.
+    ///
+    /// # Returns
+    ///
+    /// The visual column after the end of the text
+    pub fn draw_virtual_text(
+        &mut self,
+        text: &str,
+        style: Style,
+        row: u16,
+        mut col: usize,
+    ) -> usize {
+        let visible_row = (row as usize)
+            .checked_sub(self.offset.row)
+            .filter(|&row| row < self.viewport.height as usize);
+        for grapheme in text.graphemes(true) {
+            let width = grapheme.width();
+            if let Some(visible_row) = visible_row {
+                if self.column_in_bounds(col, width) {
+                    self.surface.set_string(
+                        self.viewport.x + (col - self.offset.col) as u16,
+                        self.viewport.y + visible_row as u16,
+                        grapheme,
+                        style,
+                    );
+                }
+            }
+            col += width;
+        }
+        col
+    }
+
     /// Overlay indentation guides ontop of a rendered line
     /// The indentation level is computed in `draw_lines`.
     /// Therefore this function must always be called afterwards.
-    pub fn draw_indent_guides(&mut self, indent_level: usize, mut row: u16) {
+    pub fn draw_indent_guides(&mut self, indent_level: usize, doc_line: usize, mut row: u16) {
         if !self.draw_indent_guides || self.offset.row > row as usize {
             return;
         }
+        let active_level = self
+            .active_indent_guide
+            .as_ref()
+            .filter(|guide| guide.lines.contains(&doc_line))
+            .map(|guide| guide.level);
         row -= self.offset.row as u16;
 
         // Don't draw indent guides outside of view
@@ -409,8 +574,53 @@ pub fn draw_indent_guides(&mut self, indent_level: usize, mut row: u16) {
                 as u16;
             let y = self.viewport.y + row;
             debug_assert!(self.surface.in_bounds(x, y));
-            self.surface
-                .set_string(x, y, &self.indent_guide_char, self.indent_guide_style);
+            if active_level == Some(i) {
+                self.surface.set_string(
+                    x,
+                    y,
+                    &self.active_indent_guide_char,
+                    self.active_indent_guide_style,
+                );
+            } else {
+                self.surface
+                    .set_string(x, y, &self.indent_guide_char, self.indent_guide_style);
+            }
+        }
+    }
+
+    /// Emphasizes the indent guide of the innermost syntax node which contains `cursor` and
+    /// spans multiple lines, if `indent-guides.highlight-active-scope` is enabled.
+    pub fn set_active_indent_guide(&mut self, doc: &Document, cursor: usize) {
+        self.active_indent_guide = None;
+        if !doc.config.load().indent_guides.highlight_active_scope {
+            return;
+        }
+        let Some(syntax) = doc.syntax() else {
+            return;
+        };
+        let text = doc.text().slice(..);
+        let cursor = cursor.min(text.len_chars());
+        let cursor_line = text.char_to_line(cursor);
+        let byte = text.char_to_byte(cursor) as u32;
+
+        let mut node = syntax.descendant_for_byte_range(byte, byte);
+        while let Some(current) = node {
+            let start_line = text.byte_to_line(current.start_byte() as usize);
+            let end_line = text.byte_to_line(current.end_byte() as usize);
+            if start_line < cursor_line && cursor_line <= end_line {
+                let level = indent_level_for_line(
+                    text.line(start_line),
+                    doc.tab_width(),
+                    self.indent_width as usize,
+                );
+                // the guide is shown on the body, between the first and the last line
+                self.active_indent_guide = Some(ActiveIndentGuide {
+                    level,
+                    lines: start_line + 1..end_line,
+                });
+                return;
+            }
+            node = current.parent();
         }
     }
 
@@ -2,22 +2,28 @@
     commands::{self, OnKeyCallback, OnKeyCallbackKind},
     compositor::{Component, Context, Event, EventResult},
     events::{OnModeSwitch, PostCommand},
-    handlers::completion::CompletionItem,
+    handlers::{auto_save, completion::CompletionItem},
+    idle::{IdlePriority, IdleScheduler, IdleTask},
     key,
-    keymap::{KeymapResult, Keymaps},
+    keymap::{KeyTrie, KeymapResult, Keymaps},
     ui::{
         document::{render_document, LinePos, TextRenderer},
+        overlay::overlaid,
         statusline,
-        text_decorations::{self, Decoration, DecorationManager, InlineDiagnostics},
-        Completion, ProgressSpinners,
+        text_decorations::{
+            self, Decoration, DecorationManager, InlineDiagnostics, VirtualTextDecoration,
+        },
+        winbar::Winbar,
+        Completion, ProgressSpinners, SyntaxTreePanel, TerminalPanel,
     },
 };
 
 use helix_core::{
     diagnostic::NumberOrString,
+    doc_formatter,
     graphemes::{next_grapheme_boundary, prev_grapheme_boundary},
     movement::Direction,
-    syntax::{self, OverlayHighlights},
+    syntax::{self, Highlight, OverlayHighlights},
     text_annotations::TextAnnotations,
     unicode::width::UnicodeWidthStr,
     visual_offset_from_block, Change, Position, Range, Selection, Transaction,
@@ -25,13 +31,13 @@
 use helix_view::{
     annotations::diagnostics::DiagnosticFilter,
     document::{Mode, SCRATCH_BUFFER_NAME},
-    editor::{CompleteAction, CursorShapeConfig},
+    editor::{CompleteAction, CursorHighlightViews, CursorShapeConfig},
     graphics::{Color, CursorKind, Modifier, Rect, Style},
     input::{KeyEvent, MouseButton, MouseEvent, MouseEventKind},
     keyboard::{KeyCode, KeyModifiers},
     Document, Editor, Theme, View,
 };
-use std::{mem::take, num::NonZeroUsize, ops, path::PathBuf, rc::Rc};
+use std::{mem::take, num::NonZeroUsize, ops, path::PathBuf, rc::Rc, time::Duration};
 
 use tui::{buffer::Buffer as Surface, text::Span};
 
@@ -42,10 +48,29 @@ pub struct EditorView {
     pub(crate) last_insert: (commands::MappableCommand, Vec<InsertEvent>),
     pub(crate) completion: Option<Completion>,
     spinners: ProgressSpinners,
+    idle_tasks: IdleScheduler,
+    winbar: Winbar,
+    /// The syntax tree panel on the right of the screen, see `:tree-sitter-playground`.
+    pub syntax_tree: Option<SyntaxTreePanel>,
+    /// The terminal panel below the views, see `:terminal`.
+    pub terminal_panel: Option<TerminalPanel>,
+    /// The screen area, to lay out the panels when handling events rather than when drawing.
+    area: Rect,
+    /// How the last key was handled, see `:key-trace`.
+    key_trace: Option<String>,
     /// Tracks if the terminal window is focused by reaction to terminal focus events
     terminal_focused: bool,
 }
 
+/// The parts of the screen, see [`EditorView::layout`].
+struct Layout {
+    bufferline: bool,
+    layoutline: bool,
+    views: Rect,
+    syntax_tree: Option<Rect>,
+    terminal: Option<Rect>,
+}
+
 #[derive(Debug, Clone)]
 pub enum InsertEvent {
     Key(KeyEvent),
@@ -59,6 +84,23 @@ pub enum InsertEvent {
 
 impl EditorView {
     pub fn new(keymaps: Keymaps) -> Self {
+        let mut idle_tasks = IdleScheduler::new();
+        idle_tasks.register(IdleTask::new(
+            "inlay-hints",
+            IdlePriority::Normal,
+            Duration::from_millis(5),
+            commands::compute_inlay_hints_for_all_views,
+        ));
+        idle_tasks.register(
+            IdleTask::new(
+                "auto-save",
+                IdlePriority::Low,
+                Duration::from_millis(50),
+                auto_save::save_after_delay,
+            )
+            .after(auto_save::after_delay),
+        );
+
         Self {
             keymaps,
             on_next_key: None,
@@ -66,14 +108,83 @@ pub fn new(keymaps: Keymaps) -> Self {
             last_insert: (commands::MappableCommand::normal_mode, Vec::new()),
             completion: None,
             spinners: ProgressSpinners::default(),
+            idle_tasks,
+            winbar: Winbar::default(),
+            syntax_tree: None,
+            terminal_panel: None,
+            area: Rect::default(),
+            key_trace: None,
             terminal_focused: true,
         }
     }
 
+    /// Splits `area` into the bufferline, the views and the panels shown.
+    fn layout(&self, editor: &Editor, area: Rect) -> Layout {
+        // check if bufferline should be rendered
+        use helix_view::editor::BufferLine;
+        let bufferline = match editor.config().bufferline {
+            BufferLine::Always => true,
+            BufferLine::Multiple if editor.documents.len() > 1 => true,
+            _ => false,
+        };
+
+        // the layouts are listed on the bufferline row
+        let layoutline = editor.tree.layout_count() > 1;
+
+        // -1 for commandline and -1 for bufferline
+        let mut views = area.clip_bottom(1);
+        if bufferline || layoutline {
+            views = views.clip_top(1);
+        }
+        let syntax_tree = self.syntax_tree.as_ref().map(|_| {
+            let width = SyntaxTreePanel::width(views.width);
+            let panel_area = views.clip_left(views.width - width);
+            views = views.clip_right(width);
+            panel_area
+        });
+        let terminal = self.terminal_panel.as_ref().map(|_| {
+            let height = TerminalPanel::height(views.height);
+            let panel_area = views.clip_top(views.height - height);
+            views = views.clip_bottom(height);
+            panel_area
+        });
+        Layout {
+            bufferline,
+            layoutline,
+            views,
+            syntax_tree,
+            terminal,
+        }
+    }
+
+    /// Shows the terminal panel, see [`TerminalPanel::open`].
+    pub fn open_terminal(&mut self, editor: &mut Editor) -> std::io::Result<()> {
+        self.terminal_panel = Some(TerminalPanel::open(editor)?);
+        self.resize_terminal(editor);
+        Ok(())
+    }
+
+    /// Resizes the terminal to its panel. This is called after events which may change the
+    /// layout, as resizing the terminal makes the shell redraw.
+    pub fn resize_terminal(&self, editor: &mut Editor) {
+        let Some(area) = self.layout(editor, self.area).terminal else {
+            return;
+        };
+        if let Some(terminal) = &mut editor.terminal {
+            let inner = TerminalPanel::inner(area);
+            terminal.resize(inner.height, inner.width);
+        }
+    }
+
     pub fn spinners_mut(&mut self) -> &mut ProgressSpinners {
         &mut self.spinners
     }
 
+    /// The tasks run when the editor goes idle. Features register their idle work here.
+    pub fn idle_tasks_mut(&mut self) -> &mut IdleScheduler {
+        &mut self.idle_tasks
+    }
+
     pub fn render_view(
         &self,
         editor: &Editor,
@@ -85,8 +196,14 @@ pub fn render_view(
     ) {
         let inner = view.inner_area(doc);
         let area = view.area;
-        let theme = &editor.theme;
         let config = editor.config();
+        let theme = match &editor.inactive_theme {
+            Some(theme) if !is_focused => {
+                surface.set_style(area, theme.get("ui.background"));
+                theme
+            }
+            _ => &editor.theme,
+        };
         let loader = editor.syn_loader.load();
 
         let view_offset = doc.view_offset(view.id);
@@ -94,11 +211,15 @@ pub fn render_view(
         let text_annotations = view.text_annotations(doc, Some(theme));
         let mut decorations = DecorationManager::default();
 
-        if is_focused && config.cursorline {
+        // Throttled rendering skips decorations which change on every cursor motion
+        let throttled = editor.throttle_rendering();
+        let highlight_cursors = !throttled
+            && (is_focused || config.cursor_highlight_views == CursorHighlightViews::All);
+        if highlight_cursors && config.cursorline {
             decorations.add_decoration(Self::cursorline(doc, view, theme));
         }
 
-        if is_focused && config.cursorcolumn {
+        if highlight_cursors && config.cursorcolumn {
             Self::highlight_cursorcolumn(doc, view, surface, theme, inner, &text_annotations);
         }
 
@@ -156,6 +277,9 @@ pub fn render_view(
             if let Some(overlay) = Self::highlight_focused_view_elements(view, doc, theme) {
                 overlays.push(overlay);
             }
+            if let Some(syntax_tree) = &self.syntax_tree {
+                overlays.extend(syntax_tree.overlays(theme));
+            }
         }
 
         let gutter_overflow = view.gutter_offset(doc) == 0;
@@ -196,6 +320,9 @@ pub fn render_view(
             inline_diagnostic_config,
             config.end_of_line_diagnostics,
         ));
+        if !doc.virtual_text().is_empty() {
+            decorations.add_decoration(VirtualTextDecoration::new(doc, theme));
+        }
         render_document(
             surface,
             inner,
@@ -206,8 +333,22 @@ pub fn render_view(
             overlays,
             theme,
             decorations,
+            (is_focused && !throttled).then_some(primary_cursor),
+            Some(doc.selection(view.id)),
         );
 
+        if !is_focused && config.dim_inactive_views {
+            let style = theme
+                .try_get("ui.view.inactive")
+                .unwrap_or_else(|| Style::default().add_modifier(Modifier::DIM));
+            surface.set_style(area, style);
+        }
+        if !is_focused && config.inactive_views.dim > 0 {
+            let background = theme.get("ui.background").bg;
+            let amount = config.inactive_views.dim.min(100);
+            crate::compositor::dim_surface(surface, area, background, amount);
+        }
+
         // if we're not at the edge of the screen, draw a right border
         if viewport.right() != view.area.right() {
             let x = area.right();
@@ -231,8 +372,15 @@ pub fn render_view(
             .clip_top(view.area.height.saturating_sub(1))
             .clip_bottom(1); // -1 from bottom to remove commandline
 
-        let mut context =
-            statusline::RenderContext::new(editor, doc, view, is_focused, &self.spinners);
+        let key_layer = self.keymaps.sticky().map(|node| node.name());
+        let mut context = statusline::RenderContext::new(
+            editor,
+            doc,
+            view,
+            is_focused,
+            &self.spinners,
+            key_layer,
+        );
 
         statusline::render(&mut context, statusline_area, surface);
     }
@@ -269,17 +417,22 @@ pub fn render_rulers(
 
     fn viewport_byte_range(
         text: helix_core::RopeSlice,
-        row: usize,
+        anchor: usize,
         height: u16,
     ) -> std::ops::Range<usize> {
         // Calculate viewport byte ranges:
         // Saturating subs to make it inclusive zero indexing.
+        let row = text.char_to_line(anchor.min(text.len_chars()));
         let last_line = text.len_lines().saturating_sub(1);
         let last_visible_line = (row + height as usize).saturating_sub(1).min(last_line);
-        let start = text.line_to_byte(row.min(last_line));
-        let end = text.line_to_byte(last_visible_line + 1);
-
-        start..end
+        // A row shows at most one block of a very long line, the rest of the line doesn't
+        // have to be highlighted
+        let start = doc_formatter::block_start(text, anchor);
+        let end = text
+            .line_to_char(last_visible_line + 1)
+            .min(start + height as usize * doc_formatter::MAX_BLOCK_CHARS);
+
+        text.char_to_byte(start)..text.char_to_byte(end)
     }
 
     /// Get the syntax highlighter for a document in a view represented by the first line
@@ -293,8 +446,7 @@ pub fn doc_syntax_highlighter<'editor>(
     ) -> Option<syntax::Highlighter<'editor>> {
         let syntax = doc.syntax()?;
         let text = doc.text().slice(..);
-        let row = text.char_to_line(anchor.min(text.len_chars()));
-        let range = Self::viewport_byte_range(text, row, height);
+        let range = Self::viewport_byte_range(text, anchor, height);
         let range = range.start as u32..range.end as u32;
 
         let highlighter = syntax.highlighter(text, loader, range);
@@ -308,9 +460,7 @@ pub fn overlay_syntax_highlights(
         text_annotations: &TextAnnotations,
     ) -> OverlayHighlights {
         let text = doc.text().slice(..);
-        let row = text.char_to_line(anchor.min(text.len_chars()));
-
-        let mut range = Self::viewport_byte_range(text, row, height);
+        let mut range = Self::viewport_byte_range(text, anchor, height);
         range = text.byte_to_char(range.start)..text.byte_to_char(range.end);
 
         text_annotations.collect_overlay_highlights(range)
@@ -325,8 +475,7 @@ pub fn doc_rainbow_highlights(
     ) -> Option<OverlayHighlights> {
         let syntax = doc.syntax()?;
         let text = doc.text().slice(..);
-        let row = text.char_to_line(anchor.min(text.len_chars()));
-        let visible_range = Self::viewport_byte_range(text, row, height);
+        let visible_range = Self::viewport_byte_range(text, anchor, height);
         let start = syntax::child_for_byte_range(
             &syntax.tree().root_node(),
             visible_range.start as u32..visible_range.end as u32,
@@ -334,7 +483,20 @@ pub fn doc_rainbow_highlights(
         .map_or(visible_range.start as u32, |node| node.start_byte());
         let range = start..visible_range.end as u32;
 
-        Some(syntax.rainbow_highlights(text, theme.rainbow_length(), loader, range))
+        // The theme's `rainbow` styles come first in its highlights
+        let rainbow_scopes = &doc.config.load().rainbow_scopes;
+        let palette: Vec<_> = if rainbow_scopes.is_empty() {
+            (0..theme.rainbow_length() as u32)
+                .map(Highlight::new)
+                .collect()
+        } else {
+            rainbow_scopes
+                .iter()
+                .filter_map(|scope| theme.find_highlight(scope))
+                .collect()
+        };
+
+        Some(syntax.rainbow_highlights(text, &palette, loader, range))
     }
 
     /// Get highlight spans for document diagnostics
@@ -612,6 +774,8 @@ pub fn render_bufferline(editor: &Editor, viewport: Rect, surface: &mut Surface)
             .try_get("ui.bufferline")
             .unwrap_or_else(|| editor.theme.get("ui.statusline.inactive"));
 
+        let jump_label = editor.theme.get("ui.virtual.jump-label");
+
         let mut x = viewport.x;
         let current_doc = view!(editor).doc;
 
@@ -634,16 +798,91 @@ pub fn render_bufferline(editor: &Editor, viewport: Rect, surface: &mut Surface)
             let used_width = viewport.x.saturating_sub(x);
             let rem_width = surface.area.width.saturating_sub(used_width);
 
+            let start = x;
             x = surface
                 .set_stringn(x, viewport.y, text, rem_width as usize, style)
                 .0;
 
+            // the label covers the start of the file name
+            if let Some((_, label)) = editor
+                .bufferline_jump_labels
+                .iter()
+                .find(|(id, _)| *id == doc.id())
+            {
+                let width = x.saturating_sub(start + 1) as usize;
+                surface.set_stringn(start + 1, viewport.y, label, width, jump_label);
+            }
+
             if x >= surface.area.right() {
                 break;
             }
         }
     }
 
+    /// Render the list of layouts right-aligned on the bufferline row
+    pub fn render_layouts(editor: &Editor, viewport: Rect, surface: &mut Surface, clear: bool) {
+        if clear {
+            surface.clear_with(
+                viewport,
+                editor
+                    .theme
+                    .try_get("ui.bufferline.background")
+                    .unwrap_or_else(|| editor.theme.get("ui.statusline")),
+            );
+        }
+
+        let layout_active = editor
+            .theme
+            .try_get("ui.bufferline.active")
+            .unwrap_or_else(|| editor.theme.get("ui.statusline.active"));
+
+        let layout_inactive = editor
+            .theme
+            .try_get("ui.bufferline")
+            .unwrap_or_else(|| editor.theme.get("ui.statusline.inactive"));
+
+        let current = editor.tree.current_layout();
+        let labels: Vec<_> = editor
+            .tree
+            .layout_names()
+            .enumerate()
+            .map(|(i, name)| {
+                let number = (i + 1).to_string();
+                let label = if name == number {
+                    format!(" {number} ")
+                } else {
+                    format!(" {number}:{name} ")
+                };
+                let style = if i == current {
+                    layout_active
+                } else {
+                    layout_inactive
+                };
+                (label, style)
+            })
+            .collect();
+
+        let width: usize = labels.iter().map(|(label, _)| label.width()).sum();
+        let mut x = viewport
+            .right()
+            .saturating_sub(width as u16)
+            .max(viewport.x);
+        for (label, style) in labels {
+            x = surface
+                .set_stringn(
+                    x,
+                    viewport.y,
+                    label,
+                    viewport.right().saturating_sub(x) as usize,
+                    style,
+                )
+                .0;
+            if x >= viewport.right() {
+                break;
+            }
+        }
+    }
+
     pub fn render_gutter<'d>(
         editor: &'d Editor,
         doc: &'d Document,
@@ -667,7 +906,7 @@ pub fn render_gutter<'d>(
         let gutter_style_virtual = theme.get("ui.gutter.virtual");
         let gutter_selected_style_virtual = theme.get("ui.gutter.selected.virtual");
 
-        for gutter_type in view.gutters() {
+        for gutter_type in view.gutters(doc) {
             let mut gutter = gutter_type.style(editor, doc, view, theme, is_focused);
             let width = gutter_type.width(view, doc);
             // avoid lots of small allocations by reusing a text buffer for each line
@@ -868,11 +1107,36 @@ fn handle_keymap_event(
         cxt: &mut commands::Context,
         event: KeyEvent,
     ) -> Option<KeymapResult> {
-        let mut last_mode = mode;
         self.pseudo_pending.extend(self.keymaps.pending());
         let key_result = self.keymaps.get(mode, event);
         cxt.editor.autoinfo = self.keymaps.sticky().map(|node| node.infobox());
+        if let Some(timeout) = self.keymaps.pending_timeout() {
+            cxt.editor.schedule_idle_timer(timeout);
+        }
+
+        self.execute_keymap_result(mode, cxt, key_result)
+    }
+
+    fn execute_keymap_result(
+        &mut self,
+        mode: Mode,
+        cxt: &mut commands::Context,
+        key_result: KeymapResult,
+    ) -> Option<KeymapResult> {
+        let resolution = match &key_result {
+            KeymapResult::Matched(command) => format!("`{}`", command.name()),
+            KeymapResult::MatchedSequence(commands) => commands
+                .iter()
+                .map(|command| format!("`{}`", command.name()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            KeymapResult::Pending(node) => format!("pending in `{}`", node.name()),
+            KeymapResult::NotFound => "not bound".to_string(),
+            KeymapResult::Cancelled(_) => "cancelled the pending keys".to_string(),
+        };
+        self.key_trace = Some(format!("{mode} keymap: {resolution}"));
 
+        let mut last_mode = mode;
         let mut execute_command = |command: &commands::MappableCommand| {
             command.execute(cxt);
             helix_event::dispatch(PostCommand { command, cx: cxt });
@@ -923,25 +1187,44 @@ fn insert_mode(&mut self, cx: &mut commands::Context, event: KeyEvent) {
                         }
                     }
                 }
-                KeymapResult::Cancelled(pending) => {
-                    for ev in pending {
-                        match ev.char() {
-                            Some(ch) => commands::insert::insert_char(cx, ch),
-                            None => {
-                                if let KeymapResult::Matched(command) =
-                                    self.keymaps.get(Mode::Insert, ev)
-                                {
-                                    command.execute(cx);
-                                }
-                            }
-                        }
+                KeymapResult::Cancelled(pending) => self.insert_cancelled_keys(cx, pending),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Inserts keys which were pending in insert mode but didn't complete a binding.
+    fn insert_cancelled_keys(&mut self, cx: &mut commands::Context, pending: Vec<KeyEvent>) {
+        for ev in pending {
+            match ev.char() {
+                Some(ch) => commands::insert::insert_char(cx, ch),
+                None => {
+                    if let KeymapResult::Matched(command) = self.keymaps.get(Mode::Insert, ev) {
+                        command.execute(cx);
                     }
                 }
-                _ => unreachable!(),
             }
         }
     }
 
+    /// Resolves pending keys whose keymap node timed out. Returns whether there were any.
+    fn handle_keymap_timeout(&mut self, cx: &mut commands::Context) -> bool {
+        let Some(key_result) = self.keymaps.timeout() else {
+            return false;
+        };
+        let mode = cx.editor.mode();
+        cx.editor.autoinfo = self.keymaps.sticky().map(|node| node.infobox());
+        if let Some(KeymapResult::Cancelled(pending)) =
+            self.execute_keymap_result(mode, cx, key_result)
+        {
+            if mode == Mode::Insert {
+                self.insert_cancelled_keys(cx, pending);
+            }
+        }
+        self.pseudo_pending.clear();
+        true
+    }
+
     fn command_mode(&mut self, mode: Mode, cxt: &mut commands::Context, event: KeyEvent) {
         match (event, cxt.editor.count) {
             // If the count is already started and the input is a number, always continue the count.
@@ -952,11 +1235,13 @@ fn command_mode(&mut self, mode: Mode, cxt: &mut commands::Context, event: KeyEv
                     return;
                 }
                 cxt.editor.count = NonZeroUsize::new(count);
+                self.key_trace = Some("count".to_string());
             }
             // A non-zero digit will start the count if that number isn't used by a keymap.
             (key!(i @ '1'..='9'), None) if !self.keymaps.contains_key(mode, event) => {
                 let i = i.to_digit(10).unwrap() as usize;
                 cxt.editor.count = NonZeroUsize::new(i);
+                self.key_trace = Some("count".to_string());
             }
             // special handling for repeat operator
             (key!('.'), _) if self.keymaps.pending().is_empty() => {
@@ -1090,19 +1375,66 @@ pub fn clear_completion(&mut self, editor: &mut Editor) -> Option<OnKeyCallback>
     }
 
     pub fn handle_idle_timeout(&mut self, cx: &mut commands::Context) -> EventResult {
-        commands::compute_inlay_hints_for_all_views(cx.editor, cx.jobs);
+        if self.handle_keymap_timeout(cx) {
+            // The idle timer fired early for the pending keys, wait for the actual idle timeout.
+            cx.editor.reset_idle_timer();
+            return EventResult::Consumed(None);
+        }
+        self.idle_tasks.run(cx.editor, cx.jobs);
 
         EventResult::Ignored(None)
     }
 }
 
 impl EditorView {
+    /// Sends keys and pastes to the terminal panel while it's focused, see [`TerminalPanel`].
+    /// Returns `None` for the events the editor handles as usual.
+    fn handle_terminal_event(
+        &mut self,
+        event: &Event,
+        cx: &mut crate::compositor::Context,
+    ) -> Option<EventResult> {
+        let terminal_panel = self.terminal_panel.as_mut()?;
+        match event {
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(_),
+                row,
+                column,
+                ..
+            }) => {
+                terminal_panel.focused = terminal_panel.contains(*column, *row);
+                return terminal_panel
+                    .focused
+                    .then_some(EventResult::Consumed(None));
+            }
+            _ if !terminal_panel.focused => return None,
+            Event::Key(key) => {
+                let mut key = *key;
+                canonicalize_key(&mut key);
+                let hides_panel = matches!(
+                    self.keymaps.map()[&Mode::Normal].search(&[key]),
+                    Some(KeyTrie::MappableCommand(command)) if command.name() == "toggle_terminal"
+                );
+                if hides_panel {
+                    self.terminal_panel = None;
+                    return Some(EventResult::Consumed(None));
+                }
+            }
+            _ => (),
+        }
+        match terminal_panel.handle_event(event, cx) {
+            EventResult::Ignored(_) => None,
+            result => Some(result),
+        }
+    }
+
     /// must be called whenever the editor processed input that
     /// is not a `KeyEvent`. In these cases any pending keys/on next
     /// key callbacks must be canceled.
     fn handle_non_key_input(&mut self, cxt: &mut commands::Context) {
-        cxt.editor.status_msg = None;
+        cxt.editor.clear_status();
         cxt.editor.reset_idle_timer();
+        self.idle_tasks.cancel();
         // HACKS: create a fake key event that will never trigger any actual map
         // and therefore simply acts as "dismiss"
         let null_key_event = KeyEvent {
@@ -1158,6 +1490,15 @@ fn handle_mouse_event(
             MouseEventKind::Down(MouseButton::Left) => {
                 let editor = &mut cxt.editor;
 
+                if let Some((view_id, crumb)) = self.winbar.breadcrumb_at(editor, row, column) {
+                    let range = crumb.range.clone();
+                    editor.focus(view_id);
+                    let doc = doc_mut!(editor, &view!(editor, view_id).doc);
+                    doc.set_selection(view_id, Selection::single(range.start, range.end));
+                    editor.ensure_cursor_in_view(view_id);
+                    return EventResult::Consumed(None);
+                }
+
                 if let Some((pos, view_id)) = pos_and_view(editor, row, column, true) {
                     editor.focus(view_id);
 
@@ -1244,6 +1585,7 @@ fn handle_mouse_event(
 
                 let offset = config.scroll_lines.unsigned_abs();
                 commands::scroll(cxt, offset, direction, false);
+                cxt.editor.sync_scroll_bind(cxt.editor.tree.focus);
 
                 cxt.editor.tree.focus = current_view;
                 cxt.editor.ensure_cursor_in_view(current_view);
@@ -1352,14 +1694,15 @@ fn on_next_key(
             false
         }
     }
-}
 
-impl Component for EditorView {
-    fn handle_event(
+    fn handle_editor_event(
         &mut self,
         event: &Event,
         context: &mut crate::compositor::Context,
     ) -> EventResult {
+        if let Some(result) = self.handle_terminal_event(event, context) {
+            return result;
+        }
         let mut cx = commands::Context {
             editor: context.editor,
             count: None,
@@ -1373,6 +1716,16 @@ fn handle_event(
             Event::Paste(contents) => {
                 self.handle_non_key_input(&mut cx);
                 cx.count = cx.editor.count;
+                if commands::paste_file_paths(&mut cx, contents) {
+                    cx.editor.count = None;
+                    let callbacks = take(&mut cx.callback);
+                    let callback: crate::compositor::Callback = Box::new(move |compositor, cx| {
+                        for callback in callbacks {
+                            callback(compositor, cx)
+                        }
+                    });
+                    return EventResult::Consumed(Some(callback));
+                }
                 commands::paste_bracketed_value(&mut cx, contents.clone());
                 cx.editor.count = None;
 
@@ -1380,6 +1733,9 @@ fn handle_event(
                 let mode = cx.editor.mode();
                 let (view, doc) = current!(cx.editor);
                 view.ensure_cursor_in_view(doc, config.scrolloff);
+                let view_id = view.id;
+                cx.editor.sync_scroll_bind(view_id);
+                let (view, doc) = current!(cx.editor);
 
                 // Store a history state if not in insert mode. Otherwise wait till we exit insert
                 // to include any edits to the paste in the history state.
@@ -1395,15 +1751,21 @@ fn handle_event(
                 EventResult::Consumed(None)
             }
             Event::Key(mut key) => {
+                // The idle timer may not have fired yet if the pending keys timed out in between.
+                self.handle_keymap_timeout(&mut cx);
+                self.key_trace = None;
                 cx.editor.reset_idle_timer();
+                self.idle_tasks.cancel();
                 canonicalize_key(&mut key);
 
                 // clear status
-                cx.editor.status_msg = None;
+                cx.editor.clear_status();
 
                 let mode = cx.editor.mode();
 
-                if !self.on_next_key(OnKeyCallbackKind::PseudoPending, &mut cx, key) {
+                if self.on_next_key(OnKeyCallbackKind::PseudoPending, &mut cx, key) {
+                    self.key_trace = Some("taken by the pending command".to_string());
+                } else {
                     match mode {
                         Mode::Insert => {
                             // let completion swallow the event if necessary
@@ -1448,7 +1810,9 @@ fn handle_event(
                             }
 
                             // if completion didn't take the event, we pass it onto commands
-                            if !consumed {
+                            if consumed {
+                                self.key_trace = Some("completion menu".to_string());
+                            } else {
                                 self.insert_mode(&mut cx, key);
 
                                 // record last_insert key
@@ -1479,6 +1843,9 @@ fn handle_event(
                 let (view, doc) = current!(cx.editor);
 
                 view.ensure_cursor_in_view(doc, config.scrolloff);
+                let view_id = view.id;
+                cx.editor.sync_scroll_bind(view_id);
+                let (view, doc) = current!(cx.editor);
 
                 // Store a history state if not in insert mode. This also takes care of
                 // committing changes when leaving insert mode.
@@ -1503,43 +1870,70 @@ fn handle_event(
             Event::IdleTimeout => self.handle_idle_timeout(&mut cx),
             Event::FocusGained => {
                 self.terminal_focused = true;
+                context.editor.reset_idle_timer();
+                if context.editor.config().auto_reload && context.editor.reload_changed_documents()
+                {
+                    // show the merge conflicts
+                    let callback: crate::compositor::Callback = Box::new(|compositor, cx| {
+                        let picker = commands::make_quickfix_picker(cx.editor);
+                        compositor.push(Box::new(overlaid(picker)));
+                    });
+                    return EventResult::Consumed(Some(callback));
+                }
                 EventResult::Consumed(None)
             }
             Event::FocusLost => {
-                if context.editor.config().auto_save.focus_lost {
+                let auto_save = context.editor.config().auto_save.clone();
+                if auto_save.focus_lost {
                     let options = commands::WriteAllOptions {
                         force: false,
                         write_scratch: false,
-                        auto_format: false,
+                        auto_format: auto_save.format,
                     };
                     if let Err(e) = commands::typed::write_all_impl(context, options) {
                         context.editor.set_error(format!("{}", e));
                     }
                 }
                 self.terminal_focused = false;
+                // Nothing needs to be computed on idle while the user is elsewhere
+                context.editor.clear_idle_timer();
                 EventResult::Consumed(None)
             }
         }
     }
+}
+
+impl Component for EditorView {
+    fn handle_event(
+        &mut self,
+        event: &Event,
+        context: &mut crate::compositor::Context,
+    ) -> EventResult {
+        if let Event::Resize(width, height) = *event {
+            self.area = Rect::new(0, 0, width, height);
+        }
+        let result = self.handle_editor_event(event, context);
+        self.resize_terminal(context.editor);
+        result
+    }
 
     fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         // clear with background color
         surface.set_style(area, cx.editor.theme.get("ui.background"));
         let config = cx.editor.config();
 
-        // check if bufferline should be rendered
-        use helix_view::editor::BufferLine;
-        let use_bufferline = match config.bufferline {
-            BufferLine::Always => true,
-            BufferLine::Multiple if cx.editor.documents.len() > 1 => true,
-            _ => false,
-        };
-
-        // -1 for commandline and -1 for bufferline
-        let mut editor_area = area.clip_bottom(1);
-        if use_bufferline {
-            editor_area = editor_area.clip_top(1);
+        // The panel is closed once the shell exited
+        if cx.editor.terminal.is_none() {
+            self.terminal_panel = None;
         }
+        self.area = area;
+        let Layout {
+            bufferline: use_bufferline,
+            layoutline: use_layoutline,
+            views: editor_area,
+            syntax_tree: syntax_tree_area,
+            terminal: terminal_area,
+        } = self.layout(cx.editor, area);
 
         // if the terminal size suddenly changed, we need to trigger a resize
         cx.editor.resize(editor_area);
@@ -1547,10 +1941,29 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         if use_bufferline {
             Self::render_bufferline(cx.editor, area.with_height(1), surface);
         }
+        if use_layoutline {
+            Self::render_layouts(cx.editor, area.with_height(1), surface, !use_bufferline);
+        }
+
+        if config.winbar {
+            self.winbar.update(cx.editor);
+        }
+        if let Some(syntax_tree) = &mut self.syntax_tree {
+            syntax_tree.update(cx.editor);
+        }
 
         for (view, is_focused) in cx.editor.tree.views() {
             let doc = cx.editor.document(view.doc).unwrap();
             self.render_view(cx.editor, doc, view, area, surface, is_focused);
+            if config.winbar {
+                self.winbar.render(cx.editor, view, surface, is_focused);
+            }
+        }
+        if let (Some(syntax_tree), Some(area)) = (&self.syntax_tree, syntax_tree_area) {
+            syntax_tree.render(cx.editor, area, surface);
+        }
+        if let (Some(terminal_panel), Some(area)) = (&mut self.terminal_panel, terminal_area) {
+            terminal_panel.render(area, surface, cx);
         }
 
         if config.auto_info {
@@ -1565,6 +1978,23 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
 
         // render status msg
         if let Some((status_msg, severity)) = &cx.editor.status_msg {
+            let messages = &cx.editor.messages;
+            let mut status_msg = std::borrow::Cow::Borrowed(status_msg.as_ref());
+            let repeated = messages.last_count();
+            if repeated > 1
+                && messages
+                    .iter()
+                    .next_back()
+                    .is_some_and(|message| message.text == status_msg)
+            {
+                status_msg.to_mut().push_str(&format!(" (×{repeated})"));
+            }
+            if messages.suppressed() > 0 {
+                let suppressed = messages.suppressed();
+                status_msg
+                    .to_mut()
+                    .push_str(&format!(" (+{suppressed} more, see :messages)"));
+            }
             status_msg_width = status_msg.width();
             use helix_view::editor::Severity;
             let style = if *severity == Severity::Error {
@@ -1624,7 +2054,10 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         }
     }
 
-    fn cursor(&self, _area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+    fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        if let Some(terminal_panel) = self.terminal_panel.as_ref().filter(|panel| panel.focused) {
+            return terminal_panel.cursor(area, editor);
+        }
         match editor.cursor() {
             // all block cursors are drawn manually
             (pos, CursorKind::Block) => {
@@ -1638,6 +2071,10 @@ fn cursor(&self, _area: Rect, editor: &Editor) -> (Option<Position>, CursorKind)
             cursor => cursor,
         }
     }
+
+    fn last_key_trace(&self) -> Option<String> {
+        self.key_trace.clone()
+    }
 }
 
 fn canonicalize_key(key: &mut KeyEvent) {
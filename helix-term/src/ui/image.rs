@@ -0,0 +1,452 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use helix_view::{graphics::Rect, DocumentId};
+use tui::{
+    buffer::{Buffer as Surface, Crop, Image},
+    widgets::{Block, Widget},
+};
+
+use crate::{
+    compositor::{Callback, Component, Context, Event, EventResult},
+    key,
+};
+
+pub const ID: &str = "image-preview";
+
+/// The extensions of the files opened in the preview.
+const EXTENSIONS: [&str; 6] = ["png", "gif", "jpg", "jpeg", "bmp", "webp"];
+
+/// Cells are about twice as high as they are wide, the size of an image in cells is estimated
+/// with this ratio.
+const CELL_ASPECT: u64 = 2;
+
+/// The zoom levels, in percent of the size fitting the preview.
+const MIN_ZOOM: u16 = 25;
+const MAX_ZOOM: u16 = 800;
+const ZOOM_STEP: u16 = 25;
+
+/// How far the shown part of a zoomed in image moves, in percent of the hidden part.
+const PAN_STEP: u16 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Gif,
+    Jpeg,
+    Bmp,
+    WebP,
+}
+
+impl ImageFormat {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Gif => "GIF",
+            Self::Jpeg => "JPEG",
+            Self::Bmp => "BMP",
+            Self::WebP => "WebP",
+        }
+    }
+}
+
+/// The format and dimensions of an image, read from its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn be16(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn le16(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn le24(data: &[u8], at: usize) -> Option<u32> {
+    Some(le16(data, at)? | (*data.get(at + 2)? as u32) << 16)
+}
+
+fn be32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+impl ImageInfo {
+    /// Reads the format and dimensions of an image file, `None` if it isn't an image in one of
+    /// the known formats.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (format, width, height) = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            (ImageFormat::Png, be32(data, 16)?, be32(data, 20)?)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            (ImageFormat::Gif, le16(data, 6)?, le16(data, 8)?)
+        } else if data.starts_with(b"BM") {
+            // The height is negative for images stored top to bottom
+            let height = (le32(data, 22)? as i32).unsigned_abs();
+            (ImageFormat::Bmp, le32(data, 18)?, height)
+        } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+            let (width, height) = Self::webp_size(data)?;
+            (ImageFormat::WebP, width, height)
+        } else if data.starts_with(b"\xff\xd8") {
+            let (width, height) = Self::jpeg_size(data)?;
+            (ImageFormat::Jpeg, width, height)
+        } else {
+            return None;
+        };
+        Some(Self {
+            format,
+            width,
+            height,
+        })
+    }
+
+    /// The size of a WebP image is stored differently for each of its encodings.
+    fn webp_size(data: &[u8]) -> Option<(u32, u32)> {
+        match data.get(12..16)? {
+            b"VP8X" => Some((le24(data, 24)? + 1, le24(data, 27)? + 1)),
+            b"VP8L" => {
+                let bits = le32(data, 21)?;
+                Some(((bits & 0x3fff) + 1, (bits >> 14 & 0x3fff) + 1))
+            }
+            b"VP8 " => Some((le16(data, 26)? & 0x3fff, le16(data, 28)? & 0x3fff)),
+            _ => None,
+        }
+    }
+
+    /// The size of a JPEG image is stored in its start of frame segment, which follows the
+    /// metadata segments.
+    fn jpeg_size(data: &[u8]) -> Option<(u32, u32)> {
+        let mut i = 2;
+        loop {
+            if *data.get(i)? != 0xff {
+                return None;
+            }
+            match *data.get(i + 1)? {
+                // Fill bytes
+                0xff => i += 1,
+                // Markers without a segment
+                0x01 | 0xd0..=0xd8 => i += 2,
+                // Start of frame, except for the markers of the same range which aren't
+                0xc0..=0xcf if !matches!(data[i + 1], 0xc4 | 0xc8 | 0xcc) => {
+                    return Some((be16(data, i + 7)?, be16(data, i + 5)?));
+                }
+                _ => i += 2 + be16(data, i + 2)? as usize,
+            }
+        }
+    }
+}
+
+/// Whether `path` is opened in the preview, by its extension.
+pub fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+/// Returns where an image is drawn in `viewport` when it is shown at `zoom` percent of the size
+/// fitting the viewport, and the part of it which is shown if it doesn't fit. `pan` is the
+/// position of that part in percent of the hidden part of the image, horizontally and
+/// vertically.
+pub(crate) fn layout(
+    info: &ImageInfo,
+    viewport: Rect,
+    zoom: u16,
+    pan: (u16, u16),
+) -> (Rect, Option<Crop>) {
+    let (width, height) = (info.width.max(1) as u64, info.height.max(1) as u64);
+    let (viewport_width, viewport_height) = (viewport.width as u64, viewport.height as u64);
+
+    let fit_cols = viewport_width
+        .min(viewport_height * CELL_ASPECT * width / height)
+        .max(1);
+    let fit_rows = (fit_cols * height / (width * CELL_ASPECT)).max(1);
+    let cols = (fit_cols * zoom as u64 / 100).max(1);
+    let rows = (fit_rows * zoom as u64 / 100).max(1);
+    let shown_cols = cols.min(viewport_width);
+    let shown_rows = rows.min(viewport_height);
+
+    let crop = (cols > shown_cols || rows > shown_rows).then(|| {
+        let crop_width = width * shown_cols / cols;
+        let crop_height = height * shown_rows / rows;
+        Crop {
+            x: ((width - crop_width) * pan.0 as u64 / 100) as u32,
+            y: ((height - crop_height) * pan.1 as u64 / 100) as u32,
+            width: crop_width as u32,
+            height: crop_height as u32,
+        }
+    });
+    let area = Rect::new(
+        viewport.x + ((viewport_width - shown_cols) / 2) as u16,
+        viewport.y + ((viewport_height - shown_rows) / 2) as u16,
+        shown_cols as u16,
+        shown_rows as u16,
+    );
+    (area, crop)
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while unit < UNITS.len() - 1 && size >= 1024.0 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size}{}", UNITS[unit])
+    } else {
+        format!("{size:.1}{}", UNITS[unit])
+    }
+}
+
+/// The read-only preview shown instead of the contents of an image file when it is opened. The
+/// image is shown with the kitty graphics protocol or sixel graphics where the terminal supports
+/// them, otherwise the format, dimensions and size of the file are shown.
+///
+/// Only PNG images are shown, the other formats would have to be decoded first.
+pub struct ImagePreview {
+    path: PathBuf,
+    /// The document of the file, closed with the preview.
+    doc: DocumentId,
+    png: Option<Arc<[u8]>>,
+    info: Option<ImageInfo>,
+    size: u64,
+    /// The zoom in percent of the size fitting the preview.
+    zoom: u16,
+    pan: (u16, u16),
+}
+
+impl ImagePreview {
+    pub fn new(path: PathBuf, doc: DocumentId, data: Vec<u8>) -> Self {
+        let info = ImageInfo::parse(&data);
+        Self {
+            path,
+            doc,
+            size: data.len() as u64,
+            png: info
+                .filter(|info| info.format == ImageFormat::Png)
+                .map(|_| data.into()),
+            info,
+            zoom: 100,
+            pan: (50, 50),
+        }
+    }
+
+    fn pan(&mut self, horizontal: i32, vertical: i32) {
+        let step = |pan: u16, direction: i32| {
+            (pan as i32 + direction * PAN_STEP as i32).clamp(0, 100) as u16
+        };
+        self.pan = (step(self.pan.0, horizontal), step(self.pan.1, vertical));
+    }
+
+    fn status(&self) -> String {
+        let mut status = match &self.info {
+            Some(info) => format!(
+                "{} {}×{} · {}",
+                info.format.name(),
+                info.width,
+                info.height,
+                format_size(self.size)
+            ),
+            None => format_size(self.size),
+        };
+        if self.png.is_some() {
+            status.push_str(&format!(" · {}%", self.zoom));
+        }
+        status
+    }
+}
+
+impl Component for ImagePreview {
+    fn handle_event(&mut self, event: &Event, _cx: &mut Context) -> EventResult {
+        let Event::Key(key) = event else {
+            return EventResult::Ignored(None);
+        };
+        match *key {
+            key!(Esc) | key!('q') => {
+                let doc = self.doc;
+                let close: Callback = Box::new(move |compositor, cx| {
+                    compositor.remove(ID);
+                    if let Err(err) = cx.editor.close_document(doc, false) {
+                        log::debug!("image document not closed: {err:?}");
+                    }
+                });
+                return EventResult::Consumed(Some(close));
+            }
+            key!('+') => self.zoom = (self.zoom + ZOOM_STEP).min(MAX_ZOOM),
+            key!('-') => self.zoom = self.zoom.saturating_sub(ZOOM_STEP).max(MIN_ZOOM),
+            key!('=') => {
+                self.zoom = 100;
+                self.pan = (50, 50);
+            }
+            key!('h') | key!(Left) => self.pan(-1, 0),
+            key!('l') | key!(Right) => self.pan(1, 0),
+            key!('k') | key!(Up) => self.pan(0, -1),
+            key!('j') | key!(Down) => self.pan(0, 1),
+            _ => (),
+        }
+        // The preview is read-only, other keys are not passed on to the editor
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        let text_style = theme.get("ui.text");
+        let hint_style = theme.get("ui.text.inactive");
+        surface.clear_with(area, theme.get("ui.background"));
+
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let block = Block::bordered()
+            .title(format!(" {name} "))
+            .border_style(theme.get("ui.window"));
+        let inner = block.inner(area);
+        block.render(area, surface);
+        if inner.height < 2 {
+            return;
+        }
+
+        let status = self.status();
+        let bottom = inner.y + inner.height - 1;
+        surface.set_stringn(inner.x, bottom, &status, inner.width as usize, text_style);
+        let keys = if self.png.is_some() {
+            "+/- zoom  = fit  hjkl pan  q close"
+        } else {
+            "q close"
+        };
+        let keys_x = inner.x + inner.width.saturating_sub(keys.len() as u16);
+        if keys_x > inner.x + status.chars().count() as u16 {
+            surface.set_string(keys_x, bottom, keys, hint_style);
+        }
+
+        let viewport = inner.clip_bottom(1);
+        if let (Some(png), Some(info), true) = (&self.png, &self.info, cx.editor.terminal_images) {
+            let (area, crop) = layout(info, viewport, self.zoom, self.pan);
+            surface.images.push(Image {
+                png: png.clone(),
+                area,
+                crop,
+            });
+            return;
+        }
+
+        // The metadata in the status line is all there is to show
+        let reason = match &self.info {
+            None => "Not an image in a known format".to_string(),
+            Some(_) if !cx.editor.terminal_images => {
+                "The terminal can't show images, they are shown in terminals supporting the kitty \
+                 graphics protocol or sixel graphics"
+                    .to_string()
+            }
+            Some(info) => format!("{} images can't be shown, only PNG", info.format.name()),
+        };
+        let lines = [self.path.to_string_lossy().into_owned(), status, reason];
+        let top = viewport.y + viewport.height.saturating_sub(lines.len() as u16) / 2;
+        for (row, line) in lines.iter().enumerate().take(viewport.height as usize) {
+            let width = (line.chars().count() as u16).min(viewport.width);
+            surface.set_stringn(
+                viewport.x + (viewport.width - width) / 2,
+                top + row as u16,
+                line,
+                viewport.width as usize,
+                text_style,
+            );
+        }
+    }
+
+    fn id(&self) -> Option<&'static str> {
+        Some(ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn parse_image_headers() {
+        let info = |format, width, height| {
+            Some(ImageInfo {
+                format,
+                width,
+                height,
+            })
+        };
+        assert_eq!(
+            ImageInfo::parse(&png_header(800, 600)),
+            info(ImageFormat::Png, 800, 600)
+        );
+        assert_eq!(
+            ImageInfo::parse(b"GIF89a\x20\x03\x58\x02"),
+            info(ImageFormat::Gif, 800, 600)
+        );
+        // A JPEG with an APP0 segment before the start of frame
+        let jpeg = b"\xff\xd8\xff\xe0\x00\x04JF\xff\xc0\x00\x11\x08\x02\x58\x03\x20";
+        assert_eq!(ImageInfo::parse(jpeg), info(ImageFormat::Jpeg, 800, 600));
+        assert_eq!(ImageInfo::parse(b"\xff\xd8\xff\xe0\x00\x10"), None);
+        assert_eq!(ImageInfo::parse(b"fn main() {}"), None);
+
+        assert!(is_image_path(Path::new("assets/logo.PNG")));
+        assert!(!is_image_path(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn layout_fits_and_zooms() {
+        let info = ImageInfo {
+            format: ImageFormat::Png,
+            width: 800,
+            height: 400,
+        };
+        let viewport = Rect::new(0, 0, 100, 50);
+        // 800x400 pixels are 2:1, with cells twice as high as wide that's 4:1 cells
+        assert_eq!(
+            layout(&info, viewport, 100, (50, 50)),
+            (Rect::new(0, 12, 100, 25), None)
+        );
+        assert_eq!(
+            layout(&info, viewport, 50, (50, 50)),
+            (Rect::new(25, 19, 50, 12), None)
+        );
+        // Zoomed in, the middle half of the image is shown
+        assert_eq!(
+            layout(&info, viewport, 200, (50, 50)),
+            (
+                Rect::new(0, 0, 100, 50),
+                Some(Crop {
+                    x: 200,
+                    y: 0,
+                    width: 400,
+                    height: 400
+                })
+            )
+        );
+        assert_eq!(
+            layout(&info, viewport, 200, (100, 0)).1.map(|crop| crop.x),
+            Some(400)
+        );
+    }
+}
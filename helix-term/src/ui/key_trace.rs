@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+
+use helix_view::{graphics::Rect, input::KeyEvent};
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Widget},
+};
+
+use crate::compositor::{Component, Context};
+
+pub const ID: &str = "key-trace";
+
+/// The number of keys kept in the trace.
+const MAX_ENTRIES: usize = 12;
+
+/// How a layer of the compositor handled a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerTrace {
+    /// The name of the component's type, without its module path and generics.
+    pub component: String,
+    pub consumed: bool,
+    /// The component's own account of what it did with the key, see
+    /// [`Component::last_key_trace`].
+    pub details: Option<String>,
+}
+
+/// The panel opened with `:key-trace`, showing how each key was routed through the layers of
+/// the compositor: the layers that ignored it, the one that consumed it and, for the editor,
+/// which keymap node or command it resolved to.
+///
+/// The panel itself ignores all events, the compositor records the keys into it.
+#[derive(Debug, Default)]
+pub struct KeyTrace {
+    entries: VecDeque<(KeyEvent, Vec<LayerTrace>)>,
+}
+
+impl KeyTrace {
+    pub fn record(&mut self, key: KeyEvent, route: Vec<LayerTrace>) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, route));
+    }
+
+    fn format_entry(key: &KeyEvent, route: &[LayerTrace]) -> String {
+        let mut line = format!("{:<8}", key.key_sequence_format());
+        for (i, layer) in route.iter().enumerate() {
+            if i > 0 {
+                line.push_str(" → ");
+            }
+            line.push_str(&layer.component);
+            if let Some(details) = &layer.details {
+                line.push_str(": ");
+                line.push_str(details);
+            } else if !layer.consumed {
+                line.push_str(" (ignored)");
+            }
+        }
+        if !route.last().is_some_and(|layer| layer.consumed) {
+            line.push_str(" → unhandled");
+        }
+        line
+    }
+}
+
+/// Shortens a type name like `helix_term::ui::picker::Picker<T, D>` to `Picker`.
+pub fn short_type_name(type_name: &str) -> &str {
+    let name = type_name.split('<').next().unwrap_or(type_name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+impl Component for KeyTrace {
+    fn render(&mut self, viewport: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        let text_style = theme.get("ui.text.info");
+        let popup_style = theme.get("ui.popup.info");
+
+        let lines: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(key, route)| Self::format_entry(key, route))
+            .collect();
+        // Like the infobox, in the bottom right corner above the statusline
+        let width = (viewport.width * 2 / 3).max(40);
+        let height = MAX_ENTRIES as u16 + 2; // +2 for border
+        let area = viewport.intersection(Rect::new(
+            viewport.width.saturating_sub(width),
+            viewport.height.saturating_sub(height + 2), // +2 for statusline
+            width,
+            height,
+        ));
+        surface.clear_with(area, popup_style);
+
+        let block = Block::bordered()
+            .title(" Key trace ")
+            .border_style(popup_style);
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        if lines.is_empty() {
+            surface.set_stringn(
+                inner.x,
+                inner.y,
+                "Press a key to see how it is handled",
+                inner.width as usize,
+                text_style,
+            );
+        }
+        for (row, line) in lines.iter().enumerate().take(inner.height as usize) {
+            surface.set_stringn(
+                inner.x,
+                inner.y + row as u16,
+                line,
+                inner.width as usize,
+                text_style,
+            );
+        }
+    }
+
+    fn id(&self) -> Option<&'static str> {
+        Some(ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn format_entries() {
+        assert_eq!(
+            short_type_name("helix_term::ui::picker::Picker<helix_term::Foo, ()>"),
+            "Picker"
+        );
+
+        let key = KeyEvent::from_str("C-n").unwrap();
+        let route = vec![
+            LayerTrace {
+                component: "Popup".to_string(),
+                consumed: false,
+                details: None,
+            },
+            LayerTrace {
+                component: "EditorView".to_string(),
+                consumed: true,
+                details: Some("insert keymap: `completion`".to_string()),
+            },
+        ];
+        assert_eq!(
+            KeyTrace::format_entry(&key, &route),
+            "<C-n>   Popup (ignored) → EditorView: insert keymap: `completion`"
+        );
+        assert_eq!(
+            KeyTrace::format_entry(&key, &route[..1]),
+            "<C-n>   Popup (ignored) → unhandled"
+        );
+    }
+}
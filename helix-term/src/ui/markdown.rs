@@ -14,7 +14,7 @@
     RopeSlice, Syntax,
 };
 use helix_view::{
-    graphics::{Margin, Rect, Style},
+    graphics::{Hyperlink, Margin, Rect, Style},
     theme::Modifier,
     Theme,
 };
@@ -139,6 +139,8 @@ pub fn highlighted_code_block<'a>(
 
 pub struct Markdown {
     contents: String,
+    /// The language of code blocks which don't name one.
+    default_language: Option<String>,
 
     config_loader: Arc<ArcSwap<syntax::Loader>>,
 }
@@ -165,10 +167,18 @@ impl Markdown {
     pub fn new(contents: String, config_loader: Arc<ArcSwap<syntax::Loader>>) -> Self {
         Self {
             contents,
+            default_language: None,
             config_loader,
         }
     }
 
+    /// Highlights code blocks without a language as `language`, for documentation which is
+    /// known to be about code in that language.
+    pub fn with_default_language(mut self, language: &str) -> Self {
+        self.default_language = Some(language.to_string());
+        self
+    }
+
     pub fn parse(&self, theme: Option<&Theme>) -> tui::text::Text<'_> {
         fn push_line<'a>(spans: &mut Vec<Span<'a>>, lines: &mut Vec<Spans<'a>>) {
             let spans = std::mem::take(spans);
@@ -177,6 +187,20 @@ fn push_line<'a>(spans: &mut Vec<Span<'a>>, lines: &mut Vec<Spans<'a>>) {
             }
         }
 
+        // Text inside a link to a URL links to it, relative links have no target to open
+        fn link_style(tags: &[Tag], style: Style) -> Style {
+            let link = tags.iter().rev().find_map(|tag| match tag {
+                Tag::Link { dest_url, .. } if url::Url::parse(dest_url).is_ok() => {
+                    Some(Hyperlink::new(dest_url))
+                }
+                _ => None,
+            });
+            match link {
+                Some(link) => style.hyperlink(link),
+                None => style,
+            }
+        }
+
         let mut options = Options::empty();
         options.insert(Options::ENABLE_STRIKETHROUGH);
         let parser = Parser::new_ext(&self.contents, options);
@@ -295,9 +319,9 @@ fn push_line<'a>(spans: &mut Vec<Span<'a>>, lines: &mut Vec<Spans<'a>>) {
                 }
                 Event::Text(text) => {
                     if let Some(Tag::CodeBlock(kind)) = tags.last() {
-                        let language = match kind {
-                            CodeBlockKind::Fenced(language) => language,
-                            CodeBlockKind::Indented => "",
+                        let language: &str = match kind {
+                            CodeBlockKind::Fenced(language) if !language.is_empty() => language,
+                            _ => self.default_language.as_deref().unwrap_or_default(),
                         };
                         let tui_text = highlighted_code_block(
                             &text,
@@ -324,11 +348,11 @@ fn push_line<'a>(spans: &mut Vec<Span<'a>>, lines: &mut Vec<Spans<'a>>) {
                             }
                             _ => text_style,
                         };
-                        spans.push(Span::styled(text, style));
+                        spans.push(Span::styled(text, link_style(&tags, style)));
                     }
                 }
                 Event::Code(text) | Event::Html(text) => {
-                    spans.push(Span::styled(text, code_style));
+                    spans.push(Span::styled(text, link_style(&tags, code_style)));
                 }
                 Event::SoftBreak | Event::HardBreak => {
                     push_line(&mut spans, &mut lines);
@@ -1,7 +1,9 @@
 mod completion;
 mod document;
 pub(crate) mod editor;
+pub mod image;
 mod info;
+pub mod key_trace;
 pub mod lsp;
 mod markdown;
 pub mod menu;
@@ -12,16 +14,20 @@
 mod select;
 mod spinner;
 mod statusline;
+mod syntax_tree;
+mod terminal;
 mod text;
 mod text_decorations;
+mod winbar;
 
 use crate::compositor::Compositor;
-use crate::filter_picker_entry;
 use crate::job::{self, Callback};
+use crate::{alt, filter_picker_entry};
 pub use completion::Completion;
 pub use editor::EditorView;
 use helix_stdx::rope;
 use helix_view::theme::Style;
+pub use image::ImagePreview;
 pub use markdown::Markdown;
 pub use menu::Menu;
 pub use picker::{Column as PickerColumn, FileLocation, Picker};
@@ -29,8 +35,11 @@
 pub use prompt::{Prompt, PromptEvent};
 pub use select::Select;
 pub use spinner::{ProgressSpinners, Spinner};
+pub use syntax_tree::SyntaxTreePanel;
+pub use terminal::TerminalPanel;
 pub use text::Text;
 
+use helix_view::editor::{SearchCase, SearchConfig, SearchFlags};
 use helix_view::Editor;
 use tui::text::{Span, Spans};
 
@@ -97,6 +106,99 @@ pub fn raw_regex_prompt(
     completion_fn: impl FnMut(&Editor, &str) -> Vec<prompt::Completion> + 'static,
     fun: impl Fn(&mut crate::compositor::Context, rope::Regex, &str, PromptEvent) + 'static,
 ) {
+    let prompt = build_regex_prompt(cx, prompt, history_register, completion_fn, false, fun);
+    cx.push_layer(Box::new(prompt));
+}
+
+/// Like [raw_regex_prompt] but the regex honors [Editor::search_flags], which are toggled
+/// with `Alt-c`, `Alt-w` and `Alt-r` while the prompt is open. The active flags are shown
+/// in the prompt.
+pub fn search_prompt(
+    cx: &mut crate::commands::Context,
+    history_register: Option<char>,
+    completion_fn: impl FnMut(&Editor, &str) -> Vec<prompt::Completion> + 'static,
+    fun: impl Fn(&mut crate::compositor::Context, rope::Regex, &str, PromptEvent) + 'static,
+) {
+    let label = search_prompt_label(cx.editor.search_flags);
+    let prompt = build_regex_prompt(cx, label, history_register, completion_fn, true, fun)
+        .with_key_handler(|editor, event| {
+            let flags = &mut editor.search_flags;
+            match event {
+                alt!('c') => flags.case = flags.case.next(),
+                alt!('w') => flags.whole_word = !flags.whole_word,
+                alt!('r') => flags.literal = !flags.literal,
+                _ => return None,
+            }
+            Some(search_prompt_label(*flags))
+        });
+    cx.push_layer(Box::new(prompt));
+}
+
+fn search_prompt_label(flags: SearchFlags) -> std::borrow::Cow<'static, str> {
+    let active: Vec<_> = [
+        (flags.case == SearchCase::Sensitive, "case"),
+        (flags.case == SearchCase::Insensitive, "ignore case"),
+        (flags.whole_word, "word"),
+        (flags.literal, "literal"),
+    ]
+    .into_iter()
+    .filter_map(|(active, name)| active.then_some(name))
+    .collect();
+    if active.is_empty() {
+        "search:".into()
+    } else {
+        format!("search ({}):", active.join(", ")).into()
+    }
+}
+
+/// Builds the regex used to search for `query`, honoring the `search.smart-case` option and
+/// the search prompt toggles.
+pub fn search_regex(
+    config: &SearchConfig,
+    flags: SearchFlags,
+    query: &str,
+) -> anyhow::Result<rope::Regex> {
+    let case_insensitive = match flags.case {
+        SearchCase::Smart => config.smart_case && !query.chars().any(char::is_uppercase),
+        SearchCase::Sensitive => false,
+        SearchCase::Insensitive => true,
+    };
+    let mut pattern = if flags.literal {
+        helix_core::regex::escape(query)
+    } else {
+        query.to_string()
+    };
+    if flags.whole_word {
+        // Word boundaries are only asserted at the ends of the query which are word characters:
+        // `\b` before a leading `-` would need a word character in front of it instead. An
+        // escaped last character of a regex, like `\d`, is a class rather than a word character.
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let mut rev = query.chars().rev();
+        let last = rev.next();
+        let escaped = !flags.literal && rev.take_while(|&c| c == '\\').count() % 2 == 1;
+        let boundary = |word: bool| if word { r"\b" } else { "" };
+        let start = boundary(query.starts_with(is_word));
+        let end = boundary(last.is_some_and(is_word) && !escaped);
+        pattern = format!("{start}(?:{pattern}){end}");
+    }
+    let regex = rope::RegexBuilder::new()
+        .syntax(
+            rope::Config::new()
+                .case_insensitive(case_insensitive)
+                .multi_line(true),
+        )
+        .build(&pattern)?;
+    Ok(regex)
+}
+
+fn build_regex_prompt(
+    cx: &mut crate::commands::Context,
+    prompt: std::borrow::Cow<'static, str>,
+    history_register: Option<char>,
+    completion_fn: impl FnMut(&Editor, &str) -> Vec<prompt::Completion> + 'static,
+    search_flags: bool,
+    fun: impl Fn(&mut crate::compositor::Context, rope::Regex, &str, PromptEvent) + 'static,
+) -> Prompt {
     let (view, doc) = current!(cx.editor);
     let doc_id = view.doc;
     let snapshot = doc.selection(view.id).clone();
@@ -120,20 +222,13 @@ pub fn raw_regex_prompt(
                         return;
                     }
 
-                    let case_insensitive = if config.search.smart_case {
-                        !input.chars().any(char::is_uppercase)
+                    let flags = if search_flags {
+                        cx.editor.search_flags
                     } else {
-                        false
+                        SearchFlags::default()
                     };
 
-                    match rope::RegexBuilder::new()
-                        .syntax(
-                            rope::Config::new()
-                                .case_insensitive(case_insensitive)
-                                .multi_line(true),
-                        )
-                        .build(input)
-                    {
+                    match search_regex(&config.search, flags, input) {
                         Ok(regex) => {
                             let (view, doc) = current!(cx.editor);
 
@@ -166,7 +261,8 @@ pub fn raw_regex_prompt(
                                                     size.height as usize - 2, // 2 = statusline + commandline
                                                     0,
                                                 )))
-                                                .auto_close(true);
+                                                .auto_close(true)
+                                                .dim_background(false);
                                             compositor.replace_or_push("invalid-regex", popup);
                                         },
                                     ));
@@ -184,8 +280,7 @@ pub fn raw_regex_prompt(
     .with_language("regex", std::sync::Arc::clone(&cx.editor.syn_loader));
     // Calculate initial completion
     prompt.recalculate_completion(cx.editor);
-    // prompt
-    cx.push_layer(Box::new(prompt));
+    prompt
 }
 
 /// We want to exclude files that the editor can't handle yet
@@ -204,6 +299,17 @@ fn get_excluded_types() -> ignore::types::Types {
         .expect("failed to build excluded_types")
 }
 
+/// A popup listing errors found in the configuration files, which were replaced by the defaults.
+pub fn config_errors_popup(errors: &[String], editor: &Editor) -> Popup<Markdown> {
+    let mut contents =
+        String::from("Errors were found in the configuration, the defaults are used instead.\n");
+    for error in errors {
+        contents.push_str(&format!("\n```\n{error}\n```\n"));
+    }
+    let contents = Markdown::new(contents, editor.syn_loader.clone());
+    Popup::new("config-errors", contents).auto_close(true)
+}
+
 #[derive(Debug)]
 pub struct FilePickerData {
     root: PathBuf,
@@ -423,10 +529,15 @@ pub mod completers {
     use helix_core::syntax::config::LanguageServerFeature;
     use helix_view::document::SCRATCH_BUFFER_NAME;
     use helix_view::theme;
-    use helix_view::{editor::Config, Editor};
+    use helix_view::{
+        editor::{Config, OPTION_VARIANTS},
+        Editor,
+    };
     use once_cell::sync::Lazy;
     use std::borrow::Cow;
     use std::collections::BTreeSet;
+    use std::ops::Deref;
+    use std::path::{Path, PathBuf};
     use tui::text::Span;
 
     pub type Completer = fn(&Editor, &str) -> Vec<Completion>;
@@ -449,7 +560,7 @@ pub fn buffer(editor: &Editor, input: &str) -> Vec<Completion> {
     }
 
     pub fn theme(_editor: &Editor, input: &str) -> Vec<Completion> {
-        let mut names = theme::Loader::read_names(&helix_loader::config_dir().join("themes"));
+        let mut names = theme::Loader::read_names(&helix_loader::user_config_dir().join("themes"));
         for rt_dir in helix_loader::runtime_dirs() {
             names.extend(theme::Loader::read_names(&rt_dir.join("themes")));
         }
@@ -519,6 +630,44 @@ pub fn setting(_editor: &Editor, input: &str) -> Vec<Completion> {
             .collect()
     }
 
+    /// Completes values for the config option `key`, starting with its current value.
+    pub fn setting_value(editor: &Editor, key: &str, input: &str) -> Vec<Completion> {
+        fuzzy_match(input, setting_values(&editor.config(), key), false)
+            .into_iter()
+            .map(|(value, _)| ((0..), Span::raw(value)))
+            .collect()
+    }
+
+    /// The values of the config option `key`: its current value, then the other value of a
+    /// boolean or the other names of an option from [`OPTION_VARIANTS`].
+    pub(crate) fn setting_values(config: &Config, key: &str) -> Vec<String> {
+        let config = serde_json::json!(config);
+        let pointer = format!("/{}", key.to_lowercase().replace('.', "/"));
+        let Some(value) = config.pointer(&pointer) else {
+            return Vec::new();
+        };
+
+        let current = match value {
+            serde_json::Value::String(current) => current.clone(),
+            current => current.to_string(),
+        };
+        let mut values = vec![current.clone()];
+        if let Some(current) = value.as_bool() {
+            values.push((!current).to_string());
+        } else if let Some((_, variants)) = OPTION_VARIANTS
+            .iter()
+            .find(|(option, _)| option.eq_ignore_ascii_case(key))
+        {
+            values.extend(
+                variants
+                    .iter()
+                    .filter(|variant| **variant != current)
+                    .map(|variant| variant.to_string()),
+            );
+        }
+        values
+    }
+
     pub fn filename(editor: &Editor, input: &str) -> Vec<Completion> {
         filename_with_git_ignore(editor, input, true)
     }
@@ -552,6 +701,13 @@ pub fn language(editor: &Editor, input: &str) -> Vec<Completion> {
             .collect()
     }
 
+    pub fn picker_source(_editor: &Editor, input: &str) -> Vec<Completion> {
+        fuzzy_match(input, super::picker::source::names(), false)
+            .into_iter()
+            .map(|(name, _)| ((0..), name.into()))
+            .collect()
+    }
+
     pub fn lsp_workspace_command(editor: &Editor, input: &str) -> Vec<Completion> {
         let commands = doc!(editor)
             .language_servers_with_feature(LanguageServerFeature::WorkspaceCommand)
@@ -597,6 +753,43 @@ enum FileMatch {
         Accept,
     }
 
+    /// Resolves the components of `dir` which aren't existing directories to the subdirectory
+    /// matching them best, so that `s/u/` completes the files in `src/ui/`.
+    fn fuzzy_resolve_dir(dir: &Path) -> Option<PathBuf> {
+        use std::path::Component;
+
+        let mut resolved = PathBuf::new();
+        for component in dir.components() {
+            let Component::Normal(name) = component else {
+                resolved.push(component);
+                continue;
+            };
+            if resolved.join(name).is_dir() {
+                resolved.push(name);
+                continue;
+            }
+            let parent = if resolved.as_os_str().is_empty() {
+                helix_stdx::env::current_working_dir()
+            } else {
+                resolved.clone()
+            };
+            let dirs = std::fs::read_dir(parent)
+                .ok()?
+                .filter_map(|entry| {
+                    let entry = entry.ok()?;
+                    if entry.file_type().ok()?.is_dir() {
+                        entry.file_name().into_string().ok()
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            let (best, _) = fuzzy_match(name.to_str()?, dirs, true).into_iter().next()?;
+            resolved.push(best);
+        }
+        Some(resolved)
+    }
+
     // TODO: we could return an iter/lazy thing so it can fetch as many as it needs.
     fn filename_impl<F>(
         editor: &Editor,
@@ -610,10 +803,12 @@ fn filename_impl<F>(
         // Rust's filename handling is really annoying.
 
         use ignore::WalkBuilder;
-        use std::path::Path;
+        use std::cmp::Reverse;
+        use std::path::MAIN_SEPARATOR;
 
         let is_tilde = input == "~";
-        let path = helix_stdx::path::expand_tilde(Path::new(input));
+        // `~` and environment variables like `$HOME`
+        let path = helix_stdx::path::expand(Path::new(input));
 
         let (dir, file_name) = if input.ends_with(std::path::MAIN_SEPARATOR) {
             (path, None)
@@ -641,10 +836,43 @@ fn filename_impl<F>(
             (path, file_name)
         };
 
+        // When the input doesn't name an existing directory, its components are matched fuzzily
+        // against the directories on the way and the whole input is replaced by the completion.
+        let mut prefix = None;
+        let dir = if dir.is_dir() {
+            dir
+        } else {
+            match fuzzy_resolve_dir(&dir) {
+                Some(resolved) => {
+                    let mut resolved = resolved.to_string_lossy().into_owned();
+                    if !resolved.is_empty() && !resolved.ends_with(MAIN_SEPARATOR) {
+                        resolved.push(MAIN_SEPARATOR);
+                    }
+                    prefix = Some(resolved.clone());
+                    Cow::Owned(PathBuf::from(resolved))
+                }
+                None => return Vec::new(),
+            }
+        };
+        // The file name expanded from a variable isn't a suffix of the input either
+        if prefix.is_none() && !is_tilde {
+            if let Some(file_name) = &file_name {
+                if !input.ends_with(file_name.as_str()) {
+                    prefix = Some(dir.join("").to_string_lossy().into_owned());
+                }
+            }
+        }
+
+        // Hidden files are completed when the file picker shows them or the name starts with `.`
+        let hidden = editor.config().file_picker.hidden
+            && !file_name
+                .as_ref()
+                .is_some_and(|file_name| file_name.starts_with('.'));
+
         let end = input.len()..;
 
         let files = WalkBuilder::new(&dir)
-            .hidden(false)
+            .hidden(hidden)
             .follow_links(false) // We're scanning over depth 1
             .git_ignore(git_ignore)
             .max_depth(Some(1))
@@ -690,30 +918,44 @@ fn filename_impl<F>(
         let symlink_color = editor.theme.get("ui.text.symlink");
 
         let style_from_file = |file: Utf8PathBuf| {
+            let path = match &prefix {
+                Some(prefix) => format!("{prefix}{}", file.path),
+                None => file.path,
+            };
             if file.is_symlink {
-                Span::styled(file.path, symlink_color)
+                Span::styled(path, symlink_color)
             } else if file.is_dir {
-                Span::styled(file.path, directory_color)
+                Span::styled(path, directory_color)
             } else {
-                Span::raw(file.path)
+                Span::raw(path)
             }
         };
 
-        // if empty, return a list of dirs and files in current dir
+        // Directories are listed before files
         if let Some(file_name) = file_name {
-            let range = (input.len().saturating_sub(file_name.len()))..;
-            fuzzy_match(&file_name, files, true)
+            let range = if prefix.is_some() {
+                0..
+            } else {
+                (input.len().saturating_sub(file_name.len()))..
+            };
+            let mut files = fuzzy_match(&file_name, files, true);
+            files.sort_by_key(|(file, score)| (Reverse(*score), !file.is_dir));
+            files
                 .into_iter()
                 .map(|(name, _)| (range.clone(), style_from_file(name)))
                 .collect()
 
             // TODO: complete to longest common match
         } else {
-            let mut files: Vec<_> = files
-                .map(|file| (end.clone(), style_from_file(file)))
-                .collect();
-            files.sort_unstable_by(|(_, path1), (_, path2)| path1.content.cmp(&path2.content));
+            let range = if prefix.is_some() { 0.. } else { end };
+            let mut files: Vec<_> = files.collect();
+            files.sort_unstable_by(|file1, file2| {
+                (!file1.is_dir, &file1.path).cmp(&(!file2.is_dir, &file2.path))
+            });
             files
+                .into_iter()
+                .map(|file| (range.clone(), style_from_file(file)))
+                .collect()
         }
     }
 
@@ -731,6 +973,16 @@ pub fn register(editor: &Editor, input: &str) -> Vec<Completion> {
             .collect()
     }
 
+    pub fn named_selection(editor: &Editor, input: &str) -> Vec<Completion> {
+        let doc = doc!(editor);
+        let names = doc.named_selections.keys().cloned();
+
+        fuzzy_match(input, names, false)
+            .into_iter()
+            .map(|(name, _)| ((0..), name.into()))
+            .collect()
+    }
+
     pub fn program(_editor: &Editor, input: &str) -> Vec<Completion> {
         static PROGRAMS_IN_PATH: Lazy<BTreeSet<String>> = Lazy::new(|| {
             // Go through the entire PATH and read all files into a set.
@@ -796,6 +1048,8 @@ pub fn shell(editor: &Editor, input: &str) -> Vec<Completion> {
 mod tests {
     use std::fs::{create_dir, File};
 
+    use helix_view::editor::{Config, OPTION_VARIANTS};
+
     use super::*;
 
     #[test]
@@ -814,4 +1068,87 @@ fn test_get_child_if_single_dir() {
 
         assert_eq!(get_child_if_single_dir(root.path()), None);
     }
+
+    #[test]
+    fn setting_values() {
+        let config = Config::default();
+        let values = |key: &str| completers::setting_values(&config, key);
+        assert_eq!(values("line-number"), ["absolute", "relative", "hybrid"]);
+        assert_eq!(
+            values("cursor-shape.insert"),
+            ["block", "bar", "underline", "hidden"]
+        );
+        assert_eq!(values("cursorline"), ["false", "true"]);
+        assert_eq!(values("scrolloff"), ["5"]);
+        assert!(values("no-such-option").is_empty());
+    }
+
+    #[test]
+    fn option_variants_are_valid() {
+        for (key, variants) in OPTION_VARIANTS {
+            for variant in *variants {
+                let mut config = serde_json::json!(Config::default());
+                let pointer = format!("/{}", key.replace('.', "/"));
+                *config.pointer_mut(&pointer).unwrap() = variant.to_string().into();
+                assert!(
+                    serde_json::from_value::<Config>(config).is_ok(),
+                    "{key} = {variant:?}"
+                );
+            }
+        }
+    }
+
+    fn matches(flags: SearchFlags, query: &str, text: &str) -> bool {
+        use helix_stdx::rope::RopeSliceExt;
+
+        let regex = search_regex(&SearchConfig::default(), flags, query).unwrap();
+        regex.is_match(helix_core::Rope::from(text).slice(..).regex_input())
+    }
+
+    #[test]
+    fn search_regex_case() {
+        let flags = |case| SearchFlags {
+            case,
+            ..SearchFlags::default()
+        };
+        assert!(matches(flags(SearchCase::Smart), "foo", "FOO"));
+        assert!(!matches(flags(SearchCase::Smart), "Foo", "foo"));
+        assert!(!matches(flags(SearchCase::Sensitive), "foo", "FOO"));
+        assert!(matches(flags(SearchCase::Insensitive), "Foo", "foo"));
+        assert_eq!(SearchCase::Smart.next(), SearchCase::Sensitive);
+        assert_eq!(SearchCase::Sensitive.next(), SearchCase::Insensitive);
+        assert_eq!(SearchCase::Insensitive.next(), SearchCase::Smart);
+    }
+
+    #[test]
+    fn search_regex_whole_word() {
+        let word = SearchFlags {
+            whole_word: true,
+            ..SearchFlags::default()
+        };
+        let literal_word = SearchFlags {
+            literal: true,
+            ..word
+        };
+        assert!(matches(word, "foo", "a foo b"));
+        assert!(!matches(word, "foo", "foobar"));
+        assert!(matches(word, "fo+", "fooo"));
+        assert!(!matches(word, "fo+", "fooox"));
+        assert!(matches(literal_word, "-x", " -x "));
+        assert!(!matches(literal_word, "-x", "-xy"));
+        // `\d` ends in a class, so no boundary is asserted after it.
+        assert!(matches(word, r"a\d", "a1b"));
+        assert!(!matches(word, r"a\d", "ba1"));
+    }
+
+    #[test]
+    fn search_regex_literal() {
+        let literal = SearchFlags {
+            literal: true,
+            ..SearchFlags::default()
+        };
+        assert!(matches(literal, "a.b", "a.b"));
+        assert!(!matches(literal, "a.b", "axb"));
+        assert!(matches(SearchFlags::default(), "a.b", "axb"));
+    }
 }
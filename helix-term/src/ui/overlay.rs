@@ -73,4 +73,8 @@ fn cursor(&self, area: Rect, ctx: &Editor) -> (Option<Position>, CursorKind) {
     fn id(&self) -> Option<&'static str> {
         self.content.id()
     }
+
+    fn background_dim(&self, editor: &Editor) -> u8 {
+        editor.config().dim_background.overlay
+    }
 }
@@ -1,5 +1,6 @@
 mod handlers;
 mod query;
+pub mod source;
 
 use crate::{
     alt,
@@ -8,6 +9,7 @@
     ui::{
         self,
         document::{render_document, LinePos, TextRenderer},
+        image::{self, ImageFormat, ImageInfo},
         picker::query::PickerQuery,
         text_decorations::DecorationManager,
         EditorView,
@@ -20,7 +22,7 @@
 use thiserror::Error;
 use tokio::sync::mpsc::Sender;
 use tui::{
-    buffer::Buffer as Surface,
+    buffer::{Buffer as Surface, Image},
     layout::Constraint,
     text::{Span, Spans},
     widgets::{Block, BorderType, Cell, Row, Table},
@@ -42,11 +44,12 @@
 use crate::ui::{Prompt, PromptEvent};
 use helix_core::{
     char_idx_at_visual_offset, fuzzy::MATCHER, movement::Direction,
-    text_annotations::TextAnnotations, unicode::segmentation::UnicodeSegmentation, Position,
+    text_annotations::TextAnnotations, unicode::segmentation::UnicodeSegmentation, Position, Rope,
 };
 use helix_view::{
     editor::Action,
-    graphics::{CursorKind, Margin, Modifier, Rect},
+    graphics::{CursorKind, Hyperlink, Margin, Modifier, Rect},
+    input::KeyEvent,
     theme::Style,
     view::ViewPosition,
     Document, DocumentId, Editor,
@@ -57,8 +60,10 @@
 pub const ID: &str = "picker";
 
 pub const MIN_AREA_WIDTH_FOR_PREVIEW: u16 = 72;
-/// Biggest file size to preview in bytes
+/// Biggest file size to preview in bytes, only the head of bigger files is previewed
 pub const MAX_FILE_SIZE_FOR_PREVIEW: u64 = 10 * 1024 * 1024;
+/// The number of bytes read from the head of a file bigger than [MAX_FILE_SIZE_FOR_PREVIEW]
+const PREVIEW_HEAD_SIZE: u64 = 256 * 1024;
 
 #[derive(PartialEq, Eq, Hash)]
 pub enum PathOrId<'a> {
@@ -86,8 +91,9 @@ fn from(v: DocumentId) -> Self {
 pub enum CachedPreview {
     Document(Box<Document>),
     Directory(Vec<(String, bool)>),
+    /// A PNG image, shown where the terminal supports images.
+    Image(Arc<[u8]>, ImageInfo),
     Binary,
-    LargeFile,
     NotFound,
 }
 
@@ -114,6 +120,13 @@ fn dir_content(&self) -> Option<&Vec<(String, bool)>> {
         }
     }
 
+    fn image(&self) -> Option<(&Arc<[u8]>, &ImageInfo)> {
+        match self {
+            Preview::Cached(CachedPreview::Image(png, info)) => Some((png, info)),
+            _ => None,
+        }
+    }
+
     /// Alternate text to show for the preview.
     fn placeholder(&self) -> &str {
         match *self {
@@ -121,8 +134,8 @@ fn placeholder(&self) -> &str {
             Self::Cached(preview) => match preview {
                 CachedPreview::Document(_) => "<Invalid file location>",
                 CachedPreview::Directory(_) => "<Invalid directory location>",
+                CachedPreview::Image(..) => "<Image file>",
                 CachedPreview::Binary => "<Binary file>",
-                CachedPreview::LargeFile => "<File too large to preview>",
                 CachedPreview::NotFound => "<File not found>",
             },
         }
@@ -269,6 +282,10 @@ pub struct Picker<T: 'static + Send + Sync, D: 'static> {
     /// An event handler for syntax highlighting the currently previewed file.
     preview_highlight_handler: Sender<Arc<Path>>,
     dynamic_query_handler: Option<Sender<DynamicQueryChange>>,
+    /// Whether the visible entries are labelled for jumping to them, see `Alt-j`.
+    jump_labels: bool,
+    /// The first character of a jump label once it was typed.
+    jump_label_prefix: Option<usize>,
 }
 
 impl<T: 'static + Send + Sync, D: 'static + Send + Sync> Picker<T, D> {
@@ -394,6 +411,8 @@ fn with(
             file_fn: None,
             preview_highlight_handler: PreviewHighlightHandler::<T, D>::default().spawn(),
             dynamic_query_handler: None,
+            jump_labels: false,
+            jump_label_prefix: None,
         }
     }
 
@@ -628,31 +647,58 @@ fn get_preview<'picker, 'editor>(
                                 .collect();
                             Ok(CachedPreview::Directory(file_names))
                         } else if metadata.is_file() {
-                            if metadata.len() > MAX_FILE_SIZE_FOR_PREVIEW {
-                                return Ok(CachedPreview::LargeFile);
+                            // Large files are previewed by reading only their head, the
+                            // document is opened for real once the item is accepted.
+                            let is_large = metadata.len() > MAX_FILE_SIZE_FOR_PREVIEW;
+                            if !is_large && image::is_image_path(&path) {
+                                let data = std::fs::read(&path)?;
+                                if let Some(info) = ImageInfo::parse(&data)
+                                    .filter(|info| info.format == ImageFormat::Png)
+                                {
+                                    return Ok(CachedPreview::Image(data.into(), info));
+                                }
                             }
-                            let content_type = std::fs::File::open(&path).and_then(|file| {
-                                // Read up to 1kb to detect the content type
-                                let n = file.take(1024).read_to_end(&mut self.read_buffer)?;
-                                let content_type =
-                                    content_inspector::inspect(&self.read_buffer[..n]);
-                                self.read_buffer.clear();
-                                Ok(content_type)
-                            })?;
+                            // Read at least 1kb to detect the content type
+                            let limit = if is_large { PREVIEW_HEAD_SIZE } else { 1024 };
+                            self.read_buffer.clear();
+                            let n = std::fs::File::open(&path)?
+                                .take(limit)
+                                .read_to_end(&mut self.read_buffer)?;
+                            let content_type =
+                                content_inspector::inspect(&self.read_buffer[..n.min(1024)]);
                             if content_type.is_binary() {
                                 return Ok(CachedPreview::Binary);
                             }
-                            let mut doc = Document::open(
-                                &path,
-                                None,
-                                false,
-                                editor.config.clone(),
-                                editor.syn_loader.clone(),
-                            )
-                            .or(Err(std::io::Error::new(
-                                std::io::ErrorKind::NotFound,
-                                "Cannot open document",
-                            )))?;
+                            let mut doc = if is_large {
+                                // Drop the trailing partial line
+                                let head = &self.read_buffer[..n];
+                                let end = head
+                                    .iter()
+                                    .rposition(|&byte| byte == b'\n')
+                                    .map_or(n, |idx| idx + 1);
+                                let text = Rope::from(String::from_utf8_lossy(&head[..end]));
+                                let mut doc = Document::from(
+                                    text,
+                                    None,
+                                    editor.config.clone(),
+                                    editor.syn_loader.clone(),
+                                );
+                                doc.set_path(Some(&path));
+                                doc
+                            } else {
+                                Document::open(
+                                    &path,
+                                    None,
+                                    false,
+                                    editor.config.clone(),
+                                    editor.syn_loader.clone(),
+                                )
+                                .or(Err(std::io::Error::new(
+                                    std::io::ErrorKind::NotFound,
+                                    "Cannot open document",
+                                )))?
+                            };
+                            self.read_buffer.clear();
                             let loader = editor.syn_loader.load();
                             if let Some(language_config) = doc.detect_language_config(&loader) {
                                 doc.language = Some(language_config);
@@ -681,6 +727,33 @@ fn get_preview<'picker, 'editor>(
         }
     }
 
+    /// Handles a key while jump labels are shown: typing a label selects the visible entry
+    /// with that label, any other key hides the labels.
+    fn handle_jump_label_key(&mut self, event: KeyEvent, editor: &Editor) {
+        let alphabet = &editor.config().jump_label_alphabet;
+        let idx = event
+            .char()
+            .filter(|_| event.modifiers.is_empty())
+            .and_then(|ch| alphabet.iter().position(|&it| it == ch));
+        match (self.jump_label_prefix, idx) {
+            (None, Some(outer)) => {
+                self.jump_label_prefix = Some(outer);
+                return;
+            }
+            (Some(outer), Some(inner)) => {
+                let rows = self.completion_height.max(1) as u32;
+                let offset = self.cursor - self.cursor % rows;
+                let row = (outer * alphabet.len() + inner) as u32;
+                if row < rows && offset + row < self.matcher.snapshot().matched_item_count() {
+                    self.cursor = offset + row;
+                }
+            }
+            _ => (),
+        }
+        self.jump_labels = false;
+        self.jump_label_prefix = None;
+    }
+
     fn render_picker(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         let status = self.matcher.tick(10);
         let snapshot = self.matcher.snapshot();
@@ -758,11 +831,21 @@ fn render_picker(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context)
             matcher.config.set_match_paths()
         }
 
+        let editor: &Editor = cx.editor;
         let options = snapshot.matched_items(offset..end).map(|item| {
             let mut widths = self.widths.iter_mut();
             let mut matcher_index = 0;
-
-            Row::new(self.columns.iter().map(|column| {
+            // The primary column of items in a file links to the file, for terminals which
+            // open links on ctrl-click
+            let link =
+                self.file_fn
+                    .as_ref()
+                    .and_then(|file_fn| match file_fn(editor, item.data)?.0 {
+                        PathOrId::Path(path) => Hyperlink::file(path),
+                        PathOrId::Id(id) => Hyperlink::file(editor.document(id)?.path()?),
+                    });
+
+            Row::new(self.columns.iter().enumerate().map(|(i, column)| {
                 if column.hidden {
                     return Cell::default();
                 }
@@ -831,6 +914,11 @@ fn render_picker(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context)
                     *max_width = width as u16;
                 }
 
+                if let Some(link) = link.filter(|_| i == self.primary_column) {
+                    for span in cell.content.lines.iter_mut().flat_map(|line| &mut line.0) {
+                        span.style = span.style.hyperlink(link);
+                    }
+                }
                 cell
             }))
         });
@@ -878,6 +966,26 @@ fn render_picker(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context)
             },
             self.truncate_start,
         );
+
+        // -- Jump labels, drawn over the highlight symbol
+        if self.jump_labels {
+            let alphabet = &cx.editor.config().jump_label_alphabet;
+            let style = cx.editor.theme.get("ui.virtual.jump-label");
+            let top = inner.y + self.header_height();
+            for row in 0..(end - offset) as usize {
+                let outer = row / alphabet.len();
+                if outer >= alphabet.len() {
+                    break;
+                }
+                if self.jump_label_prefix.is_some_and(|prefix| prefix != outer) {
+                    continue;
+                }
+                let label: String = [alphabet[outer], alphabet[row % alphabet.len()]]
+                    .iter()
+                    .collect();
+                surface.set_stringn(inner.x, top + row as u16, label, 2, style);
+            }
+        }
     }
 
     fn render_preview(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
@@ -907,6 +1015,17 @@ fn render_preview(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context
                     doc
                 }
                 _ => {
+                    if let (Some((png, info)), true) = (preview.image(), cx.editor.terminal_images)
+                    {
+                        let (area, crop) = image::layout(info, inner, 100, (50, 50));
+                        surface.images.push(Image {
+                            png: png.clone(),
+                            area,
+                            crop,
+                        });
+                        return;
+                    }
+
                     if let Some(dir_content) = preview.dir_content() {
                         for (i, (path, is_dir)) in
                             dir_content.iter().take(inner.height as usize).enumerate()
@@ -1019,6 +1138,8 @@ fn render_preview(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context
                 overlay_highlights,
                 &cx.editor.theme,
                 decorations,
+                None,
+                None,
             );
         }
     }
@@ -1084,6 +1205,11 @@ fn handle_event(&mut self, event: &Event, ctx: &mut Context) -> EventResult {
             EventResult::Consumed(Some(callback))
         };
 
+        if self.jump_labels {
+            self.handle_jump_label_key(key_event, ctx.editor);
+            return EventResult::Consumed(None);
+        }
+
         match key_event {
             shift!(Tab) | key!(Up) | ctrl!('p') => {
                 self.move_by(1, Direction::Backward);
@@ -1159,6 +1285,9 @@ fn handle_event(&mut self, event: &Event, ctx: &mut Context) -> EventResult {
             ctrl!('t') => {
                 self.toggle_preview();
             }
+            alt!('j') => {
+                self.jump_labels = !ctx.editor.config().jump_label_alphabet.is_empty();
+            }
             _ => {
                 self.prompt_handle_event(event, ctx);
             }
@@ -0,0 +1,135 @@
+//! Pickers registered at runtime, so that embedders of the editor and plugins can add their own
+//! pickers (issue trackers, docsets, ...) without patching helix-term. A registered source is
+//! opened with `:picker <name>`, or pushed by the embedder with the picker of [`picker`].
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use futures_util::stream::{BoxStream, StreamExt};
+use helix_view::{editor::Action, Editor};
+use once_cell::sync::Lazy;
+
+use super::{Column, FileLocation, PathOrId, Picker};
+use crate::compositor::Context;
+
+/// An item of a [`PickerSource`].
+#[derive(Debug, Clone, Default)]
+pub struct SourceItem {
+    /// The text shown for the item and matched against the query.
+    pub label: String,
+    /// Text shown next to the label, for example the state of an issue.
+    pub detail: String,
+    /// The file shown in the preview by default.
+    pub path: Option<PathBuf>,
+    /// The range of lines of `path` highlighted in the preview.
+    pub lines: Option<(usize, usize)>,
+    /// Data identifying the item for the source, for example the URL of an issue.
+    pub data: String,
+}
+
+type ItemsFn = Box<dyn Fn(&mut Editor) -> BoxStream<'static, SourceItem> + Send + Sync>;
+type PreviewFn =
+    Box<dyn for<'a> Fn(&'a Editor, &'a SourceItem) -> Option<FileLocation<'a>> + Send + Sync>;
+type AcceptFn = Box<dyn Fn(&mut Context, &SourceItem, Action) + Send + Sync>;
+
+pub struct PickerSource {
+    title: String,
+    items: ItemsFn,
+    preview: PreviewFn,
+    on_accept: AcceptFn,
+}
+
+impl PickerSource {
+    /// A source whose items are produced by `items`, which is called each time the picker is
+    /// opened. The picker shows the items as the stream yields them. Accepting an item calls
+    /// `on_accept`.
+    pub fn new(
+        title: impl Into<String>,
+        items: impl Fn(&mut Editor) -> BoxStream<'static, SourceItem> + Send + Sync + 'static,
+        on_accept: impl Fn(&mut Context, &SourceItem, Action) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            items: Box::new(items),
+            preview: Box::new(preview_path),
+            on_accept: Box::new(on_accept),
+        }
+    }
+
+    /// Replaces the preview, which shows the `path` of the item by default.
+    pub fn with_preview(
+        mut self,
+        preview: impl for<'a> Fn(&'a Editor, &'a SourceItem) -> Option<FileLocation<'a>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.preview = Box::new(preview);
+        self
+    }
+}
+
+fn preview_path<'a>(_editor: &'a Editor, item: &'a SourceItem) -> Option<FileLocation<'a>> {
+    let path = item.path.as_deref()?;
+    Some((PathOrId::Path(path), item.lines))
+}
+
+static SOURCES: Lazy<RwLock<BTreeMap<String, Arc<PickerSource>>>> = Lazy::new(Default::default);
+
+/// Registers `source` under `name`, replacing a source registered under the same name.
+pub fn register(name: impl Into<String>, source: PickerSource) {
+    SOURCES
+        .write()
+        .unwrap()
+        .insert(name.into(), Arc::new(source));
+}
+
+/// Removes the source registered under `name`.
+pub fn unregister(name: &str) {
+    SOURCES.write().unwrap().remove(name);
+}
+
+/// The names of the registered sources, sorted.
+pub fn names() -> Vec<String> {
+    SOURCES.read().unwrap().keys().cloned().collect()
+}
+
+/// Creates the picker of the source registered under `name`. Push it wrapped in
+/// [`overlaid`](crate::ui::overlay::overlaid).
+pub fn picker(editor: &mut Editor, name: &str) -> anyhow::Result<Picker<SourceItem, ()>> {
+    let source = SOURCES
+        .read()
+        .unwrap()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No picker source named '{name}'"))?;
+
+    let columns = [
+        Column::new(source.title.as_str(), |item: &SourceItem, _| {
+            item.label.as_str().into()
+        }),
+        Column::new("detail", |item: &SourceItem, _| item.detail.as_str().into()),
+    ];
+    let (matcher, injector) = Picker::stream(columns, ());
+
+    let mut items = (source.items)(editor);
+    let items_injector = injector.clone();
+    tokio::spawn(async move {
+        while let Some(item) = items.next().await {
+            if items_injector.push(item).is_err() {
+                // The picker was closed
+                break;
+            }
+        }
+    });
+
+    let preview = source.clone();
+    let picker = Picker::with_stream(matcher, 0, injector, move |cx, item, action| {
+        (source.on_accept)(cx, item, action)
+    })
+    .with_preview(move |editor, item| (preview.preview)(editor, item));
+    Ok(picker)
+}
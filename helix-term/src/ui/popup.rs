@@ -39,6 +39,7 @@ pub struct Popup<T: Component> {
     ignore_escape_key: bool,
     id: &'static str,
     has_scrollbar: bool,
+    dim_background: bool,
 }
 
 impl<T: Component> Popup<T> {
@@ -53,6 +54,7 @@ pub fn new(id: &'static str, contents: T) -> Self {
             ignore_escape_key: false,
             id,
             has_scrollbar: true,
+            dim_background: true,
         }
     }
 
@@ -95,6 +97,13 @@ pub fn ignore_escape_key(mut self, ignore: bool) -> Self {
         self
     }
 
+    /// Whether the popup dims the content behind it, if `editor.dim-background.popup` is set.
+    /// Popups which show up while typing, like signature help, shouldn't.
+    pub fn dim_background(mut self, dim: bool) -> Self {
+        self.dim_background = dim;
+        self
+    }
+
     pub fn scroll_half_page_down(&mut self) {
         self.scroll_half_pages += 1;
     }
@@ -387,4 +396,12 @@ fn render(&mut self, viewport: Rect, surface: &mut Surface, cx: &mut Context) {
     fn id(&self) -> Option<&'static str> {
         Some(self.id)
     }
+
+    fn background_dim(&self, editor: &Editor) -> u8 {
+        if self.dim_background {
+            editor.config().dim_background.popup
+        } else {
+            0
+        }
+    }
 }
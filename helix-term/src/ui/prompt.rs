@@ -1,12 +1,16 @@
 use crate::compositor::{Component, Compositor, Context, Event, EventResult};
 use crate::{alt, ctrl, key, shift, ui};
 use arc_swap::ArcSwap;
-use helix_core::syntax;
+use helix_core::{fuzzy::fuzzy_match, syntax};
 use helix_view::document::Mode;
 use helix_view::input::KeyEvent;
 use helix_view::keyboard::KeyCode;
 use std::sync::Arc;
-use std::{borrow::Cow, ops::RangeFrom};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    ops::{Range, RangeFrom},
+};
 use tui::buffer::Buffer as Surface;
 use tui::text::Span;
 use tui::widgets::{Block, Widget};
@@ -22,6 +26,7 @@
 };
 
 type PromptCharHandler = Box<dyn Fn(&mut Prompt, char, &Context)>;
+type PromptKeyHandler = Box<dyn Fn(&mut Editor, KeyEvent) -> Option<Cow<'static, str>>>;
 
 pub type Completion = (RangeFrom<usize>, Span<'static>);
 type CompletionFn = Box<dyn FnMut(&Editor, &str) -> Vec<Completion>>;
@@ -42,11 +47,19 @@ pub struct Prompt {
     selection: Option<usize>,
     history_register: Option<char>,
     history_pos: Option<usize>,
+    /// Whether the completions are the history entries matching the line, see `C-r C-r`.
+    history_search: bool,
     completion_fn: CompletionFn,
     callback_fn: CallbackFn,
     pub doc_fn: DocFn,
     next_char_handler: Option<PromptCharHandler>,
+    /// See [Prompt::with_key_handler].
+    key_handler: Option<PromptKeyHandler>,
     language: Option<(&'static str, Arc<ArcSwap<syntax::Loader>>)>,
+    /// Whether `Enter` inserts a newline instead of validating, see [Prompt::with_multiline].
+    multiline: bool,
+    /// Whether the input is a password or another secret, see [Prompt::with_secret].
+    secret: bool,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -72,6 +85,10 @@ pub enum Movement {
     ForwardWord(usize),
     StartOfLine,
     EndOfLine,
+    /// Moves to the visual row above in a multi-line prompt, keeping the column.
+    RowUp(usize),
+    /// Moves to the visual row below in a multi-line prompt, keeping the column.
+    RowDown(usize),
     None,
 }
 
@@ -79,6 +96,37 @@ fn is_word_sep(c: char) -> bool {
     c == std::path::MAIN_SEPARATOR || c.is_whitespace()
 }
 
+/// Splits `text` into the byte ranges of its visual rows: one per line, with lines wider
+/// than `width` soft wrapped at grapheme boundaries. The newlines are not part of any row.
+fn soft_wrap(text: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut line_start = 0;
+    for line in text.split('\n') {
+        let mut row_start = line_start;
+        let mut row_width = 0;
+        for (idx, grapheme) in line.grapheme_indices(true) {
+            let grapheme_width = grapheme.width();
+            if row_width > 0 && row_width + grapheme_width > width {
+                rows.push(row_start..line_start + idx);
+                row_start = line_start + idx;
+                row_width = 0;
+            }
+            row_width += grapheme_width;
+        }
+        rows.push(row_start..line_start + line.len());
+        line_start += line.len() + 1;
+    }
+    rows
+}
+
+/// Returns the index of the row containing the byte offset `cursor`. A cursor at the point
+/// where a line is soft wrapped is on the following row.
+fn row_at(rows: &[Range<usize>], cursor: usize) -> usize {
+    rows.partition_point(|row| row.start <= cursor)
+        .saturating_sub(1)
+}
+
 impl Prompt {
     pub fn new(
         prompt: Cow<'static, str>,
@@ -98,11 +146,15 @@ pub fn new(
             selection: None,
             history_register,
             history_pos: None,
+            history_search: false,
             completion_fn: Box::new(completion_fn),
             callback_fn: Box::new(callback_fn),
             doc_fn: Box::new(|_| None),
             next_char_handler: None,
+            key_handler: None,
             language: None,
+            multiline: false,
+            secret: false,
         }
     }
 
@@ -133,6 +185,37 @@ pub fn with_language(
         self
     }
 
+    /// Makes the prompt accept multiple lines of input, for example for commit messages.
+    /// `Enter` inserts a newline and `Alt-Enter` validates, `Up` and `Down` move between
+    /// the lines. Long lines are soft wrapped and the prompt grows upwards as lines are added.
+    pub fn with_multiline(mut self) -> Self {
+        self.multiline = true;
+        self
+    }
+
+    /// Makes the prompt read a password or another secret. The input isn't drawn, not even
+    /// masked, nor completed or added to the history, and it is overwritten with zeros once the
+    /// prompt closes. The callback still gets the input on each change.
+    pub fn with_secret(mut self) -> Self {
+        self.secret = true;
+        self.multiline = false;
+        self.history_register = None;
+        self.completion.clear();
+        // Reserve the space upfront: growing the line would leave copies of it behind
+        self.line = String::with_capacity(SECRET_CAPACITY);
+        self
+    }
+
+    /// Lets `handler` see key events before the prompt. When it returns a new prompt text,
+    /// the key is consumed, the text replaced and the input passed to the callback again.
+    pub fn with_key_handler(
+        mut self,
+        handler: impl Fn(&mut Editor, KeyEvent) -> Option<Cow<'static, str>> + 'static,
+    ) -> Self {
+        self.key_handler = Some(Box::new(handler));
+        self
+    }
+
     pub fn line(&self) -> &String {
         &self.line
     }
@@ -156,7 +239,29 @@ pub(crate) fn first_history_completion<'a>(
 
     pub fn recalculate_completion(&mut self, editor: &Editor) {
         self.exit_selection();
-        self.completion = (self.completion_fn)(editor, &self.line);
+        if self.secret {
+            return;
+        }
+        self.completion = match self.history_register {
+            Some(register) if self.history_search => self.history_completions(editor, register),
+            _ => (self.completion_fn)(editor, &self.line),
+        };
+    }
+
+    /// Fuzzy matches the line against the history, most recent entries first for equal scores.
+    fn history_completions(&self, editor: &Editor, register: char) -> Vec<Completion> {
+        let mut seen = HashSet::new();
+        let entries: Vec<_> = editor
+            .registers
+            .read(register, editor)
+            .into_iter()
+            .flatten()
+            .filter(|entry| seen.insert(entry.clone()))
+            .collect();
+        fuzzy_match(&self.line, entries, false)
+            .into_iter()
+            .map(|(entry, _)| (0.., entry.into_owned().into()))
+            .collect()
     }
 
     /// Compute the cursor position after applying movement
@@ -246,12 +351,44 @@ fn eval_movement(&self, movement: Movement) -> usize {
                 }
                 position
             }
+            Movement::StartOfLine if self.multiline => self.line[..self.cursor]
+                .rfind('\n')
+                .map_or(0, |idx| idx + 1),
+            Movement::EndOfLine if self.multiline => self.line[self.cursor..]
+                .find('\n')
+                .map_or(self.line.len(), |idx| self.cursor + idx),
             Movement::StartOfLine => 0,
             Movement::EndOfLine => self.line.len(),
+            Movement::RowUp(rep) => self.move_rows(-(rep as isize)),
+            Movement::RowDown(rep) => self.move_rows(rep as isize),
             Movement::None => self.cursor,
         }
     }
 
+    /// Returns the position `offset` visual rows away from the cursor, as close as possible
+    /// to the cursor's column. The rows are wrapped to the width of the last render.
+    fn move_rows(&self, offset: isize) -> usize {
+        let rows = soft_wrap(&self.line, self.line_area.width as usize);
+        let row = row_at(&rows, self.cursor);
+        let target = row
+            .saturating_add_signed(offset)
+            .min(rows.len().saturating_sub(1));
+        if target == row {
+            return self.cursor;
+        }
+        let col = self.line[rows[row].start..self.cursor].width();
+        let target = rows[target].clone();
+
+        let mut width = 0;
+        for (idx, grapheme) in self.line[target.clone()].grapheme_indices(true) {
+            width += grapheme.width();
+            if width > col {
+                return target.start + idx;
+            }
+        }
+        target.end
+    }
+
     pub fn insert_char(&mut self, c: char, cx: &Context) {
         if let Some(handler) = &self.next_char_handler.take() {
             handler(self, c, cx);
@@ -280,11 +417,11 @@ pub fn move_cursor(&mut self, movement: Movement) {
     }
 
     pub fn move_start(&mut self) {
-        self.cursor = 0;
+        self.move_cursor(Movement::StartOfLine);
     }
 
     pub fn move_end(&mut self) {
-        self.cursor = self.line.len();
+        self.move_cursor(Movement::EndOfLine);
     }
 
     pub fn delete_char_backwards(&mut self, editor: &Editor) {
@@ -400,6 +537,12 @@ pub fn exit_selection(&mut self) {
 
 const BASE_WIDTH: u16 = 30;
 
+/// The maximum number of rows shown by a multi-line prompt, longer input is scrolled.
+const MAX_INPUT_HEIGHT: u16 = 10;
+
+/// The capacity reserved for the input of a secret prompt.
+const SECRET_CAPACITY: usize = 256;
+
 impl Prompt {
     pub fn render_prompt(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         let theme = &cx.editor.theme;
@@ -408,6 +551,18 @@ pub fn render_prompt(&mut self, area: Rect, surface: &mut Surface, cx: &mut Cont
         let selected_color = theme.get("ui.menu.selected");
         let suggestion_color = theme.get("ui.text.inactive");
         let background = theme.get("ui.background");
+
+        let line_width = area.width.saturating_sub(self.prompt.len() as u16 + 2);
+        let rows = if self.multiline {
+            soft_wrap(&self.line, line_width as usize)
+        } else {
+            vec![0..self.line.len()]
+        };
+        let input_height = (rows.len() as u16)
+            .min(MAX_INPUT_HEIGHT)
+            .min(area.height.saturating_sub(1))
+            .max(1);
+
         // completion
 
         let max_len = self
@@ -424,11 +579,11 @@ pub fn render_prompt(&mut self, area: Rect, surface: &mut Surface, cx: &mut Cont
         let height = (self.completion.len() as u16)
             .div_ceil(cols)
             .min(10) // at most 10 rows (or less)
-            .min(area.height.saturating_sub(1));
+            .min(area.height.saturating_sub(input_height));
 
         let completion_area = Rect::new(
             area.x,
-            (area.height - height).saturating_sub(1),
+            (area.height - height).saturating_sub(input_height),
             area.width,
             height,
         );
@@ -476,7 +631,8 @@ pub fn render_prompt(&mut self, area: Rect, surface: &mut Surface, cx: &mut Cont
             }
         }
 
-        if let Some(doc) = (self.doc_fn)(&self.line) {
+        let doc = (!self.secret).then(|| (self.doc_fn)(&self.line)).flatten();
+        if let Some(doc) = doc {
             let mut text = ui::Text::new(doc.to_string());
 
             let max_width = BASE_WIDTH * 3;
@@ -506,7 +662,7 @@ pub fn render_prompt(&mut self, area: Rect, surface: &mut Surface, cx: &mut Cont
             text.render(inner, surface, cx);
         }
 
-        let line = area.height - 1;
+        let line = area.height.saturating_sub(input_height);
         surface.clear_with(area.clip_top(line), background);
         // render buffer text
         surface.set_string(area.x, area.y + line, &self.prompt, prompt_color);
@@ -516,7 +672,29 @@ pub fn render_prompt(&mut self, area: Rect, surface: &mut Surface, cx: &mut Cont
             .clip_top(line)
             .clip_right(2);
 
-        if self.line.is_empty() {
+        if self.secret {
+            self.anchor = 0;
+        } else if self.multiline && !self.line.is_empty() {
+            // the anchor is the first visible row, keep the cursor's row in view
+            let row = row_at(&rows, self.cursor);
+            let height = input_height as usize;
+            if row < self.anchor {
+                self.anchor = row;
+            } else if row >= self.anchor + height {
+                self.anchor = row + 1 - height;
+            }
+            self.anchor = self.anchor.min(rows.len().saturating_sub(height));
+
+            for (i, row) in rows.iter().skip(self.anchor).take(height).enumerate() {
+                surface.set_stringn(
+                    self.line_area.x,
+                    self.line_area.y + i as u16,
+                    &self.line[row.clone()],
+                    self.line_area.width as usize,
+                    prompt_color,
+                );
+            }
+        } else if self.line.is_empty() {
             self.anchor = 0;
             // Show the most recently entered value as a suggestion.
             if let Some(suggestion) = self.first_history_completion(cx.editor) {
@@ -616,6 +794,16 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
             compositor.pop();
         })));
 
+        if let Some(prompt) = self
+            .key_handler
+            .as_ref()
+            .and_then(|handler| handler(cx.editor, event))
+        {
+            self.prompt = prompt;
+            (self.callback_fn)(cx, &self.line, PromptEvent::Update);
+            return EventResult::Consumed(None);
+        }
+
         match event {
             ctrl!('c') | key!(Esc) => {
                 (self.callback_fn)(cx, &self.line, PromptEvent::Abort);
@@ -669,7 +857,11 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
                     (self.callback_fn)(cx, &self.line, PromptEvent::Update);
                 }
             }
-            key!(Enter) => {
+            key!(Enter) if self.multiline => {
+                self.insert_char('\n', cx);
+                (self.callback_fn)(cx, &self.line, PromptEvent::Update);
+            }
+            key!(Enter) | alt!(Enter) => {
                 if self.selection.is_some() && self.line.ends_with(std::path::MAIN_SEPARATOR) {
                     self.recalculate_completion(cx.editor);
                 } else {
@@ -682,16 +874,16 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
                     let input = if self.line.is_empty() {
                         &last_item
                     } else {
-                        if last_item != self.line {
-                            // store in history
-                            if let Some(register) = self.history_register {
-                                if let Err(err) =
-                                    cx.editor.registers.push(register, self.line.clone())
-                                {
-                                    cx.editor.set_error(err.to_string());
-                                }
-                            };
-                        }
+                        // store in history, skipping repeats of the last entry
+                        if let Some(register) = self.history_register.filter(|_| !self.secret) {
+                            if let Err(err) = cx
+                                .editor
+                                .registers
+                                .push_history(register, self.line.clone())
+                            {
+                                cx.editor.set_error(err.to_string());
+                            }
+                        };
 
                         &self.line
                     };
@@ -701,6 +893,8 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
                     return close_fn;
                 }
             }
+            key!(Up) if self.multiline => self.move_cursor(Movement::RowUp(1)),
+            key!(Down) if self.multiline => self.move_cursor(Movement::RowDown(1)),
             ctrl!('p') | key!(Up) => {
                 if let Some(register) = self.history_register {
                     self.change_history(cx, register, CompletionDirection::Backward);
@@ -724,6 +918,14 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
                 (self.callback_fn)(cx, &self.line, PromptEvent::Update)
             }
             ctrl!('q') => self.exit_selection(),
+            ctrl!('r') if self.next_char_handler.is_some() && self.history_register.is_some() => {
+                // a second `C-r` toggles fuzzy searching through the history
+                self.next_char_handler = None;
+                self.history_search = !self.history_search;
+                self.recalculate_completion(cx.editor);
+                (self.callback_fn)(cx, &self.line, PromptEvent::Update);
+                return EventResult::Consumed(None);
+            }
             ctrl!('r') => {
                 self.completion = cx
                     .editor
@@ -763,11 +965,30 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
     }
 
     fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        if self.multiline {
+            let rows = soft_wrap(&self.line, self.line_area.width as usize);
+            let row = row_at(&rows, self.cursor);
+            let col = self.line[rows[row].start..self.cursor].width();
+            return (
+                Some(Position::new(
+                    self.line_area.y as usize + row.saturating_sub(self.anchor),
+                    self.line_area.x as usize + col,
+                )),
+                editor.config().cursor_shape.from_mode(Mode::Insert),
+            );
+        }
+
         let area = area
             .clip_left(self.prompt.len() as u16)
             .clip_right(if self.prompt.is_empty() { 2 } else { 0 });
 
-        let mut col = area.left() as usize + self.line[self.anchor..self.cursor].width();
+        // The cursor of a secret input stays put to not reveal its length
+        let input_width = if self.secret {
+            0
+        } else {
+            self.line[self.anchor..self.cursor].width()
+        };
+        let mut col = area.left() as usize + input_width;
 
         // ensure the cursor does not go beyond elipses
         if self.truncate_end
@@ -791,3 +1012,15 @@ fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind)
         )
     }
 }
+
+impl Drop for Prompt {
+    fn drop(&mut self) {
+        if self.secret {
+            // Overwrite the secret in place: clearing keeps the allocation and the zeros fit
+            let len = self.line.len();
+            self.line.clear();
+            self.line.extend(std::iter::repeat_n('\0', len));
+            std::hint::black_box(&self.line);
+        }
+    }
+}
@@ -6,6 +6,7 @@
     document::{Mode, SCRATCH_BUFFER_NAME},
     graphics::Rect,
     theme::Style,
+    watch::WatchState,
     Document, Editor, View,
 };
 
@@ -21,6 +22,8 @@ pub struct RenderContext<'a> {
     pub view: &'a View,
     pub focused: bool,
     pub spinners: &'a ProgressSpinners,
+    /// The label of the active sticky key layer.
+    pub key_layer: Option<&'a str>,
     pub parts: RenderBuffer<'a>,
 }
 
@@ -31,6 +34,7 @@ pub fn new(
         view: &'a View,
         focused: bool,
         spinners: &'a ProgressSpinners,
+        key_layer: Option<&'a str>,
     ) -> Self {
         RenderContext {
             editor,
@@ -38,6 +42,7 @@ pub fn new(
             view,
             focused,
             spinners,
+            key_layer,
             parts: RenderBuffer::default(),
         }
     }
@@ -157,6 +162,12 @@ fn get_render_function<'a, F>(element_id: StatusLineElementID) -> impl Fn(&mut R
         helix_view::editor::StatusLineElement::VersionControl => render_version_control,
         helix_view::editor::StatusLineElement::Register => render_register,
         helix_view::editor::StatusLineElement::CurrentWorkingDirectory => render_cwd,
+        helix_view::editor::StatusLineElement::KeyLayer => render_key_layer,
+        helix_view::editor::StatusLineElement::SearchMatches => render_search_matches,
+        helix_view::editor::StatusLineElement::PinnedIndicator => render_pinned_indicator,
+        helix_view::editor::StatusLineElement::ThrottledIndicator => render_throttled_indicator,
+        helix_view::editor::StatusLineElement::WatchTask => render_watch_task,
+        helix_view::editor::StatusLineElement::Jobs => render_jobs,
     }
 }
 
@@ -343,6 +354,33 @@ fn render_selections<'a, F>(context: &mut RenderContext<'a>, write: F)
     );
 }
 
+fn render_search_matches<'a, F>(context: &mut RenderContext<'a>, write: F)
+where
+    F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
+{
+    let Some(matches) = context.editor.search_matches.as_ref().filter(|matches| {
+        matches.doc == context.doc.id() && matches.version == context.doc.version()
+    }) else {
+        return;
+    };
+    if matches.complete && matches.matches.is_empty() {
+        return;
+    }
+    let cursor = context
+        .doc
+        .selection(context.view.id)
+        .primary()
+        .cursor(context.doc.text().slice(..));
+    let index = matches.index_at(cursor);
+    let total = matches.matches.len();
+    let more = if matches.complete { "" } else { "+" };
+    let style = context.editor.theme.get("ui.statusline.search-matches");
+    write(
+        context,
+        Span::styled(format!(" match {index}/{total}{more} "), style),
+    );
+}
+
 fn render_primary_selection_length<'a, F>(context: &mut RenderContext<'a>, write: F)
 where
     F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
@@ -499,6 +537,67 @@ fn render_read_only_indicator<'a, F>(context: &mut RenderContext<'a>, write: F)
     write(context, title.into());
 }
 
+fn render_pinned_indicator<'a, F>(context: &mut RenderContext<'a>, write: F)
+where
+    F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
+{
+    let title = if context.view.pinned {
+        " [pinned] "
+    } else {
+        ""
+    };
+    write(context, title.into());
+}
+
+fn render_throttled_indicator<'a, F>(context: &mut RenderContext<'a>, write: F)
+where
+    F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
+{
+    let title = if context.editor.throttle_rendering() {
+        " [throttled] "
+    } else {
+        ""
+    };
+    write(context, title.into());
+}
+
+fn render_watch_task<'a, F>(context: &mut RenderContext<'a>, write: F)
+where
+    F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
+{
+    let editor = context.editor;
+    let Some(task) = &editor.watch else {
+        return;
+    };
+    let (symbol, scope) = match task.state {
+        WatchState::Running => ("…", "hint"),
+        WatchState::Passed => ("✓", "info"),
+        WatchState::Failed(_) => ("✗", "error"),
+    };
+    write(context, " ".into());
+    write(context, Span::styled(symbol, editor.theme.get(scope)));
+    let text = match task.state {
+        WatchState::Failed(locations) if locations > 0 => {
+            format!(" {} ({locations}) ", task.command)
+        }
+        _ => format!(" {} ", task.command),
+    };
+    write(context, text.into());
+}
+
+fn render_jobs<'a, F>(context: &mut RenderContext<'a>, write: F)
+where
+    F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
+{
+    let jobs = crate::job::running_jobs();
+    let text = match jobs.as_slice() {
+        [] => return,
+        [job] => format!(" {}… ", job.name),
+        jobs => format!(" {} jobs… ", jobs.len()),
+    };
+    write(context, text.into());
+}
+
 fn render_file_base_name<'a, F>(context: &mut RenderContext<'a>, write: F)
 where
     F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
@@ -554,6 +653,19 @@ fn render_register<'a, F>(context: &mut RenderContext<'a>, write: F)
     }
 }
 
+fn render_key_layer<'a, F>(context: &mut RenderContext<'a>, write: F)
+where
+    F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
+{
+    let layer = context
+        .key_layer
+        .filter(|layer| context.focused && !layer.is_empty());
+    if let Some(layer) = layer {
+        let style = context.editor.theme.get("ui.statusline.key-layer");
+        write(context, Span::styled(format!(" {layer} "), style));
+    }
+}
+
 fn render_file_indent_style<'a, F>(context: &mut RenderContext<'a>, write: F)
 where
     F: Fn(&mut RenderContext<'a>, Span<'a>) + Copy,
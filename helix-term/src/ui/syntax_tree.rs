@@ -0,0 +1,346 @@
+use std::ops::Range;
+
+use helix_core::{
+    syntax::{Loader, OverlayHighlights, StructuralQuery},
+    Language, RopeSlice, Syntax,
+};
+use helix_view::{graphics::Rect, theme::Theme, DocumentId, Editor};
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Widget},
+};
+
+/// The panel is never narrower than this, unless the screen is.
+const MIN_WIDTH: u16 = 30;
+
+/// A named node of the syntax tree, one line of the panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeLine {
+    pub depth: usize,
+    pub field: Option<String>,
+    pub kind: String,
+    pub byte_range: Range<u32>,
+    /// The 0-based line and column the node starts at.
+    pub start: (usize, usize),
+}
+
+/// Returns the named nodes of the syntax tree which overlap `range`, in document order.
+/// Subtrees outside of the range are not visited, so this is cheap for the viewport of a large
+/// document.
+pub fn tree_lines(syntax: &Syntax, text: RopeSlice, range: Range<u32>) -> Vec<TreeLine> {
+    let mut lines = Vec::new();
+    let mut cursor = syntax.tree().root_node().walk();
+    let mut depth = 0;
+    loop {
+        let node = cursor.node();
+        let node_range = node.byte_range();
+        let overlaps = node_range.start <= range.end && node_range.end >= range.start;
+        if overlaps && node.is_named() {
+            let start = text.byte_to_char(node_range.start as usize);
+            let line = text.char_to_line(start);
+            lines.push(TreeLine {
+                depth,
+                field: cursor.field_name().map(str::to_string),
+                kind: node.kind().to_string(),
+                byte_range: node_range,
+                start: (line, start - text.line_to_char(line)),
+            });
+        }
+        if overlaps && cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return lines;
+            }
+            depth -= 1;
+        }
+    }
+}
+
+/// A node captured by the query of the panel.
+#[derive(Debug, Clone)]
+struct CapturedNode {
+    name: String,
+    /// The char range of the node.
+    range: Range<usize>,
+    /// The first line of the node's text.
+    text: String,
+    start: (usize, usize),
+}
+
+/// The side panel toggled with `:tree-sitter-playground`. It shows the syntax tree of the
+/// viewport of the focused view with the node under the cursor highlighted and, if a query is
+/// given, the nodes captured by it. Captured nodes are also highlighted in the document with
+/// the theme's style for the name of their capture, so `@keyword` looks like a keyword.
+#[derive(Default)]
+pub struct SyntaxTreePanel {
+    query_source: Option<String>,
+    /// The query compiled for the language it was last run against.
+    query: Option<(Language, Result<StructuralQuery, String>)>,
+    /// The document, version, cursor and first visible line the contents were computed for.
+    key: Option<(DocumentId, i32, usize, usize)>,
+    lines: Vec<TreeLine>,
+    cursor_line: Option<usize>,
+    /// The char range of the node under the cursor.
+    cursor_node: Option<Range<usize>>,
+    captures: Vec<CapturedNode>,
+}
+
+impl SyntaxTreePanel {
+    pub fn new(query_source: Option<String>) -> Self {
+        Self {
+            query_source,
+            ..Self::default()
+        }
+    }
+
+    /// The width of the panel on a screen `width` columns wide.
+    pub fn width(width: u16) -> u16 {
+        (width / 3).max(MIN_WIDTH).min(width / 2)
+    }
+
+    /// Recomputes the contents for the focused view if its document, cursor or scroll position
+    /// changed.
+    pub fn update(&mut self, editor: &Editor) {
+        let (view, doc) = helix_view::current_ref!(editor);
+        let text = doc.text().slice(..);
+        let cursor = doc.selection(view.id).primary().cursor(text);
+        let first_line = text.char_to_line(doc.view_offset(view.id).anchor.min(text.len_chars()));
+        let key = (doc.id(), doc.version(), cursor, first_line);
+        if self.key == Some(key) {
+            return;
+        }
+        self.key = Some(key);
+        self.lines.clear();
+        self.cursor_line = None;
+        self.cursor_node = None;
+        self.captures.clear();
+
+        let Some(syntax) = doc.syntax() else {
+            return;
+        };
+        let last_line = view.estimate_last_doc_line(doc);
+        let start = text.line_to_byte(first_line) as u32;
+        let end = text.line_to_byte((last_line + 1).min(text.len_lines())) as u32;
+        self.lines = tree_lines(syntax, text, start..end);
+
+        let byte_pos = text.char_to_byte(cursor) as u32;
+        if let Some(node) = syntax.named_descendant_for_byte_range(byte_pos, byte_pos) {
+            let range = node.byte_range();
+            self.cursor_line = self
+                .lines
+                .iter()
+                .rposition(|line| line.byte_range == range && line.kind == node.kind());
+            self.cursor_node = Some(
+                text.byte_to_char(range.start as usize)..text.byte_to_char(range.end as usize),
+            );
+        }
+
+        let loader = editor.syn_loader.load();
+        self.run_query(syntax, &loader, text, start..end);
+    }
+
+    fn run_query(&mut self, syntax: &Syntax, loader: &Loader, text: RopeSlice, range: Range<u32>) {
+        let Some(source) = &self.query_source else {
+            return;
+        };
+        let language = syntax.root_language();
+        if self.query.as_ref().map(|(lang, _)| *lang) != Some(language) {
+            let query = match loader.structural_query(language, source) {
+                Some(query) => query.map_err(|err| err.to_string()),
+                None => Err("Grammar is not available for this buffer's language".to_string()),
+            };
+            self.query = Some((language, query));
+        }
+        let Some((_, Ok(query))) = &self.query else {
+            return;
+        };
+
+        let root = syntax.tree().root_node();
+        for (name, range) in query.captures(&root, text, range) {
+            let start = text.byte_to_char(range.start as usize);
+            let end = text.byte_to_char(range.end as usize);
+            let line = text.char_to_line(start);
+            let first_line = text.slice(start..end).lines().next().map(String::from);
+            self.captures.push(CapturedNode {
+                name: name.to_string(),
+                range: start..end,
+                text: first_line.unwrap_or_default().trim_end().to_string(),
+                start: (line, start - text.line_to_char(line)),
+            });
+        }
+    }
+
+    /// Highlights the node under the cursor and the captured nodes in the focused view.
+    /// Captures nested in an earlier capture are only listed in the panel.
+    pub fn overlays(&self, theme: &Theme) -> Vec<OverlayHighlights> {
+        let mut overlays = Vec::new();
+        if let (Some(highlight), Some(range)) = (
+            theme.find_highlight_exact("ui.highlight"),
+            self.cursor_node.clone(),
+        ) {
+            overlays.push(OverlayHighlights::single(highlight, range));
+        }
+
+        let mut captures: Vec<_> = self
+            .captures
+            .iter()
+            .filter_map(|capture| {
+                Some((theme.find_highlight(&capture.name)?, capture.range.clone()))
+            })
+            .collect();
+        captures.sort_by_key(|(_, range)| (range.start, std::cmp::Reverse(range.end)));
+        let mut end = 0;
+        captures.retain(|(_, range)| {
+            let keep = range.start >= end;
+            if keep {
+                end = range.end;
+            }
+            keep
+        });
+        if !captures.is_empty() {
+            overlays.push(OverlayHighlights::Heterogenous {
+                highlights: captures,
+            });
+        }
+        overlays
+    }
+
+    pub fn render(&self, editor: &Editor, area: Rect, surface: &mut Surface) {
+        let theme = &editor.theme;
+        let background = theme.get("ui.background");
+        let text_style = theme.get("ui.text");
+        let dim_style = theme.get("ui.text.inactive");
+        let field_style = theme.get("variable.other.member");
+        let cursor_style = theme.get("ui.selection");
+        surface.clear_with(area, background);
+
+        let (tree_area, captures_area) = match self.query_source {
+            Some(_) => {
+                let height = area.height / 2;
+                (
+                    area.clip_bottom(height),
+                    Some(area.clip_top(area.height - height)),
+                )
+            }
+            None => (area, None),
+        };
+
+        let block = Block::bordered()
+            .title(" Syntax tree ")
+            .border_style(theme.get("ui.window"));
+        let inner = block.inner(tree_area);
+        block.render(tree_area, surface);
+
+        // Keep the node under the cursor in view
+        let height = inner.height as usize;
+        let offset = match self.cursor_line {
+            Some(line) if line >= height => line - height / 2,
+            _ => 0,
+        };
+        for (row, (i, line)) in self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .enumerate()
+        {
+            let y = inner.y + row as u16;
+            let right = inner.right();
+            let mut x = inner.x + (line.depth * 2).min(inner.width as usize) as u16;
+            if let Some(field) = &line.field {
+                x = surface
+                    .set_stringn(
+                        x,
+                        y,
+                        format!("{field}: "),
+                        right.saturating_sub(x) as usize,
+                        field_style,
+                    )
+                    .0;
+            }
+            x = surface
+                .set_stringn(
+                    x,
+                    y,
+                    &line.kind,
+                    right.saturating_sub(x) as usize,
+                    text_style,
+                )
+                .0;
+            let (line_nr, col) = line.start;
+            surface.set_stringn(
+                x,
+                y,
+                format!(" {}:{}", line_nr + 1, col + 1),
+                right.saturating_sub(x) as usize,
+                dim_style,
+            );
+            if self.cursor_line == Some(i) {
+                surface.set_style(Rect::new(inner.x, y, inner.width, 1), cursor_style);
+            }
+        }
+
+        if let Some(area) = captures_area {
+            self.render_captures(editor, area, surface);
+        }
+    }
+
+    fn render_captures(&self, editor: &Editor, area: Rect, surface: &mut Surface) {
+        let theme = &editor.theme;
+        let text_style = theme.get("ui.text");
+        let dim_style = theme.get("ui.text.inactive");
+        let title = format!(" {} ", self.query_source.as_deref().unwrap_or_default());
+        let block = Block::bordered()
+            .title(title.as_str())
+            .border_style(theme.get("ui.window"));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        if let Some((_, Err(err))) = &self.query {
+            surface.set_stringn(
+                inner.x,
+                inner.y,
+                err,
+                inner.width as usize,
+                theme.get("error"),
+            );
+            return;
+        }
+
+        let right = inner.right();
+        for (row, capture) in self.captures.iter().take(inner.height as usize).enumerate() {
+            let y = inner.y + row as u16;
+            let capture_style = theme.try_get(&capture.name).unwrap_or(text_style);
+            let mut x = surface
+                .set_stringn(
+                    inner.x,
+                    y,
+                    format!("@{}", capture.name),
+                    inner.width as usize,
+                    capture_style,
+                )
+                .0;
+            let (line, col) = capture.start;
+            x = surface
+                .set_stringn(
+                    x,
+                    y,
+                    format!(" {}:{} ", line + 1, col + 1),
+                    right.saturating_sub(x) as usize,
+                    dim_style,
+                )
+                .0;
+            surface.set_stringn(
+                x,
+                y,
+                &capture.text,
+                right.saturating_sub(x) as usize,
+                text_style,
+            );
+        }
+    }
+}
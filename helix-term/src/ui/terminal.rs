@@ -0,0 +1,138 @@
+use helix_core::{unicode::width::UnicodeWidthChar, Position};
+use helix_view::{
+    graphics::{CursorKind, Rect},
+    input::Event,
+    terminal::Terminal,
+    Editor,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Borders, Widget},
+};
+
+use crate::compositor::{Component, Context, EventResult};
+
+/// The panel is never lower than this, unless the screen is.
+const MIN_HEIGHT: u16 = 8;
+
+/// The panel below the views showing the embedded terminal, `editor.terminal`, toggled with
+/// `:terminal` and `toggle_terminal`. While it's focused the keys go to the shell, except for
+/// the keys bound to `toggle_terminal` in normal mode, which hide it again. Clicking the panel
+/// or the views moves the focus between them.
+pub struct TerminalPanel {
+    pub focused: bool,
+    /// The area the panel was drawn in last, with and without the title.
+    area: Rect,
+    inner: Rect,
+}
+
+impl TerminalPanel {
+    /// Shows the terminal, focused, starting the user's `$SHELL` in the working directory
+    /// unless it's already running.
+    pub fn open(editor: &mut Editor) -> std::io::Result<Self> {
+        if editor.terminal.is_none() {
+            let area = editor.tree.area();
+            let rows = Self::height(area.height).saturating_sub(1);
+            let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+            let cwd = helix_stdx::env::current_working_dir();
+            editor.terminal = Some(Terminal::spawn(&shell, &cwd, rows, area.width)?);
+        }
+        Ok(Self {
+            focused: true,
+            area: Rect::default(),
+            inner: Rect::default(),
+        })
+    }
+
+    /// The height of the panel on a screen `height` rows high.
+    pub fn height(height: u16) -> u16 {
+        (height / 3).max(MIN_HEIGHT).min(height / 2)
+    }
+
+    /// The area of the terminal screen in a panel drawn in `area`, below its title.
+    pub fn inner(area: Rect) -> Rect {
+        area.clip_top(1)
+    }
+
+    /// Whether the cell at `column` and `row` of the screen is part of the panel.
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        self.area.intersects(Rect::new(column, row, 1, 1))
+    }
+}
+
+impl Component for TerminalPanel {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let Some(terminal) = &mut cx.editor.terminal else {
+            return EventResult::Ignored(None);
+        };
+        match event {
+            Event::Key(key) => terminal.send_key(*key),
+            Event::Paste(text) => terminal.paste(text),
+            _ => return EventResult::Ignored(None),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let editor = &mut *cx.editor;
+        let Some(terminal) = &mut editor.terminal else {
+            return;
+        };
+        let theme = &editor.theme;
+        let background = theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let title = match terminal.screen().title() {
+            "" => " Terminal ".to_string(),
+            title => format!(" {title} "),
+        };
+        let border_style = if self.focused {
+            theme.get("ui.statusline")
+        } else {
+            theme.get("ui.statusline.inactive")
+        };
+        let block = Block::new()
+            .borders(Borders::TOP)
+            .title(title.as_str())
+            .border_style(border_style);
+        let inner = Self::inner(area);
+        block.render(area, surface);
+        self.area = area;
+        self.inner = inner;
+
+        let screen = terminal.screen();
+        for row in 0..screen.rows().min(inner.height) {
+            let mut col = 0;
+            while col < screen.cols().min(inner.width) {
+                let cell = screen.cell(row, col);
+                surface.set_stringn(
+                    inner.x + col,
+                    inner.y + row,
+                    cell.symbol.encode_utf8(&mut [0; 4]),
+                    (inner.width - col) as usize,
+                    background.patch(cell.style),
+                );
+                // The cell after a wide character is covered by it
+                col += cell.symbol.width().unwrap_or(1).max(1) as u16;
+            }
+        }
+    }
+
+    fn cursor(&self, _area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        let cursor = editor
+            .terminal
+            .as_ref()
+            .and_then(|terminal| terminal.screen().cursor())
+            .filter(|&(row, col)| row < self.inner.height && col < self.inner.width);
+        match cursor {
+            Some((row, col)) => (
+                Some(Position::new(
+                    (self.inner.y + row) as usize,
+                    (self.inner.x + col) as usize,
+                )),
+                CursorKind::Block,
+            ),
+            None => (None, CursorKind::Hidden),
+        }
+    }
+}
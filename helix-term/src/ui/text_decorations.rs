@@ -7,8 +7,10 @@
 use crate::ui::document::{LinePos, TextRenderer};
 
 pub use diagnostics::InlineDiagnostics;
+pub use virtual_text::VirtualTextDecoration;
 
 mod diagnostics;
+mod virtual_text;
 
 /// Decorations are the primary mechanism for extending the text rendering.
 ///
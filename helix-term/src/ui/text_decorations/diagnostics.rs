@@ -97,30 +97,15 @@ fn draw_decoration_at(&mut self, g: &'static str, severity: Severity, col: u16,
 
     fn draw_eol_diagnostic(&mut self, diag: &Diagnostic, row: u16, col: usize) -> u16 {
         let style = self.styles.severity_style(diag.severity());
-        let width = self.renderer.viewport.width;
-        let start_col = (col - self.renderer.offset.col) as u16;
-        let mut end_col = start_col;
-        let mut draw_col = (col + 1) as u16;
+        let mut end_col = col;
+        let mut draw_col = col + 1;
 
         for line in diag.message.lines() {
-            if !self.renderer.column_in_bounds(draw_col as usize, 1) {
-                break;
-            }
-
-            (end_col, _) = self.renderer.set_string_truncated(
-                self.renderer.viewport.x + draw_col,
-                row,
-                line,
-                width.saturating_sub(draw_col) as usize,
-                |_| style,
-                true,
-                false,
-            );
-
-            draw_col = end_col - self.renderer.viewport.x + 2; // double space between lines
+            end_col = self.renderer.draw_virtual_text(line, style, row, draw_col);
+            draw_col = end_col + 2; // double space between lines
         }
 
-        end_col - start_col
+        (end_col - col) as u16
     }
 
     fn draw_diagnostic(&mut self, diag: &Diagnostic, col: u16, next_severity: Option<Severity>) {
@@ -0,0 +1,125 @@
+use std::ops::Range;
+
+use helix_core::doc_formatter::FormattedGrapheme;
+use helix_core::{Position, RopeSlice};
+use helix_view::annotations::virtual_text::{
+    line_end, virtual_text_at_line, VirtualText, VirtualTextPlacement,
+};
+use helix_view::{Document, Theme};
+
+use crate::ui::document::{LinePos, TextRenderer};
+use crate::ui::text_decorations::Decoration;
+
+/// Renders the [`VirtualText`] of a document. The space for text below a line is reserved by
+/// [`helix_view::annotations::virtual_text::VirtualTextLines`].
+pub struct VirtualTextDecoration<'a> {
+    text: RopeSlice<'a>,
+    virtual_text: &'a [VirtualText],
+    theme: &'a Theme,
+    tab_width: usize,
+    idx: usize,
+    /// The virtual text of the line which ended on the current visual line.
+    line: Option<(Range<usize>, usize)>,
+}
+
+impl<'a> VirtualTextDecoration<'a> {
+    pub fn new(doc: &'a Document, theme: &'a Theme) -> Self {
+        VirtualTextDecoration {
+            text: doc.text().slice(..),
+            virtual_text: doc.virtual_text(),
+            theme,
+            tab_width: doc.tab_width(),
+            idx: 0,
+            line: None,
+        }
+    }
+
+    fn next_anchor(&self) -> usize {
+        self.virtual_text
+            .get(self.idx)
+            .map_or(usize::MAX, |vt| line_end(self.text, vt.char_idx))
+    }
+
+    /// The visual width of the indentation of `line`, used to align text below it.
+    fn indent_width(&self, line: usize) -> usize {
+        let mut width = 0;
+        for ch in self.text.line(line).chars() {
+            match ch {
+                ' ' => width += 1,
+                '\t' => width += self.tab_width - width % self.tab_width,
+                _ => break,
+            }
+        }
+        width
+    }
+}
+
+impl Decoration for VirtualTextDecoration<'_> {
+    fn reset_pos(&mut self, pos: usize) -> usize {
+        self.idx = virtual_text_at_line(self.text, self.virtual_text, pos);
+        self.line = None;
+        self.next_anchor()
+    }
+
+    fn decorate_grapheme(
+        &mut self,
+        _renderer: &mut TextRenderer,
+        grapheme: &FormattedGrapheme,
+    ) -> usize {
+        let line = self
+            .text
+            .char_to_line(grapheme.char_idx.min(self.text.len_chars()));
+        let line_start = self.text.line_to_char(line);
+        let start = self.idx;
+        while let Some(vt) = self.virtual_text.get(self.idx) {
+            if vt.char_idx > grapheme.char_idx {
+                break;
+            }
+            self.idx += 1;
+        }
+        // skip text anchored before the line, for example inside a concealed range
+        let start = start
+            + self.virtual_text[start..self.idx].partition_point(|vt| vt.char_idx < line_start);
+        self.line = Some((start..self.idx, line));
+        self.next_anchor()
+    }
+
+    fn render_virt_lines(
+        &mut self,
+        renderer: &mut TextRenderer,
+        pos: LinePos,
+        virt_off: Position,
+    ) -> Position {
+        let Some((range, line)) = self.line.take() else {
+            return Position::new(0, 0);
+        };
+
+        // end-of-line text starts after the line and any end-of-line text before it
+        let mut col = virt_off.col + 1;
+        for vt in &self.virtual_text[range.clone()] {
+            if vt.placement != VirtualTextPlacement::EndOfLine {
+                continue;
+            }
+            let style = self.theme.get(&vt.scope);
+            let text = vt.text.lines().next().unwrap_or_default();
+            col = renderer.draw_virtual_text(text, style, pos.visual_line, col) + 2;
+        }
+        let width = col.saturating_sub(virt_off.col + 2);
+
+        let indent = self.indent_width(line);
+        let mut row = pos.visual_line + virt_off.row as u16;
+        for vt in &self.virtual_text[range] {
+            if vt.placement != VirtualTextPlacement::Below {
+                continue;
+            }
+            let style = self.theme.get(&vt.scope);
+            for (i, text) in vt.text.lines().enumerate() {
+                renderer.draw_virtual_text(text, style, row + i as u16, indent);
+            }
+            row += vt.height() as u16;
+        }
+        let height = row - pos.visual_line - virt_off.row as u16;
+
+        Position::new(height as usize, width)
+    }
+}
@@ -0,0 +1,229 @@
+use std::{collections::HashMap, ops::Range};
+
+use helix_core::{
+    syntax::{Loader, QueryIterEvent},
+    unicode::width::UnicodeWidthStr,
+    RopeSlice, Syntax,
+};
+use helix_view::{graphics::Rect, DocumentId, Editor, View, ViewId};
+use tui::buffer::Buffer as Surface;
+
+const SEPARATOR: &str = " › ";
+
+/// Only the start of a definition is searched for its name.
+const MAX_NAME_OFFSET: u32 = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breadcrumb {
+    pub name: String,
+    /// The char range of the symbol's name.
+    pub range: Range<usize>,
+}
+
+/// Returns the symbols enclosing `pos`, outermost first. Symbols are the definitions
+/// captured by the language's `tags.scm` query.
+pub fn breadcrumbs(
+    syntax: &Syntax,
+    loader: &Loader,
+    text: RopeSlice,
+    pos: usize,
+) -> Vec<Breadcrumb> {
+    let byte_pos = text.char_to_byte(pos.min(text.len_chars())) as u32;
+    let Some(node) = syntax.descendant_for_byte_range(byte_pos, byte_pos) else {
+        return Vec::new();
+    };
+
+    let mut crumbs = Vec::new();
+    let mut ancestor = Some(node);
+    while let Some(node) = ancestor {
+        let range = node.byte_range();
+        let name_range = range.start..range.end.min(range.start.saturating_add(MAX_NAME_OFFSET));
+        let mut tags = syntax.tags(text, loader, name_range);
+        while let Some(event) = tags.next() {
+            let QueryIterEvent::Match(mat) = event else {
+                continue;
+            };
+            let Some(query) = loader.tag_query(tags.current_language()) else {
+                continue;
+            };
+            if !query
+                .query
+                .capture_name(mat.capture)
+                .starts_with("definition.")
+                || mat.node.parent().as_ref() != Some(&node)
+            {
+                continue;
+            }
+            let name = mat.node.byte_range();
+            let start = text.byte_to_char(name.start as usize);
+            let end = text.byte_to_char(name.end as usize);
+            crumbs.push(Breadcrumb {
+                name: text.slice(start..end).to_string(),
+                range: start..end,
+            });
+            break;
+        }
+        ancestor = node.parent();
+    }
+
+    crumbs.reverse();
+    crumbs
+}
+
+/// The line above each view showing the path of symbols to the cursor, see the
+/// `editor.winbar` option. Clicking a symbol jumps to it.
+#[derive(Default)]
+pub struct Winbar {
+    /// Breadcrumbs by view, along with the document, version and cursor they were computed for.
+    cache: HashMap<ViewId, (DocumentId, i32, usize, Vec<Breadcrumb>)>,
+}
+
+impl Winbar {
+    /// Recomputes the breadcrumbs of all views whose document or cursor changed.
+    pub fn update(&mut self, editor: &Editor) {
+        let loader = editor.syn_loader.load();
+        self.cache
+            .retain(|view_id, _| editor.tree.contains(*view_id));
+
+        for (view, _) in editor.tree.views() {
+            let doc = &editor.documents[&view.doc];
+            let text = doc.text().slice(..);
+            let cursor = doc.selection(view.id).primary().cursor(text);
+            let key = (doc.id(), doc.version(), cursor);
+            if self
+                .cache
+                .get(&view.id)
+                .is_some_and(|(id, version, pos, _)| (*id, *version, *pos) == key)
+            {
+                continue;
+            }
+            let crumbs = doc
+                .syntax()
+                .map(|syntax| breadcrumbs(syntax, &loader, text, cursor))
+                .unwrap_or_default();
+            self.cache.insert(view.id, (key.0, key.1, key.2, crumbs));
+        }
+    }
+
+    fn crumbs(&self, view_id: ViewId) -> &[Breadcrumb] {
+        self.cache
+            .get(&view_id)
+            .map_or(&[], |(_, _, _, crumbs)| crumbs.as_slice())
+    }
+
+    /// The area of the winbar of `view`, the row above the view.
+    fn area(view: &View) -> Rect {
+        Rect::new(
+            view.area.x,
+            view.area.y.saturating_sub(1),
+            view.area.width,
+            1,
+        )
+    }
+
+    pub fn render(&self, editor: &Editor, view: &View, surface: &mut Surface, is_focused: bool) {
+        let theme = &editor.theme;
+        let base_style = if is_focused {
+            theme
+                .try_get("ui.winbar")
+                .unwrap_or_else(|| theme.get("ui.statusline"))
+        } else {
+            theme
+                .try_get("ui.winbar.inactive")
+                .unwrap_or_else(|| theme.get("ui.statusline.inactive"))
+        };
+        let separator_style = base_style.patch(theme.get("ui.winbar.separator"));
+
+        let area = Self::area(view);
+        surface.clear_with(area, base_style);
+
+        let mut x = area.x + 1;
+        for (i, crumb) in self.crumbs(view.id).iter().enumerate() {
+            if i > 0 {
+                x = surface
+                    .set_stringn(
+                        x,
+                        area.y,
+                        SEPARATOR,
+                        area.right().saturating_sub(x) as usize,
+                        separator_style,
+                    )
+                    .0;
+            }
+            x = surface
+                .set_stringn(
+                    x,
+                    area.y,
+                    &crumb.name,
+                    area.right().saturating_sub(x) as usize,
+                    base_style,
+                )
+                .0;
+            if x >= area.right() {
+                break;
+            }
+        }
+    }
+
+    /// Returns the breadcrumb at the given screen position, if it is on a winbar.
+    pub fn breadcrumb_at(
+        &self,
+        editor: &Editor,
+        row: u16,
+        column: u16,
+    ) -> Option<(ViewId, &Breadcrumb)> {
+        if !editor.tree.has_winbar() {
+            return None;
+        }
+        let view = editor.tree.views().find_map(|(view, _)| {
+            let area = Self::area(view);
+            (area.y == row && area.left() <= column && column < area.right()).then_some(view)
+        })?;
+
+        let mut x = Self::area(view).x as usize + 1;
+        for (i, crumb) in self.crumbs(view.id).iter().enumerate() {
+            if i > 0 {
+                x += SEPARATOR.chars().count();
+            }
+            let width = crumb.name.width();
+            if (x..x + width).contains(&(column as usize)) {
+                return Some((view.id, crumb));
+            }
+            x += width;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use helix_core::Rope;
+
+    use super::*;
+
+    #[test]
+    fn breadcrumbs_of_nested_definitions() {
+        let loader = helix_core::config::default_lang_loader();
+        let text = Rope::from("mod outer {\n    fn inner() {\n        let x = 1;\n    }\n}\n");
+        let text = text.slice(..);
+        let language = loader.language_for_name("rust").unwrap();
+        let syntax = Syntax::new(text, language, &loader).unwrap();
+
+        let crumbs = |pos| breadcrumbs(&syntax, &loader, text, pos);
+        assert_eq!(
+            crumbs(41),
+            [
+                Breadcrumb {
+                    name: "outer".into(),
+                    range: 4..9
+                },
+                Breadcrumb {
+                    name: "inner".into(),
+                    range: 19..24
+                },
+            ]
+        );
+        // Outside of any definition
+        assert!(crumbs(text.len_chars()).is_empty());
+    }
+}
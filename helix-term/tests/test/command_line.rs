@@ -17,6 +17,28 @@ async fn history_completion() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn history_search() -> anyhow::Result<()> {
+    let history = ":echo first<ret>:echo second<ret>:echo first<ret>";
+    // A second C-r fuzzy searches the history, the most recent entry is completed first for
+    // equal scores
+    for (search, expected) in [("sec", "second"), ("echo", "first")] {
+        test_key_sequence(
+            &mut AppBuilder::new().build()?,
+            Some(&format!("{history}:<C-r><C-r>{search}<tab><ret>")),
+            Some(&|app| {
+                let (status, &severity) = app.editor.get_status().unwrap();
+                assert_eq!(severity, Severity::Info);
+                assert_eq!(status.as_ref(), expected);
+            }),
+            false,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn prompt_reset_anchor() -> anyhow::Result<()> {
     test_key_sequence(
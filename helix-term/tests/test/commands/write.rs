@@ -5,7 +5,10 @@
 
 use helix_core::diagnostic::Severity;
 use helix_stdx::path;
-use helix_view::doc;
+use helix_view::{
+    doc,
+    editor::{FsyncPolicy, WriteMethod},
+};
 
 use super::*;
 
@@ -128,6 +131,83 @@ async fn test_write_quit_fail() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cquit_modified_buffer_fails() -> anyhow::Result<()> {
+    test_key_sequence(
+        &mut AppBuilder::new().build()?,
+        Some("ihello<esc>:cq<ret>"),
+        Some(&|app| {
+            assert_eq!(1, app.editor.documents().count());
+            assert_eq!(&Severity::Error, app.editor.get_status().unwrap().1);
+        }),
+        false,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_quit_all_scratch_fails() -> anyhow::Result<()> {
+    test_key_sequence(
+        &mut AppBuilder::new().build()?,
+        Some("ihello<esc>:wqa<ret>"),
+        Some(&|app| {
+            assert_eq!(1, app.editor.documents().count());
+            assert_eq!(&Severity::Error, app.editor.get_status().unwrap().1);
+        }),
+        false,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_quit_all_prompt_scratch_save_cancels() -> anyhow::Result<()> {
+    test_key_sequence(
+        &mut AppBuilder::new().build()?,
+        Some("ihello<esc>:qa<ret>s<ret>"),
+        Some(&|app| {
+            assert_eq!(1, app.editor.documents().count());
+            let (status, severity) = app.editor.get_status().unwrap();
+            assert_eq!("Quit cancelled", *status);
+            assert_eq!(&Severity::Info, severity);
+        }),
+        false,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_quit_all_prompt_discard() -> anyhow::Result<()> {
+    test_key_sequence(
+        &mut AppBuilder::new().build()?,
+        Some("ihello<esc>:qa<ret>d<ret>"),
+        None,
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_quit_all_prompt_save() -> anyhow::Result<()> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    let mut app = helpers::AppBuilder::new()
+        .with_file(file.path(), None)
+        .build()?;
+
+    test_key_sequence(&mut app, Some("ihello<esc>:qa<ret>s<ret>"), None, true).await?;
+
+    helpers::assert_file_has_content(&mut file, &LineFeedHandling::Native.apply("hello"))?;
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_buffer_close_concurrent() -> anyhow::Result<()> {
     test_key_sequences(
@@ -442,6 +522,81 @@ async fn test_write_auto_format_fails_still_writes() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn format_confirm_app(file: &tempfile::NamedTempFile) -> anyhow::Result<Application> {
+    let lang_conf = indoc! {r#"
+            [[language]]
+            name = "rust"
+            formatter = { command = "tr", args = [ "a-z", "A-Z" ] }
+        "#};
+    let config = Config {
+        editor: helix_view::editor::Config {
+            format_confirm_threshold: Some(0),
+            ..helpers::test_editor_config()
+        },
+        ..helpers::test_config()
+    };
+
+    helpers::AppBuilder::new()
+        .with_config(config)
+        .with_file(file.path(), None)
+        .with_input_text("#[l|]#et foo = 0;\n")
+        .with_lang_loader(helpers::test_syntax_loader(Some(lang_conf.into())))
+        .build()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_format_confirm_apply() -> anyhow::Result<()> {
+    let mut file = tempfile::Builder::new().suffix(".rs").tempfile()?;
+    let mut app = format_confirm_app(&file)?;
+
+    test_key_sequences(
+        &mut app,
+        vec![
+            (Some(":w<ret>"), None),
+            (
+                // the first choice applies the changes and writes them
+                Some("<ret>"),
+                Some(&|app| {
+                    assert_eq!("LET FOO = 0;\n", doc!(app.editor).text().to_string());
+                }),
+            ),
+        ],
+        false,
+    )
+    .await?;
+
+    helpers::assert_file_has_content(&mut file, "LET FOO = 0;\n")?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_format_confirm_discard() -> anyhow::Result<()> {
+    let mut file = tempfile::Builder::new().suffix(".rs").tempfile()?;
+    let mut app = format_confirm_app(&file)?;
+
+    test_key_sequences(
+        &mut app,
+        vec![
+            (Some(":w<ret>"), None),
+            (
+                Some("<down><ret>"),
+                Some(&|app| {
+                    assert_eq!("let foo = 0;\n", doc!(app.editor).text().to_string());
+                    assert!(!doc!(app.editor).is_modified());
+                }),
+            ),
+        ],
+        false,
+    )
+    .await?;
+
+    // the file is written without the formatting changes
+    helpers::assert_file_has_content(&mut file, "let foo = 0;\n")?;
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_write_new_path() -> anyhow::Result<()> {
     let mut file1 = tempfile::NamedTempFile::new().unwrap();
@@ -864,6 +1019,97 @@ async fn test_hardlink_write() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn write_method_config(write_method: WriteMethod) -> Config {
+    Config {
+        editor: helix_view::editor::Config {
+            write_method,
+            fsync: FsyncPolicy::Directory,
+            ..helpers::test_editor_config()
+        },
+        ..helpers::test_config()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_rename() -> anyhow::Result<()> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    let mut app = helpers::AppBuilder::new()
+        .with_config(write_method_config(WriteMethod::Rename))
+        .with_file(file.path(), None)
+        .build()?;
+
+    test_key_sequence(
+        &mut app,
+        Some("ithe gostak distims the doshes<ret><esc>:w<ret>"),
+        None,
+        false,
+    )
+    .await?;
+
+    reload_file(&mut file).unwrap();
+    let mut file_content = String::new();
+    file.as_file_mut().read_to_string(&mut file_content)?;
+
+    assert_eq!(
+        LineFeedHandling::Native.apply("the gostak distims the doshes"),
+        file_content
+    );
+    // The temporary file was renamed over the file
+    let dir = file.path().parent().unwrap();
+    let prefix = file
+        .path()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    let leftovers = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.starts_with(&prefix) && name.ends_with(".tmp")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg(not(target_os = "android"))]
+async fn test_hardlink_write_rename() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let mut file = tempfile::NamedTempFile::new_in(&dir)?;
+    let hardlink_path = dir.path().join("linked");
+    std::fs::hard_link(file.path(), &hardlink_path)?;
+
+    let mut app = helpers::AppBuilder::new()
+        .with_config(write_method_config(WriteMethod::Rename))
+        .with_file(&hardlink_path, None)
+        .build()?;
+
+    test_key_sequence(
+        &mut app,
+        Some("ithe gostak distims the doshes<ret><esc>:w<ret>"),
+        None,
+        false,
+    )
+    .await?;
+
+    reload_file(&mut file).unwrap();
+    let mut file_content = String::new();
+    file.as_file_mut().read_to_string(&mut file_content)?;
+
+    assert_eq!(
+        LineFeedHandling::Native.apply("the gostak distims the doshes"),
+        file_content
+    );
+    // Hard links are written with a backup instead, which keeps them intact
+    assert!(same_file::is_same_file(file.path(), &hardlink_path)?);
+
+    Ok(())
+}
+
 async fn edit_file_with_content(file_content: &[u8]) -> anyhow::Result<()> {
     let mut file = tempfile::NamedTempFile::new()?;
 
@@ -960,3 +1206,44 @@ async fn test_move_file_when_given_dir_only() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_move_dir_with_open_documents() -> anyhow::Result<()> {
+    let root = tempfile::tempdir()?;
+    let root_path = root.path().canonicalize()?;
+    let source_dir = root_path.join("source");
+    let target_dir = root_path.join("target");
+    std::fs::create_dir_all(source_dir.join("nested"))?;
+    let file = source_dir.join("file.ext");
+    let nested_file = source_dir.join("nested").join("file.ext");
+    std::fs::File::create(&file)?;
+    std::fs::File::create(&nested_file)?;
+
+    let mut app = helpers::AppBuilder::new()
+        .with_file(&file, None)
+        .with_file(&nested_file, None)
+        .build()?;
+
+    app.editor.move_path(&source_dir, &target_dir)?;
+
+    // the documents of all files in the directory follow it
+    let mut paths: Vec<_> = app
+        .editor
+        .documents()
+        .filter_map(|doc| doc.path().cloned())
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            target_dir.join("file.ext"),
+            target_dir.join("nested").join("file.ext")
+        ]
+    );
+    assert!(target_dir.join("nested").join("file.ext").is_file());
+    assert!(!source_dir.exists());
+
+    test_key_sequence(&mut app, None, None, false).await?;
+
+    Ok(())
+}
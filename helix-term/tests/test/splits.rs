@@ -214,3 +214,70 @@ async fn test_changes_in_splits_jumplist_sync() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_scroll_bind_scrolls_bound_splits() -> anyhow::Result<()> {
+    let content: String = (1..=100).map(|line| format!("line {line}\n")).collect();
+    let file = helpers::temp_file_with_contents(content)?;
+    let mut app = helpers::AppBuilder::new()
+        .with_file(file.path(), None)
+        .build()?;
+
+    // Bind both splits of the file, then scroll the focused one down by three lines
+    test_key_sequence(
+        &mut app,
+        Some("<C-w>v<C-w>b<C-w>w<C-w>bzjzjzj"),
+        Some(&|app| {
+            let doc = app.editor.documents().next().unwrap();
+            let top_lines: Vec<_> = app
+                .editor
+                .tree
+                .views()
+                .map(|(view, _)| doc.text().char_to_line(doc.view_offset(view.id).anchor))
+                .collect();
+            assert_eq!(vec![3, 3], top_lines);
+        }),
+        false,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pinned_split_opens_documents_in_preview_split() -> anyhow::Result<()> {
+    let file1 = tempfile::NamedTempFile::new()?;
+    let file2 = tempfile::NamedTempFile::new()?;
+    let file3 = tempfile::NamedTempFile::new()?;
+
+    let mut app = helpers::AppBuilder::new()
+        .with_file(file1.path(), None)
+        .build()?;
+
+    // The second document opened from the pinned split reuses the preview split
+    test_key_sequence(
+        &mut app,
+        Some(&format!(
+            "<C-w>p:o {}<ret><C-w>w:o {}<ret>",
+            file2.path().to_string_lossy(),
+            file3.path().to_string_lossy()
+        )),
+        Some(&|app| {
+            let path =
+                |view: &helix_view::View| app.editor.document(view.doc).unwrap().path().cloned();
+            let views: Vec<_> = app.editor.tree.views().map(|(view, _)| view).collect();
+            assert_eq!(2, views.len());
+
+            let pinned = views.iter().find(|view| view.pinned).unwrap();
+            assert_eq!(Some(path::normalize(file1.path())), path(pinned));
+
+            let focused = app.editor.tree.get(app.editor.tree.focus);
+            assert!(!focused.pinned);
+            assert_eq!(Some(path::normalize(file3.path())), path(focused));
+        }),
+        false,
+    )
+    .await?;
+
+    Ok(())
+}
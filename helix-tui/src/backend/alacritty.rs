@@ -1,8 +1,19 @@
 use std::io::{self, Write};
-use helix_view::graphics::{Color, CursorKind, Modifier, Rect, Style, UnderlineStyle};
-use crate::{backend::Backend, buffer::Cell, terminal::Config};
+use std::sync::Arc;
+use helix_core::unicode::width::UnicodeWidthStr;
+use helix_view::clipboard::ClipboardType;
+use helix_view::editor::{ColorDepthConfig, KittyKeyboardProtocolConfig};
+use helix_view::graphics::{Color, CursorKind, Hyperlink, Modifier, Rect, Style, UnderlineStyle};
+use helix_view::input::TerminalResponse;
+use helix_view::theme::Mode;
+use crate::{
+    backend::{sanitize_title, Backend},
+    buffer::{Cell, Image, Scroll},
+    terminal::Config,
+};
+use super::sixel::{self, Pixels};
 
-fn write_color(writer: &mut impl Write, color: Color, is_bg: bool) -> io::Result<()> {
+pub(crate) fn write_color(writer: &mut impl Write, color: Color, is_bg: bool) -> io::Result<()> {
     match color {
         Color::Reset => write!(writer, "\x1b[{}m", if is_bg { 49 } else { 39 }),
         Color::Black => write!(writer, "\x1b[{}m", if is_bg { 40 } else { 30 }),
@@ -26,100 +37,1869 @@ fn write_color(writer: &mut impl Write, color: Color, is_bg: bool) -> io::Result
     }
 }
 
+pub(crate) fn write_underline_color(writer: &mut impl Write, color: Color) -> io::Result<()> {
+    // SGR 58 only takes indexed or RGB colors, named colors are their ANSI palette index.
+    let index = match color {
+        Color::Reset => return write!(writer, "\x1b[59m"),
+        Color::Rgb(r, g, b) => return write!(writer, "\x1b[58;2;{};{};{}m", r, g, b),
+        Color::Indexed(i) => i,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::LightGray => 7,
+        Color::Gray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+    write!(writer, "\x1b[58;5;{}m", index)
+}
+
+/// Sets the cursor color with OSC 12, or restores the terminal's default with OSC 112.
+pub(super) fn write_cursor_color(writer: &mut impl Write, color: Color) -> io::Result<()> {
+    let Some((r, g, b)) = color_to_rgb(color) else {
+        return write!(writer, "\x1b]112\x1b\\");
+    };
+    write!(writer, "\x1b]12;#{r:02x}{g:02x}{b:02x}\x1b\\")
+}
+
+pub(crate) fn write_underline_style(
+    writer: &mut impl Write,
+    style: UnderlineStyle,
+) -> io::Result<()> {
+    match style {
+        UnderlineStyle::Reset => write!(writer, "\x1b[24m"),
+        UnderlineStyle::Line => write!(writer, "\x1b[4m"),
+        UnderlineStyle::DoubleLine => write!(writer, "\x1b[4:2m"),
+        UnderlineStyle::Curl => write!(writer, "\x1b[4:3m"),
+        UnderlineStyle::Dotted => write!(writer, "\x1b[4:4m"),
+        UnderlineStyle::Dashed => write!(writer, "\x1b[4:5m"),
+    }
+}
+
+/// Starts a hyperlink with OSC 8, or ends the current one with `None`. The `id` parameter joins
+/// the parts of a link split across lines, which terminals then highlight together.
+pub(crate) fn write_hyperlink(
+    writer: &mut impl Write,
+    hyperlink: Option<Hyperlink>,
+) -> io::Result<()> {
+    let Some(hyperlink) = hyperlink else {
+        return write!(writer, "\x1b]8;;\x1b\\");
+    };
+    // A control character would end the sequence early
+    let target: String = hyperlink
+        .target()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    write!(writer, "\x1b]8;id={};{}\x1b\\", hyperlink.id(), target)
+}
+
+/// Writes the SGR sequences changing the active modifiers from `from` to `to`.
+pub(crate) fn write_modifier_diff(
+    writer: &mut impl Write,
+    from: Modifier,
+    to: Modifier,
+) -> io::Result<()> {
+    let removed = from - to;
+    if removed.contains(Modifier::REVERSED) {
+        write!(writer, "\x1b[27m")?;
+    }
+    // SGR 22 turns off both bold and dim
+    if removed.intersects(Modifier::BOLD | Modifier::DIM) {
+        write!(writer, "\x1b[22m")?;
+    }
+    if removed.contains(Modifier::ITALIC) {
+        write!(writer, "\x1b[23m")?;
+    }
+    // SGR 25 turns off both blink speeds
+    if removed.intersects(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK) {
+        write!(writer, "\x1b[25m")?;
+    }
+    if removed.contains(Modifier::HIDDEN) {
+        write!(writer, "\x1b[28m")?;
+    }
+    if removed.contains(Modifier::CROSSED_OUT) {
+        write!(writer, "\x1b[29m")?;
+    }
+
+    let mut added = to - from;
+    if removed.intersects(Modifier::BOLD | Modifier::DIM) {
+        added |= to & (Modifier::BOLD | Modifier::DIM);
+    }
+    if removed.intersects(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK) {
+        added |= to & (Modifier::SLOW_BLINK | Modifier::RAPID_BLINK);
+    }
+    if added.contains(Modifier::BOLD) {
+        write!(writer, "\x1b[1m")?;
+    }
+    if added.contains(Modifier::DIM) {
+        write!(writer, "\x1b[2m")?;
+    }
+    if added.contains(Modifier::ITALIC) {
+        write!(writer, "\x1b[3m")?;
+    }
+    if added.contains(Modifier::SLOW_BLINK) {
+        write!(writer, "\x1b[5m")?;
+    }
+    if added.contains(Modifier::RAPID_BLINK) {
+        write!(writer, "\x1b[6m")?;
+    }
+    if added.contains(Modifier::REVERSED) {
+        write!(writer, "\x1b[7m")?;
+    }
+    if added.contains(Modifier::HIDDEN) {
+        write!(writer, "\x1b[8m")?;
+    }
+    if added.contains(Modifier::CROSSED_OUT) {
+        write!(writer, "\x1b[9m")?;
+    }
+    Ok(())
+}
+
+/// The colors a terminal can show. Colors beyond them are drawn as the closest color the
+/// terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorDepth {
+    Ansi16,
+    Indexed256,
+    TrueColor,
+}
+
+/// What the terminal supports. Detected from the environment and the terminfo database when the
+/// backend is created, and from the terminal's replies to queries on `claim`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Capabilities {
+    color_depth: ColorDepth,
+    /// Styled and colored underlines (SGR 4:x and 58).
+    extended_underlines: bool,
+    /// Images with the kitty graphics protocol.
+    graphics: bool,
+    /// Images with sixel graphics, used when the kitty graphics protocol isn't supported.
+    sixel: bool,
+    /// The size of a cell in pixels, which sixel images are scaled to.
+    cell_size: Option<(u16, u16)>,
+    /// Keys encoded with the kitty keyboard protocol, when its flags are pushed.
+    kitty_keyboard: bool,
+    /// Hyperlinks with OSC 8, which can't be queried.
+    hyperlinks: bool,
+}
+
+/// The capabilities of the terminal, as reported by [`AlacrittyBackend::probe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectedCapabilities {
+    pub true_color: bool,
+    /// Styled and colored underlines, like undercurls.
+    pub extended_underlines: bool,
+    pub kitty_keyboard: bool,
+    /// Frames drawn at once with synchronized updates (mode 2026).
+    pub synchronized_output: bool,
+    pub kitty_graphics: bool,
+    pub sixel: bool,
+    pub hyperlinks: bool,
+}
+
+/// Terminals which support true color and extended underlines without always advertising them.
+const KNOWN_TERMINALS: [&str; 5] = ["alacritty", "kitty", "wezterm", "foot", "ghostty"];
+
+/// Terminals which support the kitty graphics protocol, for when the query isn't answered.
+const GRAPHICS_TERMINALS: [&str; 2] = ["kitty", "ghostty"];
+
+/// The size of the base64 chunks an image is transmitted in, the maximum of the protocol.
+const IMAGE_CHUNK_SIZE: usize = 4096;
+
+/// The number of images kept by the terminal or as encoded sixels after they are no longer
+/// drawn, so that switching back to them doesn't transmit or encode them again.
+const IMAGE_CACHE_SIZE: usize = 8;
+
+impl Capabilities {
+    /// Detects the capabilities from the values of `TERM` and `COLORTERM`.
+    fn from_env_vars(term: &str, colorterm: &str) -> Self {
+        let known = KNOWN_TERMINALS.iter().any(|name| term.contains(name));
+        let color_depth =
+            if known || matches!(colorterm, "truecolor" | "24bit") || term.ends_with("-direct") {
+                ColorDepth::TrueColor
+            } else if term.contains("256color") {
+                ColorDepth::Indexed256
+            } else {
+                ColorDepth::Ansi16
+            };
+        Self {
+            color_depth,
+            extended_underlines: known,
+            graphics: GRAPHICS_TERMINALS.iter().any(|name| term.contains(name)),
+            sixel: false,
+            cell_size: None,
+            kitty_keyboard: false,
+            hyperlinks: known,
+        }
+    }
+
+    /// Detects the capabilities from the environment and the terminfo database.
+    fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let mut capabilities = Self::from_env_vars(&var("TERM"), &var("COLORTERM"));
+        if std::env::var_os("WSL_DISTRO_NAME").is_some() {
+            capabilities.color_depth = ColorDepth::TrueColor;
+        }
+        // WezTerm sets TERM to xterm-256color by default
+        capabilities.graphics |= var("TERM_PROGRAM") == "WezTerm";
+        capabilities.hyperlinks |= matches!(
+            var("TERM_PROGRAM").as_str(),
+            "WezTerm" | "iTerm.app" | "vscode"
+        );
+        // GNOME Terminal and other VTE based terminals since VTE 0.50
+        capabilities.hyperlinks |= var("VTE_VERSION")
+            .parse::<u32>()
+            .is_ok_and(|version| version >= 5000);
+        if let Ok(terminfo) = termini::TermInfo::from_env() {
+            let has = |name| terminfo.extended_cap(name).is_some();
+            if has("RGB") || has("Tc") || (has("setrgbf") && has("setrgbb")) {
+                capabilities.color_depth = ColorDepth::TrueColor;
+            }
+            capabilities.extended_underlines |= has("Smulx") || has("Su");
+        }
+        capabilities
+    }
+
+    /// Asks the terminal itself for the `RGB` and `Smulx` terminfo capabilities with XTGETTCAP,
+    /// which also works when the local terminfo database doesn't describe the terminal, for
+    /// example over SSH, and whether it supports the kitty graphics and keyboard protocols. The
+    /// queries are followed by a primary device attributes query so that terminals without
+    /// XTGETTCAP support don't have to time out, its reply tells whether sixel graphics are
+    /// supported.
+    fn query(&mut self, writer: &mut impl Write) {
+        let query: String = ["RGB", "Smulx"]
+            .iter()
+            .map(|name| format!("\x1bP+q{}\x1b\\", hex_encode(name)))
+            .chain(
+                [
+                    GRAPHICS_QUERY,
+                    KITTY_KEYBOARD_QUERY,
+                    CELL_SIZE_QUERY,
+                    "\x1b[c",
+                ]
+                .map(String::from),
+            )
+            .collect();
+        let Some(reply) = query_terminal(writer, &query, ends_with_device_attributes) else {
+            return;
+        };
+        self.graphics |= parse_graphics_reply(&reply);
+        self.sixel |= parse_sixel_reply(&reply);
+        self.cell_size = parse_cell_size_reply(&reply).or(self.cell_size);
+        self.kitty_keyboard = parse_kitty_keyboard_reply(&reply);
+        for name in parse_xtgettcap_reply(&reply) {
+            match name.as_str() {
+                "RGB" => self.color_depth = ColorDepth::TrueColor,
+                "Smulx" => self.extended_underlines = true,
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Asks whether a 1x1 RGB image could be shown, without showing it.
+const GRAPHICS_QUERY: &str = "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\";
+
+/// Whether the reply to [`GRAPHICS_QUERY`] is included in `reply`.
+fn parse_graphics_reply(reply: &[u8]) -> bool {
+    reply.windows(11).any(|window| window == b"\x1b_Gi=31;OK")
+}
+
+/// Whether the primary device attributes in `reply` include sixel graphics (attribute 4).
+fn parse_sixel_reply(reply: &[u8]) -> bool {
+    let Ok(reply) = std::str::from_utf8(reply) else {
+        return false;
+    };
+    let Some(start) = reply.rfind("\x1b[?") else {
+        return false;
+    };
+    reply[start + 3..]
+        .strip_suffix('c')
+        .is_some_and(|attributes| {
+            has_sixel_attribute(attributes.split(';').filter_map(|value| value.parse().ok()))
+        })
+}
+
+/// Whether primary device attributes include sixel graphics. The first value is the class of
+/// the terminal, not an attribute.
+fn has_sixel_attribute(mut attributes: impl Iterator<Item = u16>) -> bool {
+    attributes.next();
+    attributes.any(|attribute| attribute == 4)
+}
+
+/// Asks for the size of a cell in pixels, answered with `CSI 6 ; height ; width t`.
+const CELL_SIZE_QUERY: &str = "\x1b[16t";
+
+/// Parses the reply to [`CELL_SIZE_QUERY`] included in `reply` as `(width, height)`.
+fn parse_cell_size_reply(reply: &[u8]) -> Option<(u16, u16)> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let start = reply.find("\x1b[6;")? + 4;
+    let end = start + reply[start..].find('t')?;
+    let (height, width) = reply[start..end].split_once(';')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+/// Asks for the flags of the kitty keyboard protocol, answered with `CSI ? flags u`.
+const KITTY_KEYBOARD_QUERY: &str = "\x1b[?u";
+
+/// Whether the reply to [`KITTY_KEYBOARD_QUERY`] is included in `reply`.
+fn parse_kitty_keyboard_reply(reply: &[u8]) -> bool {
+    reply.split(|byte| *byte == 0x1b).any(|sequence| {
+        sequence
+            .strip_prefix(b"[?")
+            .and_then(|rest| rest.strip_suffix(b"u"))
+            .is_some_and(|flags| flags.iter().all(u8::is_ascii_digit))
+    })
+}
+
+/// Writes the escape sequences showing the image transmitted as `id` at the area of `image`.
+/// The cursor stays where the image is placed, not below it, so that the cells after it aren't
+/// scrolled.
+fn write_kitty_placement(writer: &mut impl Write, id: u32, image: &Image) -> io::Result<()> {
+    write!(writer, "\x1b[{};{}H", image.area.y + 1, image.area.x + 1)?;
+    write!(
+        writer,
+        "\x1b_Ga=p,i={id},q=2,C=1,c={},r={}",
+        image.area.width, image.area.height
+    )?;
+    if let Some(crop) = image.crop {
+        write!(
+            writer,
+            ",x={},y={},w={},h={}",
+            crop.x, crop.y, crop.width, crop.height
+        )?;
+    }
+    write!(writer, "\x1b\\")
+}
+
+/// Writes the escape sequences transmitting `png` to be stored by the terminal as `id`, without
+/// showing it.
+fn write_kitty_transmission(writer: &mut impl Write, id: u32, png: &[u8]) -> io::Result<()> {
+    let keys = format!("a=t,i={id},f=100,t=d,q=2");
+    let data = base64_encode(png);
+    let chunks = data.as_bytes().chunks(IMAGE_CHUNK_SIZE);
+    let last = chunks.len().saturating_sub(1);
+    for (i, chunk) in chunks.enumerate() {
+        let more = u8::from(i != last);
+        // Only the first chunk carries the keys
+        if i == 0 {
+            write!(writer, "\x1b_G{keys},m={more};")?;
+        } else {
+            write!(writer, "\x1b_Gm={more};")?;
+        }
+        writer.write_all(chunk)?;
+        write!(writer, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+fn hex_encode(name: &str) -> String {
+    name.bytes().map(|byte| format!("{byte:02X}")).collect()
+}
+
+/// Returns the names of the capabilities which the terminal reported as supported in its
+/// `DCS 1 + r name = value ST` replies to XTGETTCAP queries.
+fn parse_xtgettcap_reply(reply: &[u8]) -> Vec<String> {
+    let Ok(reply) = std::str::from_utf8(reply) else {
+        return Vec::new();
+    };
+    reply
+        .split("\x1bP1+r")
+        .skip(1)
+        .filter_map(|rest| {
+            let hex = &rest[..rest.find(['=', '\x1b'])?];
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                .collect::<Option<Vec<_>>>()?;
+            String::from_utf8(bytes).ok()
+        })
+        .collect()
+}
+
+/// The 16 ANSI colors with xterm's default palette, in palette order.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::LightGray, (229, 229, 229)),
+    (Color::Gray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The levels of each channel in the 6x6x6 color cube of the 256 color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).unsigned_abs().pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// The RGB value of `color` with xterm's default palette, `None` for [`Color::Reset`].
+pub(crate) fn color_to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(i) => Some(indexed_to_rgb(i)),
+        color => ANSI_COLORS
+            .iter()
+            .find(|(ansi, _)| *ansi == color)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_COLORS[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Returns the closest color of the color cube or the gray ramp of the 256 color palette.
+fn rgb_to_indexed(rgb: (u8, u8, u8)) -> u8 {
+    let cube_index = |value: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (value as i32 - **level as i32).abs())
+            .map_or(0, |(i, _)| i as u8)
+    };
+    let cube = 16 + 36 * cube_index(rgb.0) + 6 * cube_index(rgb.1) + cube_index(rgb.2);
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    if distance(indexed_to_rgb(gray), rgb) < distance(indexed_to_rgb(cube), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn rgb_to_ansi(rgb: (u8, u8, u8)) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, ansi)| distance(*ansi, rgb))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// Returns the closest color to `color` which can be shown with `depth`.
+fn degrade_color(color: Color, depth: ColorDepth) -> Color {
+    match (color, depth) {
+        (_, ColorDepth::TrueColor) => color,
+        (Color::Rgb(r, g, b), ColorDepth::Indexed256) => Color::Indexed(rgb_to_indexed((r, g, b))),
+        (Color::Rgb(r, g, b), ColorDepth::Ansi16) => rgb_to_ansi((r, g, b)),
+        (Color::Indexed(index), ColorDepth::Ansi16) => rgb_to_ansi(indexed_to_rgb(index)),
+        _ => color,
+    }
+}
+
+/// Undoes everything the backend may have enabled, whether it did or not: pops the kitty
+/// keyboard flags, disables mouse reporting, focus reporting, theme mode notifications and
+/// bracketed paste, resets the attributes and the cursor shape and color, then leaves the
+/// alternate screen. For panic hooks, which can't reach the backend to `restore` it.
+pub const RESET_SEQUENCE: &str = "\x1b[?2026l\x1b[<u\x1b[?1006l\x1b[?1002l\x1b[?1000l\
+    \x1b[0m\x1b[59m\x1b[?1004l\x1b[?2031l\x1b[?2004l\x1b[0 q\x1b[?25h\x1b]112\x1b\\\x1b[?1049l";
+
+/// The initial capacity of the frame buffer, enough for a full frame of a large terminal.
+const FRAME_BUFFER_CAPACITY: usize = 64 * 1024;
+
+/// How long to wait for the terminal to answer a query.
+#[cfg(unix)]
+const QUERY_TIMEOUT_MS: libc::c_int = 100;
+
+/// Reads the terminal size with the `TIOCGWINSZ` ioctl, from stdout or from the controlling
+/// terminal when stdout is redirected.
+#[cfg(unix)]
+fn os_size() -> Option<Rect> {
+    use std::os::fd::AsRawFd;
+
+    fn winsize(fd: libc::c_int) -> Option<Rect> {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        // SAFETY: TIOCGWINSZ only writes to the passed `winsize`
+        if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } != 0
+            || size.ws_col == 0
+            || size.ws_row == 0
+        {
+            return None;
+        }
+        Some(Rect::new(0, 0, size.ws_col, size.ws_row))
+    }
+
+    winsize(libc::STDOUT_FILENO).or_else(|| {
+        let tty = std::fs::File::open("/dev/tty").ok()?;
+        winsize(tty.as_raw_fd())
+    })
+}
+
+/// Reads the size of the visible window of the console's screen buffer, which also holds the
+/// scrollback.
+#[cfg(windows)]
+fn os_size() -> Option<Rect> {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleScreenBufferInfo, GetStdHandle, CONSOLE_SCREEN_BUFFER_INFO, STD_OUTPUT_HANDLE,
+    };
+
+    let mut info: CONSOLE_SCREEN_BUFFER_INFO = unsafe { std::mem::zeroed() };
+    // SAFETY: GetConsoleScreenBufferInfo only writes to the passed `info`
+    if unsafe { GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) } == 0 {
+        return None;
+    }
+    let window = info.srWindow;
+    let width = u16::try_from(window.Right - window.Left + 1).ok()?;
+    let height = u16::try_from(window.Bottom - window.Top + 1).ok()?;
+    (width > 0 && height > 0).then(|| Rect::new(0, 0, width, height))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn os_size() -> Option<Rect> {
+    None
+}
+
+/// Enables the processing of VT sequences written to the console, which older consoles don't
+/// do by default. Returns the previous mode of the console, to restore it with
+/// [`set_console_mode`].
+#[cfg(windows)]
+fn enable_virtual_terminal_processing() -> Result<u32, io::Error> {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, DISABLE_NEWLINE_AUTO_RETURN,
+        ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_OUTPUT_HANDLE,
+    };
+
+    let mut mode = 0;
+    // SAFETY: GetConsoleMode only writes to the passed `mode`
+    if unsafe { GetConsoleMode(GetStdHandle(STD_OUTPUT_HANDLE), &mut mode) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    set_console_mode(mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING | DISABLE_NEWLINE_AUTO_RETURN)?;
+    Ok(mode)
+}
+
+#[cfg(windows)]
+fn set_console_mode(mode: u32) -> Result<(), io::Error> {
+    use windows_sys::Win32::System::Console::{GetStdHandle, SetConsoleMode, STD_OUTPUT_HANDLE};
+
+    // SAFETY: a plain call with the handle of the process's stdout
+    if unsafe { SetConsoleMode(GetStdHandle(STD_OUTPUT_HANDLE), mode) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Writes `query` and reads the terminal's reply until `is_complete` returns true for it. The
+/// reply is read from the controlling terminal, which must already be in raw mode.
+#[cfg(unix)]
+fn query_terminal(
+    writer: &mut impl Write,
+    query: &str,
+    is_complete: impl Fn(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+    use std::io::Read;
+    use std::os::fd::AsRawFd;
+
+    let mut tty = std::fs::File::open("/dev/tty").ok()?;
+    write!(writer, "{}", query).ok()?;
+    writer.flush().ok()?;
+
+    let mut reply = Vec::new();
+    let mut buf = [0; 32];
+    while !is_complete(&reply) && reply.len() < 256 {
+        let mut fd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a single, initialized `pollfd`
+        if unsafe { libc::poll(&mut fd, 1, QUERY_TIMEOUT_MS) } <= 0 {
+            return None;
+        }
+        match tty.read(&mut buf) {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => reply.extend_from_slice(&buf[..n]),
+        }
+    }
+    Some(reply)
+}
+
+#[cfg(not(unix))]
+fn query_terminal(
+    _writer: &mut impl Write,
+    _query: &str,
+    _is_complete: impl Fn(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+    None
+}
+
+/// Asks the terminal for its size with `CSI 18 t`, for when it can't be read with an ioctl.
+fn query_size(writer: &mut impl Write) -> Option<Rect> {
+    let reply = query_terminal(writer, "\x1b[18t", |reply| reply.ends_with(b"t"))?;
+    parse_size_reply(&reply)
+}
+
+/// Whether the terminal supports synchronized output (mode 2026), asked with DECRQM. The query
+/// is followed by a primary device attributes query so that terminals without DECRQM support
+/// don't have to time out.
+fn detect_synchronized_output(writer: &mut impl Write) -> bool {
+    query_terminal(writer, "\x1b[?2026$p\x1b[c", ends_with_device_attributes)
+        .is_some_and(|reply| parse_mode_reply(&reply, 2026))
+}
+
+/// Asks the terminal for its theme mode with a mode 2031 query, or else for its background
+/// color with OSC 11 to derive the theme mode from its luminance. The queries are followed by a
+/// primary device attributes query, which every terminal answers, so that terminals without
+/// support for either don't have to time out.
+fn detect_theme_mode(writer: &mut impl Write) -> Option<Mode> {
+    let reply = query_terminal(
+        writer,
+        "\x1b[?996n\x1b]11;?\x1b\\\x1b[c",
+        ends_with_device_attributes,
+    )?;
+    parse_theme_mode_reply(&reply).or_else(|| parse_background_reply(&reply))
+}
+
+/// Parses the `CSI ? 997 ; 1 n` (dark) or `CSI ? 997 ; 2 n` (light) reply to a mode 2031 query,
+/// which is also sent unprompted when the theme mode changes while notifications are enabled.
+fn parse_theme_mode_reply(reply: &[u8]) -> Option<Mode> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let start = reply.find("\x1b[?997;")? + 7;
+    match reply[start..].get(..2)? {
+        "1n" => Some(Mode::Dark),
+        "2n" => Some(Mode::Light),
+        _ => None,
+    }
+}
+
+/// Whether `reply` ends with a `CSI ? attributes c` reply to a primary device attributes query.
+fn ends_with_device_attributes(reply: &[u8]) -> bool {
+    let Some(start) = reply.windows(3).rposition(|window| window == b"\x1b[?") else {
+        return false;
+    };
+    match reply[start + 3..].split_last() {
+        Some((b'c', attributes)) => attributes
+            .iter()
+            .all(|byte| byte.is_ascii_digit() || *byte == b';'),
+        _ => false,
+    }
+}
+
+/// Parses the `OSC 11 ; rgb:RRRR/GGGG/BBBB ST` reply to an OSC 11 query. Each channel has one
+/// to four hex digits and the reply may be terminated with either ST or BEL.
+fn parse_background_reply(reply: &[u8]) -> Option<Mode> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let start = reply.find("\x1b]11;")? + 5;
+    let reply = &reply[start..];
+    let end = reply.find(['\x07', '\x1b'])?;
+    parse_background_color(&reply[..end])
+}
+
+/// The theme mode for an `rgb:r/g/b` background color, from its luminance.
+fn parse_background_color(color: &str) -> Option<Mode> {
+    let mut channels = color.strip_prefix("rgb:")?.split('/').map(|channel| {
+        if !(1..=4).contains(&channel.len()) {
+            return None;
+        }
+        let max = (1u32 << (4 * channel.len())) - 1;
+        let value = u32::from_str_radix(channel, 16).ok()?;
+        Some(value as f32 / max as f32)
+    });
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 {
+        Mode::Light
+    } else {
+        Mode::Dark
+    })
+}
+
+/// Encodes `bytes` with the standard base64 alphabet and padding, as OSC 52 and the kitty
+/// graphics protocol expect.
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Parses the `CSI 8 ; rows ; cols t` reply to a `CSI 18 t` query.
+fn parse_size_reply(reply: &[u8]) -> Option<Rect> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let start = reply.rfind("\x1b[8;")?;
+    let (rows, cols) = reply[start + 4..].strip_suffix('t')?.split_once(';')?;
+    Some(Rect::new(0, 0, cols.parse().ok()?, rows.parse().ok()?))
+}
+
+/// Parses the `CSI ? mode ; value $ y` reply to a DECRQM query. Returns whether the mode is
+/// recognized and can be changed.
+fn parse_mode_reply(reply: &[u8], mode: u16) -> bool {
+    let Ok(reply) = std::str::from_utf8(reply) else {
+        return false;
+    };
+    let prefix = format!("\x1b[?{};", mode);
+    reply.rfind(&prefix).is_some_and(|start| {
+        let setting = &reply[start + prefix.len()..];
+        matches!(setting.split_once("$y"), Some(("1" | "2", _)))
+    })
+}
+
+/// The images transmitted to the terminal and the sixels of the images drawn recently, least
+/// recently drawn first. Images are only transmitted or encoded again when they are evicted.
+#[derive(Debug, Default)]
+struct ImageCache {
+    /// The ids the terminal stores the images under, with the kitty graphics protocol.
+    kitty: Vec<(Arc<[u8]>, u32)>,
+    next_id: u32,
+    /// The encoded sixels of the images, empty for images which can't be decoded.
+    sixel: Vec<(Image, String)>,
+}
+
+impl ImageCache {
+    /// The id `png` is stored under by the terminal, `None` if it has to be transmitted as a new
+    /// image with the id returned by [`ImageCache::insert_kitty`].
+    fn kitty_id(&mut self, png: &Arc<[u8]>) -> Option<u32> {
+        let i = self
+            .kitty
+            .iter()
+            .position(|(data, _)| Arc::ptr_eq(data, png))?;
+        let entry = self.kitty.remove(i);
+        let id = entry.1;
+        self.kitty.push(entry);
+        Some(id)
+    }
+
+    fn insert_kitty(&mut self, png: Arc<[u8]>) -> u32 {
+        // Id 0 means no id to the protocol
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        self.kitty.push((png, self.next_id));
+        self.next_id
+    }
+
+    /// Removes the least recently drawn images beyond [`IMAGE_CACHE_SIZE`] which aren't drawn in
+    /// the current frame, returns the ids of the images the terminal should free.
+    fn evict_kitty(&mut self, images: &[Image]) -> Vec<u32> {
+        evict(&mut self.kitty, |(png, _)| {
+            images.iter().any(|image| Arc::ptr_eq(&image.png, png))
+        })
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect()
+    }
+
+    /// The sixel of `image` scaled to its area with cells of `cell_size` pixels, encoded on the
+    /// first draw.
+    fn sixel(&mut self, image: &Image, cell_size: (u16, u16)) -> &str {
+        let entry = match self.sixel.iter().position(|(cached, _)| cached == image) {
+            Some(i) => self.sixel.remove(i),
+            None => {
+                let width = image.area.width as u32 * cell_size.0 as u32;
+                let height = image.area.height as u32 * cell_size.1 as u32;
+                let sixel = Pixels::decode_png(&image.png)
+                    .filter(|pixels| pixels.width > 0 && pixels.height > 0)
+                    .map(|pixels| sixel::encode(&pixels.scale(image.crop, width, height)))
+                    .unwrap_or_default();
+                (image.clone(), sixel)
+            }
+        };
+        self.sixel.push(entry);
+        &self.sixel.last().unwrap().1
+    }
+
+    fn evict_sixels(&mut self, images: &[Image]) {
+        evict(&mut self.sixel, |(cached, _)| images.contains(cached));
+    }
+}
+
+/// Removes the first entries beyond [`IMAGE_CACHE_SIZE`] for which `in_frame` returns false.
+fn evict<T>(entries: &mut Vec<T>, in_frame: impl Fn(&T) -> bool) -> Vec<T> {
+    let mut excess = entries.len().saturating_sub(IMAGE_CACHE_SIZE);
+    let mut evicted = Vec::new();
+    let mut i = 0;
+    while excess > 0 && i < entries.len() {
+        if in_frame(&entries[i]) {
+            i += 1;
+        } else {
+            evicted.push(entries.remove(i));
+            excess -= 1;
+        }
+    }
+    evicted
+}
 
 pub struct AlacrittyBackend<W: Write> {
     writer: W,
-    size: Rect,
+    /// Everything drawn since the last flush. A frame is written to `writer` at once on `flush`,
+    /// instead of with a write per escape sequence and cell.
+    buffer: Vec<u8>,
+    /// The last size reported by the terminal, updated by `size` so that `draw` clamps wide
+    /// symbols at the current right edge after a resize.
+    size: std::cell::Cell<Rect>,
+    /// Colors the terminal can't show are drawn as the closest color it can show. Without
+    /// extended underlines, underlines are plain lines in the foreground color.
+    detected: Capabilities,
+    /// Overrides the detected color depth, see `editor.color-depth` and `editor.true-color`.
+    color_depth: Option<ColorDepth>,
+    /// Overrides for false negatives of the detection, see `editor.undercurl`.
+    force_extended_underlines: bool,
+    /// Whether frames are wrapped in synchronized updates, detected on `claim`.
+    synchronized_output: bool,
+    /// Whether a synchronized update was started by `draw` and is ended by `flush`.
+    in_synchronized_update: bool,
+    /// Whether mouse events are reported, see the `editor.mouse` option.
+    mouse_capture: bool,
+    /// The theme mode of the terminal, detected on `claim` from its background color.
+    theme_mode: Option<Mode>,
+    /// Whether the kitty keyboard protocol is requested, see `editor.kitty-keyboard-protocol`.
+    kitty_keyboard: bool,
+    /// The last title set with `set_title`, the original title is restored on `restore`.
+    title: Option<String>,
+    /// Whether the cursor color was changed with `set_cursor_color`.
+    cursor_color: bool,
+    images: ImageCache,
+    claimed: bool,
+    /// The mode of the console before `claim` enabled VT processing.
+    #[cfg(windows)]
+    console_mode: Option<u32>,
 }
 
 impl<W: Write> AlacrittyBackend<W> {
     pub fn new(mut writer: W) -> Result<Self, io::Error> {
-        // Just setting a dummy size for now; handle actual terminal size query later
+        let size = os_size()
+            .or_else(|| query_size(&mut writer))
+            .unwrap_or(Rect::new(0, 0, 80, 24));
         Ok(Self {
             writer,
-            size: Rect::new(0, 0, 80, 24),
+            buffer: Vec::with_capacity(FRAME_BUFFER_CAPACITY),
+            size: std::cell::Cell::new(size),
+            detected: Capabilities::detect(),
+            color_depth: None,
+            force_extended_underlines: false,
+            synchronized_output: false,
+            in_synchronized_update: false,
+            mouse_capture: true,
+            theme_mode: None,
+            kitty_keyboard: true,
+            title: None,
+            cursor_color: false,
+            images: ImageCache::default(),
+            claimed: false,
+            #[cfg(windows)]
+            console_mode: None,
         })
     }
+
+    /// Asks the terminal for the capabilities detected on `claim`, without claiming it, for
+    /// `--health`. The terminal must be in raw mode for the replies to be read.
+    pub fn probe(&mut self) -> DetectedCapabilities {
+        self.synchronized_output = detect_synchronized_output(&mut self.writer);
+        self.detected.query(&mut self.writer);
+        let capabilities = self.capabilities();
+        DetectedCapabilities {
+            true_color: capabilities.color_depth == ColorDepth::TrueColor,
+            extended_underlines: capabilities.extended_underlines,
+            kitty_keyboard: self.kitty_keyboard && capabilities.kitty_keyboard,
+            synchronized_output: self.synchronized_output,
+            kitty_graphics: capabilities.graphics,
+            sixel: capabilities.sixel,
+            hyperlinks: capabilities.hyperlinks,
+        }
+    }
+
+    /// Writes the buffered output to the terminal.
+    fn flush_buffer(&mut self) -> Result<(), io::Error> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.writer.flush()
+    }
+
+    /// The detected capabilities with the overrides of the configuration applied.
+    fn capabilities(&self) -> Capabilities {
+        let mut capabilities = self.detected;
+        if let Some(color_depth) = self.color_depth {
+            capabilities.color_depth = color_depth;
+        }
+        capabilities.extended_underlines |= self.force_extended_underlines;
+        capabilities
+    }
+
+    /// Pushes the "disambiguate escape codes" flag of the kitty keyboard protocol: keys which
+    /// are ambiguous in the legacy encoding, like `C-i` or `C-S-a`, are sent as `CSI u`
+    /// sequences. Terminals without support for the protocol ignore it.
+    fn push_kitty_keyboard_flags(&mut self) -> Result<(), io::Error> {
+        write!(self.buffer, "\x1b[>1u")
+    }
+
+    fn pop_kitty_keyboard_flags(&mut self) -> Result<(), io::Error> {
+        write!(self.buffer, "\x1b[<u")
+    }
+
+    /// Enables SGR mouse reporting of clicks, releases, drags and the scroll wheel.
+    fn enable_mouse_capture(&mut self) -> Result<(), io::Error> {
+        write!(self.buffer, "\x1b[?1000h\x1b[?1002h\x1b[?1006h")
+    }
+
+    fn disable_mouse_capture(&mut self) -> Result<(), io::Error> {
+        write!(self.buffer, "\x1b[?1006l\x1b[?1002l\x1b[?1000l")
+    }
+
+    /// Copies `content` to the system clipboard (or the primary selection) with OSC 52, which
+    /// also works over SSH as long as the terminal allows programs to set the clipboard.
+    /// Reading the clipboard is not supported: the reply would have to be read from the input
+    /// stream which is owned by the event loop.
+    pub fn set_clipboard(
+        &mut self,
+        content: &str,
+        clipboard_type: ClipboardType,
+    ) -> Result<(), io::Error> {
+        let selection = match clipboard_type {
+            ClipboardType::Clipboard => 'c',
+            ClipboardType::Selection => 'p',
+        };
+        write!(
+            self.buffer,
+            "\x1b]52;{selection};{}\x1b\\",
+            base64_encode(content.as_bytes())
+        )?;
+        self.flush_buffer()
+    }
+}
+
+impl<W: Write> Drop for AlacrittyBackend<W> {
+    /// Restores the terminal when the backend is dropped while it is still claimed, for example
+    /// when the editor exits early with an error. A panic must instead be handled by a panic
+    /// hook writing [`RESET_SEQUENCE`]: the message is printed before unwinding drops the
+    /// backend, and restoring afterwards would move the cursor back over it.
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+        if let Err(err) = self.restore() {
+            log::error!("failed to restore the terminal: {err}");
+        }
+    }
 }
 
 impl<W: Write> Backend for AlacrittyBackend<W> {
     fn claim(&mut self) -> Result<(), io::Error> {
-        // Enter alternate screen and enable raw mode
-        write!(self.writer, "\x1b[?1049h")?;
-        self.writer.flush()
+        #[cfg(windows)]
+        if self.console_mode.is_none() {
+            self.console_mode = Some(enable_virtual_terminal_processing()?);
+        }
+        // Enter alternate screen, enable bracketed paste, focus reporting (`CSI I` and `CSI O`
+        // when the terminal gains and loses focus) and mode 2031 notifications of theme mode
+        // changes, like the system switching between dark and light
+        write!(self.buffer, "\x1b[?1049h\x1b[?2004h\x1b[?1004h\x1b[?2031h")?;
+        if self.mouse_capture {
+            self.enable_mouse_capture()?;
+        }
+        if self.kitty_keyboard {
+            self.push_kitty_keyboard_flags()?;
+        }
+        self.flush_buffer()?;
+        self.synchronized_output = detect_synchronized_output(&mut self.writer);
+        self.detected.query(&mut self.writer);
+        log::debug!("detected terminal capabilities: {:?}", self.detected);
+        self.theme_mode = detect_theme_mode(&mut self.writer);
+        self.claimed = true;
+        Ok(())
     }
 
-    fn reconfigure(&mut self, _config: Config) -> Result<(), io::Error> {
+    fn reconfigure(&mut self, config: Config) -> Result<(), io::Error> {
+        self.color_depth = match config.color_depth {
+            ColorDepthConfig::Auto => config
+                .force_enable_true_color
+                .then_some(ColorDepth::TrueColor),
+            ColorDepthConfig::TrueColor => Some(ColorDepth::TrueColor),
+            ColorDepthConfig::Indexed256 => Some(ColorDepth::Indexed256),
+            ColorDepthConfig::Ansi16 => Some(ColorDepth::Ansi16),
+        };
+        self.force_extended_underlines = config.force_enable_extended_underlines;
+        if self.claimed && config.enable_mouse_capture != self.mouse_capture {
+            if config.enable_mouse_capture {
+                self.enable_mouse_capture()?;
+            } else {
+                self.disable_mouse_capture()?;
+            }
+            self.flush_buffer()?;
+        }
+        self.mouse_capture = config.enable_mouse_capture;
+
+        let kitty_keyboard =
+            config.kitty_keyboard_protocol != KittyKeyboardProtocolConfig::Disabled;
+        if self.claimed && kitty_keyboard != self.kitty_keyboard {
+            if kitty_keyboard {
+                self.push_kitty_keyboard_flags()?;
+            } else {
+                self.pop_kitty_keyboard_flags()?;
+            }
+            self.flush_buffer()?;
+        }
+        self.kitty_keyboard = kitty_keyboard;
         Ok(())
     }
 
+    /// Undoes everything `claim` and `reconfigure` enabled. Restoring an unclaimed terminal does
+    /// nothing, so that restoring again on drop doesn't write anything.
     fn restore(&mut self) -> Result<(), io::Error> {
-        // Leave alternate screen
-        write!(self.writer, "\x1b[?1049l")?;
-        self.writer.flush()
+        if !self.claimed {
+            return Ok(());
+        }
+        if self.in_synchronized_update {
+            write!(self.buffer, "\x1b[?2026l")?;
+            self.in_synchronized_update = false;
+        }
+        if self.mouse_capture {
+            self.disable_mouse_capture()?;
+        }
+        if self.kitty_keyboard {
+            self.pop_kitty_keyboard_flags()?;
+        }
+        if self.title.take().is_some() {
+            // Pop the title saved by `set_title`
+            write!(self.buffer, "\x1b[23;0t")?;
+        }
+        if std::mem::take(&mut self.cursor_color) {
+            write_cursor_color(&mut self.buffer, Color::Reset)?;
+        }
+        if !self.images.kitty.is_empty() {
+            // Free the images stored by the terminal
+            write!(self.buffer, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        }
+        self.images = ImageCache::default();
+        self.claimed = false;
+        // Reset all attributes, including underline styles and colors, disable focus reporting,
+        // theme mode notifications and bracketed paste and leave the alternate screen
+        write!(
+            self.buffer,
+            "\x1b[0m\x1b[59m\x1b[?1004l\x1b[?2031l\x1b[?2004l\x1b[?1049l"
+        )?;
+        // Restore the terminal's default cursor shape
+        write!(self.buffer, "\x1b[0 q\x1b[?25h")?;
+        self.flush_buffer()?;
+        #[cfg(windows)]
+        if let Some(mode) = self.console_mode.take() {
+            set_console_mode(mode)?;
+        }
+        Ok(())
     }
 
     fn draw<'a, I>(&mut self, content: I) -> Result<(), io::Error>
     where
         I: Iterator<Item = (u16, u16, &'a Cell)>,
     {
+        let mut fg = Color::Reset;
+        let mut bg = Color::Reset;
+        let mut underline_color = Color::Reset;
+        let mut underline_style = UnderlineStyle::Reset;
+        let mut modifier = Modifier::empty();
+        let mut hyperlink = None;
+        let capabilities = self.capabilities();
+        let right_edge = self.size.get().width as usize;
+        // Where the terminal's cursor is after the last symbol written
+        let mut cursor: Option<(u16, u16)> = None;
+        let mut content = content.peekable();
+        if self.synchronized_output && !self.in_synchronized_update && content.peek().is_some() {
+            // Begin a synchronized update, the terminal renders the frame at once on `flush`
+            write!(self.buffer, "\x1b[?2026h")?;
+            self.in_synchronized_update = true;
+        }
         for (x, y, cell) in content {
-            // Move cursor
-            write!(self.writer, "\x1b[{};{}H", y + 1, x + 1)?;
+            // The cells hidden by the second half of a wide symbol are already covered
+            if matches!(cursor, Some(p) if x < p.0 && y == p.1) {
+                continue;
+            }
+            // A wide symbol in the last column would wrap or be cut off depending on the
+            // terminal, and an empty one wouldn't move the cursor, a space is written instead
+            let (symbol, width) = match cell.symbol.width() {
+                0 => (" ", 1),
+                width if x as usize + width > right_edge => (" ", 1),
+                width => (cell.symbol.as_str(), width),
+            };
+            // Move the cursor unless the cell directly follows the last symbol
+            if cursor != Some((x, y)) {
+                write!(self.buffer, "\x1b[{};{}H", y + 1, x + 1)?;
+            }
+            cursor = Some((x + width as u16, y));
 
-            // Render modifiers
-            if cell.modifier.contains(Modifier::BOLD) {
-                write!(self.writer, "\x1b[1m")?;
+            if cell.modifier != modifier {
+                write_modifier_diff(&mut self.buffer, modifier, cell.modifier)?;
+                modifier = cell.modifier;
             }
-            if cell.modifier.contains(Modifier::ITALIC) {
-                write!(self.writer, "\x1b[3m")?;
+            if cell.fg != fg {
+                let color = degrade_color(cell.fg, capabilities.color_depth);
+                write_color(&mut self.buffer, color, false)?;
+                fg = cell.fg;
             }
-            if cell.modifier.contains(Modifier::REVERSED) {
-                write!(self.writer, "\x1b[7m")?;
+            if cell.bg != bg {
+                let color = degrade_color(cell.bg, capabilities.color_depth);
+                write_color(&mut self.buffer, color, true)?;
+                bg = cell.bg;
             }
 
-            // Colors
-            write_color(&mut self.writer, cell.fg, false)?;
-            write_color(&mut self.writer, cell.bg, true)?;
+            // Without extended underlines, underlines are plain lines in the foreground color
+            let (new_style, new_color) = match cell.underline_style {
+                UnderlineStyle::Reset => (UnderlineStyle::Reset, underline_color),
+                style if capabilities.extended_underlines => (style, cell.underline_color),
+                _ => (UnderlineStyle::Line, underline_color),
+            };
+            if new_color != underline_color {
+                let color = degrade_color(new_color, capabilities.color_depth);
+                write_underline_color(&mut self.buffer, color)?;
+                underline_color = new_color;
+            }
+            if new_style != underline_style {
+                write_underline_style(&mut self.buffer, new_style)?;
+                underline_style = new_style;
+            }
+
+            if capabilities.hyperlinks && cell.hyperlink != hyperlink {
+                write_hyperlink(&mut self.buffer, cell.hyperlink)?;
+                hyperlink = cell.hyperlink;
+            }
 
             // Write symbol
-            write!(self.writer, "{}", cell.symbol)?;
+            write!(self.buffer, "{}", symbol)?;
+        }
 
-            // Reset
-            write!(self.writer, "\x1b[0m")?;
+        // Reset
+        if hyperlink.is_some() {
+            write_hyperlink(&mut self.buffer, None)?;
         }
-        Ok(())
+        write!(self.buffer, "\x1b[0m")
     }
 
     fn hide_cursor(&mut self) -> Result<(), io::Error> {
-        write!(self.writer, "\x1b[?25l")
+        write!(self.buffer, "\x1b[?25l")
     }
 
-    fn show_cursor(&mut self, _kind: CursorKind) -> Result<(), io::Error> {
-        write!(self.writer, "\x1b[?25h")
+    fn show_cursor(&mut self, kind: CursorKind) -> Result<(), io::Error> {
+        // DECSCUSR steady cursor shapes
+        let shape = match kind {
+            CursorKind::Block => 2,
+            CursorKind::Underline => 4,
+            CursorKind::Bar => 6,
+            CursorKind::Hidden => return self.hide_cursor(),
+        };
+        write!(self.buffer, "\x1b[?25h\x1b[{} q", shape)
     }
 
     fn set_cursor(&mut self, x: u16, y: u16) -> Result<(), io::Error> {
-        write!(self.writer, "\x1b[{};{}H", y + 1, x + 1)
+        write!(self.buffer, "\x1b[{};{}H", y + 1, x + 1)
+    }
+
+    fn set_cursor_color(&mut self, color: Color) -> Result<(), io::Error> {
+        self.cursor_color = color != Color::Reset;
+        write_cursor_color(&mut self.buffer, color)
     }
 
     fn clear(&mut self) -> Result<(), io::Error> {
-        write!(self.writer, "\x1b[2J")
+        write!(self.buffer, "\x1b[2J")
+    }
+
+    /// Scrolls with a scroll region (DECSTBM) and `SU`/`SD`. The exposed rows get the default
+    /// background since `draw` ends with all attributes reset.
+    fn scroll_region(&mut self, scroll: Scroll) -> Result<(), io::Error> {
+        if self.synchronized_output && !self.in_synchronized_update {
+            write!(self.buffer, "\x1b[?2026h")?;
+            self.in_synchronized_update = true;
+        }
+        write!(self.buffer, "\x1b[{};{}r", scroll.top + 1, scroll.bottom)?;
+        if scroll.amount > 0 {
+            write!(self.buffer, "\x1b[{}S", scroll.amount)?;
+        } else {
+            write!(self.buffer, "\x1b[{}T", -scroll.amount)?;
+        }
+        // Reset the scroll region to the whole screen
+        write!(self.buffer, "\x1b[r")
     }
 
     fn size(&self) -> Result<Rect, io::Error> {
-        Ok(self.size)
+        // Re-query so that `Terminal::autoresize` picks up resizes
+        if let Some(size) = os_size() {
+            self.size.set(size);
+        }
+        Ok(self.size.get())
     }
 
     fn flush(&mut self) -> Result<(), io::Error> {
-        self.writer.flush()
+        if self.in_synchronized_update {
+            write!(self.buffer, "\x1b[?2026l")?;
+            self.in_synchronized_update = false;
+        }
+        self.flush_buffer()
+    }
+
+    /// Sets the title with OSC 2, it is written on the next `flush`.
+    fn set_title(&mut self, title: &str) -> Result<(), io::Error> {
+        if self.title.as_deref() == Some(title) {
+            return Ok(());
+        }
+        if self.title.is_none() {
+            // Save the original title on the terminal's title stack
+            write!(self.buffer, "\x1b[22;0t")?;
+        }
+        write!(self.buffer, "\x1b]2;{}\x1b\\", sanitize_title(title))?;
+        self.title = Some(title.to_string());
+        Ok(())
     }
 
     fn supports_true_color(&self) -> bool {
-        true // We can assume alacritty backend targets true color
+        self.capabilities().color_depth == ColorDepth::TrueColor
+    }
+
+    fn supports_images(&self) -> bool {
+        self.detected.graphics || self.detected.sixel
+    }
+
+    fn draws_images_into_cells(&self) -> bool {
+        !self.detected.graphics && self.detected.sixel
+    }
+
+    fn supports_kitty_keyboard(&self) -> bool {
+        self.kitty_keyboard && self.detected.kitty_keyboard
+    }
+
+    /// With the kitty graphics protocol, deletes the placements of all images and places the new
+    /// ones. Images are only transmitted once, the terminal keeps them until they are evicted
+    /// from the cache. With sixel graphics, draws the images over the cells.
+    fn draw_images(&mut self, images: &[Image]) -> Result<(), io::Error> {
+        if self.detected.graphics {
+            write!(self.buffer, "\x1b_Ga=d,d=a,q=2\x1b\\")?;
+            for image in images {
+                let id = match self.images.kitty_id(&image.png) {
+                    Some(id) => id,
+                    None => {
+                        let id = self.images.insert_kitty(image.png.clone());
+                        write_kitty_transmission(&mut self.buffer, id, &image.png)?;
+                        id
+                    }
+                };
+                write_kitty_placement(&mut self.buffer, id, image)?;
+            }
+            for id in self.images.evict_kitty(images) {
+                write!(self.buffer, "\x1b_Ga=d,d=I,i={id},q=2\x1b\\")?;
+            }
+        } else if self.detected.sixel {
+            let cell_size = self.detected.cell_size.unwrap_or(sixel::DEFAULT_CELL_SIZE);
+            for image in images {
+                write!(
+                    self.buffer,
+                    "\x1b[{};{}H",
+                    image.area.y + 1,
+                    image.area.x + 1
+                )?;
+                let encoded = self.images.sixel(image, cell_size);
+                self.buffer.extend_from_slice(encoded.as_bytes());
+            }
+            self.images.evict_sixels(images);
+        }
+        Ok(())
+    }
+
+    fn get_theme_mode(&self) -> Option<Mode> {
+        self.theme_mode
+    }
+
+    /// Updates the detected capabilities with late replies to the queries of `claim`, and the
+    /// theme mode with background color and mode 2031 reports.
+    fn handle_response(&mut self, response: &TerminalResponse) {
+        match response {
+            TerminalResponse::KittyKeyboardFlags(_) => self.detected.kitty_keyboard = true,
+            TerminalResponse::DeviceAttributes(attributes) => {
+                self.detected.sixel |= has_sixel_attribute(attributes.iter().copied());
+            }
+            TerminalResponse::Capability { name } => match name.as_str() {
+                "RGB" => self.detected.color_depth = ColorDepth::TrueColor,
+                "Smulx" => self.detected.extended_underlines = true,
+                _ => (),
+            },
+            TerminalResponse::Mode {
+                mode: 2026,
+                setting,
+            } => self.synchronized_output = matches!(setting, 1 | 2),
+            TerminalResponse::Osc { number: 11, value } => {
+                if let Some(mode) = parse_background_color(value) {
+                    self.theme_mode = Some(mode);
+                }
+            }
+            TerminalResponse::ThemeMode(mode) => self.theme_mode = Some(*mode),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Crop;
+
+    fn backend(extended_underlines: bool) -> AlacrittyBackend<Vec<u8>> {
+        AlacrittyBackend {
+            writer: Vec::new(),
+            buffer: Vec::new(),
+            size: std::cell::Cell::new(Rect::new(0, 0, 80, 24)),
+            detected: Capabilities {
+                color_depth: ColorDepth::TrueColor,
+                extended_underlines,
+                graphics: true,
+                sixel: false,
+                cell_size: None,
+                kitty_keyboard: false,
+                hyperlinks: true,
+            },
+            color_depth: None,
+            force_extended_underlines: false,
+            synchronized_output: false,
+            in_synchronized_update: false,
+            mouse_capture: true,
+            theme_mode: None,
+            kitty_keyboard: true,
+            title: None,
+            cursor_color: false,
+            images: ImageCache::default(),
+            claimed: false,
+            #[cfg(windows)]
+            console_mode: None,
+        }
+    }
+
+    fn draw(cells: &[(u16, u16, Cell)], extended_underlines: bool) -> String {
+        let mut backend = backend(extended_underlines);
+        backend
+            .draw(cells.iter().map(|(x, y, cell)| (*x, *y, cell)))
+            .unwrap();
+        assert!(backend.writer.is_empty());
+        backend.flush().unwrap();
+        String::from_utf8(std::mem::take(&mut backend.writer)).unwrap()
+    }
+
+    fn cell(symbol: &str, style: Style) -> Cell {
+        let mut cell = Cell::default();
+        cell.set_symbol(symbol).set_style(style);
+        cell
+    }
+
+    #[test]
+    fn draw_skips_unchanged_position_and_style() {
+        let red = Style::default().fg(Color::Red);
+        let output = draw(
+            &[
+                (0, 0, cell("a", red)),
+                (1, 0, cell("b", red)),
+                (2, 0, cell("c", red.add_modifier(Modifier::BOLD))),
+                (0, 1, cell("d", Style::default())),
+            ],
+            false,
+        );
+        assert_eq!(
+            output,
+            "\x1b[1;1H\x1b[31mab\x1b[1mc\x1b[2;1H\x1b[22m\x1b[39md\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn draw_skips_hidden_cells_of_wide_symbols() {
+        let output = draw(
+            &[
+                (0, 0, cell("a", Style::default())),
+                (1, 0, cell("中", Style::default())),
+                (2, 0, cell(" ", Style::default())),
+                (3, 0, cell("🦀", Style::default())),
+                (5, 0, cell("b", Style::default())),
+                // The second half of a wide symbol drawn in an earlier frame
+                (1, 1, cell(" ", Style::default())),
+                (2, 1, cell("c", Style::default())),
+            ],
+            false,
+        );
+        assert_eq!(output, "\x1b[1;1Ha中🦀b\x1b[2;2H c\x1b[0m");
+    }
+
+    #[test]
+    fn draw_clamps_wide_symbols_at_the_right_edge() {
+        let output = draw(
+            &[
+                (78, 0, cell("中", Style::default())),
+                (79, 1, cell("中", Style::default())),
+                (0, 2, cell("", Style::default())),
+                (1, 2, cell("d", Style::default())),
+            ],
+            false,
+        );
+        assert_eq!(output, "\x1b[1;79H中\x1b[2;80H \x1b[3;1H d\x1b[0m");
+    }
+
+    #[test]
+    fn draw_past_the_old_width_after_a_resize() {
+        let mut backend = backend(false);
+        // As stored by `size` once the terminal reports its new size
+        backend.size.set(Rect::new(0, 0, 120, 24));
+        let cells = [
+            (80, 0, cell("a", Style::default())),
+            (100, 0, cell("中", Style::default())),
+            (118, 1, cell("中", Style::default())),
+            (119, 2, cell("中", Style::default())),
+        ];
+        backend
+            .draw(cells.iter().map(|(x, y, cell)| (*x, *y, cell)))
+            .unwrap();
+        backend.flush().unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.writer)).unwrap(),
+            "\x1b[1;81Ha\x1b[1;101H中\x1b[2;119H中\x1b[3;120H \x1b[0m"
+        );
+    }
+
+    #[test]
+    fn draw_wraps_hyperlinks() {
+        let link = Hyperlink::new("file:///tmp/a\x1b.rs");
+        let linked = Style::default().hyperlink(link);
+        let output = draw(
+            &[
+                (0, 0, cell("a", linked)),
+                (1, 0, cell("b", linked)),
+                (2, 0, cell("c", Style::default())),
+                (0, 1, cell("d", linked)),
+            ],
+            false,
+        );
+        let id = link.id();
+        assert_eq!(
+            output,
+            format!(
+                "\x1b[1;1H\x1b]8;id={id};file:///tmp/a.rs\x1b\\ab\x1b]8;;\x1b\\c\x1b[2;1H\x1b]8;id={id};file:///tmp/a.rs\x1b\\d\x1b]8;;\x1b\\\x1b[0m"
+            )
+        );
+    }
+
+    #[test]
+    fn modifier_diff() {
+        let diff = |from, to| {
+            let mut output = Vec::new();
+            write_modifier_diff(&mut output, from, to).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(
+            diff(Modifier::empty(), Modifier::HIDDEN | Modifier::CROSSED_OUT),
+            "\x1b[8m\x1b[9m"
+        );
+        assert_eq!(diff(Modifier::CROSSED_OUT, Modifier::empty()), "\x1b[29m");
+        // Removing bold also removes dim, which is added again
+        assert_eq!(
+            diff(Modifier::BOLD | Modifier::DIM, Modifier::DIM),
+            "\x1b[22m\x1b[2m"
+        );
+        assert_eq!(
+            diff(
+                Modifier::SLOW_BLINK | Modifier::ITALIC,
+                Modifier::RAPID_BLINK | Modifier::ITALIC
+            ),
+            "\x1b[25m\x1b[6m"
+        );
+    }
+
+    #[test]
+    fn draw_styled_underlines() {
+        let curl = Style::default()
+            .underline_style(UnderlineStyle::Curl)
+            .underline_color(Color::Rgb(255, 0, 0));
+        let cells = [(0, 0, cell("a", curl)), (1, 0, cell("b", Style::default()))];
+        assert_eq!(
+            draw(&cells, true),
+            "\x1b[1;1H\x1b[58;2;255;0;0m\x1b[4:3ma\x1b[24mb\x1b[0m"
+        );
+        // Without extended underlines, only plain underlines are drawn
+        assert_eq!(draw(&cells, false), "\x1b[1;1H\x1b[4ma\x1b[24mb\x1b[0m");
+    }
+
+    #[test]
+    fn draw_underline_colors() {
+        let line = Style::default().underline_style(UnderlineStyle::Line);
+        let cells = [
+            (0, 0, cell("a", line.underline_color(Color::Indexed(3)))),
+            (1, 0, cell("b", line.underline_color(Color::Red))),
+            (2, 0, cell("c", line.underline_color(Color::Red))),
+        ];
+        assert_eq!(
+            draw(&cells, true),
+            "\x1b[1;1H\x1b[58;5;3m\x1b[4ma\x1b[58;5;1mbc\x1b[0m"
+        );
+        // Without colored underlines the underline takes the foreground color
+        assert_eq!(draw(&cells, false), "\x1b[1;1H\x1b[4mabc\x1b[0m");
+    }
+
+    #[test]
+    fn parse_kitty_keyboard_reply_flags() {
+        assert!(parse_kitty_keyboard_reply(b"\x1b[?0u\x1b[?62;22c"));
+        assert!(parse_kitty_keyboard_reply(
+            b"\x1b_Gi=31;OK\x1b\\\x1b[?1u\x1b[?62;22c"
+        ));
+        assert!(!parse_kitty_keyboard_reply(b"\x1b[?62;22c"));
+    }
+
+    #[test]
+    fn parse_size_reply_ignores_preceding_input() {
+        assert_eq!(
+            parse_size_reply(b"x\x1b[8;40;120t"),
+            Some(Rect::new(0, 0, 120, 40))
+        );
+        assert_eq!(parse_size_reply(b"\x1b[8;40t"), None);
     }
-    
-    fn get_theme_mode(&self) -> Option<helix_view::theme::Mode> {
-        None
+
+    #[test]
+    fn parse_mode_reply_supported_values() {
+        assert!(parse_mode_reply(b"\x1b[?2026;2$y", 2026));
+        assert!(parse_mode_reply(b"\x1b[?2026;1$y", 2026));
+        assert!(!parse_mode_reply(b"\x1b[?2026;0$y", 2026));
+        assert!(!parse_mode_reply(b"\x1b[?2004;2$y", 2026));
+        // Followed by the reply to the primary device attributes query
+        assert!(parse_mode_reply(b"\x1b[?2026;2$y\x1b[?62;22c", 2026));
+        assert!(!parse_mode_reply(b"\x1b[?2026;0$y\x1b[?62;22c", 2026));
+        assert!(!parse_mode_reply(b"\x1b[?62;22c", 2026));
+    }
+
+    #[test]
+    fn cursor_color_is_restored() {
+        let mut backend = backend(true);
+        backend.claimed = true;
+        backend.kitty_keyboard = false;
+        backend.mouse_capture = false;
+        backend.set_cursor_color(Color::Rgb(255, 0, 128)).unwrap();
+        backend.set_cursor_color(Color::Red).unwrap();
+        backend.restore().unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.writer)).unwrap(),
+            "\x1b]12;#ff0080\x1b\\\x1b]12;#cd0000\x1b\\\x1b]112\x1b\\\x1b[0m\x1b[59m\x1b[?1004l\x1b[?2031l\x1b[?2004l\x1b[?1049l\x1b[0 q\x1b[?25h"
+        );
+    }
+
+    #[test]
+    fn restore_undoes_claimed_modes() {
+        let mut backend = backend(true);
+        backend.claimed = true;
+        backend.in_synchronized_update = true;
+        backend.restore().unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.writer)).unwrap(),
+            "\x1b[?2026l\x1b[?1006l\x1b[?1002l\x1b[?1000l\x1b[<u\x1b[0m\x1b[59m\x1b[?1004l\x1b[?2031l\x1b[?2004l\x1b[?1049l\x1b[0 q\x1b[?25h"
+        );
+
+        backend.restore().unwrap();
+        assert!(backend.writer.is_empty());
+    }
+
+    #[test]
+    fn reconfigure_toggles_modes() {
+        let config = |mouse, kitty_keyboard_protocol| Config {
+            enable_mouse_capture: mouse,
+            force_enable_extended_underlines: false,
+            force_enable_true_color: false,
+            color_depth: ColorDepthConfig::Auto,
+            hardware_scroll: false,
+            kitty_keyboard_protocol,
+        };
+        let mut backend = backend(true);
+        // Before `claim` the modes are only recorded
+        backend
+            .reconfigure(config(false, KittyKeyboardProtocolConfig::Disabled))
+            .unwrap();
+        assert!(backend.writer.is_empty() && backend.buffer.is_empty());
+
+        backend.claimed = true;
+        backend
+            .reconfigure(config(true, KittyKeyboardProtocolConfig::Auto))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.writer)).unwrap(),
+            "\x1b[?1000h\x1b[?1002h\x1b[?1006h\x1b[>1u"
+        );
+        backend
+            .reconfigure(config(true, KittyKeyboardProtocolConfig::Auto))
+            .unwrap();
+        assert!(backend.writer.is_empty());
+        backend
+            .reconfigure(config(false, KittyKeyboardProtocolConfig::Disabled))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.writer)).unwrap(),
+            "\x1b[?1006l\x1b[?1002l\x1b[?1000l\x1b[<u"
+        );
+        backend.claimed = false;
+    }
+
+    #[test]
+    fn set_title_saves_and_restores_the_original_title() {
+        let mut backend = backend(true);
+        backend.claimed = true;
+        backend.set_title("main.rs [+] — helix").unwrap();
+        backend.set_title("main.rs [+] — helix").unwrap();
+        backend.set_title("a\x07b").unwrap();
+        backend.flush().unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.writer)).unwrap(),
+            "\x1b[22;0t\x1b]2;main.rs [+] — helix\x1b\\\x1b]2;ab\x1b\\"
+        );
+
+        backend.restore().unwrap();
+        let output = String::from_utf8(std::mem::take(&mut backend.writer)).unwrap();
+        assert!(output.contains("\x1b[23;0t"));
+    }
+
+    #[test]
+    fn configured_color_depth_overrides_detection() {
+        let config = |force_enable_true_color, color_depth| Config {
+            enable_mouse_capture: true,
+            force_enable_extended_underlines: false,
+            force_enable_true_color,
+            color_depth,
+            hardware_scroll: false,
+            kitty_keyboard_protocol: KittyKeyboardProtocolConfig::Auto,
+        };
+        let mut backend = backend(false);
+        backend.detected.color_depth = ColorDepth::Ansi16;
+        let mut depth = |force_enable_true_color, color_depth| {
+            backend
+                .reconfigure(config(force_enable_true_color, color_depth))
+                .unwrap();
+            backend.capabilities().color_depth
+        };
+        assert_eq!(depth(false, ColorDepthConfig::Auto), ColorDepth::Ansi16);
+        assert_eq!(depth(true, ColorDepthConfig::Auto), ColorDepth::TrueColor);
+        assert_eq!(
+            depth(true, ColorDepthConfig::Indexed256),
+            ColorDepth::Indexed256
+        );
+    }
+
+    #[test]
+    fn capabilities_from_env_vars() {
+        let detect = |term, colorterm| {
+            let capabilities = Capabilities::from_env_vars(term, colorterm);
+            (capabilities.color_depth, capabilities.extended_underlines)
+        };
+        assert_eq!(detect("xterm-kitty", ""), (ColorDepth::TrueColor, true));
+        assert!(Capabilities::from_env_vars("xterm-ghostty", "").graphics);
+        assert!(!Capabilities::from_env_vars("alacritty", "truecolor").graphics);
+        assert!(Capabilities::from_env_vars("foot", "").hyperlinks);
+        assert!(!Capabilities::from_env_vars("xterm-256color", "truecolor").hyperlinks);
+        assert_eq!(
+            detect("xterm-256color", "truecolor"),
+            (ColorDepth::TrueColor, false)
+        );
+        assert_eq!(detect("xterm-direct", ""), (ColorDepth::TrueColor, false));
+        assert_eq!(detect("tmux-256color", ""), (ColorDepth::Indexed256, false));
+        assert_eq!(detect("screen", ""), (ColorDepth::Ansi16, false));
+    }
+
+    #[test]
+    fn parse_xtgettcap_supported_names() {
+        let reply = b"\x1bP1+r524742=382F382F38\x1b\\\x1bP0+r536D756C78\x1b\\\x1b[?62;22c";
+        assert_eq!(parse_xtgettcap_reply(reply), vec!["RGB".to_string()]);
+        assert_eq!(hex_encode("Smulx"), "536D756C78");
+    }
+
+    #[test]
+    fn degrade_colors_to_color_depth() {
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(degrade_color(orange, ColorDepth::TrueColor), orange);
+        assert_eq!(
+            degrade_color(orange, ColorDepth::Indexed256),
+            Color::Indexed(208)
+        );
+        assert_eq!(
+            degrade_color(Color::Rgb(128, 128, 128), ColorDepth::Indexed256),
+            Color::Indexed(244)
+        );
+        assert_eq!(
+            degrade_color(Color::Rgb(250, 10, 10), ColorDepth::Ansi16),
+            Color::LightRed
+        );
+        assert_eq!(
+            degrade_color(Color::Indexed(4), ColorDepth::Ansi16),
+            Color::Blue
+        );
+        assert_eq!(
+            degrade_color(Color::Reset, ColorDepth::Ansi16),
+            Color::Reset
+        );
+    }
+
+    #[test]
+    fn set_clipboard_with_osc52() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+
+        let mut backend = backend(true);
+        backend
+            .set_clipboard("hello", ClipboardType::Clipboard)
+            .unwrap();
+        assert_eq!(backend.writer, b"\x1b]52;c;aGVsbG8=\x1b\\");
+    }
+
+    #[test]
+    fn draw_images_with_kitty_graphics() {
+        assert!(parse_graphics_reply(b"\x1b_Gi=31;OK\x1b\\\x1b[?62;22c"));
+        assert!(!parse_graphics_reply(b"\x1b[?62;22c"));
+
+        let mut backend = backend(true);
+        let image = Image {
+            png: b"png".as_slice().into(),
+            area: Rect::new(2, 1, 10, 5),
+            crop: Some(Crop {
+                x: 0,
+                y: 8,
+                width: 16,
+                height: 4,
+            }),
+        };
+        backend.draw_images(&[image.clone()]).unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.buffer)).unwrap(),
+            "\x1b_Ga=d,d=a,q=2\x1b\\\x1b_Ga=t,i=1,f=100,t=d,q=2,m=0;cG5n\x1b\\\x1b[2;3H\
+             \x1b_Ga=p,i=1,q=2,C=1,c=10,r=5,x=0,y=8,w=16,h=4\x1b\\"
+        );
+
+        // The image is only placed again, it is still stored by the terminal
+        let moved = Image {
+            area: Rect::new(0, 0, 4, 2),
+            crop: None,
+            ..image.clone()
+        };
+        backend.draw_images(&[moved]).unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut backend.buffer)).unwrap(),
+            "\x1b_Ga=d,d=a,q=2\x1b\\\x1b[1;1H\x1b_Ga=p,i=1,q=2,C=1,c=4,r=2\x1b\\"
+        );
+
+        // Images which are no longer drawn are freed once the cache is full
+        let others: Vec<Image> = (0..IMAGE_CACHE_SIZE)
+            .map(|_| Image {
+                png: b"png".as_slice().into(),
+                ..image.clone()
+            })
+            .collect();
+        backend.draw_images(&others).unwrap();
+        let output = String::from_utf8(std::mem::take(&mut backend.buffer)).unwrap();
+        assert!(output.ends_with("\x1b_Ga=d,d=I,i=1,q=2\x1b\\"));
+        assert_eq!(backend.images.kitty.len(), IMAGE_CACHE_SIZE);
+    }
+
+    #[test]
+    fn draw_images_with_sixel_graphics() {
+        assert!(parse_sixel_reply(b"\x1b[?62;4;22c"));
+        assert!(!parse_sixel_reply(b"\x1b[?4;22c"));
+        assert_eq!(
+            parse_cell_size_reply(b"\x1b[6;20;9t\x1b[?62;22c"),
+            Some((9, 20))
+        );
+        assert_eq!(parse_cell_size_reply(b"\x1b[?62;22c"), None);
+
+        let mut backend = backend(true);
+        backend.detected.graphics = false;
+        assert!(!backend.supports_images());
+        backend.handle_response(&TerminalResponse::DeviceAttributes(vec![64, 4, 22]));
+        assert!(backend.supports_images());
+        assert!(backend.draws_images_into_cells());
+        backend.detected.cell_size = Some((1, 1));
+
+        // A 1x1 red PNG
+        let png: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x02\0\0\0\
+            \x90wS\xde\0\0\0\x0cIDATx\x9cc\xf8\xcf\xc0\0\0\x03\x01\x01\0\xc9\xfe\x92\xef\
+            \0\0\0\0IEND\xaeB`\x82";
+        let image = Image {
+            png: png.into(),
+            area: Rect::new(1, 2, 2, 1),
+            crop: None,
+        };
+        backend.draw_images(&[image]).unwrap();
+        assert_eq!(
+            String::from_utf8(backend.buffer).unwrap(),
+            "\x1b[3;2H\x1bP0;1;0q\"1;1;2;1#180;2;100;0;0#180@@\x1b\\"
+        );
+    }
+
+    #[test]
+    fn late_responses_update_capabilities() {
+        let mut backend = backend(false);
+        assert!(!backend.supports_kitty_keyboard());
+        backend.handle_response(&TerminalResponse::KittyKeyboardFlags(0));
+        assert!(backend.supports_kitty_keyboard());
+        backend.handle_response(&TerminalResponse::Osc {
+            number: 11,
+            value: "rgb:ffff/ffff/ffff".to_string(),
+        });
+        assert_eq!(backend.get_theme_mode(), Some(Mode::Light));
+    }
+
+    #[test]
+    fn parse_background_reply_luminance() {
+        assert_eq!(
+            parse_background_reply(b"\x1b]11;rgb:0000/0000/0000\x1b\\\x1b[?62;22c"),
+            Some(Mode::Dark)
+        );
+        assert_eq!(
+            parse_background_reply(b"\x1b]11;rgb:fdfd/f6f6/e3e3\x07"),
+            Some(Mode::Light)
+        );
+        assert_eq!(
+            parse_background_reply(b"\x1b]11;rgb:ff/f/fff\x07"),
+            Some(Mode::Light)
+        );
+        assert_eq!(parse_background_reply(b"\x1b[?62;22c"), None);
+
+        // A mode 2031 reply is preferred over the background color
+        let reply = b"\x1b[?997;2n\x1b]11;rgb:0000/0000/0000\x1b\\\x1b[?62;22c";
+        assert_eq!(parse_theme_mode_reply(reply), Some(Mode::Light));
+        assert_eq!(parse_theme_mode_reply(b"\x1b[?997;1n"), Some(Mode::Dark));
+        assert_eq!(parse_theme_mode_reply(b"\x1b[?997;3n"), None);
+
+        assert!(ends_with_device_attributes(
+            b"\x1b]11;rgb:cccc/0/0\x1b\\\x1b[?62;22c"
+        ));
+        assert!(!ends_with_device_attributes(
+            b"\x1b]11;rgb:cccc/0/0\x1b\\\x1b[?62;2"
+        ));
+        assert!(!ends_with_device_attributes(b"\x1b]11;rgb:cc"));
     }
 }
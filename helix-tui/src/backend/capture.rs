@@ -0,0 +1,194 @@
+use crate::{
+    backend::Backend,
+    buffer::{Buffer, Cell, Image, Scroll},
+    terminal::Config,
+};
+use helix_core::unicode::width::UnicodeWidthStr;
+use helix_view::graphics::{Color, CursorKind, Rect, Style};
+use std::io;
+
+/// A backend keeping the screen in memory, for tests asserting on the rendered content.
+///
+/// Unlike [`super::TestBackend`] it keeps track of everything a terminal would show: the cursor,
+/// its shape and the window title, and it reports true color support so that themes are drawn
+/// with their exact colors.
+#[derive(Debug)]
+pub struct CaptureBackend {
+    screen: Buffer,
+    cursor: (u16, u16),
+    cursor_kind: CursorKind,
+    title: Option<String>,
+}
+
+impl CaptureBackend {
+    pub fn new(width: u16, height: u16) -> CaptureBackend {
+        CaptureBackend {
+            screen: Buffer::empty(Rect::new(0, 0, width, height)),
+            cursor: (0, 0),
+            cursor_kind: CursorKind::Hidden,
+            title: None,
+        }
+    }
+
+    /// Resizes the screen, the next `Terminal::autoresize` picks up the new size.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.screen.resize(Rect::new(0, 0, width, height));
+    }
+
+    pub fn screen(&self) -> &Buffer {
+        &self.screen
+    }
+
+    /// The text of a row, without trailing whitespace.
+    pub fn row(&self, y: u16) -> String {
+        let width = self.screen.area.width as usize;
+        let start = y as usize * width;
+        let mut row = String::with_capacity(width);
+        let mut skip: usize = 0;
+        for cell in &self.screen.content[start..start + width] {
+            // The cells following a multi-width symbol are hidden by it
+            if skip == 0 {
+                row.push_str(&cell.symbol);
+            }
+            skip = std::cmp::max(skip, cell.symbol.width()).saturating_sub(1);
+        }
+        row.truncate(row.trim_end().len());
+        row
+    }
+
+    /// The text on the screen, one line per row without trailing whitespace.
+    pub fn contents(&self) -> String {
+        (0..self.screen.area.height)
+            .map(|y| self.row(y))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The position of the first occurrence of `text` on the screen, searching row by row.
+    /// Only finds text within a single row.
+    pub fn find(&self, text: &str) -> Option<(u16, u16)> {
+        (0..self.screen.area.height).find_map(|y| {
+            let row = self.row(y);
+            let start = row.find(text)?;
+            Some((row[..start].width() as u16, y))
+        })
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<&Cell> {
+        self.screen.get(x, y)
+    }
+
+    pub fn style(&self, x: u16, y: u16) -> Option<Style> {
+        self.cell(x, y).map(Cell::style)
+    }
+
+    pub fn fg(&self, x: u16, y: u16) -> Option<Color> {
+        self.cell(x, y).map(|cell| cell.fg)
+    }
+
+    pub fn bg(&self, x: u16, y: u16) -> Option<Color> {
+        self.cell(x, y).map(|cell| cell.bg)
+    }
+
+    /// The cursor position, `None` while the cursor is hidden.
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        (self.cursor_kind != CursorKind::Hidden).then_some(self.cursor)
+    }
+
+    pub fn cursor_kind(&self) -> CursorKind {
+        self.cursor_kind
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+}
+
+impl Backend for CaptureBackend {
+    fn claim(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn reconfigure(&mut self, _config: Config) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn restore(&mut self) -> Result<(), io::Error> {
+        self.title = None;
+        Ok(())
+    }
+
+    fn draw<'a, I>(&mut self, content: I) -> Result<(), io::Error>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        for (x, y, cell) in content {
+            if let Some(target) = self.screen.get_mut(x, y) {
+                *target = cell.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> Result<(), io::Error> {
+        self.cursor_kind = CursorKind::Hidden;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self, kind: CursorKind) -> Result<(), io::Error> {
+        self.cursor_kind = kind;
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> Result<(), io::Error> {
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), io::Error> {
+        self.screen.reset();
+        Ok(())
+    }
+
+    fn scroll_region(&mut self, scroll: Scroll) -> Result<(), io::Error> {
+        self.screen.scroll_rows(scroll);
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Rect, io::Error> {
+        Ok(self.screen.area)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn set_cursor_color(&mut self, _color: Color) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<(), io::Error> {
+        self.title = Some(title.to_string());
+        Ok(())
+    }
+
+    fn supports_true_color(&self) -> bool {
+        true
+    }
+
+    fn supports_images(&self) -> bool {
+        false
+    }
+
+    fn supports_kitty_keyboard(&self) -> bool {
+        false
+    }
+
+    fn draw_images(&mut self, _images: &[Image]) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn get_theme_mode(&self) -> Option<helix_view::theme::Mode> {
+        None
+    }
+}
@@ -2,9 +2,13 @@
 
 use std::io;
 
-use crate::{buffer::Cell, terminal::Config};
+use crate::{
+    buffer::{Cell, Image, Scroll},
+    terminal::Config,
+};
 
-use helix_view::graphics::{CursorKind, Rect};
+use helix_view::graphics::{Color, CursorKind, Rect};
+use helix_view::input::TerminalResponse;
 
 #[cfg(all(feature = "termina", not(windows)))]
 mod termina;
@@ -12,8 +16,15 @@
 pub use self::termina::TerminaBackend;
 
 mod alacritty;
-pub use self::alacritty::AlacrittyBackend;
+pub(crate) use self::alacritty::{
+    color_to_rgb, write_color, write_modifier_diff, write_underline_color, write_underline_style,
+};
+pub use self::alacritty::{AlacrittyBackend, DetectedCapabilities, RESET_SEQUENCE};
 
+mod sixel;
+
+mod capture;
+pub use self::capture::CaptureBackend;
 
 mod test;
 pub use self::test::TestBackend;
@@ -36,12 +47,42 @@ fn draw<'a, I>(&mut self, content: I) -> Result<(), io::Error>
     fn show_cursor(&mut self, kind: CursorKind) -> Result<(), io::Error>;
     /// Sets the cursor to the given position
     fn set_cursor(&mut self, x: u16, y: u16) -> Result<(), io::Error>;
+    /// Sets the color of the cursor, [`Color::Reset`] restores the terminal's default. The
+    /// default is restored by `restore`
+    fn set_cursor_color(&mut self, color: Color) -> Result<(), io::Error>;
     /// Clears the terminal
     fn clear(&mut self) -> Result<(), io::Error>;
+    /// Scrolls the rows of a region, see [`crate::buffer::Buffer::scroll_rows`]
+    fn scroll_region(&mut self, scroll: Scroll) -> Result<(), io::Error>;
     /// Gets the size of the terminal in cells
     fn size(&self) -> Result<Rect, io::Error>;
     /// Flushes the terminal buffer
     fn flush(&mut self) -> Result<(), io::Error>;
+    /// Sets the title of the terminal window. The title from before the first call is restored
+    /// by `restore`
+    fn set_title(&mut self, title: &str) -> Result<(), io::Error>;
     fn supports_true_color(&self) -> bool;
+    /// Whether `draw_images` shows images, with the kitty graphics protocol or sixel graphics.
+    fn supports_images(&self) -> bool;
+    /// Whether images are drawn into the cells under them instead of on top of them. Such
+    /// images are overwritten by the cells drawn after them, the terminal draws them again when
+    /// a cell under them changes and draws the cells under an image again when it is removed.
+    fn draws_images_into_cells(&self) -> bool {
+        false
+    }
+    /// Whether keys are encoded with the kitty keyboard protocol, so that a lone ESC byte is
+    /// never the Escape key.
+    fn supports_kitty_keyboard(&self) -> bool;
+    /// Replaces the images shown on top of the cells with `images`
+    fn draw_images(&mut self, images: &[Image]) -> Result<(), io::Error>;
     fn get_theme_mode(&self) -> Option<helix_view::theme::Mode>;
+    /// Handles a reply to a query which was read with the input, after the backend stopped
+    /// waiting for it. Backends which don't query the terminal ignore them.
+    fn handle_response(&mut self, _response: &TerminalResponse) {}
+}
+
+/// Removes control characters from a window title, they would end the escape sequence setting
+/// it early.
+fn sanitize_title(title: &str) -> String {
+    title.chars().filter(|ch| !ch.is_control()).collect()
 }
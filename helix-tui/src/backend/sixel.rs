@@ -0,0 +1,238 @@
+//! Images drawn with sixel graphics, for terminals without the kitty graphics protocol.
+//!
+//! Unlike kitty images, which are shown on top of the cells, sixel images are drawn into the
+//! cells they cover: they are drawn again when a cell under them changes, and the cells are
+//! drawn again when an image is removed, see [`crate::backend::Backend::draws_images_into_cells`].
+
+use std::fmt::Write as _;
+
+use crate::buffer::Crop;
+
+/// The size of a cell in pixels, for terminals which don't report it.
+pub(crate) const DEFAULT_CELL_SIZE: (u16, u16) = (10, 20);
+
+/// The number of levels of each channel of the colors images are quantized to.
+const LEVELS: u32 = 6;
+
+/// An image decoded to 8-bit RGBA pixels, row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Pixels {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Pixels {
+    /// Decodes a PNG image, `None` if it isn't a valid PNG.
+    pub fn decode_png(png: &[u8]) -> Option<Self> {
+        let mut decoder = png::Decoder::new(std::io::Cursor::new(png));
+        // Palettes, low bit depths and transparency chunks are expanded to 8-bit channels
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().ok()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).ok()?;
+        buffer.truncate(info.buffer_size());
+        let rgba = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer
+                .iter()
+                .flat_map(|&value| [value, value, value, 255])
+                .collect(),
+            png::ColorType::Indexed => return None,
+        };
+        Some(Self {
+            width: info.width,
+            height: info.height,
+            rgba,
+        })
+    }
+
+    /// The `crop` of the image, or the whole image, scaled to `width` by `height` pixels with
+    /// the nearest pixel.
+    pub fn scale(&self, crop: Option<Crop>, width: u32, height: u32) -> Self {
+        let crop = crop.unwrap_or(Crop {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        });
+        let crop_x = crop.x.min(self.width.saturating_sub(1)) as u64;
+        let crop_y = crop.y.min(self.height.saturating_sub(1)) as u64;
+        let crop_width = (crop.width as u64).clamp(1, self.width as u64 - crop_x);
+        let crop_height = (crop.height as u64).clamp(1, self.height as u64 - crop_y);
+
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height as u64 {
+            let source_y = crop_y + y * crop_height / height as u64;
+            for x in 0..width as u64 {
+                let source_x = crop_x + x * crop_width / width as u64;
+                let i = ((source_y * self.width as u64 + source_x) * 4) as usize;
+                rgba.extend_from_slice(&self.rgba[i..i + 4]);
+            }
+        }
+        Self {
+            width,
+            height,
+            rgba,
+        }
+    }
+}
+
+/// The color register of a pixel, `None` if it is mostly transparent.
+fn register(pixel: &[u8]) -> Option<u32> {
+    if pixel[3] < 128 {
+        return None;
+    }
+    let level = |channel: u8| (channel as u32 * LEVELS) / 256;
+    Some((level(pixel[0]) * LEVELS + level(pixel[1])) * LEVELS + level(pixel[2]))
+}
+
+/// Writes `count` repetitions of a sixel, with a repeat introducer for longer runs.
+fn write_run(out: &mut String, sixel: u8, count: usize) {
+    let sixel = (b'?' + sixel) as char;
+    if count > 3 {
+        write!(out, "!{count}{sixel}").unwrap();
+    } else {
+        out.extend(std::iter::repeat_n(sixel, count));
+    }
+}
+
+/// Encodes `pixels` as a sixel image. The colors are quantized to a 6x6x6 color cube and
+/// mostly transparent pixels leave the cells under them as they are.
+pub(crate) fn encode(pixels: &Pixels) -> String {
+    let (width, height) = (pixels.width as usize, pixels.height as usize);
+    let registers: Vec<Option<u32>> = pixels.rgba.chunks_exact(4).map(register).collect();
+
+    // Transparent background, the raster attributes set square pixels and the image size
+    let mut out = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+    let mut used = [false; (LEVELS * LEVELS * LEVELS) as usize];
+    for register in registers.iter().flatten() {
+        used[*register as usize] = true;
+    }
+    let percent = |level: u32| level * 100 / (LEVELS - 1);
+    for (register, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+        let register = register as u32;
+        let (r, g, b) = (register / 36, register / 6 % 6, register % 6);
+        write!(
+            out,
+            "#{register};2;{};{};{}",
+            percent(r),
+            percent(g),
+            percent(b)
+        )
+        .unwrap();
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut colors: Vec<u32> = rows
+            .clone()
+            .flat_map(|y| {
+                registers[y * width..(y + 1) * width]
+                    .iter()
+                    .flatten()
+                    .copied()
+            })
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for (i, color) in colors.into_iter().enumerate() {
+            // Each color of the band is drawn over the same pixels again
+            if i > 0 {
+                out.push('$');
+            }
+            write!(out, "#{color}").unwrap();
+            let mut run = (0, 0);
+            for x in 0..width {
+                let sixel = rows
+                    .clone()
+                    .filter(|y| registers[y * width + x] == Some(color))
+                    .fold(0, |sixel, y| sixel | 1 << (y - band));
+                if sixel != run.0 && run.1 > 0 {
+                    write_run(&mut out, run.0, run.1);
+                    run.1 = 0;
+                }
+                run = (sixel, run.1 + 1);
+            }
+            write_run(&mut out, run.0, run.1);
+        }
+        // No line feed after the last band, it could scroll the screen at the bottom
+        if band + 6 < height {
+            out.push('-');
+        }
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_quantizes_and_run_length_encodes() {
+        let red = [255, 0, 0, 255];
+        let clear = [0, 0, 0, 0];
+        let rgba: Vec<u8> = [red; 5].into_iter().chain([clear]).flatten().collect();
+        let pixels = Pixels {
+            width: 3,
+            height: 2,
+            rgba,
+        };
+        assert_eq!(
+            encode(&pixels),
+            "\x1bP0;1;0q\"1;1;3;2#180;2;100;0;0#180BB@\x1b\\"
+        );
+
+        let wide = Pixels {
+            width: 8,
+            height: 1,
+            rgba: [red; 8].into_iter().flatten().collect(),
+        };
+        assert!(encode(&wide).ends_with("#180!8@\x1b\\"));
+    }
+
+    #[test]
+    fn scale_crops_and_resizes() {
+        let pixel = |value: u8| [value, value, value, 255];
+        let pixels = Pixels {
+            width: 2,
+            height: 2,
+            rgba: [pixel(0), pixel(1), pixel(2), pixel(3)].concat(),
+        };
+        let scaled = pixels.scale(None, 4, 2);
+        assert_eq!(
+            scaled.rgba,
+            [
+                pixel(0),
+                pixel(0),
+                pixel(1),
+                pixel(1),
+                pixel(2),
+                pixel(2),
+                pixel(3),
+                pixel(3)
+            ]
+            .concat()
+        );
+        let cropped = pixels.scale(
+            Some(Crop {
+                x: 1,
+                y: 1,
+                width: 1,
+                height: 1,
+            }),
+            1,
+            1,
+        );
+        assert_eq!(cropped.rgba, pixel(3));
+    }
+}
@@ -14,9 +14,12 @@
     Event, OneBased, PlatformTerminal, Terminal as _, WindowSize,
 };
 
-use crate::{buffer::Cell, terminal::Config};
+use crate::{
+    buffer::{Cell, Image, Scroll},
+    terminal::Config,
+};
 
-use super::Backend;
+use super::{alacritty::write_cursor_color, sanitize_title, Backend};
 
 // These macros are helpers to set/unset modes like bracketed paste or enter/exit the alternate
 // screen.
@@ -76,6 +79,10 @@ pub struct TerminaBackend {
     capabilities: Capabilities,
     reset_cursor_command: String,
     is_synchronized_output_set: bool,
+    /// The last title set with `set_title`, the original title is restored on `restore`.
+    title: Option<String>,
+    /// Whether the cursor color was changed with `set_cursor_color`.
+    cursor_color: bool,
 }
 
 impl TerminaBackend {
@@ -112,6 +119,8 @@ pub fn new(config: Config) -> io::Result<Self> {
             capabilities,
             reset_cursor_command,
             is_synchronized_output_set: false,
+            title: None,
+            cursor_color: false,
         })
     }
 
@@ -412,6 +421,13 @@ fn reconfigure(&mut self, mut config: Config) -> io::Result<()> {
     fn restore(&mut self) -> io::Result<()> {
         self.disable_extensions()?;
         self.disable_mouse_capture()?;
+        if self.title.take().is_some() {
+            // Pop the title saved by `set_title`
+            write!(self.terminal, "\x1b[23;0t")?;
+        }
+        if std::mem::take(&mut self.cursor_color) {
+            write_cursor_color(&mut self.terminal, Color::Reset)?;
+        }
         write!(
             self.terminal,
             "{}{}{}{}",
@@ -555,6 +571,18 @@ fn clear(&mut self) -> io::Result<()> {
         self.flush()
     }
 
+    fn scroll_region(&mut self, scroll: Scroll) -> io::Result<()> {
+        self.start_synchronized_render()?;
+        // Set the scroll region (DECSTBM), scroll it and reset it to the whole screen
+        write!(self.terminal, "\x1b[{};{}r", scroll.top + 1, scroll.bottom)?;
+        if scroll.amount > 0 {
+            write!(self.terminal, "\x1b[{}S", scroll.amount)?;
+        } else {
+            write!(self.terminal, "\x1b[{}T", -scroll.amount)?;
+        }
+        write!(self.terminal, "\x1b[r")
+    }
+
     fn size(&self) -> io::Result<Rect> {
         let WindowSize { rows, cols, .. } = self.terminal.get_dimensions()?;
         Ok(Rect::new(0, 0, cols, rows))
@@ -564,10 +592,40 @@ fn flush(&mut self) -> io::Result<()> {
         self.terminal.flush()
     }
 
+    fn set_cursor_color(&mut self, color: Color) -> io::Result<()> {
+        self.cursor_color = color != Color::Reset;
+        write_cursor_color(&mut self.terminal, color)
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        if self.title.as_deref() == Some(title) {
+            return Ok(());
+        }
+        if self.title.is_none() {
+            // Save the original title on the terminal's title stack
+            write!(self.terminal, "\x1b[22;0t")?;
+        }
+        write!(self.terminal, "\x1b]2;{}\x1b\\", sanitize_title(title))?;
+        self.title = Some(title.to_string());
+        Ok(())
+    }
+
     fn supports_true_color(&self) -> bool {
         self.capabilities.true_color
     }
 
+    fn supports_images(&self) -> bool {
+        false
+    }
+
+    fn supports_kitty_keyboard(&self) -> bool {
+        self.capabilities.kitty_keyboard == KittyKeyboardSupport::Full
+    }
+
+    fn draw_images(&mut self, _images: &[Image]) -> Result<(), io::Error> {
+        Ok(())
+    }
+
     fn get_theme_mode(&self) -> Option<theme::Mode> {
         self.capabilities.theme_mode
     }
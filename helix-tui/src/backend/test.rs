@@ -1,10 +1,10 @@
 use crate::{
     backend::Backend,
-    buffer::{Buffer, Cell},
+    buffer::{Buffer, Cell, Image, Scroll},
     terminal::Config,
 };
 use helix_core::unicode::width::UnicodeWidthStr;
-use helix_view::graphics::{CursorKind, Rect};
+use helix_view::graphics::{Color, CursorKind, Rect};
 use std::{fmt::Write, io};
 
 /// A backend used for the integration tests.
@@ -149,6 +149,11 @@ fn clear(&mut self) -> Result<(), io::Error> {
         Ok(())
     }
 
+    fn scroll_region(&mut self, scroll: Scroll) -> Result<(), io::Error> {
+        self.buffer.scroll_rows(scroll);
+        Ok(())
+    }
+
     fn size(&self) -> Result<Rect, io::Error> {
         Ok(Rect::new(0, 0, self.width, self.height))
     }
@@ -157,10 +162,30 @@ fn flush(&mut self) -> Result<(), io::Error> {
         Ok(())
     }
 
+    fn set_cursor_color(&mut self, _color: Color) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn set_title(&mut self, _title: &str) -> Result<(), io::Error> {
+        Ok(())
+    }
+
     fn supports_true_color(&self) -> bool {
         false
     }
 
+    fn supports_images(&self) -> bool {
+        false
+    }
+
+    fn supports_kitty_keyboard(&self) -> bool {
+        false
+    }
+
+    fn draw_images(&mut self, _images: &[Image]) -> Result<(), io::Error> {
+        Ok(())
+    }
+
     fn get_theme_mode(&self) -> Option<helix_view::theme::Mode> {
         None
     }
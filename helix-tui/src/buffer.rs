@@ -1,13 +1,17 @@
 //! Contents of a terminal screen. A [Buffer] is made up of [Cell]s.
 use crate::text::{Span, Spans};
 use helix_core::unicode::width::UnicodeWidthStr;
-use std::cmp::min;
+use std::{
+    cmp::min,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
 use unicode_segmentation::UnicodeSegmentation;
 
-use helix_view::graphics::{Color, Modifier, Rect, Style, UnderlineStyle};
+use helix_view::graphics::{Color, Hyperlink, Modifier, Rect, Style, UnderlineStyle};
 
 /// One cell of the terminal. Contains one stylized grapheme.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Cell {
     pub symbol: String,
     pub fg: Color,
@@ -15,6 +19,7 @@ pub struct Cell {
     pub underline_color: Color,
     pub underline_style: UnderlineStyle,
     pub modifier: Modifier,
+    pub hyperlink: Option<Hyperlink>,
 }
 
 impl Cell {
@@ -58,6 +63,9 @@ pub fn set_style(&mut self, style: Style) -> &mut Cell {
         if let Some(style) = style.underline_style {
             self.underline_style = style;
         }
+        if let Some(hyperlink) = style.hyperlink {
+            self.hyperlink = Some(hyperlink);
+        }
 
         self.modifier.insert(style.add_modifier);
         self.modifier.remove(style.sub_modifier);
@@ -66,12 +74,15 @@ pub fn set_style(&mut self, style: Style) -> &mut Cell {
 
     /// Returns the current style of the cell
     pub fn style(&self) -> Style {
-        Style::default()
-            .fg(self.fg)
-            .bg(self.bg)
-            .underline_color(self.underline_color)
-            .underline_style(self.underline_style)
-            .add_modifier(self.modifier)
+        Style {
+            hyperlink: self.hyperlink,
+            ..Style::default()
+                .fg(self.fg)
+                .bg(self.bg)
+                .underline_color(self.underline_color)
+                .underline_style(self.underline_style)
+                .add_modifier(self.modifier)
+        }
     }
 
     /// Resets the cell to a default blank state
@@ -83,6 +94,7 @@ pub fn reset(&mut self) {
         self.underline_color = Color::Reset;
         self.underline_style = UnderlineStyle::Reset;
         self.modifier = Modifier::empty();
+        self.hyperlink = None;
     }
 }
 
@@ -95,10 +107,58 @@ fn default() -> Cell {
             underline_color: Color::Reset,
             underline_style: UnderlineStyle::Reset,
             modifier: Modifier::empty(),
+            hyperlink: None,
         }
     }
 }
 
+/// A vertical shift of the rows of a region, see [`Buffer::detect_scroll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scroll {
+    /// The first row of the region.
+    pub top: u16,
+    /// The row after the last row of the region.
+    pub bottom: u16,
+    /// The number of rows the content moves up, negative if it moves down.
+    pub amount: i16,
+}
+
+/// The symbol of the cells marked by [`Buffer::invalidate`], a noncharacter which is never
+/// drawn.
+const INVALID_SYMBOL: &str = "\u{FFFF}";
+
+/// The number of rows a scroll has to save from being redrawn to be used.
+const MIN_SCROLL_GAIN: usize = 3;
+
+/// An image shown by the terminal on top of the cells of `area`, see
+/// [`crate::backend::Backend::draw_images`].
+#[derive(Debug, Clone)]
+pub struct Image {
+    /// The image encoded as PNG.
+    pub png: Arc<[u8]>,
+    pub area: Rect,
+    /// The part of the image which is shown, the whole image if `None`.
+    pub crop: Option<Crop>,
+}
+
+/// Images are compared by identity, comparing their data on every frame would be too slow.
+impl PartialEq for Image {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.png, &other.png) && self.area == other.area && self.crop == other.crop
+    }
+}
+
+impl Eq for Image {}
+
+/// A rectangle of an image, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 /// A buffer that maps to the desired content of the terminal after the draw call
 ///
 /// No widget in the library interacts directly with the terminal. Instead each of them is required
@@ -134,6 +194,9 @@ pub struct Buffer {
     /// The content of the buffer. The length of this Vec should always be equal to area.width *
     /// area.height
     pub content: Vec<Cell>,
+    /// The images drawn on top of the content. Unlike cells they are not diffed, all images are
+    /// drawn again when any of them changes.
+    pub images: Vec<Image>,
 }
 
 impl Buffer {
@@ -148,7 +211,11 @@ pub fn empty(area: Rect) -> Buffer {
     pub fn filled(area: Rect, cell: &Cell) -> Buffer {
         let size = area.area();
         let content = vec![cell.clone(); size];
-        Buffer { area, content }
+        Buffer {
+            area,
+            content,
+            images: Vec::new(),
+        }
     }
 
     /// Returns a Buffer containing the given lines
@@ -608,11 +675,121 @@ pub fn resize(&mut self, area: Rect) {
         self.area = area;
     }
 
-    /// Reset all cells in the buffer
+    /// Marks the cells of `area` as unknown, so that the next diff against this buffer updates
+    /// them whatever their content. Used for the cells an image was drawn into.
+    pub fn invalidate(&mut self, area: Rect) {
+        let area = self.area.intersection(area);
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                self[(x, y)].set_symbol(INVALID_SYMBOL);
+            }
+        }
+    }
+
+    /// Reset all cells in the buffer and remove its images
     pub fn reset(&mut self) {
         for c in &mut self.content {
             c.reset();
         }
+        self.images.clear();
+    }
+
+    fn row_hashes(&self) -> Vec<u64> {
+        self.content
+            .chunks(self.area.width.max(1) as usize)
+            .map(|row| {
+                let mut hasher = DefaultHasher::new();
+                row.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect()
+    }
+
+    /// Detects a region of this buffer which `next` shows shifted vertically, for example
+    /// after scrolling a document by a few lines. Scrolling the region on the terminal and
+    /// drawing the difference is then cheaper than redrawing the shifted rows.
+    ///
+    /// Rows are compared by their hashes. A collision only makes the scroll less useful: the
+    /// difference to the scrolled buffer is drawn as usual.
+    pub fn detect_scroll(&self, next: &Buffer) -> Option<Scroll> {
+        if self.area != next.area || self.area.width == 0 {
+            return None;
+        }
+        let prev = self.row_hashes();
+        let next = next.row_hashes();
+        let height = prev.len();
+        let changed = prev.iter().zip(&next).filter(|(a, b)| a != b).count();
+        if changed < MIN_SCROLL_GAIN {
+            return None;
+        }
+
+        let mut best: Option<(usize, Scroll)> = None;
+        for shift in 1..=height / 2 {
+            for up in [true, false] {
+                // The row of this buffer which is shown at row `y` of `next` after the shift
+                let source = |y: usize| {
+                    if up {
+                        Some(y + shift).filter(|&source| source < height)
+                    } else {
+                        y.checked_sub(shift)
+                    }
+                };
+                let mut y = 0;
+                while y < height {
+                    if source(y).is_none_or(|source| prev[source] != next[y]) {
+                        y += 1;
+                        continue;
+                    }
+                    let start = y;
+                    let mut gain = 0;
+                    while y < height && source(y).is_some_and(|source| prev[source] == next[y]) {
+                        gain += usize::from(prev[y] != next[y]);
+                        y += 1;
+                    }
+                    // The rows exposed by the scroll are cleared and have to be redrawn
+                    let exposed = if up {
+                        y..y + shift
+                    } else {
+                        start - shift..start
+                    };
+                    let lost = exposed.filter(|&row| prev[row] == next[row]).count();
+                    let gain = gain.saturating_sub(lost);
+                    if gain >= MIN_SCROLL_GAIN && best.is_none_or(|(best, _)| gain > best) {
+                        let scroll = if up {
+                            Scroll {
+                                top: start as u16,
+                                bottom: (y + shift) as u16,
+                                amount: shift as i16,
+                            }
+                        } else {
+                            Scroll {
+                                top: (start - shift) as u16,
+                                bottom: y as u16,
+                                amount: -(shift as i16),
+                            }
+                        };
+                        best = Some((gain, scroll));
+                    }
+                }
+            }
+        }
+        best.map(|(_, scroll)| scroll)
+    }
+
+    /// Shifts the rows of a region like the terminal does when scrolling it. The exposed rows
+    /// are reset.
+    pub fn scroll_rows(&mut self, scroll: Scroll) {
+        let width = self.area.width as usize;
+        let region = &mut self.content[scroll.top as usize * width..scroll.bottom as usize * width];
+        let shift = (scroll.amount.unsigned_abs() as usize * width).min(region.len());
+        if scroll.amount > 0 {
+            region.rotate_left(shift);
+            let len = region.len();
+            region[len - shift..].iter_mut().for_each(Cell::reset);
+        } else {
+            region.rotate_right(shift);
+            region[..shift].iter_mut().for_each(Cell::reset);
+        }
     }
 
     /// Clear an area in the buffer
@@ -1020,4 +1197,48 @@ fn buffer_merge3() {
         };
         assert_eq!(one, merged);
     }
+
+    #[test]
+    fn detect_and_apply_scroll() {
+        // The document above the statusline scrolled down by two lines
+        let top = Buffer::with_lines(vec!["1", "2", "3", "4", "5", "6", "7", "8", "S"]);
+        let scrolled = Buffer::with_lines(vec!["3", "4", "5", "6", "7", "8", "9", "0", "S"]);
+
+        let scroll = top.detect_scroll(&scrolled).unwrap();
+        assert_eq!(
+            scroll,
+            Scroll {
+                top: 0,
+                bottom: 8,
+                amount: 2
+            }
+        );
+        let mut previous = top.clone();
+        previous.scroll_rows(scroll);
+        // Only the exposed rows are drawn
+        assert_eq!(
+            previous.diff(&scrolled),
+            vec![(0, 6, &cell("9")), (0, 7, &cell("0"))]
+        );
+
+        let scroll = scrolled.detect_scroll(&top).unwrap();
+        assert_eq!(
+            scroll,
+            Scroll {
+                top: 0,
+                bottom: 8,
+                amount: -2
+            }
+        );
+        let mut previous = scrolled.clone();
+        previous.scroll_rows(scroll);
+        assert_eq!(
+            previous.diff(&top),
+            vec![(0, 0, &cell("1")), (0, 1, &cell("2"))]
+        );
+
+        // Changing a few rows isn't a scroll
+        let edited = Buffer::with_lines(vec!["1", "x", "3", "4", "5", "6", "7", "8", "S"]);
+        assert_eq!(top.detect_scroll(&edited), None);
+    }
 }
@@ -0,0 +1,200 @@
+//! Serializes a rendered [`Buffer`] to text with ANSI escape sequences or to HTML, for example
+//! for screenshots in documentation or theme previews.
+
+use std::fmt::Write as _;
+
+use helix_core::unicode::width::UnicodeWidthStr;
+use helix_view::graphics::{Color, Modifier, UnderlineStyle};
+
+use crate::{
+    backend::{
+        color_to_rgb, write_color, write_modifier_diff, write_underline_color,
+        write_underline_style,
+    },
+    buffer::{Buffer, Cell},
+};
+
+/// The visible cells of row `y`: the cells following a multi-width symbol are hidden by it.
+fn row_cells(buffer: &Buffer, y: u16) -> impl Iterator<Item = &Cell> {
+    let width = buffer.area.width as usize;
+    let start = buffer.index_of(buffer.area.x, y);
+    let mut skip: usize = 0;
+    buffer.content[start..start + width]
+        .iter()
+        .filter(move |cell| {
+            let visible = skip == 0;
+            skip = std::cmp::max(skip, cell.symbol.width()).saturating_sub(1);
+            visible
+        })
+}
+
+/// The buffer as lines of text with SGR sequences for the colors and modifiers, as a terminal
+/// with true color and extended underline support shows it. Each line ends with a reset.
+pub fn to_ansi(buffer: &Buffer) -> String {
+    let mut out = Vec::new();
+    for y in buffer.area.top()..buffer.area.bottom() {
+        let mut fg = Color::Reset;
+        let mut bg = Color::Reset;
+        let mut underline_color = Color::Reset;
+        let mut underline_style = UnderlineStyle::Reset;
+        let mut modifier = Modifier::empty();
+        for cell in row_cells(buffer, y) {
+            // Writing to a `Vec` can't fail
+            if cell.modifier != modifier {
+                write_modifier_diff(&mut out, modifier, cell.modifier).unwrap();
+                modifier = cell.modifier;
+            }
+            if cell.fg != fg {
+                write_color(&mut out, cell.fg, false).unwrap();
+                fg = cell.fg;
+            }
+            if cell.bg != bg {
+                write_color(&mut out, cell.bg, true).unwrap();
+                bg = cell.bg;
+            }
+            if cell.underline_color != underline_color {
+                write_underline_color(&mut out, cell.underline_color).unwrap();
+                underline_color = cell.underline_color;
+            }
+            if cell.underline_style != underline_style {
+                write_underline_style(&mut out, cell.underline_style).unwrap();
+                underline_style = cell.underline_style;
+            }
+            out.extend_from_slice(cell.symbol.as_bytes());
+        }
+        out.extend_from_slice(b"\x1b[0m\n");
+    }
+    String::from_utf8(out).expect("cell symbols and escape sequences are valid UTF-8")
+}
+
+/// The buffer as a `<pre>` element with a `<span>` for each run of styled cells with the same
+/// style. Colors are inlined as CSS, [`Color::Reset`] is left to the page's own colors. The
+/// palette colors use xterm's defaults.
+pub fn to_html(buffer: &Buffer) -> String {
+    let mut html = String::from("<pre>");
+    for y in buffer.area.top()..buffer.area.bottom() {
+        let mut run_style = String::new();
+        for cell in row_cells(buffer, y) {
+            let style = css(cell);
+            if style != run_style {
+                if !run_style.is_empty() {
+                    html.push_str("</span>");
+                }
+                if !style.is_empty() {
+                    write!(html, "<span style=\"{style}\">").unwrap();
+                }
+                run_style = style;
+            }
+            for c in cell.symbol.chars() {
+                match c {
+                    '&' => html.push_str("&amp;"),
+                    '<' => html.push_str("&lt;"),
+                    '>' => html.push_str("&gt;"),
+                    c => html.push(c),
+                }
+            }
+        }
+        if !run_style.is_empty() {
+            html.push_str("</span>");
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n");
+    html
+}
+
+/// The inline CSS of a cell.
+fn css(cell: &Cell) -> String {
+    let hex = |color| color_to_rgb(color).map(|(r, g, b)| format!("#{r:02x}{g:02x}{b:02x}"));
+    let (mut fg, mut bg) = (hex(cell.fg), hex(cell.bg));
+    if cell.modifier.contains(Modifier::REVERSED) {
+        // The page's colors are unknown, the system colors stand in for reversed defaults
+        (fg, bg) = match (bg, fg) {
+            (None, None) => (Some("Canvas".to_string()), Some("CanvasText".to_string())),
+            (bg, fg) => (bg, fg),
+        };
+    }
+
+    let mut css = String::new();
+    if let Some(fg) = fg {
+        write!(css, "color:{fg};").unwrap();
+    }
+    if let Some(bg) = bg {
+        write!(css, "background-color:{bg};").unwrap();
+    }
+    if cell.modifier.contains(Modifier::BOLD) {
+        css.push_str("font-weight:bold;");
+    }
+    if cell.modifier.contains(Modifier::ITALIC) {
+        css.push_str("font-style:italic;");
+    }
+    if cell.modifier.intersects(Modifier::DIM | Modifier::HIDDEN) {
+        let opacity = if cell.modifier.contains(Modifier::HIDDEN) {
+            0.0
+        } else {
+            0.5
+        };
+        write!(css, "opacity:{opacity};").unwrap();
+    }
+    let underline = match cell.underline_style {
+        UnderlineStyle::Reset => None,
+        UnderlineStyle::Line => Some("solid"),
+        UnderlineStyle::Curl => Some("wavy"),
+        UnderlineStyle::Dotted => Some("dotted"),
+        UnderlineStyle::Dashed => Some("dashed"),
+        UnderlineStyle::DoubleLine => Some("double"),
+    };
+    let crossed_out = cell.modifier.contains(Modifier::CROSSED_OUT);
+    match (underline, crossed_out) {
+        (Some(_), true) => css.push_str("text-decoration-line:underline line-through;"),
+        (Some(_), false) => css.push_str("text-decoration-line:underline;"),
+        (None, true) => css.push_str("text-decoration-line:line-through;"),
+        (None, false) => (),
+    }
+    if let Some(underline) = underline {
+        write!(css, "text-decoration-style:{underline};").unwrap();
+        if let Some(color) = hex(cell.underline_color) {
+            write!(css, "text-decoration-color:{color};").unwrap();
+        }
+    }
+    css
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helix_view::graphics::{Rect, Style};
+
+    fn buffer() -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 2));
+        buffer.set_string(
+            0,
+            0,
+            "a<",
+            Style::default()
+                .fg(Color::Rgb(255, 0, 0))
+                .add_modifier(Modifier::BOLD),
+        );
+        buffer.set_string(0, 1, "漢b", Style::default().bg(Color::Blue));
+        buffer
+    }
+
+    #[test]
+    fn ansi() {
+        assert_eq!(
+            to_ansi(&buffer()),
+            "\x1b[1m\x1b[38;2;255;0;0ma<\x1b[22m\x1b[39m  \x1b[0m\n\
+             \x1b[44m漢b\x1b[49m \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(
+            to_html(&buffer()),
+            "<pre><span style=\"color:#ff0000;font-weight:bold;\">a&lt;</span>  \n\
+             <span style=\"background-color:#0000ee;\">漢b</span> \n\
+             </pre>\n"
+        );
+    }
+}
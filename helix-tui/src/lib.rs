@@ -1,5 +1,6 @@
 pub mod backend;
 pub mod buffer;
+pub mod export;
 pub mod layout;
 pub mod symbols;
 pub mod terminal;
@@ -2,9 +2,12 @@
 //! Frontend for [Backend]
 
 use crate::{backend::Backend, buffer::Buffer};
-use helix_view::editor::{Config as EditorConfig, KittyKeyboardProtocolConfig};
-use helix_view::graphics::{CursorKind, Rect};
-use std::io;
+use helix_view::editor::{ColorDepthConfig, Config as EditorConfig, KittyKeyboardProtocolConfig};
+use helix_view::graphics::{Color, CursorKind, Rect};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 /// UNSTABLE
@@ -25,6 +28,9 @@ pub struct Viewport {
 pub struct Config {
     pub enable_mouse_capture: bool,
     pub force_enable_extended_underlines: bool,
+    pub force_enable_true_color: bool,
+    pub color_depth: ColorDepthConfig,
+    pub hardware_scroll: bool,
     pub kitty_keyboard_protocol: KittyKeyboardProtocolConfig,
 }
 
@@ -33,6 +39,9 @@ fn from(config: &EditorConfig) -> Self {
         Self {
             enable_mouse_capture: config.mouse,
             force_enable_extended_underlines: config.undercurl,
+            force_enable_true_color: config.true_color,
+            color_depth: config.color_depth,
+            hardware_scroll: config.hardware_scroll,
             kitty_keyboard_protocol: config.kitty_keyboard_protocol,
         }
     }
@@ -69,8 +78,15 @@ pub struct Terminal<B>
     current: usize,
     /// Kind of cursor (hidden or others)
     cursor_kind: CursorKind,
+    /// Color of the cursor, [`Color::Reset`] for the terminal's default
+    cursor_color: Color,
     /// Viewport
     viewport: Viewport,
+    /// Whether vertical shifts between frames are drawn by scrolling the terminal, see
+    /// [`Buffer::detect_scroll`].
+    hardware_scroll: bool,
+    /// How long the last [`Terminal::draw`] took to encode the changes and to flush them.
+    draw_timings: (Duration, Duration),
 }
 
 /// Default terminal size: 80 columns, 24 lines
@@ -110,7 +126,10 @@ pub fn with_options(backend: B, options: TerminalOptions) -> io::Result<Terminal
             ],
             current: 0,
             cursor_kind: CursorKind::Block,
+            cursor_color: Color::Reset,
             viewport: options.viewport,
+            hardware_scroll: false,
+            draw_timings: (Duration::ZERO, Duration::ZERO),
         })
     }
 
@@ -119,10 +138,13 @@ pub fn claim(&mut self) -> io::Result<()> {
     }
 
     pub fn reconfigure(&mut self, config: Config) -> io::Result<()> {
+        self.hardware_scroll = config.hardware_scroll;
         self.backend.reconfigure(config)
     }
 
     pub fn restore(&mut self) -> io::Result<()> {
+        // The backend restores the terminal's cursor color
+        self.cursor_color = Color::Reset;
         self.backend.restore()
     }
 
@@ -149,10 +171,46 @@ pub fn backend_mut(&mut self) -> &mut B {
     /// Obtains a difference between the previous and the current buffer and passes it to the
     /// current backend for drawing.
     pub fn flush(&mut self) -> io::Result<()> {
+        let images_in_cells = self.backend.draws_images_into_cells();
+        // Scrolling would move images drawn into the cells along with them
+        let has_images_in_cells =
+            images_in_cells && self.buffers.iter().any(|buffer| !buffer.images.is_empty());
+        if self.hardware_scroll && !has_images_in_cells {
+            let previous_buffer = &self.buffers[1 - self.current];
+            if let Some(scroll) = previous_buffer.detect_scroll(&self.buffers[self.current]) {
+                self.backend.scroll_region(scroll)?;
+                // Keep the previous buffer in sync with the terminal, so that only the rows
+                // which changed after scrolling are drawn
+                self.buffers[1 - self.current].scroll_rows(scroll);
+            }
+        }
+
+        let images_changed = self.buffers[0].images != self.buffers[1].images;
+        if images_in_cells && images_changed {
+            // The cells under the previous images are drawn again, over the images
+            let previous_buffer = &mut self.buffers[1 - self.current];
+            for image in std::mem::take(&mut previous_buffer.images) {
+                previous_buffer.invalidate(image.area);
+            }
+        }
+
         let previous_buffer = &self.buffers[1 - self.current];
         let current_buffer = &self.buffers[self.current];
         let updates = previous_buffer.diff(current_buffer);
-        self.backend.draw(updates.into_iter())
+        // Images drawn into the cells are drawn again over the cells updated under them
+        let images_overwritten = images_in_cells
+            && updates.iter().any(|(x, y, _)| {
+                let cell = Rect::new(*x, *y, 1, 1);
+                current_buffer
+                    .images
+                    .iter()
+                    .any(|image| image.area.intersects(cell))
+            });
+        self.backend.draw(updates.into_iter())?;
+        if images_changed || images_overwritten {
+            self.backend.draw_images(&current_buffer.images)?;
+        }
+        Ok(())
     }
 
     /// Updates the Terminal so that internal buffers match the requested size. Requested size will
@@ -193,6 +251,7 @@ pub fn draw(
         // let cursor_position = frame.cursor_position;
 
         // Draw to stdout
+        let start = Instant::now();
         self.flush()?;
 
         if let Some((x, y)) = cursor_position {
@@ -209,10 +268,18 @@ pub fn draw(
         self.current = 1 - self.current;
 
         // Flush
+        let flush_start = Instant::now();
         self.backend.flush()?;
+        self.draw_timings = (flush_start - start, flush_start.elapsed());
         Ok(())
     }
 
+    /// How long the last [`Terminal::draw`] took to diff the frame against the previous one and
+    /// encode the changes, and to flush them to the terminal.
+    pub fn draw_timings(&self) -> (Duration, Duration) {
+        self.draw_timings
+    }
+
     #[inline]
     pub fn cursor_kind(&self) -> CursorKind {
         self.cursor_kind
@@ -230,6 +297,15 @@ pub fn show_cursor(&mut self, kind: CursorKind) -> io::Result<()> {
         Ok(())
     }
 
+    /// Sets the color of the cursor, only sent to the terminal when it changed.
+    pub fn set_cursor_color(&mut self, color: Color) -> io::Result<()> {
+        if color != self.cursor_color {
+            self.backend.set_cursor_color(color)?;
+            self.cursor_color = color;
+        }
+        Ok(())
+    }
+
     pub fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
         self.backend.set_cursor(x, y)
     }
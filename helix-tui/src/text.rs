@@ -138,6 +138,7 @@ pub fn width(&self) -> usize {
     ///                 underline_style: None,
     ///                 add_modifier: Modifier::empty(),
     ///                 sub_modifier: Modifier::empty(),
+    ///                 hyperlink: None,
     ///             },
     ///         },
     ///         StyledGrapheme {
@@ -149,6 +150,7 @@ pub fn width(&self) -> usize {
     ///                 underline_style: None,
     ///                 add_modifier: Modifier::empty(),
     ///                 sub_modifier: Modifier::empty(),
+    ///                 hyperlink: None,
     ///             },
     ///         },
     ///         StyledGrapheme {
@@ -160,6 +162,7 @@ pub fn width(&self) -> usize {
     ///                 underline_style: None,
     ///                 add_modifier: Modifier::empty(),
     ///                 sub_modifier: Modifier::empty(),
+    ///                 hyperlink: None,
     ///             },
     ///         },
     ///         StyledGrapheme {
@@ -171,6 +174,7 @@ pub fn width(&self) -> usize {
     ///                 underline_style: None,
     ///                 add_modifier: Modifier::empty(),
     ///                 sub_modifier: Modifier::empty(),
+    ///                 hyperlink: None,
     ///             },
     ///         },
     ///     ],
@@ -1,7 +1,8 @@
 use helix_tui::{
-    backend::{Backend, TestBackend},
+    backend::{Backend, CaptureBackend, TestBackend},
     Terminal,
 };
+use helix_view::graphics::{Color, CursorKind, Modifier, Style};
 
 #[test]
 fn terminal_buffer_size_should_not_be_limited() {
@@ -12,6 +13,35 @@ fn terminal_buffer_size_should_not_be_limited() {
     assert_eq!(size.height, 400);
 }
 
+#[test]
+fn capture_backend_records_the_screen() {
+    let mut terminal = Terminal::new(CaptureBackend::new(12, 3)).unwrap();
+    let buffer = terminal.current_buffer_mut();
+    buffer.set_string(0, 0, "fn main", Style::default().fg(Color::Rgb(1, 2, 3)));
+    buffer.set_string(2, 2, "NOR", Style::default().add_modifier(Modifier::BOLD));
+    terminal.draw(Some((3, 0)), CursorKind::Bar).unwrap();
+
+    let backend = terminal.backend();
+    assert_eq!(backend.contents(), "fn main\n\n  NOR");
+    assert_eq!(backend.find("NOR"), Some((2, 2)));
+    assert_eq!(backend.fg(0, 0), Some(Color::Rgb(1, 2, 3)));
+    assert_eq!(backend.fg(7, 0), Some(Color::Reset));
+    assert!(backend
+        .style(2, 2)
+        .unwrap()
+        .add_modifier
+        .contains(Modifier::BOLD));
+    assert_eq!(backend.cursor(), Some((3, 0)));
+
+    // Only the changed cells are drawn by the next frame
+    terminal
+        .current_buffer_mut()
+        .set_string(0, 0, "fn", Style::default());
+    terminal.draw(None, CursorKind::Hidden).unwrap();
+    assert_eq!(terminal.backend().contents(), "fn\n\n");
+    assert_eq!(terminal.backend().cursor(), None);
+}
+
 // #[test]
 // fn terminal_draw_returns_the_completed_frame() -> Result<(), Box<dyn Error>> {
 //     let backend = TestBackend::new(10, 10);
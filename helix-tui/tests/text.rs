@@ -76,6 +76,7 @@ fn styled_graphemes() {
                     underline_style: None,
                     add_modifier: Modifier::empty(),
                     sub_modifier: Modifier::empty(),
+                    hyperlink: None,
                 },
             },
             StyledGrapheme {
@@ -87,6 +88,7 @@ fn styled_graphemes() {
                     underline_style: None,
                     add_modifier: Modifier::empty(),
                     sub_modifier: Modifier::empty(),
+                    hyperlink: None,
                 },
             },
             StyledGrapheme {
@@ -98,6 +100,7 @@ fn styled_graphemes() {
                     underline_style: None,
                     add_modifier: Modifier::empty(),
                     sub_modifier: Modifier::empty(),
+                    hyperlink: None,
                 },
             },
             StyledGrapheme {
@@ -109,6 +112,7 @@ fn styled_graphemes() {
                     underline_style: None,
                     add_modifier: Modifier::empty(),
                     sub_modifier: Modifier::empty(),
+                    hyperlink: None,
                 },
             },
         ],
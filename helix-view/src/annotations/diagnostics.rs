@@ -98,6 +98,7 @@ pub fn text_fmt(&self, anchor_col: u16, width: u16) -> TextFormat {
             tab_width: 4,
             max_wrap: self.max_wrap.min(width / 4),
             max_indent_retain: 0,
+            continuation_indent: 0,
             wrap_indicator: "".into(),
             wrap_indicator_highlight: None,
             viewport_width: width,
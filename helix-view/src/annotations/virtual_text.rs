@@ -0,0 +1,133 @@
+//! Virtual text attached to document lines.
+//!
+//! Components which want to show text after the end of a line (blame, end-of-line
+//! diagnostics) or on virtual lines below it (code lens) set it with
+//! [`Document::set_virtual_text`] instead of rendering it themselves. The anchors are mapped
+//! through edits and the text is always attached to the *document* line of its anchor: it is
+//! placed after the last visual line of a soft wrapped line and scrolls horizontally with the
+//! text.
+
+use helix_core::line_ending::line_end_char_index;
+use helix_core::text_annotations::LineAnnotation;
+use helix_core::{Position, RopeSlice};
+
+use crate::Document;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualTextPlacement {
+    /// After the end of the line, following any other end-of-line text.
+    EndOfLine,
+    /// On virtual lines below the line. Each line of the text takes up one virtual line.
+    Below,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualText {
+    /// The char the text is anchored to. The text is shown on the line containing it.
+    pub char_idx: usize,
+    pub placement: VirtualTextPlacement,
+    pub text: String,
+    /// The theme scope used to style the text, for example `ui.virtual.blame`.
+    pub scope: String,
+    /// The component which set this text, see [`Document::set_virtual_text`].
+    pub(crate) source: &'static str,
+}
+
+impl VirtualText {
+    pub fn new(
+        char_idx: usize,
+        placement: VirtualTextPlacement,
+        text: impl Into<String>,
+        scope: impl Into<String>,
+    ) -> Self {
+        Self {
+            char_idx,
+            placement,
+            text: text.into(),
+            scope: scope.into(),
+            source: "",
+        }
+    }
+
+    /// The number of virtual lines this text takes up.
+    pub fn height(&self) -> usize {
+        match self.placement {
+            VirtualTextPlacement::EndOfLine => 0,
+            VirtualTextPlacement::Below => self.text.lines().count().max(1),
+        }
+    }
+}
+
+/// Returns the index of the first virtual text shown on the line containing `char_idx`.
+pub fn virtual_text_at_line(
+    text: RopeSlice,
+    virtual_text: &[VirtualText],
+    char_idx: usize,
+) -> usize {
+    let line = text.char_to_line(char_idx.min(text.len_chars()));
+    let line_start = text.line_to_char(line);
+    virtual_text.partition_point(|vt| vt.char_idx < line_start)
+}
+
+/// Returns the end of the line containing `char_idx`: the char index of its line ending, or
+/// the end of the text for the last line.
+pub fn line_end(text: RopeSlice, char_idx: usize) -> usize {
+    let line = text.char_to_line(char_idx.min(text.len_chars()));
+    line_end_char_index(&text, line)
+}
+
+/// Reserves the virtual lines for [`VirtualTextPlacement::Below`] text.
+pub struct VirtualTextLines<'a> {
+    text: RopeSlice<'a>,
+    virtual_text: &'a [VirtualText],
+    idx: usize,
+}
+
+impl<'a> VirtualTextLines<'a> {
+    pub(crate) fn new(doc: &'a Document) -> Box<dyn LineAnnotation + 'a> {
+        Box::new(VirtualTextLines {
+            text: doc.text().slice(..),
+            virtual_text: doc.virtual_text(),
+            idx: 0,
+        })
+    }
+}
+
+impl LineAnnotation for VirtualTextLines<'_> {
+    fn reset_pos(&mut self, char_idx: usize) -> usize {
+        self.idx = virtual_text_at_line(self.text, self.virtual_text, char_idx);
+        usize::MAX
+    }
+
+    fn insert_virtual_lines(
+        &mut self,
+        line_end_char_idx: usize,
+        _line_end_visual_pos: Position,
+        doc_line: usize,
+    ) -> Position {
+        // Soft wrapped lines end before the line ending, only reserve space once the
+        // whole document line has been laid out.
+        let last_line = doc_line + 1 >= self.text.len_lines();
+        let next_line_start = if last_line {
+            self.text.len_chars()
+        } else {
+            self.text.line_to_char(doc_line + 1)
+        };
+        if line_end_char_idx < next_line_start {
+            return Position::new(0, 0);
+        }
+
+        let line_start = self.text.line_to_char(doc_line);
+        let mut height = 0;
+        while let Some(vt) = self.virtual_text.get(self.idx) {
+            if vt.char_idx >= next_line_start && !last_line {
+                break;
+            }
+            if vt.char_idx >= line_start {
+                height += vt.height();
+            }
+            self.idx += 1;
+        }
+        Position::new(height, 0)
+    }
+}
@@ -2,7 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::sync::OnceLock;
 use thiserror::Error;
+use tokio::sync::mpsc::UnboundedSender;
 
 #[derive(Clone, Copy)]
 pub enum ClipboardType {
@@ -31,6 +33,15 @@ pub enum ClipboardError {
 
 type Result<T> = std::result::Result<T, ClipboardError>;
 
+static TERMCODE_SINK: OnceLock<UnboundedSender<(ClipboardType, String)>> = OnceLock::new();
+
+/// Hands the copies of the `termcode` provider to `sink` instead of writing the OSC 52 sequence
+/// to stdout directly, where it could end up in the middle of a frame. The receiver is expected
+/// to forward them to the terminal backend.
+pub fn set_termcode_sink(sink: UnboundedSender<(ClipboardType, String)>) {
+    let _ = TERMCODE_SINK.set(sink);
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub use external::ClipboardProvider;
 #[cfg(target_arch = "wasm32")]
@@ -293,6 +304,11 @@ fn paste_to_builtin(
                 #[cfg(feature = "term")]
                 Self::Termcode => {
                     use std::io::Write;
+                    if let Some(sink) = super::TERMCODE_SINK.get() {
+                        if sink.send((clipboard_type, content.to_owned())).is_ok() {
+                            return Ok(());
+                        }
+                    }
                     use termina::escape::osc::{self, Osc};
                     let selection = match clipboard_type {
                         ClipboardType::Clipboard => osc::Selection::CLIPBOARD,
@@ -24,7 +24,7 @@
 use serde::Serialize;
 use std::borrow::Cow;
 use std::cell::Cell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
 use std::future::Future;
 use std::io;
@@ -44,8 +44,9 @@
 };
 
 use crate::{
-    editor::Config,
-    events::{DocumentDidChange, SelectionDidChange},
+    annotations::virtual_text::VirtualText,
+    editor::{Config, FsyncPolicy, GutterType, WriteMethod},
+    events::{DocumentDidChange, DocumentEditRejected, SelectionDidChange},
     expansion,
     view::ViewPosition,
     DocumentId, Editor, Theme, View, ViewId,
@@ -142,6 +143,9 @@ pub struct Document {
     pub(crate) id: DocumentId,
     text: Rope,
     selections: HashMap<ViewId, Selection>,
+    /// Selections saved by name, mapped through the document's changes. See
+    /// [`crate::named_selections`].
+    pub named_selections: BTreeMap<String, Selection>,
     view_data: HashMap<ViewId, ViewData>,
     pub active_snippet: Option<ActiveSnippet>,
 
@@ -150,6 +154,8 @@ pub struct Document {
     /// To know if they're up-to-date, check the `id` field in `DocumentInlayHints`.
     pub(crate) inlay_hints: HashMap<ViewId, DocumentInlayHints>,
     pub(crate) jump_labels: HashMap<ViewId, Vec<Overlay>>,
+    /// End-of-line and inter-line virtual text, sorted by anchor.
+    virtual_text: Vec<VirtualText>,
     /// Set to `true` when the document is updated, reset to `false` on the next inlay hints
     /// update from the LSP
     pub inlay_hints_oudated: bool,
@@ -171,6 +177,8 @@ pub struct Document {
     pub syntax: Option<Syntax>,
     /// Corresponding language scope name. Usually `source.<lang>`.
     pub language: Option<Arc<LanguageConfiguration>>,
+    /// The gutter layout of the language, overriding the `gutters` layout of the views.
+    pub(crate) gutter_layout: Option<Vec<GutterType>>,
 
     /// Pending changes since last history commit.
     changes: ChangeSet,
@@ -195,8 +203,18 @@ pub struct Document {
 
     pub(crate) diagnostics: Vec<Diagnostic>,
     pub(crate) language_servers: HashMap<LanguageServerName, Arc<Client>>,
+    /// Language servers detached from this document with `:lsp-disable`, which aren't started
+    /// or attached for it until they are enabled again.
+    pub(crate) disabled_language_servers: HashSet<LanguageServerName>,
 
     diff_handle: Option<DiffHandle>,
+    /// The number of bytes of the file loaded so far while `:follow` watches it for appends.
+    followed_len: Option<u64>,
+    /// Whether the document's path is a named pipe, which is read continuously instead of
+    /// being loaded once.
+    fifo: bool,
+    /// The file of a document opened before its text was read, see [`Document::is_loading`].
+    pending_load: Option<PendingLoad>,
     version_control_head: Option<Arc<ArcSwap<Box<str>>>>,
 
     // when document was used for most-recent-used buffer picker
@@ -212,6 +230,11 @@ pub struct Document {
     // large refactor that would make `&mut Editor` available on the `DocumentDidChange` event.
     pub color_swatch_controller: TaskController,
     pub pull_diagnostic_controller: TaskController,
+    /// Controls the task loading appended content while the file is followed with `:follow`
+    /// or read from a named pipe.
+    pub follow_controller: TaskController,
+    /// Controls the task reading a large file in the background, see [`Document::is_loading`].
+    pub load_controller: TaskController,
 
     // NOTE: this field should eventually go away - we should use the Editor's syn_loader instead
     // of storing a copy on every doc. Then we can remove the surrounding `Arc` and use the
@@ -519,6 +542,76 @@ pub fn from_reader<R: std::io::Read + ?Sized>(
     Ok((rope, encoding, has_bom))
 }
 
+/// Whether `path` is a named pipe (FIFO).
+#[cfg(unix)]
+pub fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_path: &Path) -> bool {
+    false
+}
+
+/// A large file opened by [`Document::open`] whose text is read in the background, see
+/// `editor.large-file-threshold`.
+#[derive(Debug, Clone)]
+pub struct PendingLoad {
+    pub path: PathBuf,
+    /// The encoding the file was opened with, otherwise it is detected.
+    pub encoding: Option<&'static Encoding>,
+    /// Whether the syntax tree is parsed, see `editor.syntax-max-file-size`.
+    pub syntax: bool,
+}
+
+/// The text of a [`PendingLoad`], see [`Document::finish_loading`].
+pub struct LoadedText {
+    text: Rope,
+    encoding: &'static Encoding,
+    has_bom: bool,
+    language: Option<Arc<LanguageConfiguration>>,
+    syntax: Option<Syntax>,
+}
+
+impl PendingLoad {
+    /// Decodes the text of the file from `reader`, detects its language and parses its syntax
+    /// tree. This takes a while for large files, it is meant to run on a blocking thread.
+    pub fn read<R: std::io::Read + ?Sized>(
+        &self,
+        reader: &mut R,
+        loader: &syntax::Loader,
+    ) -> Result<LoadedText, io::Error> {
+        let (text, encoding, has_bom) = from_reader(reader, self.encoding)?;
+        let language = loader
+            .language_for_file(Some(&self.path), text.slice(..))
+            .map(|language| loader.language(language).config().clone());
+        let syntax = language
+            .as_ref()
+            .filter(|_| self.syntax)
+            .and_then(|config| {
+                Syntax::new(text.slice(..), config.language(), loader)
+                    .map_err(|err| {
+                        if err != syntax::HighlighterError::NoRootConfig {
+                            log::warn!(
+                                "Error building syntax for '{}': {err}",
+                                self.path.display()
+                            );
+                        }
+                    })
+                    .ok()
+            });
+        Ok(LoadedText {
+            text,
+            encoding,
+            has_bom,
+            language,
+            syntax,
+        })
+    }
+}
+
 pub fn read_to_string<R: std::io::Read + ?Sized>(
     reader: &mut R,
     encoding: Option<&'static Encoding>,
@@ -671,6 +764,63 @@ pub async fn to_writer<'a, W: tokio::io::AsyncWriteExt + Unpin + ?Sized>(
     Ok(())
 }
 
+/// Writes `rope` to a temporary file next to `path` and renames it over `path`, so that `path`
+/// has either its old or its new contents if the editor is interrupted. The temporary file
+/// takes over the permissions and ownership of `path`.
+async fn write_renamed(
+    path: &Path,
+    encoding_with_bom_info: (&'static Encoding, bool),
+    rope: &Rope,
+    fsync: FsyncPolicy,
+) -> Result<(), Error> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("{} has no parent directory", path.display()))?
+        .to_path_buf();
+    let prefix = path.file_name().unwrap_or_default().to_os_string();
+    let temp_file = tokio::task::spawn_blocking(move || {
+        tempfile::Builder::new()
+            .prefix(&prefix)
+            .suffix(".tmp")
+            .tempfile_in(dir)
+    })
+    .await??;
+    // The temporary file is removed when `temp_path` is dropped, unless it was renamed
+    let (file, temp_path) = temp_file.into_parts();
+
+    let mut dst = tokio::fs::File::from_std(file);
+    to_writer(&mut dst, encoding_with_bom_info, rope).await?;
+    if fsync != FsyncPolicy::None {
+        dst.sync_all().await?;
+    }
+    drop(dst);
+
+    let target = path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<(), Error> {
+        copy_metadata(&target, &temp_path)?;
+        temp_path.persist(&target)?;
+        Ok(())
+    })
+    .await??;
+
+    if fsync == FsyncPolicy::Directory {
+        sync_parent_dir(path).await?;
+    }
+    Ok(())
+}
+
+/// Flushes the directory of `path`, which makes files created in or renamed into it durable.
+async fn sync_parent_dir(path: &Path) -> Result<(), Error> {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        tokio::fs::File::open(dir).await?.sync_all().await?;
+    }
+    // Directories can't be opened as files on Windows, where renames are flushed with the file
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
 fn take_with<T, F>(mut_ref: &mut T, f: F)
 where
     T: Default,
@@ -703,6 +853,7 @@ pub fn from(
             has_bom,
             text,
             selections: HashMap::default(),
+            named_selections: BTreeMap::new(),
             inlay_hints: HashMap::default(),
             inlay_hints_oudated: false,
             view_data: Default::default(),
@@ -712,6 +863,7 @@ pub fn from(
             restore_cursor: false,
             syntax: None,
             language: None,
+            gutter_layout: None,
             changes,
             old_state,
             diagnostics: Vec::new(),
@@ -722,17 +874,24 @@ pub fn from(
             last_saved_revision: 0,
             modified_since_accessed: false,
             language_servers: HashMap::new(),
+            disabled_language_servers: HashSet::new(),
             diff_handle: None,
+            followed_len: None,
+            fifo: false,
+            pending_load: None,
             config,
             version_control_head: None,
             focused_at: std::time::Instant::now(),
             readonly: false,
             jump_labels: HashMap::new(),
+            virtual_text: Vec::new(),
             color_swatches: None,
             color_swatch_controller: TaskController::new(),
             syn_loader,
             previous_diagnostic_id: None,
             pull_diagnostic_controller: TaskController::new(),
+            follow_controller: TaskController::new(),
+            load_controller: TaskController::new(),
         }
     }
 
@@ -755,8 +914,10 @@ pub fn open(
         config: Arc<dyn DynAccess<Config>>,
         syn_loader: Arc<ArcSwap<syntax::Loader>>,
     ) -> Result<Self, DocumentOpenError> {
-        // If the path is not a regular file (e.g.: /dev/random) it should not be opened.
-        if path.metadata().is_ok_and(|metadata| !metadata.is_file()) {
+        // If the path is not a regular file (e.g.: /dev/random) it should not be opened. Named
+        // pipes are the exception: they start out empty and are read as data arrives.
+        let fifo = is_fifo(path);
+        if !fifo && path.metadata().is_ok_and(|metadata| !metadata.is_file()) {
             return Err(DocumentOpenError::IrregularFile);
         }
 
@@ -767,8 +928,32 @@ pub fn open(
         };
         encoding = encoding.or(editor_config.encoding);
 
+        // Large files are read in the background, the document is empty until then
+        let size = path.metadata().map_or(0, |metadata| metadata.len());
+        if !fifo && size > config.load().large_file_threshold {
+            let pending_load = PendingLoad {
+                path: path.to_path_buf(),
+                encoding,
+                syntax: config
+                    .load()
+                    .syntax_max_file_size
+                    .is_none_or(|max| size <= max),
+            };
+            let line_ending: LineEnding = editor_config
+                .line_ending
+                .unwrap_or_else(|| config.load().default_line_ending.into());
+            let text = Rope::from(line_ending.as_str());
+            let encoding = encoding.unwrap_or(encoding::UTF_8);
+            let mut doc = Self::from(text, Some((encoding, false)), config, syn_loader);
+            doc.set_path(Some(path));
+            doc.editor_config = editor_config;
+            doc.readonly = true;
+            doc.pending_load = Some(pending_load);
+            return Ok(doc);
+        }
+
         // Open the file if it exists, otherwise assume it is a new file (and thus empty).
-        let (rope, encoding, has_bom) = if path.exists() {
+        let (rope, encoding, has_bom) = if path.exists() && !fifo {
             let mut file = std::fs::File::open(path)?;
             from_reader(&mut file, encoding)?
         } else {
@@ -790,6 +975,10 @@ pub fn open(
 
         doc.editor_config = editor_config;
         doc.detect_indent_and_line_ending();
+        if fifo {
+            doc.fifo = true;
+            doc.readonly = true;
+        }
 
         Ok(doc)
     }
@@ -815,16 +1004,17 @@ pub fn format(
         &self,
         editor: &Editor,
     ) -> Option<BoxFuture<'static, Result<Transaction, FormatterError>>> {
-        if let Some((fmt_cmd, fmt_args)) = self
+        if let Some((fmt_cmd, formatter)) = self
             .language_config()
             .and_then(|c| c.formatter.as_ref())
             .and_then(|formatter| {
-                Some((
-                    helix_stdx::env::which(&formatter.command).ok()?,
-                    &formatter.args,
-                ))
+                let command = expansion::expand(editor, Token::expand(&formatter.command))
+                    .map_err(|err| log::error!("Failed to expand formatter command: {err}"))
+                    .ok()?;
+                Some((helix_stdx::env::which(&*command).ok()?, formatter))
             })
         {
+            let fmt_args = &formatter.args;
             log::debug!(
                 "formatting '{}' with command '{}', args {fmt_args:?}",
                 self.display_name(),
@@ -839,12 +1029,25 @@ pub fn format(
                 process.current_dir(doc_dir);
             }
 
-            let args = match fmt_args
+            // Each argument is expanded on its own and passed to the formatter as is, without
+            // going through a shell.
+            let expand = |content: &String| {
+                expansion::expand(editor, Token::expand(content)).map(Cow::into_owned)
+            };
+            let expanded = fmt_args
                 .iter()
-                .map(|content| expansion::expand(editor, Token::expand(content)))
+                .map(expand)
                 .collect::<Result<Vec<_>, _>>()
-            {
-                Ok(args) => args,
+                .and_then(|args| {
+                    let environment = formatter
+                        .environment
+                        .iter()
+                        .map(|(key, value)| Ok((key, expand(value)?)))
+                        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+                    Ok((args, environment))
+                });
+            let (args, environment) = match expanded {
+                Ok(expanded) => expanded,
                 Err(err) => {
                     log::error!("Failed to expand formatter arguments: {err}");
                     return None;
@@ -852,7 +1055,8 @@ pub fn format(
             };
 
             process
-                .args(args.iter().map(AsRef::as_ref))
+                .args(&args)
+                .envs(environment)
                 .stdin(Stdio::piped())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped());
@@ -965,6 +1169,10 @@ impl Future<Output = Result<DocumentSavedEvent, anyhow::Error>> + 'static + Send
             self.path().map(|path| path.to_string_lossy())
         );
 
+        if self.is_loading() {
+            bail!("can't save file, it is still loading");
+        }
+
         // we clone and move text + path into the future so that we asynchronously save the current
         // state without blocking any further edits.
         let text = self.text().clone();
@@ -985,7 +1193,13 @@ impl Future<Output = Result<DocumentSavedEvent, anyhow::Error>> + 'static + Send
         // mark changes up to now as saved
         let current_rev = self.get_current_revision();
         let doc_id = self.id();
-        let atomic_save = self.config.load().atomic_save;
+        let config = self.config.load();
+        let write_method = if config.atomic_save {
+            config.write_method
+        } else {
+            WriteMethod::InPlace
+        };
+        let fsync = config.fsync;
 
         let encoding_with_bom_info = (self.encoding, self.has_bom);
         let last_saved_time = self.last_saved_time;
@@ -1041,86 +1255,97 @@ impl Future<Output = Result<DocumentSavedEvent, anyhow::Error>> + 'static + Send
                 Err(err) => return Err(err.into()),
             };
             let must_copy = is_hardlink || is_symlink;
-            let backup = if path.exists() && atomic_save {
-                let path_ = write_path.clone();
-                // hacks: we use tempfile to handle the complex task of creating
-                // non clobbered temporary path for us we don't want
-                // the whole automatically delete path on drop thing
-                // since the path doesn't exist yet, we just want
-                // the path
-                tokio::task::spawn_blocking(move || -> Option<PathBuf> {
-                    let mut builder = tempfile::Builder::new();
-                    builder.prefix(path_.file_name()?).suffix(".bck");
-
-                    let backup_path = if must_copy {
-                        builder
-                            .make_in(path_.parent()?, |backup| std::fs::copy(&path_, backup))
-                            .ok()?
-                            .into_temp_path()
-                    } else {
-                        builder
-                            .make_in(path_.parent()?, |backup| std::fs::rename(&path_, backup))
-                            .ok()?
-                            .into_temp_path()
-                    };
-
-                    backup_path.keep().ok()
-                })
-                .await
-                .ok()
-                .flatten()
+            if write_method == WriteMethod::Rename && !is_hardlink && write_path.exists() {
+                write_renamed(&write_path, encoding_with_bom_info, &text, fsync).await?;
             } else {
-                None
-            };
-
-            let write_result: anyhow::Result<_> = async {
-                let mut dst = tokio::fs::File::create(&write_path).await?;
-                to_writer(&mut dst, encoding_with_bom_info, &text).await?;
-                dst.sync_all().await?;
-                Ok(())
-            }
-            .await;
+                let backup = if path.exists() && write_method != WriteMethod::InPlace {
+                    let path_ = write_path.clone();
+                    // hacks: we use tempfile to handle the complex task of creating
+                    // non clobbered temporary path for us we don't want
+                    // the whole automatically delete path on drop thing
+                    // since the path doesn't exist yet, we just want
+                    // the path
+                    tokio::task::spawn_blocking(move || -> Option<PathBuf> {
+                        let mut builder = tempfile::Builder::new();
+                        builder.prefix(path_.file_name()?).suffix(".bck");
+
+                        let backup_path = if must_copy {
+                            builder
+                                .make_in(path_.parent()?, |backup| std::fs::copy(&path_, backup))
+                                .ok()?
+                                .into_temp_path()
+                        } else {
+                            builder
+                                .make_in(path_.parent()?, |backup| std::fs::rename(&path_, backup))
+                                .ok()?
+                                .into_temp_path()
+                        };
+
+                        backup_path.keep().ok()
+                    })
+                    .await
+                    .ok()
+                    .flatten()
+                } else {
+                    None
+                };
 
-            let save_time = match fs::metadata(&write_path).await {
-                Ok(metadata) => metadata.modified().map_or(SystemTime::now(), |mtime| mtime),
-                Err(_) => SystemTime::now(),
-            };
+                let write_result: anyhow::Result<_> = async {
+                    let mut dst = tokio::fs::File::create(&write_path).await?;
+                    to_writer(&mut dst, encoding_with_bom_info, &text).await?;
+                    if fsync != FsyncPolicy::None {
+                        dst.sync_all().await?;
+                    }
+                    Ok(())
+                }
+                .await;
+
+                if let Some(backup) = backup {
+                    if must_copy {
+                        let mut delete = true;
+                        if write_result.is_err() {
+                            // Restore backup
+                            let _ = tokio::fs::copy(&backup, &write_path).await.map_err(|e| {
+                                delete = false;
+                                log::error!("Failed to restore backup on write failure: {e}")
+                            });
+                        }
 
-            if let Some(backup) = backup {
-                if must_copy {
-                    let mut delete = true;
-                    if write_result.is_err() {
-                        // Restore backup
-                        let _ = tokio::fs::copy(&backup, &write_path).await.map_err(|e| {
-                            delete = false;
+                        if delete {
+                            // Delete backup
+                            let _ = tokio::fs::remove_file(backup).await.map_err(|e| {
+                                log::error!("Failed to remove backup file on write: {e}")
+                            });
+                        }
+                    } else if write_result.is_err() {
+                        // restore backup
+                        let _ = tokio::fs::rename(&backup, &write_path).await.map_err(|e| {
                             log::error!("Failed to restore backup on write failure: {e}")
                         });
+                    } else {
+                        // copy metadata and delete backup
+                        let write_path = write_path.clone();
+                        let _ = tokio::task::spawn_blocking(move || {
+                            let _ = copy_metadata(&backup, &write_path)
+                                .map_err(|e| log::error!("Failed to copy metadata on write: {e}"));
+                            let _ = std::fs::remove_file(backup).map_err(|e| {
+                                log::error!("Failed to remove backup file on write: {e}")
+                            });
+                        })
+                        .await;
                     }
+                }
 
-                    if delete {
-                        // Delete backup
-                        let _ = tokio::fs::remove_file(backup)
-                            .await
-                            .map_err(|e| log::error!("Failed to remove backup file on write: {e}"));
-                    }
-                } else if write_result.is_err() {
-                    // restore backup
-                    let _ = tokio::fs::rename(&backup, &write_path)
-                        .await
-                        .map_err(|e| log::error!("Failed to restore backup on write failure: {e}"));
-                } else {
-                    // copy metadata and delete backup
-                    let _ = tokio::task::spawn_blocking(move || {
-                        let _ = copy_metadata(&backup, &write_path)
-                            .map_err(|e| log::error!("Failed to copy metadata on write: {e}"));
-                        let _ = std::fs::remove_file(backup)
-                            .map_err(|e| log::error!("Failed to remove backup file on write: {e}"));
-                    })
-                    .await;
+                write_result?;
+                if fsync == FsyncPolicy::Directory {
+                    sync_parent_dir(&write_path).await?;
                 }
             }
 
-            write_result?;
+            let save_time = match fs::metadata(&write_path).await {
+                Ok(metadata) => metadata.modified().map_or(SystemTime::now(), |mtime| mtime),
+                Err(_) => SystemTime::now(),
+            };
 
             let event = DocumentSavedEvent {
                 revision: current_rev,
@@ -1150,14 +1375,12 @@ pub fn detect_language(&mut self, loader: &syntax::Loader) {
         self.set_language(self.detect_language_config(loader), loader);
     }
 
-    /// Detect the programming language based on the file type.
+    /// Detect the programming language based on the file type, shebang and content.
     pub fn detect_language_config(
         &self,
         loader: &syntax::Loader,
     ) -> Option<Arc<syntax::config::LanguageConfiguration>> {
-        let language = loader
-            .language_for_filename(self.path.as_ref()?)
-            .or_else(|| loader.language_for_shebang(self.text().slice(..)))?;
+        let language = loader.language_for_file(self.path.as_deref(), self.text().slice(..))?;
 
         Some(loader.language(language).config().clone())
     }
@@ -1220,15 +1443,14 @@ pub fn detect_readonly(&mut self) {
         };
     }
 
-    /// Reload the document from its path.
-    pub fn reload(
-        &mut self,
-        view: &mut View,
-        provider_registry: &DiffProviderRegistry,
-    ) -> Result<(), Error> {
+    /// Reads the text of the document's file for reloading.
+    fn read_for_reload(&mut self) -> Result<Option<(PathBuf, Rope)>, Error> {
+        if self.fifo {
+            bail!("cannot reload from a named pipe");
+        }
         let encoding = self.encoding;
         let path = match self.path() {
-            None => return Ok(()),
+            None => return Ok(None),
             Some(path) => match path.exists() {
                 true => path.to_owned(),
                 false => bail!("can't find file to reload from {:?}", self.display_name()),
@@ -1240,6 +1462,18 @@ pub fn reload(
 
         let mut file = std::fs::File::open(&path)?;
         let (rope, ..) = from_reader(&mut file, Some(encoding))?;
+        Ok(Some((path, rope)))
+    }
+
+    /// Reload the document from its path.
+    pub fn reload(
+        &mut self,
+        view: &mut View,
+        provider_registry: &DiffProviderRegistry,
+    ) -> Result<(), Error> {
+        let Some((path, rope)) = self.read_for_reload()? else {
+            return Ok(());
+        };
 
         // Calculate the difference between the buffer and source text, and apply it.
         // This is not considered a modification of the contents of the file regardless
@@ -1251,16 +1485,209 @@ pub fn reload(
         self.pickup_last_saved_time();
         self.detect_indent_and_line_ending();
 
-        match provider_registry.get_diff_base(&path) {
+        self.refresh_version_control(&path, provider_registry);
+
+        Ok(())
+    }
+
+    /// Updates the diff base and the version control head for the file at `path`, for
+    /// example after the file was reloaded or moved.
+    pub fn refresh_version_control(
+        &mut self,
+        path: &Path,
+        provider_registry: &DiffProviderRegistry,
+    ) {
+        match provider_registry.get_diff_base(path) {
             Some(diff_base) => self.set_diff_base(diff_base),
             None => self.diff_handle = None,
         }
 
-        self.version_control_head = provider_registry.get_current_head_name(&path);
+        self.version_control_head = provider_registry.get_current_head_name(path);
+    }
+
+    /// Whether the file was modified on disk since the document was last loaded or saved.
+    pub fn changed_on_disk(&self) -> bool {
+        !self.fifo
+            && self
+                .path()
+                .and_then(|path| path.metadata().ok())
+                .and_then(|metadata| metadata.modified().ok())
+                .is_some_and(|mtime| mtime > self.last_saved_time)
+    }
+
+    /// Whether the document's path is a named pipe. Data written to the pipe is appended to the
+    /// document as it arrives, see [`Editor::append_to_document`].
+    pub fn is_fifo(&self) -> bool {
+        self.fifo
+    }
+
+    /// Whether the document is a large file whose text is still read in the background. It is
+    /// empty and read-only until then, see [`Editor::finish_loading`].
+    pub fn is_loading(&self) -> bool {
+        self.pending_load.is_some()
+    }
+
+    pub fn pending_load(&self) -> Option<&PendingLoad> {
+        self.pending_load.as_ref()
+    }
+
+    /// Replaces the empty text of a document opened before its file was read with the loaded
+    /// text. Like a document opened with its text, it isn't modified and has no history. Edits
+    /// are rejected while it is loading, see [`Document::apply`].
+    pub fn finish_loading(&mut self, loaded: LoadedText) {
+        self.pending_load = None;
+        self.text = loaded.text;
+        self.changes = ChangeSet::new(self.text.slice(..));
+        self.old_state = None;
+        self.history = Cell::new(History::default());
+        self.last_saved_revision = 0;
+        for selection in self.selections.values_mut() {
+            *selection = Selection::point(0);
+        }
+        self.encoding = loaded.encoding;
+        self.has_bom = loaded.has_bom;
+        self.set_language_config(loaded.language);
+        self.syntax = loaded.syntax;
+        self.detect_readonly();
+        self.detect_indent_and_line_ending();
+        self.pickup_last_saved_time();
+    }
+
+    /// Whether content appended to the file is loaded, see [`Document::load_appended`].
+    pub fn is_following(&self) -> bool {
+        self.followed_len.is_some()
+    }
 
+    /// Starts or stops following the file for appended content. Following starts at the
+    /// current end of the file.
+    pub fn set_following(&mut self, follow: bool) -> Result<(), Error> {
+        if !follow {
+            self.follow_controller.cancel();
+            self.followed_len = None;
+            return Ok(());
+        }
+        if self.fifo {
+            bail!("named pipes are already read as data arrives");
+        }
+        let Some(path) = self.path() else {
+            bail!("cannot follow a buffer without a file");
+        };
+        self.followed_len = Some(path.metadata()?.len());
         Ok(())
     }
 
+    /// Loads the lines appended to the followed file since the last call and inserts them at
+    /// the end of the document, returns whether the text changed. A line which is still being
+    /// written is loaded once its line ending arrives. A file which shrank, for example because
+    /// a log was rotated, is reloaded entirely.
+    ///
+    /// Documents with unsaved changes are left alone: the file no longer matches their text.
+    pub fn load_appended(
+        &mut self,
+        view: &mut View,
+        provider_registry: &DiffProviderRegistry,
+    ) -> Result<bool, Error> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let (Some(offset), Some(path)) = (self.followed_len, self.path()) else {
+            return Ok(false);
+        };
+        if self.is_modified() {
+            return Ok(false);
+        }
+
+        let mut file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        if len < offset {
+            self.reload(view, provider_registry)?;
+            self.followed_len = Some(len);
+            return Ok(true);
+        }
+
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        file.take(len - offset).read_to_end(&mut bytes)?;
+        let Some(end) = bytes.iter().rposition(|&byte| byte == b'\n') else {
+            return Ok(false);
+        };
+        bytes.truncate(end + 1);
+        let (appended, _) = self.encoding.decode_without_bom_handling(&bytes);
+        self.append(view, &appended);
+        self.pickup_last_saved_time();
+        self.followed_len = Some(offset + bytes.len() as u64);
+        Ok(true)
+    }
+
+    /// Inserts `text` at the end of the document without marking it as modified, for content
+    /// which was added to the file rather than by the user. A document which only holds the
+    /// default line ending, like a file which was empty when it was opened, is replaced.
+    pub fn append(&mut self, view: &mut View, text: &str) {
+        let end = self.text.len_chars();
+        let start = if self.text == self.line_ending.as_str() {
+            0
+        } else {
+            end
+        };
+        let transaction = Transaction::change(
+            self.text(),
+            std::iter::once((start, end, Some(text.into()))),
+        );
+        self.apply(&transaction, view.id);
+        self.append_changes_to_history(view);
+        self.reset_modified();
+    }
+
+    /// Returns the text as it was when the document was last loaded from or saved to disk.
+    /// Changes which were not yet appended to the history are ignored.
+    pub fn saved_text(&self) -> Rope {
+        let mut text = self.text.clone();
+        let history = self.history.take();
+        if let Some(transaction) = history.changes_to(self.last_saved_revision) {
+            transaction.apply(&mut text);
+        }
+        self.history.set(history);
+        text
+    }
+
+    /// Reloads the document from its path, keeping unsaved changes by merging them with the
+    /// changes on disk. The text last loaded from or saved to disk is the merge base.
+    ///
+    /// The file's text and the merge result are added to the history as two revisions, so
+    /// undoing the merge goes back to the file's text. Returns the line ranges of the
+    /// conflicts, which are marked with conflict markers like in a git merge.
+    pub fn reload_merged(
+        &mut self,
+        view: &mut View,
+        provider_registry: &DiffProviderRegistry,
+    ) -> Result<Vec<std::ops::Range<usize>>, Error> {
+        let Some((path, theirs)) = self.read_for_reload()? else {
+            return Ok(Vec::new());
+        };
+
+        self.append_changes_to_history(view);
+        let merge = helix_core::diff::merge_ropes(
+            &self.saved_text(),
+            self.text(),
+            &theirs,
+            ("buffer", "disk"),
+        );
+
+        // The file's text becomes the saved revision and so the base of the next merge.
+        let transaction = helix_core::diff::compare_ropes(self.text(), &theirs);
+        self.apply(&transaction, view.id);
+        self.append_changes_to_history(view);
+        self.reset_modified();
+        self.pickup_last_saved_time();
+
+        let transaction = helix_core::diff::compare_ropes(self.text(), &merge.text);
+        self.apply(&transaction, view.id);
+        self.append_changes_to_history(view);
+
+        self.refresh_version_control(&path, provider_registry);
+
+        Ok(merge.conflicts)
+    }
+
     /// Sets the [`Document`]'s encoding with the encoding correspondent to `label`.
     pub fn set_encoding(&mut self, label: &str) -> Result<(), Error> {
         let encoding =
@@ -1300,19 +1727,54 @@ pub fn set_language(
         &mut self,
         language_config: Option<Arc<syntax::config::LanguageConfiguration>>,
         loader: &syntax::Loader,
+    ) {
+        self.set_language_config(language_config);
+        let too_large = self
+            .config
+            .load()
+            .syntax_max_file_size
+            .is_some_and(|max| self.text.len_bytes() as u64 > max);
+        self.syntax = self
+            .language
+            .as_ref()
+            .filter(|_| !too_large)
+            .and_then(|config| {
+                Syntax::new(self.text.slice(..), config.language(), loader)
+                    .map_err(|err| {
+                        // `NoRootConfig` means that there was an issue loading the language/syntax
+                        // config for the root language of the document. An error must have already
+                        // been logged by `LanguageData::syntax_config`.
+                        if err != syntax::HighlighterError::NoRootConfig {
+                            log::warn!(
+                                "Error building syntax for '{}': {err}",
+                                self.display_name()
+                            );
+                        }
+                    })
+                    .ok()
+            });
+    }
+
+    /// Sets the language of the document and its gutters, without its syntax tree.
+    fn set_language_config(
+        &mut self,
+        language_config: Option<Arc<syntax::config::LanguageConfiguration>>,
     ) {
         self.language = language_config;
-        self.syntax = self.language.as_ref().and_then(|config| {
-            Syntax::new(self.text.slice(..), config.language(), loader)
-                .map_err(|err| {
-                    // `NoRootConfig` means that there was an issue loading the language/syntax
-                    // config for the root language of the document. An error must have already
-                    // been logged by `LanguageData::syntax_config`.
-                    if err != syntax::HighlighterError::NoRootConfig {
-                        log::warn!("Error building syntax for '{}': {err}", self.display_name());
-                    }
+        self.gutter_layout = self.language.as_ref().and_then(|config| {
+            let gutters = config.gutters.as_ref()?;
+            let layout = gutters
+                .iter()
+                .filter_map(|gutter| {
+                    gutter
+                        .parse()
+                        .map_err(|err| {
+                            log::warn!("Invalid gutter for '{}': {err}", config.language_id)
+                        })
+                        .ok()
                 })
-                .ok()
+                .collect();
+            Some(layout)
         });
     }
 
@@ -1423,6 +1885,13 @@ fn apply_impl(
                 .ensure_invariants(self.text.slice(..));
         }
 
+        for selection in self.named_selections.values_mut() {
+            *selection = selection
+                .clone()
+                .map(transaction.changes())
+                .ensure_invariants(self.text.slice(..));
+        }
+
         for view_data in self.view_data.values_mut() {
             view_data.view_position.anchor = transaction
                 .changes()
@@ -1531,6 +2000,12 @@ fn apply_impl(
             apply_inlay_hint_changes(padding_after_inlay_hints);
         }
 
+        changes.update_positions(
+            self.virtual_text
+                .iter_mut()
+                .map(|virtual_text| (&mut virtual_text.char_idx, Assoc::After)),
+        );
+
         helix_event::dispatch(DocumentDidChange {
             doc: self,
             view: view_id,
@@ -1560,6 +2035,13 @@ fn apply_inner(
         view_id: ViewId,
         emit_lsp_notification: bool,
     ) -> bool {
+        // The placeholder text of a loading document is replaced once the file is read, so
+        // edits to it would be lost
+        if self.is_loading() && !transaction.changes().is_empty() {
+            helix_event::dispatch(DocumentEditRejected { doc: self });
+            return false;
+        }
+
         // store the state just before any changes are made. This allows us to undo to the
         // state just before a transaction was applied.
         if self.changes.is_empty() && !transaction.changes().is_empty() {
@@ -1579,7 +2061,8 @@ fn apply_inner(
         }
         success
     }
-    /// Apply a [`Transaction`] to the [`Document`] to change its text.
+    /// Apply a [`Transaction`] to the [`Document`] to change its text. Changes to a document
+    /// which is still loading are rejected.
     pub fn apply(&mut self, transaction: &Transaction, view_id: ViewId) -> bool {
         self.apply_inner(transaction, view_id, true)
     }
@@ -1851,6 +2334,28 @@ pub fn remove_language_server_by_name(&mut self, name: &str) -> Option<Arc<Clien
         self.language_servers.remove(name)
     }
 
+    /// Detaches the language server from the document and keeps it from being attached again
+    /// until [`Self::enable_language_server`]. The server keeps running for other documents.
+    pub fn disable_language_server(&mut self, name: &str) -> Option<Arc<Client>> {
+        self.disabled_language_servers.insert(name.to_string());
+        let client = self.language_servers.remove(name)?;
+        client.text_document_did_close(self.identifier());
+        self.clear_diagnostics_for_language_server(client.id());
+        self.reset_all_inlay_hints();
+        self.inlay_hints_oudated = true;
+        Some(client)
+    }
+
+    /// Allows a disabled language server to be attached again, which happens on the next
+    /// [`Editor::refresh_language_servers`]. Returns whether it was disabled.
+    pub fn enable_language_server(&mut self, name: &str) -> bool {
+        self.disabled_language_servers.remove(name)
+    }
+
+    pub fn disabled_language_servers(&self) -> impl Iterator<Item = &str> {
+        self.disabled_language_servers.iter().map(String::as_str)
+    }
+
     pub fn language_servers_with_feature(
         &self,
         feature: LanguageServerFeature,
@@ -2264,6 +2769,14 @@ pub fn text_format(&self, mut viewport_width: u16, theme: Option<&Theme>) -> Tex
             .and_then(|soft_wrap| soft_wrap.max_indent_retain)
             .or(editor_soft_wrap.max_indent_retain)
             .unwrap_or(40);
+        let wrap_at_word = language_soft_wrap
+            .and_then(|soft_wrap| soft_wrap.wrap_at_word)
+            .or(editor_soft_wrap.wrap_at_word)
+            .unwrap_or(true);
+        let continuation_indent = language_soft_wrap
+            .and_then(|soft_wrap| soft_wrap.continuation_indent)
+            .or(editor_soft_wrap.continuation_indent)
+            .unwrap_or(0);
         let wrap_indicator = language_soft_wrap
             .and_then(|soft_wrap| soft_wrap.wrap_indicator.clone())
             .or_else(|| config.soft_wrap.wrap_indicator.clone())
@@ -2272,8 +2785,14 @@ pub fn text_format(&self, mut viewport_width: u16, theme: Option<&Theme>) -> Tex
         TextFormat {
             soft_wrap: enable_soft_wrap && viewport_width > 10,
             tab_width,
-            max_wrap: max_wrap.min(viewport_width / 4),
+            // without any free space at the end of the line words are split at the last column
+            max_wrap: if wrap_at_word {
+                max_wrap.min(viewport_width / 4)
+            } else {
+                0
+            },
             max_indent_retain: max_indent_retain.min(viewport_width * 2 / 5),
+            continuation_indent: continuation_indent.min(viewport_width / 4),
             // avoid spinning forever when the window manager
             // sets the size to something tiny
             viewport_width,
@@ -2297,6 +2816,27 @@ pub fn remove_jump_labels(&mut self, view_id: ViewId) {
         self.jump_labels.remove(&view_id);
     }
 
+    /// End-of-line and inter-line virtual text set by all components, sorted by anchor.
+    pub fn virtual_text(&self) -> &[VirtualText] {
+        &self.virtual_text
+    }
+
+    /// Replaces the virtual text previously set by `source` (usually the name of the feature
+    /// providing it, like `"blame"`). Text on the same line is shown in the order of its
+    /// anchors.
+    pub fn set_virtual_text(&mut self, source: &'static str, mut virtual_text: Vec<VirtualText>) {
+        self.virtual_text.retain(|vt| vt.source != source);
+        for vt in &mut virtual_text {
+            vt.source = source;
+        }
+        self.virtual_text.extend(virtual_text);
+        self.virtual_text.sort_by_key(|vt| vt.char_idx);
+    }
+
+    pub fn clear_virtual_text(&mut self, source: &'static str) {
+        self.virtual_text.retain(|vt| vt.source != source);
+    }
+
     /// Get the inlay hints for this document and `view_id`.
     pub fn inlay_hints(&self, view_id: ViewId) -> Option<&DocumentInlayHints> {
         self.inlay_hints.get(&view_id)
@@ -2355,6 +2895,47 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn virtual_text_follows_edits() {
+        use crate::annotations::virtual_text::VirtualTextPlacement;
+
+        let mut doc = Document::from(
+            Rope::from("foo\nbar\n"),
+            None,
+            Arc::new(ArcSwap::new(Arc::new(Config::default()))),
+            Arc::new(ArcSwap::from_pointee(syntax::Loader::default())),
+        );
+        let view = ViewId::default();
+        doc.set_selection(view, Selection::single(0, 0));
+
+        let eol = |char_idx, text| {
+            VirtualText::new(
+                char_idx,
+                VirtualTextPlacement::EndOfLine,
+                text,
+                "ui.virtual",
+            )
+        };
+        doc.set_virtual_text("blame", vec![eol(4, "blame")]);
+        doc.set_virtual_text("lens", vec![eol(0, "lens")]);
+        let texts = |doc: &Document| -> Vec<(usize, String)> {
+            doc.virtual_text()
+                .iter()
+                .map(|vt| (vt.char_idx, vt.text.clone()))
+                .collect()
+        };
+        assert_eq!(texts(&doc), [(0, "lens".into()), (4, "blame".into())]);
+
+        let transaction = Transaction::insert(doc.text(), &Selection::single(0, 0), "a\n".into());
+        doc.apply(&transaction, view);
+        assert_eq!(texts(&doc), [(2, "lens".into()), (6, "blame".into())]);
+
+        doc.set_virtual_text("blame", vec![eol(0, "new blame")]);
+        assert_eq!(texts(&doc), [(0, "new blame".into()), (2, "lens".into())]);
+        doc.clear_virtual_text("lens");
+        assert_eq!(texts(&doc), [(0, "new blame".into())]);
+    }
+
     #[test]
     fn changeset_to_changes_ignore_line_endings() {
         use helix_lsp::{lsp, Client, OffsetEncoding};
@@ -2513,6 +3094,46 @@ fn changeset_to_changes() {
         );
     }
 
+    #[test]
+    fn large_file_is_loaded_later() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"hello\nworld\n").unwrap();
+        let config = Config {
+            large_file_threshold: 4,
+            ..Config::default()
+        };
+        let loader = Arc::new(ArcSwap::from_pointee(syntax::Loader::default()));
+        let mut doc = Document::open(
+            file.path(),
+            None,
+            true,
+            Arc::new(ArcSwap::new(Arc::new(config))),
+            loader.clone(),
+        )
+        .unwrap();
+        assert!(doc.is_loading());
+        assert!(doc.readonly);
+        assert_eq!(doc.text().len_lines(), 2);
+        assert!(doc.save::<PathBuf>(None, true).is_err());
+
+        // Edits are rejected until the text is loaded, moving the cursor is not
+        let view = ViewId::default();
+        doc.ensure_view_init(view);
+        let edit = Transaction::insert(doc.text(), doc.selection(view), "lost".into());
+        assert!(!doc.apply(&edit, view));
+        assert_eq!(doc.text().len_lines(), 2);
+        assert!(!doc.is_modified());
+        let select = Transaction::new(doc.text()).with_selection(Selection::point(1));
+        assert!(doc.apply(&select, view));
+
+        let pending = doc.pending_load().unwrap().clone();
+        let mut reader = std::fs::File::open(&pending.path).unwrap();
+        doc.finish_loading(pending.read(&mut reader, &loader.load()).unwrap());
+        assert!(!doc.is_loading());
+        assert_eq!(doc.text(), "hello\nworld\n");
+        assert!(!doc.is_modified());
+    }
+
     #[test]
     fn test_line_ending() {
         assert_eq!(
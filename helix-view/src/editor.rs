@@ -2,19 +2,27 @@
     annotations::diagnostics::{DiagnosticFilter, InlineDiagnosticsConfig},
     clipboard::ClipboardProvider,
     document::{
-        DocumentOpenError, DocumentSavedEventFuture, DocumentSavedEventResult, Mode, SavePoint,
+        DocumentOpenError, DocumentSavedEventFuture, DocumentSavedEventResult, LoadedText, Mode,
+        SavePoint,
     },
-    events::{DocumentDidClose, DocumentDidOpen, DocumentFocusLost},
-    graphics::{CursorKind, Rect},
+    events::{DocumentDidClose, DocumentDidOpen, DocumentDidStartLoading, DocumentFocusLost},
+    graphics::{Color, CursorKind, Rect},
+    gutter::Gutter,
     handlers::Handlers,
     info::Info,
     input::KeyEvent,
-    register::Registers,
+    messages::MessageLog,
+    metrics::Metrics,
+    named_selections,
+    quickfix::{QuickfixEntry, QuickfixList},
+    register::{self, Registers},
+    terminal::{self, Terminal},
     theme::{self, Theme},
     tree::{self, Tree},
+    watch::WatchTask,
     Document, DocumentId, View, ViewId,
 };
-use helix_event::dispatch;
+use helix_event::{dispatch, TaskController};
 use helix_vcs::DiffProviderRegistry;
 
 use futures_util::stream::select_all::SelectAll;
@@ -45,11 +53,12 @@
 use helix_core::{
     auto_pairs::AutoPairs,
     diagnostic::DiagnosticProvider,
+    movement::SubWordRules,
     syntax::{
         self,
         config::{AutoPairConfig, IndentationHeuristic, LanguageServerFeature, SoftWrap},
     },
-    Change, LineEnding, Position, Range, Selection, Uri, NATIVE_LINE_ENDING,
+    Change, LineEnding, Position, Range, RopeSlice, Selection, Uri, NATIVE_LINE_ENDING,
 };
 use helix_dap::{self as dap, registry::DebugAdapterId};
 use helix_lsp::lsp;
@@ -179,7 +188,8 @@ fn default() -> Self {
 pub struct FilePickerConfig {
     /// IgnoreOptions
     /// Enables ignoring hidden files.
-    /// Whether to hide hidden files in file picker and global search results. Defaults to true.
+    /// Whether to hide hidden files in file picker, global search results and path completions,
+    /// unless the completed name starts with `.`. Defaults to true.
     pub hidden: bool,
     /// Enables following symlinks.
     /// Whether to follow symbolic links in file picker and file or directory completions. Defaults to true.
@@ -306,6 +316,14 @@ pub struct Config {
     pub cursorline: bool,
     /// Highlight the columns cursors are currently on. Defaults to false.
     pub cursorcolumn: bool,
+    /// The views in which the cursorline and cursorcolumn are highlighted. Defaults to
+    /// `focused`.
+    pub cursor_highlight_views: CursorHighlightViews,
+    /// De-emphasize the text of unfocused views with the `ui.view.inactive` style, or dim it
+    /// if the theme doesn't set one. Defaults to false.
+    pub dim_inactive_views: bool,
+    /// A secondary theme, or a dimmed variant of the current one, for unfocused views.
+    pub inactive_views: InactiveViewsConfig,
     #[serde(deserialize_with = "deserialize_gutter_seq_or_struct")]
     pub gutters: GutterConfig,
     /// Middle click paste support. Defaults to true.
@@ -314,6 +332,22 @@ pub struct Config {
     /// etc. Optionally, this can be a list of 2-tuples to specify a
     /// global list of characters to pair. Defaults to true.
     pub auto_pairs: AutoPairConfig,
+    /// Whether to reload documents whose files changed on disk when the terminal regains
+    /// focus, or as soon as they change with `file-watcher`. Unsaved changes are merged with
+    /// the changes on disk. Defaults to `false`.
+    pub auto_reload: bool,
+    /// Whether to watch the workspace for files changed outside of the editor and notify the
+    /// language servers interested in them. Read on startup. Defaults to `true`.
+    pub file_watcher: bool,
+    /// Whether to fetch and build the tree-sitter grammars of opened documents which aren't
+    /// built yet, in the background. Defaults to `true`.
+    pub auto_build_grammars: bool,
+    /// Insert mode abbreviations: typing a non-word character after a word which is a key
+    /// of this map replaces the word with its value. Defaults to no abbreviations.
+    pub abbreviations: HashMap<String, String>,
+    /// Additional pairs for the surround commands and the `mi`/`ma` text objects, from the
+    /// opening to the closing character. Defaults to no pairs.
+    pub surround_pairs: HashMap<char, char>,
     /// Automatic auto-completion, automatically pop up without user trigger. Defaults to true.
     pub auto_completion: bool,
     /// Enable filepath completion.
@@ -326,6 +360,9 @@ pub struct Config {
     pub word_completion: WordCompletion,
     /// Automatic formatting on save. Defaults to true.
     pub auto_format: bool,
+    /// Ask before applying formatting which changes more than this percentage of the
+    /// document's lines, showing the changes on request. Defaults to `None`, never asking.
+    pub format_confirm_threshold: Option<u8>,
     /// Default register used for yank/paste. Defaults to '"'
     pub default_yank_register: char,
     /// Automatic save on focus lost and/or after delay.
@@ -351,7 +388,15 @@ pub struct Config {
     pub completion_timeout: Duration,
     /// Whether to insert the completion suggestion on hover. Defaults to true.
     pub preview_completion_insert: bool,
+    /// Whether to show the documentation of the selected completion item next to the menu.
+    /// It can also be toggled while the menu is open. Defaults to true.
+    pub completion_docs: bool,
+    /// The number of word characters before the cursor needed to automatically pop up
+    /// completions. Defaults to 2.
     pub completion_trigger_len: u8,
+    /// Highlight scopes, like `comment` or `string`, in which completions don't pop up
+    /// automatically. A scope also matches its sub-scopes. Defaults to no scopes.
+    pub auto_completion_disabled_scopes: Vec<String>,
     /// Whether to instruct the LSP to replace the entire word when applying a completion
     /// or to only insert new text
     pub completion_replace: bool,
@@ -368,6 +413,12 @@ pub struct Config {
     pub cursor_shape: CursorShapeConfig,
     /// Set to `true` to override automatic detection of terminal truecolor support in the event of a false negative. Defaults to `false`.
     pub true_color: bool,
+    /// The number of colors used to draw themes. Colors the terminal can't show are drawn as
+    /// the closest color it can show. Defaults to `auto`, the detected color depth.
+    pub color_depth: ColorDepthConfig,
+    /// Whether to draw scrolled views by scrolling the terminal and only drawing the exposed
+    /// lines, which is faster over slow connections. Defaults to `false`.
+    pub hardware_scroll: bool,
     /// Set to `true` to override automatic detection of terminal undercurl support in the event of a false negative. Defaults to `false`.
     pub undercurl: bool,
     /// Search configuration.
@@ -385,6 +436,10 @@ pub struct Config {
     pub indent_guides: IndentGuidesConfig,
     /// Whether to color modes with different colors. Defaults to `false`.
     pub color_modes: bool,
+    /// Whether the terminal cursor takes the background of `ui.cursor.primary.<mode>` from the
+    /// theme, the cursor shapes of `cursor-shape` other than `block` are drawn by the terminal.
+    /// Defaults to `false`.
+    pub terminal_cursor_color: bool,
     pub soft_wrap: SoftWrap,
     /// Workspace specific lsp ceiling dirs
     pub workspace_lsp_roots: Vec<PathBuf>,
@@ -395,7 +450,12 @@ pub struct Config {
     /// Whether to use atomic operations to write documents to disk.
     /// This prevents data loss if the editor is interrupted while writing the file, but may
     /// confuse some file watching/hot reloading programs. Defaults to `true`.
+    /// `false` is the same as `write-method = "in-place"`.
     pub atomic_save: bool,
+    /// How documents are written to disk when `atomic-save` is enabled. Defaults to `backup`.
+    pub write_method: WriteMethod,
+    /// What is flushed to the disk before a write counts as done. Defaults to `file`.
+    pub fsync: FsyncPolicy,
     /// Whether to automatically remove all trailing line-endings after the final one on write.
     /// Defaults to `false`.
     pub trim_final_newlines: bool,
@@ -404,6 +464,9 @@ pub struct Config {
     pub trim_trailing_whitespace: bool,
     /// Enables smart tab
     pub smart_tab: Option<SmartTabConfig>,
+    /// Where the sub word motions and textobject split words. Defaults to splitting at both
+    /// camelCase and snake_case boundaries.
+    pub sub_word: SubWordRules,
     /// Draw border around popups.
     pub popup_border: PopupBorderConfig,
     /// Which indent heuristic to use when a new line is inserted
@@ -415,19 +478,146 @@ pub struct Config {
         deserialize_with = "deserialize_alphabet"
     )]
     pub jump_label_alphabet: Vec<char>,
+    /// Lists of words that increment and decrement cycle through, e.g. `["true", "false"]`.
+    pub increment_words: Vec<Vec<String>>,
     /// Display diagnostic below the line they occur.
     pub inline_diagnostics: InlineDiagnosticsConfig,
     pub end_of_line_diagnostics: DiagnosticFilter,
+    /// Which diagnostics `goto_next_diag` and friends jump to.
+    pub diagnostic_navigation: DiagnosticNavigationConfig,
     // Set to override the default clipboard provider
     pub clipboard_provider: ClipboardProvider,
     /// Whether to read settings from [EditorConfig](https://editorconfig.org) files. Defaults to
     /// `true`.
     pub editor_config: bool,
+    /// Files larger than this many bytes are read in the background when they are opened, so
+    /// that the editor stays responsive. The document is empty and edits to it are rejected
+    /// until its text is loaded. Defaults to 16 MiB.
+    pub large_file_threshold: u64,
+    /// Files larger than this many bytes are opened without syntax highlighting and the other
+    /// tree-sitter features. Defaults to no limit.
+    pub syntax_max_file_size: Option<u64>,
     /// Whether to render rainbow colors for matching brackets. Defaults to `false`.
     pub rainbow_brackets: bool,
+    /// Theme scopes used for the nesting levels of rainbow brackets instead of the theme's
+    /// `rainbow` palette. Defaults to empty.
+    pub rainbow_scopes: Vec<String>,
     /// Whether to enable Kitty Keyboard Protocol
     pub kitty_keyboard_protocol: KittyKeyboardProtocolConfig,
+    /// Time in milliseconds a lone ESC read from the terminal waits for the rest of an escape
+    /// sequence before it is sent as the Escape key. Raise it when Alt chords are split into
+    /// ESC and a key over slow connections. Not used while the kitty keyboard protocol is
+    /// active, ESC is unambiguous then. Defaults to 20ms.
+    #[serde(
+        serialize_with = "serialize_duration_millis",
+        deserialize_with = "deserialize_duration_millis"
+    )]
+    pub esc_timeout: Duration,
+    /// What to do when a paste only consists of the absolute paths of existing files, as
+    /// terminals paste files dropped on them: `ask`, `open` the files or `insert` the paths.
+    /// Defaults to `ask`.
+    pub paste_file_paths: PasteFilePaths,
     pub buffer_picker: BufferPickerConfig,
+    /// How much to dim the editor behind overlays and popups.
+    pub dim_background: DimBackgroundConfig,
+    /// Whether to save named registers on exit and restore them on startup, per workspace.
+    /// Defaults to `false`.
+    pub persist_registers: bool,
+    /// Whether to save the command and search histories on exit and restore them on
+    /// startup. Defaults to `false`.
+    pub persist_history: bool,
+    /// Whether to save the named selections of each file when it is closed and restore them
+    /// when it is opened again. Defaults to `false`.
+    pub persist_selections: bool,
+    /// Whether to save the open files, their selections and the splits of the workspace on
+    /// exit. Defaults to `false`.
+    pub persist_session: bool,
+    /// Whether to restore the saved session of the workspace when started without files.
+    /// Defaults to `false`.
+    pub restore_session: bool,
+    /// Whether to show a line above each view with the symbols enclosing the cursor.
+    /// Defaults to `false`.
+    pub winbar: bool,
+    /// Whether to redraw less often for slow terminals, for example over a high latency SSH
+    /// connection. Defaults to `auto`, which throttles rendering while flushing frames is slow.
+    pub throttle_rendering: ThrottleRenderingConfig,
+    /// Whether to record every change to a document with a path in its edit journal, see
+    /// [`crate::journal`]. Defaults to `false`.
+    pub edit_journal: bool,
+    /// Limits on background work and when to pause it, see [`ResourceConfig`].
+    pub resources: ResourceConfig,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThrottleRenderingConfig {
+    /// Throttle rendering while the terminal is detected to be slow.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ThrottleRenderingConfig {
+    pub const VARIANTS: &'static [&'static str] = &["auto", "always", "never"];
+}
+
+/// Process-wide limits on background work, see [`crate::resources`].
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ResourceConfig {
+    /// The maximum number of background jobs running at once, such as language server
+    /// requests and shell commands. Further jobs wait for one to finish. `0` disables the
+    /// limit. Defaults to `0`.
+    pub max_background_jobs: usize,
+    /// The maximum number of requests in flight to each language server. Further requests
+    /// fail until a response arrives. `0` disables the limit. Defaults to `0`.
+    pub max_lsp_requests: usize,
+    /// The number of threads searching files in the global search, `0` for one per CPU.
+    /// Defaults to `0`.
+    pub grep_threads: usize,
+    /// Time in milliseconds above which handling and drawing an input event counts as slow.
+    /// After a run of slow events, background work such as indexing the words of open
+    /// documents is paused until the editor is responsive again. `0` disables pausing.
+    /// Defaults to 150ms.
+    #[serde(
+        serialize_with = "serialize_duration_millis",
+        deserialize_with = "deserialize_duration_millis"
+    )]
+    pub latency_threshold: Duration,
+}
+
+impl Default for ResourceConfig {
+    fn default() -> Self {
+        Self {
+            max_background_jobs: 0,
+            max_lsp_requests: 0,
+            grep_threads: 0,
+            latency_threshold: Duration::from_millis(150),
+        }
+    }
+}
+
+/// How much to dim the content behind a component while it is open, in percent. `0` disables
+/// dimming.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct DimBackgroundConfig {
+    /// Pickers and other components overlaid on the editor.
+    pub overlay: u8,
+    /// Popups such as hover documentation.
+    pub popup: u8,
+}
+
+/// How unfocused views are rendered so that the focused one stands out.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct InactiveViewsConfig {
+    /// The theme unfocused views are rendered with instead of the current theme.
+    pub theme: Option<String>,
+    /// How much to blend the colors of unfocused views toward `ui.background`, in percent, a
+    /// dimmed variant of their theme. `0` disables dimming.
+    pub dim: u8,
 }
 
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Clone, Copy)]
@@ -456,6 +646,41 @@ pub fn is_current(self) -> bool {
     }
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorDepthConfig {
+    /// Detected from the environment and the terminal, see also `editor.true-color`.
+    #[default]
+    Auto,
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    /// The 256 color palette.
+    #[serde(rename = "256")]
+    Indexed256,
+    /// The 16 ANSI colors.
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+impl ColorDepthConfig {
+    pub const VARIANTS: &'static [&'static str] = &["auto", "truecolor", "256", "16"];
+}
+
+/// What to do with a paste of only paths of existing files, see `editor.paste-file-paths`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PasteFilePaths {
+    /// Ask whether to open the files or insert the paths.
+    #[default]
+    Ask,
+    Open,
+    Insert,
+}
+
+impl PasteFilePaths {
+    pub const VARIANTS: &'static [&'static str] = &["ask", "open", "insert"];
+}
+
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "kebab-case")]
 pub enum KittyKeyboardProtocolConfig {
@@ -465,6 +690,10 @@ pub enum KittyKeyboardProtocolConfig {
     Enabled,
 }
 
+impl KittyKeyboardProtocolConfig {
+    pub const VARIANTS: &'static [&'static str] = &["auto", "disabled", "enabled"];
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Eq, PartialOrd, Ord)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 pub struct SmartTabConfig {
@@ -586,6 +815,17 @@ pub struct SearchConfig {
     pub wrap_around: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct DiagnosticNavigationConfig {
+    /// The minimum severity of the diagnostics visited by `goto_next_diag`, `goto_prev_diag`,
+    /// `goto_first_diag` and `goto_last_diag`. Defaults to `hint`, visiting all diagnostics.
+    pub min_severity: Severity,
+    /// Whether `goto_next_diag` and `goto_prev_diag` wrap around at the end of the document.
+    /// Defaults to false.
+    pub wrap_around: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct StatusLineConfig {
@@ -605,13 +845,19 @@ fn default() -> Self {
         Self {
             left: vec![
                 E::Mode,
+                E::KeyLayer,
                 E::Spinner,
                 E::FileName,
                 E::ReadOnlyIndicator,
+                E::PinnedIndicator,
+                E::ThrottledIndicator,
+                E::WatchTask,
+                E::Jobs,
                 E::FileModificationIndicator,
             ],
             center: vec![],
             right: vec![
+                E::SearchMatches,
                 E::Diagnostics,
                 E::Selections,
                 E::Register,
@@ -715,6 +961,24 @@ pub enum StatusLineElement {
 
     /// The base of current working directory
     CurrentWorkingDirectory,
+
+    /// The label of the active sticky key layer, if any
+    KeyLayer,
+
+    /// The index of the search match under the cursor and the number of matches
+    SearchMatches,
+
+    /// An indicator that shows `[pinned]` when the view is pinned
+    PinnedIndicator,
+
+    /// An indicator that shows `[throttled]` while rendering is throttled
+    ThrottledIndicator,
+
+    /// The state of the command of watch mode, see `:watch`
+    WatchTask,
+
+    /// The named background jobs which are running, see `:jobs`
+    Jobs,
 }
 
 // Cursor shape is read and used on every rendered frame and so needs
@@ -784,6 +1048,10 @@ pub enum BufferLine {
     Multiple,
 }
 
+impl BufferLine {
+    pub const VARIANTS: &'static [&'static str] = &["never", "always", "multiple"];
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum LineNumber {
@@ -793,6 +1061,13 @@ pub enum LineNumber {
     /// If focused and in normal/select mode, show relative line number to the primary cursor.
     /// If unfocused or in insert mode, show absolute line number.
     Relative,
+
+    /// Like `Relative`, but show the absolute line number on the line of the primary cursor.
+    Hybrid,
+}
+
+impl LineNumber {
+    pub const VARIANTS: &'static [&'static str] = &["absolute", "relative", "hybrid"];
 }
 
 impl std::str::FromStr for LineNumber {
@@ -802,22 +1077,55 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "absolute" | "abs" => Ok(Self::Absolute),
             "relative" | "rel" => Ok(Self::Relative),
-            _ => anyhow::bail!("Line number can only be `absolute` or `relative`."),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => anyhow::bail!("Line number can only be `absolute`, `relative` or `hybrid`."),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
+pub enum CursorHighlightViews {
+    /// Only highlight the cursors of the focused view
+    #[default]
+    Focused,
+    /// Highlight the cursors of every view
+    All,
+}
+
+impl CursorHighlightViews {
+    pub const VARIANTS: &'static [&'static str] = &["focused", "all"];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum GutterType {
     /// Show diagnostics and other features like breakpoints
     Diagnostics,
+    /// Show breakpoints and the current debugger position. The diagnostics gutter no longer
+    /// shows them when this gutter is part of the layout.
+    Breakpoints,
     /// Show line numbers
     LineNumbers,
     /// Show one blank space
     Spacer,
     /// Highlight local changes
     Diff,
+    /// A gutter registered with [`Editor::register_gutter`], for example by bookmarks.
+    Custom(String),
+}
+
+impl GutterType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Diagnostics => "diagnostics",
+            Self::Breakpoints => "breakpoints",
+            Self::LineNumbers => "line-numbers",
+            Self::Spacer => "spacer",
+            Self::Diff => "diff",
+            Self::Custom(name) => name,
+        }
+    }
 }
 
 impl std::str::FromStr for GutterType {
@@ -826,16 +1134,39 @@ impl std::str::FromStr for GutterType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "diagnostics" => Ok(Self::Diagnostics),
+            "breakpoints" => Ok(Self::Breakpoints),
             "spacer" => Ok(Self::Spacer),
             "line-numbers" => Ok(Self::LineNumbers),
             "diff" => Ok(Self::Diff),
+            name if !name.is_empty()
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '-') =>
+            {
+                Ok(Self::Custom(name.to_string()))
+            }
             _ => anyhow::bail!(
-                "Gutter type can only be `diagnostics`, `spacer`, `line-numbers` or `diff`."
+                "Gutter type can only be `diagnostics`, `breakpoints`, `spacer`, `line-numbers`, \
+                 `diff` or the name of a registered gutter."
             ),
         }
     }
 }
 
+impl TryFrom<String> for GutterType {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<GutterType> for String {
+    fn from(gutter: GutterType) -> Self {
+        gutter.as_str().to_string()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct WhitespaceConfig {
@@ -863,6 +1194,10 @@ pub enum WhitespaceRender {
         nnbsp: Option<WhitespaceRenderValue>,
         tab: Option<WhitespaceRenderValue>,
         newline: Option<WhitespaceRenderValue>,
+        /// Spaces and tabs at the end of a line, rendered in addition to `space` and `tab`.
+        trailing: Option<WhitespaceRenderValue>,
+        /// ASCII control characters, rendered as control pictures like `␀`.
+        control: Option<WhitespaceRenderValue>,
     },
 }
 
@@ -870,8 +1205,8 @@ pub enum WhitespaceRender {
 #[serde(rename_all = "kebab-case")]
 pub enum WhitespaceRenderValue {
     None,
-    // TODO
-    // Selection,
+    /// Only render whitespace inside of selections.
+    Selection,
     All,
 }
 
@@ -916,6 +1251,46 @@ pub fn newline(&self) -> WhitespaceRenderValue {
             } => newline.or(default).unwrap_or(WhitespaceRenderValue::None),
         }
     }
+    pub fn trailing(&self) -> WhitespaceRenderValue {
+        match *self {
+            Self::Basic(val) => val,
+            Self::Specific {
+                default, trailing, ..
+            } => trailing.or(default).unwrap_or(WhitespaceRenderValue::None),
+        }
+    }
+    pub fn control(&self) -> WhitespaceRenderValue {
+        match *self {
+            Self::Basic(val) => val,
+            Self::Specific {
+                default, control, ..
+            } => control.or(default).unwrap_or(WhitespaceRenderValue::None),
+        }
+    }
+
+    fn values(&self) -> [WhitespaceRenderValue; 7] {
+        [
+            self.space(),
+            self.nbsp(),
+            self.nnbsp(),
+            self.tab(),
+            self.newline(),
+            self.trailing(),
+            self.control(),
+        ]
+    }
+
+    /// Whether any kind of whitespace is rendered.
+    pub fn is_visible(&self) -> bool {
+        self.values()
+            .iter()
+            .any(|&value| value != WhitespaceRenderValue::None)
+    }
+
+    /// Whether any kind of whitespace is only rendered inside selections.
+    pub fn uses_selection(&self) -> bool {
+        self.values().contains(&WhitespaceRenderValue::Selection)
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -927,6 +1302,9 @@ pub struct AutoSave {
     /// Auto save on focus lost. Defaults to false.
     #[serde(default)]
     pub focus_lost: bool,
+    /// Format documents with `editor.auto-format` before auto saving them. Defaults to false.
+    #[serde(default)]
+    pub format: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -1003,6 +1381,11 @@ pub struct IndentGuidesConfig {
     pub render: bool,
     pub character: char,
     pub skip_levels: u8,
+    /// Emphasize the indent guide of the scope containing the cursor with the
+    /// `ui.virtual.indent-guide.active` style.
+    pub highlight_active_scope: bool,
+    /// The character of the emphasized indent guide, defaults to `character`.
+    pub active_character: Option<char>,
 }
 
 impl Default for IndentGuidesConfig {
@@ -1011,10 +1394,51 @@ fn default() -> Self {
             skip_levels: 0,
             render: false,
             character: '│',
+            highlight_active_scope: false,
+            active_character: None,
         }
     }
 }
 
+/// How a document is written to its file.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WriteMethod {
+    /// Move the file to a backup, write the file and restore the backup if writing failed.
+    /// The backup is a copy for hard links, which keeps them intact.
+    #[default]
+    Backup,
+    /// Write a temporary file next to the file and rename it over the file, so that the file
+    /// always has either the old or the new contents. Falls back to `backup` for hard links,
+    /// which would be replaced by a separate file.
+    Rename,
+    /// Truncate the file and write it, keeping its inode, hard links and ownership. The file
+    /// is left partially written if the editor is interrupted.
+    InPlace,
+}
+
+impl WriteMethod {
+    pub const VARIANTS: &'static [&'static str] = &["backup", "rename", "in-place"];
+}
+
+/// What is flushed to the disk when a document is written.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FsyncPolicy {
+    /// Leave flushing to the operating system.
+    None,
+    /// Flush the contents of the file.
+    #[default]
+    File,
+    /// Flush the contents of the file and its directory, so that a created or renamed file
+    /// survives a crash too.
+    Directory,
+}
+
+impl FsyncPolicy {
+    pub const VARIANTS: &'static [&'static str] = &["none", "file", "directory"];
+}
+
 /// Line ending configuration.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -1064,6 +1488,10 @@ pub enum PopupBorderConfig {
     Menu,
 }
 
+impl PopupBorderConfig {
+    pub const VARIANTS: &'static [&'static str] = &["none", "all", "popup", "menu"];
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 pub struct WordCompletion {
@@ -1080,6 +1508,28 @@ fn default() -> Self {
     }
 }
 
+/// The config options which take one of a fixed set of names, by their key as in `:set`, with
+/// the names. Used to complete the values of `:set`.
+pub const OPTION_VARIANTS: &[(&str, &[&str])] = &[
+    ("line-number", LineNumber::VARIANTS),
+    ("cursor-highlight-views", CursorHighlightViews::VARIANTS),
+    ("cursor-shape.normal", CursorKind::VARIANTS),
+    ("cursor-shape.select", CursorKind::VARIANTS),
+    ("cursor-shape.insert", CursorKind::VARIANTS),
+    ("color-depth", ColorDepthConfig::VARIANTS),
+    ("bufferline", BufferLine::VARIANTS),
+    ("write-method", WriteMethod::VARIANTS),
+    ("fsync", FsyncPolicy::VARIANTS),
+    ("popup-border", PopupBorderConfig::VARIANTS),
+    ("indent-heuristic", IndentationHeuristic::VARIANTS),
+    (
+        "kitty-keyboard-protocol",
+        KittyKeyboardProtocolConfig::VARIANTS,
+    ),
+    ("paste-file-paths", PasteFilePaths::VARIANTS),
+    ("throttle-rendering", ThrottleRenderingConfig::VARIANTS),
+];
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -1094,25 +1544,38 @@ fn default() -> Self {
             line_number: LineNumber::Absolute,
             cursorline: false,
             cursorcolumn: false,
+            cursor_highlight_views: CursorHighlightViews::default(),
+            dim_inactive_views: false,
+            inactive_views: InactiveViewsConfig::default(),
             gutters: GutterConfig::default(),
             middle_click_paste: true,
             auto_pairs: AutoPairConfig::default(),
+            auto_reload: false,
+            file_watcher: true,
+            auto_build_grammars: true,
+            abbreviations: HashMap::new(),
+            surround_pairs: HashMap::new(),
             auto_completion: true,
             path_completion: true,
             word_completion: WordCompletion::default(),
             auto_format: true,
+            format_confirm_threshold: None,
             default_yank_register: '"',
             auto_save: AutoSave::default(),
             idle_timeout: Duration::from_millis(250),
             completion_timeout: Duration::from_millis(250),
             preview_completion_insert: true,
+            completion_docs: true,
             completion_trigger_len: 2,
+            auto_completion_disabled_scopes: Vec::new(),
             auto_info: true,
             file_picker: FilePickerConfig::default(),
             file_explorer: FileExplorerConfig::default(),
             statusline: StatusLineConfig::default(),
             cursor_shape: CursorShapeConfig::default(),
             true_color: false,
+            color_depth: ColorDepthConfig::default(),
+            hardware_scroll: false,
             undercurl: false,
             search: SearchConfig::default(),
             lsp: LspConfig::default(),
@@ -1122,6 +1585,7 @@ fn default() -> Self {
             bufferline: BufferLine::default(),
             indent_guides: IndentGuidesConfig::default(),
             color_modes: false,
+            terminal_cursor_color: false,
             soft_wrap: SoftWrap {
                 enable: Some(false),
                 ..SoftWrap::default()
@@ -1133,19 +1597,39 @@ fn default() -> Self {
             default_line_ending: LineEndingConfig::default(),
             insert_final_newline: true,
             atomic_save: true,
+            write_method: WriteMethod::default(),
+            fsync: FsyncPolicy::default(),
             trim_final_newlines: false,
             trim_trailing_whitespace: false,
             smart_tab: Some(SmartTabConfig::default()),
+            sub_word: SubWordRules::default(),
             popup_border: PopupBorderConfig::None,
             indent_heuristic: IndentationHeuristic::default(),
             jump_label_alphabet: ('a'..='z').collect(),
+            increment_words: vec![vec!["true".to_string(), "false".to_string()]],
             inline_diagnostics: InlineDiagnosticsConfig::default(),
             end_of_line_diagnostics: DiagnosticFilter::Enable(Severity::Hint),
+            diagnostic_navigation: DiagnosticNavigationConfig::default(),
             clipboard_provider: ClipboardProvider::default(),
             editor_config: true,
+            large_file_threshold: 16 * 1024 * 1024,
+            syntax_max_file_size: None,
             rainbow_brackets: false,
+            rainbow_scopes: Vec::new(),
             kitty_keyboard_protocol: Default::default(),
+            esc_timeout: Duration::from_millis(20),
+            paste_file_paths: PasteFilePaths::default(),
             buffer_picker: BufferPickerConfig::default(),
+            dim_background: DimBackgroundConfig::default(),
+            persist_registers: false,
+            persist_history: false,
+            persist_selections: false,
+            persist_session: false,
+            restore_session: false,
+            winbar: false,
+            throttle_rendering: ThrottleRenderingConfig::default(),
+            edit_journal: false,
+            resources: ResourceConfig::default(),
         }
     }
 }
@@ -1197,9 +1681,23 @@ pub struct Editor {
     pub language_servers: helix_lsp::Registry,
     pub diagnostics: Diagnostics,
     pub diff_providers: DiffProviderRegistry,
+    /// The matches of the last search, see [`SearchMatches`].
+    pub search_matches: Option<SearchMatches>,
+    pub search_matches_controller: TaskController,
+    /// The toggles of the search prompt, they also apply to `search_next` and `search_prev`.
+    pub search_flags: SearchFlags,
+    /// The jump labels shown on the bufferline while `goto_word_all_views` waits for a label.
+    pub bufferline_jump_labels: Vec<(DocumentId, String)>,
+    pub quickfix: QuickfixList,
+    /// The command run each time a file of the workspace is saved, see `:watch`.
+    pub watch: Option<WatchTask>,
+    /// The split reused for documents opened from a pinned view, see [`Editor::preview_view`].
+    preview_view: Option<ViewId>,
 
     pub debug_adapters: dap::registry::Registry,
     pub breakpoints: HashMap<PathBuf, Vec<Breakpoint>>,
+    /// Gutters registered by features, shown when their name is part of the gutter layout.
+    pub gutter_providers: HashMap<String, Gutter>,
 
     pub syn_loader: Arc<ArcSwap<syntax::Loader>>,
     pub theme_loader: Arc<theme::Loader>,
@@ -1209,6 +1707,15 @@ pub struct Editor {
     /// The currently applied editor theme. While previewing a theme, the previewed theme
     /// is set here.
     pub theme: Theme,
+    /// The theme of unfocused views from `editor.inactive-views.theme`, loaded when the config
+    /// changes.
+    pub inactive_theme: Option<Theme>,
+    /// Whether the terminal reported support for true color. Themes which are not 16 color
+    /// themes are only applied if this, the `true-color` option or `COLORTERM` allow it.
+    pub terminal_true_color: bool,
+    /// Whether the terminal can show images. Image files are otherwise previewed with their
+    /// format and dimensions.
+    pub terminal_images: bool,
 
     /// The primary Selection prior to starting a goto_line_number preview. This is
     /// restored when the preview is aborted, or added to the jumplist when it is
@@ -1216,6 +1723,8 @@ pub struct Editor {
     pub last_selection: Option<Selection>,
 
     pub status_msg: Option<(Cow<'static, str>, Severity)>,
+    /// Every status message, see `:messages`.
+    pub messages: MessageLog,
     pub autoinfo: Option<Info>,
 
     pub config: Arc<dyn DynAccess<Config>>,
@@ -1225,6 +1734,13 @@ pub struct Editor {
     redraw_timer: Pin<Box<Sleep>>,
     last_motion: Option<Motion>,
     pub last_completion: Option<CompleteAction>,
+    /// The layout of the completion documentation panel, kept across completions.
+    pub completion_docs: CompletionDocsLayout,
+    /// The running REPLs by language.
+    pub repls: HashMap<String, Repl>,
+    /// The shell of the terminal panel, started with `:terminal`. It keeps running while the
+    /// panel is hidden.
+    pub terminal: Option<Terminal>,
     last_cwd: Option<PathBuf>,
 
     pub exit_code: i32,
@@ -1247,16 +1763,78 @@ pub struct Editor {
 
     pub mouse_down_range: Option<Range>,
     pub cursor_cache: CursorCache,
+    /// Whether the frontend detected that the terminal is slow to draw frames, see
+    /// [`Editor::throttle_rendering`].
+    pub slow_terminal: bool,
+    /// Timings of the main loop recorded by the frontend, see `:metrics`.
+    pub metrics: Metrics,
 }
 
 pub type Motion = Box<dyn Fn(&mut Editor)>;
 
+/// Options toggled inside the search prompt with `Alt-c`, `Alt-w` and `Alt-r`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchFlags {
+    pub case: SearchCase,
+    /// Only match whole words.
+    pub whole_word: bool,
+    /// Match the query literally instead of as a regex.
+    pub literal: bool,
+}
+
+/// How the search prompt matches case, cycled with `Alt-c`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchCase {
+    /// As configured with the `search.smart-case` option.
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl SearchCase {
+    /// The next mode of the `Alt-c` toggle.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Smart => Self::Sensitive,
+            Self::Sensitive => Self::Insensitive,
+            Self::Insensitive => Self::Smart,
+        }
+    }
+}
+
+/// The matches of the last search in a document, shown by the `search-matches` statusline
+/// element. Large documents are searched in the background, `matches` then grows in batches
+/// until `complete` is set.
+#[derive(Debug, Clone)]
+pub struct SearchMatches {
+    pub doc: DocumentId,
+    /// The document version the matches were computed for.
+    pub version: i32,
+    pub query: String,
+    pub flags: SearchFlags,
+    /// The char ranges of the matches found so far, in document order.
+    pub matches: Vec<std::ops::Range<usize>>,
+    /// Whether the whole document has been searched.
+    pub complete: bool,
+}
+
+impl SearchMatches {
+    /// Returns the one-based index of the last match starting at or before `pos`, or zero if
+    /// there is none.
+    pub fn index_at(&self, pos: usize) -> usize {
+        self.matches.partition_point(|mat| mat.start <= pos)
+    }
+}
+
 #[derive(Debug)]
 pub enum EditorEvent {
     DocumentSaved(DocumentSavedEventResult),
     ConfigEvent(ConfigEvent),
     LanguageServerMessage((LanguageServerId, Call)),
     DebuggerEvent((DebugAdapterId, dap::Payload)),
+    /// Output of the shell of the terminal panel, `None` once it exited.
+    TerminalOutput(Option<Vec<u8>>),
     IdleTimer,
     Redraw,
 }
@@ -1287,6 +1865,26 @@ pub enum CompleteAction {
     },
 }
 
+/// A REPL process started with `send_to_repl`, see `repl` in languages.toml. Dropping it
+/// kills the process.
+#[derive(Debug)]
+pub struct Repl {
+    /// Code written to the process's stdin.
+    pub input: tokio::sync::mpsc::UnboundedSender<String>,
+    /// The scratch buffer the process's output is appended to.
+    pub output: DocumentId,
+    pub controller: helix_event::TaskController,
+}
+
+/// The state of the documentation panel of the completion menu, changed while the menu is
+/// open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionDocsLayout {
+    pub visible: bool,
+    /// The width the panel was resized to, it fits its contents if `None`.
+    pub width: Option<u16>,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Action {
     Load,
@@ -1312,6 +1910,16 @@ pub enum CloseError {
     SaveError(anyhow::Error),
 }
 
+/// Loads the theme of `editor.inactive-views.theme`. A theme which fails to load is logged and
+/// unfocused views use the current theme.
+fn load_inactive_theme(loader: &theme::Loader, config: &Config) -> Option<Theme> {
+    let name = config.inactive_views.theme.as_ref()?;
+    loader
+        .load(name)
+        .map_err(|err| log::warn!("Failed to load the inactive views theme '{name}': {err}"))
+        .ok()
+}
+
 impl Editor {
     pub fn new(
         mut area: Rect,
@@ -1323,13 +1931,32 @@ pub fn new(
         let language_servers = helix_lsp::Registry::new(syn_loader.clone());
         let conf = config.load();
         let auto_pairs = (&conf.auto_pairs).into();
+        crate::resources::apply(&conf.resources);
+
+        let mut registers = Registers::new(Box::new(arc_swap::access::Map::new(
+            Arc::clone(&config),
+            |config: &Config| &config.clipboard_provider,
+        )));
+        if conf.persist_registers {
+            if let Err(err) = registers.restore(&register::registers_file()) {
+                log::error!("Failed to restore registers: {err}");
+            }
+        }
+        if conf.persist_history {
+            if let Err(err) = registers.restore_history(&register::history_file()) {
+                log::error!("Failed to restore the command history: {err}");
+            }
+        }
 
         // HAXX: offset the render area height by 1 to account for prompt/commandline
         area.height -= 1;
 
+        let mut tree = Tree::new(area);
+        tree.set_winbar(conf.winbar);
+
         Self {
             mode: Mode::Normal,
-            tree: Tree::new(area),
+            tree,
             next_document_id: DocumentId::default(),
             documents: BTreeMap::new(),
             saves: HashMap::new(),
@@ -1340,25 +1967,40 @@ pub fn new(
             macro_recording: None,
             macro_replaying: Vec::new(),
             theme: theme_loader.default(),
+            inactive_theme: load_inactive_theme(&theme_loader, &conf),
+            terminal_true_color: false,
+            terminal_images: false,
             language_servers,
             diagnostics: Diagnostics::new(),
             diff_providers: DiffProviderRegistry::default(),
+            search_matches: None,
+            search_matches_controller: TaskController::new(),
+            search_flags: SearchFlags::default(),
+            bufferline_jump_labels: Vec::new(),
+            quickfix: QuickfixList::default(),
+            watch: None,
+            preview_view: None,
             debug_adapters: dap::registry::Registry::new(),
             breakpoints: HashMap::new(),
+            gutter_providers: HashMap::new(),
             syn_loader,
             theme_loader,
             last_theme: None,
             last_selection: None,
-            registers: Registers::new(Box::new(arc_swap::access::Map::new(
-                Arc::clone(&config),
-                |config: &Config| &config.clipboard_provider,
-            ))),
+            registers,
             status_msg: None,
+            messages: MessageLog::default(),
             autoinfo: None,
             idle_timer: Box::pin(sleep(conf.idle_timeout)),
             redraw_timer: Box::pin(sleep(Duration::MAX)),
             last_motion: None,
             last_completion: None,
+            completion_docs: CompletionDocsLayout {
+                visible: conf.completion_docs,
+                width: None,
+            },
+            repls: HashMap::new(),
+            terminal: None,
             last_cwd: None,
             config,
             auto_pairs,
@@ -1368,6 +2010,8 @@ pub fn new(
             handlers,
             mouse_down_range: None,
             cursor_cache: CursorCache::default(),
+            slow_terminal: false,
+            metrics: Metrics::default(),
         }
     }
 
@@ -1403,11 +2047,25 @@ pub fn config(&self) -> DynGuard<Config> {
         self.config.load()
     }
 
+    /// Whether rendering is throttled: frames are drawn less often and decorations which
+    /// follow the cursor, like the cursorline, are not drawn, see `editor.throttle-rendering`.
+    pub fn throttle_rendering(&self) -> bool {
+        match self.config().throttle_rendering {
+            ThrottleRenderingConfig::Auto => self.slow_terminal,
+            ThrottleRenderingConfig::Always => true,
+            ThrottleRenderingConfig::Never => false,
+        }
+    }
+
     /// Call if the config has changed to let the editor update all
     /// relevant members.
     pub fn refresh_config(&mut self, old_config: &Config) {
         let config = self.config();
         self.auto_pairs = (&config.auto_pairs).into();
+        crate::resources::apply(&config.resources);
+        if config.inactive_views.theme != old_config.inactive_views.theme {
+            self.inactive_theme = load_inactive_theme(&self.theme_loader, &config);
+        }
         self.reset_idle_timer();
         self._refresh();
         helix_event::dispatch(crate::events::ConfigDidChange {
@@ -1431,29 +2089,50 @@ pub fn reset_idle_timer(&mut self) {
             .reset(Instant::now() + config.idle_timeout);
     }
 
+    /// Fires the idle timer after `timeout` unless it is already due sooner.
+    pub fn schedule_idle_timer(&mut self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        if deadline < self.idle_timer.deadline() {
+            self.idle_timer.as_mut().reset(deadline);
+        }
+    }
+
     pub fn clear_status(&mut self) {
         self.status_msg = None;
+        self.messages.clear_shown();
+    }
+
+    /// Logs a message and shows it in the status line, unless a more severe message was shown
+    /// there just before, see [`MessageLog`].
+    pub fn set_message<T: Into<Cow<'static, str>>>(&mut self, message: T, severity: Severity) {
+        let message = message.into();
+        if self
+            .messages
+            .push(&message, severity, std::time::Instant::now())
+        {
+            self.status_msg = Some((message, severity));
+        }
     }
 
     #[inline]
     pub fn set_status<T: Into<Cow<'static, str>>>(&mut self, status: T) {
         let status = status.into();
         log::debug!("editor status: {}", status);
-        self.status_msg = Some((status, Severity::Info));
+        self.set_message(status, Severity::Info);
     }
 
     #[inline]
     pub fn set_error<T: Into<Cow<'static, str>>>(&mut self, error: T) {
         let error = error.into();
         log::debug!("editor error: {}", error);
-        self.status_msg = Some((error, Severity::Error));
+        self.set_message(error, Severity::Error);
     }
 
     #[inline]
     pub fn set_warning<T: Into<Cow<'static, str>>>(&mut self, warning: T) {
         let warning = warning.into();
         log::warn!("editor warning: {}", warning);
-        self.status_msg = Some((warning, Severity::Warning));
+        self.set_message(warning, Severity::Warning);
     }
 
     #[inline]
@@ -1520,6 +2199,19 @@ pub fn language_server_by_id(
             .map(|client| &**client)
     }
 
+    /// Removes the diagnostics of a language server which was stopped or exited from all
+    /// documents, including those of files which aren't open.
+    pub fn clear_language_server_diagnostics(&mut self, server_id: LanguageServerId) {
+        for diags in self.diagnostics.values_mut() {
+            diags.retain(|(_, provider)| provider.language_server_id() != Some(server_id));
+        }
+        self.diagnostics.retain(|_, diags| !diags.is_empty());
+
+        for doc in self.documents_mut() {
+            doc.clear_diagnostics_for_language_server(server_id);
+        }
+    }
+
     /// Refreshes the language server for a given document
     pub fn refresh_language_servers(&mut self, doc_id: DocumentId) {
         self.launch_language_servers(doc_id)
@@ -1560,8 +2252,24 @@ pub fn move_path(&mut self, old_path: &Path, new_path: &Path) -> io::Result<()>
             fs::rename(old_path, &new_path)?;
         }
 
-        if let Some(doc) = self.document_by_path(old_path) {
-            self.set_doc_path(doc.id(), &new_path);
+        // Moving a directory moves the documents of all the files in it
+        let moved: Vec<_> = self
+            .documents()
+            .filter_map(|doc| {
+                let path = doc.path()?;
+                let new_doc_path = if path == old_path {
+                    new_path.clone()
+                } else if is_dir {
+                    new_path.join(path.strip_prefix(old_path).ok()?)
+                } else {
+                    return None;
+                };
+                Some((doc.id(), new_doc_path))
+            })
+            .collect();
+        for (doc_id, new_doc_path) in moved {
+            self.set_doc_path(doc_id, &new_doc_path);
+            doc_mut!(self, &doc_id).refresh_version_control(&new_doc_path, &self.diff_providers);
         }
         let is_dir = new_path.is_dir();
         for ls in self.language_servers.iter_clients() {
@@ -1581,6 +2289,80 @@ pub fn move_path(&mut self, old_path: &Path, new_path: &Path) -> io::Result<()>
         Ok(())
     }
 
+    /// Deletes the file or directory at `path`, moving it to the trash unless `permanently`
+    /// is set. Documents of the deleted files are closed.
+    pub fn remove_path(&mut self, path: &Path, permanently: bool) -> io::Result<()> {
+        let is_dir = path.is_dir();
+        let language_servers: Vec<_> = self
+            .language_servers
+            .iter_clients()
+            .filter(|client| client.is_initialized())
+            .cloned()
+            .collect();
+        for language_server in language_servers {
+            let Some(request) = language_server.will_delete(path, is_dir) else {
+                continue;
+            };
+            let edit = match helix_lsp::block_on(request) {
+                Ok(edit) => edit.unwrap_or_default(),
+                Err(err) => {
+                    log::error!("invalid willDelete response: {err:?}");
+                    continue;
+                }
+            };
+            if let Err(err) = self.apply_workspace_edit(language_server.offset_encoding(), &edit) {
+                log::error!("failed to apply workspace edit: {err:?}")
+            }
+        }
+
+        if permanently {
+            if is_dir {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        } else {
+            helix_stdx::trash::trash(path)?;
+        }
+
+        let doc_ids: Vec<_> = self
+            .documents()
+            .filter(|doc| {
+                doc.path()
+                    .is_some_and(|doc_path| doc_path.starts_with(path))
+            })
+            .map(|doc| doc.id())
+            .collect();
+        for doc_id in doc_ids {
+            let _ = self.close_document(doc_id, true);
+        }
+
+        for ls in self.language_servers.iter_clients() {
+            if ls.is_initialized() {
+                ls.did_delete(path, is_dir);
+            }
+        }
+        self.language_servers
+            .file_event_handler
+            .file_changed(path.to_owned());
+        Ok(())
+    }
+
+    /// Copies the file at `old_path` to `new_path` and notifies the language servers.
+    pub fn copy_path(&mut self, old_path: &Path, new_path: &Path) -> io::Result<()> {
+        fs::copy(old_path, new_path)?;
+        let new_path = canonicalize(new_path);
+        for ls in self.language_servers.iter_clients() {
+            if ls.is_initialized() {
+                ls.did_create(&new_path, false);
+            }
+        }
+        self.language_servers
+            .file_event_handler
+            .file_changed(new_path);
+        Ok(())
+    }
+
     pub fn set_doc_path(&mut self, doc_id: DocumentId, path: &Path) {
         let doc = doc_mut!(self, &doc_id);
         let old_path = doc.path();
@@ -1638,7 +2420,13 @@ fn launch_language_servers(&mut self, doc_id: DocumentId) {
         // store only successfully started language servers
         let language_servers = lang.as_ref().map_or_else(HashMap::default, |language| {
             self.language_servers
-                .get(language, path.as_ref(), root_dirs, config.lsp.snippets)
+                .get(
+                    language,
+                    path.as_ref(),
+                    root_dirs,
+                    config.lsp.snippets,
+                    &doc.disabled_language_servers,
+                )
                 .filter_map(|(lang, client)| match client {
                     Ok(client) => Some((lang, client)),
                     Err(err) => {
@@ -1713,6 +2501,7 @@ fn _refresh(&mut self) {
             }
         }
 
+        self.tree.set_winbar(config.winbar);
         for (view, _) in self.tree.views_mut() {
             let doc = doc_mut!(self, &view.doc);
             view.sync_changes(doc);
@@ -1747,6 +2536,19 @@ pub fn switch(&mut self, id: DocumentId, action: Action) {
             self.enter_normal_mode();
         }
 
+        let view = view!(self);
+        if matches!(action, Action::Replace) && view.pinned && view.doc != id {
+            // pinned views keep their document, show the new one in the preview split instead
+            match self.preview_view() {
+                Some(preview) => self.focus(preview),
+                None => {
+                    self.switch(id, Action::VerticalSplit);
+                    self.preview_view = Some(self.tree.focus);
+                    return;
+                }
+            }
+        }
+
         let focust_lost = match action {
             Action::Replace => {
                 let (view, doc) = current_ref!(self);
@@ -1818,6 +2620,11 @@ pub fn switch(&mut self, id: DocumentId, action: Action) {
                     .try_get(self.tree.focus)
                     .filter(|v| id == v.doc) // Different Document
                     .cloned()
+                    .map(|mut view| {
+                        view.pinned = false;
+                        view.scroll_bind = false;
+                        view
+                    })
                     .unwrap_or_else(|| View::new(id, self.config().gutters.clone()));
                 let view_id = self.tree.split(
                     view,
@@ -1844,6 +2651,16 @@ pub fn switch(&mut self, id: DocumentId, action: Action) {
         }
     }
 
+    /// The split which documents are opened in when the focused view is pinned, if it is
+    /// still open in the current layout and not pinned itself.
+    pub fn preview_view(&self) -> Option<ViewId> {
+        let preview = self.preview_view?;
+        self.tree
+            .views()
+            .any(|(view, _)| view.id == preview && !view.pinned)
+            .then_some(preview)
+    }
+
     /// Generate an id for a new document and register it.
     fn new_document(&mut self, mut doc: Document) -> DocumentId {
         let id = self.next_document_id;
@@ -1915,23 +2732,21 @@ pub fn open(&mut self, path: &Path, action: Action) -> Result<DocumentId, Docume
                 self.syn_loader.clone(),
             )?;
 
-            let diagnostics =
-                Editor::doc_diagnostics(&self.language_servers, &self.diagnostics, &doc);
-            doc.replace_diagnostics(diagnostics, &[], None);
-
-            if let Some(diff_base) = self.diff_providers.get_diff_base(&path) {
-                doc.set_diff_base(diff_base);
+            // Large files are read in the background and finish opening once they are loaded,
+            // see `Editor::finish_loading`
+            if doc.is_loading() {
+                let id = self.new_document(doc);
+                helix_event::dispatch(DocumentDidStartLoading {
+                    editor: self,
+                    doc: id,
+                });
+                self.switch(id, action);
+                return Ok(id);
             }
-            doc.set_version_control_head(self.diff_providers.get_current_head_name(&path));
 
+            self.prepare_opened_document(&mut doc, &path);
             let id = self.new_document(doc);
-            self.launch_language_servers(id);
-
-            helix_event::dispatch(DocumentDidOpen {
-                editor: self,
-                doc: id,
-            });
-
+            self.did_open(id);
             id
         };
 
@@ -1940,6 +2755,50 @@ pub fn open(&mut self, path: &Path, action: Action) -> Result<DocumentId, Docume
         Ok(id)
     }
 
+    /// Replaces the placeholder text of a large file opened while it was read in the background
+    /// with the loaded text, then finishes opening it like [`Editor::open`] does.
+    pub fn finish_loading(&mut self, doc_id: DocumentId, loaded: LoadedText) {
+        let Some(mut doc) = self.documents.remove(&doc_id) else {
+            return;
+        };
+        doc.finish_loading(loaded);
+        if let Some(path) = doc.path().cloned() {
+            self.prepare_opened_document(&mut doc, &path);
+        }
+        self.documents.insert(doc_id, doc);
+        self.did_open(doc_id);
+    }
+
+    /// Loads the diagnostics, the version control state and the named selections of a document
+    /// opened from `path`.
+    fn prepare_opened_document(&self, doc: &mut Document, path: &Path) {
+        let diagnostics = Editor::doc_diagnostics(&self.language_servers, &self.diagnostics, doc);
+        doc.replace_diagnostics(diagnostics, &[], None);
+
+        if let Some(diff_base) = self.diff_providers.get_diff_base(path) {
+            doc.set_diff_base(diff_base);
+        }
+        doc.set_version_control_head(self.diff_providers.get_current_head_name(path));
+
+        if self.config().persist_selections {
+            let file = named_selections::selections_file(path);
+            match named_selections::load(&file, doc.text().slice(..)) {
+                Ok(selections) => doc.named_selections = selections,
+                Err(err) => log::error!("Failed to restore named selections: {err}"),
+            }
+        }
+    }
+
+    /// Starts the language servers of a newly opened document and dispatches
+    /// [`DocumentDidOpen`].
+    fn did_open(&mut self, doc_id: DocumentId) {
+        self.launch_language_servers(doc_id);
+        helix_event::dispatch(DocumentDidOpen {
+            editor: self,
+            doc: doc_id,
+        });
+    }
+
     pub fn close(&mut self, id: ViewId) {
         // Remove selections for the closed view on all documents.
         for doc in self.documents_mut() {
@@ -1968,7 +2827,7 @@ enum Action {
 
         let actions: Vec<Action> = self
             .tree
-            .views_mut()
+            .all_views_mut()
             .filter_map(|(view, _focus)| {
                 view.remove_document(&doc_id);
 
@@ -1998,6 +2857,11 @@ enum Action {
         }
 
         let doc = self.documents.remove(&doc_id).unwrap();
+        if self.config().persist_selections {
+            if let Err(err) = named_selections::save_document(&doc) {
+                log::error!("Failed to save named selections: {err}");
+            }
+        }
 
         // If the document we removed was visible in all views, we will have no more views. We don't
         // want to close the editor just for a simple buffer close, so we need to create a new view
@@ -2120,10 +2984,118 @@ pub fn transpose_view(&mut self) {
         self.tree.transpose();
     }
 
+    /// Creates a new layout (tab page) after the current one, showing the current document,
+    /// and switches to it. Layouts have their own splits but share the open documents.
+    pub fn new_layout(&mut self, name: Option<String>) {
+        let doc_id = view!(self).doc;
+        let name = name.unwrap_or_else(|| (self.tree.layout_count() + 1).to_string());
+        self.tree.new_layout(name);
+
+        let view = View::new(doc_id, self.config().gutters.clone());
+        let view_id = self.tree.insert(view);
+        let doc = doc_mut!(self, &doc_id);
+        doc.ensure_view_init(view_id);
+        doc.mark_as_focused();
+        self._refresh();
+    }
+
+    /// Switches to the layout at `idx`. Returns `false` if there is no such layout.
+    pub fn switch_layout(&mut self, idx: usize) -> bool {
+        if idx >= self.tree.layout_count() {
+            return false;
+        }
+        if self.tree.switch_layout(idx) {
+            // the views of inactive layouts were not kept in sync with document changes
+            self._refresh();
+            let doc_id = view!(self).doc;
+            doc_mut!(self, &doc_id).mark_as_focused();
+        }
+        true
+    }
+
+    /// Closes the current layout and all of its views, the documents stay open. Returns
+    /// `false` if it is the only layout.
+    pub fn close_layout(&mut self) -> bool {
+        let views = self.tree.close_layout();
+        if views.is_empty() {
+            return false;
+        }
+        for doc in self.documents_mut() {
+            for &view_id in &views {
+                doc.remove_view(view_id);
+            }
+        }
+        self._refresh();
+        true
+    }
+
     pub fn should_close(&self) -> bool {
         self.tree.is_empty()
     }
 
+    /// Registers a gutter which is shown when `name` is part of the gutter layout, either in
+    /// the `gutters` option or in a language's `gutters`. Custom gutters are one column wide.
+    pub fn register_gutter(&mut self, name: impl Into<String>, gutter: Gutter) {
+        self.gutter_providers.insert(name.into(), gutter);
+    }
+
+    /// Toggles scroll binding for the given view. Returns whether the view is now bound.
+    pub fn toggle_scroll_bind(&mut self, id: ViewId) -> bool {
+        let view = self.tree.get_mut(id);
+        let doc = &self.documents[&view.doc];
+        view.scroll_bind = !view.scroll_bind;
+        view.scroll_bind_line = top_line(doc, view.id);
+        view.scroll_bind
+    }
+
+    /// Scrolls the scroll-bound views of the current layout along with the view `id`, called
+    /// after commands which may have scrolled it. When the top line of the view changed since
+    /// the last sync, the other bound views are scrolled by the same number of lines. Their
+    /// cursors are moved to stay in view so that focusing them doesn't scroll them back. All
+    /// other views keep their own viewport.
+    pub fn sync_scroll_bind(&mut self, id: ViewId) {
+        let Some(view) = self.tree.try_get(id).filter(|view| view.scroll_bind) else {
+            return;
+        };
+        let top = top_line(&self.documents[&view.doc], id);
+        let delta = top as isize - view.scroll_bind_line as isize;
+        if delta == 0 {
+            return;
+        }
+        self.tree.get_mut(id).scroll_bind_line = top;
+
+        let scrolloff = self.config().scrolloff;
+        for (view, _) in self.tree.views_mut() {
+            if view.id == id || !view.scroll_bind {
+                continue;
+            }
+            let doc = doc_mut!(self, &view.doc);
+            let text = doc.text().slice(..);
+            let last_line = text.len_lines().saturating_sub(1);
+            let line = top_line(doc, view.id)
+                .saturating_add_signed(delta)
+                .min(last_line);
+            let anchor = text.line_to_char(line);
+
+            let height = view.inner_height();
+            let scrolloff = scrolloff.min(height.saturating_sub(1) / 2);
+            let cursor_line = text.char_to_line(doc.selection(view.id).primary().cursor(text));
+            let min = line + scrolloff;
+            let max = (line + height.saturating_sub(scrolloff + 1)).max(min);
+            let cursor = (!(min..=max).contains(&cursor_line))
+                .then(|| text.line_to_char(cursor_line.clamp(min, max).min(last_line)));
+
+            let mut offset = doc.view_offset(view.id);
+            offset.anchor = anchor;
+            offset.vertical_offset = 0;
+            doc.set_view_offset(view.id, offset);
+            if let Some(pos) = cursor {
+                doc.set_selection(view.id, Selection::point(pos));
+            }
+            view.scroll_bind_line = line;
+        }
+    }
+
     pub fn ensure_cursor_in_view(&mut self, id: ViewId) {
         let config = self.config();
         let view = self.tree.get(id);
@@ -2230,6 +3202,204 @@ pub fn cursor(&self) -> (Option<Position>, CursorKind) {
         }
     }
 
+    /// The color of the terminal cursor in the current mode, see
+    /// [`Config::terminal_cursor_color`]. [`Color::Reset`] keeps the terminal's default.
+    pub fn cursor_color(&self) -> Color {
+        if !self.config().terminal_cursor_color {
+            return Color::Reset;
+        }
+        self.theme
+            .get(&format!("ui.cursor.primary.{}", self.mode))
+            .bg
+            .unwrap_or(Color::Reset)
+    }
+
+    /// Loads the content appended to the file of a document followed with `:follow`, see
+    /// [`Editor::append_pinned`]. Returns whether the document is still followed.
+    pub fn follow_document(&mut self, doc_id: DocumentId) -> bool {
+        if !self
+            .documents
+            .get(&doc_id)
+            .is_some_and(Document::is_following)
+        {
+            return false;
+        }
+        let result = self.append_pinned(doc_id, |doc, view, diff_providers| {
+            doc.load_appended(view, diff_providers)
+        });
+        if let Err(err) = result {
+            let doc = doc_mut!(self, &doc_id);
+            let _ = doc.set_following(false);
+            let message = format!("stopped following {}: {err}", doc.display_name());
+            self.set_error(message);
+            return false;
+        }
+        true
+    }
+
+    /// Appends `text` to a document, for example data read from a named pipe, see
+    /// [`Editor::append_pinned`].
+    pub fn append_to_document(&mut self, doc_id: DocumentId, text: &str) {
+        let _ = self.append_pinned(doc_id, |doc, view, _| {
+            doc.append(view, text);
+            Ok(true)
+        });
+    }
+
+    /// Adds content to the end of a document with `append`, which returns whether the text
+    /// changed. Views whose cursor is on the last line move to the new last line, so they stay
+    /// pinned to the end of the document until the user moves away.
+    fn append_pinned(
+        &mut self,
+        doc_id: DocumentId,
+        append: impl FnOnce(&mut Document, &mut View, &DiffProviderRegistry) -> Result<bool, Error>,
+    ) -> Result<(), Error> {
+        let scrolloff = self.config().scrolloff;
+        let focus = self.tree.focus;
+        let Some(doc) = self.documents.get_mut(&doc_id) else {
+            return Ok(());
+        };
+        let view_ids: Vec<_> = doc
+            .selections()
+            .keys()
+            .copied()
+            .filter(|view_id| self.tree.contains(*view_id))
+            .collect();
+
+        let last_line = |text: RopeSlice| {
+            if text.line(text.len_lines() - 1).len_chars() == 0 {
+                text.len_lines().saturating_sub(2)
+            } else {
+                text.len_lines() - 1
+            }
+        };
+        let text = doc.text().slice(..);
+        let pinned: Vec<_> = view_ids
+            .iter()
+            .copied()
+            .filter(|&view_id| {
+                let cursor = doc.selection(view_id).primary().cursor(text);
+                text.char_to_line(cursor) >= last_line(text)
+            })
+            .collect();
+
+        // Documents which aren't shown are changed through the focused view, like on reload
+        let view_id = view_ids.first().copied().unwrap_or(focus);
+        doc.ensure_view_init(view_id);
+        let view = self.tree.get_mut(view_id);
+        view.sync_changes(doc);
+        if !append(doc, view, &self.diff_providers)? {
+            return Ok(());
+        }
+
+        let text = doc.text().slice(..);
+        let pos = text.line_to_char(last_line(text));
+        for view_id in pinned {
+            doc.set_selection(view_id, Selection::point(pos));
+            let view = self.tree.get_mut(view_id);
+            view.ensure_cursor_in_view(doc, scrolloff);
+        }
+        Ok(())
+    }
+
+    /// Reloads the documents whose files changed on disk, see `editor.auto-reload`. Unsaved
+    /// changes are merged with the changes on disk. Conflicts replace the quickfix list,
+    /// returns whether there were any.
+    pub fn reload_changed_documents(&mut self) -> bool {
+        let scrolloff = self.config().scrolloff;
+        let focus = self.tree.focus;
+        let doc_ids: Vec<_> = self
+            .documents
+            .values()
+            .filter(|doc| doc.changed_on_disk())
+            .map(|doc| doc.id())
+            .collect();
+
+        let mut conflicts = Vec::new();
+        let mut errors = Vec::new();
+        for doc_id in doc_ids {
+            let doc = self.documents.get_mut(&doc_id).unwrap();
+            let view_id = doc
+                .selections()
+                .keys()
+                .copied()
+                .find(|view_id| self.tree.contains(*view_id))
+                .unwrap_or(focus);
+            doc.ensure_view_init(view_id);
+            let view = self.tree.get_mut(view_id);
+            view.sync_changes(doc);
+
+            let result = if doc.is_modified() {
+                doc.reload_merged(view, &self.diff_providers)
+            } else {
+                doc.reload(view, &self.diff_providers).map(|_| Vec::new())
+            };
+            let lines = match result {
+                Ok(lines) => lines,
+                Err(err) => {
+                    errors.push(format!("{}: {err}", doc.display_name()));
+                    continue;
+                }
+            };
+            view.ensure_cursor_in_view(doc, scrolloff);
+
+            let path = doc.path().cloned();
+            if let Some(path) = &path {
+                self.language_servers
+                    .file_event_handler
+                    .file_changed(path.clone());
+            }
+            let text = doc.text();
+            conflicts.extend(lines.into_iter().map(|lines| {
+                QuickfixEntry {
+                    doc_id: Some(doc_id),
+                    path: path.clone(),
+                    start: Position::new(lines.start, 0),
+                    end: Position::new(lines.end, 0),
+                    text: format!("merge conflict: {}", text.line(lines.start + 1))
+                        .trim_end()
+                        .to_string(),
+                }
+            }));
+        }
+
+        if !errors.is_empty() {
+            self.set_error(format!("failed to reload {}", errors.join(", ")));
+        }
+        if conflicts.is_empty() {
+            return false;
+        }
+        self.set_warning(format!(
+            "{} conflicts merging changes from disk, resolve them or undo the merge",
+            conflicts.len()
+        ));
+        self.quickfix = QuickfixList::new("merge conflicts", conflicts);
+        true
+    }
+
+    /// Saves the named registers for the current workspace if `persist-registers` is enabled,
+    /// the command and search histories if `persist-history` is enabled, the named
+    /// selections of open documents if `persist-selections` is enabled and the session if
+    /// `persist-session` is enabled.
+    pub fn save_registers(&self) -> anyhow::Result<()> {
+        let config = self.config();
+        if config.persist_registers {
+            self.registers.save(&register::registers_file())?;
+        }
+        if config.persist_history {
+            self.registers.save_history(&register::history_file())?;
+        }
+        if config.persist_selections {
+            for doc in self.documents() {
+                named_selections::save_document(doc)?;
+            }
+        }
+        if config.persist_session {
+            session::save(self, &session::session_file())?;
+        }
+        Ok(())
+    }
+
     /// Closes language servers with timeout. The default timeout is 10000 ms, use
     /// `timeout` parameter to override this.
     pub async fn close_language_servers(
@@ -2291,6 +3461,11 @@ pub async fn wait_event(&mut self) -> EditorEvent {
                     self.redraw_timer.as_mut().reset(Instant::now() + Duration::from_secs(86400 * 365 * 30));
                     return EditorEvent::Redraw
                 }
+                // After the redraw timer, so that a shell printing without a pause doesn't
+                // keep the screen from updating
+                output = terminal::read_output(self.terminal.as_mut()) => {
+                    return EditorEvent::TerminalOutput(output)
+                }
                 _ = &mut self.idle_timer  => {
                     return EditorEvent::IdleTimer
                 }
@@ -2298,6 +3473,19 @@ pub async fn wait_event(&mut self) -> EditorEvent {
         }
     }
 
+    /// Handles [`EditorEvent::TerminalOutput`]: draws the output to the terminal's screen, or
+    /// closes the terminal once its shell exited.
+    pub fn handle_terminal_output(&mut self, output: Option<Vec<u8>>) {
+        let Some(output) = output else {
+            self.terminal = None;
+            self.set_status("The terminal's shell exited");
+            return;
+        };
+        if let Some(terminal) = &mut self.terminal {
+            terminal.process(&output);
+        }
+    }
+
     pub async fn flush_writes(&mut self) -> anyhow::Result<()> {
         while self.write_count > 0 {
             if let Some(save_event) = self.save_queue.next().await {
@@ -2425,6 +3613,13 @@ fn jump_to(&mut self, view_id: ViewId, dest_doc_id: DocumentId, mut selection: S
     }
 }
 
+/// Returns the document line at the top of the view.
+fn top_line(doc: &Document, view_id: ViewId) -> usize {
+    let text = doc.text().slice(..);
+    let anchor = doc.view_offset(view_id).anchor.min(text.len_chars());
+    text.char_to_line(anchor)
+}
+
 fn try_restore_indent(doc: &mut Document, view: &mut View) {
     use helix_core::{
         chars::char_is_whitespace,
@@ -2486,3 +3681,34 @@ pub fn reset(&self) {
         self.0.set(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+
+    /// Checks that each of `variants` is the name `T` is serialized with.
+    fn assert_variants<T: DeserializeOwned + Serialize>(variants: &[&str]) {
+        for variant in variants {
+            let value: T = serde_json::from_value(serde_json::json!(variant))
+                .unwrap_or_else(|err| panic!("{variant:?}: {err}"));
+            assert_eq!(serde_json::json!(value), serde_json::json!(variant));
+        }
+    }
+
+    #[test]
+    fn option_variants_round_trip() {
+        assert_variants::<LineNumber>(LineNumber::VARIANTS);
+        assert_variants::<CursorHighlightViews>(CursorHighlightViews::VARIANTS);
+        assert_variants::<CursorKind>(CursorKind::VARIANTS);
+        assert_variants::<ColorDepthConfig>(ColorDepthConfig::VARIANTS);
+        assert_variants::<BufferLine>(BufferLine::VARIANTS);
+        assert_variants::<WriteMethod>(WriteMethod::VARIANTS);
+        assert_variants::<FsyncPolicy>(FsyncPolicy::VARIANTS);
+        assert_variants::<PopupBorderConfig>(PopupBorderConfig::VARIANTS);
+        assert_variants::<IndentationHeuristic>(IndentationHeuristic::VARIANTS);
+        assert_variants::<KittyKeyboardProtocolConfig>(KittyKeyboardProtocolConfig::VARIANTS);
+        assert_variants::<PasteFilePaths>(PasteFilePaths::VARIANTS);
+        assert_variants::<ThrottleRenderingConfig>(ThrottleRenderingConfig::VARIANTS);
+    }
+}
@@ -9,6 +9,14 @@
         editor: &'a mut Editor,
         doc: DocumentId
     }
+    // called instead of `DocumentDidOpen` when a large file is opened before its text is read,
+    // `DocumentDidOpen` follows once it is loaded
+    DocumentDidStartLoading<'a> {
+        editor: &'a mut Editor,
+        doc: DocumentId
+    }
+    // called when an edit of a document is rejected because its text is still loading
+    DocumentEditRejected<'a> { doc: &'a Document }
     DocumentDidChange<'a> {
         doc: &'a mut Document,
         view: ViewId,
@@ -20,6 +28,8 @@
         editor: &'a mut Editor,
         doc: Document
     }
+    // called once a document was written to its file
+    DocumentDidSave<'a> { editor: &'a mut Editor, doc: DocumentId }
     SelectionDidChange<'a> { doc: &'a mut Document, view: ViewId }
     DiagnosticsDidChange<'a> { editor: &'a mut Editor, doc: DocumentId }
     // called **after** a document loses focus (but not when its closed)
@@ -126,6 +126,9 @@ pub fn expand<'a>(editor: &Editor, token: Token<'a>) -> Result<Cow<'a, str>> {
                 ))
             }
         }
+        TokenKind::Expansion(ExpansionKind::Environment) => Ok(Cow::Owned(
+            std::env::var(token.content.as_ref()).unwrap_or_default(),
+        )),
         TokenKind::Expand => expand_inner(editor, token.content),
         TokenKind::Expansion(ExpansionKind::Shell) => expand_shell(editor, token.content),
         // Note: see the docs for this variant.
@@ -1,9 +1,14 @@
 use bitflags::bitflags;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::{max, min},
+    collections::HashMap,
     fmt,
+    path::Path,
     str::FromStr,
+    sync::Arc,
 };
 
 #[must_use]
@@ -63,6 +68,10 @@ pub enum CursorKind {
     Hidden,
 }
 
+impl CursorKind {
+    pub const VARIANTS: &'static [&'static str] = &["block", "bar", "underline", "hidden"];
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Margin {
     pub horizontal: u16,
@@ -278,7 +287,7 @@ pub fn intersects(self, other: Rect) -> bool {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Color {
     Reset,
     Black,
@@ -395,7 +404,7 @@ fn from(color: Color) -> Self {
 }
 
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnderlineStyle {
     Reset,
     Line,
@@ -447,7 +456,7 @@ fn from(style: UnderlineStyle) -> Self {
     ///
     /// let m = Modifier::BOLD | Modifier::ITALIC;
     /// ```
-    #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+    #[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
     pub struct Modifier: u16 {
         const BOLD              = 0b0000_0000_0001;
         const DIM               = 0b0000_0000_0010;
@@ -512,6 +521,7 @@ fn from_str(modifier: &str) -> Result<Self, Self::Err> {
 ///         underline_color: Some(Color::Reset),
 ///         underline_style: Some(UnderlineStyle::Reset),
 ///         sub_modifier: Modifier::empty(),
+///         hyperlink: None,
 ///     },
 ///     buffer[(0, 0)].style(),
 /// );
@@ -539,6 +549,7 @@ fn from_str(modifier: &str) -> Result<Self, Self::Err> {
 ///         underline_style: Some(UnderlineStyle::Reset),
 ///         add_modifier: Modifier::empty(),
 ///         sub_modifier: Modifier::empty(),
+///         hyperlink: None,
 ///     },
 ///     buffer[(0, 0)].style(),
 /// );
@@ -551,6 +562,8 @@ pub struct Style {
     pub underline_style: Option<UnderlineStyle>,
     pub add_modifier: Modifier,
     pub sub_modifier: Modifier,
+    /// Makes the text a link, which terminals supporting OSC 8 hyperlinks open on ctrl-click.
+    pub hyperlink: Option<Hyperlink>,
 }
 
 impl Default for Style {
@@ -568,6 +581,7 @@ pub const fn new() -> Self {
             underline_style: None,
             add_modifier: Modifier::empty(),
             sub_modifier: Modifier::empty(),
+            hyperlink: None,
         }
     }
 
@@ -580,6 +594,7 @@ pub const fn reset() -> Self {
             underline_style: None,
             add_modifier: Modifier::empty(),
             sub_modifier: Modifier::all(),
+            hyperlink: None,
         }
     }
 
@@ -683,6 +698,21 @@ pub fn remove_modifier(mut self, modifier: Modifier) -> Style {
         self
     }
 
+    /// Makes the text a link to `hyperlink`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// # use helix_view::graphics::{Hyperlink, Style};
+    /// let link = Hyperlink::new("https://helix-editor.com");
+    /// let style = Style::default().hyperlink(link);
+    /// assert_eq!(style.patch(Style::default()).hyperlink, Some(link));
+    /// ```
+    pub const fn hyperlink(mut self, hyperlink: Hyperlink) -> Style {
+        self.hyperlink = Some(hyperlink);
+        self
+    }
+
     /// Results in a combined style that is equivalent to applying the two individual styles to
     /// a style one after the other.
     ///
@@ -701,6 +731,7 @@ pub fn patch(mut self, other: Style) -> Style {
         self.bg = other.bg.or(self.bg);
         self.underline_color = other.underline_color.or(self.underline_color);
         self.underline_style = other.underline_style.or(self.underline_style);
+        self.hyperlink = other.hyperlink.or(self.hyperlink);
 
         self.add_modifier.remove(other.sub_modifier);
         self.add_modifier.insert(other.add_modifier);
@@ -711,6 +742,48 @@ pub fn patch(mut self, other: Style) -> Style {
     }
 }
 
+/// The target of a hyperlink, such as `https://` or `file://` URL. Targets are interned for the
+/// life of the process so that [`Style`] stays `Copy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hyperlink(u32);
+
+#[derive(Default)]
+struct HyperlinkTargets {
+    ids: HashMap<Arc<str>, u32>,
+    targets: Vec<Arc<str>>,
+}
+
+static HYPERLINK_TARGETS: Lazy<Mutex<HyperlinkTargets>> = Lazy::new(Default::default);
+
+impl Hyperlink {
+    pub fn new(target: &str) -> Self {
+        let mut targets = HYPERLINK_TARGETS.lock();
+        if let Some(&id) = targets.ids.get(target) {
+            return Self(id);
+        }
+        let id = targets.targets.len() as u32;
+        let target: Arc<str> = target.into();
+        targets.targets.push(target.clone());
+        targets.ids.insert(target, id);
+        Self(id)
+    }
+
+    /// A `file://` link to an absolute path, `None` for relative paths.
+    pub fn file(path: &Path) -> Option<Self> {
+        let url = url::Url::from_file_path(path).ok()?;
+        Some(Self::new(url.as_str()))
+    }
+
+    /// A number identifying the target, the same for every link to it.
+    pub fn id(self) -> u32 {
+        self.0
+    }
+
+    pub fn target(self) -> Arc<str> {
+        HYPERLINK_TARGETS.lock().targets[self.0 as usize].clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,12 +13,12 @@ fn count_digits(n: usize) -> usize {
 }
 
 pub type GutterFn<'doc> = Box<dyn FnMut(usize, bool, bool, &mut String) -> Option<Style> + 'doc>;
-pub type Gutter =
-    for<'doc> fn(&'doc Editor, &'doc Document, &View, &Theme, bool, usize) -> GutterFn<'doc>;
+/// Creates the [`GutterFn`] which renders a gutter for one frame.
+pub type Gutter = for<'doc> fn(&'doc Editor, &'doc Document, &View, &Theme, bool) -> GutterFn<'doc>;
 
 impl GutterType {
     pub fn style<'doc>(
-        self,
+        &self,
         editor: &'doc Editor,
         doc: &'doc Document,
         view: &View,
@@ -26,21 +26,33 @@ pub fn style<'doc>(
         is_focused: bool,
     ) -> GutterFn<'doc> {
         match self {
+            GutterType::Diagnostics if view.gutters(doc).contains(&GutterType::Breakpoints) => {
+                diagnostic(editor, doc, view, theme, is_focused)
+            }
             GutterType::Diagnostics => {
                 diagnostics_or_breakpoints(editor, doc, view, theme, is_focused)
             }
+            GutterType::Breakpoints => {
+                breakpoints_and_execution_pause(editor, doc, view, theme, is_focused)
+            }
             GutterType::LineNumbers => line_numbers(editor, doc, view, theme, is_focused),
             GutterType::Spacer => padding(editor, doc, view, theme, is_focused),
             GutterType::Diff => diff(editor, doc, view, theme, is_focused),
+            GutterType::Custom(name) => match editor.gutter_providers.get(name) {
+                Some(gutter) => gutter(editor, doc, view, theme, is_focused),
+                None => padding(editor, doc, view, theme, is_focused),
+            },
         }
     }
 
-    pub fn width(self, view: &View, doc: &Document) -> usize {
+    pub fn width(&self, view: &View, doc: &Document) -> usize {
         match self {
             GutterType::Diagnostics => 1,
+            GutterType::Breakpoints => 1,
             GutterType::LineNumbers => line_numbers_width(view, doc),
             GutterType::Spacer => 1,
             GutterType::Diff => 1,
+            GutterType::Custom(_) => 1,
         }
     }
 }
@@ -173,10 +185,14 @@ pub fn line_numbers<'doc>(
             } else {
                 use crate::{document::Mode, editor::LineNumber};
 
-                let relative = line_number == LineNumber::Relative
-                    && mode != Mode::Insert
+                let relative = mode != Mode::Insert
                     && is_focused
-                    && current_line != line;
+                    && match line_number {
+                        LineNumber::Absolute => false,
+                        LineNumber::Relative => true,
+                        // the line of the primary cursor shows its absolute number
+                        LineNumber::Hybrid => current_line != line,
+                    };
 
                 let display_num = if relative {
                     current_line.abs_diff(line)
@@ -306,6 +322,22 @@ fn execution_pause_indicator<'doc>(
     )
 }
 
+pub fn breakpoints_and_execution_pause<'doc>(
+    editor: &'doc Editor,
+    doc: &'doc Document,
+    view: &View,
+    theme: &Theme,
+    is_focused: bool,
+) -> GutterFn<'doc> {
+    let mut breakpoints = breakpoints(editor, doc, view, theme, is_focused);
+    let mut execution_pause_indicator = execution_pause_indicator(editor, doc, theme, is_focused);
+
+    Box::new(move |line, selected, first_visual_line: bool, out| {
+        execution_pause_indicator(line, selected, first_visual_line, out)
+            .or_else(|| breakpoints(line, selected, first_visual_line, out))
+    })
+}
+
 pub fn diagnostics_or_breakpoints<'doc>(
     editor: &'doc Editor,
     doc: &'doc Document,
@@ -429,4 +461,32 @@ fn test_line_numbers_gutter_width_resizes() {
         assert_eq!(view.gutters.layout[1].width(&view, &doc_short), 1);
         assert_eq!(view.gutters.layout[1].width(&view, &doc_long), 2);
     }
+
+    #[test]
+    fn test_parse_gutter_types() {
+        assert_eq!(
+            "breakpoints".parse::<GutterType>().unwrap(),
+            GutterType::Breakpoints
+        );
+        assert_eq!(
+            "bookmarks".parse::<GutterType>().unwrap(),
+            GutterType::Custom("bookmarks".to_string())
+        );
+        assert!("".parse::<GutterType>().is_err());
+        assert!("line numbers".parse::<GutterType>().is_err());
+
+        let gutters: GutterConfig =
+            serde_json::from_value(serde_json::json!({ "layout": ["diff", "bookmarks"] })).unwrap();
+        assert_eq!(
+            gutters.layout,
+            vec![
+                GutterType::Diff,
+                GutterType::Custom("bookmarks".to_string())
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&gutters.layout).unwrap(),
+            serde_json::json!(["diff", "bookmarks"])
+        );
+    }
 }
@@ -11,17 +11,10 @@
 pub mod lsp;
 pub mod word_index;
 
-#[derive(Debug)]
-pub enum AutoSaveEvent {
-    DocumentChanged { save_after: u64 },
-    LeftInsertMode,
-}
-
 pub struct Handlers {
     // only public because most of the actual implementation is in helix-term right now :/
     pub completions: CompletionHandler,
     pub signature_hints: Sender<lsp::SignatureHelpEvent>,
-    pub auto_save: Sender<AutoSaveEvent>,
     pub document_colors: Sender<lsp::DocumentColorsEvent>,
     pub word_index: word_index::Handler,
     pub pull_diagnostics: Sender<lsp::PullDiagnosticsEvent>,
@@ -225,9 +225,13 @@ fn clear(&self) {
     ///
     /// This task wraps a MPSC queue and spawns blocking tasks which update the index. Updates
     /// are applied one-by-one to ensure that changes to the index are **serialized**:
-    /// updates to each document must be applied in-order.
+    /// updates to each document must be applied in-order. Indexing waits while background work
+    /// is paused, see [`crate::resources`].
     async fn run(self, mut events: mpsc::UnboundedReceiver<Event>) {
         while let Some(event) = events.recv().await {
+            if !matches!(event, Event::Clear) {
+                crate::resources::wait_until_resumed().await;
+            }
             let this = self.clone();
             tokio::task::spawn_blocking(move || match event {
                 Event::Insert(text) => {
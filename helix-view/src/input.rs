@@ -958,40 +958,395 @@ fn parsing_valid_macros() {
         );
     }
 
+    #[test]
+    fn parsing_focus_reports() {
+        let mut parser = VteEventParser::new();
+        assert_eq!(
+            parser.advance(b"\x1b[O\x1b[I"),
+            vec![Event::FocusLost, Event::FocusGained]
+        );
+    }
+
+    #[test]
+    fn parsing_bracketed_paste() {
+        let mut parser = VteEventParser::new();
+        assert!(parser.advance(b"\x1b[200~foo\r").is_empty());
+        assert_eq!(
+            parser.advance(b"bar\x1b[201~x"),
+            vec![
+                Event::Paste("foo\rbar".into()),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('x'),
+                    modifiers: KeyModifiers::NONE,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsing_sgr_mouse_events() {
+        let mut parser = VteEventParser::new();
+        assert_eq!(
+            parser.advance(b"\x1b[<0;5;3M\x1b[<32;6;3M\x1b[<0;6;3m\x1b[<81;1;1M"),
+            vec![
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column: 4,
+                    row: 2,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Drag(MouseButton::Left),
+                    column: 5,
+                    row: 2,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Up(MouseButton::Left),
+                    column: 5,
+                    row: 2,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::ScrollDown,
+                    column: 0,
+                    row: 0,
+                    modifiers: KeyModifiers::CONTROL,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsing_csi_u_keys() {
+        let mut parser = VteEventParser::new();
+        assert_eq!(
+            parser.advance(b"\x1b[97;6u\x1b[13;5u\x1b[1;3C\x1b[27u\x1b[97;1:3u\x1b[3;2~"),
+            vec![
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('A'),
+                    modifiers: KeyModifiers::CONTROL,
+                }),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    modifiers: KeyModifiers::CONTROL,
+                }),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Right,
+                    modifiers: KeyModifiers::ALT,
+                }),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Delete,
+                    modifiers: KeyModifiers::SHIFT,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsing_modified_function_keys() {
+        let mut parser = VteEventParser::new();
+        assert_eq!(
+            parser.advance(b"\x1b[1;5P\x1b[1;5R\x1b[1;2S"),
+            vec![
+                key(KeyCode::F(1), KeyModifiers::CONTROL),
+                key(KeyCode::F(3), KeyModifiers::CONTROL),
+                key(KeyCode::F(4), KeyModifiers::SHIFT),
+            ]
+        );
+        assert!(parser.take_responses().is_empty());
+    }
+
     #[test]
     fn parsing_invalid_macros_fails() {
         assert!(parse_macro("abc<C-").is_err());
         assert!(parse_macro("abc>123").is_err());
         assert!(parse_macro("wd<foo>").is_err());
     }
+
+    /// Feeds `reads` to a parser one after the other and returns the events of each read.
+    fn parse_reads(reads: &[&[u8]]) -> Vec<Vec<Event>> {
+        let mut parser = VteEventParser::new();
+        reads.iter().map(|read| parser.advance(read)).collect()
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent { code, modifiers })
+    }
+
+    #[test]
+    fn vte_parser_completes_split_utf8() {
+        assert_eq!(
+            parse_reads(&[b"a\xC3", b"\xA9\xE2\x82", b"\xAC"]),
+            [
+                vec![key(KeyCode::Char('a'), KeyModifiers::NONE)],
+                vec![key(KeyCode::Char('é'), KeyModifiers::NONE)],
+                vec![key(KeyCode::Char('€'), KeyModifiers::NONE)],
+            ]
+        );
+    }
+
+    #[test]
+    fn vte_parser_completes_split_escape_sequences() {
+        // CSI with partial parameters
+        assert_eq!(
+            parse_reads(&[b"\x1b[1;", b"5", b"A"]),
+            [
+                vec![],
+                vec![],
+                vec![key(KeyCode::Up, KeyModifiers::CONTROL)]
+            ]
+        );
+        // SGR mouse report
+        let events = parse_reads(&[b"\x1b[<0;1", b"0;5M"]);
+        assert!(events[0].is_empty());
+        assert_eq!(
+            events[1],
+            [Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 9,
+                row: 4,
+                modifiers: KeyModifiers::NONE,
+            })]
+        );
+        // SS3 and Alt chords
+        assert_eq!(
+            parse_reads(&[b"\x1bO", b"P\x1b", b"x"]),
+            [
+                vec![],
+                vec![key(KeyCode::F(1), KeyModifiers::NONE)],
+                vec![key(KeyCode::Char('x'), KeyModifiers::ALT)],
+            ]
+        );
+        assert_eq!(
+            parse_reads(&[b"\x1bOx"]),
+            [vec![
+                key(KeyCode::Char('O'), KeyModifiers::ALT),
+                key(KeyCode::Char('x'), KeyModifiers::NONE),
+            ]]
+        );
+    }
+
+    #[test]
+    fn vte_parser_separates_terminal_responses() {
+        let mut parser = VteEventParser::new();
+        let events = parser.advance(
+            b"a\x1b[?1u\x1b]11;rgb:0000/0000/0000\x1b\\b\
+              \x1bP1+r524742=382F38\x1b\\\x1b[?62;22c",
+        );
+        assert_eq!(
+            events,
+            [
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                key(KeyCode::Char('b'), KeyModifiers::NONE),
+            ]
+        );
+        assert_eq!(
+            parser.take_responses(),
+            [
+                TerminalResponse::KittyKeyboardFlags(1),
+                TerminalResponse::Osc {
+                    number: 11,
+                    value: "rgb:0000/0000/0000".to_string(),
+                },
+                TerminalResponse::Capability {
+                    name: "RGB".to_string(),
+                },
+                TerminalResponse::DeviceAttributes(vec![62, 22]),
+            ]
+        );
+
+        // Split across reads, and the mode report has two intermediates
+        assert_eq!(
+            parse_reads(&[b"\x1b[?2026;", b"2$y\x1b\\"]),
+            [vec![], vec![key(KeyCode::Char('\\'), KeyModifiers::ALT)]]
+        );
+        let mut parser = VteEventParser::new();
+        parser.advance(b"\x1b[?2026;");
+        parser.advance(b"2$y");
+        assert_eq!(
+            parser.take_responses(),
+            [TerminalResponse::Mode {
+                mode: 2026,
+                setting: 2,
+            }]
+        );
+
+        // Mode 2031 notifications when the system switches between dark and light
+        let mut parser = VteEventParser::new();
+        parser.advance(b"\x1b[?997;2n\x1b[?997;1n");
+        assert_eq!(
+            parser.take_responses(),
+            [
+                TerminalResponse::ThemeMode(crate::theme::Mode::Light),
+                TerminalResponse::ThemeMode(crate::theme::Mode::Dark),
+            ]
+        );
+    }
+
+    #[test]
+    fn vte_parser_completes_split_paste() {
+        assert_eq!(
+            parse_reads(&[b"\x1b[200~hel", b"lo\n\xC3", b"\xA9\x1b[20", b"1~a"]),
+            [
+                vec![],
+                vec![],
+                vec![],
+                vec![
+                    Event::Paste("hello\né".to_string()),
+                    key(KeyCode::Char('a'), KeyModifiers::NONE),
+                ],
+            ]
+        );
+    }
 }
 
+/// Parses raw terminal input into events. The parser is incremental: UTF-8 characters, escape
+/// sequences and bracketed pastes split across reads are completed by the following reads.
 pub struct VteEventParser {
-    pub events: Vec<Event>,
-    pub parser: vte::Parser,
+    parser: vte::Parser,
+    performer: VtePerformer,
 }
 
 impl VteEventParser {
     pub fn new() -> Self {
-        Self { 
-            events: Vec::new(),
+        Self {
             parser: vte::Parser::new(),
+            performer: VtePerformer::default(),
         }
     }
 
+    /// Parses `bytes` and returns the events they completed. Replies of the terminal to
+    /// queries are kept apart from the events, see [`VteEventParser::take_responses`].
     pub fn advance(&mut self, bytes: &[u8]) -> Vec<Event> {
-        let mut performer = VtePerformer { events: Vec::new() };
-        self.parser.advance(&mut performer, bytes);
-        performer.events
+        self.parser.advance(&mut self.performer, bytes);
+        std::mem::take(&mut self.performer.events)
     }
+
+    /// Takes the replies to terminal queries read by [`VteEventParser::advance`] since the last
+    /// call, for the backend which sent the queries.
+    pub fn take_responses(&mut self) -> Vec<TerminalResponse> {
+        std::mem::take(&mut self.performer.responses)
+    }
+}
+
+/// A reply of the terminal to a query. Replies which arrive after the backend stopped waiting
+/// for them are read interleaved with the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalResponse {
+    /// `CSI ? attributes c`, the primary device attributes.
+    DeviceAttributes(Vec<u16>),
+    /// `CSI ? flags u`, the enabled flags of the kitty keyboard protocol.
+    KittyKeyboardFlags(u16),
+    /// `CSI ? mode ; setting $ y`, the setting of a DEC private mode.
+    Mode { mode: u16, setting: u16 },
+    /// `CSI ? 997 ; 1 n` or `2 n`, the theme mode reported by mode 2031.
+    ThemeMode(crate::theme::Mode),
+    /// `OSC number ; value ST`, for example the background color for `OSC 11`.
+    Osc { number: u16, value: String },
+    /// `DCS 1 + r name = value ST`, a capability the terminal reported as supported to an
+    /// XTGETTCAP query.
+    Capability { name: String },
+}
+
+/// Decodes the parameters of an SGR mouse report, the coordinates are one-based.
+fn sgr_mouse_event(params: &vte::Params, release: bool) -> Option<MouseEvent> {
+    let mut params = params.iter().map(|param| param.first().copied());
+    let code = params.next()??;
+    let column = params.next()??.saturating_sub(1);
+    let row = params.next()??.saturating_sub(1);
+
+    let mut modifiers = KeyModifiers::NONE;
+    if code & 4 != 0 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if code & 8 != 0 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if code & 16 != 0 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+
+    let button = match code & 0b11 {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    };
+    let kind = if code & 64 != 0 {
+        match code & 0b11 {
+            0 => MouseEventKind::ScrollUp,
+            1 => MouseEventKind::ScrollDown,
+            2 => MouseEventKind::ScrollLeft,
+            _ => MouseEventKind::ScrollRight,
+        }
+    } else if code & 32 != 0 {
+        button.map_or(MouseEventKind::Moved, MouseEventKind::Drag)
+    } else if release {
+        MouseEventKind::Up(button?)
+    } else {
+        MouseEventKind::Down(button?)
+    };
+
+    Some(MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers,
+    })
 }
 
+/// The state of the parser between reads, besides the state of the `vte::Parser` itself.
+#[derive(Default)]
 struct VtePerformer {
     events: Vec<Event>,
+    /// Set between the `CSI 200 ~` and `CSI 201 ~` markers of a bracketed paste.
+    paste: Option<String>,
+    /// Set after `ESC O`, the next character completes an SS3 sequence. Some terminals send
+    /// these for F1-F4 and the cursor keys.
+    ss3: bool,
+    responses: Vec<TerminalResponse>,
+    /// The data of an XTGETTCAP reply which hasn't ended yet.
+    capability: Option<Vec<u8>>,
+    /// Set after a string sequence which ends with `ESC \`, the `\` isn't an Alt chord.
+    string_terminator: bool,
+}
+
+impl VtePerformer {
+    /// Called at the start of each sequence. Sends the `ESC O` of an SS3 sequence which wasn't
+    /// completed as `A-O`.
+    fn sequence_start(&mut self) {
+        self.string_terminator = false;
+        if std::mem::take(&mut self.ss3) {
+            self.events.push(Event::Key(KeyEvent {
+                code: KeyCode::Char('O'),
+                modifiers: KeyModifiers::ALT,
+            }));
+        }
+    }
 }
 
 impl vte::Perform for VtePerformer {
     fn print(&mut self, c: char) {
+        if let Some(paste) = &mut self.paste {
+            paste.push(c);
+            return;
+        }
+        if self.ss3 {
+            if let Some(code) = ss3_key_code(c) {
+                self.ss3 = false;
+                self.events.push(Event::Key(KeyEvent {
+                    code,
+                    modifiers: KeyModifiers::NONE,
+                }));
+                return;
+            }
+        }
+        self.sequence_start();
         self.events.push(Event::Key(KeyEvent {
             code: KeyCode::Char(c),
             modifiers: KeyModifiers::NONE,
@@ -999,6 +1354,13 @@ fn print(&mut self, c: char) {
     }
 
     fn execute(&mut self, byte: u8) {
+        self.sequence_start();
+        if let Some(paste) = &mut self.paste {
+            if matches!(byte, 0x09 | 0x0A | 0x0D) {
+                paste.push(byte as char);
+            }
+            return;
+        }
         match byte {
             0x08 | 0x7F => self.events.push(Event::Key(KeyEvent {
                 code: KeyCode::Backspace,
@@ -1030,28 +1392,293 @@ fn execute(&mut self, byte: u8) {
         }
     }
 
-    fn csi_dispatch(&mut self, _params: &vte::Params, intermediates: &[u8], ignore: bool, action: char) {
-        if ignore || intermediates.len() > 1 {
+    fn csi_dispatch(
+        &mut self,
+        params: &vte::Params,
+        intermediates: &[u8],
+        ignore: bool,
+        action: char,
+    ) {
+        self.sequence_start();
+        if ignore {
+            return;
+        }
+        if let Some(response) = csi_response(params, intermediates, action) {
+            self.responses.push(response);
+            return;
+        }
+        if intermediates.len() > 1 {
             return;
         }
 
-        if intermediates.is_empty() {
-            let code = match action {
-                'A' => Some(KeyCode::Up),
-                'B' => Some(KeyCode::Down),
-                'C' => Some(KeyCode::Right),
-                'D' => Some(KeyCode::Left),
-                'H' => Some(KeyCode::Home),
-                'F' => Some(KeyCode::End),
-                _ => None,
-            };
+        // Bracketed paste: the pasted text is delivered as a single event
+        if action == '~' && intermediates.is_empty() {
+            match params.iter().next() {
+                Some([200]) => {
+                    self.paste = Some(String::new());
+                    return;
+                }
+                Some([201]) => {
+                    if let Some(paste) = self.paste.take() {
+                        self.events.push(Event::Paste(paste));
+                    }
+                    return;
+                }
+                _ => (),
+            }
+        }
 
-            if let Some(c) = code {
-                self.events.push(Event::Key(KeyEvent {
-                    code: c,
-                    modifiers: KeyModifiers::NONE,
-                }));
+        if self.paste.is_some() {
+            return;
+        }
+
+        // Focus reports: `CSI I` when the terminal gains focus, `CSI O` when it loses it
+        if intermediates.is_empty() && params.is_empty() && matches!(action, 'I' | 'O') {
+            self.events.push(if action == 'I' {
+                Event::FocusGained
+            } else {
+                Event::FocusLost
+            });
+            return;
+        }
+
+        // SGR mouse reports: `CSI < button ; column ; row M` (press) or `m` (release)
+        if intermediates == b"<" && matches!(action, 'M' | 'm') {
+            if let Some(event) = sgr_mouse_event(params, action == 'm') {
+                self.events.push(Event::Mouse(event));
             }
+            return;
+        }
+
+        if intermediates.is_empty() {
+            if let Some(key) = csi_key_event(params, action) {
+                self.events.push(Event::Key(key));
+            }
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        let string_terminator = self.string_terminator;
+        self.sequence_start();
+        if string_terminator && byte == b'\\' {
+            return;
+        }
+        if ignore || !intermediates.is_empty() || self.paste.is_some() {
+            return;
+        }
+        if byte == b'O' {
+            self.ss3 = true;
+            return;
+        }
+        // Other characters after ESC are the legacy encoding of Alt chords
+        self.events.push(Event::Key(KeyEvent {
+            code: KeyCode::Char(byte as char),
+            modifiers: KeyModifiers::ALT,
+        }));
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        self.sequence_start();
+        self.string_terminator = !bell_terminated;
+        let [number, value @ ..] = params else {
+            return;
+        };
+        let Some(number) = std::str::from_utf8(number)
+            .ok()
+            .and_then(|n| n.parse().ok())
+        else {
+            return;
+        };
+        let value: Vec<_> = value
+            .iter()
+            .map(|part| String::from_utf8_lossy(part))
+            .collect();
+        self.responses.push(TerminalResponse::Osc {
+            number,
+            value: value.join(";"),
+        });
+    }
+
+    fn hook(&mut self, params: &vte::Params, intermediates: &[u8], ignore: bool, action: char) {
+        self.sequence_start();
+        // Only capabilities reported as supported start with `1`
+        let supported = matches!(params.iter().next(), Some([1]));
+        if !ignore && supported && intermediates == b"+" && action == 'r' {
+            self.capability = Some(Vec::new());
+        }
+    }
+
+    fn put(&mut self, byte: u8) {
+        if let Some(capability) = &mut self.capability {
+            capability.push(byte);
+        }
+    }
+
+    fn unhook(&mut self) {
+        self.string_terminator = true;
+        let Some(capability) = self.capability.take() else {
+            return;
+        };
+        let name = capability
+            .split(|byte| *byte == b'=')
+            .next()
+            .unwrap_or_default();
+        if let Some(name) = hex_decode(name) {
+            self.responses.push(TerminalResponse::Capability { name });
         }
     }
 }
+
+/// Decodes the replies to queries which are CSI sequences.
+fn csi_response(
+    params: &vte::Params,
+    intermediates: &[u8],
+    action: char,
+) -> Option<TerminalResponse> {
+    let mut values = params
+        .iter()
+        .map(|param| param.first().copied().unwrap_or(0));
+    let response = match (intermediates, action) {
+        (b"?", 'c') => TerminalResponse::DeviceAttributes(values.collect()),
+        (b"?", 'u') => TerminalResponse::KittyKeyboardFlags(values.next().unwrap_or(0)),
+        (b"?$", 'y') => TerminalResponse::Mode {
+            mode: values.next()?,
+            setting: values.next()?,
+        },
+        (b"?", 'n') => match (values.next()?, values.next()?) {
+            (997, 1) => TerminalResponse::ThemeMode(crate::theme::Mode::Dark),
+            (997, 2) => TerminalResponse::ThemeMode(crate::theme::Mode::Light),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(response)
+}
+
+/// Decodes the hex encoded names of XTGETTCAP replies.
+fn hex_decode(hex: &[u8]) -> Option<String> {
+    let bytes = hex
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// The key of an `ESC O` (SS3) sequence.
+fn ss3_key_code(c: char) -> Option<KeyCode> {
+    let code = match c {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        'P' => KeyCode::F(1),
+        'Q' => KeyCode::F(2),
+        'R' => KeyCode::F(3),
+        'S' => KeyCode::F(4),
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Decodes `CSI number ; modifiers [:event-type] action` key sequences: the legacy encoding of
+/// the cursor, editing and function keys and the `CSI u` encoding of the kitty keyboard
+/// protocol. Releases are ignored.
+fn csi_key_event(params: &vte::Params, action: char) -> Option<KeyEvent> {
+    let mut params = params.iter();
+    let number = params.next().and_then(|param| param.first().copied());
+    let (modifiers, event_type) = params.next().map_or((1, 1), |param| {
+        (
+            param.first().copied().unwrap_or(1),
+            param.get(1).copied().unwrap_or(1),
+        )
+    });
+    if event_type == 3 {
+        return None;
+    }
+    let mut modifiers = csi_modifiers(modifiers);
+
+    let code = match action {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        'P' => KeyCode::F(1),
+        'Q' => KeyCode::F(2),
+        'R' => KeyCode::F(3),
+        'S' => KeyCode::F(4),
+        'Z' => {
+            modifiers.insert(KeyModifiers::SHIFT);
+            KeyCode::Tab
+        }
+        '~' => match number? {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            n @ 11..=15 => KeyCode::F(n as u8 - 10),
+            n @ 17..=21 => KeyCode::F(n as u8 - 11),
+            n @ 23..=24 => KeyCode::F(n as u8 - 12),
+            _ => return None,
+        },
+        'u' => kitty_key_code(number?)?,
+        _ => return None,
+    };
+
+    // Shifted letters are bound as upper case letters, for example `C-A`
+    let code = match code {
+        KeyCode::Char(ch) if modifiers.contains(KeyModifiers::SHIFT) && ch.is_lowercase() => {
+            modifiers.remove(KeyModifiers::SHIFT);
+            KeyCode::Char(ch.to_uppercase().next().unwrap_or(ch))
+        }
+        code => code,
+    };
+    Some(KeyEvent { code, modifiers })
+}
+
+/// The modifier parameter is one plus a bitmask of shift, alt, ctrl and super. Hyper, meta and
+/// the lock states aren't supported.
+fn csi_modifiers(param: u16) -> KeyModifiers {
+    let bits = param.saturating_sub(1);
+    let mut modifiers = KeyModifiers::NONE;
+    if bits & 1 != 0 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if bits & 2 != 0 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if bits & 4 != 0 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+    if bits & 8 != 0 {
+        modifiers |= KeyModifiers::SUPER;
+    }
+    modifiers
+}
+
+/// The key of a `CSI u` sequence: a unicode codepoint or one of the functional keys the kitty
+/// keyboard protocol maps into the private use area.
+fn kitty_key_code(number: u16) -> Option<KeyCode> {
+    let code = match number {
+        9 => KeyCode::Tab,
+        13 => KeyCode::Enter,
+        27 => KeyCode::Esc,
+        127 => KeyCode::Backspace,
+        57358 => KeyCode::CapsLock,
+        57359 => KeyCode::ScrollLock,
+        57360 => KeyCode::NumLock,
+        57361 => KeyCode::PrintScreen,
+        57362 => KeyCode::Pause,
+        57363 => KeyCode::Menu,
+        n @ 57376..=57398 => KeyCode::F((n - 57376 + 13) as u8),
+        // other keypad, media and modifier keys
+        57344..=63743 => return None,
+        n => KeyCode::Char(char::from_u32(n as u32)?),
+    };
+    Some(code)
+}
@@ -0,0 +1,55 @@
+//! The edit journal: an append-only log of the changes applied to documents, see the
+//! `editor.edit-journal` option.
+//!
+//! Each document with a path gets its own journal in `<state dir>/journal`, named after the
+//! document's path. Every change is one JSON object per line, with the operations of the
+//! change in order:
+//!
+//! ```json
+//! {"time":"2024-05-01T12:00:00+02:00","version":3,"changes":[["retain",4],["insert","x"],["delete",1]]}
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use helix_core::{ChangeSet, Operation};
+use serde_json::{json, Value};
+
+use crate::register::encode_path;
+
+/// The journal of the document at `path`.
+pub fn journal_file(path: &Path) -> PathBuf {
+    helix_loader::state_dir()
+        .join("journal")
+        .join(format!("{}.jsonl", encode_path(path)))
+}
+
+/// Formats the journal entry of a change, `time` is the time it was applied.
+pub fn entry(time: &str, version: i32, changes: &ChangeSet) -> String {
+    let changes: Vec<Value> = changes
+        .changes()
+        .iter()
+        .map(|operation| match operation {
+            Operation::Retain(n) => json!(["retain", n]),
+            Operation::Delete(n) => json!(["delete", n]),
+            Operation::Insert(text) => json!(["insert", text.as_str()]),
+        })
+        .collect();
+    json!({ "time": time, "version": version, "changes": changes }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use helix_core::{Rope, Transaction};
+
+    use super::*;
+
+    #[test]
+    fn entry_lists_operations() {
+        let doc = Rope::from("hello");
+        let transaction = Transaction::change(&doc, [(1, 2, Some("a".into()))].into_iter());
+        assert_eq!(
+            entry("now", 2, transaction.changes()),
+            r#"{"changes":[["retain",1],["insert","a"],["delete",1],["retain",3]],"time":"now","version":2}"#
+        );
+    }
+}
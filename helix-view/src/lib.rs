@@ -12,11 +12,20 @@
 pub mod handlers;
 pub mod info;
 pub mod input;
+pub mod journal;
 pub mod keyboard;
+pub mod messages;
+pub mod metrics;
+pub mod named_selections;
+pub mod quickfix;
 pub mod register;
+pub mod resources;
+pub mod session;
+pub mod terminal;
 pub mod theme;
 pub mod tree;
 pub mod view;
+pub mod watch;
 
 use std::num::NonZeroUsize;
 
@@ -0,0 +1,150 @@
+//! The log of status messages, shown with `:messages`.
+//!
+//! Messages arriving in quick succession compete for the single status line. Repeats of the
+//! last message are counted instead of logged again, and a message doesn't replace a more
+//! severe one which has been shown for less than [`HOLD_MORE_SEVERE`], so an error can't be
+//! overwritten by an unrelated info message before it could be read. Every message is logged
+//! regardless.
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    time::{Duration, Instant, SystemTime},
+};
+
+use helix_core::diagnostic::Severity;
+
+/// How long a message stays in the status line before a less severe one may replace it.
+pub const HOLD_MORE_SEVERE: Duration = Duration::from_secs(3);
+
+/// The number of messages kept in the log.
+const MAX_MESSAGES: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub text: Cow<'static, str>,
+    pub severity: Severity,
+    /// When the message was last sent.
+    pub time: SystemTime,
+    /// How many times the message was sent in a row.
+    pub count: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct MessageLog {
+    messages: VecDeque<Message>,
+    /// The severity of the message in the status line and since when it is shown.
+    shown: Option<(Severity, Instant)>,
+    /// The number of messages logged but not shown since the status line last changed.
+    suppressed: usize,
+}
+
+impl MessageLog {
+    /// Logs a message and returns whether it should replace the status line message.
+    pub fn push(&mut self, text: &Cow<'static, str>, severity: Severity, now: Instant) -> bool {
+        match self.messages.back_mut() {
+            Some(last) if last.text == *text && last.severity == severity => {
+                last.count += 1;
+                last.time = SystemTime::now();
+            }
+            _ => {
+                if self.messages.len() == MAX_MESSAGES {
+                    self.messages.pop_front();
+                }
+                self.messages.push_back(Message {
+                    text: text.clone(),
+                    severity,
+                    time: SystemTime::now(),
+                    count: 1,
+                });
+            }
+        }
+
+        let held = self.shown.is_some_and(|(shown_severity, since)| {
+            shown_severity > severity && now.saturating_duration_since(since) < HOLD_MORE_SEVERE
+        });
+        if held {
+            self.suppressed += 1;
+        } else {
+            self.shown = Some((severity, now));
+            self.suppressed = 0;
+        }
+        !held
+    }
+
+    /// Called when the status line is cleared, any message may be shown next.
+    pub fn clear_shown(&mut self) {
+        self.shown = None;
+        self.suppressed = 0;
+    }
+
+    /// The number of messages which were held back by the message in the status line.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    /// The number of times the last message was sent in a row.
+    pub fn last_count(&self) -> usize {
+        self.messages.back().map_or(0, |message| message.count)
+    }
+
+    /// The logged messages, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.messages.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_held() {
+        let mut log = MessageLog::default();
+        let start = Instant::now();
+        let error = Cow::Borrowed("write failed");
+        let info = Cow::Borrowed("3 files indexed");
+
+        assert!(log.push(&error, Severity::Error, start));
+        // A less severe message right after the error is logged but not shown
+        assert!(!log.push(&info, Severity::Info, start + Duration::from_millis(100)));
+        assert_eq!(log.suppressed(), 1);
+        // Another error replaces it
+        assert!(log.push(&error, Severity::Error, start + Duration::from_millis(200)));
+        assert_eq!(log.suppressed(), 0);
+        assert_eq!(log.last_count(), 1);
+        // Once the error was shown for a while, it can be replaced
+        let later = start + HOLD_MORE_SEVERE + Duration::from_secs(1);
+        assert!(log.push(&info, Severity::Info, later));
+
+        let texts: Vec<_> = log.iter().map(|message| message.text.as_ref()).collect();
+        assert_eq!(
+            texts,
+            [
+                "write failed",
+                "3 files indexed",
+                "write failed",
+                "3 files indexed"
+            ]
+        );
+
+        log.clear_shown();
+        assert!(log.push(&info, Severity::Hint, later));
+    }
+
+    #[test]
+    fn repeats_are_counted() {
+        let mut log = MessageLog::default();
+        let now = Instant::now();
+        let message = Cow::Borrowed("Language server exited");
+        for _ in 0..3 {
+            assert!(log.push(&message, Severity::Info, now));
+        }
+        assert_eq!(log.iter().count(), 1);
+        assert_eq!(log.last_count(), 3);
+    }
+}
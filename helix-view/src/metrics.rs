@@ -0,0 +1,184 @@
+//! Timings of the phases of the main loop: handling input events and language server messages,
+//! rendering the compositor, drawing the changed cells and flushing them to the terminal.
+//! Recording is off by default and toggled at runtime with `:metrics`. While it is on, the
+//! timings are also logged periodically.
+
+use std::{
+    fmt::Write as _,
+    time::{Duration, Instant},
+};
+
+/// How often a summary of the timings is logged while recording.
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Handling an input event, for example a key press.
+    Events,
+    /// Handling a message of a language server.
+    Lsp,
+    /// Rendering the compositor's layers to the frame.
+    Render,
+    /// Diffing the frame against the previous one and encoding the changes.
+    Draw,
+    /// Writing the encoded changes to the terminal.
+    Flush,
+}
+
+impl Phase {
+    pub const ALL: [Self; 5] = [
+        Self::Events,
+        Self::Lsp,
+        Self::Render,
+        Self::Draw,
+        Self::Flush,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Events => "events",
+            Self::Lsp => "lsp",
+            Self::Render => "render",
+            Self::Draw => "draw",
+            Self::Flush => "flush",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseStats {
+    pub count: u32,
+    pub total: Duration,
+    pub max: Duration,
+    pub last: Duration,
+}
+
+impl PhaseStats {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+        self.last = duration;
+    }
+
+    pub fn average(&self) -> Duration {
+        self.total.checked_div(self.count).unwrap_or(Duration::ZERO)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    enabled: bool,
+    stats: [PhaseStats; Phase::ALL.len()],
+    /// The timings since the last log line.
+    window: [PhaseStats; Phase::ALL.len()],
+    last_log: Option<Instant>,
+}
+
+impl Metrics {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Starts or stops recording. Starting again discards the previous timings.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.reset();
+        }
+        self.enabled = enabled;
+    }
+
+    pub fn reset(&mut self) {
+        self.stats = Default::default();
+        self.window = Default::default();
+        self.last_log = Some(Instant::now());
+    }
+
+    /// The start of a phase to pass to [`Metrics::finish`], `None` while not recording.
+    pub fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    /// Records the time since `start`, see [`Metrics::start`].
+    pub fn finish(&mut self, phase: Phase, start: Option<Instant>) {
+        if let Some(start) = start {
+            self.record(phase, start.elapsed());
+        }
+    }
+
+    pub fn record(&mut self, phase: Phase, duration: Duration) {
+        if !self.enabled {
+            return;
+        }
+        self.stats[phase as usize].record(duration);
+        self.window[phase as usize].record(duration);
+
+        if self
+            .last_log
+            .is_none_or(|last_log| last_log.elapsed() >= LOG_INTERVAL)
+        {
+            log::info!(
+                "metrics of the last {}s:\n{}",
+                LOG_INTERVAL.as_secs(),
+                summary(&self.window)
+            );
+            self.window = Default::default();
+            self.last_log = Some(Instant::now());
+        }
+    }
+
+    pub fn stats(&self, phase: Phase) -> PhaseStats {
+        self.stats[phase as usize]
+    }
+
+    /// A table of the timings since recording started, one row per phase.
+    pub fn summary(&self) -> String {
+        summary(&self.stats)
+    }
+}
+
+fn summary(stats: &[PhaseStats]) -> String {
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let mut summary = format!(
+        "{:<8}{:>8}{:>10}{:>10}{:>10}",
+        "phase", "count", "avg ms", "max ms", "last ms"
+    );
+    for (phase, stats) in Phase::ALL.iter().zip(stats) {
+        write!(
+            summary,
+            "\n{:<8}{:>8}{:>10.2}{:>10.2}{:>10.2}",
+            phase.name(),
+            stats.count,
+            ms(stats.average()),
+            ms(stats.max),
+            ms(stats.last)
+        )
+        .unwrap();
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_while_enabled() {
+        let mut metrics = Metrics::default();
+        metrics.record(Phase::Render, Duration::from_millis(5));
+        assert_eq!(metrics.stats(Phase::Render).count, 0);
+
+        metrics.set_enabled(true);
+        metrics.record(Phase::Render, Duration::from_millis(2));
+        metrics.record(Phase::Render, Duration::from_millis(4));
+        let stats = metrics.stats(Phase::Render);
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.average(), Duration::from_millis(3));
+        assert_eq!(stats.max, Duration::from_millis(4));
+        assert_eq!(stats.last, Duration::from_millis(4));
+        assert_eq!(metrics.stats(Phase::Draw), PhaseStats::default());
+        assert!(metrics
+            .summary()
+            .contains("render         2      3.00      4.00      4.00"));
+    }
+}
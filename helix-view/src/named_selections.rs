@@ -0,0 +1,130 @@
+//! Named selection sets: selections saved under a name with `:selection-save` and restored
+//! with `:selection-restore`.
+//!
+//! The sets of a document are kept up to date with its changes, see [`Document::apply`]. With
+//! the `editor.persist-selections` option they are also saved per file in
+//! `<state dir>/selections`, as a JSON object mapping names to the ranges (character offsets of
+//! the anchor and head) and the index of the primary range.
+//!
+//! [`Document::apply`]: crate::Document::apply
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use helix_core::{Range, RopeSlice, Selection, SmallVec};
+use serde::{Deserialize, Serialize};
+
+use crate::{register::encode_path, Document};
+
+#[derive(Serialize, Deserialize)]
+struct SavedSelection {
+    ranges: Vec<(usize, usize)>,
+    primary: usize,
+}
+
+/// The file which the named selections of the document at `path` are saved to.
+pub fn selections_file(path: &Path) -> PathBuf {
+    helix_loader::state_dir()
+        .join("selections")
+        .join(format!("{}.json", encode_path(path)))
+}
+
+/// Saves named selections to `file`, removing the file if there are none.
+pub fn save(file: &Path, selections: &BTreeMap<String, Selection>) -> Result<()> {
+    if selections.is_empty() {
+        return match std::fs::remove_file(file) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        };
+    }
+
+    let saved: BTreeMap<&str, SavedSelection> = selections
+        .iter()
+        .map(|(name, selection)| {
+            let saved = SavedSelection {
+                ranges: selection.iter().map(|r| (r.anchor, r.head)).collect(),
+                primary: selection.primary_index(),
+            };
+            (name.as_str(), saved)
+        })
+        .collect();
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(file, serde_json::to_vec(&saved)?)?;
+    Ok(())
+}
+
+/// Saves the named selections of a document with a path.
+pub fn save_document(doc: &Document) -> Result<()> {
+    match doc.path() {
+        Some(path) => save(&selections_file(path), &doc.named_selections),
+        None => Ok(()),
+    }
+}
+
+/// Restores the named selections saved with [save] for `text`. Ranges past the end of the text,
+/// for example because the file was changed by another program, are clamped to it. A missing
+/// file is not an error.
+pub fn load(file: &Path, text: RopeSlice) -> Result<BTreeMap<String, Selection>> {
+    let contents = match std::fs::read(file) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let saved: BTreeMap<String, SavedSelection> = serde_json::from_slice(&contents)?;
+
+    let len = text.len_chars();
+    let selections = saved
+        .into_iter()
+        .filter(|(_, saved)| !saved.ranges.is_empty())
+        .map(|(name, saved)| {
+            let ranges: SmallVec<_> = saved
+                .ranges
+                .into_iter()
+                .map(|(anchor, head)| Range::new(anchor.min(len), head.min(len)))
+                .collect();
+            let primary = saved.primary.min(ranges.len() - 1);
+            let selection = Selection::new(ranges, primary).ensure_invariants(text);
+            (name, selection)
+        })
+        .collect();
+    Ok(selections)
+}
+
+#[cfg(test)]
+mod tests {
+    use helix_core::{smallvec, Rope};
+
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("selections.json");
+        let text = Rope::from("hello world\n");
+
+        let mut selections = BTreeMap::new();
+        selections.insert(
+            "words".to_string(),
+            Selection::new(smallvec![Range::new(0, 5), Range::new(6, 11)], 1),
+        );
+        save(&file, &selections).unwrap();
+        assert_eq!(load(&file, text.slice(..)).unwrap(), selections);
+
+        // The text shrank since the selections were saved
+        let text = Rope::from("hello\n");
+        let loaded = load(&file, text.slice(..)).unwrap();
+        assert_eq!(
+            loaded["words"],
+            Selection::new(smallvec![Range::new(0, 5), Range::new(6, 6)], 1)
+        );
+
+        save(&file, &BTreeMap::new()).unwrap();
+        assert!(!file.exists());
+        assert!(load(&file, text.slice(..)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! The quickfix list: a list of locations (search matches, build errors, ...) which can be
+//! browsed with the `:quickfix` picker and stepped through with `goto_next_quickfix` and
+//! `goto_prev_quickfix`.
+
+use std::path::PathBuf;
+
+use helix_core::Position;
+
+use crate::DocumentId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickfixEntry {
+    /// The document the entry points into, if it was open when the entry was added.
+    pub doc_id: Option<DocumentId>,
+    /// The path of the file, used to reopen it once the document was closed.
+    pub path: Option<PathBuf>,
+    /// The zero-based start of the location, the column is a char offset into the line.
+    pub start: Position,
+    /// The zero-based end of the location, exclusive.
+    pub end: Position,
+    /// The text shown for the entry, for example the matched line.
+    pub text: String,
+}
+
+#[derive(Debug, Default)]
+pub struct QuickfixList {
+    pub title: String,
+    pub entries: Vec<QuickfixEntry>,
+    /// The index of the entry which was jumped to last.
+    pub current: Option<usize>,
+}
+
+impl QuickfixList {
+    pub fn new(title: impl Into<String>, entries: Vec<QuickfixEntry>) -> Self {
+        Self {
+            title: title.into(),
+            entries,
+            current: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Selects the entry `count` entries after the current one, wrapping around at the end.
+    pub fn next(&mut self, count: usize) -> Option<&QuickfixEntry> {
+        let len = self.entries.len();
+        if len == 0 {
+            return None;
+        }
+        let idx = match self.current {
+            Some(current) => (current + count) % len,
+            None => (count - 1) % len,
+        };
+        self.select(idx)
+    }
+
+    /// Selects the entry `count` entries before the current one, wrapping around at the start.
+    pub fn prev(&mut self, count: usize) -> Option<&QuickfixEntry> {
+        let len = self.entries.len();
+        if len == 0 {
+            return None;
+        }
+        let current = self.current.unwrap_or(0);
+        let idx = (current + len - count % len) % len;
+        self.select(idx)
+    }
+
+    pub fn select(&mut self, idx: usize) -> Option<&QuickfixEntry> {
+        let entry = self.entries.get(idx)?;
+        self.current = Some(idx);
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(len: usize) -> QuickfixList {
+        let entries = (0..len)
+            .map(|row| QuickfixEntry {
+                doc_id: None,
+                path: None,
+                start: Position::new(row, 0),
+                end: Position::new(row, 1),
+                text: String::new(),
+            })
+            .collect();
+        QuickfixList::new("test", entries)
+    }
+
+    #[test]
+    fn next_and_prev_wrap_around() {
+        let mut list = list(3);
+        assert_eq!(list.next(1).unwrap().start.row, 0);
+        assert_eq!(list.next(2).unwrap().start.row, 2);
+        assert_eq!(list.next(1).unwrap().start.row, 0);
+        assert_eq!(list.prev(1).unwrap().start.row, 2);
+        assert_eq!(list.prev(4).unwrap().start.row, 1);
+
+        assert!(QuickfixList::default().next(1).is_none());
+    }
+}
@@ -1,4 +1,9 @@
-use std::{borrow::Cow, collections::HashMap, iter};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    iter,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Result;
 use arc_swap::access::DynAccess;
@@ -217,6 +222,123 @@ fn clear_clipboard(&mut self, clipboard_type: ClipboardType) {
     pub fn clipboard_provider_name(&self) -> String {
         self.clipboard_provider.load().name().into_owned()
     }
+
+    /// Pushes a value onto a history register, such as the command (`:`) or search (`/`)
+    /// history. Repeating the most recent entry does not add a new one.
+    pub fn push_history(&mut self, name: char, value: String) -> Result<()> {
+        if self
+            .inner
+            .get(&name)
+            .and_then(|values| values.last())
+            .is_some_and(|last| *last == value)
+        {
+            return Ok(());
+        }
+        self.push(name, value)
+    }
+
+    /// Saves the persistent registers to `path` as JSON. Values are written in the order
+    /// they are read. Empty registers are skipped.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.save_filtered(path, is_persistent, usize::MAX)
+    }
+
+    /// Restores registers saved with [Registers::save]. Registers which are already set
+    /// in this session are left untouched. A missing file is not an error.
+    pub fn restore(&mut self, path: &Path) -> Result<()> {
+        self.restore_filtered(path, is_persistent)
+    }
+
+    /// Saves the command and search histories to `path`, keeping the most recent
+    /// [HISTORY_LIMIT] entries of each.
+    pub fn save_history(&self, path: &Path) -> Result<()> {
+        self.save_filtered(path, is_history, HISTORY_LIMIT)
+    }
+
+    /// Restores the histories saved with [Registers::save_history].
+    pub fn restore_history(&mut self, path: &Path) -> Result<()> {
+        self.restore_filtered(path, is_history)
+    }
+
+    fn save_filtered(&self, path: &Path, filter: fn(char) -> bool, limit: usize) -> Result<()> {
+        let registers: HashMap<char, Vec<&str>> = self
+            .inner
+            .iter()
+            .filter(|(name, values)| filter(**name) && !values.is_empty())
+            .map(|(name, values)| {
+                let values = values.iter().rev().take(limit).map(String::as_str);
+                (*name, values.collect())
+            })
+            .collect();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(&registers)?)?;
+        Ok(())
+    }
+
+    fn restore_filtered(&mut self, path: &Path, filter: fn(char) -> bool) -> Result<()> {
+        let contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        let registers: HashMap<char, Vec<String>> = serde_json::from_slice(&contents)?;
+
+        for (name, mut values) in registers {
+            if !filter(name) {
+                continue;
+            }
+            values.reverse();
+            self.inner.entry(name).or_insert(values);
+        }
+        Ok(())
+    }
+}
+
+/// The number of entries kept per history when saving it.
+pub const HISTORY_LIMIT: usize = 200;
+
+/// Registers which are saved across sessions: the named registers `a`-`z`, the search
+/// register `/` and the default macro register `@`.
+fn is_persistent(name: char) -> bool {
+    name.is_ascii_lowercase() || matches!(name, '/' | '@')
+}
+
+/// Registers holding prompt histories which are saved with [Registers::save_history]: the
+/// command line (`:`), search (`/`) and shell command (`|`) histories.
+fn is_history(name: char) -> bool {
+    matches!(name, ':' | '/' | '|')
+}
+
+/// The file which the command and search histories are saved to, shared by all workspaces.
+pub fn history_file() -> PathBuf {
+    helix_loader::state_dir().join("history.json")
+}
+
+/// The file which persistent registers are saved to for the current workspace.
+///
+/// Each workspace gets its own file in `<state dir>/registers`, named after the workspace
+/// path with separators percent-encoded.
+pub fn registers_file() -> PathBuf {
+    let (workspace, _) = helix_loader::find_workspace();
+    let name = encode_path(&workspace);
+    helix_loader::state_dir()
+        .join("registers")
+        .join(format!("{name}.json"))
+}
+
+/// Turns a path into a file name by percent-encoding its separators.
+pub(crate) fn encode_path(path: &Path) -> String {
+    let mut name = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '%' | '/' | '\\' | ':' => name.push_str(&format!("%{:02X}", c as u32)),
+            c => name.push(c),
+        }
+    }
+    name
 }
 
 fn read_from_clipboard<'a>(
@@ -333,3 +455,100 @@ fn len(&self) -> usize {
 trait DoubleEndedExactSizeIterator: DoubleEndedIterator + ExactSizeIterator {}
 
 impl<I: DoubleEndedIterator + ExactSizeIterator> DoubleEndedExactSizeIterator for I {}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arc_swap::ArcSwap;
+
+    use super::*;
+
+    fn registers() -> Registers {
+        Registers::new(Box::new(Arc::new(ArcSwap::from_pointee(
+            ClipboardProvider::None,
+        ))))
+    }
+
+    #[test]
+    fn push_history_skips_repeated_entries() {
+        let mut registers = registers();
+        for value in ["a", "a", "b", "a"] {
+            registers.push_history(':', value.to_string()).unwrap();
+        }
+        assert_eq!(registers.inner[&':'], ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn save_and_restore_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+
+        let mut registers = registers();
+        for i in 0..HISTORY_LIMIT + 50 {
+            registers.push_history(':', i.to_string()).unwrap();
+        }
+        registers.push_history('/', "search".to_string()).unwrap();
+        registers.push('a', "not history".to_string()).unwrap();
+        registers.save_history(&path).unwrap();
+
+        let mut restored = registers();
+        // Histories of this session are kept
+        restored.push_history('/', "current".to_string()).unwrap();
+        restored.restore_history(&path).unwrap();
+
+        // Only the most recent entries are kept, in order
+        let commands = &restored.inner[&':'];
+        assert_eq!(commands.len(), HISTORY_LIMIT);
+        assert_eq!(commands.first().unwrap(), "50");
+        assert_eq!(commands.last().unwrap(), &(HISTORY_LIMIT + 49).to_string());
+        assert_eq!(restored.inner[&'/'], ["current"]);
+        assert!(!restored.inner.contains_key(&'a'));
+    }
+
+    #[test]
+    fn save_and_restore_registers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("registers").join("workspace.json");
+
+        let mut registers = registers();
+        registers
+            .write('a', vec!["one".into(), "two".into()])
+            .unwrap();
+        registers.write('b', vec!["saved".into()]).unwrap();
+        registers.write('@', vec!["macro".into()]).unwrap();
+        registers.write('"', vec!["yank".into()]).unwrap();
+        registers.push_history(':', "command".to_string()).unwrap();
+        registers.save(&path).unwrap();
+
+        let mut restored = registers();
+        // Registers set in this session are kept
+        restored.write('b', vec!["current".into()]).unwrap();
+        restored.restore(&path).unwrap();
+
+        assert_eq!(restored.inner[&'a'], registers.inner[&'a']);
+        assert_eq!(restored.inner[&'b'], ["current"]);
+        assert_eq!(restored.inner[&'@'], ["macro"]);
+        assert!(!restored.inner.contains_key(&'"'));
+        assert!(!restored.inner.contains_key(&':'));
+    }
+
+    #[test]
+    fn encode_path_escapes_separators() {
+        assert_eq!(
+            encode_path(Path::new("/home/user/100%/src")),
+            "%2Fhome%2Fuser%2F100%25%2Fsrc"
+        );
+        assert_eq!(encode_path(Path::new(r"C:\src")), "C%3A%5Csrc");
+    }
+
+    #[test]
+    fn restore_missing_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registers = registers();
+        registers
+            .restore_history(&dir.path().join("history.json"))
+            .unwrap();
+        assert!(registers.inner.is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! Process-wide limits on background work, and pausing it while the editor is slow to respond.
+//!
+//! [`apply`] sets the limits of `editor.resources`: the number of background jobs running at
+//! once and the number of requests in flight to each language server. When handling and
+//! drawing input events takes longer than `latency-threshold` for a run of events,
+//! [`LatencyMonitor`] pauses background work, such as indexing the words of open documents,
+//! for [`PAUSE`] after the last run of slow events.
+
+use std::{
+    future::Future,
+    pin::pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use tokio::{sync::Notify, time::Instant};
+
+use crate::editor::ResourceConfig;
+
+/// The number of consecutive slow events after which background work is paused.
+const SLOW_EVENTS: u32 = 5;
+
+/// How long background work stays paused after the last run of slow events.
+pub const PAUSE: Duration = Duration::from_secs(5);
+
+/// The maximum number of background jobs running at once, `0` for no limit.
+static MAX_BACKGROUND_JOBS: AtomicUsize = AtomicUsize::new(0);
+static RUNNING_BACKGROUND_JOBS: AtomicUsize = AtomicUsize::new(0);
+/// Notified when a background job finishes or the limit is raised.
+static BACKGROUND_JOB_SLOT: Notify = Notify::const_new();
+
+/// Background work is paused until this deadline.
+static PAUSED_UNTIL: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Applies the limits of the config, called on startup and when the config is reloaded.
+pub fn apply(config: &ResourceConfig) {
+    MAX_BACKGROUND_JOBS.store(config.max_background_jobs, Ordering::Relaxed);
+    BACKGROUND_JOB_SLOT.notify_waiters();
+    helix_lsp::set_max_concurrent_requests(config.max_lsp_requests);
+}
+
+/// A slot of `max-background-jobs`, released when dropped.
+#[must_use]
+pub struct BackgroundJobSlot(());
+
+impl Drop for BackgroundJobSlot {
+    fn drop(&mut self) {
+        RUNNING_BACKGROUND_JOBS.fetch_sub(1, Ordering::AcqRel);
+        BACKGROUND_JOB_SLOT.notify_waiters();
+    }
+}
+
+/// Waits until fewer than `max-background-jobs` background jobs are running.
+pub async fn background_job_slot() -> BackgroundJobSlot {
+    loop {
+        // Registered before checking the count so that a job finishing in between isn't missed
+        let mut finished = pin!(BACKGROUND_JOB_SLOT.notified());
+        finished.as_mut().enable();
+        let max = MAX_BACKGROUND_JOBS.load(Ordering::Relaxed);
+        let acquired =
+            RUNNING_BACKGROUND_JOBS.fetch_update(Ordering::AcqRel, Ordering::Relaxed, |running| {
+                (max == 0 || running < max).then_some(running + 1)
+            });
+        if acquired.is_ok() {
+            return BackgroundJobSlot(());
+        }
+        finished.await;
+    }
+}
+
+/// Runs `job` once a background job slot is free, see [`background_job_slot`].
+pub async fn limit_background_job<F: Future>(job: F) -> F::Output {
+    let _slot = background_job_slot().await;
+    job.await
+}
+
+/// Whether background work is currently paused.
+pub fn background_paused() -> bool {
+    PAUSED_UNTIL
+        .lock()
+        .is_some_and(|deadline| deadline > Instant::now())
+}
+
+/// Pauses background work for `duration`, or longer if it is already paused for longer.
+pub fn pause_background(duration: Duration) {
+    let deadline = Instant::now() + duration;
+    let mut paused_until = PAUSED_UNTIL.lock();
+    if paused_until.is_none_or(|paused_until| paused_until < deadline) {
+        *paused_until = Some(deadline);
+    }
+}
+
+/// Waits until background work isn't paused. Work which can be deferred, like indexing, waits
+/// for this before each step.
+pub async fn wait_until_resumed() {
+    loop {
+        let deadline = *PAUSED_UNTIL.lock();
+        match deadline {
+            Some(deadline) if deadline > Instant::now() => tokio::time::sleep_until(deadline).await,
+            _ => return,
+        }
+    }
+}
+
+/// Pauses background work when input events are slow to handle and draw for a run of events,
+/// see `editor.resources.latency-threshold`.
+#[derive(Debug, Default)]
+pub struct LatencyMonitor {
+    /// Consecutive events slower than the threshold.
+    streak: u32,
+}
+
+impl LatencyMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records how long an input event took to handle and draw. Returns whether this paused
+    /// background work.
+    pub fn record(&mut self, latency: Duration, config: &ResourceConfig) -> bool {
+        if config.latency_threshold.is_zero() || latency <= config.latency_threshold {
+            self.streak = 0;
+            return false;
+        }
+        self.streak += 1;
+        if self.streak < SLOW_EVENTS {
+            return false;
+        }
+        let paused = background_paused();
+        pause_background(PAUSE);
+        if !paused {
+            log::info!("pausing background work, input events took {latency:?} to handle");
+        }
+        !paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sustained_slow_events_pause_background_work() {
+        let config = ResourceConfig {
+            latency_threshold: Duration::from_millis(50),
+            ..ResourceConfig::default()
+        };
+        let slow = Duration::from_millis(80);
+        let fast = Duration::from_millis(2);
+        let mut monitor = LatencyMonitor::new();
+
+        // A single fast event resets the streak
+        for _ in 0..SLOW_EVENTS - 1 {
+            assert!(!monitor.record(slow, &config));
+        }
+        assert!(!monitor.record(fast, &config));
+        assert!(!background_paused());
+        for _ in 0..SLOW_EVENTS - 1 {
+            assert!(!monitor.record(slow, &config));
+        }
+        assert!(monitor.record(slow, &config));
+        assert!(background_paused());
+        // Further slow events extend the pause
+        assert!(!monitor.record(slow, &config));
+    }
+}
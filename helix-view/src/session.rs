@@ -0,0 +1,209 @@
+//! Sessions: the open files, their selections and the splits showing them.
+//!
+//! With the `editor.persist-session` option the session is saved per workspace in
+//! `<state dir>/sessions` when the editor closes. It is restored when the editor is started
+//! without files in the workspace with `editor.restore-session` or `--restore-session`. Files
+//! which no longer exist are skipped, a view showing one shows a scratch buffer instead.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use helix_core::{Range, Selection, SmallVec};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    align_view,
+    editor::Action,
+    register::encode_path,
+    tree::{Layout, Split},
+    Align, DocumentId, Editor, ViewId,
+};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Session {
+    /// The splits of the active layout.
+    splits: SessionSplit,
+    /// The files open without being shown in a view.
+    #[serde(default)]
+    buffers: Vec<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum SessionSplit {
+    View {
+        /// The file shown in the view, `None` for a scratch buffer.
+        path: Option<PathBuf>,
+        /// The ranges of the selection as the character offsets of the anchor and head.
+        ranges: Vec<(usize, usize)>,
+        primary: usize,
+        #[serde(default)]
+        focused: bool,
+    },
+    Container {
+        layout: Layout,
+        children: Vec<SessionSplit>,
+    },
+}
+
+/// The file which the session of the current workspace is saved to.
+pub fn session_file() -> PathBuf {
+    let (workspace, _) = helix_loader::find_workspace();
+    helix_loader::state_dir()
+        .join("sessions")
+        .join(format!("{}.json", encode_path(&workspace)))
+}
+
+/// Saves the open files, their selections and the splits of the active layout to `file`.
+pub fn save(editor: &Editor, file: &Path) -> Result<()> {
+    let splits = session_split(editor, editor.tree.splits());
+    let buffers = editor
+        .documents()
+        .filter(|doc| !editor.tree.views().any(|(view, _)| view.doc == doc.id()))
+        .filter_map(|doc| doc.path().cloned())
+        .collect();
+    let session = Session { splits, buffers };
+
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(file, serde_json::to_vec(&session)?)?;
+    Ok(())
+}
+
+fn session_split(editor: &Editor, split: Split) -> SessionSplit {
+    match split {
+        Split::View(view_id) => {
+            let view = editor.tree.get(view_id);
+            let doc = &editor.documents[&view.doc];
+            let selection = doc.selection(view_id);
+            SessionSplit::View {
+                path: doc.path().cloned(),
+                ranges: selection.iter().map(|r| (r.anchor, r.head)).collect(),
+                primary: selection.primary_index(),
+                focused: view_id == editor.tree.focus,
+            }
+        }
+        Split::Container(layout, children) => SessionSplit::Container {
+            layout,
+            children: children
+                .into_iter()
+                .map(|child| session_split(editor, child))
+                .collect(),
+        },
+    }
+}
+
+/// Restores the session saved with [save] to `file` into the focused view, which is split as
+/// needed. Returns whether there was a session to restore.
+pub fn restore(editor: &mut Editor, file: &Path) -> Result<bool> {
+    let contents = match std::fs::read(file) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+    let session: Session = serde_json::from_slice(&contents)?;
+
+    for path in session.buffers.iter().filter(|path| path.is_file()) {
+        editor.open(path, Action::Load)?;
+    }
+    let focus = restore_split(editor, session.splits)?;
+    if let Some(focus) = focus {
+        editor.focus(focus);
+    }
+    Ok(true)
+}
+
+/// Restores `split` into the focused view. Returns the view which was focused in the session.
+fn restore_split(editor: &mut Editor, split: SessionSplit) -> Result<Option<ViewId>> {
+    match split {
+        SessionSplit::View {
+            path,
+            ranges,
+            primary,
+            focused,
+        } => {
+            let doc_id = match path.filter(|path| path.is_file()) {
+                Some(path) => editor.open(&path, Action::Replace)?,
+                None => return Ok(focused.then_some(editor.tree.focus)),
+            };
+            let view_id = editor.tree.focus;
+            restore_selection(editor, doc_id, view_id, ranges, primary);
+            Ok(focused.then_some(view_id))
+        }
+        SessionSplit::Container { layout, children } => {
+            // Split the focused view once per further child, then restore each child into its
+            // view. The splits of a nested container have the other layout, so they split the
+            // child's view instead of adding to this container.
+            let action = match layout {
+                Layout::Horizontal => Action::HorizontalSplit,
+                Layout::Vertical => Action::VerticalSplit,
+            };
+            let mut views = vec![editor.tree.focus];
+            for _ in 1..children.len() {
+                let doc_id = view!(editor).doc;
+                editor.switch(doc_id, action);
+                views.push(editor.tree.focus);
+            }
+
+            let mut focus = None;
+            for (child, view_id) in children.into_iter().zip(views) {
+                editor.focus(view_id);
+                focus = restore_split(editor, child)?.or(focus);
+            }
+            Ok(focus)
+        }
+    }
+}
+
+fn restore_selection(
+    editor: &mut Editor,
+    doc_id: DocumentId,
+    view_id: ViewId,
+    ranges: Vec<(usize, usize)>,
+    primary: usize,
+) {
+    if ranges.is_empty() {
+        return;
+    }
+    let doc = doc_mut!(editor, &doc_id);
+    let text = doc.text().slice(..);
+    // The file may have been changed by another program
+    let len = text.len_chars();
+    let ranges: SmallVec<_> = ranges
+        .into_iter()
+        .map(|(anchor, head)| Range::new(anchor.min(len), head.min(len)))
+        .collect();
+    let primary = primary.min(ranges.len() - 1);
+    let selection = Selection::new(ranges, primary).ensure_invariants(text);
+    doc.set_selection(view_id, selection);
+    let view = editor.tree.get(view_id);
+    align_view(doc, view, Align::Center);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_format() {
+        let session = Session {
+            splits: SessionSplit::Container {
+                layout: Layout::Vertical,
+                children: vec![SessionSplit::View {
+                    path: Some(PathBuf::from("/project/src/main.rs")),
+                    ranges: vec![(0, 4)],
+                    primary: 0,
+                    focused: true,
+                }],
+            },
+            buffers: vec![PathBuf::from("/project/Cargo.toml")],
+        };
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(
+            json,
+            r#"{"splits":{"container":{"layout":"vertical","children":[{"view":{"path":"/project/src/main.rs","ranges":[[0,4]],"primary":0,"focused":true}}]}},"buffers":["/project/Cargo.toml"]}"#
+        );
+        assert_eq!(serde_json::from_str::<Session>(&json).unwrap(), session);
+    }
+}
@@ -0,0 +1,1038 @@
+//! An embedded terminal: a shell running in a pseudo terminal, with its output parsed into a grid
+//! of cells by [`Screen`]. The editor reads the output as one of the event sources of
+//! `Editor::wait_event`, the terminal panel of the UI draws the screen and sends the keys typed
+//! into it to the shell.
+
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+    process::Command,
+    sync::mpsc,
+};
+
+use helix_core::unicode::width::UnicodeWidthChar;
+use tokio::sync::mpsc::{channel, Receiver};
+
+use crate::{
+    graphics::{Color, Modifier, Style, UnderlineStyle},
+    input::KeyEvent,
+    keyboard::{KeyCode, KeyModifiers},
+};
+
+/// The size of the reads of the output thread.
+const READ_SIZE: usize = 4096;
+
+/// The number of reads the output thread buffers before it waits for the editor to process
+/// them, which in turn makes the shell wait.
+const OUTPUT_BUFFER: usize = 16;
+
+const TAB_WIDTH: u16 = 8;
+
+/// The colors of the SGR parameters 30-37 and 90-97, in the order of their palette indices.
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightGray,
+    Color::Gray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// A cell of the [`Screen`]. A wide character takes up two cells, the second one is a space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: char,
+    /// The style set by the shell, colors it didn't set are `None`.
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            symbol: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+/// The screen of an xterm-like terminal, written to by parsing the output of a program with a
+/// `vte::Parser`. It covers what shells and common full screen programs use: cursor movement,
+/// erasing, scroll regions, colors and text attributes and the alternate screen. Lines scrolled
+/// off the top aren't kept.
+pub struct Screen {
+    rows: u16,
+    cols: u16,
+    cells: Vec<Cell>,
+    /// The cells of the primary screen while the alternate screen is shown.
+    primary: Option<Vec<Cell>>,
+    row: u16,
+    col: u16,
+    /// Set after a character was printed in the last column, the next one starts a new line.
+    pending_wrap: bool,
+    saved_cursor: (u16, u16, Style),
+    style: Style,
+    /// The first and last row moved by line feeds, set with DECSTBM.
+    scroll_top: u16,
+    scroll_bottom: u16,
+    cursor_visible: bool,
+    auto_wrap: bool,
+    /// DECCKM, the cursor keys send SS3 sequences rather than CSI ones.
+    application_cursor: bool,
+    bracketed_paste: bool,
+    title: String,
+    /// Replies to the queries of the program, like the cursor position, to be written back.
+    replies: Vec<u8>,
+}
+
+impl Screen {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+        Self {
+            rows,
+            cols,
+            cells: vec![Cell::default(); rows as usize * cols as usize],
+            primary: None,
+            row: 0,
+            col: 0,
+            pending_wrap: false,
+            saved_cursor: (0, 0, Style::default()),
+            style: Style::default(),
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+            cursor_visible: true,
+            auto_wrap: true,
+            application_cursor: false,
+            bracketed_paste: false,
+            title: String::new(),
+            replies: Vec::new(),
+        }
+    }
+
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    pub fn cols(&self) -> u16 {
+        self.cols
+    }
+
+    pub fn cell(&self, row: u16, col: u16) -> &Cell {
+        &self.cells[self.index(row, col)]
+    }
+
+    /// The row and column of the cursor, `None` while the program hides it.
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        self.cursor_visible.then_some((self.row, self.col))
+    }
+
+    /// The title set by the program, empty if it didn't set one.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The text of the screen, one line per row without trailing whitespace.
+    pub fn contents(&self) -> String {
+        let lines: Vec<String> = self
+            .cells
+            .chunks(self.cols as usize)
+            .map(|row| {
+                let line: String = row.iter().map(|cell| cell.symbol).collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+        lines.join("\n").trim_end().to_string()
+    }
+
+    /// Resizes the screen, keeping the row of the cursor in view.
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+        if (rows, cols) == (self.rows, self.cols) {
+            return;
+        }
+        let shift = (self.row + 1).saturating_sub(rows);
+        let (old_rows, old_cols) = (self.rows, self.cols);
+        let resize = |cells: &[Cell]| {
+            let mut resized = vec![Cell::default(); rows as usize * cols as usize];
+            for row in 0..rows.min(old_rows - shift) {
+                for col in 0..cols.min(old_cols) {
+                    let old = (row + shift) as usize * old_cols as usize + col as usize;
+                    resized[row as usize * cols as usize + col as usize] = cells[old];
+                }
+            }
+            resized
+        };
+        self.cells = resize(&self.cells);
+        self.primary = self.primary.as_deref().map(resize);
+        self.rows = rows;
+        self.cols = cols;
+        self.row -= shift;
+        self.col = self.col.min(cols - 1);
+        self.pending_wrap = false;
+        self.scroll_top = 0;
+        self.scroll_bottom = rows - 1;
+    }
+
+    fn index(&self, row: u16, col: u16) -> usize {
+        row as usize * self.cols as usize + col as usize
+    }
+
+    /// An erased cell, which keeps the background color of the current style.
+    fn blank(&self) -> Cell {
+        Cell {
+            symbol: ' ',
+            style: Style {
+                bg: self.style.bg,
+                ..Style::default()
+            },
+        }
+    }
+
+    fn erase(&mut self, start: usize, end: usize) {
+        let blank = self.blank();
+        let end = end.min(self.cells.len());
+        self.cells[start.min(end)..end].fill(blank);
+    }
+
+    /// Moves the rows `top..=bottom` up by `count`, the rows at the bottom are blank.
+    fn scroll_up(&mut self, top: u16, bottom: u16, count: u16) {
+        let cols = self.cols as usize;
+        let count = count.min(bottom + 1 - top) as usize;
+        let (start, end) = (top as usize * cols, (bottom as usize + 1) * cols);
+        self.cells[start..end].rotate_left(count * cols);
+        self.erase(end - count * cols, end);
+    }
+
+    /// Moves the rows `top..=bottom` down by `count`, the rows at the top are blank.
+    fn scroll_down(&mut self, top: u16, bottom: u16, count: u16) {
+        let cols = self.cols as usize;
+        let count = count.min(bottom + 1 - top) as usize;
+        let (start, end) = (top as usize * cols, (bottom as usize + 1) * cols);
+        self.cells[start..end].rotate_right(count * cols);
+        self.erase(start, start + count * cols);
+    }
+
+    fn line_feed(&mut self) {
+        self.pending_wrap = false;
+        if self.row == self.scroll_bottom {
+            self.scroll_up(self.scroll_top, self.scroll_bottom, 1);
+        } else if self.row + 1 < self.rows {
+            self.row += 1;
+        }
+    }
+
+    fn reverse_line_feed(&mut self) {
+        self.pending_wrap = false;
+        if self.row == self.scroll_top {
+            self.scroll_down(self.scroll_top, self.scroll_bottom, 1);
+        } else {
+            self.row = self.row.saturating_sub(1);
+        }
+    }
+
+    fn move_to(&mut self, row: u16, col: u16) {
+        self.row = row.min(self.rows - 1);
+        self.col = col.min(self.cols - 1);
+        self.pending_wrap = false;
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved_cursor = (self.row, self.col, self.style);
+    }
+
+    fn restore_cursor(&mut self) {
+        let (row, col, style) = self.saved_cursor;
+        self.move_to(row, col);
+        self.style = style;
+    }
+
+    fn set_alternate_screen(&mut self, enabled: bool) {
+        if enabled && self.primary.is_none() {
+            let blank = vec![Cell::default(); self.cells.len()];
+            self.primary = Some(std::mem::replace(&mut self.cells, blank));
+        } else if let Some(primary) = self.primary.take().filter(|_| !enabled) {
+            self.cells = primary;
+        }
+    }
+
+    fn set_private_mode(&mut self, mode: u16, enabled: bool) {
+        match mode {
+            1 => self.application_cursor = enabled,
+            7 => self.auto_wrap = enabled,
+            25 => self.cursor_visible = enabled,
+            47 | 1047 => self.set_alternate_screen(enabled),
+            1049 => {
+                if enabled {
+                    self.save_cursor();
+                    self.set_alternate_screen(true);
+                    self.erase(0, self.cells.len());
+                } else {
+                    self.set_alternate_screen(false);
+                    self.restore_cursor();
+                }
+            }
+            2004 => self.bracketed_paste = enabled,
+            _ => log::debug!("unsupported private mode {mode} in the terminal"),
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, params: &vte::Params) {
+        let params: Vec<&[u16]> = params.iter().collect();
+        if params.is_empty() {
+            self.style = Style::default();
+            return;
+        }
+        let mut i = 0;
+        while i < params.len() {
+            let param = params[i];
+            i += 1;
+            let style = &mut self.style;
+            match param[0] {
+                0 => *style = Style::default(),
+                1 => style.add_modifier.insert(Modifier::BOLD),
+                2 => style.add_modifier.insert(Modifier::DIM),
+                3 => style.add_modifier.insert(Modifier::ITALIC),
+                4 => {
+                    style.underline_style = match param.get(1) {
+                        Some(0) => None,
+                        Some(2) => Some(UnderlineStyle::DoubleLine),
+                        Some(3) => Some(UnderlineStyle::Curl),
+                        Some(4) => Some(UnderlineStyle::Dotted),
+                        Some(5) => Some(UnderlineStyle::Dashed),
+                        _ => Some(UnderlineStyle::Line),
+                    }
+                }
+                5 => style.add_modifier.insert(Modifier::SLOW_BLINK),
+                6 => style.add_modifier.insert(Modifier::RAPID_BLINK),
+                7 => style.add_modifier.insert(Modifier::REVERSED),
+                8 => style.add_modifier.insert(Modifier::HIDDEN),
+                9 => style.add_modifier.insert(Modifier::CROSSED_OUT),
+                21 => style.underline_style = Some(UnderlineStyle::DoubleLine),
+                22 => style.add_modifier.remove(Modifier::BOLD | Modifier::DIM),
+                23 => style.add_modifier.remove(Modifier::ITALIC),
+                24 => style.underline_style = None,
+                25 => style
+                    .add_modifier
+                    .remove(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+                27 => style.add_modifier.remove(Modifier::REVERSED),
+                28 => style.add_modifier.remove(Modifier::HIDDEN),
+                29 => style.add_modifier.remove(Modifier::CROSSED_OUT),
+                n @ 30..=37 => style.fg = Some(ANSI_COLORS[n as usize - 30]),
+                n @ 90..=97 => style.fg = Some(ANSI_COLORS[n as usize - 90 + 8]),
+                39 => style.fg = None,
+                n @ 40..=47 => style.bg = Some(ANSI_COLORS[n as usize - 40]),
+                n @ 100..=107 => style.bg = Some(ANSI_COLORS[n as usize - 100 + 8]),
+                49 => style.bg = None,
+                59 => style.underline_color = None,
+                n @ (38 | 48 | 58) => {
+                    let (color, consumed) = extended_color(param, &params[i..]);
+                    i += consumed;
+                    match n {
+                        38 => style.fg = color,
+                        48 => style.bg = color,
+                        _ => style.underline_color = color,
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Parses the color of an SGR 38, 48 or 58 parameter, either in the colon separated form
+/// `38:5:n` or `38:2::r:g:b`, or in the semicolon separated form `38;5;n` which takes up the
+/// parameters after it in `rest`. Returns the color and the number of parameters of `rest` it
+/// consumed.
+fn extended_color(param: &[u16], rest: &[&[u16]]) -> (Option<Color>, usize) {
+    let to_u8 = |value: u16| value.min(u8::MAX as u16) as u8;
+    if param.len() > 1 {
+        let color = match param[1..] {
+            [5, n, ..] => Some(Color::Indexed(to_u8(n))),
+            [2, _, r, g, b, ..] | [2, r, g, b] => Some(Color::Rgb(to_u8(r), to_u8(g), to_u8(b))),
+            _ => None,
+        };
+        return (color, 0);
+    }
+    let value = |i: usize| rest.get(i).map(|param| param[0]);
+    match value(0) {
+        Some(5) => (value(1).map(|n| Color::Indexed(to_u8(n))), 2),
+        Some(2) => match (value(1), value(2), value(3)) {
+            (Some(r), Some(g), Some(b)) => (Some(Color::Rgb(to_u8(r), to_u8(g), to_u8(b))), 4),
+            _ => (None, rest.len()),
+        },
+        _ => (None, 0),
+    }
+}
+
+impl vte::Perform for Screen {
+    fn print(&mut self, c: char) {
+        // Combining characters aren't drawn
+        let width = match c.width() {
+            Some(0) | None => return,
+            Some(width) => width as u16,
+        };
+        if self.pending_wrap && self.auto_wrap {
+            self.line_feed();
+            self.col = 0;
+        }
+        self.pending_wrap = false;
+        if self.col + width > self.cols {
+            if self.auto_wrap && width <= self.cols {
+                self.line_feed();
+                self.col = 0;
+            } else {
+                self.col = self.cols.saturating_sub(width);
+            }
+        }
+        let index = self.index(self.row, self.col);
+        self.cells[index] = Cell {
+            symbol: c,
+            style: self.style,
+        };
+        if width == 2 && index + 1 < self.cells.len() {
+            self.cells[index + 1] = Cell {
+                symbol: ' ',
+                style: self.style,
+            };
+        }
+        self.col += width;
+        if self.col >= self.cols {
+            self.col = self.cols - 1;
+            self.pending_wrap = true;
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x08 => self.move_to(self.row, self.col.saturating_sub(1)),
+            0x09 => self.move_to(self.row, (self.col / TAB_WIDTH + 1) * TAB_WIDTH),
+            0x0A..=0x0C => self.line_feed(),
+            0x0D => self.move_to(self.row, 0),
+            _ => (),
+        }
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &vte::Params,
+        intermediates: &[u8],
+        ignore: bool,
+        action: char,
+    ) {
+        if ignore {
+            return;
+        }
+        let values: Vec<u16> = params.iter().map(|param| param[0]).collect();
+        let value = |i: usize| values.get(i).copied().unwrap_or(0);
+        // The number of rows, columns or characters to move by or act on, at least one
+        let count = value(0).max(1);
+        let (row, col) = (self.row, self.col);
+
+        match (intermediates, action) {
+            (b"?", 'h' | 'l') => {
+                for &mode in &values {
+                    self.set_private_mode(mode, action == 'h');
+                }
+            }
+            (b"", 'A') => self.move_to(row.saturating_sub(count), col),
+            (b"", 'B' | 'e') => self.move_to(row.saturating_add(count), col),
+            (b"", 'C' | 'a') => self.move_to(row, col.saturating_add(count)),
+            (b"", 'D') => self.move_to(row, col.saturating_sub(count)),
+            (b"", 'E') => self.move_to(row.saturating_add(count), 0),
+            (b"", 'F') => self.move_to(row.saturating_sub(count), 0),
+            (b"", 'G' | '`') => self.move_to(row, count - 1),
+            (b"", 'd') => self.move_to(count - 1, col),
+            (b"", 'H' | 'f') => self.move_to(value(0).max(1) - 1, value(1).max(1) - 1),
+            (b"", 'J') => {
+                let cursor = self.index(row, col);
+                match value(0) {
+                    0 => self.erase(cursor, self.cells.len()),
+                    1 => self.erase(0, cursor + 1),
+                    _ => self.erase(0, self.cells.len()),
+                }
+            }
+            (b"", 'K') => {
+                let (start, cursor) = (self.index(row, 0), self.index(row, col));
+                let end = start + self.cols as usize;
+                match value(0) {
+                    0 => self.erase(cursor, end),
+                    1 => self.erase(start, cursor + 1),
+                    _ => self.erase(start, end),
+                }
+            }
+            (b"", 'L') if (self.scroll_top..=self.scroll_bottom).contains(&row) => {
+                self.scroll_down(row, self.scroll_bottom, count);
+                self.move_to(row, 0);
+            }
+            (b"", 'M') if (self.scroll_top..=self.scroll_bottom).contains(&row) => {
+                self.scroll_up(row, self.scroll_bottom, count);
+                self.move_to(row, 0);
+            }
+            (b"", '@' | 'P') => {
+                let (start, end) = (self.index(row, col), self.index(row, self.cols - 1) + 1);
+                let count = (count as usize).min(end - start);
+                if action == '@' {
+                    self.cells[start..end].rotate_right(count);
+                    self.erase(start, start + count);
+                } else {
+                    self.cells[start..end].rotate_left(count);
+                    self.erase(end - count, end);
+                }
+            }
+            (b"", 'X') => {
+                let start = self.index(row, col);
+                let end = self.index(row, self.cols - 1) + 1;
+                self.erase(start, (start + count as usize).min(end));
+            }
+            (b"", 'S') => self.scroll_up(self.scroll_top, self.scroll_bottom, count),
+            (b"", 'T') => self.scroll_down(self.scroll_top, self.scroll_bottom, count),
+            (b"", 'm') => self.select_graphic_rendition(params),
+            (b"", 'r') => {
+                let top = value(0).max(1) - 1;
+                let bottom = match value(1) {
+                    0 => self.rows - 1,
+                    bottom => bottom.min(self.rows) - 1,
+                };
+                if top < bottom {
+                    self.scroll_top = top;
+                    self.scroll_bottom = bottom;
+                    self.move_to(0, 0);
+                }
+            }
+            (b"", 's') => self.save_cursor(),
+            (b"", 'u') => self.restore_cursor(),
+            (b"", 'n') => match value(0) {
+                5 => self.replies.extend_from_slice(b"\x1b[0n"),
+                6 => self
+                    .replies
+                    .extend(format!("\x1b[{};{}R", row + 1, col + 1).bytes()),
+                _ => (),
+            },
+            // Primary device attributes: a VT100 with advanced video
+            (b"", 'c') if value(0) == 0 => self.replies.extend_from_slice(b"\x1b[?1;2c"),
+            _ => log::debug!(
+                "unsupported CSI sequence {:?} {action} in the terminal",
+                String::from_utf8_lossy(intermediates)
+            ),
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        // Character set designations like `ESC ( B` have intermediates, only UTF-8 is supported
+        if ignore || !intermediates.is_empty() {
+            return;
+        }
+        match byte {
+            b'7' => self.save_cursor(),
+            b'8' => self.restore_cursor(),
+            b'D' => self.line_feed(),
+            b'E' => {
+                self.line_feed();
+                self.col = 0;
+            }
+            b'M' => self.reverse_line_feed(),
+            b'c' => *self = Self::new(self.rows, self.cols),
+            _ => (),
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        if let [b"0" | b"2", title @ ..] = params {
+            let title: Vec<_> = title
+                .iter()
+                .map(|part| String::from_utf8_lossy(part))
+                .collect();
+            self.title = title.join(";");
+        }
+    }
+}
+
+/// The bytes an xterm sends for `key`, `None` for keys it doesn't send anything for.
+fn key_bytes(key: KeyEvent, application_cursor: bool) -> Option<Vec<u8>> {
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    // The modifier parameter of xterm's CSI sequences
+    let modifier = 1 + shift as u8 + 2 * alt as u8 + 4 * ctrl as u8;
+    let cursor_key = |c: char| match (modifier, application_cursor) {
+        (1, true) => format!("\x1bO{c}"),
+        (1, false) => format!("\x1b[{c}"),
+        _ => format!("\x1b[1;{modifier}{c}"),
+    };
+    let tilde_key = |n: u8| match modifier {
+        1 => format!("\x1b[{n}~"),
+        _ => format!("\x1b[{n};{modifier}~"),
+    };
+    let alt_prefix = if alt { "\x1b" } else { "" };
+
+    let bytes = match key.code {
+        KeyCode::Char(c) if ctrl => {
+            let byte = match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => c as u8 - b'a' + 1,
+                '@' | ' ' | '2' => 0,
+                '[' | '3' => 0x1B,
+                '\\' | '4' => 0x1C,
+                ']' | '5' => 0x1D,
+                '^' | '6' => 0x1E,
+                '_' | '7' | '/' => 0x1F,
+                '?' | '8' => 0x7F,
+                _ => return None,
+            };
+            let mut bytes = alt_prefix.as_bytes().to_vec();
+            bytes.push(byte);
+            return Some(bytes);
+        }
+        KeyCode::Char(c) => format!("{alt_prefix}{c}"),
+        KeyCode::Enter => format!("{alt_prefix}\r"),
+        KeyCode::Tab if shift => "\x1b[Z".to_string(),
+        KeyCode::Tab => format!("{alt_prefix}\t"),
+        KeyCode::Backspace => format!("{alt_prefix}\x7f"),
+        KeyCode::Esc => format!("{alt_prefix}\x1b"),
+        KeyCode::Up => cursor_key('A'),
+        KeyCode::Down => cursor_key('B'),
+        KeyCode::Right => cursor_key('C'),
+        KeyCode::Left => cursor_key('D'),
+        KeyCode::Home => cursor_key('H'),
+        KeyCode::End => cursor_key('F'),
+        KeyCode::Insert => tilde_key(2),
+        KeyCode::Delete => tilde_key(3),
+        KeyCode::PageUp => tilde_key(5),
+        KeyCode::PageDown => tilde_key(6),
+        KeyCode::F(n @ 1..=4) => {
+            let c = (b'P' + n - 1) as char;
+            match modifier {
+                1 => format!("\x1bO{c}"),
+                _ => format!("\x1b[1;{modifier}{c}"),
+            }
+        }
+        KeyCode::F(n @ 5..=12) => tilde_key([15, 17, 18, 19, 20, 21, 23, 24][n as usize - 5]),
+        _ => return None,
+    };
+    Some(bytes.into_bytes())
+}
+
+/// A shell running in a pseudo terminal, see the [module documentation](self).
+pub struct Terminal {
+    screen: Screen,
+    parser: vte::Parser,
+    pty: pty::Pty,
+    /// The input of the shell, written on a thread so that a shell which doesn't read it can't
+    /// block the editor.
+    input: mpsc::Sender<Vec<u8>>,
+    output: Receiver<Vec<u8>>,
+}
+
+impl Terminal {
+    /// Starts `shell` in `cwd` on a screen of `rows` by `cols` cells. Its output is read on a
+    /// thread of its own until all programs using the terminal exited or the terminal is dropped.
+    pub fn spawn(shell: &str, cwd: &Path, rows: u16, cols: u16) -> io::Result<Self> {
+        let mut command = Command::new(shell);
+        command
+            .current_dir(cwd)
+            .env("TERM", "xterm-256color")
+            .env_remove("COLUMNS")
+            .env_remove("LINES");
+        let screen = Screen::new(rows, cols);
+        let (pty, mut reader) = pty::Pty::spawn(command, screen.rows, screen.cols)?;
+        let mut writer = pty.writer()?;
+        let (tx, output) = channel(OUTPUT_BUFFER);
+        std::thread::Builder::new()
+            .name("terminal output".to_string())
+            .spawn(move || {
+                let mut buf = [0; READ_SIZE];
+                loop {
+                    match reader.read(&mut buf) {
+                        Ok(0) => return,
+                        Ok(n) => {
+                            if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                                return;
+                            }
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        // Linux fails with EIO once the other side of the terminal is closed
+                        Err(_) => return,
+                    }
+                }
+            })?;
+        let (input, rx) = mpsc::channel::<Vec<u8>>();
+        std::thread::Builder::new()
+            .name("terminal input".to_string())
+            .spawn(move || {
+                for bytes in rx {
+                    if let Err(err) = writer.write_all(&bytes) {
+                        log::warn!("failed to write to the terminal: {err}");
+                        return;
+                    }
+                }
+            })?;
+        Ok(Self {
+            screen,
+            parser: vte::Parser::new(),
+            pty,
+            input,
+            output,
+        })
+    }
+
+    /// Waits for the next output of the shell, `None` once it exited. The output read already
+    /// is returned at once, so that a lot of it isn't drawn one read at a time.
+    ///
+    /// This is cancel safe, so it can be used in `tokio::select!` with other event sources.
+    pub async fn read(&mut self) -> Option<Vec<u8>> {
+        let mut output = self.output.recv().await?;
+        while let Ok(more) = self.output.try_recv() {
+            output.extend_from_slice(&more);
+        }
+        Some(output)
+    }
+
+    /// Parses `output` of the shell into the screen and answers the queries in it.
+    pub fn process(&mut self, output: &[u8]) {
+        self.parser.advance(&mut self.screen, output);
+        let replies = std::mem::take(&mut self.screen.replies);
+        if !replies.is_empty() {
+            self.write(&replies);
+        }
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    /// Resizes the screen and tells the shell about the new size.
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        if (rows.max(1), cols.max(1)) == (self.screen.rows, self.screen.cols) {
+            return;
+        }
+        self.screen.resize(rows, cols);
+        if let Err(err) = self.pty.resize(self.screen.rows, self.screen.cols) {
+            log::warn!("failed to resize the terminal: {err}");
+        }
+    }
+
+    /// Sends `key` to the shell, encoded as by an xterm.
+    pub fn send_key(&mut self, key: KeyEvent) {
+        if let Some(bytes) = key_bytes(key, self.screen.application_cursor) {
+            self.write(&bytes);
+        }
+    }
+
+    /// Sends pasted text to the shell, as a bracketed paste if the program asked for one.
+    pub fn paste(&mut self, text: &str) {
+        // The text can't end the bracketed paste early
+        let text = text.replace('\x1b', "");
+        if self.screen.bracketed_paste {
+            self.write(format!("\x1b[200~{text}\x1b[201~").as_bytes());
+        } else {
+            self.write(text.as_bytes());
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        // The input thread only exits after failing to write, which it logged
+        let _ = self.input.send(bytes.to_vec());
+    }
+}
+
+/// Waits for the output of `terminal`, see [`Terminal::read`], and forever without one.
+pub async fn read_output(terminal: Option<&mut Terminal>) -> Option<Vec<u8>> {
+    match terminal {
+        Some(terminal) => terminal.read().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(unix)]
+mod pty {
+    use std::{
+        fs::File,
+        io::{self, Read},
+        os::{
+            fd::{AsRawFd, FromRawFd, OwnedFd},
+            unix::process::CommandExt,
+        },
+        process::{Child, Command},
+    };
+
+    /// The controlling side of a pseudo terminal with the process running in it.
+    pub struct Pty {
+        master: File,
+        /// The write end of a pipe which is closed with the terminal to stop the [`Reader`].
+        _stop: OwnedFd,
+        /// Taken to reap the process once the terminal is dropped.
+        child: Option<Child>,
+    }
+
+    /// Reads the output of the programs in the terminal until they exited or the [`Pty`] is
+    /// dropped, which ends the output.
+    pub struct Reader {
+        master: File,
+        stop: OwnedFd,
+    }
+
+    impl Read for Reader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut fds = [self.master.as_raw_fd(), self.stop.as_raw_fd()].map(|fd| libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            });
+            // SAFETY: both descriptors are open and poll only writes the returned events.
+            check(unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as _, -1) })?;
+            if fds[1].revents != 0 {
+                return Ok(0);
+            }
+            self.master.read(buf)
+        }
+    }
+
+    fn winsize(rows: u16, cols: u16) -> libc::winsize {
+        libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+
+    fn check(result: libc::c_int) -> io::Result<()> {
+        if result == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    impl Pty {
+        /// Opens a pseudo terminal of `rows` by `cols` cells and spawns `command` as the leader
+        /// of a new session with the terminal as its controlling terminal.
+        pub fn spawn(mut command: Command, rows: u16, cols: u16) -> io::Result<(Self, Reader)> {
+            let (mut master, mut slave) = (-1, -1);
+            let mut size = winsize(rows, cols);
+            // SAFETY: openpty only writes the two file descriptors, the name and the terminal
+            // attributes are optional.
+            check(unsafe {
+                libc::openpty(
+                    &mut master,
+                    &mut slave,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    &mut size,
+                )
+            })?;
+            // SAFETY: openpty succeeded, so both are open and owned by nobody else.
+            let (master, slave) =
+                unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+            for fd in [&master, &slave] {
+                // SAFETY: the descriptor is open. The programs in the terminal must not inherit
+                // it, or the terminal isn't closed when they exit.
+                check(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) })?;
+            }
+
+            let slave = File::from(slave);
+            command
+                .stdin(slave.try_clone()?)
+                .stdout(slave.try_clone()?)
+                .stderr(slave);
+            // SAFETY: only async-signal-safe functions are called between fork and exec.
+            unsafe {
+                command.pre_exec(|| {
+                    // Job control and signals like SIGINT for ctrl-c need the session
+                    check(libc::setsid())?;
+                    check(libc::ioctl(0, libc::TIOCSCTTY as _, 0))
+                });
+            }
+            let child = command.spawn()?;
+
+            let mut pipe = [-1; 2];
+            // SAFETY: pipe only writes the two file descriptors.
+            check(unsafe { libc::pipe(pipe.as_mut_ptr()) })?;
+            // SAFETY: pipe succeeded, so both are open and owned by nobody else.
+            let (stop_read, stop_write) =
+                unsafe { (OwnedFd::from_raw_fd(pipe[0]), OwnedFd::from_raw_fd(pipe[1])) };
+            for fd in [&stop_read, &stop_write] {
+                // SAFETY: the descriptor is open. The pipe must only be closed with the terminal.
+                check(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) })?;
+            }
+            let master = File::from(master);
+            let reader = Reader {
+                master: master.try_clone()?,
+                stop: stop_read,
+            };
+            let pty = Self {
+                master,
+                _stop: stop_write,
+                child: Some(child),
+            };
+            Ok((pty, reader))
+        }
+
+        /// A handle to write the input of the programs in the terminal.
+        pub fn writer(&self) -> io::Result<File> {
+            self.master.try_clone()
+        }
+
+        pub fn resize(&self, rows: u16, cols: u16) -> io::Result<()> {
+            let size = winsize(rows, cols);
+            // SAFETY: the descriptor is open and TIOCSWINSZ only reads the size.
+            check(unsafe {
+                libc::ioctl(
+                    self.master.as_raw_fd(),
+                    libc::TIOCSWINSZ as _,
+                    &size as *const libc::winsize,
+                )
+            })
+        }
+    }
+
+    impl Drop for Pty {
+        fn drop(&mut self) {
+            // The programs the shell started get SIGHUP once the terminal is closed, which
+            // happens when the master and the pipe stopping the reader are dropped after this.
+            // The shell is reaped on a thread so that dropping the terminal doesn't wait for it.
+            if let Some(mut child) = self.child.take() {
+                let _ = child.kill();
+                std::thread::spawn(move || child.wait());
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod pty {
+    use std::{convert::Infallible, fs::File, io, process::Command};
+
+    pub struct Pty(Infallible);
+
+    impl Pty {
+        pub fn spawn(_command: Command, _rows: u16, _cols: u16) -> io::Result<(Self, File)> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the embedded terminal is only supported on Unix",
+            ))
+        }
+
+        pub fn writer(&self) -> io::Result<File> {
+            match self.0 {}
+        }
+
+        pub fn resize(&self, _rows: u16, _cols: u16) -> io::Result<()> {
+            match self.0 {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(rows: u16, cols: u16, output: &[u8]) -> Screen {
+        let mut screen = Screen::new(rows, cols);
+        vte::Parser::new().advance(&mut screen, output);
+        screen
+    }
+
+    #[test]
+    fn print_wraps_and_scrolls() {
+        let screen = parse(2, 4, b"abcdef\r\ngh");
+        assert_eq!(screen.contents(), "ef\ngh");
+        assert_eq!(screen.cursor(), Some((1, 2)));
+
+        // Printing in the last column doesn't move to the next line yet
+        let screen = parse(2, 4, b"abcd");
+        assert_eq!(screen.cursor(), Some((0, 3)));
+        assert_eq!(screen.contents(), "abcd");
+    }
+
+    #[test]
+    fn cursor_movement_and_erasing() {
+        let screen = parse(3, 6, b"hello\r\nworld\x1b[1;2H\x1b[K\x1b[2;3H\x1b[1P");
+        assert_eq!(screen.contents(), "h\nwold");
+        assert_eq!(screen.cursor(), Some((1, 2)));
+
+        let screen = parse(3, 4, b"a\r\nb\r\nc\x1b[2;1H\x1b[L");
+        assert_eq!(screen.contents(), "a\n\nb");
+    }
+
+    #[test]
+    fn scroll_region() {
+        let screen = parse(4, 4, b"1\r\n2\r\n3\r\n4\x1b[2;3r\x1b[3;1H\n5");
+        assert_eq!(screen.contents(), "1\n3\n5\n4");
+    }
+
+    #[test]
+    fn graphic_rendition() {
+        let screen = parse(1, 8, b"\x1b[1;31ma\x1b[38;5;100;48:2::1:2:3mb\x1b[0mc");
+        assert_eq!(
+            screen.cell(0, 0).style,
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(screen.cell(0, 1).style.fg, Some(Color::Indexed(100)));
+        assert_eq!(screen.cell(0, 1).style.bg, Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(screen.cell(0, 2).style, Style::default());
+    }
+
+    #[test]
+    fn alternate_screen() {
+        let mut screen = parse(2, 8, b"shell\x1b[?1049h\x1b[Hvim");
+        assert_eq!(screen.contents(), "vim");
+        vte::Parser::new().advance(&mut screen, b"\x1b[?1049l");
+        assert_eq!(screen.contents(), "shell");
+        assert_eq!(screen.cursor(), Some((0, 5)));
+    }
+
+    #[test]
+    fn queries_are_answered() {
+        let mut screen = parse(4, 8, b"ab\x1b[6n\x1b[c");
+        assert_eq!(std::mem::take(&mut screen.replies), b"\x1b[1;3R\x1b[?1;2c");
+    }
+
+    #[test]
+    fn resize_keeps_the_cursor_row() {
+        let mut screen = parse(4, 4, b"1\r\n2\r\n3\r\n4");
+        screen.resize(2, 2);
+        assert_eq!(screen.contents(), "3\n4");
+        assert_eq!(screen.cursor(), Some((1, 1)));
+    }
+
+    #[test]
+    fn keys() {
+        let key = |code, modifiers| KeyEvent { code, modifiers };
+        let bytes = |key, application_cursor| key_bytes(key, application_cursor).unwrap();
+        assert_eq!(
+            bytes(key(KeyCode::Char('c'), KeyModifiers::CONTROL), false),
+            b"\x03"
+        );
+        assert_eq!(
+            bytes(key(KeyCode::Char('b'), KeyModifiers::ALT), false),
+            b"\x1bb"
+        );
+        assert_eq!(
+            bytes(key(KeyCode::Up, KeyModifiers::NONE), false),
+            b"\x1b[A"
+        );
+        assert_eq!(bytes(key(KeyCode::Up, KeyModifiers::NONE), true), b"\x1bOA");
+        assert_eq!(
+            bytes(key(KeyCode::Right, KeyModifiers::CONTROL), true),
+            b"\x1b[1;5C"
+        );
+        assert_eq!(
+            bytes(key(KeyCode::Tab, KeyModifiers::SHIFT), false),
+            b"\x1b[Z"
+        );
+        assert_eq!(
+            bytes(key(KeyCode::F(5), KeyModifiers::NONE), false),
+            b"\x1b[15~"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{graphics::Rect, View, ViewId};
+use serde::{Deserialize, Serialize};
 use slotmap::SlotMap;
 
 // the dimensions are recomputed on window resize/tree change.
@@ -10,16 +11,35 @@ pub struct Tree {
     pub focus: ViewId,
     // fullscreen: bool,
     area: Rect,
+    /// Whether to reserve a row above each view for the winbar.
+    winbar: bool,
 
     nodes: SlotMap<ViewId, Node>,
 
+    /// The named layouts (tab pages). The root and focus of the active layout are kept in
+    /// `root` and `focus` and only written back when switching to another layout.
+    layouts: Vec<TreeLayout>,
+    /// The index of the active layout.
+    layout: usize,
+
     // used for traversals
     stack: Vec<(ViewId, Rect)>,
 }
 
+/// A separate tree of splits with its own focus. The nodes of all layouts share the slot map
+/// so that view ids are unique across layouts.
+#[derive(Debug)]
+struct TreeLayout {
+    name: String,
+    root: ViewId,
+    focus: ViewId,
+}
+
 #[derive(Debug)]
 pub struct Node {
     parent: ViewId,
+    /// The root container of the layout the node is part of.
+    root: ViewId,
     content: Content,
 }
 
@@ -33,6 +53,7 @@ impl Node {
     pub fn container(layout: Layout) -> Self {
         Self {
             parent: ViewId::default(),
+            root: ViewId::default(),
             content: Content::Container(Box::new(Container::new(layout))),
         }
     }
@@ -40,18 +61,27 @@ pub fn container(layout: Layout) -> Self {
     pub fn view(view: View) -> Self {
         Self {
             parent: ViewId::default(),
+            root: ViewId::default(),
             content: Content::View(Box::new(view)),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Layout {
     Horizontal,
     Vertical,
     // could explore stacked/tabbed
 }
 
+/// The arrangement of the views of a layout, see [`Tree::splits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Split {
+    View(ViewId),
+    Container(Layout, Vec<Split>),
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Direction {
     Up,
@@ -92,13 +122,21 @@ pub fn new(area: Rect) -> Self {
 
         // root is it's own parent
         nodes[root].parent = root;
+        nodes[root].root = root;
 
         Self {
             root,
             focus: root,
             // fullscreen: false,
             area,
+            winbar: false,
             nodes,
+            layouts: vec![TreeLayout {
+                name: String::from("1"),
+                root,
+                focus: root,
+            }],
+            layout: 0,
             stack: Vec::new(),
         }
     }
@@ -108,6 +146,7 @@ pub fn insert(&mut self, view: View) -> ViewId {
         let parent = self.nodes[focus].parent;
         let mut node = Node::view(view);
         node.parent = parent;
+        node.root = self.root;
         let node = self.nodes.insert(node);
         self.get_mut(node).id = node;
 
@@ -145,7 +184,8 @@ pub fn split(&mut self, view: View, layout: Layout) -> ViewId {
         let focus = self.focus;
         let parent = self.nodes[focus].parent;
 
-        let node = Node::view(view);
+        let mut node = Node::view(view);
+        node.root = self.root;
         let node = self.nodes.insert(node);
         self.get_mut(node).id = node;
 
@@ -173,6 +213,7 @@ pub fn split(&mut self, view: View, layout: Layout) -> ViewId {
         } else {
             let mut split = Node::container(layout);
             split.parent = parent;
+            split.root = self.root;
             let split = self.nodes.insert(split);
 
             let container = match &mut self.nodes[split] {
@@ -248,13 +289,14 @@ fn remove_or_replace(&mut self, child: ViewId, replacement: Option<ViewId>) {
     }
 
     pub fn remove(&mut self, index: ViewId) {
+        let root = self.nodes[index].root;
         if self.focus == index {
             // focus on something else
             self.focus = self.prev();
         }
 
         let parent = self.nodes[index].parent;
-        let parent_is_root = parent == self.root;
+        let parent_is_root = parent == root;
 
         self.remove_or_replace(index, None);
 
@@ -266,21 +308,69 @@ pub fn remove(&mut self, index: ViewId) {
             self.remove_or_replace(parent, Some(sibling));
         }
 
+        if root != self.root {
+            // the view was part of an inactive layout
+            let first = self.first_view(root);
+            let layout = self
+                .layouts
+                .iter_mut()
+                .find(|layout| layout.root == root)
+                .unwrap();
+            if layout.focus == index {
+                layout.focus = first;
+            }
+        }
+        if self.container_mut(root).children.is_empty() && self.layouts.len() > 1 {
+            self.remove_layout(root);
+        }
+
         self.recalculate()
     }
 
+    /// Iterates over the views of the active layout.
     pub fn views(&self) -> impl Iterator<Item = (&View, bool)> {
         let focus = self.focus;
+        let root = self.root;
         self.nodes.iter().filter_map(move |(key, node)| match node {
             Node {
                 content: Content::View(view),
+                root: view_root,
                 ..
-            } => Some((view.as_ref(), focus == key)),
+            } if *view_root == root => Some((view.as_ref(), focus == key)),
             _ => None,
         })
     }
 
+    /// Iterates over the views of the active layout.
     pub fn views_mut(&mut self) -> impl Iterator<Item = (&mut View, bool)> {
+        let focus = self.focus;
+        let root = self.root;
+        self.nodes
+            .iter_mut()
+            .filter_map(move |(key, node)| match node {
+                Node {
+                    content: Content::View(view),
+                    root: view_root,
+                    ..
+                } if *view_root == root => Some((view.as_mut(), focus == key)),
+                _ => None,
+            })
+    }
+
+    /// Iterates over the views of all layouts, including the inactive ones.
+    pub fn all_views(&self) -> impl Iterator<Item = (&View, bool)> {
+        let focus = self.focus;
+        self.nodes.iter().filter_map(move |(key, node)| match node {
+            Node {
+                content: Content::View(view),
+                ..
+            } => Some((view.as_ref(), focus == key)),
+            _ => None,
+        })
+    }
+
+    /// Iterates over the views of all layouts, including the inactive ones.
+    pub fn all_views_mut(&mut self) -> impl Iterator<Item = (&mut View, bool)> {
         let focus = self.focus;
         self.nodes
             .iter_mut()
@@ -293,6 +383,123 @@ pub fn views_mut(&mut self) -> impl Iterator<Item = (&mut View, bool)> {
             })
     }
 
+    /// Returns the first view below `id`, or `id` itself if it is an empty container.
+    fn first_view(&self, mut id: ViewId) -> ViewId {
+        while let Content::Container(container) = &self.nodes[id].content {
+            match container.children.first() {
+                Some(&child) => id = child,
+                None => break,
+            }
+        }
+        id
+    }
+
+    /// The number of layouts, there is always at least one.
+    pub fn layout_count(&self) -> usize {
+        self.layouts.len()
+    }
+
+    /// The index of the active layout.
+    pub fn current_layout(&self) -> usize {
+        self.layout
+    }
+
+    pub fn layout_names(&self) -> impl Iterator<Item = &str> {
+        self.layouts.iter().map(|layout| layout.name.as_str())
+    }
+
+    pub fn rename_layout(&mut self, name: String) {
+        self.layouts[self.layout].name = name;
+    }
+
+    /// Creates an empty layout after the active one and switches to it. The caller is expected
+    /// to insert a view into it.
+    pub fn new_layout(&mut self, name: String) -> usize {
+        let root = self.nodes.insert(Node::container(Layout::Vertical));
+        // root is it's own parent
+        self.nodes[root].parent = root;
+        self.nodes[root].root = root;
+
+        self.save_layout();
+        let idx = self.layout + 1;
+        self.layouts.insert(
+            idx,
+            TreeLayout {
+                name,
+                root,
+                focus: root,
+            },
+        );
+        self.load_layout(idx);
+        idx
+    }
+
+    /// Switches to the layout at `idx`. Returns `false` if there is no such layout or it is
+    /// already active.
+    pub fn switch_layout(&mut self, idx: usize) -> bool {
+        if idx >= self.layouts.len() || idx == self.layout {
+            return false;
+        }
+        self.save_layout();
+        self.load_layout(idx);
+        true
+    }
+
+    /// Closes the active layout and switches to the one before it. Returns the ids of the
+    /// removed views, the caller is responsible for cleaning up any state kept for them. The
+    /// last layout can't be closed.
+    pub fn close_layout(&mut self) -> Vec<ViewId> {
+        if self.layouts.len() == 1 {
+            return Vec::new();
+        }
+        let views = self.traverse().map(|(id, _)| id).collect();
+
+        let root = self.root;
+        let mut stack = std::mem::take(&mut self.container_mut(root).children);
+        while let Some(id) = stack.pop() {
+            if let Some(Node {
+                content: Content::Container(container),
+                ..
+            }) = self.nodes.remove(id)
+            {
+                stack.extend(container.children);
+            }
+        }
+        self.remove_layout(root);
+        self.recalculate();
+        views
+    }
+
+    fn save_layout(&mut self) {
+        let layout = &mut self.layouts[self.layout];
+        layout.root = self.root;
+        layout.focus = self.focus;
+    }
+
+    fn load_layout(&mut self, idx: usize) {
+        self.layout = idx;
+        self.root = self.layouts[idx].root;
+        self.focus = self.layouts[idx].focus;
+        self.recalculate();
+    }
+
+    /// Removes the empty layout with the given root. If it is the active layout, the one
+    /// before it becomes active.
+    fn remove_layout(&mut self, root: ViewId) {
+        self.save_layout();
+        let idx = self
+            .layouts
+            .iter()
+            .position(|layout| layout.root == root)
+            .unwrap();
+        self.nodes.remove(root);
+        self.layouts.remove(idx);
+        if idx < self.layout || (idx == self.layout && self.layout > 0) {
+            self.layout -= 1;
+        }
+        self.load_layout(self.layout);
+    }
+
     /// Get reference to a [View] by index.
     /// # Panics
     ///
@@ -352,6 +559,20 @@ pub fn resize(&mut self, area: Rect) -> bool {
         false
     }
 
+    /// Reserves a row above each view for the winbar. The row is not part of the view's area.
+    pub fn set_winbar(&mut self, winbar: bool) -> bool {
+        if self.winbar != winbar {
+            self.winbar = winbar;
+            self.recalculate();
+            return true;
+        }
+        false
+    }
+
+    pub fn has_winbar(&self) -> bool {
+        self.winbar
+    }
+
     pub fn recalculate(&mut self) {
         if self.is_empty() {
             // There are no more views, so the tree should focus itself again.
@@ -373,7 +594,7 @@ pub fn recalculate(&mut self) {
             match &mut node.content {
                 Content::View(view) => {
                     // debug!!("setting view area {:?}", area);
-                    view.area = area;
+                    view.area = area.clip_top(self.winbar as u16);
                 } // TODO: call f()
                 Content::Container(container) => {
                     // debug!!("setting container area {:?}", area);
@@ -445,6 +666,25 @@ pub fn traverse(&self) -> Traverse<'_> {
         Traverse::new(self)
     }
 
+    /// The splits of the active layout, starting at its root container.
+    pub fn splits(&self) -> Split {
+        self.split_at(self.root)
+    }
+
+    fn split_at(&self, id: ViewId) -> Split {
+        match &self.nodes[id].content {
+            Content::View(_) => Split::View(id),
+            Content::Container(container) => Split::Container(
+                container.layout,
+                container
+                    .children
+                    .iter()
+                    .map(|&child| self.split_at(child))
+                    .collect(),
+            ),
+        }
+    }
+
     // Finds the split in the given direction if it exists
     pub fn find_split_in_direction(&self, id: ViewId, direction: Direction) -> Option<ViewId> {
         let parent = self.nodes[id].parent;
@@ -728,6 +968,31 @@ mod test {
     use crate::editor::GutterConfig;
     use crate::DocumentId;
 
+    #[test]
+    fn splits() {
+        let mut tree = Tree::new(Rect::new(0, 0, 180, 80));
+        let view = View::new(DocumentId::default(), GutterConfig::default());
+        let left = tree.insert(view);
+        let view = View::new(DocumentId::default(), GutterConfig::default());
+        let right = tree.split(view, Layout::Vertical);
+        let view = View::new(DocumentId::default(), GutterConfig::default());
+        let bottom = tree.split(view, Layout::Horizontal);
+
+        assert_eq!(
+            tree.splits(),
+            Split::Container(
+                Layout::Vertical,
+                vec![
+                    Split::View(left),
+                    Split::Container(
+                        Layout::Horizontal,
+                        vec![Split::View(right), Split::View(bottom)]
+                    ),
+                ]
+            )
+        );
+    }
+
     #[test]
     fn find_split_in_direction() {
         let mut tree = Tree::new(Rect {
@@ -965,4 +1230,43 @@ fn vsplit_gap_rounding() {
                 .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn layouts_have_separate_views() {
+        let mut tree = Tree::new(Rect::new(0, 0, 180, 80));
+        let view = View::new(DocumentId::default(), GutterConfig::default());
+        let v1 = tree.insert(view);
+
+        assert_eq!(tree.new_layout("2".into()), 1);
+        assert!(tree.is_empty());
+        let view = View::new(DocumentId::default(), GutterConfig::default());
+        let v2 = tree.insert(view);
+        let view = View::new(DocumentId::default(), GutterConfig::default());
+        let v3 = tree.split(view, Layout::Vertical);
+        assert_eq!(tree.views().count(), 2);
+        assert_eq!(tree.views_mut().count(), 2);
+        assert_eq!(tree.all_views().count(), 3);
+
+        assert!(tree.switch_layout(0));
+        assert_eq!(tree.focus, v1);
+        let views: Vec<_> = tree.views().map(|(view, _)| view.id).collect();
+        assert_eq!(views, vec![v1]);
+        let views: Vec<_> = tree.views_mut().map(|(view, _)| view.id).collect();
+        assert_eq!(views, vec![v1]);
+
+        // an inactive layout is removed once its last view is closed
+        tree.remove(v2);
+        assert_eq!(tree.layout_count(), 2);
+        tree.remove(v3);
+        assert_eq!(tree.layout_count(), 1);
+        assert_eq!(tree.focus, v1);
+
+        tree.new_layout("3".into());
+        let view = View::new(DocumentId::default(), GutterConfig::default());
+        let v4 = tree.insert(view);
+        assert_eq!(tree.close_layout(), vec![v4]);
+        assert_eq!(tree.current_layout(), 0);
+        assert_eq!(tree.all_views().count(), 1);
+        assert!(tree.close_layout().is_empty());
+    }
 }
@@ -1,6 +1,9 @@
 use crate::{
     align_view,
-    annotations::diagnostics::InlineDiagnostics,
+    annotations::{
+        diagnostics::InlineDiagnostics,
+        virtual_text::{VirtualTextLines, VirtualTextPlacement},
+    },
     document::{DocumentColorSwatches, DocumentInlayHints},
     editor::{GutterConfig, GutterType},
     graphics::Rect,
@@ -24,6 +27,8 @@
 
 const JUMP_LIST_CAPACITY: usize = 30;
 
+const SELECTION_HISTORY_CAPACITY: usize = 100;
+
 type Jump = (DocumentId, Selection);
 
 #[derive(Debug, Clone)]
@@ -120,6 +125,103 @@ fn apply(&mut self, transaction: &Transaction, doc: &Document) {
     }
 }
 
+/// The selections a view had before commands changed them without editing the text, stepped
+/// through with `undo_selection` and `redo_selection` independently of the undo history of the
+/// document. The history is of one document, switching to another one starts a new history.
+#[derive(Debug, Clone, Default)]
+pub struct SelectionHistory {
+    doc: Option<DocumentId>,
+    undo: Vec<Selection>,
+    redo: Vec<Selection>,
+    /// The selection and document version after the last command, to tell whether the next
+    /// command changed the selection.
+    last: Option<(DocumentId, i32, Selection)>,
+}
+
+impl SelectionHistory {
+    /// Called after each command with the selection of `doc` in the view. If the command
+    /// changed the selection without editing the text, the previous selection is recorded.
+    pub fn update(&mut self, doc: &Document, selection: &Selection) {
+        let last = self
+            .last
+            .replace((doc.id(), doc.version(), selection.clone()));
+        let Some((doc_id, version, previous)) = last else {
+            return;
+        };
+        if doc_id != doc.id() || version != doc.version() || previous == *selection {
+            return;
+        }
+        if self.doc != Some(doc_id) {
+            self.doc = Some(doc_id);
+            self.undo.clear();
+        }
+        if self.undo.len() >= SELECTION_HISTORY_CAPACITY {
+            self.undo.remove(0);
+        }
+        self.undo.push(previous);
+        self.redo.clear();
+    }
+
+    /// Steps `count` selections back from `current`, the selection of `doc` in the view.
+    /// Returns the selection to restore, `None` if there is no earlier one.
+    pub fn undo(&mut self, doc: &Document, current: &Selection, count: usize) -> Option<Selection> {
+        if self.doc != Some(doc.id()) {
+            return None;
+        }
+        let selection = Self::step(&mut self.undo, &mut self.redo, current, count)?;
+        self.last = Some((doc.id(), doc.version(), selection.clone()));
+        Some(selection)
+    }
+
+    /// Steps `count` selections forward again after [`SelectionHistory::undo`].
+    pub fn redo(&mut self, doc: &Document, current: &Selection, count: usize) -> Option<Selection> {
+        if self.doc != Some(doc.id()) {
+            return None;
+        }
+        let selection = Self::step(&mut self.redo, &mut self.undo, current, count)?;
+        self.last = Some((doc.id(), doc.version(), selection.clone()));
+        Some(selection)
+    }
+
+    fn step(
+        from: &mut Vec<Selection>,
+        to: &mut Vec<Selection>,
+        current: &Selection,
+        count: usize,
+    ) -> Option<Selection> {
+        let mut selection = from.pop()?;
+        to.push(current.clone());
+        for _ in 1..count {
+            let Some(next) = from.pop() else {
+                break;
+            };
+            to.push(std::mem::replace(&mut selection, next));
+        }
+        Some(selection)
+    }
+
+    pub fn remove(&mut self, doc_id: &DocumentId) {
+        if self.doc == Some(*doc_id) {
+            *self = Self::default();
+        }
+    }
+
+    /// Maps the recorded selections through a [`Transaction`] of the document, like the
+    /// jumplist.
+    fn apply(&mut self, transaction: &Transaction, doc: &Document) {
+        if self.doc != Some(doc.id()) {
+            return;
+        }
+        let text = doc.text().slice(..);
+        for selection in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            *selection = selection
+                .clone()
+                .map(transaction.changes())
+                .ensure_invariants(text);
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Copy, Default)]
 pub struct ViewPosition {
     pub anchor: usize,
@@ -133,6 +235,8 @@ pub struct View {
     pub area: Rect,
     pub doc: DocumentId,
     pub jumps: JumpList,
+    /// The selections before the last commands, see `undo_selection`.
+    pub selection_history: SelectionHistory,
     // documents accessed from this view from the oldest one to last viewed one
     pub docs_access_history: Vec<DocumentId>,
     /// the last modified files before the current one
@@ -157,6 +261,14 @@ pub struct View {
     // left to future work. For now we treat all views as focused and give them
     // each their own handler.
     pub diagnostics_handler: DiagnosticsHandler,
+    /// Pinned views keep their document: switching to another document opens it in the
+    /// preview split instead, see [`crate::Editor::switch`].
+    pub pinned: bool,
+    /// Scroll-bound views scroll along with the focused view, see
+    /// [`crate::Editor::sync_scroll_bind`].
+    pub scroll_bind: bool,
+    /// The top line of the view when scroll-bound views were last synced.
+    pub(crate) scroll_bind_line: usize,
 }
 
 impl fmt::Debug for View {
@@ -176,12 +288,16 @@ pub fn new(doc: DocumentId, gutters: GutterConfig) -> Self {
             doc,
             area: Rect::default(), // will get calculated upon inserting into tree
             jumps: JumpList::new((doc, Selection::point(0))), // TODO: use actual sel
+            selection_history: SelectionHistory::default(),
             docs_access_history: Vec::new(),
             last_modified_docs: [None, None],
             object_selections: Vec::new(),
             gutters,
             doc_revisions: HashMap::new(),
             diagnostics_handler: DiagnosticsHandler::new(),
+            pinned: false,
+            scroll_bind: false,
+            scroll_bind_line: 0,
         }
     }
 
@@ -204,14 +320,14 @@ pub fn inner_width(&self, doc: &Document) -> u16 {
         self.area.clip_left(self.gutter_offset(doc)).width
     }
 
-    pub fn gutters(&self) -> &[GutterType] {
-        &self.gutters.layout
+    /// The gutter layout of the view, the language of `doc` may override it.
+    pub fn gutters<'a>(&'a self, doc: &'a Document) -> &'a [GutterType] {
+        doc.gutter_layout.as_deref().unwrap_or(&self.gutters.layout)
     }
 
     pub fn gutter_offset(&self, doc: &Document) -> u16 {
         let total_width = self
-            .gutters
-            .layout
+            .gutters(doc)
             .iter()
             .map(|gutter| gutter.width(self, doc) as u16)
             .sum();
@@ -513,6 +629,14 @@ pub fn text_annotations<'a>(
             ));
         }
 
+        if doc
+            .virtual_text()
+            .iter()
+            .any(|vt| vt.placement == VirtualTextPlacement::Below)
+        {
+            text_annotations.add_line_annotation(VirtualTextLines::new(doc));
+        }
+
         text_annotations
     }
 
@@ -633,6 +757,7 @@ pub fn gutter_coords_at_screen_coords(&self, row: u16, column: u16) -> Option<Po
 
     pub fn remove_document(&mut self, doc_id: &DocumentId) {
         self.jumps.remove(doc_id);
+        self.selection_history.remove(doc_id);
         self.docs_access_history.retain(|doc| doc != doc_id);
     }
 
@@ -660,6 +785,7 @@ pub fn remove_document(&mut self, doc_id: &DocumentId) {
     /// Applies a [`Transaction`] to the view.
     pub fn apply(&mut self, transaction: &Transaction, doc: &mut Document) {
         self.jumps.apply(transaction, doc);
+        self.selection_history.apply(transaction, doc);
         self.doc_revisions
             .insert(doc.id(), doc.get_current_revision());
     }
@@ -1092,4 +1218,35 @@ fn test_text_pos_at_screen_coords_graphemes() {
             Some(7)
         );
     }
+
+    #[test]
+    fn test_selection_history() {
+        let doc = Document::from(
+            Rope::from_str("abc def"),
+            None,
+            Arc::new(ArcSwap::new(Arc::new(Config::default()))),
+            Arc::new(ArcSwap::from_pointee(syntax::Loader::default())),
+        );
+        let mut history = SelectionHistory::default();
+        let selection = Selection::point;
+
+        history.update(&doc, &selection(0));
+        assert_eq!(history.undo(&doc, &selection(0), 1), None);
+        history.update(&doc, &selection(4));
+        history.update(&doc, &selection(4));
+        history.update(&doc, &selection(6));
+
+        assert_eq!(history.undo(&doc, &selection(6), 1), Some(selection(4)));
+        history.update(&doc, &selection(4));
+        assert_eq!(history.undo(&doc, &selection(4), 1), Some(selection(0)));
+        assert_eq!(history.undo(&doc, &selection(0), 1), None);
+        assert_eq!(history.redo(&doc, &selection(0), 2), Some(selection(6)));
+        assert_eq!(history.redo(&doc, &selection(6), 1), None);
+
+        // A new selection change drops the undone ones
+        assert_eq!(history.undo(&doc, &selection(6), 1), Some(selection(4)));
+        history.update(&doc, &selection(2));
+        assert_eq!(history.redo(&doc, &selection(2), 1), None);
+        assert_eq!(history.undo(&doc, &selection(2), 1), Some(selection(4)));
+    }
 }
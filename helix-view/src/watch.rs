@@ -0,0 +1,135 @@
+//! Watch mode: a shell command, for example `cargo check`, which is run again each time a file
+//! of the workspace is saved, see `:watch`. The locations of the errors and warnings in its
+//! output replace the quickfix list and the statusline shows whether it passed.
+
+use std::path::Path;
+
+use helix_core::Position;
+
+use crate::quickfix::QuickfixEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchState {
+    Running,
+    Passed,
+    /// The command failed with this many locations in its output.
+    Failed(usize),
+}
+
+#[derive(Debug)]
+pub struct WatchTask {
+    pub command: String,
+    pub state: WatchState,
+    /// Whether a file was saved while the command was running, it runs again once it finished.
+    pub rerun: bool,
+}
+
+impl WatchTask {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            state: WatchState::Running,
+            rerun: false,
+        }
+    }
+}
+
+/// The locations in the output of a command: lines of the form `path:line:column: message` or
+/// `path:line: message` as printed by most compilers and linters, and the ` --> path:line:column`
+/// lines of rustc which point at the message on the line before. Relative paths are resolved
+/// against `cwd`.
+pub fn parse_locations(output: &str, cwd: &Path) -> Vec<QuickfixEntry> {
+    let mut entries = Vec::new();
+    let mut message = "";
+    for line in output.lines() {
+        if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if let Some((path, position)) = parse_location(location) {
+                entries.push(entry(cwd, path, position, message));
+            }
+            continue;
+        }
+        match line.split_once(": ") {
+            Some((location, text)) => match parse_location(location) {
+                Some((path, position)) => entries.push(entry(cwd, path, position, text)),
+                None => message = line,
+            },
+            None if !line.starts_with(' ') => message = line,
+            None => (),
+        }
+    }
+    entries
+}
+
+/// Parses `path:line:column` or `path:line`, with one-based line and column.
+fn parse_location(location: &str) -> Option<(&str, Position)> {
+    let mut parts = location.trim_end().rsplitn(3, ':');
+    let last = parts.next()?.parse::<usize>().ok()?;
+    let (path, row, col) = match parts.next()?.parse::<usize>() {
+        Ok(row) => (parts.next()?, row, last),
+        Err(_) => (location.rsplit_once(':')?.0, last, 1),
+    };
+    if path.is_empty() || path.contains(char::is_whitespace) || row == 0 {
+        return None;
+    }
+    Some((path, Position::new(row - 1, col.saturating_sub(1))))
+}
+
+fn entry(cwd: &Path, path: &str, start: Position, text: &str) -> QuickfixEntry {
+    QuickfixEntry {
+        doc_id: None,
+        path: Some(helix_stdx::path::normalize(cwd.join(path))),
+        start,
+        end: Position::new(start.row, start.col + 1),
+        text: text.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn locations() {
+        let output = "\
+error[E0308]: mismatched types
+ --> src/main.rs:4:18
+  |
+src/lib.rs:10: warning: unused variable
+lib/foo.c:3:5: error: expected ';'
+Finished in 0.1s: 2 errors
+";
+        let entries = parse_locations(output, Path::new("/project"));
+        let locations: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.path.clone().unwrap(),
+                    entry.start,
+                    entry.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            locations,
+            [
+                (
+                    PathBuf::from("/project/src/main.rs"),
+                    Position::new(3, 17),
+                    "error[E0308]: mismatched types"
+                ),
+                (
+                    PathBuf::from("/project/src/lib.rs"),
+                    Position::new(9, 0),
+                    "warning: unused variable"
+                ),
+                (
+                    PathBuf::from("/project/lib/foo.c"),
+                    Position::new(2, 4),
+                    "error: expected ';'"
+                ),
+            ]
+        );
+    }
+}
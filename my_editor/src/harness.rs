@@ -0,0 +1,165 @@
+//! A harness for tests of the whole editor without a terminal: keymaps, popups, the statusline,
+//! etc. A [`Harness`] runs an [`EditorRuntime`] drawing to a [`CaptureBackend`], presses keys
+//! and handles the job callbacks and other events of the editor they cause, then the test
+//! asserts on the documents or the screen.
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn delete_line() -> anyhow::Result<()> {
+//!     let mut harness = Harness::new(RuntimeOptions::default())?;
+//!     harness.press("ione<ret>two<esc>kxd").await?;
+//!     assert_eq!(harness.text(), "two\n");
+//!     assert!(harness.statusline().contains("NOR"));
+//!     harness.finish().await
+//! }
+//! ```
+//!
+//! Runtimes share the job queue of their Tokio runtime, so each test needs its own Tokio
+//! runtime, as with `#[tokio::test]`, and tests running in parallel in one process need the
+//! `integration` feature.
+
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures_util::FutureExt;
+use helix_core::Selection;
+use helix_view::{
+    input::{parse_macro, Event},
+    Editor,
+};
+use tui::backend::CaptureBackend;
+
+use crate::{script::command_keys, EditorRuntime, RuntimeOptions};
+
+/// The size of the screen of [`Harness::new`].
+const SIZE: (u16, u16) = (80, 24);
+
+/// How many times [`Harness::pump`] yields to the spawned jobs before it checks for events.
+const PUMP_YIELDS: usize = 16;
+
+/// An editor driven by a test, see the [module documentation](self).
+pub struct Harness {
+    runtime: EditorRuntime<CaptureBackend>,
+}
+
+impl Harness {
+    /// Starts an editor with a screen of 80 by 24 cells. Pass a `config` in `options` so that the
+    /// test doesn't depend on the user's `config.toml`.
+    pub fn new(options: RuntimeOptions) -> Result<Self> {
+        let (width, height) = SIZE;
+        Self::with_size(options, width, height)
+    }
+
+    pub fn with_size(options: RuntimeOptions, width: u16, height: u16) -> Result<Self> {
+        let backend = CaptureBackend::new(width, height);
+        let runtime = EditorRuntime::new(backend, options)?;
+        Ok(Self { runtime })
+    }
+
+    /// Presses `keys`, in the notation of macros, for example `%s<space>foo<ret>c`, and then
+    /// handles the events they caused with [`Harness::pump`].
+    pub async fn press(&mut self, keys: &str) -> Result<()> {
+        for key in parse_macro(keys)? {
+            self.runtime.handle_event(&Event::Key(key));
+        }
+        self.pump().await;
+        Ok(())
+    }
+
+    /// Runs a typed command, without the leading `:`, as if typed into the command prompt.
+    pub async fn command(&mut self, command: &str) {
+        for key in command_keys(command) {
+            self.runtime.handle_event(&Event::Key(key));
+        }
+        self.pump().await;
+    }
+
+    /// Handles the job callbacks, status messages and other events of the editor which are
+    /// ready. The spawned jobs run until they wait on something else, like a timer or a language
+    /// server, before each check, so with a current-thread Tokio runtime the same keys always
+    /// lead to the same state. Events which aren't ready yet are left alone, see
+    /// [`Harness::wait_for`].
+    pub async fn pump(&mut self) {
+        loop {
+            for _ in 0..PUMP_YIELDS {
+                tokio::task::yield_now().await;
+            }
+            match self.runtime.next_internal_event().now_or_never() {
+                Some(event) => self.runtime.handle_internal_event(event).await,
+                None => break,
+            }
+        }
+    }
+
+    /// Handles the editor's events as they arrive until `done` returns true, for results which
+    /// take time, like the completions of a language server. Fails after `timeout`.
+    pub async fn wait_for(
+        &mut self,
+        timeout: Duration,
+        mut done: impl FnMut(&Editor) -> bool,
+    ) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            self.pump().await;
+            if done(self.runtime.editor()) {
+                return Ok(());
+            }
+            match tokio::time::timeout_at(deadline, self.runtime.next_internal_event()).await {
+                Ok(event) => self.runtime.handle_internal_event(event).await,
+                Err(_) => bail!("timed out after {timeout:?}"),
+            }
+        }
+    }
+
+    pub fn editor(&self) -> &Editor {
+        self.runtime.editor()
+    }
+
+    pub fn runtime_mut(&mut self) -> &mut EditorRuntime<CaptureBackend> {
+        &mut self.runtime
+    }
+
+    /// The text of the focused document.
+    pub fn text(&self) -> String {
+        helix_view::doc!(self.editor()).text().to_string()
+    }
+
+    /// The selection of the focused document in the focused view.
+    pub fn selection(&self) -> Selection {
+        let (view, doc) = helix_view::current_ref!(self.editor());
+        doc.selection(view.id).clone()
+    }
+
+    /// The status message, if it is an error.
+    pub fn error(&self) -> Option<String> {
+        let editor = self.editor();
+        editor
+            .get_status()
+            .filter(|_| editor.is_err())
+            .map(|(message, _)| message.to_string())
+    }
+
+    /// Draws a frame and returns the screen, see [`CaptureBackend`] to read it.
+    pub fn render(&mut self) -> &CaptureBackend {
+        self.runtime.render_now();
+        self.runtime.terminal().backend()
+    }
+
+    /// Draws a frame and returns the text of the whole screen, one line per row.
+    pub fn screen(&mut self) -> String {
+        self.render().contents()
+    }
+
+    /// Draws a frame and returns the statusline of the focused view, when it is the only view.
+    pub fn statusline(&mut self) -> String {
+        let backend = self.render();
+        let y = backend.screen().area.height.saturating_sub(2);
+        backend.row(y)
+    }
+
+    /// Shuts the editor down, which waits for pending writes.
+    pub async fn finish(mut self) -> Result<()> {
+        self.runtime.shutdown().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+//! Terminal input as a stream of parsed events. [`TerminalInput`] reads the raw input on a
+//! thread of its own and [`InputParser`] turns it into key, mouse, paste and focus events and
+//! the terminal's replies to the backend's queries.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use helix_view::input::{Event, KeyCode, KeyEvent, KeyModifiers, TerminalResponse, VteEventParser};
+use tokio::{
+    sync::mpsc,
+    time::{sleep, Sleep},
+};
+
+/// The size of the reads of the input thread.
+const READ_SIZE: usize = 1024;
+
+/// How long the input thread waits for stdin to become readable before it checks whether it was
+/// paused, see [`TerminalInput::pause`].
+#[cfg(unix)]
+const POLL_TIMEOUT_MS: libc::c_int = 20;
+
+/// An event read from the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputEvent {
+    Event(Event),
+    /// A reply to a query of the backend, see `Backend::handle_response`.
+    Response(TerminalResponse),
+}
+
+/// Parses raw terminal input into [`InputEvent`]s.
+///
+/// A lone ESC byte is either the Escape key or the start of an escape sequence split across
+/// reads. Unless the terminal sends the Escape key as an escape sequence, as with the kitty
+/// keyboard protocol, it is held back until more input arrives or [`InputParser::release_esc`]
+/// is called once `editor.esc-timeout` passed.
+pub struct InputParser {
+    vte: VteEventParser,
+    held_esc: bool,
+}
+
+impl Default for InputParser {
+    fn default() -> Self {
+        Self {
+            vte: VteEventParser::new(),
+            held_esc: false,
+        }
+    }
+}
+
+impl InputParser {
+    /// Parses the `bytes` read from the terminal. With `hold_esc`, a read of a lone ESC is held
+    /// back, see [`InputParser::is_holding_esc`].
+    pub fn advance(&mut self, bytes: &[u8], hold_esc: bool) -> Vec<InputEvent> {
+        if bytes == [0x1B] && hold_esc {
+            self.held_esc = true;
+            return Vec::new();
+        }
+
+        // The rest of the escape sequence of a held back ESC arrived in time
+        if std::mem::take(&mut self.held_esc) {
+            self.vte.advance(&[0x1B]);
+        }
+        let events = self.vte.advance(bytes);
+        events
+            .into_iter()
+            .map(InputEvent::Event)
+            .chain(
+                self.vte
+                    .take_responses()
+                    .into_iter()
+                    .map(InputEvent::Response),
+            )
+            .collect()
+    }
+
+    pub fn is_holding_esc(&self) -> bool {
+        self.held_esc
+    }
+
+    /// The Escape key, if a lone ESC is held back and nothing followed it in time.
+    pub fn release_esc(&mut self) -> Option<InputEvent> {
+        std::mem::take(&mut self.held_esc).then_some(InputEvent::Event(Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+        })))
+    }
+}
+
+/// Terminal input read on a dedicated thread and parsed with an [`InputParser`]. The thread
+/// reads until the end of the input or an error.
+pub struct TerminalInput {
+    reads: mpsc::Receiver<io::Result<Vec<u8>>>,
+    /// Set while the thread must not read, see [`TerminalInput::pause`].
+    paused: Arc<(Mutex<bool>, Condvar)>,
+    parser: InputParser,
+    /// Events parsed from a read which weren't returned yet.
+    pending: VecDeque<InputEvent>,
+    /// Set while a lone ESC waits for the rest of an escape sequence.
+    esc_timeout: Option<Pin<Box<Sleep>>>,
+}
+
+impl TerminalInput {
+    /// Reads the input of the process's terminal from stdin. Stdin is only read once input is
+    /// available, so [`TerminalInput::pause`] doesn't wait for the next key press.
+    #[cfg(unix)]
+    pub fn stdin() -> io::Result<Self> {
+        Self::spawn(io::stdin(), || poll_stdin(POLL_TIMEOUT_MS))
+    }
+
+    /// Reads the input of the process's terminal from stdin.
+    #[cfg(not(unix))]
+    pub fn stdin() -> io::Result<Self> {
+        Self::new(io::stdin())
+    }
+
+    pub fn new(reader: impl Read + Send + 'static) -> io::Result<Self> {
+        Self::spawn(reader, || true)
+    }
+
+    /// Spawns the input thread. It only reads once `readable` returns true, which must return in
+    /// a timely manner so that pausing doesn't block for long.
+    fn spawn(
+        mut reader: impl Read + Send + 'static,
+        mut readable: impl FnMut() -> bool + Send + 'static,
+    ) -> io::Result<Self> {
+        let (tx, reads) = mpsc::channel(32);
+        let paused = Arc::new((Mutex::new(false), Condvar::new()));
+        let thread_paused = paused.clone();
+        std::thread::Builder::new()
+            .name("terminal input".to_string())
+            .spawn(move || {
+                let mut buf = [0; READ_SIZE];
+                loop {
+                    let (lock, resumed) = &*thread_paused;
+                    // The lock is held while reading, so that pausing waits for a read in progress
+                    let Ok(mut is_paused) = lock.lock() else {
+                        return;
+                    };
+                    while *is_paused {
+                        is_paused = match resumed.wait(is_paused) {
+                            Ok(is_paused) => is_paused,
+                            Err(_) => return,
+                        };
+                    }
+                    if !readable() {
+                        continue;
+                    }
+                    let read = match reader.read(&mut buf) {
+                        Ok(0) => return,
+                        Ok(n) => Ok(buf[..n].to_vec()),
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(err) => Err(err),
+                    };
+                    let failed = read.is_err();
+                    if tx.blocking_send(read).is_err() || failed {
+                        return;
+                    }
+                }
+            })?;
+        Ok(Self {
+            reads,
+            paused,
+            parser: InputParser::default(),
+            pending: VecDeque::new(),
+            esc_timeout: None,
+        })
+    }
+
+    /// Waits for the next event, `None` once the input ended. A lone ESC is held back for
+    /// `esc_timeout`, `None` when the terminal sends the Escape key as an escape sequence.
+    ///
+    /// This is cancel safe, so it can be used in `tokio::select!` with other event sources.
+    pub async fn next(&mut self, esc_timeout: Option<Duration>) -> Option<io::Result<InputEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            let held_esc = &mut self.esc_timeout;
+            // `None` when the held back ESC timed out
+            let read = tokio::select! {
+                _ = async {
+                    match held_esc.as_mut() {
+                        Some(held_esc) => held_esc.await,
+                        None => std::future::pending().await,
+                    }
+                } => None,
+                read = self.reads.recv() => Some(read?),
+            };
+            self.esc_timeout = None;
+            match read {
+                None => self.pending.extend(self.parser.release_esc()),
+                Some(Err(err)) => return Some(Err(err)),
+                Some(Ok(bytes)) => {
+                    let events = self.parser.advance(&bytes, esc_timeout.is_some());
+                    self.pending.extend(events);
+                    if let Some(timeout) = esc_timeout.filter(|_| self.parser.is_holding_esc()) {
+                        self.esc_timeout = Some(Box::pin(sleep(timeout)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The next event which was already read along with the last one, so that all of them can
+    /// be handled before drawing.
+    pub fn next_pending(&mut self) -> Option<InputEvent> {
+        self.pending.pop_front()
+    }
+
+    /// Stops reading the input until the returned guard is dropped, so that the backend can read
+    /// the replies to its queries itself, for example when claiming the terminal again after a
+    /// suspension. A read in progress is waited for.
+    pub fn pause(&self) -> InputPause {
+        let (lock, _) = &*self.paused;
+        *lock.lock().unwrap_or_else(|err| err.into_inner()) = true;
+        InputPause {
+            paused: self.paused.clone(),
+        }
+    }
+}
+
+/// Resumes reading the input when dropped, see [`TerminalInput::pause`].
+#[must_use]
+pub struct InputPause {
+    paused: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Drop for InputPause {
+    fn drop(&mut self) {
+        let (lock, resumed) = &*self.paused;
+        *lock.lock().unwrap_or_else(|err| err.into_inner()) = false;
+        resumed.notify_one();
+    }
+}
+
+/// Waits up to `timeout_ms` for stdin to become readable. Errors are left to the read.
+#[cfg(unix)]
+fn poll_stdin(timeout_ms: libc::c_int) -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: `fd` is a single, initialized `pollfd`
+    match unsafe { libc::poll(&mut fd, 1, timeout_ms) } {
+        0 => false,
+        -1 => io::Error::last_os_error().kind() != io::ErrorKind::Interrupted,
+        _ => true,
+    }
+}
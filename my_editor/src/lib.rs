@@ -0,0 +1,20 @@
+//! The editor as a library: [`EditorRuntime`] owns the editor and its terminal and runs the main
+//! loop, so other frontends can embed the editor with their own backend and event sources.
+//! Frontends in other processes can drive a headless editor over a socket, see [`serve`], and
+//! tests can drive one with [`Harness`].
+
+mod harness;
+mod input;
+mod runtime;
+mod screenshot;
+mod script;
+#[cfg(unix)]
+mod server;
+
+pub use harness::Harness;
+pub use input::{InputEvent, InputParser, InputPause, TerminalInput};
+pub use runtime::{EditorRuntime, InternalEvent, RuntimeOptions};
+pub use screenshot::{screenshot, ScreenshotOptions};
+pub use script::Script;
+#[cfg(unix)]
+pub use server::serve;
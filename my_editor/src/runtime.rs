@@ -0,0 +1,884 @@
+//! The editor's main loop, usable with any [`Backend`] and any source of input events.
+//!
+//! [`EditorRuntime::run`] drives the editor in the process's own terminal. Frontends with their
+//! own event sources instead feed input with [`EditorRuntime::handle_event`] or
+//! [`EditorRuntime::handle_input`], for input parsed by an [`InputParser`](crate::InputParser),
+//! and wait for the editor's own events (job callbacks, language server messages, timers) with
+//! [`EditorRuntime::next_internal_event`]:
+//!
+//! ```ignore
+//! let mut runtime = EditorRuntime::new(backend, RuntimeOptions::default())?;
+//! while !runtime.should_close() {
+//!     tokio::select! {
+//!         Some(event) = my_events.recv() => {
+//!             runtime.handle_event(&event);
+//!             runtime.render();
+//!         }
+//!         event = runtime.next_internal_event() => runtime.handle_internal_event(event).await,
+//!     }
+//! }
+//! runtime.shutdown().await?;
+//! ```
+
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context as _, Result};
+use arc_swap::{access::Map, ArcSwap};
+use futures_util::StreamExt;
+use helix_core::{pos_at_coords, syntax, Position, Range};
+use helix_event::status::{self, StatusMessage};
+use helix_loader::{config::ConfigDiagnostic, grammar, startup};
+use helix_term::{
+    application::Application,
+    compositor::{self, Compositor},
+    config::{Config, ConfigLoadError},
+    handlers::HandlersBuilder,
+    job::{self, Callback, Job, Jobs},
+    keymap::Keymaps,
+    lsp_messages::LspMessageHandler,
+    render_throttle::RenderThrottle,
+    ui::{self, overlay::overlaid, EditorView},
+};
+use helix_view::{
+    clipboard::ClipboardType,
+    doc_mut,
+    document::DocumentOpenError,
+    editor::{Action, ConfigEvent, EditorEvent},
+    input::{Event, TerminalResponse},
+    metrics::Phase,
+    resources::LatencyMonitor,
+    session, theme, Editor,
+};
+use tui::{
+    backend::{AlacrittyBackend, Backend},
+    terminal::Terminal,
+};
+
+use crate::input::InputEvent;
+
+/// How long quitting waits for the jobs that must complete before quitting, e.g. formatting
+/// before a write. A job that hangs must not keep the editor from closing.
+const SHUTDOWN_JOBS_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+pub struct RuntimeOptions {
+    /// The runtime directory with the themes, queries and grammars. Defaults to the first of
+    /// [`helix_loader::runtime_dirs`].
+    pub runtime_dir: Option<PathBuf>,
+    /// The configuration to use instead of the user's `config.toml` at startup. `:config-reload`
+    /// still reads `config.toml`.
+    pub config: Option<Config>,
+    /// The files opened at startup with the positions of their cursors. If the first one is a
+    /// directory, a file picker is opened in it instead. A scratch buffer is opened if no file
+    /// could be opened.
+    pub files: Vec<(PathBuf, Vec<Position>)>,
+    /// Whether to restore the saved session of the workspace when no files are given, as with
+    /// the `editor.restore-session` option.
+    pub restore_session: bool,
+    /// The embedder's hooks and async handlers, set up after the built-in handlers.
+    pub handlers: HandlersBuilder,
+}
+
+/// An event of the editor itself, see [`EditorRuntime::next_internal_event`].
+pub struct InternalEvent(Internal);
+
+enum Internal {
+    DeferredFrame,
+    Callback(anyhow::Result<Option<Callback>>),
+    Status(helix_event::status::StatusMessage),
+    Editor(EditorEvent),
+}
+
+/// The editor, its compositor and the terminal it draws to.
+///
+/// Only one runtime can exist per process: the job queue the editor's commands send their
+/// callbacks to is global.
+pub struct EditorRuntime<B: Backend> {
+    editor: Editor,
+    compositor: Compositor,
+    jobs: Jobs,
+    terminal: Terminal<B>,
+    config: Arc<ArcSwap<Config>>,
+    /// The runtime's languages.toml, the base of the language configuration.
+    lang_config_path: PathBuf,
+    /// The terminal's light or dark mode, which picks the variant of the configured theme.
+    theme_mode: Option<theme::Mode>,
+    /// Handles the messages of language servers and tracks the work done progress they report.
+    lsp: LspMessageHandler,
+    throttle: RenderThrottle,
+    /// Pauses background work while input events are slow to handle.
+    latency: LatencyMonitor,
+    /// The grammars of the open documents checked by [`EditorRuntime::build_missing_grammars`].
+    /// A grammar which fails to build isn't tried again.
+    checked_grammars: HashSet<String>,
+}
+
+impl<B: Backend> EditorRuntime<B> {
+    /// Claims the terminal, loads the configuration, themes and languages, opens the files of
+    /// `options` and draws the first frame. Must be called within a Tokio runtime.
+    pub fn new(backend: B, options: RuntimeOptions) -> Result<Self> {
+        let mut terminal = Terminal::new(backend)?;
+        terminal.claim()?;
+        let area = terminal.size();
+
+        let runtime_dir = match options.runtime_dir {
+            Some(runtime_dir) => runtime_dir,
+            None => helix_loader::runtime_dirs()
+                .first()
+                .context("no runtime directory found")?
+                .clone(),
+        };
+        // The loader looks for a `themes` directory in each of these, user themes first
+        let theme_loader =
+            theme::Loader::new(&[helix_loader::user_config_dir(), runtime_dir.clone()]);
+
+        // Errors in the config files fall back to the defaults and are shown in a popup.
+        let mut config_errors = Vec::new();
+
+        let lang_config_path = runtime_dir
+            .parent()
+            .context("the runtime directory has no parent")?
+            .join("languages.toml");
+        let lang_loader =
+            match startup::measure("language config", || load_lang_loader(&lang_config_path)) {
+                Ok(lang_loader) => lang_loader,
+                Err(err) => {
+                    config_errors.push(format!("{err:#}"));
+                    helix_core::config::default_lang_loader()
+                }
+            };
+        let lang_loader = Arc::new(ArcSwap::from_pointee(lang_loader));
+
+        let config = match options.config {
+            Some(config) => config,
+            None => match startup::measure("config", Config::load_default) {
+                Ok(config) => config,
+                Err(ConfigLoadError::BadConfig(err)) => {
+                    config_errors.push(err.to_string());
+                    Config::default()
+                }
+                Err(ConfigLoadError::Error(_)) => Config::default(),
+            },
+        };
+        let config = Arc::new(ArcSwap::from_pointee(config));
+        terminal.reconfigure((&config.load().editor).into())?;
+
+        // The job queue must exist before the handlers are set up, they send callbacks to it
+        let jobs = Jobs::new();
+        let handlers = options.handlers.build(config.clone());
+
+        let mut editor = Editor::new(
+            area.clip_bottom(1),
+            Arc::new(theme_loader),
+            lang_loader,
+            Arc::new(Map::new(Arc::clone(&config), |config: &Config| {
+                &config.editor
+            })),
+            handlers,
+        );
+
+        // Pick the light or dark variant of the configured theme to match the terminal
+        // background
+        let backend = terminal.backend();
+        let theme_mode = backend.get_theme_mode();
+        editor.terminal_true_color = backend.supports_true_color();
+        editor.terminal_images = backend.supports_images();
+        startup::measure("theme loading", || {
+            Application::load_configured_theme(
+                &mut editor,
+                &config.load(),
+                editor.terminal_true_color,
+                theme_mode,
+            )
+        });
+
+        let mut compositor = Compositor::new(area);
+        let keys = Box::new(Map::new(Arc::clone(&config), |config: &Config| {
+            &config.keys
+        }));
+        compositor.push(Box::new(EditorView::new(Keymaps::new(keys))));
+
+        let restore_session = options.files.is_empty()
+            && (options.restore_session || editor.config().restore_session);
+        open_files(&mut editor, &mut compositor, options.files)?;
+        if restore_session {
+            match session::restore(&mut editor, &session::session_file()) {
+                Ok(true) => (),
+                Ok(false) => editor.set_status("No session saved for this workspace"),
+                Err(err) => editor.set_error(format!("Failed to restore the session: {err}")),
+            }
+        }
+
+        if !config_errors.is_empty() {
+            let popup = ui::config_errors_popup(&config_errors, &editor);
+            compositor.push(Box::new(popup));
+        }
+
+        let mut runtime = Self {
+            editor,
+            compositor,
+            jobs,
+            terminal,
+            config,
+            lang_config_path,
+            theme_mode,
+            lsp: LspMessageHandler::new(),
+            throttle: RenderThrottle::new(),
+            latency: LatencyMonitor::new(),
+            checked_grammars: HashSet::new(),
+        };
+        runtime.build_missing_grammars();
+        runtime.terminal.clear()?;
+        runtime.render();
+        Ok(runtime)
+    }
+
+    pub fn editor(&self) -> &Editor {
+        &self.editor
+    }
+
+    pub fn editor_mut(&mut self) -> &mut Editor {
+        &mut self.editor
+    }
+
+    /// The compositor, to add components of the embedder next to the editor view, see
+    /// [`Compositor::push`].
+    pub fn compositor_mut(&mut self) -> &mut Compositor {
+        &mut self.compositor
+    }
+
+    pub fn terminal(&self) -> &Terminal<B> {
+        &self.terminal
+    }
+
+    pub fn terminal_mut(&mut self) -> &mut Terminal<B> {
+        &mut self.terminal
+    }
+
+    /// Whether the editor was closed, for example with `:quit`.
+    pub fn should_close(&self) -> bool {
+        self.editor.should_close()
+    }
+
+    /// Routes an input event through the compositor: keymaps, popups, the hooks run after
+    /// commands, etc. Call [`EditorRuntime::render`] to draw the result. Resizes are handled by
+    /// [`EditorRuntime::resize`].
+    pub fn handle_event(&mut self, event: &Event) {
+        let received = Instant::now();
+        let start = self.editor.metrics.start();
+        let mut cx = compositor::Context {
+            editor: &mut self.editor,
+            jobs: &mut self.jobs,
+            scroll: None,
+        };
+        self.compositor.handle_event(event, &mut cx);
+        self.editor.metrics.finish(Phase::Events, start);
+        self.latency
+            .record(received.elapsed(), &self.editor.config().resources);
+        self.build_missing_grammars();
+    }
+
+    /// Handles an event read from the terminal: input events go to the editor, replies to
+    /// terminal queries to the backend.
+    pub fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Event(event) => self.handle_event(&event),
+            InputEvent::Response(response) => self.handle_terminal_response(&response),
+        }
+    }
+
+    /// How long a lone ESC read from the terminal is held back, see
+    /// [`TerminalInput::next`](crate::TerminalInput::next).
+    /// With the kitty keyboard protocol the Escape key is sent as an escape sequence, so a lone
+    /// ESC is always the start of one and the parser waits for the rest of it.
+    pub fn esc_timeout(&self) -> Option<Duration> {
+        let kitty_keyboard = self.terminal.backend().supports_kitty_keyboard();
+        (!kitty_keyboard).then(|| self.editor.config().esc_timeout)
+    }
+
+    /// Passes a reply to a terminal query read with the input on to the backend. The configured
+    /// theme is applied again when it changed what the terminal supports or its theme mode.
+    fn handle_terminal_response(&mut self, response: &TerminalResponse) {
+        let backend = self.terminal.backend_mut();
+        backend.handle_response(response);
+        let theme_mode = backend.get_theme_mode();
+        let true_color = backend.supports_true_color();
+        if theme_mode != self.theme_mode || true_color != self.editor.terminal_true_color {
+            self.theme_mode = theme_mode;
+            self.editor.terminal_true_color = true_color;
+            Application::load_configured_theme(
+                &mut self.editor,
+                &self.config.load(),
+                true_color,
+                theme_mode,
+            );
+        }
+    }
+
+    /// Resizes the editor to the size reported by the backend.
+    pub fn resize(&mut self) -> io::Result<()> {
+        let area = self.terminal.autoresize()?;
+        self.compositor.resize(area);
+        self.editor.resize(area.clip_bottom(1));
+        self.handle_event(&Event::Resize(area.width, area.height));
+        Ok(())
+    }
+
+    /// Waits for the next event of the editor itself: a deferred frame, a job callback, a status
+    /// message, a language server message, a config change or the idle timer. Pass it to
+    /// [`EditorRuntime::handle_internal_event`].
+    ///
+    /// This is cancel safe, so it can be used in `tokio::select!` with other event sources.
+    pub async fn next_internal_event(&mut self) -> InternalEvent {
+        let event = tokio::select! {
+            // Frames deferred while rendering is throttled
+            _ = self.throttle.deferred_frame() => Internal::DeferredFrame,
+            // Async job callbacks (completion results, LSP write responses, etc.)
+            Some(callback) = self.jobs.callbacks.recv() => Internal::Callback(Ok(Some(callback))),
+            // Jobs that must complete before quitting
+            Some(callback) = self.jobs.wait_futures.next() => Internal::Callback(callback),
+            // Status messages sent from handlers and background tasks
+            Some(message) = self.jobs.status_messages.recv() => Internal::Status(message),
+            event = self.editor.wait_event() => Internal::Editor(event),
+        };
+        InternalEvent(event)
+    }
+
+    /// Handles an event returned by [`EditorRuntime::next_internal_event`] and renders if it
+    /// changed anything.
+    pub async fn handle_internal_event(&mut self, InternalEvent(event): InternalEvent) {
+        match event {
+            Internal::DeferredFrame => {}
+            Internal::Callback(callback) => {
+                self.jobs
+                    .handle_callback(&mut self.editor, &mut self.compositor, callback);
+            }
+            Internal::Status(message) => {
+                use helix_event::status::Severity as StatusSeverity;
+                use helix_view::editor::Severity;
+
+                let severity = match message.severity {
+                    StatusSeverity::Hint => Severity::Hint,
+                    StatusSeverity::Info => Severity::Info,
+                    StatusSeverity::Warning => Severity::Warning,
+                    StatusSeverity::Error => Severity::Error,
+                };
+                self.editor.set_message(message.message, severity);
+            }
+            Internal::Editor(EditorEvent::LanguageServerMessage((id, call))) => {
+                self.lsp
+                    .handle_message(
+                        &mut self.editor,
+                        &mut self.compositor,
+                        &mut self.jobs,
+                        call,
+                        id,
+                    )
+                    .await;
+            }
+            Internal::Editor(EditorEvent::ConfigEvent(event)) => self.handle_config_event(event),
+            Internal::Editor(EditorEvent::DocumentSaved(event)) => {
+                Application::handle_document_write(&mut self.editor, event);
+            }
+            Internal::Editor(EditorEvent::TerminalOutput(output)) => {
+                self.editor.handle_terminal_output(output);
+            }
+            Internal::Editor(EditorEvent::Redraw) => {}
+            Internal::Editor(EditorEvent::IdleTimer) => {
+                self.editor.clear_idle_timer();
+                self.handle_event(&Event::IdleTimeout);
+            }
+            // Stopped, continued, output and terminated events update the debugger state in the
+            // editor; the breakpoint gutter and the debug pickers read it from there.
+            Internal::Editor(EditorEvent::DebuggerEvent((id, payload))) => {
+                if !self.editor.handle_debugger_message(id, payload).await {
+                    return;
+                }
+            }
+        }
+        self.build_missing_grammars();
+        self.render();
+    }
+
+    /// Fetches and builds the tree-sitter grammars of the open documents which aren't built
+    /// yet, in the background, with `editor.auto-build-grammars`. The progress is reported in
+    /// the statusline. Once built, the language configuration is reloaded and the documents are
+    /// highlighted.
+    fn build_missing_grammars(&mut self) {
+        if !self.editor.config().auto_build_grammars {
+            return;
+        }
+        let names: Vec<String> = self
+            .editor
+            .documents()
+            .filter_map(|doc| doc.language_config())
+            .map(|config| config.grammar.as_ref().unwrap_or(&config.language_id))
+            .filter(|name| !self.checked_grammars.contains(*name))
+            .cloned()
+            .collect();
+        let mut missing = Vec::new();
+        for name in names {
+            if self.checked_grammars.insert(name.clone()) && !grammar::is_grammar_built(&name) {
+                missing.push(name);
+            }
+        }
+        if missing.is_empty() {
+            return;
+        }
+
+        let lang_config_path = self.lang_config_path.clone();
+        let build = async move {
+            let job = job::current_job();
+            let (built, errors) = tokio::task::spawn_blocking(move || {
+                grammar::fetch_and_build_grammars(&missing, |name, i, total| {
+                    let progress = format!("{name} ({}/{total})", i + 1);
+                    status::report_blocking(StatusMessage {
+                        severity: status::Severity::Info,
+                        message: format!("Building grammar {progress}").into(),
+                    });
+                    if let Some(job) = job {
+                        job::report_progress(job, progress);
+                    }
+                })
+            })
+            .await??;
+            let lang_loader = if built.is_empty() {
+                None
+            } else {
+                Some(load_lang_loader(&lang_config_path)?)
+            };
+
+            let callback = move |editor: &mut Editor| {
+                if let Some(lang_loader) = lang_loader {
+                    editor.syn_loader.store(Arc::new(lang_loader));
+                    let lang_loader = editor.syn_loader.load();
+                    lang_loader.set_scopes(editor.theme.scopes().to_vec());
+                    for doc in editor.documents.values_mut() {
+                        doc.detect_language(&lang_loader);
+                    }
+                }
+                if let Some((name, err)) = errors.first() {
+                    editor.set_error(format!("Failed to build grammar {name}: {err:#}"));
+                } else if !built.is_empty() {
+                    editor.set_status(format!("Built grammars: {}", built.join(", ")));
+                }
+            };
+            Ok(Callback::Editor(Box::new(callback)))
+        };
+        self.jobs
+            .add(Job::with_callback(build).named("build grammars"));
+    }
+
+    /// Applies config changes made at runtime, for example with `:set`, `:toggle` or
+    /// `:config-reload`.
+    fn handle_config_event(&mut self, event: ConfigEvent) {
+        let old_editor_config = self.editor.config();
+        match event {
+            ConfigEvent::Refresh => match self.refresh_config() {
+                Ok(()) => self.editor.set_status("Config refreshed"),
+                Err(err) => self.editor.set_error(format!("{err:#}")),
+            },
+            ConfigEvent::Update(editor_config) => {
+                let mut app_config = (**self.config.load()).clone();
+                app_config.editor = *editor_config;
+                if let Err(err) = self.terminal.reconfigure((&app_config.editor).into()) {
+                    self.editor.set_error(err.to_string());
+                }
+                self.config.store(Arc::new(app_config));
+            }
+        }
+        self.editor.refresh_config(&old_editor_config);
+    }
+
+    /// Re-reads `config.toml` and the language configuration, like `Application::refresh_config`.
+    /// The keymaps read the stored config, so they pick up the new bindings as well.
+    fn refresh_config(&mut self) -> Result<()> {
+        let config = Config::load_default().context("Failed to load config")?;
+
+        // The language loader must be replaced before the theme is set, setting the theme
+        // updates the loader's highlight scopes.
+        let lang_loader = load_lang_loader(&self.lang_config_path)?;
+        self.editor.syn_loader.store(Arc::new(lang_loader));
+        Application::load_configured_theme(
+            &mut self.editor,
+            &config,
+            self.editor.terminal_true_color,
+            self.theme_mode,
+        );
+
+        // Re-parse the open documents with the new language configuration
+        let lang_loader = self.editor.syn_loader.load();
+        for doc in self.editor.documents.values_mut() {
+            doc.detect_editor_config();
+            doc.detect_language(&lang_loader);
+            let diagnostics = Editor::doc_diagnostics(
+                &self.editor.language_servers,
+                &self.editor.diagnostics,
+                doc,
+            );
+            doc.replace_diagnostics(diagnostics, &[], None);
+        }
+
+        self.terminal.reconfigure((&config.editor).into())?;
+        self.config.store(Arc::new(config));
+        Ok(())
+    }
+
+    /// Draws a frame: the compositor renders the editor views with their syntax highlighting,
+    /// popups, the statusline, etc.
+    ///
+    /// Frames are coalesced: a frame requested within a frame interval of the previous one is
+    /// deferred, and drawn once [`EditorRuntime::next_internal_event`] returns the deferred
+    /// frame, so a burst of events is drawn once. See [`RenderThrottle`].
+    pub fn render(&mut self) {
+        if self.throttle.start_frame(&self.editor) {
+            self.draw_frame();
+        }
+    }
+
+    /// Draws a frame right away, also when the previous one was drawn within the frame
+    /// interval.
+    pub fn render_now(&mut self) {
+        self.throttle.start_frame_now();
+        self.draw_frame();
+    }
+
+    fn draw_frame(&mut self) {
+        let area = self
+            .terminal
+            .autoresize()
+            .expect("Unable to determine terminal size");
+
+        // Drain any synchronous callbacks before rendering (some commands push callbacks
+        // that must be executed before the compositor state is consistent).
+        while let Ok(callback) = self.jobs.callbacks.try_recv() {
+            self.jobs
+                .handle_callback(&mut self.editor, &mut self.compositor, Ok(Some(callback)));
+        }
+
+        let surface = self.terminal.current_buffer_mut();
+        let bg = self.editor.theme.get("ui.background");
+        surface.clear_with(area, bg);
+
+        let mut cx = compositor::Context {
+            editor: &mut self.editor,
+            jobs: &mut self.jobs,
+            scroll: None,
+        };
+        let render_start = cx.editor.metrics.start();
+        self.compositor.render(area, surface, &mut cx);
+        self.editor.metrics.finish(Phase::Render, render_start);
+
+        let (pos, kind) = self.compositor.cursor(area, &self.editor);
+        let pos = pos.map(|pos| (pos.col as u16, pos.row as u16));
+        // The backend only writes the title when it changed
+        if let Err(err) = self
+            .terminal
+            .backend_mut()
+            .set_title(&window_title(&self.editor))
+        {
+            log::warn!("failed to set the terminal title: {err}");
+        }
+        if let Err(err) = self.terminal.set_cursor_color(self.editor.cursor_color()) {
+            log::warn!("failed to set the cursor color: {err}");
+        }
+        let start = Instant::now();
+        self.terminal.draw(pos, kind).unwrap();
+        self.throttle
+            .finish_frame(&mut self.editor, start.elapsed());
+        let (draw, flush) = self.terminal.draw_timings();
+        self.editor.metrics.record(Phase::Draw, draw);
+        self.editor.metrics.record(Phase::Flush, flush);
+        startup::finish();
+    }
+
+    /// Finishes the work still pending when the editor closes and gives the terminal back:
+    /// waits for the jobs that must complete before quitting (for at most
+    /// [`SHUTDOWN_JOBS_TIMEOUT`]), flushes pending writes, saves the state persisted across
+    /// sessions and shuts the language servers down. Errors of these steps are logged, the
+    /// remaining steps still run.
+    pub async fn shutdown(&mut self) -> io::Result<()> {
+        let jobs = self
+            .jobs
+            .finish(&mut self.editor, Some(&mut self.compositor));
+        match tokio::time::timeout(SHUTDOWN_JOBS_TIMEOUT, jobs).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => log::error!("Error executing job: {err}"),
+            Err(_) => log::error!("Timed out waiting for jobs to finish"),
+        }
+
+        if let Err(err) = self.editor.flush_writes().await {
+            log::error!("Error writing: {err}");
+        }
+
+        if let Err(err) = self.editor.save_registers() {
+            log::error!("Failed to save registers: {err}");
+        }
+
+        if self.editor.close_language_servers(None).await.is_err() {
+            log::error!("Timed out waiting for language servers to shutdown");
+        }
+
+        self.terminal.restore()
+    }
+}
+
+impl EditorRuntime<AlacrittyBackend<io::Stdout>> {
+    /// Runs the editor in the process's terminal until it is closed: reads input, follows
+    /// resizes and writes clipboard copies with OSC 52. On Unix input is read from stdin and
+    /// the editor suspends on `SIGTSTP`, `platform_terminal` leaves raw mode while suspended.
+    /// On Windows input is read from the console's event source. The terminal must already be
+    /// in raw mode.
+    ///
+    /// The runtime is shut down with [`EditorRuntime::shutdown`] before returning, also when
+    /// the loop failed.
+    pub async fn run(&mut self, platform_terminal: &mut termina::PlatformTerminal) -> Result<()> {
+        let result = self.event_loop(platform_terminal).await;
+        self.shutdown().await?;
+        result
+    }
+
+    /// Writes a copy of the `termcode` clipboard provider to the terminal.
+    fn write_clipboard(&mut self, clipboard_type: ClipboardType, content: &str) {
+        let backend = self.terminal.backend_mut();
+        if let Err(err) = backend.set_clipboard(content, clipboard_type) {
+            self.editor
+                .set_error(format!("Failed to set the clipboard: {err}"));
+            self.render();
+        }
+    }
+}
+
+#[cfg(not(windows))]
+impl EditorRuntime<AlacrittyBackend<io::Stdout>> {
+    async fn event_loop(
+        &mut self,
+        platform_terminal: &mut termina::PlatformTerminal,
+    ) -> Result<()> {
+        use signal_hook::consts::signal::{SIGCONT, SIGTSTP, SIGWINCH};
+        use signal_hook_tokio::Signals;
+
+        let mut input = crate::TerminalInput::stdin().context("failed to read terminal input")?;
+        let mut signals =
+            Signals::new([SIGWINCH, SIGTSTP, SIGCONT]).context("build signal handler")?;
+
+        // Copies of the `termcode` clipboard provider are written by the backend with OSC 52
+        let (clipboard_tx, mut clipboard_rx) = tokio::sync::mpsc::unbounded_channel();
+        helix_view::clipboard::set_termcode_sink(clipboard_tx);
+
+        while !self.should_close() {
+            let esc_timeout = self.esc_timeout();
+            tokio::select! {
+                event = input.next(esc_timeout) => match event {
+                    Some(Ok(event)) => {
+                        self.handle_input(event);
+                        // Draw once for all the events of a read
+                        while let Some(event) = input.next_pending() {
+                            self.handle_input(event);
+                        }
+                        self.render();
+                    }
+                    Some(Err(err)) => return Err(err).context("failed to read terminal input"),
+                    None => break,
+                },
+
+                Some(signal) = signals.next() => match signal {
+                    // `suspend` (Ctrl-Z) raises SIGTSTP
+                    SIGTSTP => self.suspend(platform_terminal)?,
+                    SIGCONT => self.resume(platform_terminal, &input)?,
+                    // Terminal resizes: the backend reports the new size
+                    _ => {
+                        self.resize()?;
+                        // Resizing clears the screen, redraw everything
+                        self.render();
+                    }
+                },
+
+                // Clipboard copies, sent to the terminal between frames
+                Some((clipboard_type, content)) = clipboard_rx.recv() => {
+                    self.write_clipboard(clipboard_type, &content);
+                }
+
+                event = self.next_internal_event() => self.handle_internal_event(event).await,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gives the terminal back to the shell and stops the process until `SIGCONT`.
+    fn suspend(&mut self, platform_terminal: &mut termina::PlatformTerminal) -> Result<()> {
+        use termina::Terminal as _;
+
+        self.terminal.restore()?;
+        platform_terminal.enter_cooked_mode()?;
+
+        // Handling SIGTSTP replaced its default action, so the process is stopped with SIGSTOP.
+        // It is sent to the whole process group, otherwise a parent waiting for the editor
+        // (e.g. `git commit`) keeps the terminal stuck.
+        //
+        // SAFETY: a plain call to a POSIX function, a pid of 0 is the caller's process group.
+        if unsafe { libc::kill(0, libc::SIGSTOP) } != 0 {
+            return Err(io::Error::last_os_error()).context("failed to suspend");
+        }
+        Ok(())
+    }
+
+    /// Takes the terminal back after a suspension and redraws the whole screen, which the shell
+    /// drew over in between. `input` is paused while claiming the terminal, which reads the
+    /// replies to its queries itself.
+    fn resume(
+        &mut self,
+        platform_terminal: &mut termina::PlatformTerminal,
+        input: &crate::TerminalInput,
+    ) -> Result<()> {
+        use termina::Terminal as _;
+
+        platform_terminal.enter_raw_mode()?;
+        let pause = input.pause();
+        // Claiming can fail while the shell still holds the terminal, see
+        // https://github.com/neovim/neovim/issues/12322
+        for retries in 1..=10 {
+            match self.terminal.claim() {
+                Ok(()) => break,
+                Err(err) if retries == 10 => {
+                    return Err(err).context("failed to claim the terminal")
+                }
+                Err(_) => continue,
+            }
+        }
+        drop(pause);
+
+        // The size may have changed while the editor was stopped
+        self.resize()?;
+        self.terminal.clear()?;
+        self.render();
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+impl EditorRuntime<AlacrittyBackend<io::Stdout>> {
+    async fn event_loop(
+        &mut self,
+        platform_terminal: &mut termina::PlatformTerminal,
+    ) -> Result<()> {
+        use termina::Terminal as _;
+
+        // The console reports input as events rather than bytes, resizes included
+        let mut events =
+            termina::EventStream::new(platform_terminal.event_reader(), |event| !event.is_escape());
+
+        // Copies of the `termcode` clipboard provider are written by the backend with OSC 52
+        let (clipboard_tx, mut clipboard_rx) = tokio::sync::mpsc::unbounded_channel();
+        helix_view::clipboard::set_termcode_sink(clipboard_tx);
+
+        while !self.should_close() {
+            tokio::select! {
+                event = events.next() => match event {
+                    Some(Ok(termina::Event::WindowResized(_))) => {
+                        self.resize()?;
+                        // Resizing clears the screen, redraw everything
+                        self.render();
+                    }
+                    Some(Ok(event)) => {
+                        self.handle_event(&event.into());
+                        self.render();
+                    }
+                    Some(Err(err)) => return Err(err).context("failed to read console input"),
+                    None => break,
+                },
+
+                // Clipboard copies, sent to the terminal between frames
+                Some((clipboard_type, content)) = clipboard_rx.recv() => {
+                    self.write_clipboard(clipboard_type, &content);
+                }
+
+                event = self.next_internal_event() => self.handle_internal_event(event).await,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens the files given at startup, see [`RuntimeOptions::files`].
+fn open_files(
+    editor: &mut Editor,
+    compositor: &mut Compositor,
+    files: Vec<(PathBuf, Vec<Position>)>,
+) -> Result<()> {
+    let mut files = files.into_iter().peekable();
+    if let Some((dir, _)) = files.next_if(|(path, _)| path.is_dir()) {
+        let picker = ui::file_picker(editor, dir);
+        compositor.push(Box::new(overlaid(picker)));
+    }
+
+    let mut opened = 0;
+    for (path, positions) in files {
+        if path.is_dir() {
+            bail!("expected a path to a file, but found a directory: {path:?}");
+        }
+        let action = if opened == 0 {
+            Action::VerticalSplit
+        } else {
+            Action::Load
+        };
+        let doc_id = match editor.open(&path, action) {
+            // Skip irregular files like sockets
+            Err(DocumentOpenError::IrregularFile) => continue,
+            result => result.with_context(|| format!("failed to open {}", path.display()))?,
+        };
+        opened += 1;
+        if positions.is_empty() {
+            continue;
+        }
+
+        let view_id = editor.tree.focus;
+        let doc = doc_mut!(editor, &doc_id);
+        let selection = positions
+            .into_iter()
+            .map(|coords| Range::point(pos_at_coords(doc.text().slice(..), coords, true)))
+            .collect();
+        doc.set_selection(view_id, selection);
+    }
+
+    if opened == 0 {
+        editor.new_file(Action::VerticalSplit);
+    }
+    Ok(())
+}
+
+/// Loads the language configuration: the runtime's `languages.toml` merged with the user's
+/// `languages.toml` files.
+fn load_lang_loader(path: &Path) -> Result<syntax::Loader> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read languages.toml at {path:?}"))?;
+    let base: toml::Value = toml::from_str(&source)
+        .map_err(|err| ConfigDiagnostic::new(&err).with_source(path, &source, err.span()))?;
+
+    let user_files = helix_loader::config::user_lang_config_files();
+    let mut config = base;
+    for (file, source) in &user_files {
+        let user_config: toml::Value = toml::from_str(source)
+            .map_err(|err| ConfigDiagnostic::new(&err).with_source(file, source, err.span()))?;
+        config = helix_loader::merge_toml_values(config, user_config, 3);
+    }
+    let config: syntax::config::Configuration = config
+        .try_into()
+        .map_err(|err| ConfigDiagnostic::new(&err).locate(&user_files))?;
+    Ok(syntax::Loader::new(config)?)
+}
+
+/// The terminal window title: the focused document and whether it has unsaved changes.
+fn window_title(editor: &Editor) -> String {
+    let doc = helix_view::doc!(editor);
+    let modified = if doc.is_modified() { " [+]" } else { "" };
+    format!("{}{modified} — helix", doc.display_name())
+}
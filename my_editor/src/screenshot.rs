@@ -0,0 +1,68 @@
+//! Renders a file to a styled [`Buffer`] without a terminal or an event loop, for documentation
+//! screenshots and theme previews. [`tui::export`] serializes the buffer to ANSI text or HTML.
+
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use helix_term::config::Config;
+use tui::{backend::CaptureBackend, buffer::Buffer};
+
+use crate::{EditorRuntime, RuntimeOptions};
+
+/// What [`screenshot`] renders.
+pub struct ScreenshotOptions {
+    pub file: PathBuf,
+    /// The name of the theme, the configured one if `None`.
+    pub theme: Option<String>,
+    pub width: u16,
+    pub height: u16,
+    /// See [`RuntimeOptions::runtime_dir`].
+    pub runtime_dir: Option<PathBuf>,
+    /// See [`RuntimeOptions::config`].
+    pub config: Option<Config>,
+}
+
+impl Default for ScreenshotOptions {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::new(),
+            theme: None,
+            width: 80,
+            height: 24,
+            runtime_dir: None,
+            config: None,
+        }
+    }
+}
+
+/// Opens the file in an editor of the given size and renders a single frame: the editor view
+/// with its syntax highlighting and the statusline, as drawn right after startup.
+///
+/// The editor is shut down before returning. Like [`EditorRuntime`], it can't be used while
+/// another runtime exists in the process.
+pub async fn screenshot(options: ScreenshotOptions) -> Result<Buffer> {
+    let runtime_options = RuntimeOptions {
+        runtime_dir: options.runtime_dir,
+        config: options.config,
+        files: vec![(options.file, Vec::new())],
+        ..RuntimeOptions::default()
+    };
+    let backend = CaptureBackend::new(options.width, options.height);
+    let mut runtime = EditorRuntime::new(backend, runtime_options)?;
+    let theme = options.theme.as_deref().map(|name| {
+        let theme = runtime.editor().theme_loader.load(name);
+        theme.with_context(|| format!("failed to load the theme {name}"))
+    });
+    let result = match theme.transpose() {
+        Ok(theme) => {
+            if let Some(theme) = theme {
+                runtime.editor_mut().set_theme(theme);
+            }
+            runtime.render_now();
+            Ok(runtime.terminal().backend().screen().clone())
+        }
+        Err(err) => Err(err),
+    };
+    runtime.shutdown().await?;
+    result
+}
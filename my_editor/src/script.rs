@@ -0,0 +1,119 @@
+//! Headless scripted runs for batch editing and smoke tests: a [`Script`] of keys and typed
+//! commands is fed to an editor drawing to a [`CaptureBackend`] instead of a terminal.
+//!
+//! A script file has one step per line. Lines starting with `:` are typed commands, run as if
+//! typed into the command prompt. Other lines are keys in the notation of macros, for example
+//! `ggdd` or `%s<space>foo<ret>c`. Empty lines and lines starting with `#` are skipped.
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use helix_view::input::{parse_macro, Event, KeyCode, KeyEvent, KeyModifiers};
+use tui::backend::CaptureBackend;
+
+use crate::EditorRuntime;
+
+/// The editor counts as settled once it had no events for this long: the job callbacks,
+/// language server messages and status messages caused by a step arrive in quick succession.
+const SETTLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+enum Step {
+    Keys(Vec<KeyEvent>),
+    Command(String),
+}
+
+/// The steps run by [`EditorRuntime::run_script`].
+#[derive(Default)]
+pub struct Script {
+    steps: Vec<Step>,
+}
+
+impl Script {
+    /// Parses a script file, see the [module documentation](self).
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut script = Self::default();
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_prefix(':') {
+                Some(command) => script.push_command(command),
+                None => {
+                    let keys = parse_macro(line).with_context(|| format!("line {}", i + 1))?;
+                    script.steps.push(Step::Keys(keys));
+                }
+            }
+        }
+        Ok(script)
+    }
+
+    /// A script pressing `keys`, in the notation of macros.
+    pub fn keys(keys: &str) -> Result<Self> {
+        Ok(Self {
+            steps: vec![Step::Keys(parse_macro(keys)?)],
+        })
+    }
+
+    /// Appends a typed command, without the leading `:`.
+    pub fn push_command(&mut self, command: impl Into<String>) {
+        self.steps.push(Step::Command(command.into()));
+    }
+}
+
+impl EditorRuntime<CaptureBackend> {
+    /// Runs the steps of `script` until they are done or the editor is closed. After each step
+    /// the runtime handles the editor's events until it settles. The final frame is rendered to
+    /// the backend, read it with [`EditorRuntime::terminal`].
+    ///
+    /// The runtime is shut down before returning, which waits for pending writes. Returns the
+    /// errors shown in the statusline by the steps.
+    pub async fn run_script(&mut self, script: Script) -> Result<Vec<String>> {
+        let mut errors = Vec::new();
+        for step in script.steps {
+            if self.should_close() {
+                break;
+            }
+            let keys = match step {
+                Step::Keys(keys) => keys,
+                Step::Command(command) => command_keys(&command),
+            };
+            for key in keys {
+                self.handle_event(&Event::Key(key));
+            }
+            self.settle().await;
+
+            if self.editor().is_err() {
+                let editor = self.editor_mut();
+                if let Some((message, _)) = editor.get_status() {
+                    errors.push(message.to_string());
+                }
+                editor.clear_status();
+            }
+        }
+
+        self.render_now();
+        self.shutdown().await?;
+        Ok(errors)
+    }
+
+    async fn settle(&mut self) {
+        while let Ok(event) = tokio::time::timeout(SETTLE_TIMEOUT, self.next_internal_event()).await
+        {
+            self.handle_internal_event(event).await;
+        }
+    }
+}
+
+/// The keys typing `command` into the command prompt. The command is typed character by
+/// character rather than parsed as keys, so `<` in it is typed as is.
+pub(crate) fn command_keys(command: &str) -> Vec<KeyEvent> {
+    let key = |code| KeyEvent {
+        code,
+        modifiers: KeyModifiers::NONE,
+    };
+    std::iter::once(key(KeyCode::Char(':')))
+        .chain(command.chars().map(|c| key(KeyCode::Char(c))))
+        .chain(std::iter::once(key(KeyCode::Enter)))
+        .collect()
+}
@@ -0,0 +1,820 @@
+//! A headless server for frontends drawing the editor themselves, a GUI for example: the editor
+//! runs without a terminal and a single client drives it over a unix socket with JSON-RPC 2.0,
+//! one message per line.
+//!
+//! The client calls these methods:
+//!
+//! - `initialize` with `{"width", "height"}`: sets the size of the screen in cells and sends the
+//!   whole screen.
+//! - `resize` with `{"width", "height"}`.
+//! - `keys` with `{"keys"}`: presses keys in the notation of macros, for example `ihello<esc>`.
+//! - `command` with `{"command"}`: runs a typed command, without the leading `:`. Fails with the
+//!   error the command showed.
+//! - `open` with `{"path"}`: opens a file in the focused view.
+//! - `edit` with `{"changes"}`: replaces the text of the focused document between the `from`
+//!   and `to` character offsets of each change with its `text`. The changes are sorted and
+//!   don't overlap, offsets refer to the text before the edit.
+//! - `save` with an optional `{"path", "force"}`: writes the focused document.
+//! - `text`: returns the `path`, `text` and `modified` state of the focused document.
+//! - `shutdown`: closes the editor.
+//!
+//! The server sends these notifications:
+//!
+//! - `redraw` after each frame with the cells which changed: `{"clear", "cells", "cursor",
+//!   "title"}`. When `clear` is true the screen was cleared first. Each cell has its `x`, `y`,
+//!   `symbol`, `fg`, `bg`, `underline-color`, `underline-style`, `modifiers` and `hyperlink`,
+//!   the URL the cell links to or `null`. Colors are theme color names, `#rrggbb` or a number
+//!   for indexed colors. `cursor` is `{"x", "y", "kind"}` or `null` while it is hidden.
+//! - `status` when the status message changes: `{"message", "severity"}` or `null`.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use helix_core::{Tendril, Transaction};
+use helix_lsp::jsonrpc::{self, ErrorCode, Params};
+use helix_view::{
+    current, doc,
+    editor::{Action, Severity},
+    graphics::{Color, CursorKind, Rect, UnderlineStyle},
+    input::{parse_macro, Event},
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{unix::OwnedWriteHalf, UnixListener, UnixStream},
+};
+use tui::{
+    backend::Backend,
+    buffer::{Buffer, Cell, Image, Scroll},
+    terminal::Config,
+};
+
+use crate::{script::command_keys, EditorRuntime, RuntimeOptions};
+
+/// The size of the screen until the client calls `initialize`.
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// The error code of methods which failed in the editor, for example a command showing an
+/// error.
+const EDITOR_ERROR: i64 = 1;
+
+/// Listens on `socket`, runs the editor once a client connected and serves it until the client
+/// disconnects, calls `shutdown` or the editor is closed. The socket file is removed before
+/// returning.
+pub async fn serve(socket: &Path, options: RuntimeOptions) -> Result<()> {
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("failed to listen on {}", socket.display()))?;
+    let result = accept(listener, options).await;
+    if let Err(err) = std::fs::remove_file(socket) {
+        log::warn!("failed to remove the socket {}: {err}", socket.display());
+    }
+    result
+}
+
+async fn accept(listener: UnixListener, options: RuntimeOptions) -> Result<()> {
+    let (stream, _) = listener
+        .accept()
+        .await
+        .context("failed to accept a client")?;
+    serve_client(stream, options).await
+}
+
+/// Runs the editor for the client connected with `stream` until it disconnects, calls
+/// `shutdown` or the editor is closed.
+async fn serve_client(stream: UnixStream, options: RuntimeOptions) -> Result<()> {
+    let (reader, writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let (width, height) = DEFAULT_SIZE;
+    let mut server = Server {
+        runtime: EditorRuntime::new(ServerBackend::new(width, height), options)?,
+        writer,
+        status: None,
+        shutdown: false,
+    };
+    let result = async {
+        server.send_updates().await?;
+        while !server.shutdown && !server.runtime.should_close() {
+            tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(line) => server.handle_line(&line).await?,
+                    None => break,
+                },
+                event = server.runtime.next_internal_event() => {
+                    server.runtime.handle_internal_event(event).await;
+                }
+            }
+            server.send_updates().await?;
+        }
+        io::Result::Ok(())
+    }
+    .await;
+    server.runtime.shutdown().await?;
+    result.context("the connection to the client failed")
+}
+
+struct Server {
+    runtime: EditorRuntime<ServerBackend>,
+    writer: OwnedWriteHalf,
+    /// The status message last sent to the client.
+    status: Option<(String, Severity)>,
+    shutdown: bool,
+}
+
+#[derive(Deserialize)]
+struct Size {
+    width: u16,
+    height: u16,
+}
+
+#[derive(Deserialize)]
+struct Keys {
+    keys: String,
+}
+
+#[derive(Deserialize)]
+struct Command {
+    command: String,
+}
+
+#[derive(Deserialize)]
+struct Open {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Edit {
+    changes: Vec<Change>,
+}
+
+#[derive(Deserialize)]
+struct Change {
+    from: usize,
+    to: usize,
+    text: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Save {
+    path: Option<PathBuf>,
+    force: bool,
+}
+
+impl Server {
+    /// Handles a request or notification of the client and answers requests.
+    async fn handle_line(&mut self, line: &str) -> io::Result<()> {
+        let (id, result) = match serde_json::from_str(line) {
+            Ok(jsonrpc::Call::MethodCall(call)) => {
+                (Some(call.id), self.call(&call.method, call.params))
+            }
+            Ok(jsonrpc::Call::Notification(notification)) => {
+                if let Err(err) = self.call(&notification.method, notification.params) {
+                    log::warn!(
+                        "failed to handle the notification {}: {err}",
+                        notification.method
+                    );
+                }
+                (None, Ok(Value::Null))
+            }
+            Ok(jsonrpc::Call::Invalid { id }) => (
+                Some(id),
+                Err(error(ErrorCode::InvalidRequest, "invalid request")),
+            ),
+            Err(err) => (
+                Some(jsonrpc::Id::Null),
+                Err(error(ErrorCode::ParseError, err.to_string())),
+            ),
+        };
+        self.runtime.render();
+
+        let Some(id) = id else {
+            return Ok(());
+        };
+        let output = match result {
+            Ok(result) => jsonrpc::Output::Success(jsonrpc::Success {
+                jsonrpc: Some(jsonrpc::Version::V2),
+                result,
+                id,
+            }),
+            Err(error) => jsonrpc::Output::Failure(jsonrpc::Failure {
+                jsonrpc: Some(jsonrpc::Version::V2),
+                error,
+                id,
+            }),
+        };
+        self.send(&output).await
+    }
+
+    fn call(&mut self, method: &str, params: Params) -> Result<Value, jsonrpc::Error> {
+        match method {
+            "initialize" => {
+                let size: Size = params.parse()?;
+                self.resize(size)?;
+                // The client draws the whole screen, also when its size didn't change
+                self.runtime
+                    .terminal_mut()
+                    .clear()
+                    .map_err(internal_error)?;
+            }
+            "resize" => self.resize(params.parse()?)?,
+            "keys" => {
+                let Keys { keys } = params.parse()?;
+                let keys = parse_macro(&keys)
+                    .map_err(|err| jsonrpc::Error::invalid_params(err.to_string()))?;
+                for key in keys {
+                    self.runtime.handle_event(&Event::Key(key));
+                }
+            }
+            "command" => {
+                let Command { command } = params.parse()?;
+                for key in command_keys(&command) {
+                    self.runtime.handle_event(&Event::Key(key));
+                }
+                let editor = self.runtime.editor();
+                if editor.is_err() {
+                    let (message, _) = editor.get_status().unwrap();
+                    return Err(error(
+                        ErrorCode::ServerError(EDITOR_ERROR),
+                        message.to_string(),
+                    ));
+                }
+            }
+            "open" => {
+                let Open { path } = params.parse()?;
+                self.runtime
+                    .editor_mut()
+                    .open(&path, Action::Replace)
+                    .map_err(|err| {
+                        editor_error(format!("failed to open {}: {err}", path.display()))
+                    })?;
+            }
+            "edit" => {
+                let Edit { changes } = params.parse()?;
+                self.edit(changes)?;
+            }
+            "save" => {
+                let Save { path, force } = parse_optional(params)?;
+                let editor = self.runtime.editor_mut();
+                let doc_id = doc!(editor).id();
+                editor
+                    .save(doc_id, path, force)
+                    .map_err(|err| editor_error(err.to_string()))?;
+            }
+            "text" => {
+                let editor = self.runtime.editor();
+                let doc = doc!(editor);
+                return Ok(json!({
+                    "path": doc.path(),
+                    "text": doc.text().to_string(),
+                    "modified": doc.is_modified(),
+                }));
+            }
+            "shutdown" => self.shutdown = true,
+            _ => {
+                return Err(error(
+                    ErrorCode::MethodNotFound,
+                    format!("unknown method {method}"),
+                ));
+            }
+        }
+        Ok(Value::Null)
+    }
+
+    fn resize(&mut self, Size { width, height }: Size) -> Result<(), jsonrpc::Error> {
+        if width == 0 || height == 0 {
+            return Err(jsonrpc::Error::invalid_params("the size must not be empty"));
+        }
+        self.runtime
+            .terminal_mut()
+            .backend_mut()
+            .resize(width, height);
+        self.runtime.resize().map_err(internal_error)
+    }
+
+    /// Applies `changes` to the focused document as a single undo step.
+    fn edit(&mut self, changes: Vec<Change>) -> Result<(), jsonrpc::Error> {
+        let editor = self.runtime.editor_mut();
+        let (view, doc) = current!(editor);
+        check_changes(&changes, doc.text().len_chars())?;
+        let transaction = Transaction::change(
+            doc.text(),
+            changes.into_iter().map(|change| {
+                let text = (!change.text.is_empty()).then(|| Tendril::from(change.text));
+                (change.from, change.to, text)
+            }),
+        );
+        doc.apply(&transaction, view.id);
+        doc.append_changes_to_history(view);
+        Ok(())
+    }
+
+    /// Sends the frame drawn since the last call and the status message, if they changed.
+    async fn send_updates(&mut self) -> io::Result<()> {
+        if let Some(frame) = self.runtime.terminal_mut().backend_mut().take_frame() {
+            self.notify("redraw", frame).await?;
+        }
+        let status = self
+            .runtime
+            .editor()
+            .get_status()
+            .map(|(message, severity)| (message.to_string(), *severity));
+        if status != self.status {
+            let params = match &status {
+                Some((message, severity)) => json!({ "message": message, "severity": severity }),
+                None => Value::Null,
+            };
+            self.status = status;
+            self.notify("status", params).await?;
+        }
+        Ok(())
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        let params = match params {
+            Value::Object(map) => Params::Map(map),
+            Value::Null => Params::None,
+            value => Params::Array(vec![value]),
+        };
+        let notification = jsonrpc::Notification {
+            jsonrpc: Some(jsonrpc::Version::V2),
+            method: method.to_string(),
+            params,
+        };
+        self.send(&notification).await
+    }
+
+    async fn send(&mut self, message: &impl serde::Serialize) -> io::Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        self.writer.write_all(&line).await
+    }
+}
+
+fn error(code: ErrorCode, message: impl Into<String>) -> jsonrpc::Error {
+    jsonrpc::Error {
+        code,
+        message: message.into(),
+        data: None,
+    }
+}
+
+fn editor_error(message: impl Into<String>) -> jsonrpc::Error {
+    error(ErrorCode::ServerError(EDITOR_ERROR), message)
+}
+
+fn internal_error(err: io::Error) -> jsonrpc::Error {
+    error(ErrorCode::InternalError, err.to_string())
+}
+
+/// Checks that `changes` are sorted, don't overlap and are within a text of `len` characters.
+fn check_changes(changes: &[Change], len: usize) -> Result<(), jsonrpc::Error> {
+    let mut end = 0;
+    for change in changes {
+        if change.from < end || change.from > change.to || change.to > len {
+            return Err(jsonrpc::Error::invalid_params(format!(
+                "the change of {}..{} is out of order or out of bounds",
+                change.from, change.to
+            )));
+        }
+        end = change.to;
+    }
+    Ok(())
+}
+
+/// Parses the params of a method whose params can all be left out.
+fn parse_optional<T: DeserializeOwned + Default>(params: Params) -> Result<T, jsonrpc::Error> {
+    if params.is_none() {
+        Ok(T::default())
+    } else {
+        params.parse()
+    }
+}
+
+/// A backend keeping the screen in memory and collecting the cells drawn into frames for the
+/// client, see [`ServerBackend::take_frame`].
+struct ServerBackend {
+    screen: Buffer,
+    /// The cells changed since the last frame, by row and column.
+    changed: BTreeMap<(u16, u16), Cell>,
+    /// Whether the screen was cleared since the last frame.
+    cleared: bool,
+    cursor: (u16, u16),
+    cursor_kind: CursorKind,
+    /// The cursor sent with the last frame.
+    sent_cursor: Option<(u16, u16, CursorKind)>,
+    title: String,
+    title_changed: bool,
+}
+
+impl ServerBackend {
+    fn new(width: u16, height: u16) -> Self {
+        Self {
+            screen: Buffer::empty(Rect::new(0, 0, width, height)),
+            changed: BTreeMap::new(),
+            cleared: true,
+            cursor: (0, 0),
+            cursor_kind: CursorKind::Hidden,
+            sent_cursor: None,
+            title: String::new(),
+            title_changed: false,
+        }
+    }
+
+    /// Resizes the screen, the next `Terminal::autoresize` picks up the new size.
+    fn resize(&mut self, width: u16, height: u16) {
+        self.screen.resize(Rect::new(0, 0, width, height));
+    }
+
+    fn visible_cursor(&self) -> Option<(u16, u16, CursorKind)> {
+        (self.cursor_kind != CursorKind::Hidden).then_some((
+            self.cursor.0,
+            self.cursor.1,
+            self.cursor_kind,
+        ))
+    }
+
+    /// The params of a `redraw` notification with the changes since the last frame, `None` if
+    /// nothing changed.
+    fn take_frame(&mut self) -> Option<Value> {
+        let cursor = self.visible_cursor();
+        if self.changed.is_empty()
+            && !self.cleared
+            && !self.title_changed
+            && cursor == self.sent_cursor
+        {
+            return None;
+        }
+        let cells: Vec<Value> = std::mem::take(&mut self.changed)
+            .into_iter()
+            .map(|((y, x), cell)| cell_json(x, y, &cell))
+            .collect();
+        let frame = json!({
+            "clear": self.cleared,
+            "cells": cells,
+            "cursor": cursor.map(|(x, y, kind)| json!({ "x": x, "y": y, "kind": kind })),
+            "title": self.title,
+        });
+        self.cleared = false;
+        self.title_changed = false;
+        self.sent_cursor = cursor;
+        Some(frame)
+    }
+}
+
+impl Backend for ServerBackend {
+    fn claim(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn reconfigure(&mut self, _config: Config) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn restore(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn draw<'a, I>(&mut self, content: I) -> Result<(), io::Error>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        for (x, y, cell) in content {
+            if let Some(target) = self.screen.get_mut(x, y) {
+                *target = cell.clone();
+                self.changed.insert((y, x), cell.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> Result<(), io::Error> {
+        self.cursor_kind = CursorKind::Hidden;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self, kind: CursorKind) -> Result<(), io::Error> {
+        self.cursor_kind = kind;
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> Result<(), io::Error> {
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), io::Error> {
+        self.screen.reset();
+        self.changed.clear();
+        self.cleared = true;
+        Ok(())
+    }
+
+    /// The client has no scroll regions, the rows of the region are sent again instead.
+    fn scroll_region(&mut self, scroll: Scroll) -> Result<(), io::Error> {
+        self.screen.scroll_rows(scroll);
+        let width = self.screen.area.width;
+        for y in scroll.top..scroll.bottom.min(self.screen.area.height) {
+            for x in 0..width {
+                self.changed.insert((y, x), self.screen[(x, y)].clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Rect, io::Error> {
+        Ok(self.screen.area)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn set_cursor_color(&mut self, _color: Color) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<(), io::Error> {
+        if self.title != title {
+            self.title = title.to_string();
+            self.title_changed = true;
+        }
+        Ok(())
+    }
+
+    fn supports_true_color(&self) -> bool {
+        true
+    }
+
+    fn supports_images(&self) -> bool {
+        false
+    }
+
+    fn supports_kitty_keyboard(&self) -> bool {
+        false
+    }
+
+    fn draw_images(&mut self, _images: &[Image]) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn get_theme_mode(&self) -> Option<helix_view::theme::Mode> {
+        None
+    }
+}
+
+fn cell_json(x: u16, y: u16, cell: &Cell) -> Value {
+    let modifiers: Vec<String> = cell
+        .modifier
+        .iter_names()
+        .map(|(name, _)| name.to_lowercase())
+        .collect();
+    json!({
+        "x": x,
+        "y": y,
+        "symbol": cell.symbol,
+        "fg": color_json(cell.fg),
+        "bg": color_json(cell.bg),
+        "underline-color": color_json(cell.underline_color),
+        "underline-style": underline_style_name(cell.underline_style),
+        "modifiers": modifiers,
+        "hyperlink": cell.hyperlink.map(|link| link.target().to_string()),
+    })
+}
+
+/// A color as the name of a theme color, `#rrggbb` or the number of an indexed color.
+fn color_json(color: Color) -> Value {
+    let name = match color {
+        Color::Rgb(r, g, b) => return json!(format!("#{r:02x}{g:02x}{b:02x}")),
+        Color::Indexed(index) => return json!(index),
+        Color::Reset => "default",
+        Color::Black => "black",
+        Color::Red => "red",
+        Color::Green => "green",
+        Color::Yellow => "yellow",
+        Color::Blue => "blue",
+        Color::Magenta => "magenta",
+        Color::Cyan => "cyan",
+        Color::Gray => "gray",
+        Color::LightRed => "light-red",
+        Color::LightGreen => "light-green",
+        Color::LightYellow => "light-yellow",
+        Color::LightBlue => "light-blue",
+        Color::LightMagenta => "light-magenta",
+        Color::LightCyan => "light-cyan",
+        Color::LightGray => "light-gray",
+        Color::White => "white",
+    };
+    json!(name)
+}
+
+/// The name of an underline style as in themes, `reset` for no underline.
+fn underline_style_name(style: UnderlineStyle) -> &'static str {
+    match style {
+        UnderlineStyle::Reset => "reset",
+        UnderlineStyle::Line => "line",
+        UnderlineStyle::Curl => "curl",
+        UnderlineStyle::Dotted => "dotted",
+        UnderlineStyle::Dashed => "dashed",
+        UnderlineStyle::DoubleLine => "double_line",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(from: usize, to: usize) -> Change {
+        Change {
+            from,
+            to,
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn changes_must_be_sorted_and_in_bounds() {
+        assert!(check_changes(&[], 0).is_ok());
+        assert!(check_changes(&[change(0, 2), change(2, 2), change(4, 5)], 5).is_ok());
+        // Overlapping, out of order, reversed and past the end of the text
+        for changes in [
+            vec![change(0, 3), change(2, 4)],
+            vec![change(3, 4), change(0, 1)],
+            vec![change(2, 1)],
+            vec![change(4, 6)],
+        ] {
+            let err = check_changes(&changes, 5).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidParams);
+        }
+    }
+
+    fn draw(backend: &mut ServerBackend, x: u16, y: u16, symbol: &str) {
+        let mut cell = Cell::default();
+        cell.set_symbol(symbol);
+        backend.draw(std::iter::once((x, y, &cell))).unwrap();
+    }
+
+    #[test]
+    fn frames_contain_the_changes_since_the_last_frame() {
+        let mut backend = ServerBackend::new(4, 2);
+        let frame = backend.take_frame().unwrap();
+        assert_eq!(frame["clear"], true);
+        assert_eq!(frame["cells"], json!([]));
+        assert_eq!(frame["cursor"], Value::Null);
+        assert_eq!(backend.take_frame(), None);
+
+        // Cells are sent by row, drawing over a cell again sends it once
+        draw(&mut backend, 3, 0, "a");
+        draw(&mut backend, 1, 1, "b");
+        draw(&mut backend, 0, 1, "c");
+        draw(&mut backend, 1, 1, "d");
+        let frame = backend.take_frame().unwrap();
+        assert_eq!(frame["clear"], false);
+        let cells: Vec<_> = frame["cells"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|cell| (&cell["x"], &cell["y"], &cell["symbol"]))
+            .collect();
+        assert_eq!(
+            cells,
+            [
+                (&json!(3), &json!(0), &json!("a")),
+                (&json!(0), &json!(1), &json!("c")),
+                (&json!(1), &json!(1), &json!("d")),
+            ]
+        );
+        assert_eq!(backend.take_frame(), None);
+
+        // Clearing drops the cells drawn before it
+        draw(&mut backend, 0, 0, "e");
+        backend.clear().unwrap();
+        draw(&mut backend, 2, 1, "f");
+        let frame = backend.take_frame().unwrap();
+        assert_eq!(frame["clear"], true);
+        assert_eq!(frame["cells"].as_array().unwrap().len(), 1);
+        assert_eq!(frame["cells"][0]["symbol"], "f");
+    }
+
+    #[test]
+    fn frames_are_sent_when_the_cursor_or_title_changes() {
+        let mut backend = ServerBackend::new(4, 2);
+        backend.take_frame();
+
+        backend.set_cursor(2, 1).unwrap();
+        assert_eq!(backend.take_frame(), None, "the cursor is hidden");
+        backend.show_cursor(CursorKind::Block).unwrap();
+        let frame = backend.take_frame().unwrap();
+        assert_eq!(frame["cursor"], json!({ "x": 2, "y": 1, "kind": "block" }));
+        backend.set_cursor(2, 1).unwrap();
+        assert_eq!(backend.take_frame(), None);
+        backend.hide_cursor().unwrap();
+        assert_eq!(backend.take_frame().unwrap()["cursor"], Value::Null);
+
+        backend.set_title("file.rs").unwrap();
+        assert_eq!(backend.take_frame().unwrap()["title"], "file.rs");
+        backend.set_title("file.rs").unwrap();
+        assert_eq!(backend.take_frame(), None);
+    }
+
+    /// A client of [`serve_client`] connected over a socket pair.
+    #[cfg(feature = "integration")]
+    struct Client {
+        lines: tokio::io::Lines<BufReader<tokio::net::unix::OwnedReadHalf>>,
+        writer: OwnedWriteHalf,
+        next_id: u64,
+        /// The methods of the notifications received so far.
+        notifications: Vec<String>,
+    }
+
+    #[cfg(feature = "integration")]
+    impl Client {
+        /// Calls `method` and returns its result or error, collecting the notifications sent
+        /// before the response.
+        async fn call(&mut self, method: &str, params: Value) -> Result<Value, Value> {
+            self.next_id += 1;
+            let mut request = json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method });
+            if !params.is_null() {
+                request["params"] = params;
+            }
+            let mut line = serde_json::to_vec(&request).unwrap();
+            line.push(b'\n');
+            self.writer.write_all(&line).await.unwrap();
+            loop {
+                let line = self.lines.next_line().await.unwrap().unwrap();
+                let mut message: Value = serde_json::from_str(&line).unwrap();
+                if let Some(method) = message["method"].as_str() {
+                    self.notifications.push(method.to_string());
+                    continue;
+                }
+                assert_eq!(message["id"], self.next_id);
+                return match message["error"].take() {
+                    Value::Null => Ok(message["result"].take()),
+                    error => Err(error),
+                };
+            }
+        }
+    }
+
+    #[cfg(feature = "integration")]
+    #[tokio::test]
+    async fn round_trip() -> anyhow::Result<()> {
+        let (server, client) = UnixStream::pair()?;
+        let options = RuntimeOptions {
+            config: Some(helix_term::config::Config::default()),
+            ..RuntimeOptions::default()
+        };
+        let (reader, writer) = client.into_split();
+        let mut client = Client {
+            lines: BufReader::new(reader).lines(),
+            writer,
+            next_id: 0,
+            notifications: Vec::new(),
+        };
+        let session = async move {
+            let result = client
+                .call("initialize", json!({ "width": 40, "height": 10 }))
+                .await;
+            assert_eq!(result, Ok(Value::Null));
+            assert!(client.notifications.contains(&"redraw".to_string()));
+
+            client
+                .call("keys", json!({ "keys": "ihello<esc>" }))
+                .await
+                .unwrap();
+            let changes = json!([{ "from": 0, "to": 1, "text": "j" }]);
+            client
+                .call("edit", json!({ "changes": changes }))
+                .await
+                .unwrap();
+            let text = client.call("text", Value::Null).await.unwrap();
+            assert_eq!(text["text"], "jello\n");
+            assert_eq!(text["modified"], true);
+
+            // Out of order changes
+            let changes = json!([
+                { "from": 2, "to": 3, "text": "" },
+                { "from": 0, "to": 1, "text": "" },
+            ]);
+            let error = client
+                .call("edit", json!({ "changes": changes }))
+                .await
+                .unwrap_err();
+            assert_eq!(error["code"], ErrorCode::InvalidParams.code());
+            let error = client
+                .call("no-such-method", Value::Null)
+                .await
+                .unwrap_err();
+            assert_eq!(error["code"], ErrorCode::MethodNotFound.code());
+
+            client.call("shutdown", Value::Null).await.unwrap();
+        };
+        let (result, ()) = tokio::join!(serve_client(server, options), session);
+        result
+    }
+}
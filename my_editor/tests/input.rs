@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use helix_view::{
+    input::{Event, KeyCode, KeyEvent, KeyModifiers, TerminalResponse},
+    theme::Mode,
+};
+use my_editor::{InputEvent, InputParser, TerminalInput};
+
+fn key(code: KeyCode) -> InputEvent {
+    InputEvent::Event(Event::Key(KeyEvent {
+        code,
+        modifiers: KeyModifiers::NONE,
+    }))
+}
+
+#[test]
+fn lone_esc_is_held_back() {
+    let mut parser = InputParser::default();
+    assert_eq!(parser.advance(b"\x1b", true), []);
+    assert!(parser.is_holding_esc());
+    assert_eq!(parser.release_esc(), Some(key(KeyCode::Esc)));
+    assert_eq!(parser.release_esc(), None);
+
+    // The rest of an escape sequence split across reads
+    assert_eq!(parser.advance(b"\x1b", true), []);
+    assert_eq!(parser.advance(b"[A", true), [key(KeyCode::Up)]);
+    assert!(!parser.is_holding_esc());
+
+    // Without holding it back, the parser waits for the rest of the sequence
+    assert_eq!(parser.advance(b"\x1b", false), []);
+    assert!(!parser.is_holding_esc());
+    assert_eq!(parser.advance(b"[B", false), [key(KeyCode::Down)]);
+}
+
+#[test]
+fn responses_are_read_with_the_input() {
+    let mut parser = InputParser::default();
+    assert_eq!(
+        parser.advance(b"a\x1b[?997;2n", true),
+        [
+            key(KeyCode::Char('a')),
+            InputEvent::Response(TerminalResponse::ThemeMode(Mode::Light)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn terminal_input_reads_until_the_end() {
+    let mut input = TerminalInput::new(std::io::Cursor::new(b"ab".to_vec())).unwrap();
+    let timeout = Some(Duration::from_millis(10));
+    assert_eq!(
+        input.next(timeout).await.unwrap().unwrap(),
+        key(KeyCode::Char('a'))
+    );
+    assert_eq!(input.next_pending(), Some(key(KeyCode::Char('b'))));
+    assert!(input.next(timeout).await.is_none());
+}
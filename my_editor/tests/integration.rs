@@ -0,0 +1,70 @@
+#[cfg(feature = "integration")]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use helix_term::config::Config;
+    use my_editor::{Harness, RuntimeOptions};
+
+    fn harness() -> anyhow::Result<Harness> {
+        Harness::new(RuntimeOptions {
+            config: Some(Config::default()),
+            ..RuntimeOptions::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn insert_and_undo() -> anyhow::Result<()> {
+        let mut harness = harness()?;
+        harness.press("ione<ret>two<esc>").await?;
+        assert_eq!(harness.text().lines().collect::<Vec<_>>(), ["one", "two"]);
+        assert!(harness.statusline().contains("NOR"));
+
+        harness.press("u").await?;
+        assert_eq!(harness.text().trim_end(), "");
+        harness.finish().await
+    }
+
+    #[tokio::test]
+    async fn statusline_shows_the_mode() -> anyhow::Result<()> {
+        let mut harness = harness()?;
+        harness.press("i").await?;
+        assert!(harness.statusline().contains("INS"));
+        harness.press("<esc>v").await?;
+        assert!(harness.statusline().contains("SEL"));
+        harness.finish().await
+    }
+
+    #[tokio::test]
+    async fn typed_commands() -> anyhow::Result<()> {
+        let mut harness = harness()?;
+        harness.command("no-such-command").await;
+        assert!(harness.error().is_some());
+
+        harness.command("vsplit").await;
+        assert_eq!(harness.editor().tree.views().count(), 2);
+        harness.finish().await
+    }
+
+    #[tokio::test]
+    async fn directory_argument_opens_the_file_picker() -> anyhow::Result<()> {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let mut harness = Harness::new(RuntimeOptions {
+            config: Some(Config::default()),
+            files: vec![(dir, Vec::new())],
+            ..RuntimeOptions::default()
+        })?;
+        // The picker lists the files as the directory is walked in the background
+        for _ in 0..50 {
+            if harness.screen().contains("Cargo.toml") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            harness.pump().await;
+        }
+        assert!(harness.screen().contains("Cargo.toml"));
+        // A scratch buffer is behind the picker rather than a buffer named after the directory
+        assert_eq!(harness.editor().documents().count(), 1);
+        assert!(helix_view::doc!(harness.editor()).path().is_none());
+        harness.finish().await
+    }
+}